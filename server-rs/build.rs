@@ -0,0 +1,6 @@
+fn main() {
+    // Stamp a new build time whenever the sources change; reported as `builtAt` by GET /engine/config
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rustc-env=TADPOLE_BUILT_AT={}", chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+}
@@ -15,11 +15,9 @@ struct DiscordMessage {
 }
 
 impl DiscordAdapter {
-    pub fn new(webhook_url: String) -> Self {
-        Self {
-            webhook_url,
-            client: Client::new(),
-        }
+    /// Creates a DiscordAdapter with a shared `reqwest::Client`.
+    pub fn new(client: Client, webhook_url: String) -> Self {
+        Self { webhook_url, client }
     }
 
     /// Sends a notification to a Discord channel via webhook.
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Deliberately loose: catches obvious garbage, leaves real validation to the SMTP server.
+static EMAIL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[^@\s<>]+@[^@\s<>]+\.[^@\s<>]+$").expect("valid email regex")
+});
+
+/// Returns true if `address` looks like a plain `user@domain.tld` address.
+pub fn is_valid_email(address: &str) -> bool {
+    EMAIL_RE.is_match(address)
+}
+
+/// SMTP relay settings (from `SMTP_*` and `EMAIL_FROM`).
+#[derive(Debug, Clone, Default)]
+pub struct SmtpSettings {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Transport security: `starttls` (default), `tls` or `none` (local relays only).
+    pub tls: Option<String>,
+    pub from: Option<String>,
+}
+
+pub struct EmailAdapter {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl EmailAdapter {
+    /// Builds an adapter from `settings`; a host and a sender address are required.
+    pub fn new(settings: &SmtpSettings) -> Result<Self> {
+        let host = settings.host.as_deref().ok_or_else(|| anyhow::anyhow!("SMTP_HOST is not set"))?;
+        let from = settings.from.as_deref().ok_or_else(|| anyhow::anyhow!("EMAIL_FROM is not set"))?;
+
+        let mut builder = match settings.tls.as_deref().unwrap_or("starttls").to_lowercase().as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+        };
+        if let Some(port) = settings.port {
+            builder = builder.port(port);
+        }
+        if let (Some(user), Some(pass)) = (&settings.username, &settings.password) {
+            builder = builder.credentials(Credentials::new(user.clone(), pass.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: from.parse().map_err(|e| anyhow::anyhow!("Invalid EMAIL_FROM '{}': {}", from, e))?,
+        })
+    }
+
+    /// Sends a plain-text email on behalf of an agent.
+    pub async fn send(&self, to: &str, subject: &str, body: &str) -> Result<()> {
+        if !is_valid_email(to) {
+            return Err(anyhow::anyhow!("Invalid recipient address '{}'", to));
+        }
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse().map_err(|e| anyhow::anyhow!("Invalid recipient address '{}': {}", to, e))?)
+            .subject(subject)
+            .body(body.to_string())?;
+
+        self.transport.send(message).await
+            .map_err(|e| anyhow::anyhow!("SMTP Error: {}", e))?;
+        Ok(())
+    }
+}
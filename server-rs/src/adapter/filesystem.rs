@@ -1,9 +1,91 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
 use tokio::fs;
-use anyhow::{Result, anyhow};
+use tokio::sync::Mutex;
+use serde::Serialize;
+use crate::agent::types::ToolError;
+
+type Result<T> = std::result::Result<T, ToolError>;
+
+/// Upper bound on hits returned by a single `search_files` call.
+const MAX_SEARCH_HITS: usize = 100;
+/// Files larger than this are skipped by `search_files`.
+const MAX_SEARCH_FILE_BYTES: u64 = 1024 * 1024;
+/// Upper bound on entries returned by a recursive `list_files`.
+pub const MAX_LIST_ENTRIES: usize = 1000;
+/// Directory levels a recursive `list_files` descends below the listed directory.
+pub const MAX_LIST_DEPTH: usize = 5;
+/// Workspace size cap used when `WORKSPACE_MAX_BYTES` is unset (100 MB).
+pub const DEFAULT_MAX_WORKSPACE_BYTES: u64 = 100 * 1024 * 1024;
+/// How long a computed workspace size is trusted before the tree is walked again.
+const WORKSPACE_SIZE_TTL: Duration = Duration::from_secs(5);
+
+/// Last computed size per workspace root. Adapters are built per tool call, so the
+/// cache lives here to be shared by every adapter pointing at the same workspace.
+type SizeCache = Arc<Mutex<Option<(Instant, u64)>>>;
+static WORKSPACE_SIZES: Lazy<DashMap<PathBuf, SizeCache>> = Lazy::new(DashMap::new);
+
+/// Workspace size cap from WORKSPACE_MAX_BYTES, defaulting to 100 MB.
+pub fn max_workspace_bytes() -> u64 {
+    std::env::var("WORKSPACE_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_MAX_WORKSPACE_BYTES)
+}
+
+/// A single matching line found by `FilesystemAdapter::search_files`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    /// Path relative to the workspace root, using `/` separators.
+    pub filename: String,
+    /// 1-based line number.
+    pub line_number: usize,
+    pub line_content: String,
+}
+
+/// Size, timestamps and kind of a workspace entry, as returned by `stat_file` and `list_files`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMetadata {
+    /// Path relative to the workspace root, using `/` separators.
+    pub path: String,
+    pub size_bytes: u64,
+    pub modified_at: chrono::DateTime<chrono::Utc>,
+    pub is_dir: bool,
+    pub is_file: bool,
+}
+
+impl FileMetadata {
+    fn from_std(path: String, metadata: &std::fs::Metadata) -> Self {
+        Self {
+            path,
+            size_bytes: metadata.len(),
+            modified_at: metadata.modified().map(chrono::DateTime::from).unwrap_or_default(),
+            is_dir: metadata.is_dir(),
+            is_file: metadata.is_file(),
+        }
+    }
+
+    /// The last path component, with a trailing `/` for directories.
+    pub fn name(&self) -> String {
+        let name = self.path.rsplit('/').next().unwrap_or(&self.path);
+        if self.is_dir { format!("{}/", name) } else { name.to_string() }
+    }
+
+    /// The workspace-relative path, with a trailing `/` for directories.
+    pub fn display_path(&self) -> String {
+        if self.is_dir { format!("{}/", self.path) } else { self.path.clone() }
+    }
+}
 
 pub struct FilesystemAdapter {
     pub root_path: PathBuf,
+    /// Writes that would grow the workspace past this many bytes are refused.
+    pub max_workspace_bytes: u64,
+    size_cache: SizeCache,
 }
 
 impl FilesystemAdapter {
@@ -11,8 +93,14 @@ impl FilesystemAdapter {
     /// then immediately canonicalized to get its real, symlink-resolved path.
     /// This is the SEC-03 fix: prevents symlink-based sandbox escapes.
     pub fn new(root_path: PathBuf) -> Self {
+        Self::with_max_workspace_bytes(root_path, max_workspace_bytes())
+    }
+
+    /// Like `new`, with an explicit workspace size cap instead of WORKSPACE_MAX_BYTES.
+    pub fn with_max_workspace_bytes(root_path: PathBuf, max_workspace_bytes: u64) -> Self {
         // We'll lazily canonicalize on first use to avoid blocking in new().
-        Self { root_path }
+        let size_cache = WORKSPACE_SIZES.entry(root_path.clone()).or_default().clone();
+        Self { root_path, max_workspace_bytes, size_cache }
     }
 
     /// Verifies the requested path stays inside the workspace.
@@ -23,7 +111,7 @@ impl FilesystemAdapter {
     ///
     /// This is safe even if the workspace root was created via a symlink, because we
     /// canonicalize *both* paths before comparing — eliminating the TOCTOU risk.
-    fn get_safe_path(&self, requested_path: &str) -> Result<PathBuf> {
+    pub(crate) fn get_safe_path(&self, requested_path: &str) -> Result<PathBuf> {
         // Build the candidate path (without canonicalization first)
         let mut candidate = self.root_path.clone();
 
@@ -31,7 +119,7 @@ impl FilesystemAdapter {
             match component {
                 std::path::Component::Normal(c) => candidate.push(c),
                 std::path::Component::ParentDir => {
-                    return Err(anyhow!("🚫 SECURITY FAULT: Illegal path traversal attempt detected. Access denied."));
+                    return Err(ToolError::SecurityViolation("🚫 SECURITY FAULT: Illegal path traversal attempt detected. Access denied.".to_string()));
                 }
                 // Ignore absolute roots/prefixes to keep path relative to our root
                 std::path::Component::RootDir | std::path::Component::Prefix(_) => {}
@@ -46,11 +134,11 @@ impl FilesystemAdapter {
             .unwrap_or_else(|_| candidate.clone());
 
         if !canonical_candidate.starts_with(&canonical_root) {
-            return Err(anyhow!(
+            return Err(ToolError::SecurityViolation(format!(
                 "🚫 SECURITY FAULT: Attempted to access '{}' which is outside the designated workspace '{}'.",
                 canonical_candidate.display(),
                 canonical_root.display()
-            ));
+            )));
         }
 
         Ok(candidate)
@@ -58,41 +146,219 @@ impl FilesystemAdapter {
 
     pub async fn write_file(&self, filename: &str, content: &str) -> Result<()> {
         let path = self.get_safe_path(filename)?;
+        let replaced = file_len(&path).await;
+        let mut cache = self.reserve_space(filename, replaced, content.len() as u64).await?;
 
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).await?;
         }
 
         fs::write(path, content).await?;
+        commit_space(&mut cache, replaced, content.len() as u64);
         Ok(())
     }
 
+    /// Appends `content` to a workspace file, creating it (and its parent directories) if missing.
+    pub async fn append_to_file(&self, filename: &str, content: &str) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+        let path = self.get_safe_path(filename)?;
+        let mut cache = self.reserve_space(filename, 0, content.len() as u64).await?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(path).await?;
+        file.write_all(content.as_bytes()).await?;
+        file.flush().await?;
+        commit_space(&mut cache, 0, content.len() as u64);
+        Ok(())
+    }
+
+    /// Copies `src` to `dst`, overwriting it. Both paths must resolve inside the workspace;
+    /// parent directories of `dst` are created as needed.
+    pub async fn copy_file(&self, src: &str, dst: &str) -> Result<()> {
+        let from = self.get_safe_path(src)?;
+        let to = self.get_safe_path(dst)?;
+        let added = fs::metadata(&from).await?.len();
+        let replaced = file_len(&to).await;
+        let mut cache = self.reserve_space(dst, replaced, added).await?;
+
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        fs::copy(from, to).await?;
+        commit_space(&mut cache, replaced, added);
+        Ok(())
+    }
+
+    /// Sums the sizes of every file under the workspace root. Symlinks are not followed.
+    pub async fn total_workspace_size(&self) -> Result<u64> {
+        let mut total = 0;
+        let mut pending = vec![self.root_path.clone()];
+        while let Some(dir) = pending.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let file_type = entry.file_type().await?;
+                if file_type.is_dir() {
+                    pending.push(entry.path());
+                } else if file_type.is_file() {
+                    total += entry.metadata().await?.len();
+                }
+            }
+        }
+        Ok(total)
+    }
+
+    /// Checks that replacing `replaced` bytes with `added` bytes keeps the workspace under
+    /// `max_workspace_bytes`. The returned guard holds the cached size (refreshed if older
+    /// than 5 seconds) so concurrent writers are checked one at a time.
+    async fn reserve_space(&self, filename: &str, replaced: u64, added: u64) -> Result<tokio::sync::MutexGuard<'_, Option<(Instant, u64)>>> {
+        let mut cache = self.size_cache.lock().await;
+        let current = match *cache {
+            Some((computed_at, size)) if computed_at.elapsed() < WORKSPACE_SIZE_TTL => size,
+            _ => {
+                let size = self.total_workspace_size().await?;
+                *cache = Some((Instant::now(), size));
+                size
+            }
+        };
+
+        let projected = current.saturating_sub(replaced).saturating_add(added);
+        if projected > self.max_workspace_bytes {
+            return Err(ToolError::SecurityViolation(format!(
+                "🚫 Workspace quota exceeded: writing {} bytes to '{}' would bring the workspace to {} bytes, over its {} byte limit. Delete files or ask an operator to raise WORKSPACE_MAX_BYTES.",
+                added, filename, projected, self.max_workspace_bytes
+            )));
+        }
+        Ok(cache)
+    }
+
     pub async fn read_file(&self, filename: &str) -> Result<String> {
         let path = self.get_safe_path(filename)?;
         let content = fs::read_to_string(path).await?;
         Ok(content)
     }
 
-    pub async fn list_files(&self, dir: &str) -> Result<Vec<String>> {
+    /// Reports size, modification time and kind of a workspace path without reading it.
+    pub async fn stat_file(&self, filename: &str) -> Result<FileMetadata> {
+        let path = self.get_safe_path(filename)?;
+        let metadata = fs::metadata(&path).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ToolError::NotFound(format!("'{}' does not exist in the workspace", filename)),
+            _ => ToolError::from(e),
+        })?;
+        Ok(FileMetadata::from_std(self.relative_path(&path), &metadata))
+    }
+
+    /// Lists the entries of `dir`, sorted by path. A missing directory lists as empty.
+    /// With `recursive`, also lists everything below it, breadth first, down to
+    /// `MAX_LIST_DEPTH` levels and at most `MAX_LIST_ENTRIES` entries. Symlinked
+    /// directories are listed but not entered.
+    pub async fn list_files(&self, dir: &str, recursive: bool) -> Result<Vec<FileMetadata>> {
         let path = self.get_safe_path(dir)?;
 
         if !path.exists() {
             return Ok(vec![]);
         }
 
-        let mut entries = fs::read_dir(path).await?;
         let mut files = Vec::new();
+        let mut pending = std::collections::VecDeque::from([(path, 1)]);
+        'walk: while let Some((current, depth)) = pending.pop_front() {
+            let mut entries = fs::read_dir(&current).await?;
+            let mut children = Vec::new();
+            while let Some(entry) = entries.next_entry().await? {
+                children.push(entry);
+            }
+            // Sorted so a capped listing keeps the same entries across platforms
+            children.sort_by_key(|e| e.file_name());
 
-        while let Some(entry) = entries.next_entry().await? {
-            let name = entry.file_name().to_string_lossy().to_string();
-            let is_dir = entry.file_type().await?.is_dir();
-            files.push(if is_dir { format!("{}/", name) } else { name });
+            for entry in children {
+                if recursive && files.len() >= MAX_LIST_ENTRIES {
+                    break 'walk;
+                }
+                let metadata = entry.metadata().await?;
+                if recursive && depth < MAX_LIST_DEPTH && entry.file_type().await?.is_dir() {
+                    pending.push_back((entry.path(), depth + 1));
+                }
+                files.push(FileMetadata::from_std(self.relative_path(&entry.path()), &metadata));
+            }
         }
 
-        files.sort(); // deterministic order
+        files.sort_by(|a, b| a.path.cmp(&b.path)); // deterministic order
         Ok(files)
     }
 
+    /// Recursively searches text files under `dir` for lines containing `pattern`, or matching
+    /// it as a regex when `is_regex` is set. Stops after 100 hits and skips files over 1 MB
+    /// as well as files that are not valid UTF-8.
+    pub async fn search_files(&self, dir: &str, pattern: &str, is_regex: bool) -> Result<Vec<SearchHit>> {
+        let start = self.get_safe_path(dir)?;
+        let regex = if is_regex {
+            Some(regex::Regex::new(pattern).map_err(|e| ToolError::Internal(format!("Invalid search pattern '{}': {}", pattern, e)))?)
+        } else {
+            None
+        };
+
+        let mut hits = Vec::new();
+        if !start.exists() {
+            return Ok(hits);
+        }
+
+        let mut pending = vec![start];
+        while let Some(current) = pending.pop() {
+            let mut entries = fs::read_dir(&current).await?;
+            let mut children = Vec::new();
+            while let Some(entry) = entries.next_entry().await? {
+                children.push(entry);
+            }
+            // Sorted so results are deterministic across platforms
+            children.sort_by_key(|e| e.file_name());
+
+            for entry in children {
+                let file_type = entry.file_type().await?;
+                let path = entry.path();
+                if file_type.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                if !file_type.is_file() || entry.metadata().await?.len() > MAX_SEARCH_FILE_BYTES {
+                    continue;
+                }
+                let Ok(content) = fs::read_to_string(&path).await else { continue };
+
+                let filename = self.relative_path(&path);
+                for (idx, line) in content.lines().enumerate() {
+                    let matched = match &regex {
+                        Some(re) => re.is_match(line),
+                        None => line.contains(pattern),
+                    };
+                    if matched {
+                        hits.push(SearchHit { filename: filename.clone(), line_number: idx + 1, line_content: line.to_string() });
+                        if hits.len() >= MAX_SEARCH_HITS {
+                            return Ok(hits);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(hits)
+    }
+
+    /// `path` relative to the workspace root, using `/` separators.
+    fn relative_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root_path).unwrap_or(path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
     pub async fn delete_file(&self, filename: &str) -> Result<()> {
         let path = self.get_safe_path(filename)?;
         if path.is_file() {
@@ -100,6 +366,8 @@ impl FilesystemAdapter {
         } else if path.is_dir() {
             fs::remove_dir_all(path).await?;
         }
+        // Freed space should be usable right away, not after the cache expires
+        *self.size_cache.lock().await = None;
         Ok(())
     }
 }
@@ -108,15 +376,27 @@ impl FilesystemAdapter {
 //  HELPERS
 // ─────────────────────────────────────────────────────────
 
+/// Size of the file at `path`, or 0 if it does not exist.
+async fn file_len(path: &Path) -> u64 {
+    fs::metadata(path).await.map(|m| if m.is_file() { m.len() } else { 0 }).unwrap_or(0)
+}
+
+/// Records a completed write in the cached workspace size, keeping its timestamp.
+fn commit_space(cache: &mut Option<(Instant, u64)>, replaced: u64, added: u64) {
+    if let Some((_, size)) = cache.as_mut() {
+        *size = size.saturating_sub(replaced).saturating_add(added);
+    }
+}
+
 /// Canonicalize a path, creating the directory first if it doesn't exist.
 /// This handles the common case where the workspace root hasn't been created yet.
 fn canonicalize_or_create(path: &Path) -> Result<PathBuf> {
     if !path.exists() {
         std::fs::create_dir_all(path)
-            .map_err(|e| anyhow!("Failed to create workspace root '{}': {}", path.display(), e))?;
+            .map_err(|e| ToolError::Internal(format!("Failed to create workspace root '{}': {}", path.display(), e)))?;
     }
     std::fs::canonicalize(path)
-        .map_err(|e| anyhow!("Failed to canonicalize workspace root '{}': {}", path.display(), e))
+        .map_err(|e| ToolError::Internal(format!("Failed to canonicalize workspace root '{}': {}", path.display(), e)))
 }
 
 /// Canonicalize by walking up the path until we find an existing component,
@@ -1,3 +1,7 @@
 pub mod vault;
 pub mod discord;
+pub mod slack;
 pub mod filesystem;
+pub mod email;
+pub mod s3;
+pub mod webhook;
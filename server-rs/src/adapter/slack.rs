@@ -0,0 +1,67 @@
+use reqwest::Client;
+use serde::Serialize;
+use anyhow::Result;
+
+pub struct SlackAdapter {
+    pub webhook_url: String,
+    client: Client,
+}
+
+#[derive(Debug, Serialize)]
+struct SlackMessage {
+    text: String,
+    username: String,
+    /// Overrides the webhook's default channel (e.g. `#ops`), where the workspace allows it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel: Option<String>,
+}
+
+impl SlackAdapter {
+    /// Creates a SlackAdapter with a shared `reqwest::Client`.
+    pub fn new(client: Client, webhook_url: String) -> Self {
+        Self { webhook_url, client }
+    }
+
+    /// Sends a notification to Slack via an Incoming Webhook.
+    pub async fn notify(&self, agent_name: &str, text: &str, channel: Option<&str>) -> Result<()> {
+        let msg = build_message(agent_name, text, channel);
+
+        let res = self.client.post(&self.webhook_url)
+            .json(&msg)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            let err = res.text().await?;
+            return Err(anyhow::anyhow!("Slack Webhook Error: {}", err));
+        }
+
+        Ok(())
+    }
+}
+
+fn build_message(agent_name: &str, text: &str, channel: Option<&str>) -> SlackMessage {
+    SlackMessage {
+        text: text.to_string(),
+        username: format!("Tadpole OS: {}", agent_name),
+        channel: channel.filter(|c| !c.is_empty()).map(|c| c.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slack_message_serializes_webhook_fields() {
+        let value = serde_json::to_value(build_message("Scout", "Deploy finished", Some("#ops"))).unwrap();
+        assert_eq!(value, serde_json::json!({
+            "text": "Deploy finished",
+            "username": "Tadpole OS: Scout",
+            "channel": "#ops"
+        }));
+
+        let value = serde_json::to_value(build_message("Scout", "Deploy finished", None)).unwrap();
+        assert!(value.get("channel").is_none(), "No channel override means the webhook default is used");
+    }
+}
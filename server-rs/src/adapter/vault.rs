@@ -1,6 +1,17 @@
 use std::path::PathBuf;
 use tokio::fs;
 use anyhow::Result;
+use serde::Serialize;
+
+/// Summary of one vault subdirectory (typically one mission).
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultSubdirectory {
+    pub subdirectory: String,
+    #[serde(rename = "fileCount")]
+    pub file_count: u64,
+    #[serde(rename = "totalSizeBytes")]
+    pub total_size_bytes: u64,
+}
 
 pub struct VaultAdapter {
     pub root_path: PathBuf,
@@ -30,8 +41,19 @@ impl VaultAdapter {
     }
 
     /// Appends findings to a markdown file in the vault.
-    pub async fn append_to_file(&self, filename: &str, content: &str) -> Result<()> {
-        let path = self.get_safe_path(filename)?;
+    /// When `subdirectory` is given (usually the mission ID) the file lives under `vault/<subdirectory>/`.
+    pub async fn append_to_file(&self, filename: &str, content: &str, subdirectory: Option<String>) -> Result<()> {
+        let path = match subdirectory {
+            Some(sub) => {
+                let mut components = std::path::Path::new(&sub).components();
+                match (components.next(), components.next()) {
+                    (Some(std::path::Component::Normal(_)), None) => {}
+                    _ => return Err(anyhow::anyhow!("Invalid vault subdirectory '{}'", sub)),
+                }
+                self.get_safe_path(&format!("{}/{}", sub, filename))?
+            }
+            None => self.get_safe_path(filename)?,
+        };
 
         // Ensure directory exists
         if let Some(parent) = path.parent() {
@@ -52,6 +74,47 @@ impl VaultAdapter {
         Ok(())
     }
 
+    /// Lists the vault's subdirectories with their (recursive) file counts and sizes.
+    pub async fn list_subdirectories(&self) -> Result<Vec<VaultSubdirectory>> {
+        let mut result = Vec::new();
+        let mut entries = match fs::read_dir(&self.root_path).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(result),
+            Err(e) => return Err(e.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_dir() {
+                continue;
+            }
+
+            let mut file_count = 0u64;
+            let mut total_size_bytes = 0u64;
+            let mut pending = vec![entry.path()];
+            while let Some(dir) = pending.pop() {
+                let mut children = fs::read_dir(&dir).await?;
+                while let Some(child) = children.next_entry().await? {
+                    let file_type = child.file_type().await?;
+                    if file_type.is_dir() {
+                        pending.push(child.path());
+                    } else if file_type.is_file() {
+                        file_count += 1;
+                        total_size_bytes += child.metadata().await?.len();
+                    }
+                }
+            }
+
+            result.push(VaultSubdirectory {
+                subdirectory: entry.file_name().to_string_lossy().to_string(),
+                file_count,
+                total_size_bytes,
+            });
+        }
+
+        result.sort_by(|a, b| a.subdirectory.cmp(&b.subdirectory));
+        Ok(result)
+    }
+
     #[allow(dead_code)]
     pub async fn read_file(&self, filename: &str) -> Result<String> {
         let path = self.get_safe_path(filename)?;
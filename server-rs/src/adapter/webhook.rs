@@ -0,0 +1,124 @@
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Serialize;
+use sha2::Sha256;
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of the body>` when a callback secret is set.
+pub const SIGNATURE_HEADER: &str = "X-Tadpole-Signature";
+/// Longest `output_summary` sent to a callback, in characters.
+pub const MAX_OUTPUT_SUMMARY_CHARS: usize = 500;
+
+/// Body POSTed to a mission's `callback_url` when it completes or fails.
+#[derive(Debug, Clone, Serialize)]
+pub struct MissionCallbackPayload {
+    pub mission_id: String,
+    pub status: String,
+    pub cost_usd: f64,
+    pub output_summary: String,
+}
+
+impl MissionCallbackPayload {
+    pub fn new(mission_id: &str, status: &str, cost_usd: f64, output: &str) -> Self {
+        let output = output.trim();
+        let output_summary = if output.chars().count() > MAX_OUTPUT_SUMMARY_CHARS {
+            format!("{}...", output.chars().take(MAX_OUTPUT_SUMMARY_CHARS).collect::<String>())
+        } else {
+            output.to_string()
+        };
+        Self { mission_id: mission_id.to_string(), status: status.to_string(), cost_usd, output_summary }
+    }
+}
+
+/// The `X-Tadpole-Signature` value for `body`: `sha256=` followed by the hex HMAC-SHA256.
+pub fn sign(body: &[u8], secret: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Rejects callback URLs that are not absolute `http`/`https` URLs.
+pub fn validate_callback_url(url: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| anyhow::anyhow!("invalid callback URL '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(anyhow::anyhow!("callback URL '{}' must be an http or https URL", url));
+    }
+    Ok(())
+}
+
+/// POSTs `payload` as JSON to `url`, signed with `secret` when one is given.
+/// Returns the receiver's status code; only transport failures are errors.
+pub async fn deliver(client: &Client, url: &str, secret: Option<&str>, payload: &MissionCallbackPayload) -> Result<reqwest::StatusCode> {
+    let body = serde_json::to_vec(payload)?;
+    let mut request = client.post(url).header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(secret) = secret.filter(|s| !s.is_empty()) {
+        request = request.header(SIGNATURE_HEADER, sign(&body, secret));
+    }
+    Ok(request.body(body).send().await?.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn signature_matches_the_hmac_sha256_reference_vector() {
+        // The widely published HMAC-SHA256 example for key "key"
+        assert_eq!(
+            sign(b"The quick brown fox jumps over the lazy dog", "key"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+        assert_ne!(sign(b"{}", "one"), sign(b"{}", "two"));
+    }
+
+    #[test]
+    fn callback_urls_must_be_http() {
+        assert!(validate_callback_url("https://ci.example.com/hooks/tadpole").is_ok());
+        assert!(validate_callback_url("http://127.0.0.1:9000/cb").is_ok());
+        assert!(validate_callback_url("ftp://example.com/cb").is_err());
+        assert!(validate_callback_url("not a url").is_err());
+    }
+
+    #[tokio::test]
+    async fn delivered_body_verifies_against_the_signature_header() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 4096];
+            let header_end = loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                raw.extend_from_slice(&buf[..n]);
+                if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&raw[..header_end]).to_string();
+            let header = |name: &str| head.lines()
+                .find_map(|l| l.split_once(':').filter(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.trim().to_string()))
+                .unwrap_or_default();
+            let length: usize = header("content-length").parse().unwrap_or(0);
+            while raw.len() < header_end + length {
+                let n = socket.read(&mut buf).await.unwrap();
+                raw.extend_from_slice(&buf[..n]);
+            }
+            let _ = tx.send((header(SIGNATURE_HEADER), raw[header_end..header_end + length].to_vec()));
+            socket.write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+        });
+
+        let payload = MissionCallbackPayload::new("m-1", "completed", 0.25, &"x".repeat(MAX_OUTPUT_SUMMARY_CHARS + 10));
+        let status = deliver(&Client::new(), &format!("http://{}/cb", addr), Some("s3cret"), &payload).await.unwrap();
+        assert_eq!(status, reqwest::StatusCode::NO_CONTENT);
+
+        let (signature, body) = rx.await.expect("callback received");
+        assert_eq!(signature, sign(&body, "s3cret"));
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["mission_id"], "m-1");
+        assert_eq!(json["status"], "completed");
+        assert_eq!(json["cost_usd"], 0.25);
+        assert_eq!(json["output_summary"].as_str().unwrap().chars().count(), MAX_OUTPUT_SUMMARY_CHARS + 3);
+    }
+}
@@ -1,7 +1,12 @@
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use dashmap::DashMap;
 use tokio::fs;
+use tokio::sync::broadcast;
 use serde::{Deserialize, Serialize};
-use dashmap::DashMap;
 
 /// Represents a dynamic skill loaded from `data/skills/*.json`
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +18,109 @@ pub struct SkillDefinition {
     pub schema: serde_json::Value,
     pub doc_url: Option<String>,
     pub tags: Option<Vec<String>>,
+    /// A disabled skill keeps its definition but is never offered to or run by agents.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// CPU seconds the skill's subprocess may use; unset uses `SKILL_CPU_LIMIT_SECS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill_cpu_limit_secs: Option<u64>,
+    /// Memory cap in MB for the skill's subprocess; unset uses `SKILL_MEMORY_LIMIT_MB`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill_memory_limit_mb: Option<u64>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Run counters for one dynamic skill, updated lock-free by concurrent executions.
+#[derive(Debug, Default)]
+pub struct SkillMetrics {
+    pub invocations: AtomicU64,
+    pub total_duration_ms: AtomicU64,
+    pub failures: AtomicU64,
+}
+
+impl SkillMetrics {
+    pub fn record(&self, duration: Duration, failed: bool) {
+        self.invocations.fetch_add(1, Ordering::Relaxed);
+        self.total_duration_ms.fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+        if failed {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self, name: &str) -> SkillMetricsSnapshot {
+        let invocations = self.invocations.load(Ordering::Relaxed);
+        let total_duration_ms = self.total_duration_ms.load(Ordering::Relaxed);
+        let failures = self.failures.load(Ordering::Relaxed);
+        SkillMetricsSnapshot::new(name, invocations, total_duration_ms, failures)
+    }
+}
+
+/// Point-in-time view of a skill's metrics, as served by the metrics endpoints.
+#[derive(Debug, Clone, Serialize)]
+pub struct SkillMetricsSnapshot {
+    pub name: String,
+    pub invocations: u64,
+    pub failures: u64,
+    pub avg_duration_ms: f64,
+    pub failure_rate: f64,
+}
+
+impl SkillMetricsSnapshot {
+    pub fn new(name: &str, invocations: u64, total_duration_ms: u64, failures: u64) -> Self {
+        let per_run = |value: u64| if invocations == 0 { 0.0 } else { value as f64 / invocations as f64 };
+        Self {
+            name: name.to_string(),
+            invocations,
+            failures,
+            avg_duration_ms: per_run(total_duration_ms),
+            failure_rate: per_run(failures),
+        }
+    }
+}
+
+static SKILL_NAME_RE: once_cell::sync::Lazy<regex::Regex> =
+    once_cell::sync::Lazy::new(|| regex::Regex::new(r"^[a-z][a-z0-9_-]*$").unwrap());
+
+/// Checks that a skill definition is well-formed before it is saved or loaded: a lowercase
+/// `name`, a non-empty `execution_command`, and an object `schema` that declares `properties`.
+/// Every problem found is reported in the error, separated by `; `.
+pub fn validate_skill_schema(skill: &SkillDefinition) -> anyhow::Result<()> {
+    let mut problems = Vec::new();
+
+    if !SKILL_NAME_RE.is_match(&skill.name) {
+        problems.push(format!("name '{}' must match ^[a-z][a-z0-9_-]*$", skill.name));
+    }
+    if skill.execution_command.trim().is_empty() {
+        problems.push("execution_command must not be empty".to_string());
+    }
+    match skill.schema.as_object() {
+        None => problems.push("schema must be a JSON object".to_string()),
+        Some(schema) => {
+            if schema.get("type").and_then(|t| t.as_str()) != Some("object") {
+                problems.push("schema.type must be \"object\"".to_string());
+            }
+            match schema.get("properties") {
+                None => problems.push("schema must have a properties key".to_string()),
+                Some(props) if !props.is_object() => problems.push("schema.properties must be an object".to_string()),
+                Some(_) => {}
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(problems.join("; ")))
+    }
+}
+
+/// The file name (without extension) a skill or workflow is stored under: `name` with every
+/// character other than alphanumerics, `_` and `-` replaced by `_`.
+pub(crate) fn safe_file_stem(name: &str) -> String {
+    name.replace(|c: char| !c.is_alphanumeric() && c != '_' && c != '-', "_")
 }
 
 /// Represents a dynamic workflow loaded from `data/workflows/*.md`
@@ -25,28 +133,60 @@ pub struct WorkflowDefinition {
     pub tags: Option<Vec<String>>,
 }
 
+/// A single capability that changed between two reloads.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CapabilityChange {
+    /// "skill" or "workflow"
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub name: String,
+}
+
+/// What the most recent `reload_all` changed compared to the registry before it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CapabilitiesDiff {
+    pub added: Vec<CapabilityChange>,
+    pub removed: Vec<CapabilityChange>,
+    /// Skills whose `execution_command` or `schema` changed, and workflows whose content changed.
+    pub modified: Vec<CapabilityChange>,
+}
+
+/// Skills and workflows keyed by name. Swapped as one unit on reload.
+#[derive(Debug, Clone, Default)]
+pub struct CapabilitySet {
+    pub skills: HashMap<String, SkillDefinition>,
+    pub workflows: HashMap<String, WorkflowDefinition>,
+}
+
 /// The Capabilities registry holding in-memory maps of skills and workflows.
 pub struct CapabilitiesRegistry {
     skills_dir: PathBuf,
     workflows_dir: PathBuf,
-    pub skills: DashMap<String, SkillDefinition>,
-    pub workflows: DashMap<String, WorkflowDefinition>,
+    current: RwLock<CapabilitySet>,
+    /// The set replaced by the last reload, used to compute `last_diff`.
+    pub(crate) previous: RwLock<CapabilitySet>,
+    pub last_diff: Mutex<CapabilitiesDiff>,
+    /// Serializes reloads so `previous` and `last_diff` always describe the same swap.
+    reload_lock: tokio::sync::Mutex<()>,
+    /// File modification times seen by the last reload; the watcher reloads when they drift.
+    loaded_fingerprint: Mutex<BTreeMap<PathBuf, SystemTime>>,
+    /// Engine event channel; receives `capabilities:diff` and `capabilities:reloaded` after every reload.
+    events: Option<broadcast::Sender<serde_json::Value>>,
+    /// Per-skill run counters, keyed by skill name.
+    pub metrics: DashMap<String, Arc<SkillMetrics>>,
+    /// Keep `metrics` across reloads instead of starting every skill from zero.
+    pub preserve_metrics_on_reload: bool,
 }
 
 impl CapabilitiesRegistry {
-    pub async fn new() -> anyhow::Result<Self> {
-        let data_dir = std::env::var("DATA_DIR")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| {
-                let cwd = std::env::current_dir().unwrap_or_default();
-                if cwd.ends_with("server-rs") {
-                    PathBuf::from("data")
-                } else if cwd.join("server-rs").exists() {
-                    PathBuf::from("server-rs/data")
-                } else {
-                    PathBuf::from("data")
-                }
-            });
+    /// Loads the registry from the default data directory. When `events` is set, every
+    /// reload's diff is announced on that engine event channel.
+    pub async fn new(events: Option<broadcast::Sender<serde_json::Value>>) -> anyhow::Result<Self> {
+        Self::with_data_dir(Self::default_data_dir(), events).await
+    }
+
+    /// Loads the registry from `<data_dir>/skills` and `<data_dir>/workflows`.
+    pub async fn with_data_dir(data_dir: PathBuf, events: Option<broadcast::Sender<serde_json::Value>>) -> anyhow::Result<Self> {
         let skills_dir = data_dir.join("skills");
         let workflows_dir = data_dir.join("workflows");
 
@@ -57,18 +197,66 @@ impl CapabilitiesRegistry {
         let registry = Self {
             skills_dir,
             workflows_dir,
-            skills: DashMap::new(),
-            workflows: DashMap::new(),
+            current: RwLock::new(CapabilitySet::default()),
+            previous: RwLock::new(CapabilitySet::default()),
+            last_diff: Mutex::new(CapabilitiesDiff::default()),
+            reload_lock: tokio::sync::Mutex::new(()),
+            loaded_fingerprint: Mutex::new(BTreeMap::new()),
+            events,
+            metrics: DashMap::new(),
+            preserve_metrics_on_reload: false,
         };
 
         registry.reload_all().await?;
         Ok(registry)
     }
 
-    /// Read all defined skills and workflows from disk into memory
-    pub async fn reload_all(&self) -> anyhow::Result<()> {
-        let new_skills = DashMap::new();
-        let new_workflows = DashMap::new();
+    fn default_data_dir() -> PathBuf {
+        std::env::var("DATA_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                if cwd.ends_with("server-rs") {
+                    PathBuf::from("data")
+                } else if cwd.join("server-rs").exists() {
+                    PathBuf::from("server-rs/data")
+                } else {
+                    PathBuf::from("data")
+                }
+            })
+    }
+
+    fn read_current(&self) -> std::sync::RwLockReadGuard<'_, CapabilitySet> {
+        self.current.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_current(&self) -> std::sync::RwLockWriteGuard<'_, CapabilitySet> {
+        self.current.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn skill(&self, name: &str) -> Option<SkillDefinition> {
+        self.read_current().skills.get(name).cloned()
+    }
+
+    pub fn workflow(&self, name: &str) -> Option<WorkflowDefinition> {
+        self.read_current().workflows.get(name).cloned()
+    }
+
+    pub fn all_skills(&self) -> Vec<SkillDefinition> {
+        self.read_current().skills.values().cloned().collect()
+    }
+
+    pub fn all_workflows(&self) -> Vec<WorkflowDefinition> {
+        self.read_current().workflows.values().cloned().collect()
+    }
+
+    /// Read all defined skills and workflows from disk and swap them in as one unit.
+    /// Returns the number of skills and workflows now loaded.
+    pub async fn reload_all(&self) -> anyhow::Result<(usize, usize)> {
+        let _reload = self.reload_lock.lock().await;
+        // Taken before reading so an edit made mid-reload is still seen as a change
+        let fingerprint = self.fingerprint().await?;
+        let mut fresh = CapabilitySet::default();
 
         // Load Skills
         let mut skill_entries = fs::read_dir(&self.skills_dir).await?;
@@ -76,10 +264,12 @@ impl CapabilitiesRegistry {
             let path = entry.path();
             if path.extension().and_then(|e| e.to_str()) == Some("json") {
                 if let Ok(content) = fs::read_to_string(&path).await {
-                    if let Ok(skill) = serde_json::from_str::<SkillDefinition>(&content) {
-                        new_skills.insert(skill.name.clone(), skill);
-                    } else {
-                        tracing::warn!("Failed to parse skill file: {:?}", path);
+                    match serde_json::from_str::<SkillDefinition>(&content) {
+                        Ok(skill) => match validate_skill_schema(&skill) {
+                            Ok(()) => { fresh.skills.insert(skill.name.clone(), skill); }
+                            Err(e) => tracing::warn!("Skipping invalid skill file {:?}: {}", path, e),
+                        },
+                        Err(_) => tracing::warn!("Failed to parse skill file: {:?}", path),
                     }
                 }
             }
@@ -96,7 +286,7 @@ impl CapabilitiesRegistry {
                     .to_string();
                 
                 if let Ok(content) = fs::read_to_string(&path).await {
-                    new_workflows.insert(name.clone(), WorkflowDefinition { 
+                    fresh.workflows.insert(name.clone(), WorkflowDefinition { 
                         id: None,
                         name, 
                         content,
@@ -107,63 +297,187 @@ impl CapabilitiesRegistry {
             }
         }
 
-        // Atomic swap (clearing and then replacing in a tight loop to minimize window)
-        // Note: DashMap doesn't have a single-op 'replace_all', so we clear/insert.
-        self.skills.clear();
-        for kv in new_skills {
-            self.skills.insert(kv.0, kv.1);
+        // Atomic swap: readers see either the old set or the new one, never a mix
+        let (skills_loaded, workflows_loaded) = (fresh.skills.len(), fresh.workflows.len());
+        let outgoing = std::mem::replace(&mut *self.write_current(), fresh);
+
+        tracing::info!("Loaded {} skills and {} workflows from disk", skills_loaded, workflows_loaded);
+
+        let diff = Self::compute_diff(&outgoing, &self.read_current());
+        *self.previous.write().unwrap_or_else(|e| e.into_inner()) = outgoing;
+        if let Some(events) = &self.events {
+            let _ = events.send(serde_json::json!({
+                "type": "capabilities:diff",
+                "diff": diff
+            }));
+            let _ = events.send(serde_json::json!({
+                "type": "capabilities:reloaded",
+                "skillsLoaded": skills_loaded,
+                "workflowsLoaded": workflows_loaded
+            }));
         }
+        *self.last_diff.lock().unwrap() = diff;
+        if !self.preserve_metrics_on_reload {
+            self.metrics.clear();
+        }
+        *self.loaded_fingerprint.lock().unwrap_or_else(|e| e.into_inner()) = fingerprint;
+        Ok((skills_loaded, workflows_loaded))
+    }
 
-        self.workflows.clear();
-        for kv in new_workflows {
-            self.workflows.insert(kv.0, kv.1);
+    /// Modification times of every file in the skills and workflows directories.
+    /// Adding, removing or rewriting any file changes the result.
+    async fn fingerprint(&self) -> anyhow::Result<BTreeMap<PathBuf, SystemTime>> {
+        let mut stamps = BTreeMap::new();
+        for dir in [&self.skills_dir, &self.workflows_dir] {
+            let mut entries = fs::read_dir(dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let modified = entry.metadata().await?.modified()?;
+                stamps.insert(entry.path(), modified);
+            }
         }
+        Ok(stamps)
+    }
 
-        tracing::info!("Loaded {} skills and {} workflows from disk", self.skills.len(), self.workflows.len());
-        Ok(())
+    /// Polls the data directories every `interval` and reloads when a file was added,
+    /// removed or modified. Holds a weak reference, so it stops once the registry is dropped.
+    pub fn spawn_watcher(self: &Arc<Self>, interval: Duration) {
+        let registry = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(registry) = registry.upgrade() else { break };
+                let current = match registry.fingerprint().await {
+                    Ok(current) => current,
+                    Err(e) => {
+                        tracing::warn!("⚠️ [Capabilities] Could not scan data directories: {}", e);
+                        continue;
+                    }
+                };
+                let changed = *registry.loaded_fingerprint.lock().unwrap_or_else(|e| e.into_inner()) != current;
+                if changed {
+                    tracing::info!("🔄 [Capabilities] Change detected on disk, reloading...");
+                    if let Err(e) = registry.reload_all().await {
+                        tracing::warn!("⚠️ [Capabilities] Reload failed: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Compares the set a reload replaced with the one it installed.
+    fn compute_diff(previous: &CapabilitySet, current: &CapabilitySet) -> CapabilitiesDiff {
+        let mut diff = CapabilitiesDiff::default();
+        let change = |kind, name: &String| CapabilityChange { kind, name: name.clone() };
+
+        for (name, skill) in &current.skills {
+            match previous.skills.get(name) {
+                None => diff.added.push(change("skill", name)),
+                Some(prev) if prev.execution_command != skill.execution_command || prev.schema != skill.schema => {
+                    diff.modified.push(change("skill", name))
+                }
+                Some(_) => {}
+            }
+        }
+        for name in previous.skills.keys() {
+            if !current.skills.contains_key(name) {
+                diff.removed.push(change("skill", name));
+            }
+        }
+
+        for (name, workflow) in &current.workflows {
+            match previous.workflows.get(name) {
+                None => diff.added.push(change("workflow", name)),
+                Some(prev) if prev.content != workflow.content => diff.modified.push(change("workflow", name)),
+                Some(_) => {}
+            }
+        }
+        for name in previous.workflows.keys() {
+            if !current.workflows.contains_key(name) {
+                diff.removed.push(change("workflow", name));
+            }
+        }
+
+        // HashMap iteration order is arbitrary; keep the output stable for the UI
+        for list in [&mut diff.added, &mut diff.removed, &mut diff.modified] {
+            list.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+        }
+        diff
     }
 
     pub async fn save_skill(&self, skill: SkillDefinition) -> anyhow::Result<()> {
-        // Sanitize name for filename
-        let safe_name = skill.name.replace(|c: char| !c.is_alphanumeric() && c != '_' && c != '-', "_");
+        validate_skill_schema(&skill)?;
+
+        let safe_name = safe_file_stem(&skill.name);
         let path = self.skills_dir.join(format!("{}.json", safe_name));
         
         let content = serde_json::to_string_pretty(&skill)?;
         fs::write(&path, content).await?;
         
-        self.skills.insert(skill.name.clone(), skill);
+        self.write_current().skills.insert(skill.name.clone(), skill);
         Ok(())
     }
 
+    /// Turns a skill on or off and writes the flag to its JSON file.
+    /// Returns the updated definition, or `None` if no such skill is loaded.
+    pub async fn set_skill_enabled(&self, name: &str, enabled: bool) -> anyhow::Result<Option<SkillDefinition>> {
+        let Some(mut skill) = self.skill(name) else {
+            return Ok(None);
+        };
+        skill.enabled = enabled;
+        self.save_skill(skill.clone()).await?;
+        Ok(Some(skill))
+    }
+
     pub async fn delete_skill(&self, name: &str) -> anyhow::Result<()> {
-        let safe_name = name.replace(|c: char| !c.is_alphanumeric() && c != '_' && c != '-', "_");
+        let safe_name = safe_file_stem(name);
         let path = self.skills_dir.join(format!("{}.json", safe_name));
         
         if path.exists() {
             fs::remove_file(path).await?;
         }
-        self.skills.remove(name);
+        self.write_current().skills.remove(name);
+        self.metrics.remove(name);
         Ok(())
     }
 
+    /// Counts one execution of `name` that took `duration`.
+    pub fn record_skill_run(&self, name: &str, duration: Duration, failed: bool) {
+        self.metrics.entry(name.to_string()).or_default().record(duration, failed);
+    }
+
+    /// Metrics for `name`, all zero if it has not run since the last reset.
+    pub fn skill_metrics(&self, name: &str) -> SkillMetricsSnapshot {
+        match self.metrics.get(name) {
+            Some(metrics) => metrics.snapshot(name),
+            None => SkillMetricsSnapshot::new(name, 0, 0, 0),
+        }
+    }
+
+    /// Metrics for every skill that has run since the last reset, sorted by name.
+    pub fn all_skill_metrics(&self) -> Vec<SkillMetricsSnapshot> {
+        let mut all: Vec<_> = self.metrics.iter().map(|entry| entry.value().snapshot(entry.key())).collect();
+        all.sort_by(|a, b| a.name.cmp(&b.name));
+        all
+    }
+
     pub async fn save_workflow(&self, workflow: WorkflowDefinition) -> anyhow::Result<()> {
-        let safe_name = workflow.name.replace(|c: char| !c.is_alphanumeric() && c != '_' && c != '-', "_");
+        let safe_name = safe_file_stem(&workflow.name);
         let path = self.workflows_dir.join(format!("{}.md", safe_name));
         
         fs::write(&path, &workflow.content).await?;
         
-        self.workflows.insert(workflow.name.clone(), workflow);
+        self.write_current().workflows.insert(workflow.name.clone(), workflow);
         Ok(())
     }
 
     pub async fn delete_workflow(&self, name: &str) -> anyhow::Result<()> {
-        let safe_name = name.replace(|c: char| !c.is_alphanumeric() && c != '_' && c != '-', "_");
+        let safe_name = safe_file_stem(name);
         let path = self.workflows_dir.join(format!("{}.md", safe_name));
         
         if path.exists() {
             fs::remove_file(path).await?;
         }
-        self.workflows.remove(name);
+        self.write_current().workflows.remove(name);
         Ok(())
     }
 }
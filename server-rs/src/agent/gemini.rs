@@ -1,7 +1,7 @@
 use reqwest::Client;
 
 use serde::{Deserialize, Serialize};
-use crate::agent::types::{ModelConfig, TokenUsage};
+use crate::agent::types::{ConversationTurn, GeminiSafetySetting, ModelConfig, TokenUsage};
 
 #[derive(Debug, Serialize)]
 struct GeminiPart {
@@ -31,6 +31,8 @@ struct GeminiRequest {
     contents: Vec<GeminiContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<GeminiTool>>,
+    #[serde(rename = "safetySettings", skip_serializing_if = "Option::is_none")]
+    safety_settings: Option<Vec<GeminiSafetySetting>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
 }
@@ -60,11 +62,12 @@ struct GeminiResponseContent {
 
 #[derive(Debug, Deserialize)]
 struct GeminiUsageMetadata {
-    #[serde(rename = "promptTokenCount")]
+    // Defaults: intermediate stream chunks may omit some counts
+    #[serde(rename = "promptTokenCount", default)]
     prompt_token_count: u32,
-    #[serde(rename = "candidatesTokenCount")]
+    #[serde(rename = "candidatesTokenCount", default)]
     candidates_token_count: u32,
-    #[serde(rename = "totalTokenCount")]
+    #[serde(rename = "totalTokenCount", default)]
     total_token_count: u32,
 }
 
@@ -89,30 +92,51 @@ impl GeminiProvider {
     }
 
 
+    fn endpoint(&self, method: &str) -> String {
+        let base_url = self.config.base_url.clone().unwrap_or_else(|| "https://generativelanguage.googleapis.com/v1".to_string());
+        format!("{}/models/{}:{}", base_url, self.config.model_id, method)
+    }
+
+    fn build_request(&self, system_prompt: &str, user_message: &str, history: Option<&[ConversationTurn]>, tools: Option<Vec<GeminiTool>>) -> GeminiRequest {
+        // Prior turns go first as their own contents; Gemini calls the assistant side "model"
+        let mut contents: Vec<GeminiContent> = self.config.history_window(history).iter().map(|turn| GeminiContent {
+            role: if turn.role == "assistant" { "model".to_string() } else { turn.role.clone() },
+            parts: vec![GeminiPart { text: turn.content.clone() }],
+        }).collect();
+        contents.push(GeminiContent {
+            role: "user".to_string(),
+            parts: vec![GeminiPart {
+                text: user_message.to_string(),
+            }],
+        });
+
+        // The v1 API has no system role, so the instructions ride on the first user turn
+        if !system_prompt.is_empty() {
+            if let Some(first) = contents.iter_mut().find(|c| c.role == "user") {
+                first.parts[0].text = format!("{}\n\nUSER MESSAGE:\n{}", system_prompt, first.parts[0].text);
+            }
+        }
+
+        GeminiRequest {
+            contents,
+            tools,
+            safety_settings: self.config.safety_settings.clone(),
+            user: self.config.external_id.clone(),
+        }
+    }
+
     /// Generates a response from the Gemini HTTP API.
     pub async fn generate(
         &self,
-        prompt: &str,
+        system_prompt: &str,
+        user_message: &str,
+        history: Option<&[ConversationTurn]>,
         tools: Option<Vec<GeminiTool>>,
     ) -> anyhow::Result<(String, Vec<crate::agent::types::GeminiFunctionCall>, Option<TokenUsage>)> {
-        let base_url = self.config.base_url.clone().unwrap_or_else(|| "https://generativelanguage.googleapis.com/v1".to_string());
-        let url = format!(
-            "{}/models/{}:generateContent",
-            base_url,
-            self.config.model_id
-        );
+        let url = self.endpoint("generateContent");
         tracing::info!("🌐 [Gemini] Calling URL: {}", url);
 
-        let request_body = GeminiRequest {
-            contents: vec![GeminiContent {
-                role: "user".to_string(),
-                parts: vec![GeminiPart {
-                    text: prompt.to_string(),
-                }],
-            }],
-            tools,
-            user: self.config.external_id.clone(),
-        };
+        let request_body = self.build_request(system_prompt, user_message, history, tools);
 
         let res = self.client
             .post(&url)
@@ -157,4 +181,128 @@ impl GeminiProvider {
 
         Ok((output_text, function_calls, token_usage))
     }
+
+    /// Streams a response via `streamGenerateContent` (SSE). Text parts are yielded as deltas;
+    /// function calls and usage are collected for `StreamingResponse::finish`.
+    pub async fn generate_streaming(
+        &self,
+        system_prompt: &str,
+        user_message: &str,
+        history: Option<&[ConversationTurn]>,
+        tools: Option<Vec<GeminiTool>>,
+    ) -> anyhow::Result<crate::agent::streaming::StreamingResponse> {
+        let url = format!("{}?alt=sse", self.endpoint("streamGenerateContent"));
+        tracing::info!("🌐 [Gemini] Streaming from URL: {}", url);
+
+        let res = self.client
+            .post(&url)
+            .header("x-goog-api-key", &self.api_key)
+            .json(&self.build_request(system_prompt, user_message, history, tools))
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            let error_text = res.text().await?;
+            return Err(anyhow::anyhow!("Gemini API Error: {}", error_text));
+        }
+
+        Ok(crate::agent::streaming::spawn_sse_reader(res, |data, outcome, deltas| {
+            let chunk: GeminiResponse = serde_json::from_str(data)?;
+            let parts = chunk.candidates.into_iter().flatten().next()
+                .and_then(|c| c.content)
+                .map(|c| c.parts)
+                .unwrap_or_default();
+            for part in parts {
+                if let Some(text) = part.text {
+                    let _ = deltas.unbounded_send(text);
+                }
+                if let Some(fc) = part.function_call {
+                    outcome.function_calls.push(crate::agent::types::GeminiFunctionCall { name: fc.name, args: fc.args });
+                }
+            }
+            // Usage is cumulative; the last chunk carries the final totals
+            if let Some(usage) = chunk.usage_metadata {
+                outcome.usage = Some(TokenUsage {
+                    input_tokens: usage.prompt_token_count,
+                    output_tokens: usage.candidates_token_count,
+                    total_tokens: usage.total_token_count,
+                });
+            }
+            Ok(())
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gemini_request_replays_history_with_system_prompt_first() {
+        let config = ModelConfig {
+            provider: "google".to_string(),
+            model_id: "gemini-1.5-flash".to_string(),
+            api_key: None,
+            base_url: None,
+            system_prompt: None,
+            temperature: None,
+            max_tokens: None,
+            external_id: None,
+            rpm: None,
+            rpd: None,
+            tpm: None,
+            tpd: None,
+            max_history_turns: crate::agent::types::DEFAULT_MAX_HISTORY_TURNS,
+            safety_settings: None,
+        };
+        let provider = GeminiProvider::new(Client::new(), "test-key".to_string(), config);
+        let history = vec![
+            ConversationTurn { role: "user".to_string(), content: "first".to_string() },
+            ConversationTurn { role: "assistant".to_string(), content: "second".to_string() },
+        ];
+
+        let body = serde_json::to_value(provider.build_request("system", "now", Some(&history), None)).unwrap();
+        let contents = body["contents"].as_array().unwrap();
+        let roles: Vec<&str> = contents.iter().map(|c| c["role"].as_str().unwrap()).collect();
+        let texts: Vec<&str> = contents.iter().map(|c| c["parts"][0]["text"].as_str().unwrap()).collect();
+        assert_eq!(roles, vec!["user", "model", "user"]);
+        assert_eq!(texts, vec!["system\n\nUSER MESSAGE:\nfirst", "second", "now"]);
+
+        let body = serde_json::to_value(provider.build_request("system", "now", None, None)).unwrap();
+        assert_eq!(body["contents"].as_array().unwrap().len(), 1);
+        assert_eq!(body["contents"][0]["parts"][0]["text"], "system\n\nUSER MESSAGE:\nnow");
+    }
+
+    #[test]
+    fn test_gemini_request_includes_safety_settings() {
+        let config = ModelConfig {
+            provider: "google".to_string(),
+            model_id: "gemini-1.5-flash".to_string(),
+            api_key: None,
+            base_url: None,
+            system_prompt: None,
+            temperature: None,
+            max_tokens: None,
+            external_id: None,
+            rpm: None,
+            rpd: None,
+            tpm: None,
+            tpd: None,
+            max_history_turns: crate::agent::types::DEFAULT_MAX_HISTORY_TURNS,
+            safety_settings: Some(GeminiSafetySetting::permissive()),
+        };
+        let provider = GeminiProvider::new(Client::new(), "test-key".to_string(), config);
+
+        let body = serde_json::to_value(provider.build_request("system", "now", None, None)).unwrap();
+        let settings = body["safetySettings"].as_array().unwrap();
+        assert_eq!(settings.len(), 4);
+        assert_eq!(settings[0]["category"], "HARM_CATEGORY_HARASSMENT");
+        assert!(settings.iter().all(|s| s["threshold"] == "BLOCK_NONE"));
+
+        let mut unset = provider.config.clone();
+        unset.safety_settings = None;
+        let provider = GeminiProvider::new(Client::new(), "test-key".to_string(), unset);
+        let body = serde_json::to_value(provider.build_request("system", "now", None, None)).unwrap();
+        assert!(body.get("safetySettings").is_none());
+    }
 }
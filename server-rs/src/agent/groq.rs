@@ -1,6 +1,6 @@
 use reqwest::{Client, header};
 use serde::{Deserialize, Serialize};
-use crate::agent::types::{ModelConfig, TokenUsage, GeminiFunctionCall};
+use crate::agent::types::{ConversationTurn, ModelConfig, TokenUsage, GeminiFunctionCall};
 use regex::Regex;
 use once_cell::sync::Lazy;
 
@@ -35,6 +35,8 @@ struct GroqRequest {
     user: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<GroqTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -73,6 +75,43 @@ struct GroqResponse {
     usage: Option<GroqUsage>,
 }
 
+#[derive(Debug, Deserialize)]
+struct GroqStreamChunk {
+    #[serde(default)]
+    choices: Vec<GroqStreamChoice>,
+    usage: Option<GroqUsage>,
+    /// Groq reports streaming usage here on the final chunk.
+    x_groq: Option<GroqStreamExtra>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroqStreamExtra {
+    usage: Option<GroqUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroqStreamChoice {
+    delta: GroqStreamDelta,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroqStreamDelta {
+    content: Option<String>,
+    tool_calls: Option<Vec<GroqToolCallDelta>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroqToolCallDelta {
+    index: usize,
+    function: Option<GroqFunctionDelta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroqFunctionDelta {
+    name: Option<String>,
+    arguments: Option<String>,
+}
+
 pub struct GroqProvider {
     client: Client,
     config: ModelConfig,
@@ -84,6 +123,47 @@ static FUNCTION_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"(?s)<function=([a-zA-Z0-9_-]+)[^\{]*(\{.*?\})[^<]*(?:</function>)?").unwrap()
 });
 
+/// Maps Gemini tool declarations to the Groq/OpenAI `tools` schema. Empty sets become `None`.
+fn map_tools(tools: Option<&Vec<crate::agent::gemini::GeminiTool>>) -> Option<Vec<GroqTool>> {
+    let mapped: Vec<GroqTool> = tools?.iter().flat_map(|t| {
+        t.function_declarations.iter().map(|f| {
+            GroqTool {
+                tool_type: "function".to_string(),
+                function: GroqFunctionDefinition {
+                    name: f.name.clone(),
+                    description: f.description.clone(),
+                    parameters: f.parameters.clone(),
+                },
+            }
+        })
+    }).collect();
+    if mapped.is_empty() { None } else { Some(mapped) }
+}
+
+/// Extracts a Llama-style `<function=name>{...}</function>` tag from plain text output.
+pub fn recover_function_call(text: &str) -> Option<GeminiFunctionCall> {
+    let caps = FUNCTION_REGEX.captures(text)?;
+    let name = caps.get(1).map(|m| m.as_str().to_string()).unwrap_or_default();
+    let args_str = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+
+    let mut json_str = args_str.trim().to_string();
+    if !json_str.starts_with('{') {
+        json_str.insert(0, '{');
+    }
+    if !json_str.ends_with('}') {
+        json_str.push('}');
+    }
+
+    let args: serde_json::Value = serde_json::from_str(&json_str)
+        .unwrap_or_else(|_| {
+            tracing::warn!("🛠️ [Recovery] Failed to parse recovered JSON from Groq format: {}", json_str);
+            serde_json::json!({})
+        });
+
+    tracing::info!("🛠️ [Recovery] Extracted function call from tags: {}", name);
+    Some(GeminiFunctionCall { name, args })
+}
+
 impl GroqProvider {
     /// Creates a GroqProvider with a shared `reqwest::Client`.
     pub fn new(client: Client, api_key: String, config: ModelConfig) -> Self {
@@ -95,46 +175,107 @@ impl GroqProvider {
         &self,
         system_prompt: &str,
         user_message: &str,
+        history: Option<&[ConversationTurn]>,
         tools: Option<Vec<crate::agent::gemini::GeminiTool>>,
     ) -> anyhow::Result<(String, Vec<GeminiFunctionCall>, Option<TokenUsage>)> {
-        self.generate_internal(system_prompt, user_message, tools, None).await
+        self.generate_internal(system_prompt, user_message, history, tools, None).await
+    }
+
+    /// System prompt, then the capped prior turns, then the current user message.
+    fn build_messages(&self, system_prompt: &str, user_message: &str, history: Option<&[ConversationTurn]>) -> Vec<GroqMessage> {
+        let mut messages = vec![GroqMessage {
+            role: "system".to_string(),
+            content: Some(system_prompt.to_string()),
+        }];
+        messages.extend(self.config.history_window(history).iter().map(|turn| GroqMessage {
+            role: turn.role.clone(),
+            content: Some(turn.content.clone()),
+        }));
+        messages.push(GroqMessage {
+            role: "user".to_string(),
+            content: Some(user_message.to_string()),
+        });
+        messages
+    }
+
+    /// Streams a response (`stream: true`). Content is yielded as deltas; tool-call fragments
+    /// are reassembled by index and, with usage, returned from `StreamingResponse::finish`.
+    pub async fn generate_streaming(
+        &self,
+        system_prompt: &str,
+        user_message: &str,
+        history: Option<&[ConversationTurn]>,
+        tools: Option<Vec<crate::agent::gemini::GeminiTool>>,
+    ) -> anyhow::Result<crate::agent::streaming::StreamingResponse> {
+        let url = self.config.base_url.as_deref().unwrap_or("https://api.groq.com/openai/v1/chat/completions");
+
+        let request_body = GroqRequest {
+            model: self.config.model_id.clone(),
+            messages: self.build_messages(system_prompt, user_message, history),
+            temperature: self.config.temperature,
+            user: self.config.external_id.clone(),
+            tools: map_tools(tools.as_ref()),
+            stream: Some(true),
+        };
+
+        let res = self.client
+            .post(url)
+            .header(header::AUTHORIZATION, format!("Bearer {}", self.api_key))
+            .json(&request_body)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            let error_text = res.text().await?;
+            return Err(anyhow::anyhow!("Groq API Error: {}", error_text));
+        }
+
+        // (name, arguments) per tool-call index, filled in across chunks
+        let mut partial_calls: Vec<(String, String)> = Vec::new();
+        Ok(crate::agent::streaming::spawn_sse_reader(res, move |data, outcome, deltas| {
+            let chunk: GroqStreamChunk = serde_json::from_str(data)?;
+            for choice in chunk.choices {
+                if let Some(text) = choice.delta.content {
+                    let _ = deltas.unbounded_send(text);
+                }
+                for tc in choice.delta.tool_calls.unwrap_or_default() {
+                    if partial_calls.len() <= tc.index {
+                        partial_calls.resize(tc.index + 1, (String::new(), String::new()));
+                    }
+                    if let Some(function) = tc.function {
+                        let (name, arguments) = &mut partial_calls[tc.index];
+                        name.push_str(&function.name.unwrap_or_default());
+                        arguments.push_str(&function.arguments.unwrap_or_default());
+                    }
+                    outcome.function_calls = partial_calls.iter().map(|(name, arguments)| GeminiFunctionCall {
+                        name: name.clone(),
+                        args: serde_json::from_str(arguments).unwrap_or(serde_json::json!({})),
+                    }).collect();
+                }
+            }
+            if let Some(u) = chunk.usage.or(chunk.x_groq.and_then(|x| x.usage)) {
+                outcome.usage = Some(TokenUsage {
+                    input_tokens: u.prompt_tokens,
+                    output_tokens: u.completion_tokens,
+                    total_tokens: u.total_tokens,
+                });
+            }
+            Ok(())
+        }))
     }
 
     async fn generate_internal(
         &self,
         system_prompt: &str,
         user_message: &str,
+        history: Option<&[ConversationTurn]>,
         tools: Option<Vec<crate::agent::gemini::GeminiTool>>,
         retry_msg: Option<String>,
     ) -> anyhow::Result<(String, Vec<GeminiFunctionCall>, Option<TokenUsage>)> {
         let url = self.config.base_url.as_deref().unwrap_or("https://api.groq.com/openai/v1/chat/completions");
 
-        // Map Gemini tools to Groq/OpenAI tools
-        let groq_tools = tools.as_ref().map(|ts| {
-            ts.iter().flat_map(|t| {
-                t.function_declarations.iter().map(|f| {
-                    GroqTool {
-                        tool_type: "function".to_string(),
-                        function: GroqFunctionDefinition {
-                            name: f.name.clone(),
-                            description: f.description.clone(),
-                            parameters: f.parameters.clone(),
-                        },
-                    }
-                })
-            }).collect::<Vec<GroqTool>>()
-        });
 
-        let mut messages = vec![
-            GroqMessage {
-                role: "system".to_string(),
-                content: Some(system_prompt.to_string()),
-            },
-            GroqMessage {
-                role: "user".to_string(),
-                content: Some(user_message.to_string()),
-            },
-        ];
+        let mut messages = self.build_messages(system_prompt, user_message, history);
 
         // If this is a retry, append the failed generation and correction instruction
         if let Some(ref r) = retry_msg {
@@ -153,7 +294,8 @@ impl GroqProvider {
             messages,
             temperature: self.config.temperature,
             user: self.config.external_id.clone(),
-            tools: if groq_tools.as_ref().map_or(true, |t| t.is_empty()) { None } else { groq_tools },
+            tools: map_tools(tools.as_ref()),
+            stream: None,
         };
 
         let res = self.client
@@ -202,7 +344,7 @@ impl GroqProvider {
                         // 2. If recovery fails, fallback to LLM self-correction
                         if retry_msg.is_none() {
                             tracing::warn!("🛠️ [Groq] Tool call failed natively. Attempting self-correction retry...");
-                            let result = Box::pin(self.generate_internal(system_prompt, user_message, tools, Some(failed_gen.to_string()))).await;
+                            let result = Box::pin(self.generate_internal(system_prompt, user_message, history, tools, Some(failed_gen.to_string()))).await;
                             return result;
                         }
                     }
@@ -229,29 +371,9 @@ impl GroqProvider {
                     args,
                 });
             }
-        } else {
+        } else if let Some(fc) = recover_function_call(&output_text) {
             // RECOVERY: Check for manual function tags (Llama 3 style)
-            if let Some(caps) = FUNCTION_REGEX.captures(&output_text) {
-                let name = caps.get(1).map(|m| m.as_str().to_string()).unwrap_or_default();
-                let args_str = caps.get(2).map(|m| m.as_str()).unwrap_or("");
-                
-                let mut json_str = args_str.trim().to_string();
-                if !json_str.starts_with('{') {
-                    json_str.insert(0, '{');
-                }
-                if !json_str.ends_with('}') {
-                    json_str.push('}');
-                }
-                
-                let args: serde_json::Value = serde_json::from_str(&json_str)
-                    .unwrap_or_else(|_| {
-                        tracing::warn!("🛠️ [Recovery] Failed to parse recovered JSON from Groq format: {}", json_str);
-                        serde_json::json!({})
-                    });
-                
-                tracing::info!("🛠️ [Recovery] Extracted function call from tags: {}", name);
-                function_calls.push(GeminiFunctionCall { name, args });
-            }
+            function_calls.push(fc);
         }
 
         let token_usage = parsed.usage.map(|u| TokenUsage {
@@ -263,9 +385,18 @@ impl GroqProvider {
         Ok((output_text, function_calls, token_usage))
     }
 
+    /// Transcribes speech into text in the language it was spoken.
     pub async fn transcribe(&self, audio_data: Vec<u8>, filename: &str) -> anyhow::Result<String> {
+        self.whisper(AudioTask::Transcribe, audio_data, filename).await
+    }
+
+    /// Translates speech in any language Whisper understands into English text.
+    pub async fn translate(&self, audio_data: Vec<u8>, filename: &str) -> anyhow::Result<String> {
+        self.whisper(AudioTask::Translate, audio_data, filename).await
+    }
+
+    async fn whisper(&self, task: AudioTask, audio_data: Vec<u8>, filename: &str) -> anyhow::Result<String> {
         use reqwest::multipart;
-        let url = "https://api.groq.com/openai/v1/audio/transcriptions";
 
         let part = multipart::Part::bytes(audio_data)
             .file_name(filename.to_string())
@@ -275,7 +406,7 @@ impl GroqProvider {
             .part("file", part)
             .text("model", self.config.model_id.clone());
 
-        let res = self.client.post(url)
+        let res = self.client.post(task.endpoint())
             .header(header::AUTHORIZATION, format!("Bearer {}", self.api_key))
             .multipart(form)
             .send()
@@ -296,6 +427,39 @@ impl GroqProvider {
     }
 }
 
+/// What Groq Whisper should do with an uploaded recording.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioTask {
+    /// Text in the spoken language.
+    #[default]
+    Transcribe,
+    /// English text, whatever the spoken language.
+    Translate,
+}
+
+impl AudioTask {
+    /// The Whisper endpoint that performs this task.
+    pub fn endpoint(self) -> &'static str {
+        match self {
+            AudioTask::Transcribe => "https://api.groq.com/openai/v1/audio/transcriptions",
+            AudioTask::Translate => "https://api.groq.com/openai/v1/audio/translations",
+        }
+    }
+
+    /// The model to use given the provider's configured `audio_model`. Translation always
+    /// uses `whisper-large-v3`, since the turbo variants can only transcribe.
+    pub fn model(self, audio_model: Option<&str>) -> String {
+        match self {
+            AudioTask::Transcribe => audio_model.unwrap_or(DEFAULT_WHISPER_MODEL).to_string(),
+            AudioTask::Translate => DEFAULT_WHISPER_MODEL.to_string(),
+        }
+    }
+}
+
+/// Whisper model used when the Groq provider has no `audio_model`.
+pub const DEFAULT_WHISPER_MODEL: &str = "whisper-large-v3";
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -359,4 +523,99 @@ mod tests {
             panic!("Regex did not match the missing-bracket form!");
         }
     }
+
+    #[test]
+    fn test_groq_messages_include_capped_history() {
+        let config = ModelConfig {
+            provider: "groq".to_string(),
+            model_id: "llama-3.3-70b-versatile".to_string(),
+            api_key: None,
+            base_url: None,
+            system_prompt: None,
+            temperature: None,
+            max_tokens: None,
+            external_id: None,
+            rpm: None,
+            rpd: None,
+            tpm: None,
+            tpd: None,
+            max_history_turns: 2,
+            safety_settings: None,
+        };
+        let provider = GroqProvider::new(Client::new(), "test-key".to_string(), config);
+        let history = vec![
+            ConversationTurn { role: "user".to_string(), content: "first".to_string() },
+            ConversationTurn { role: "assistant".to_string(), content: "second".to_string() },
+            ConversationTurn { role: "user".to_string(), content: "third".to_string() },
+        ];
+
+        let messages = provider.build_messages("system", "now", Some(&history));
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_deref().unwrap()).collect();
+        assert_eq!(roles, vec!["system", "assistant", "user", "user"]);
+        assert_eq!(contents, vec!["system", "second", "third", "now"]);
+    }
+
+    #[tokio::test]
+    async fn test_groq_stream_yields_deltas_and_reassembles_tool_calls() {
+        use futures::StreamExt;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let events = [
+            json!({"choices": [{"delta": {"content": "Looking "}}]}),
+            json!({"choices": [{"delta": {"content": "that up."}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [{"index": 0, "function": {"name": "share_finding", "arguments": "{\"topic\":"}}]}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [{"index": 0, "function": {"arguments": "\"Pricing\"}"}}]}}]}),
+            json!({"choices": [], "x_groq": {"usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}}}),
+        ];
+        let mut body = String::new();
+        for event in &events {
+            body.push_str(&format!("data: {}\n\n", event));
+        }
+        body.push_str("data: [DONE]\n\n");
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 8192];
+            let _ = socket.read(&mut buf).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n{}",
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let config = ModelConfig {
+            provider: "groq".to_string(),
+            model_id: "llama-3.3-70b-versatile".to_string(),
+            api_key: None,
+            base_url: Some(format!("http://{}/openai/v1/chat/completions", addr)),
+            system_prompt: None,
+            temperature: None,
+            max_tokens: None,
+            external_id: None,
+            rpm: None,
+            rpd: None,
+            tpm: None,
+            tpd: None,
+            max_history_turns: crate::agent::types::DEFAULT_MAX_HISTORY_TURNS,
+            safety_settings: None,
+        };
+        let provider = GroqProvider::new(Client::new(), "test-key".to_string(), config);
+        let mut stream = provider.generate_streaming("system", "user", None, None).await.unwrap();
+
+        let mut deltas = Vec::new();
+        while let Some(delta) = stream.deltas.next().await {
+            deltas.push(delta);
+        }
+        assert_eq!(deltas, vec!["Looking ".to_string(), "that up.".to_string()]);
+
+        let (calls, usage) = stream.finish().await.unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "share_finding");
+        assert_eq!(calls[0].args["topic"], "Pricing");
+        assert_eq!(usage.unwrap().total_tokens, 15);
+    }
 }
@@ -1,8 +1,13 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::process::Command;
+use tokio::sync::broadcast;
 use serde::{Deserialize, Serialize};
 // use crate::agent::types::ToolCall;
 
+/// Seconds a hook script may run when neither `HOOK_TIMEOUT_SECS` nor the config file sets it.
+pub const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 10;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookContext {
     pub agent_id: String,
@@ -10,19 +15,98 @@ pub struct HookContext {
     pub skill: String,
 }
 
+/// What a failing hook script does to the tool call it guards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HookFailureMode {
+    /// The error is returned and the tool call is aborted.
+    #[default]
+    Abort,
+    /// The error is logged and reported as `hooks:script_failed`; the tool call goes ahead.
+    Warn,
+}
+
+impl HookFailureMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Abort => "abort",
+            Self::Warn => "warn",
+        }
+    }
+}
+
+impl std::str::FromStr for HookFailureMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "abort" => Ok(Self::Abort),
+            "warn" => Ok(Self::Warn),
+            other => Err(anyhow::anyhow!("unknown hook failure mode '{}' (expected 'abort' or 'warn')", other)),
+        }
+    }
+}
+
+/// `data/hooks/config.toml`. Environment variables take precedence over the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HooksConfig {
+    /// `HOOK_TIMEOUT_SECS`
+    timeout_secs: Option<u64>,
+    /// `HOOK_FAILURE_MODE`
+    failure_mode: Option<String>,
+}
+
 pub struct HooksManager {
     hooks_dir: PathBuf,
+    timeout_secs: u64,
+    failure_mode: HookFailureMode,
+    events: Option<broadcast::Sender<serde_json::Value>>,
 }
 
 impl HooksManager {
-    pub fn new(data_dir: &Path) -> Self {
-        Self {
-            hooks_dir: data_dir.join("hooks"),
-        }
+    /// Reads the timeout and failure mode from `HOOK_TIMEOUT_SECS`/`HOOK_FAILURE_MODE`, falling back
+    /// to `<data_dir>/hooks/config.toml` and then the defaults. When `events` is set, failures that
+    /// do not abort the tool call are broadcast as `hooks:script_failed`.
+    pub fn new(data_dir: &Path, events: Option<broadcast::Sender<serde_json::Value>>) -> Self {
+        let hooks_dir = data_dir.join("hooks");
+        let file = load_config(&hooks_dir.join("config.toml"));
+
+        let timeout_secs = std::env::var("HOOK_TIMEOUT_SECS").ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .or(file.timeout_secs)
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS);
+        let failure_mode = std::env::var("HOOK_FAILURE_MODE").ok()
+            .or(file.failure_mode)
+            .and_then(|mode| mode.parse()
+                .map_err(|e| tracing::warn!("⚠️ [Hooks] Ignoring failure mode: {}", e))
+                .ok())
+            .unwrap_or_default();
+
+        Self::with_settings(hooks_dir, timeout_secs, failure_mode, events)
+    }
+
+    /// Builds a manager for `hooks_dir` with explicit settings, bypassing env and config file.
+    pub fn with_settings(
+        hooks_dir: PathBuf,
+        timeout_secs: u64,
+        failure_mode: HookFailureMode,
+        events: Option<broadcast::Sender<serde_json::Value>>,
+    ) -> Self {
+        Self { hooks_dir, timeout_secs, failure_mode, events }
+    }
+
+    pub fn timeout_secs(&self) -> u64 {
+        self.timeout_secs
+    }
+
+    pub fn failure_mode(&self) -> HookFailureMode {
+        self.failure_mode
     }
 
     /// Executes all scripts in the given hook subdirectory.
-    /// Returns an error if any script fails.
+    /// A script that fails returns an error in `Abort` mode; one that runs past the timeout is
+    /// killed and only logged, whatever the mode.
     pub async fn trigger_hook(&self, hook_type: &str, ctx: &HookContext, params: &serde_json::Value) -> anyhow::Result<()> {
         let dir = self.hooks_dir.join(hook_type);
         if !dir.exists() {
@@ -32,14 +116,43 @@ impl HooksManager {
         let mut entries = tokio::fs::read_dir(dir).await?;
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
-            if self.is_executable(&path) {
-                self.run_script(&path, ctx, params).await?;
+            if !self.is_executable(&path) {
+                continue;
+            }
+
+            let timeout = Duration::from_secs(self.timeout_secs);
+            match tokio::time::timeout(timeout, self.run_script(&path, ctx, params)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) if self.failure_mode == HookFailureMode::Abort => return Err(e),
+                Ok(Err(e)) => {
+                    tracing::error!("🪝 [Hooks] {} hook failed for agent {}: {}", hook_type, ctx.agent_id, e);
+                    self.report_failure(hook_type, &path, ctx, &e.to_string());
+                }
+                Err(_) => {
+                    let reason = format!("timed out after {}s", self.timeout_secs);
+                    tracing::warn!("🪝 [Hooks] {} hook {} {}; continuing", hook_type, path.display(), reason);
+                    self.report_failure(hook_type, &path, ctx, &reason);
+                }
             }
         }
 
         Ok(())
     }
 
+    fn report_failure(&self, hook_type: &str, path: &Path, ctx: &HookContext, error: &str) {
+        if let Some(events) = &self.events {
+            let _ = events.send(serde_json::json!({
+                "type": "hooks:script_failed",
+                "hook": hook_type,
+                "script": path.file_name().map(|n| n.to_string_lossy().to_string()),
+                "agentId": ctx.agent_id,
+                "missionId": ctx.mission_id,
+                "skill": ctx.skill,
+                "error": error
+            }));
+        }
+    }
+
     fn is_executable(&self, path: &Path) -> bool {
         let ext = path.extension().and_then(|e| e.to_str());
         #[cfg(windows)]
@@ -63,6 +176,8 @@ impl HooksManager {
         let output = cmd
             .env("AGENT_CONTEXT", ctx_json)
             .env("TOOL_PARAMS", params_json)
+            // A script abandoned by the timeout must not outlive it
+            .kill_on_drop(true)
             .output()
             .await?;
 
@@ -74,3 +189,67 @@ impl HooksManager {
         Ok(())
     }
 }
+
+/// Reads the hooks config file. A missing file yields defaults; an invalid one is logged and ignored.
+fn load_config(path: &Path) -> HooksConfig {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return HooksConfig::default();
+    };
+    toml::from_str(&text)
+        .unwrap_or_else(|e| {
+            tracing::warn!("⚠️ [Hooks] Ignoring invalid {}: {}", path.display(), e);
+            HooksConfig::default()
+        })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn write_script(dir: &Path, name: &str, body: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fn ctx() -> HookContext {
+        HookContext { agent_id: "1".to_string(), mission_id: Some("m1".to_string()), skill: "read_file".to_string() }
+    }
+
+    #[tokio::test]
+    async fn failing_hook_aborts_or_warns_by_mode() {
+        let root = std::env::temp_dir().join(format!("tadpole_hooks_{}", uuid::Uuid::new_v4()));
+        write_script(&root.join("pre-tool"), "deny.sh", "echo denied >&2; exit 1");
+
+        let abort = HooksManager::with_settings(root.clone(), 5, HookFailureMode::Abort, None);
+        let err = abort.trigger_hook("pre-tool", &ctx(), &serde_json::json!({})).await.unwrap_err();
+        assert!(err.to_string().contains("denied"), "{}", err);
+
+        let (tx, mut rx) = broadcast::channel(8);
+        let warn = HooksManager::with_settings(root.clone(), 5, HookFailureMode::Warn, Some(tx));
+        warn.trigger_hook("pre-tool", &ctx(), &serde_json::json!({})).await.unwrap();
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event["type"], "hooks:script_failed");
+        assert_eq!(event["script"], "deny.sh");
+        assert_eq!(event["hook"], "pre-tool");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn config_file_sets_timeout_and_mode() {
+        let root = std::env::temp_dir().join(format!("tadpole_hooks_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("hooks")).unwrap();
+        std::fs::write(root.join("hooks/config.toml"), "timeout_secs = 3\nfailure_mode = \"warn\"\n").unwrap();
+
+        let config = load_config(&root.join("hooks/config.toml"));
+        assert_eq!(config.timeout_secs, Some(3));
+        assert_eq!(config.failure_mode.unwrap().parse::<HookFailureMode>().unwrap(), HookFailureMode::Warn);
+        assert!(load_config(&root.join("hooks/absent.toml")).timeout_secs.is_none());
+        assert!("ignore".parse::<HookFailureMode>().is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+/// How often `spawn_memory_expiry_job` deletes expired facts.
+pub const MEMORY_EXPIRY_INTERVAL_SECS: u64 = 300;
+/// Longest key `remember_fact` accepts, in characters.
+pub const MAX_FACT_KEY_CHARS: usize = 128;
+/// Longest value `remember_fact` accepts, in characters.
+pub const MAX_FACT_VALUE_CHARS: usize = 2000;
+
+/// Rows whose TTL has not run out. `updated_at` is a UTC `CURRENT_TIMESTAMP`.
+const NOT_EXPIRED: &str = "(ttl_secs IS NULL OR CAST(strftime('%s', updated_at) AS INTEGER) + ttl_secs >= CAST(strftime('%s', 'now') AS INTEGER))";
+
+/// One note an agent kept for later missions.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentFact {
+    pub key: String,
+    pub value: String,
+    pub updated_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl_secs: Option<i64>,
+}
+
+/// Stores `value` under `key` for `agent_id`, replacing any earlier value and restarting its TTL.
+pub async fn remember_fact(pool: &SqlitePool, agent_id: &str, key: &str, value: &str, ttl_secs: Option<i64>) -> Result<()> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(anyhow::anyhow!("'key' is required"));
+    }
+    if key.chars().count() > MAX_FACT_KEY_CHARS {
+        return Err(anyhow::anyhow!("key exceeds {} characters", MAX_FACT_KEY_CHARS));
+    }
+    if value.chars().count() > MAX_FACT_VALUE_CHARS {
+        return Err(anyhow::anyhow!("value exceeds {} characters", MAX_FACT_VALUE_CHARS));
+    }
+    if let Some(ttl) = ttl_secs.filter(|ttl| *ttl <= 0) {
+        return Err(anyhow::anyhow!("ttl_secs {} must be positive", ttl));
+    }
+
+    sqlx::query(
+        "INSERT INTO agent_memory (id, agent_id, key, value, updated_at, ttl_secs) VALUES (?1, ?2, ?3, ?4, CURRENT_TIMESTAMP, ?5)
+         ON CONFLICT(agent_id, key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at, ttl_secs = excluded.ttl_secs")
+    .bind(Uuid::new_v4().to_string())
+    .bind(agent_id)
+    .bind(key)
+    .bind(value)
+    .bind(ttl_secs)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// The value stored under `key` for `agent_id`, or `None` if there is none or it has expired.
+pub async fn recall_fact(pool: &SqlitePool, agent_id: &str, key: &str) -> Result<Option<String>> {
+    let value = sqlx::query_scalar(&format!(
+        "SELECT value FROM agent_memory WHERE agent_id = ?1 AND key = ?2 AND {}", NOT_EXPIRED))
+    .bind(agent_id)
+    .bind(key.trim())
+    .fetch_optional(pool)
+    .await?;
+    Ok(value)
+}
+
+/// Every unexpired fact of `agent_id`, ordered by key.
+pub async fn list_facts(pool: &SqlitePool, agent_id: &str) -> Result<Vec<AgentFact>> {
+    let rows = sqlx::query(&format!(
+        "SELECT key, value, updated_at, ttl_secs FROM agent_memory WHERE agent_id = ?1 AND {} ORDER BY key", NOT_EXPIRED))
+    .bind(agent_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(|row| AgentFact {
+        key: row.get("key"),
+        value: row.get("value"),
+        updated_at: row.get("updated_at"),
+        ttl_secs: row.get("ttl_secs"),
+    }).collect())
+}
+
+/// Deletes one fact. Returns whether it existed.
+pub async fn forget_fact(pool: &SqlitePool, agent_id: &str, key: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM agent_memory WHERE agent_id = ?1 AND key = ?2")
+        .bind(agent_id)
+        .bind(key)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Deletes every fact whose TTL has run out. Returns how many were removed.
+pub async fn purge_expired_facts(pool: &SqlitePool) -> Result<u64> {
+    let result = sqlx::query(&format!("DELETE FROM agent_memory WHERE NOT {}", NOT_EXPIRED))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Renders facts as the `AGENT MEMORY` lines of a system prompt.
+pub fn format_facts(facts: &[AgentFact]) -> String {
+    facts.iter().map(|fact| format!("- {}: {}", fact.key, fact.value)).collect::<Vec<_>>().join("\n")
+}
+
+/// Deletes expired facts every `interval`.
+pub fn spawn_memory_expiry_job(pool: SqlitePool, interval: std::time::Duration) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            match purge_expired_facts(&pool).await {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!("🧠 [Memory] Removed {} expired agent fact(s)", removed),
+                Err(e) => tracing::warn!("⚠️ [Memory] Expired fact cleanup failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn memory_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::db::run_migrations(&pool, crate::db::MIGRATIONS).await.unwrap();
+        pool
+    }
+
+    /// Moves a fact's `updated_at` into the past as if it was written `secs` ago.
+    async fn age_fact(pool: &SqlitePool, agent_id: &str, key: &str, secs: i64) {
+        sqlx::query("UPDATE agent_memory SET updated_at = datetime('now', ?1) WHERE agent_id = ?2 AND key = ?3")
+            .bind(format!("-{} seconds", secs))
+            .bind(agent_id)
+            .bind(key)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn facts_are_upserted_and_recalled_per_agent() {
+        let pool = memory_pool().await;
+        remember_fact(&pool, "agent-1", "customer", "Acme Corp", None).await.unwrap();
+        remember_fact(&pool, "agent-1", "customer", "Globex", None).await.unwrap();
+        remember_fact(&pool, "agent-2", "customer", "Initech", None).await.unwrap();
+
+        assert_eq!(recall_fact(&pool, "agent-1", "customer").await.unwrap().as_deref(), Some("Globex"));
+        assert_eq!(recall_fact(&pool, "agent-2", "customer").await.unwrap().as_deref(), Some("Initech"));
+        assert_eq!(recall_fact(&pool, "agent-1", "unknown").await.unwrap(), None);
+        assert_eq!(list_facts(&pool, "agent-1").await.unwrap().len(), 1);
+
+        assert!(remember_fact(&pool, "agent-1", " ", "v", None).await.is_err());
+        assert!(remember_fact(&pool, "agent-1", "k", "v", Some(0)).await.is_err());
+
+        assert!(forget_fact(&pool, "agent-1", "customer").await.unwrap());
+        assert!(!forget_fact(&pool, "agent-1", "customer").await.unwrap());
+        assert_eq!(recall_fact(&pool, "agent-1", "customer").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn expired_facts_are_hidden_and_purged() {
+        let pool = memory_pool().await;
+        remember_fact(&pool, "agent-1", "session", "temporary", Some(60)).await.unwrap();
+        remember_fact(&pool, "agent-1", "fresh", "still valid", Some(3600)).await.unwrap();
+        remember_fact(&pool, "agent-1", "forever", "permanent", None).await.unwrap();
+        age_fact(&pool, "agent-1", "session", 120).await;
+        age_fact(&pool, "agent-1", "forever", 10 * 365 * 86_400).await;
+
+        assert_eq!(recall_fact(&pool, "agent-1", "session").await.unwrap(), None, "expired facts are not recalled before the purge runs");
+        let keys: Vec<String> = list_facts(&pool, "agent-1").await.unwrap().into_iter().map(|f| f.key).collect();
+        assert_eq!(keys, vec!["forever", "fresh"]);
+
+        assert_eq!(purge_expired_facts(&pool).await.unwrap(), 1);
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM agent_memory").fetch_one(&pool).await.unwrap();
+        assert_eq!(remaining, 2);
+
+        // Re-remembering restarts the TTL
+        remember_fact(&pool, "agent-1", "session", "renewed", Some(60)).await.unwrap();
+        assert_eq!(recall_fact(&pool, "agent-1", "session").await.unwrap().as_deref(), Some("renewed"));
+    }
+}
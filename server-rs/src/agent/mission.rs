@@ -2,11 +2,23 @@ use sqlx::SqlitePool;
 use anyhow::Result;
 use uuid::Uuid;
 use chrono::Utc;
-use sqlx::Row;
-use crate::agent::types::{Mission, MissionStatus, MissionLog};
+use sqlx::{QueryBuilder, Row, Sqlite};
+use crate::agent::types::{ConversationTurn, FindingVerdict, Mission, MissionExport, MissionFinding, MissionStatus, MissionLog, OversightRecord, PagedResult};
+use crate::state::AppState;
+use std::collections::HashSet;
+use tokio::sync::broadcast;
+
+/// Trims tags, drops empty ones and removes duplicates, keeping first-seen order.
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags.iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.clone()))
+        .collect()
+}
 
 /// Creates a new mission in the database.
-pub async fn create_mission(pool: &SqlitePool, agent_id: &str, title: &str, budget_usd: f64) -> Result<Mission> {
+pub async fn create_mission(pool: &SqlitePool, agent_id: &str, title: &str, budget_usd: f64, tags: &[String]) -> Result<Mission> {
     let mission_id = Uuid::new_v4().to_string();
     let now = Utc::now();
     
@@ -19,6 +31,9 @@ pub async fn create_mission(pool: &SqlitePool, agent_id: &str, title: &str, budg
         updated_at: now,
         budget_usd,
         cost_usd: 0.0,
+        prerequisite_mission_id: None,
+        tags: normalize_tags(tags),
+        parent_mission_id: None,
     };
 
     // Diagnostic check: Does the agent exist?
@@ -32,8 +47,8 @@ pub async fn create_mission(pool: &SqlitePool, agent_id: &str, title: &str, budg
     }
 
     sqlx::query(
-        "INSERT INTO mission_history (id, agent_id, title, status, budget_usd, cost_usd, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")
+        "INSERT INTO mission_history (id, agent_id, title, status, budget_usd, cost_usd, created_at, updated_at, tags)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)")
     .bind(&mission.id)
     .bind(&mission.agent_id)
     .bind(&mission.title)
@@ -42,12 +57,26 @@ pub async fn create_mission(pool: &SqlitePool, agent_id: &str, title: &str, budg
     .bind(mission.cost_usd)
     .bind(mission.created_at)
     .bind(mission.updated_at)
+    .bind(serde_json::to_string(&mission.tags)?)
     .execute(pool)
     .await?;
 
     Ok(mission)
 }
 
+/// Replaces a mission's tags. Returns the stored (normalized) tags, or `None` if the mission
+/// does not exist.
+pub async fn update_mission_tags(pool: &SqlitePool, mission_id: &str, tags: &[String]) -> Result<Option<Vec<String>>> {
+    let tags = normalize_tags(tags);
+    let result = sqlx::query("UPDATE mission_history SET tags = ?1, updated_at = ?2 WHERE id = ?3")
+        .bind(serde_json::to_string(&tags)?)
+        .bind(Utc::now())
+        .bind(mission_id)
+        .execute(pool)
+        .await?;
+    Ok((result.rows_affected() > 0).then_some(tags))
+}
+
 /// Updates mission status and cost.
 pub async fn update_mission(pool: &SqlitePool, mission_id: &str, status: MissionStatus, cost_usd: f64) -> Result<()> {
     let status_str = status_to_str(&status);
@@ -105,6 +134,43 @@ pub async fn log_step(
     })
 }
 
+/// Updates a mission and notifies connected dashboards with a `mission:status` event.
+pub async fn update_mission_and_broadcast(state: &AppState, mission_id: &str, status: MissionStatus, cost_usd: f64) -> Result<()> {
+    update_mission(&state.pool, mission_id, status, cost_usd).await?;
+
+    // Re-read so the event carries the owning agent and the cumulative cost
+    if let Some(mission) = get_mission_by_id(&state.pool, mission_id).await? {
+        state.emit_event(serde_json::json!({
+            "type": "mission:status",
+            "missionId": mission_id,
+            "status": status_to_str(&status),
+            "costUsd": mission.cost_usd,
+            "agentId": mission.agent_id
+        }));
+    }
+    Ok(())
+}
+
+/// Logs a mission step and notifies connected dashboards with a `mission:step` event.
+pub async fn log_step_and_broadcast(
+    state: &AppState,
+    mission_id: &str,
+    agent_id: &str,
+    source: &str,
+    text: &str,
+    severity: &str,
+    metadata: Option<serde_json::Value>
+) -> Result<MissionLog> {
+    let log = log_step(&state.pool, mission_id, agent_id, source, text, severity, metadata).await?;
+    state.emit_event(serde_json::json!({
+        "type": "mission:step",
+        "missionId": mission_id,
+        "agentId": agent_id,
+        "step": log
+    }));
+    Ok(log)
+}
+
 #[allow(dead_code)]
 pub async fn get_last_active_mission(pool: &SqlitePool, agent_id: &str) -> Result<Option<Mission>> {
     let row = sqlx::query(
@@ -116,39 +182,323 @@ pub async fn get_last_active_mission(pool: &SqlitePool, agent_id: &str) -> Resul
     Ok(row.map(|r| row_to_mission(&r)))
 }
 
-/// Shares a finding to the swarm context bus.
-pub async fn share_finding(pool: &SqlitePool, mission_id: &str, agent_id: &str, topic: &str, finding: &str) -> Result<()> {
+/// Shares a finding to the swarm context bus. `confidence`, if given, must be within 0.0–1.0.
+/// Returns the new finding's id.
+pub async fn share_finding(
+    pool: &SqlitePool,
+    mission_id: &str,
+    agent_id: &str,
+    topic: &str,
+    finding: &str,
+    confidence: Option<f32>,
+) -> Result<String> {
+    if let Some(c) = confidence.filter(|c| !(0.0..=1.0).contains(c)) {
+        return Err(anyhow::anyhow!("confidence {} is outside 0.0–1.0", c));
+    }
     let id = Uuid::new_v4().to_string();
     sqlx::query(
-        "INSERT INTO swarm_context (id, mission_id, agent_id, topic, finding) VALUES (?1, ?2, ?3, ?4, ?5)")
-    .bind(id)
+        "INSERT INTO swarm_context (id, mission_id, agent_id, topic, finding, confidence) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
+    .bind(&id)
     .bind(mission_id)
     .bind(agent_id)
     .bind(topic)
     .bind(finding)
+    .bind(confidence)
     .execute(pool)
     .await?;
-    Ok(())
+    Ok(id)
+}
+
+/// Looks up one finding of a mission. Context summaries are not findings and are never returned.
+pub async fn get_finding(pool: &SqlitePool, mission_id: &str, finding_id: &str) -> Result<Option<MissionFinding>> {
+    let row = sqlx::query(
+        "SELECT * FROM swarm_context WHERE mission_id = ?1 AND id = ?2 AND summary_through IS NULL")
+    .bind(mission_id)
+    .bind(finding_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.as_ref().map(row_to_finding))
 }
 
-/// Retrieves all findings for a mission to provide context to an agent.
+/// Records `verifier`'s verdict on a finding, replacing any earlier one.
+/// Returns the updated finding, or `None` if the mission has no such finding.
+pub async fn verify_finding(
+    pool: &SqlitePool,
+    mission_id: &str,
+    finding_id: &str,
+    verifier: &str,
+    verdict: FindingVerdict,
+) -> Result<Option<MissionFinding>> {
+    let updated = sqlx::query(
+        "UPDATE swarm_context SET verdict = ?1, verified_by = ?2, verified_at = ?3
+         WHERE mission_id = ?4 AND id = ?5 AND summary_through IS NULL")
+    .bind(verdict.as_str())
+    .bind(verifier)
+    .bind(Utc::now())
+    .bind(mission_id)
+    .bind(finding_id)
+    .execute(pool)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Ok(None);
+    }
+    get_finding(pool, mission_id, finding_id).await
+}
+
+/// Every finding of a mission, oldest first, without context summaries.
+pub async fn list_findings(pool: &SqlitePool, mission_id: &str) -> Result<Vec<MissionFinding>> {
+    let rows = sqlx::query(
+        "SELECT * FROM swarm_context WHERE mission_id = ?1 AND summary_through IS NULL ORDER BY timestamp ASC, rowid ASC")
+    .bind(mission_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(row_to_finding).collect())
+}
+
+/// One finding as it appears in an agent's prompt, with the id `verify_finding` takes and the
+/// confidence the swarm should give it.
+fn context_line(finding: &MissionFinding) -> String {
+    let confidence = match finding.effective_confidence() {
+        Some(c) => format!("confidence {:.2}", c),
+        None => "confidence unrated".to_string(),
+    };
+    let verification = match (finding.verdict, &finding.verified_by) {
+        (Some(FindingVerdict::Confirmed), Some(by)) => format!(", confirmed by {}", by),
+        (Some(FindingVerdict::Disputed), Some(by)) => match finding.confidence {
+            Some(reported) => format!(", DISPUTED by {} (reported {:.2})", by, reported),
+            None => format!(", DISPUTED by {}", by),
+        },
+        _ => String::new(),
+    };
+    format!(
+        "[Context from {} on {} | id {} | {}{}]: {}\n",
+        finding.agent_id, finding.topic, finding.id, confidence, verification, finding.finding
+    )
+}
+
+/// Characters of swarm context an agent's prompt may carry before it is summarized,
+/// unless `MAX_CONTEXT_CHARS` says otherwise.
+pub const DEFAULT_MAX_CONTEXT_CHARS: usize = 8000;
+
+/// Topic of the finding that stores a mission's condensed context.
+pub const CONTEXT_SUMMARY_TOPIC: &str = "[CONTEXT SUMMARY]";
+
+/// Retrieves the findings for a mission to provide context to an agent. Once the context has
+/// been summarized, the latest summary stands in for every finding it covers.
 pub async fn get_mission_context(pool: &SqlitePool, mission_id: &str) -> Result<String> {
+    let summary = sqlx::query(
+        "SELECT finding, summary_through FROM swarm_context
+         WHERE mission_id = ?1 AND summary_through IS NOT NULL
+         ORDER BY summary_through DESC LIMIT 1")
+    .bind(mission_id)
+    .fetch_optional(pool)
+    .await?;
+    let covered: i64 = summary.as_ref().map(|r| r.get("summary_through")).unwrap_or(0);
+
     let rows = sqlx::query(
-        "SELECT agent_id, topic, finding FROM swarm_context WHERE mission_id = ?1 ORDER BY timestamp ASC")
+        "SELECT * FROM swarm_context
+         WHERE mission_id = ?1 AND summary_through IS NULL AND rowid > ?2
+         ORDER BY timestamp ASC, rowid ASC")
     .bind(mission_id)
+    .bind(covered)
     .fetch_all(pool)
     .await?;
 
     let mut context = String::new();
-    for row in rows {
-        let agent_id_row: String = row.get("agent_id");
-        let topic: String = row.get("topic");
-        let finding: String = row.get("finding");
-        context.push_str(&format!("[Context from {} on {}]: {}\n", agent_id_row, topic, finding));
+    if let Some(summary) = summary {
+        let text: String = summary.get("finding");
+        context.push_str(&format!(
+            "{} (condensed from earlier findings; details may be omitted):\n{}\n", CONTEXT_SUMMARY_TOPIC, text.trim()
+        ));
+    }
+    for row in &rows {
+        context.push_str(&context_line(&row_to_finding(row)));
     }
     Ok(context)
 }
 
+/// Condenses a mission's swarm context with `summarize`, which receives the current context and
+/// returns the summary, and stores the result as a `[CONTEXT SUMMARY]` finding so later steps
+/// reuse it. Findings shared while `summarize` runs are not covered and stay verbatim.
+/// Returns the mission context as it reads afterwards; `summarize` is not called when no finding
+/// has arrived since the last summary.
+pub async fn summarize_context<F, Fut>(pool: &SqlitePool, mission_id: &str, summarize: F) -> Result<String>
+where
+    F: FnOnce(String) -> Fut,
+    Fut: std::future::Future<Output = Result<String>>,
+{
+    let row = sqlx::query(
+        "SELECT COALESCE(MAX(CASE WHEN summary_through IS NULL THEN rowid END), 0) AS latest,
+                COALESCE(MAX(summary_through), 0) AS covered
+         FROM swarm_context WHERE mission_id = ?1")
+    .bind(mission_id)
+    .fetch_one(pool)
+    .await?;
+    let (latest, covered): (i64, i64) = (row.get("latest"), row.get("covered"));
+    if latest <= covered {
+        return get_mission_context(pool, mission_id).await;
+    }
+
+    let context = get_mission_context(pool, mission_id).await?;
+    let summary = summarize(context).await?;
+    if summary.trim().is_empty() {
+        return Err(anyhow::anyhow!("context summary for mission {} came back empty", mission_id));
+    }
+
+    // Only findings read above are covered, so anything shared meanwhile stays verbatim
+    sqlx::query(
+        "INSERT INTO swarm_context (id, mission_id, agent_id, topic, finding, summary_through)
+         VALUES (?1, ?2, 'system', ?3, ?4, ?5)")
+    .bind(Uuid::new_v4().to_string())
+    .bind(mission_id)
+    .bind(CONTEXT_SUMMARY_TOPIC)
+    .bind(summary.trim())
+    .bind(latest)
+    .execute(pool)
+    .await?;
+
+    get_mission_context(pool, mission_id).await
+}
+
+/// Upper bound on findings returned by one `search_findings` call.
+pub const MAX_FINDING_SEARCH_RESULTS: i64 = 50;
+
+/// Filters for `search_findings`. Unset fields match everything.
+#[derive(Debug, Default)]
+pub struct FindingSearch {
+    /// Substring of the finding text (LIKE, so ASCII case-insensitive).
+    pub query: Option<String>,
+    pub agent_id: Option<String>,
+    pub topic: Option<String>,
+}
+
+/// Searches a mission's swarm context, oldest first, capped at `MAX_FINDING_SEARCH_RESULTS`.
+pub async fn search_findings(pool: &SqlitePool, mission_id: &str, search: &FindingSearch) -> Result<Vec<MissionFinding>> {
+    let mut qb = sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT * FROM swarm_context WHERE mission_id = ");
+    qb.push_bind(mission_id);
+    if let Some(query) = search.query.as_deref().filter(|q| !q.is_empty()) {
+        qb.push(" AND finding LIKE ").push_bind(format!("%{}%", escape_like(query))).push(" ESCAPE '\\'");
+    }
+    if let Some(agent_id) = &search.agent_id {
+        qb.push(" AND agent_id = ").push_bind(agent_id);
+    }
+    if let Some(topic) = &search.topic {
+        qb.push(" AND topic = ").push_bind(topic);
+    }
+    qb.push(" ORDER BY timestamp ASC, rowid ASC LIMIT ").push_bind(MAX_FINDING_SEARCH_RESULTS);
+
+    let rows = qb.build().fetch_all(pool).await?;
+    Ok(rows.iter().map(row_to_finding).collect())
+}
+
+/// Rows returned by one `query_missions_db` call; extra rows are dropped.
+pub const MAX_ANALYTICS_ROWS: usize = 100;
+/// Wall-clock budget for one `query_missions_db` query.
+pub const ANALYTICS_QUERY_TIMEOUT_SECS: u64 = 10;
+/// The only tables an analytics query can see, with the columns copied into its snapshot.
+/// Callback URLs and their signing secrets are deliberately left out.
+const ANALYTICS_TABLES: [(&str, &str); 3] = [
+    ("mission_history", "id, agent_id, title, status, created_at, updated_at, budget_usd, cost_usd, prerequisite_mission_id, tags, parent_mission_id"),
+    ("mission_logs", "id, mission_id, agent_id, source, text, severity, timestamp, metadata"),
+    ("swarm_context", "id, mission_id, agent_id, topic, finding, timestamp, summary_through, confidence, verdict, verified_by, verified_at"),
+];
+/// Most recent rows per table copied into an analytics snapshot, bounding its size.
+pub const ANALYTICS_SNAPSHOT_ROWS: i64 = 10_000;
+/// Keywords that reject an analytics query wherever they appear, case-insensitively.
+const ANALYTICS_DENIED_KEYWORDS: [&str; 12] = [
+    "INSERT", "UPDATE", "DELETE", "DROP", "ALTER", "ATTACH", "DETACH", "CREATE", "REPLACE", "PRAGMA", "VACUUM", "REINDEX",
+];
+
+/// Checks an agent-written analytics query: a single `SELECT` (or `WITH ... SELECT`) with no
+/// `;` and none of `ANALYTICS_DENIED_KEYWORDS` as a word. Returns why the query is refused.
+pub fn validate_analytics_sql(sql: &str) -> std::result::Result<(), String> {
+    if sql.trim().is_empty() {
+        return Err("query must not be empty".to_string());
+    }
+    if sql.contains(';') {
+        return Err("compound statements (';') are not allowed".to_string());
+    }
+    let words: Vec<String> = sql
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_ascii_uppercase())
+        .collect();
+    if let Some(denied) = words.iter().find(|w| ANALYTICS_DENIED_KEYWORDS.contains(&w.as_str())) {
+        return Err(format!("'{}' is not allowed in analytics queries", denied));
+    }
+    match words.first().map(String::as_str) {
+        Some("SELECT") | Some("WITH") => Ok(()),
+        _ => Err("only SELECT queries are allowed".to_string()),
+    }
+}
+
+/// Runs a validated analytics query and returns up to `MAX_ANALYTICS_ROWS` rows as JSON objects.
+///
+/// The query never touches the live database: the newest `ANALYTICS_SNAPSHOT_ROWS` rows of each
+/// analytics table are copied into a private in-memory connection through a short-lived
+/// `ATTACH`, which is detached before the agent's SQL runs. A progress handler aborts the copy
+/// and the query together once `ANALYTICS_QUERY_TIMEOUT_SECS` elapse.
+pub async fn query_missions_readonly(pool: &SqlitePool, sql: &str) -> Result<Vec<serde_json::Value>> {
+    use futures::TryStreamExt;
+    use sqlx::{Column, Connection, ValueRef};
+
+    validate_analytics_sql(sql).map_err(|e| anyhow::anyhow!(e))?;
+
+    let source = (*pool.connect_options()).clone().get_filename().to_string_lossy()
+        .replace('%', "%25").replace('?', "%3f").replace('#', "%23").replace('\\', "/");
+    // A URI so the snapshot source is opened read-only; Windows drive paths need a leading '/'
+    let source = if source.as_bytes().get(1) == Some(&b':') { format!("file:/{}?mode=ro", source) } else { format!("file:{}?mode=ro", source) };
+    let mut conn = sqlx::SqliteConnection::connect("sqlite::memory:").await?;
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(ANALYTICS_QUERY_TIMEOUT_SECS);
+    // Returning false interrupts the statement with SQLITE_INTERRUPT
+    conn.lock_handle().await?.set_progress_handler(1_000, move || std::time::Instant::now() < deadline);
+    let timed_out = || anyhow::anyhow!("analytics query exceeded {} seconds", ANALYTICS_QUERY_TIMEOUT_SECS);
+
+    sqlx::query("ATTACH DATABASE ? AS src").bind(&source).execute(&mut conn).await?;
+    for (table, columns) in ANALYTICS_TABLES {
+        let copy = format!("CREATE TABLE main.{table} AS SELECT {columns} FROM src.{table} ORDER BY rowid DESC LIMIT {ANALYTICS_SNAPSHOT_ROWS}");
+        if let Err(e) = sqlx::query(&copy).execute(&mut conn).await {
+            return Err(if std::time::Instant::now() >= deadline { timed_out() } else { e.into() });
+        }
+    }
+    sqlx::query("DETACH DATABASE src").execute(&mut conn).await?;
+
+    let fetch = async {
+        let mut rows = Vec::new();
+        let mut stream = sqlx::query(sql).fetch(&mut conn);
+        while let Some(row) = stream.try_next().await? {
+            let mut object = serde_json::Map::new();
+            for column in row.columns() {
+                let raw = row.try_get_raw(column.ordinal())?;
+                let value = if raw.is_null() {
+                    serde_json::Value::Null
+                } else {
+                    match raw.type_info().to_string().as_str() {
+                        "INTEGER" => serde_json::json!(row.try_get::<i64, _>(column.ordinal())?),
+                        "REAL" => serde_json::json!(row.try_get::<f64, _>(column.ordinal())?),
+                        "BLOB" => serde_json::json!(hex::encode(row.try_get::<Vec<u8>, _>(column.ordinal())?)),
+                        _ => serde_json::json!(row.try_get::<String, _>(column.ordinal())?),
+                    }
+                };
+                object.insert(column.name().to_string(), value);
+            }
+            rows.push(serde_json::Value::Object(object));
+            if rows.len() == MAX_ANALYTICS_ROWS {
+                break;
+            }
+        }
+        Ok::<_, anyhow::Error>(rows)
+    };
+    // Backstop for time spent outside SQLite's VM, where the progress handler is not consulted
+    match tokio::time::timeout(std::time::Duration::from_secs(ANALYTICS_QUERY_TIMEOUT_SECS + 1), fetch).await {
+        Ok(Ok(rows)) => Ok(rows),
+        Ok(Err(_)) | Err(_) if std::time::Instant::now() >= deadline => Err(timed_out()),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(timed_out()),
+    }
+}
+
 /// Retrieves a mission by its ID.
 pub async fn get_mission_by_id(pool: &SqlitePool, mission_id: &str) -> Result<Option<Mission>> {
     let row = sqlx::query(
@@ -160,6 +510,330 @@ pub async fn get_mission_by_id(pool: &SqlitePool, mission_id: &str) -> Result<Op
     Ok(row.map(|r| row_to_mission(&r)))
 }
 
+/// Records that `mission_id` waits on `prerequisite_id`.
+pub async fn set_prerequisite(pool: &SqlitePool, mission_id: &str, prerequisite_id: &str) -> Result<()> {
+    sqlx::query("UPDATE mission_history SET prerequisite_mission_id = ?1 WHERE id = ?2")
+        .bind(prerequisite_id)
+        .bind(mission_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Records that `mission_id` is a retry of `parent_id`.
+pub async fn set_parent_mission(pool: &SqlitePool, mission_id: &str, parent_id: &str) -> Result<()> {
+    sqlx::query("UPDATE mission_history SET parent_mission_id = ?1 WHERE id = ?2")
+        .bind(parent_id)
+        .bind(mission_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Stores where `mission_id`'s outcome is POSTed, and the key it is signed with.
+pub async fn set_callback(pool: &SqlitePool, mission_id: &str, url: &str, secret: Option<&str>) -> Result<()> {
+    sqlx::query("UPDATE mission_history SET callback_url = ?1, callback_secret = ?2 WHERE id = ?3")
+        .bind(url)
+        .bind(secret)
+        .bind(mission_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The mission's callback URL and secret, if it was started with a `callbackUrl`.
+pub async fn get_callback(pool: &SqlitePool, mission_id: &str) -> Result<Option<(String, Option<String>)>> {
+    let row: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT callback_url, callback_secret FROM mission_history WHERE id = ?1")
+        .bind(mission_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.and_then(|(url, secret)| url.map(|url| (url, secret))))
+}
+
+/// The first message the user sent to a mission, i.e. the task it was started with.
+pub async fn get_original_message(pool: &SqlitePool, mission_id: &str) -> Result<Option<String>> {
+    let text = sqlx::query_scalar(
+        "SELECT text FROM mission_logs WHERE mission_id = ?1 AND source = 'User' ORDER BY timestamp ASC, rowid ASC LIMIT 1")
+    .bind(mission_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(text)
+}
+
+/// Blocked missions (still `pending`) whose prerequisite has since completed.
+pub async fn get_unblocked_missions(pool: &SqlitePool) -> Result<Vec<Mission>> {
+    let rows = sqlx::query(
+        "SELECT m.* FROM mission_history m
+         JOIN mission_history p ON p.id = m.prerequisite_mission_id
+         WHERE m.status = 'pending' AND p.status = 'completed'
+         ORDER BY m.created_at")
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(row_to_mission).collect())
+}
+
+/// Returns missions that became unblocked since the last call, remembering them in `notified`
+/// so each is reported once. Missions that leave the unblocked set are forgotten.
+pub async fn poll_unblocked(pool: &SqlitePool, notified: &mut HashSet<String>) -> Result<Vec<Mission>> {
+    let unblocked = get_unblocked_missions(pool).await?;
+    notified.retain(|id| unblocked.iter().any(|m| &m.id == id));
+    Ok(unblocked.into_iter().filter(|m| notified.insert(m.id.clone())).collect())
+}
+
+/// How often `spawn_unblock_watcher` checks blocked missions.
+pub const UNBLOCK_POLL_INTERVAL_SECS: u64 = 30;
+
+/// Polls for blocked missions whose prerequisite completed and emits `mission:unblocked` for each,
+/// so the operator can re-trigger them. Blocked missions are never started automatically.
+pub fn spawn_unblock_watcher(pool: SqlitePool, event_tx: broadcast::Sender<serde_json::Value>, interval: std::time::Duration) {
+    tokio::spawn(async move {
+        let mut notified = HashSet::new();
+        loop {
+            tokio::time::sleep(interval).await;
+            match poll_unblocked(&pool, &mut notified).await {
+                Ok(missions) => {
+                    for mission in missions {
+                        tracing::info!("🔓 Mission {} unblocked: prerequisite completed", mission.id);
+                        let _ = event_tx.send(serde_json::json!({
+                            "type": "mission:unblocked",
+                            "missionId": mission.id,
+                            "agentId": mission.agent_id,
+                            "prerequisiteMissionId": mission.prerequisite_mission_id,
+                        }));
+                    }
+                }
+                Err(e) => tracing::warn!("⚠️ Blocked mission poll failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Share of an agent's budget that one day's spend may reach before `system:budget_warning` fires.
+pub const DEFAULT_BUDGET_ALERT_THRESHOLD_PCT: f64 = 80.0;
+
+/// One agent's mission spend over one day (or, in weekly reports, the week starting on `date`).
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct CostSummary {
+    /// `YYYY-MM-DD`, UTC.
+    pub date: String,
+    pub agent_id: String,
+    pub total_cost_usd: f64,
+    pub mission_count: i64,
+}
+
+/// Sums mission cost per agent and UTC creation date for missions created in `[since, until)`.
+pub async fn compute_cost_summary(
+    pool: &SqlitePool,
+    since: chrono::DateTime<Utc>,
+    until: chrono::DateTime<Utc>,
+) -> Result<Vec<CostSummary>> {
+    let rows: Vec<(String, String, f64, i64)> = sqlx::query_as(
+        "SELECT DATE(created_at) AS day, agent_id, COALESCE(SUM(cost_usd), 0.0), COUNT(*)
+         FROM mission_history WHERE created_at >= ?1 AND created_at < ?2
+         GROUP BY day, agent_id ORDER BY day, agent_id")
+    .bind(since)
+    .bind(until)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter()
+        .map(|(date, agent_id, total_cost_usd, mission_count)| CostSummary { date, agent_id, total_cost_usd, mission_count })
+        .collect())
+}
+
+/// Folds daily summaries into weeks, each dated by its Monday.
+pub fn weekly_cost_summary(daily: &[CostSummary]) -> Vec<CostSummary> {
+    use chrono::Datelike;
+    let mut weeks: std::collections::BTreeMap<(String, String), (f64, i64)> = std::collections::BTreeMap::new();
+    for day in daily {
+        let week = chrono::NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+            .map(|d| (d - chrono::Duration::days(d.weekday().num_days_from_monday() as i64)).to_string())
+            .unwrap_or_else(|_| day.date.clone());
+        let totals = weeks.entry((week, day.agent_id.clone())).or_default();
+        totals.0 += day.total_cost_usd;
+        totals.1 += day.mission_count;
+    }
+    weeks.into_iter()
+        .map(|((date, agent_id), (total_cost_usd, mission_count))| CostSummary { date, agent_id, total_cost_usd, mission_count })
+        .collect()
+}
+
+/// Computes and stores `day`'s cost summaries, replacing any earlier run for the same day, and
+/// emits `system:budget_warning` for each agent whose spend that day reached `threshold_pct`
+/// of its budget.
+pub async fn record_daily_costs(
+    pool: &SqlitePool,
+    event_tx: &broadcast::Sender<serde_json::Value>,
+    day: chrono::NaiveDate,
+    threshold_pct: f64,
+) -> Result<Vec<CostSummary>> {
+    let since = day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let summaries = compute_cost_summary(pool, since, since + chrono::Duration::days(1)).await?;
+
+    let mut tx = pool.begin().await?;
+    for summary in &summaries {
+        sqlx::query(
+            "INSERT INTO cost_summaries (date, agent_id, total_cost_usd, mission_count, computed_at) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(date, agent_id) DO UPDATE SET total_cost_usd = excluded.total_cost_usd,
+                mission_count = excluded.mission_count, computed_at = excluded.computed_at")
+        .bind(&summary.date)
+        .bind(&summary.agent_id)
+        .bind(summary.total_cost_usd)
+        .bind(summary.mission_count)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    for summary in &summaries {
+        let budget: Option<f64> = sqlx::query_scalar("SELECT budget_usd FROM agents WHERE id = ?")
+            .bind(&summary.agent_id)
+            .fetch_optional(pool)
+            .await?
+            .flatten();
+        let Some(budget) = budget.filter(|b| *b > 0.0) else { continue };
+        let used_pct = summary.total_cost_usd / budget * 100.0;
+        if used_pct >= threshold_pct {
+            tracing::warn!("💸 [Finance] Agent {} spent ${:.2} on {} ({:.0}% of its ${:.2} budget)", summary.agent_id, summary.total_cost_usd, summary.date, used_pct, budget);
+            let _ = event_tx.send(serde_json::json!({
+                "type": "system:budget_warning",
+                "agentId": summary.agent_id,
+                "date": summary.date,
+                "dailyCostUsd": summary.total_cost_usd,
+                "budgetUsd": budget,
+                "usedPct": used_pct,
+                "thresholdPct": threshold_pct,
+            }));
+        }
+    }
+
+    Ok(summaries)
+}
+
+/// Runs `record_daily_costs` for the day just ended, every midnight UTC.
+pub fn spawn_cost_summary_job(pool: SqlitePool, event_tx: broadcast::Sender<serde_json::Value>, threshold_pct: f64) {
+    tokio::spawn(async move {
+        loop {
+            let now = Utc::now();
+            let next_midnight = (now.date_naive() + chrono::Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+            tokio::time::sleep((next_midnight - now).to_std().unwrap_or_default()).await;
+
+            let day = next_midnight.date_naive() - chrono::Duration::days(1);
+            match record_daily_costs(&pool, &event_tx, day, threshold_pct).await {
+                Ok(summaries) => tracing::info!("💸 [Finance] Recorded cost summaries for {} agents on {}", summaries.len(), day),
+                Err(e) => tracing::warn!("⚠️ Daily cost summary for {} failed: {}", day, e),
+            }
+        }
+    });
+}
+
+/// Criteria for `list_missions`. `page` is 1-based; callers clamp `per_page`.
+#[derive(Debug, Clone, Default)]
+pub struct MissionFilter {
+    pub agent_id: Option<String>,
+    pub status: Option<MissionStatus>,
+    pub since: Option<chrono::DateTime<Utc>>,
+    pub until: Option<chrono::DateTime<Utc>>,
+    /// Only missions still waiting on an incomplete prerequisite.
+    pub blocked: bool,
+    /// Only missions carrying this exact tag.
+    pub tag: Option<String>,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+/// Appends the `WHERE` criteria shared by the page query and its count. Every value is bound.
+fn push_mission_filter(qb: &mut QueryBuilder<'_, Sqlite>, filter: &MissionFilter) {
+    qb.push(" WHERE 1 = 1");
+    if let Some(agent_id) = &filter.agent_id {
+        qb.push(" AND agent_id = ").push_bind(agent_id.clone());
+    }
+    if let Some(status) = &filter.status {
+        qb.push(" AND status = ").push_bind(status_to_str(status));
+    }
+    if let Some(since) = filter.since {
+        qb.push(" AND created_at >= ").push_bind(since);
+    }
+    if let Some(until) = filter.until {
+        qb.push(" AND created_at <= ").push_bind(until);
+    }
+    if let Some(tag) = &filter.tag {
+        // Tags are a JSON array, which SQLite cannot index; match the quoted element instead.
+        // This scans the table, and LIKE folds ASCII case, so "Q3" also matches "q3".
+        let quoted = serde_json::to_string(tag).unwrap_or_default();
+        qb.push(" AND tags LIKE ").push_bind(format!("%{}%", escape_like(&quoted))).push(" ESCAPE '\\'");
+    }
+    if filter.blocked {
+        qb.push(" AND status = 'pending' AND prerequisite_mission_id IS NOT NULL AND NOT EXISTS (\
+            SELECT 1 FROM mission_history p WHERE p.id = mission_history.prerequisite_mission_id AND p.status = 'completed')");
+    }
+}
+
+/// Returns one page of missions (newest first) matching `filter`, with the total match count.
+pub async fn list_missions(pool: &SqlitePool, filter: &MissionFilter) -> Result<PagedResult<Mission>> {
+    let mut count_qb: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT COUNT(*) FROM mission_history");
+    push_mission_filter(&mut count_qb, filter);
+    let total: i64 = count_qb.build_query_scalar().fetch_one(pool).await?;
+
+    let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT * FROM mission_history");
+    push_mission_filter(&mut qb, filter);
+    qb.push(" ORDER BY created_at DESC LIMIT ").push_bind(filter.per_page)
+        .push(" OFFSET ").push_bind((filter.page - 1).max(0) * filter.per_page);
+    let rows = qb.build().fetch_all(pool).await?;
+
+    Ok(PagedResult {
+        items: rows.iter().map(row_to_mission).collect(),
+        total,
+        page: filter.page,
+        per_page: filter.per_page,
+    })
+}
+
+/// Returns one page of an agent's missions (newest first), optionally narrowed to one status.
+pub async fn list_missions_for_agent(
+    pool: &SqlitePool,
+    agent_id: &str,
+    status: Option<MissionStatus>,
+    page: i64,
+    per_page: i64,
+) -> Result<PagedResult<Mission>> {
+    let filter = MissionFilter { agent_id: Some(agent_id.to_string()), status, page, per_page, ..Default::default() };
+    list_missions(pool, &filter).await
+}
+
+/// Totals over every mission an agent has run.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct AgentMissionStats {
+    pub total_cost_usd: f64,
+    /// Summed from the token usage recorded on each mission's final step.
+    pub total_tokens: i64,
+    /// Completed missions over all missions; `0.0` for an agent with none.
+    pub success_rate: f64,
+}
+
+/// Aggregates cost, token usage and success rate across all of an agent's missions.
+pub async fn agent_mission_stats(pool: &SqlitePool, agent_id: &str) -> Result<AgentMissionStats> {
+    let (total, completed, total_cost_usd): (i64, i64, f64) = sqlx::query_as(
+        "SELECT COUNT(*), COALESCE(SUM(status = 'completed'), 0), COALESCE(SUM(cost_usd), 0.0) FROM mission_history WHERE agent_id = ?")
+    .bind(agent_id)
+    .fetch_one(pool)
+    .await?;
+    let total_tokens: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(json_extract(metadata, '$.usage.totalTokens')), 0) FROM mission_logs WHERE agent_id = ? AND json_valid(metadata)")
+    .bind(agent_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(AgentMissionStats {
+        total_cost_usd,
+        total_tokens,
+        success_rate: if total > 0 { completed as f64 / total as f64 } else { 0.0 },
+    })
+}
+
 /// Retrieves recent missions for financial auditing.
 pub async fn get_recent_missions(pool: &SqlitePool, limit: i64) -> Result<Vec<Mission>> {
     let rows = sqlx::query(
@@ -171,11 +845,162 @@ pub async fn get_recent_missions(pool: &SqlitePool, limit: i64) -> Result<Vec<Mi
     Ok(rows.iter().map(row_to_mission).collect())
 }
 
+/// Retrieves an agent's most recently updated missions.
+pub async fn get_agent_missions(pool: &SqlitePool, agent_id: &str, limit: i64) -> Result<Vec<Mission>> {
+    let rows = sqlx::query(
+        "SELECT * FROM mission_history WHERE agent_id = ?1 ORDER BY updated_at DESC LIMIT ?2")
+    .bind(agent_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(row_to_mission).collect())
+}
+
+/// Retrieves one page of a mission's step log in chronological order. `page` is 1-based.
+pub async fn get_mission_logs(pool: &SqlitePool, mission_id: &str, page: i64, per_page: i64) -> Result<PagedResult<MissionLog>> {
+    get_mission_logs_paged(pool, mission_id, page, per_page, None, None).await
+}
+
+fn push_mission_log_filter(qb: &mut QueryBuilder<'_, Sqlite>, mission_id: &str, severity: Option<&str>, source: Option<&str>) {
+    qb.push(" WHERE mission_id = ").push_bind(mission_id.to_string());
+    if let Some(severity) = severity {
+        qb.push(" AND severity = ").push_bind(severity.to_string());
+    }
+    if let Some(source) = source {
+        qb.push(" AND source = ").push_bind(source.to_string());
+    }
+}
+
+/// Like `get_mission_logs`, optionally restricted to one `severity` and/or `source`.
+/// `total` counts only the matching rows.
+pub async fn get_mission_logs_paged(
+    pool: &SqlitePool,
+    mission_id: &str,
+    page: i64,
+    per_page: i64,
+    severity_filter: Option<&str>,
+    source_filter: Option<&str>,
+) -> Result<PagedResult<MissionLog>> {
+    let mut count_qb: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT COUNT(*) FROM mission_logs");
+    push_mission_log_filter(&mut count_qb, mission_id, severity_filter, source_filter);
+    let total: i64 = count_qb.build_query_scalar().fetch_one(pool).await?;
+
+    let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT * FROM mission_logs");
+    push_mission_log_filter(&mut qb, mission_id, severity_filter, source_filter);
+    qb.push(" ORDER BY timestamp ASC, rowid ASC LIMIT ").push_bind(per_page)
+        .push(" OFFSET ").push_bind((page - 1).max(0) * per_page);
+    let rows = qb.build().fetch_all(pool).await?;
+
+    Ok(PagedResult {
+        items: rows.iter().map(row_to_mission_log).collect(),
+        total,
+        page,
+        per_page,
+    })
+}
+
+/// Upper bound on log rows included in a mission export.
+pub const MAX_EXPORT_LOG_ROWS: i64 = 50_000;
+
+/// Gathers a mission, its log (capped at `MAX_EXPORT_LOG_ROWS`) and its shared findings
+/// in a single transaction so the three parts are consistent with each other.
+pub async fn get_full_mission_export(pool: &SqlitePool, mission_id: &str) -> Result<Option<MissionExport>> {
+    let mut tx = pool.begin().await?;
+
+    let Some(mission_row) = sqlx::query("SELECT * FROM mission_history WHERE id = ?1")
+        .bind(mission_id)
+        .fetch_optional(&mut *tx)
+        .await? else {
+        return Ok(None);
+    };
+
+    // One extra row tells us whether the cap cut anything off
+    let log_rows = sqlx::query(
+        "SELECT * FROM mission_logs WHERE mission_id = ?1 ORDER BY timestamp ASC LIMIT ?2")
+    .bind(mission_id)
+    .bind(MAX_EXPORT_LOG_ROWS + 1)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let finding_rows = sqlx::query(
+        "SELECT * FROM swarm_context WHERE mission_id = ?1 ORDER BY timestamp ASC")
+    .bind(mission_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let logs_truncated = log_rows.len() as i64 > MAX_EXPORT_LOG_ROWS;
+    Ok(Some(MissionExport {
+        mission: row_to_mission(&mission_row),
+        logs: log_rows.iter().take(MAX_EXPORT_LOG_ROWS as usize).map(row_to_mission_log).collect(),
+        findings: finding_rows.iter().map(row_to_finding).collect(),
+        logs_truncated,
+    }))
+}
+
+/// Which mission logs `stream_transcript` reads.
+#[derive(Debug, Clone)]
+pub enum TranscriptScope {
+    /// Every log of one mission.
+    Mission(String),
+    /// The agent's `limit` most recent logs across all of its missions.
+    Agent { agent_id: String, limit: i64 },
+}
+
+/// Streams mission logs in chronological order, reading rows from the database as the consumer
+/// pulls them instead of collecting the transcript first. Dropping the stream stops the query.
+pub fn stream_transcript(pool: SqlitePool, scope: TranscriptScope) -> impl futures::Stream<Item = Result<MissionLog>> + Send + 'static {
+    use futures::{SinkExt, StreamExt};
+
+    let (mut tx, rx) = futures::channel::mpsc::channel(64);
+    tokio::spawn(async move {
+        let query = match &scope {
+            TranscriptScope::Mission(mission_id) => sqlx::query(
+                "SELECT * FROM mission_logs WHERE mission_id = ?1 ORDER BY timestamp ASC, rowid ASC")
+                .bind(mission_id),
+            TranscriptScope::Agent { agent_id, limit } => sqlx::query(
+                "SELECT * FROM (
+                     SELECT rowid AS seq, * FROM mission_logs WHERE agent_id = ?1
+                     ORDER BY timestamp DESC, rowid DESC LIMIT ?2
+                 ) ORDER BY timestamp ASC, seq ASC")
+                .bind(agent_id)
+                .bind(*limit),
+        };
+
+        let mut rows = query.fetch(&pool);
+        while let Some(row) = rows.next().await {
+            let entry = row.map(|r| row_to_mission_log(&r)).map_err(anyhow::Error::from);
+            // The receiver is gone once the client disconnects
+            if tx.send(entry).await.is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Returns the user and agent turns of a mission, oldest first, for replay to the provider.
+/// System and tool-source steps are left out.
+pub async fn get_conversation_history(pool: &SqlitePool, mission_id: &str) -> Result<Vec<ConversationTurn>> {
+    let rows = sqlx::query(
+        "SELECT source, text FROM mission_logs WHERE mission_id = ?1 AND source IN ('User', 'Agent') ORDER BY timestamp ASC")
+    .bind(mission_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(|row| ConversationTurn {
+        role: if row.get::<String, _>("source") == "User" { "user" } else { "assistant" }.to_string(),
+        content: row.get("text"),
+    }).collect())
+}
+
 // ─────────────────────────────────────────────────────────
 //  HELPERS  (DRY: eliminates 3× duplicated row mapping)
 // ─────────────────────────────────────────────────────────
 
-fn status_to_str(status: &MissionStatus) -> &'static str {
+pub(crate) fn status_to_str(status: &MissionStatus) -> &'static str {
     match status {
         MissionStatus::Pending => "pending",
         MissionStatus::Active => "active",
@@ -195,6 +1020,113 @@ fn str_to_status(s: &str) -> MissionStatus {
     }
 }
 
+/// Persists a decided oversight entry to `oversight_log`.
+pub async fn log_oversight_decision(pool: &SqlitePool, record: &OversightRecord) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO oversight_log (id, mission_id, agent_id, skill, params, status, created_at, decided_by, decided_at, description, department)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)")
+    .bind(&record.id)
+    .bind(&record.mission_id)
+    .bind(&record.agent_id)
+    .bind(&record.skill)
+    .bind(record.params.to_string())
+    .bind(&record.status)
+    .bind(&record.created_at)
+    .bind(&record.decided_by)
+    .bind(&record.decided_at)
+    .bind(&record.description)
+    .bind(&record.department)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Criteria for `get_oversight_history`. `page` is 1-based; callers clamp `per_page`.
+#[derive(Debug, Clone, Default)]
+pub struct OversightFilter {
+    pub agent_id: Option<String>,
+    pub skill: Option<String>,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+fn push_oversight_filter(qb: &mut QueryBuilder<'_, Sqlite>, filter: &OversightFilter) {
+    qb.push(" WHERE 1 = 1");
+    if let Some(agent_id) = &filter.agent_id {
+        qb.push(" AND agent_id = ").push_bind(agent_id.clone());
+    }
+    if let Some(skill) = &filter.skill {
+        qb.push(" AND skill = ").push_bind(skill.clone());
+    }
+}
+
+/// Returns one page of persisted oversight decisions (newest first) matching `filter`.
+pub async fn get_oversight_history(pool: &SqlitePool, filter: &OversightFilter) -> Result<PagedResult<OversightRecord>> {
+    let mut count_qb: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT COUNT(*) FROM oversight_log");
+    push_oversight_filter(&mut count_qb, filter);
+    let total: i64 = count_qb.build_query_scalar().fetch_one(pool).await?;
+
+    let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+        "SELECT id, mission_id, agent_id, skill, params, status, decided_by, description, department,
+                CAST(created_at AS TEXT) AS created_at, CAST(decided_at AS TEXT) AS decided_at
+         FROM oversight_log");
+    push_oversight_filter(&mut qb, filter);
+    qb.push(" ORDER BY decided_at DESC, rowid DESC LIMIT ").push_bind(filter.per_page)
+        .push(" OFFSET ").push_bind((filter.page - 1).max(0) * filter.per_page);
+    let rows = qb.build().fetch_all(pool).await?;
+
+    Ok(PagedResult {
+        items: rows.iter().map(|row| OversightRecord {
+            id: row.get("id"),
+            mission_id: row.get("mission_id"),
+            agent_id: row.get("agent_id"),
+            skill: row.get("skill"),
+            params: serde_json::from_str(&row.get::<String, _>("params")).unwrap_or(serde_json::Value::Null),
+            status: row.get("status"),
+            decided_by: row.get("decided_by"),
+            description: row.get("description"),
+            department: row.get("department"),
+            created_at: row.get("created_at"),
+            decided_at: row.get("decided_at"),
+        }).collect(),
+        total,
+        page: filter.page,
+        per_page: filter.per_page,
+    })
+}
+
+fn row_to_mission_log(row: &sqlx::sqlite::SqliteRow) -> MissionLog {
+    MissionLog {
+        id: row.get("id"),
+        mission_id: row.get("mission_id"),
+        agent_id: row.get("agent_id"),
+        source: row.get("source"),
+        text: row.get("text"),
+        severity: row.get("severity"),
+        timestamp: row.get("timestamp"),
+        metadata: row.get::<Option<String>, _>("metadata").and_then(|m| serde_json::from_str(&m).ok()),
+    }
+}
+
+fn row_to_finding(row: &sqlx::sqlite::SqliteRow) -> MissionFinding {
+    MissionFinding {
+        id: row.get("id"),
+        agent_id: row.get("agent_id"),
+        topic: row.get("topic"),
+        finding: row.get("finding"),
+        timestamp: row.get("timestamp"),
+        confidence: row.get("confidence"),
+        verdict: row.get::<Option<String>, _>("verdict").and_then(|v| v.parse().ok()),
+        verified_by: row.get("verified_by"),
+        verified_at: row.get("verified_at"),
+    }
+}
+
+/// Escapes `\`, `%` and `_` for a LIKE pattern used with `ESCAPE '\'`.
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
 fn row_to_mission(row: &sqlx::sqlite::SqliteRow) -> Mission {
     let status_str: String = row.get("status");
     Mission {
@@ -206,5 +1138,13 @@ fn row_to_mission(row: &sqlx::sqlite::SqliteRow) -> Mission {
         updated_at: row.get("updated_at"),
         budget_usd: row.get("budget_usd"),
         cost_usd: row.get("cost_usd"),
+        // Absent on tables that predate migration 4
+        prerequisite_mission_id: row.try_get("prerequisite_mission_id").ok().flatten(),
+        // Absent on tables that predate migration 8
+        tags: row.try_get::<String, _>("tags").ok()
+            .and_then(|tags| serde_json::from_str(&tags).ok())
+            .unwrap_or_default(),
+        // Absent on tables that predate migration 10
+        parent_mission_id: row.try_get("parent_mission_id").ok().flatten(),
     }
 }
@@ -1,14 +1,23 @@
 pub mod gemini;
 pub mod groq;
+pub mod openai;
+pub mod ollama;
 pub mod types;
 pub mod runner;
 pub mod registry;
 pub mod hooks;
+pub mod oversight_policy;
+pub mod provider_health;
+pub mod sandbox;
 pub mod persistence;
 pub mod mission;
+pub mod memory;
 pub mod rates;
 pub mod rate_limiter;
+pub mod streaming;
+pub mod token_stats;
 #[cfg(test)]
+#[allow(unused_imports, clippy::nonminimal_bool)]
 mod tests;
 #[cfg(test)]
 mod tests_capabilities;
@@ -0,0 +1,283 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use crate::agent::types::{ConversationTurn, ModelConfig, TokenUsage, GeminiFunctionCall};
+
+/// Where `ollama serve` listens unless the provider's `base_url` says otherwise.
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+#[derive(Debug, Serialize)]
+struct OllamaMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaTool {
+    #[serde(rename = "type")]
+    tool_type: String,
+    function: OllamaFunctionDefinition,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaFunctionDefinition {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+/// Sampling settings, which Ollama takes under `options` rather than at the top level.
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    /// Ollama's name for the output token cap.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    /// `/api/chat` streams NDJSON by default; the runner wants one complete reply.
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<OllamaTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<OllamaOptions>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponseMessage {
+    #[serde(default)]
+    content: String,
+    tool_calls: Option<Vec<OllamaToolCall>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaFunctionCall {
+    name: String,
+    /// A JSON object, unlike OpenAI's JSON-encoded string. Strings are still accepted.
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: OllamaResponseMessage,
+    /// Prompt tokens evaluated; omitted when the prompt was served from cache.
+    prompt_eval_count: Option<u32>,
+    /// Tokens generated.
+    eval_count: Option<u32>,
+}
+
+/// Provider for models served by a local Ollama instance through its native `/api/chat` API.
+pub struct OllamaProvider {
+    client: Client,
+    config: ModelConfig,
+}
+
+impl OllamaProvider {
+    /// Creates an OllamaProvider with a shared `reqwest::Client`. Ollama needs no API key.
+    pub fn new(client: Client, config: ModelConfig) -> Self {
+        Self { client, config }
+    }
+
+    /// The chat endpoint for the configured `base_url`, which may be the server root or the full path.
+    fn chat_url(&self) -> String {
+        let base = self.config.base_url.as_deref().unwrap_or(DEFAULT_OLLAMA_URL).trim_end_matches('/');
+        if base.ends_with("/api/chat") {
+            base.to_string()
+        } else {
+            format!("{}/api/chat", base)
+        }
+    }
+
+    pub async fn generate(
+        &self,
+        system_prompt: &str,
+        user_message: &str,
+        history: Option<&[ConversationTurn]>,
+        tools: Option<Vec<crate::agent::gemini::GeminiTool>>,
+    ) -> anyhow::Result<(String, Vec<GeminiFunctionCall>, Option<TokenUsage>)> {
+        let ollama_tools = tools.as_ref().map(|ts| {
+            ts.iter().flat_map(|t| {
+                t.function_declarations.iter().map(|f| OllamaTool {
+                    tool_type: "function".to_string(),
+                    function: OllamaFunctionDefinition {
+                        name: f.name.clone(),
+                        description: f.description.clone(),
+                        parameters: f.parameters.clone(),
+                    },
+                })
+            }).collect::<Vec<OllamaTool>>()
+        });
+
+        let mut messages = Vec::new();
+        if !system_prompt.is_empty() {
+            messages.push(OllamaMessage { role: "system".to_string(), content: system_prompt.to_string() });
+        }
+        messages.extend(self.config.history_window(history).iter().map(|turn| OllamaMessage {
+            role: turn.role.clone(),
+            content: turn.content.clone(),
+        }));
+        messages.push(OllamaMessage { role: "user".to_string(), content: user_message.to_string() });
+
+        let options = (self.config.temperature.is_some() || self.config.max_tokens.is_some()).then_some(OllamaOptions {
+            temperature: self.config.temperature,
+            num_predict: self.config.max_tokens,
+        });
+
+        let request_body = OllamaRequest {
+            model: self.config.model_id.clone(),
+            messages,
+            stream: false,
+            // Models without tool support reject requests that carry `tools`, so only send them when needed
+            tools: if ollama_tools.as_ref().is_none_or(|t| t.is_empty()) { None } else { ollama_tools },
+            options,
+        };
+
+        let res = self.client.post(self.chat_url()).json(&request_body).send().await?;
+        if !res.status().is_success() {
+            let error_text = res.text().await?;
+            return Err(anyhow::anyhow!("Ollama API Error: {}", error_text));
+        }
+
+        let parsed: OllamaResponse = res.json().await?;
+        Ok(parse_response(parsed))
+    }
+}
+
+/// Converts an `/api/chat` response into the engine's `(text, calls, usage)` triple.
+fn parse_response(parsed: OllamaResponse) -> (String, Vec<GeminiFunctionCall>, Option<TokenUsage>) {
+    let function_calls = parsed.message.tool_calls.unwrap_or_default().into_iter().map(|tc| {
+        let args = match tc.function.arguments {
+            serde_json::Value::String(raw) => serde_json::from_str(&raw).unwrap_or_else(|_| {
+                tracing::warn!("🛠️ [Ollama] Failed to parse tool call arguments: {}", raw);
+                serde_json::json!({})
+            }),
+            serde_json::Value::Null => serde_json::json!({}),
+            args => args,
+        };
+        GeminiFunctionCall { name: tc.function.name, args }
+    }).collect();
+
+    let token_usage = match (parsed.prompt_eval_count, parsed.eval_count) {
+        (None, None) => None,
+        (input, output) => {
+            let (input, output) = (input.unwrap_or(0), output.unwrap_or(0));
+            Some(TokenUsage { input_tokens: input, output_tokens: output, total_tokens: input + output })
+        }
+    };
+
+    (parsed.message.content, function_calls, token_usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn provider(base_url: Option<&str>) -> OllamaProvider {
+        let config = ModelConfig {
+            provider: "ollama".to_string(),
+            model_id: "llama3.2".to_string(),
+            api_key: None,
+            base_url: base_url.map(str::to_string),
+            system_prompt: None,
+            temperature: None,
+            max_tokens: None,
+            external_id: None,
+            rpm: None,
+            rpd: None,
+            tpm: None,
+            tpd: None,
+            max_history_turns: 10,
+            safety_settings: None,
+        };
+        OllamaProvider::new(Client::new(), config)
+    }
+
+    #[test]
+    fn test_ollama_tool_call_response_parses() {
+        let body = json!({
+            "model": "llama3.2",
+            "created_at": "2024-10-01T12:00:00Z",
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{
+                    "function": {
+                        "name": "share_finding",
+                        "arguments": { "topic": "Pricing", "finding": "Tier 2 is underpriced" }
+                    }
+                }]
+            },
+            "done": true,
+            "prompt_eval_count": 120,
+            "eval_count": 30
+        });
+
+        let parsed: OllamaResponse = serde_json::from_value(body).unwrap();
+        let (text, calls, usage) = parse_response(parsed);
+
+        assert!(text.is_empty());
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "share_finding");
+        assert_eq!(calls[0].args["finding"], "Tier 2 is underpriced");
+        let usage = usage.unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens, usage.total_tokens), (120, 30, 150));
+    }
+
+    #[test]
+    fn test_ollama_text_response_with_string_arguments_and_cached_prompt() {
+        let body = json!({
+            "message": {
+                "role": "assistant",
+                "content": "Done.",
+                "tool_calls": [{ "function": { "name": "fetch_url", "arguments": "{\"url\":\"https://example.com\"}" } }]
+            },
+            "done": true,
+            "eval_count": 4
+        });
+
+        let parsed: OllamaResponse = serde_json::from_value(body).unwrap();
+        let (text, calls, usage) = parse_response(parsed);
+
+        assert_eq!(text, "Done.");
+        assert_eq!(calls[0].args["url"], "https://example.com");
+        assert_eq!(usage.unwrap().total_tokens, 4);
+
+        let bare: OllamaResponse = serde_json::from_value(json!({ "message": { "role": "assistant", "content": "hi" } })).unwrap();
+        assert!(parse_response(bare).2.is_none());
+    }
+
+    #[test]
+    fn test_ollama_chat_url_accepts_root_or_full_path() {
+        assert_eq!(provider(None).chat_url(), "http://localhost:11434/api/chat");
+        assert_eq!(provider(Some("http://gpu-box:11434/")).chat_url(), "http://gpu-box:11434/api/chat");
+        assert_eq!(provider(Some("http://gpu-box:11434/api/chat")).chat_url(), "http://gpu-box:11434/api/chat");
+    }
+
+    #[test]
+    fn test_ollama_request_disables_streaming() {
+        let request = OllamaRequest {
+            model: "qwen2.5".to_string(),
+            messages: vec![OllamaMessage { role: "user".to_string(), content: "hi".to_string() }],
+            stream: false,
+            tools: None,
+            options: Some(OllamaOptions { temperature: Some(0.2), num_predict: None }),
+        };
+
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["stream"], false);
+        assert!(value.get("tools").is_none());
+        assert!((value["options"]["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+        assert!(value["options"].get("num_predict").is_none());
+    }
+}
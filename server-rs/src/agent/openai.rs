@@ -0,0 +1,246 @@
+use reqwest::{Client, header};
+use serde::{Deserialize, Serialize};
+use crate::agent::types::{ConversationTurn, ModelConfig, TokenUsage, GeminiFunctionCall};
+
+const DEFAULT_OPENAI_URL: &str = "https://api.openai.com/v1/chat/completions";
+
+#[derive(Debug, Serialize)]
+struct OpenAIMessage {
+    role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAITool {
+    #[serde(rename = "type")]
+    tool_type: String,
+    function: OpenAIFunctionDefinition,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIFunctionDefinition {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIRequest {
+    model: String,
+    messages: Vec<OpenAIMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<OpenAITool>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIChoice {
+    message: OpenAIResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIResponseMessage {
+    content: Option<String>,
+    tool_calls: Option<Vec<OpenAIToolCall>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIToolCall {
+    function: OpenAIFunctionCall,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIFunctionCall {
+    name: String,
+    /// JSON-encoded arguments, as a string.
+    arguments: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    total_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIResponse {
+    choices: Vec<OpenAIChoice>,
+    usage: Option<OpenAIUsage>,
+}
+
+/// Provider for any OpenAI chat-completions compatible endpoint
+/// (OpenAI, Azure OpenAI, LM Studio, vLLM, local proxies).
+pub struct OpenAIProvider {
+    client: Client,
+    config: ModelConfig,
+    api_key: String,
+}
+
+impl OpenAIProvider {
+    /// Creates an OpenAIProvider with a shared `reqwest::Client`.
+    pub fn new(client: Client, api_key: String, config: ModelConfig) -> Self {
+        Self { client, config, api_key }
+    }
+
+    pub async fn generate(
+        &self,
+        system_prompt: &str,
+        user_message: &str,
+        history: Option<&[ConversationTurn]>,
+        tools: Option<Vec<crate::agent::gemini::GeminiTool>>,
+    ) -> anyhow::Result<(String, Vec<GeminiFunctionCall>, Option<TokenUsage>)> {
+        // `base_url` is the full chat-completions endpoint, matching the Groq provider
+        let url = self.config.base_url.as_deref().unwrap_or(DEFAULT_OPENAI_URL);
+
+        // Map Gemini tools to OpenAI tools
+        let openai_tools = tools.as_ref().map(|ts| {
+            ts.iter().flat_map(|t| {
+                t.function_declarations.iter().map(|f| {
+                    OpenAITool {
+                        tool_type: "function".to_string(),
+                        function: OpenAIFunctionDefinition {
+                            name: f.name.clone(),
+                            description: f.description.clone(),
+                            parameters: f.parameters.clone(),
+                        },
+                    }
+                })
+            }).collect::<Vec<OpenAITool>>()
+        });
+
+        let mut messages = vec![OpenAIMessage {
+            role: "system".to_string(),
+            content: Some(system_prompt.to_string()),
+        }];
+        messages.extend(self.config.history_window(history).iter().map(|turn| OpenAIMessage {
+            role: turn.role.clone(),
+            content: Some(turn.content.clone()),
+        }));
+        messages.push(OpenAIMessage {
+            role: "user".to_string(),
+            content: Some(user_message.to_string()),
+        });
+
+        let request_body = OpenAIRequest {
+            model: self.config.model_id.clone(),
+            messages,
+            temperature: self.config.temperature,
+            max_tokens: self.config.max_tokens,
+            user: self.config.external_id.clone(),
+            tools: if openai_tools.as_ref().is_none_or(|t| t.is_empty()) { None } else { openai_tools },
+        };
+
+        let mut request = self.client.post(url).json(&request_body);
+        // Azure OpenAI authenticates with an `api-key` header instead of a bearer token
+        if url.contains(".openai.azure.com") {
+            request = request.header("api-key", &self.api_key);
+        } else {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", self.api_key));
+        }
+
+        let res = request.send().await?;
+        if !res.status().is_success() {
+            let error_text = res.text().await?;
+            return Err(anyhow::anyhow!("OpenAI API Error: {}", error_text));
+        }
+
+        let parsed: OpenAIResponse = res.json().await?;
+        parse_response(parsed)
+    }
+}
+
+/// Converts a chat-completions response into the engine's `(text, calls, usage)` triple.
+fn parse_response(parsed: OpenAIResponse) -> anyhow::Result<(String, Vec<GeminiFunctionCall>, Option<TokenUsage>)> {
+    let choice = parsed.choices.into_iter().next()
+        .ok_or_else(|| anyhow::anyhow!("No completion returned from OpenAI-compatible endpoint"))?;
+
+    let output_text = choice.message.content.unwrap_or_default();
+
+    let function_calls = choice.message.tool_calls.unwrap_or_default().into_iter().map(|tc| {
+        let args: serde_json::Value = serde_json::from_str(&tc.function.arguments)
+            .unwrap_or_else(|_| {
+                tracing::warn!("🛠️ [OpenAI] Failed to parse tool call arguments: {}", tc.function.arguments);
+                serde_json::json!({})
+            });
+        GeminiFunctionCall { name: tc.function.name, args }
+    }).collect();
+
+    let token_usage = parsed.usage.map(|u| TokenUsage {
+        input_tokens: u.prompt_tokens,
+        output_tokens: u.completion_tokens,
+        total_tokens: u.total_tokens,
+    });
+
+    Ok((output_text, function_calls, token_usage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_openai_tool_call_response_parses() {
+        let body = json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_abc",
+                        "type": "function",
+                        "function": {
+                            "name": "share_finding",
+                            "arguments": "{\"topic\":\"Pricing\",\"finding\":\"Tier 2 is underpriced\"}"
+                        }
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": { "prompt_tokens": 120, "completion_tokens": 30, "total_tokens": 150 }
+        });
+
+        let parsed: OpenAIResponse = serde_json::from_value(body).unwrap();
+        let (text, calls, usage) = parse_response(parsed).unwrap();
+
+        assert!(text.is_empty());
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "share_finding");
+        assert_eq!(calls[0].args["finding"], "Tier 2 is underpriced");
+        assert_eq!(usage.unwrap().total_tokens, 150);
+    }
+
+    #[test]
+    fn test_openai_request_serializes_tools() {
+        let request = OpenAIRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![OpenAIMessage { role: "user".to_string(), content: Some("hi".to_string()) }],
+            temperature: None,
+            max_tokens: None,
+            user: None,
+            tools: Some(vec![OpenAITool {
+                tool_type: "function".to_string(),
+                function: OpenAIFunctionDefinition {
+                    name: "fetch_url".to_string(),
+                    description: "Fetches a URL".to_string(),
+                    parameters: json!({ "type": "object", "properties": {} }),
+                },
+            }]),
+        };
+
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["tools"][0]["type"], "function");
+        assert_eq!(value["tools"][0]["function"]["name"], "fetch_url");
+        assert!(value.get("temperature").is_none());
+    }
+}
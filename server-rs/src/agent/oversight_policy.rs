@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+use crate::agent::types::ToolCall;
+
+/// Rules file read from the data directory at startup and on `POST /system/reload-capabilities`.
+pub const POLICY_FILE: &str = "oversight_policy.json";
+
+/// What a matching rule does with an oversight request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    AutoApprove,
+    AutoReject,
+    RequireHuman,
+}
+
+/// One rule of the oversight policy. Patterns are globs where `*` matches any run of
+/// characters and `?` any single one, compared case-insensitively.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub skill_pattern: String,
+    #[serde(default = "match_all")]
+    pub agent_department_pattern: String,
+    pub action: PolicyAction,
+    /// The rule only matches calls whose JSON-encoded params are at most this long, so an
+    /// auto-approval cannot be stretched over an unexpectedly large payload.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_param_length: Option<usize>,
+}
+
+fn match_all() -> String {
+    "*".to_string()
+}
+
+impl PolicyRule {
+    pub fn matches(&self, tool_call: &ToolCall) -> bool {
+        glob_match(&self.skill_pattern, &tool_call.skill)
+            && glob_match(&self.agent_department_pattern, &tool_call.department)
+            && self.max_param_length.is_none_or(|max| tool_call.params.to_string().len() <= max)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PolicyFile {
+    #[serde(default)]
+    rules: Vec<PolicyRule>,
+}
+
+/// Ordered rules deciding which oversight requests skip the human queue.
+pub struct OversightPolicy {
+    path: PathBuf,
+    rules: RwLock<Vec<PolicyRule>>,
+    /// The dashboard's "auto-approve safe skills" switch. When off, `AutoApprove` rules
+    /// fall through to a human; `AutoReject` rules still apply.
+    auto_approve_enabled: AtomicBool,
+}
+
+impl OversightPolicy {
+    /// Loads `<data_dir>/oversight_policy.json`. A missing file means no rules; an unreadable
+    /// one is logged and also leaves every request to a human.
+    pub fn load(data_dir: &Path) -> Self {
+        let policy = Self {
+            path: data_dir.join(POLICY_FILE),
+            rules: RwLock::new(Vec::new()),
+            auto_approve_enabled: AtomicBool::new(true),
+        };
+        if let Err(e) = policy.reload() {
+            tracing::warn!("⚠️ [Oversight] Ignoring invalid {}: {}", policy.path.display(), e);
+        }
+        policy
+    }
+
+    /// A policy with fixed rules and no backing file.
+    #[allow(dead_code)]
+    pub fn with_rules(rules: Vec<PolicyRule>) -> Self {
+        Self {
+            path: PathBuf::new(),
+            rules: RwLock::new(rules),
+            auto_approve_enabled: AtomicBool::new(true),
+        }
+    }
+
+    /// Re-reads the rules file, keeping the current rules if it cannot be parsed.
+    /// Returns the number of rules loaded.
+    pub fn reload(&self) -> anyhow::Result<usize> {
+        let rules = match std::fs::read_to_string(&self.path) {
+            Ok(text) => serde_json::from_str::<PolicyFile>(&text)?.rules,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let count = rules.len();
+        *self.rules.write().unwrap_or_else(|e| e.into_inner()) = rules;
+        Ok(count)
+    }
+
+    #[allow(dead_code)]
+    pub fn rules(&self) -> Vec<PolicyRule> {
+        self.rules.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn auto_approve_enabled(&self) -> bool {
+        self.auto_approve_enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_auto_approve_enabled(&self, enabled: bool) {
+        self.auto_approve_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// The action of the first rule matching `tool_call`, or `RequireHuman` if none does.
+    pub fn evaluate(&self, tool_call: &ToolCall) -> PolicyAction {
+        let auto_approve = self.auto_approve_enabled();
+        self.rules.read().unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|rule| auto_approve || rule.action != PolicyAction::AutoApprove)
+            .find(|rule| rule.matches(tool_call))
+            .map_or(PolicyAction::RequireHuman, |rule| rule.action)
+    }
+}
+
+/// Case-insensitive glob match supporting `*` and `?`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it is currently absorbing up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, absorbed)) => {
+                    p = star + 1;
+                    t = absorbed + 1;
+                    backtrack = Some((star, absorbed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(skill: &str, department: &str, params: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: "call".to_string(),
+            mission_id: None,
+            agent_id: "agent".to_string(),
+            skill: skill.to_string(),
+            params,
+            department: department.to_string(),
+            description: String::new(),
+            timestamp: String::new(),
+        }
+    }
+
+    fn rule(skill: &str, department: &str, action: PolicyAction) -> PolicyRule {
+        PolicyRule { skill_pattern: skill.to_string(), agent_department_pattern: department.to_string(), action, max_param_length: None }
+    }
+
+    #[test]
+    fn glob_matches_wildcards_case_insensitively() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("notify_*", "notify_slack"));
+        assert!(glob_match("NOTIFY_*", "notify_discord"));
+        assert!(glob_match("*_file", "delete_file"));
+        assert!(glob_match("a*b*c", "aXXbYYbZc"));
+        assert!(glob_match("fetch_ur?", "fetch_url"));
+        assert!(!glob_match("fetch_ur?", "fetch_url_advanced"));
+        assert!(!glob_match("notify_*", "archive_to_vault"));
+        assert!(!glob_match("", "skill"));
+    }
+
+    #[test]
+    fn max_param_length_limits_a_rule() {
+        let policy = OversightPolicy::with_rules(vec![PolicyRule {
+            max_param_length: Some(40),
+            ..rule("notify_*", "*", PolicyAction::AutoApprove)
+        }]);
+
+        let short = call("notify_slack", "Marketing", serde_json::json!({ "message": "done" }));
+        assert_eq!(policy.evaluate(&short), PolicyAction::AutoApprove);
+
+        let long = call("notify_slack", "Marketing", serde_json::json!({ "message": "x".repeat(100) }));
+        assert_eq!(policy.evaluate(&long), PolicyAction::RequireHuman);
+    }
+
+    #[test]
+    fn first_matching_rule_wins_and_the_switch_disables_auto_approval() {
+        let policy = OversightPolicy::with_rules(vec![
+            rule("delete_file", "*", PolicyAction::AutoReject),
+            rule("*", "engineering", PolicyAction::RequireHuman),
+            rule("*_file", "*", PolicyAction::AutoApprove),
+        ]);
+
+        assert_eq!(policy.evaluate(&call("delete_file", "Marketing", serde_json::json!({}))), PolicyAction::AutoReject);
+        assert_eq!(policy.evaluate(&call("write_file", "Engineering", serde_json::json!({}))), PolicyAction::RequireHuman);
+        assert_eq!(policy.evaluate(&call("write_file", "Marketing", serde_json::json!({}))), PolicyAction::AutoApprove);
+        assert_eq!(policy.evaluate(&call("execute_bash", "Marketing", serde_json::json!({}))), PolicyAction::RequireHuman);
+
+        policy.set_auto_approve_enabled(false);
+        assert_eq!(policy.evaluate(&call("write_file", "Marketing", serde_json::json!({}))), PolicyAction::RequireHuman);
+        assert_eq!(policy.evaluate(&call("delete_file", "Marketing", serde_json::json!({}))), PolicyAction::AutoReject);
+    }
+
+    #[test]
+    fn rules_load_from_the_data_directory() {
+        let dir = std::env::temp_dir().join(format!("tadpole_policy_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(OversightPolicy::load(&dir).rules().is_empty(), "a missing file means no rules");
+
+        std::fs::write(dir.join(POLICY_FILE), r#"{ "rules": [
+            { "skill_pattern": "notify_*", "action": "auto_approve", "max_param_length": 500 }
+        ] }"#).unwrap();
+        let policy = OversightPolicy::load(&dir);
+        assert_eq!(policy.rules(), vec![PolicyRule { max_param_length: Some(500), ..rule("notify_*", "*", PolicyAction::AutoApprove) }]);
+
+        std::fs::write(dir.join(POLICY_FILE), "not json").unwrap();
+        assert!(policy.reload().is_err());
+        assert_eq!(policy.rules().len(), 1, "a broken file keeps the previous rules");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
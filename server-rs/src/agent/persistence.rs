@@ -47,66 +47,100 @@ pub async fn save_registry(agents: Vec<EngineAgent>) -> Result<()> {
     Ok(())
 }
 
-/// Loads agents from the database.
+/// Builds an agent from a row of the `agents` table.
+fn agent_from_row(row: &sqlx::sqlite::SqliteRow) -> EngineAgent {
+    use sqlx::Row;
+    let metadata_str: String = row.get("metadata");
+    let metadata: std::collections::HashMap<String, serde_json::Value> = 
+        serde_json::from_str(&metadata_str).unwrap_or_default();
+    
+    EngineAgent {
+        id: row.get("id"),
+        name: row.get("name"),
+        role: row.get("role"),
+        department: row.get("department"),
+        description: row.get("description"),
+        model_id: row.get("model_id"),
+        tokens_used: row.get::<Option<i64>, _>("tokens_used").unwrap_or(0) as u32,
+        status: row.get("status"),
+        theme_color: row.get("theme_color"),
+        budget_usd: row.get::<Option<f64>, _>("budget_usd").unwrap_or(0.0),
+        cost_usd: row.get::<Option<f64>, _>("cost_usd").unwrap_or(0.0),
+        metadata,
+        skills: serde_json::from_str(&row.get::<String, _>("skills")).unwrap_or_default(),
+        workflows: serde_json::from_str(&row.get::<String, _>("workflows")).unwrap_or_default(),
+        skill_allowlist: row.try_get::<Option<String>, _>("skill_allowlist").ok().flatten()
+            .and_then(|s| serde_json::from_str(&s).ok()),
+        rpm_override: row.try_get::<Option<i64>, _>("rpm_override").ok().flatten().map(|v| v as u32),
+        tpm_override: row.try_get::<Option<i64>, _>("tpm_override").ok().flatten().map(|v| v as u32),
+        model_2: row.try_get("model_2").ok(),
+        model_3: row.try_get("model_3").ok(),
+        model_config2: row.get::<Option<String>, _>("model_config2").and_then(|s| serde_json::from_str(&s).ok()),
+        model_config3: row.get::<Option<String>, _>("model_config3").and_then(|s| serde_json::from_str(&s).ok()),
+        active_model_slot: row.get::<Option<i32>, _>("active_model_slot"),
+        token_usage: TokenUsage::default(),
+        // Fallbacks for transient UI data not in core DB table yet
+        model: crate::agent::types::ModelConfig {
+            provider: "".to_string(), // Resolved dynamically in runner
+            model_id: row.get::<Option<String>, _>("model_id").unwrap_or_else(|| "gemini-1.5-pro".to_string()),
+            api_key: None,
+            base_url: None,
+            system_prompt: None,
+            temperature: None,
+            max_tokens: None,
+            external_id: None,
+            rpm: None,
+            rpd: None,
+            tpm: None,
+            tpd: None,
+            max_history_turns: crate::agent::types::DEFAULT_MAX_HISTORY_TURNS,
+            safety_settings: None,
+        },
+        active_mission: None,
+    }
+}
+
+/// Loads agents from the database. Soft-deleted agents are skipped.
 pub async fn load_agents_db(pool: &SqlitePool) -> Result<Vec<EngineAgent>> {
-    let rows = sqlx::query("SELECT * FROM agents").fetch_all(pool).await?;
-    let mut agents = Vec::new();
+    let rows = sqlx::query("SELECT * FROM agents WHERE deleted_at IS NULL").fetch_all(pool).await?;
+    Ok(rows.iter().map(agent_from_row).collect())
+}
 
-    for row in rows {
-        use sqlx::Row;
-        let metadata_str: String = row.get("metadata");
-        let metadata: std::collections::HashMap<String, serde_json::Value> = 
-            serde_json::from_str(&metadata_str).unwrap_or_default();
-        
-        let agent = EngineAgent {
-            id: row.get("id"),
-            name: row.get("name"),
-            role: row.get("role"),
-            department: row.get("department"),
-            description: row.get("description"),
-            model_id: row.get("model_id"),
-            tokens_used: row.get::<Option<i64>, _>("tokens_used").unwrap_or(0) as u32,
-            status: row.get("status"),
-            theme_color: row.get("theme_color"),
-            budget_usd: row.get::<Option<f64>, _>("budget_usd").unwrap_or(0.0),
-            cost_usd: row.get::<Option<f64>, _>("cost_usd").unwrap_or(0.0),
-            metadata,
-            skills: serde_json::from_str(&row.get::<String, _>("skills")).unwrap_or_default(),
-            workflows: serde_json::from_str(&row.get::<String, _>("workflows")).unwrap_or_default(),
-            model_2: row.try_get("model_2").ok(),
-            model_3: row.try_get("model_3").ok(),
-            model_config2: row.get::<Option<String>, _>("model_config2").and_then(|s| serde_json::from_str(&s).ok()),
-            model_config3: row.get::<Option<String>, _>("model_config3").and_then(|s| serde_json::from_str(&s).ok()),
-            active_model_slot: row.get::<Option<i32>, _>("active_model_slot"),
-            token_usage: TokenUsage::default(),
-            // Fallbacks for transient UI data not in core DB table yet
-            model: crate::agent::types::ModelConfig {
-                provider: "".to_string(), // Resolved dynamically in runner
-                model_id: row.get::<Option<String>, _>("model_id").unwrap_or_else(|| "gemini-1.5-pro".to_string()),
-                api_key: None,
-                base_url: None,
-                system_prompt: None,
-                temperature: None,
-                max_tokens: None,
-                external_id: None,
-                rpm: None,
-                rpd: None,
-                tpm: None,
-                tpd: None,
-            },
-            active_mission: None,
-        };
-        agents.push(agent);
-    }
-    Ok(agents)
+/// Loads soft-deleted agents with their deletion time, most recently deleted first.
+pub async fn load_deleted_agents_db(pool: &SqlitePool) -> Result<Vec<(EngineAgent, String)>> {
+    use sqlx::Row;
+    let rows = sqlx::query("SELECT * FROM agents WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.iter().map(|row| (agent_from_row(row), row.get::<String, _>("deleted_at"))).collect())
+}
+
+/// Marks an agent as deleted. The row is kept so it can be listed for recovery.
+pub async fn soft_delete_agent_db(pool: &SqlitePool, agent_id: &str) -> Result<()> {
+    sqlx::query("UPDATE agents SET deleted_at = ? WHERE id = ?")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(agent_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Saves a single agent to the database, or within a transaction when given `&mut *tx`.
+/// A soft-deleted agent stays deleted; only `create_agent_db` brings its row back.
+pub async fn save_agent_db<'e>(executor: impl sqlx::SqliteExecutor<'e>, agent: &EngineAgent) -> Result<()> {
+    upsert_agent(executor, agent, false).await
+}
+
+/// Saves a newly created agent. Reusing the ID of a soft-deleted agent restores that row.
+pub async fn create_agent_db<'e>(executor: impl sqlx::SqliteExecutor<'e>, agent: &EngineAgent) -> Result<()> {
+    upsert_agent(executor, agent, true).await
 }
 
-/// Saves a single agent to the database.
-pub async fn save_agent_db(pool: &SqlitePool, agent: &EngineAgent) -> Result<()> {
+async fn upsert_agent<'e>(executor: impl sqlx::SqliteExecutor<'e>, agent: &EngineAgent, restore: bool) -> Result<()> {
     let metadata_json = serde_json::to_string(&agent.metadata)?;
     
-    sqlx::query("INSERT INTO agents (id, name, role, department, description, model_id, tokens_used, status, theme_color, budget_usd, cost_usd, metadata, skills, workflows, model_2, model_3, model_config2, model_config3, active_model_slot)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    sqlx::query("INSERT INTO agents (id, name, role, department, description, model_id, tokens_used, status, theme_color, budget_usd, cost_usd, metadata, skills, workflows, model_2, model_3, model_config2, model_config3, active_model_slot, skill_allowlist, rpm_override, tpm_override)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(id) DO UPDATE SET
             name = excluded.name,
             role = excluded.role,
@@ -125,7 +159,11 @@ pub async fn save_agent_db(pool: &SqlitePool, agent: &EngineAgent) -> Result<()>
             model_3 = excluded.model_3,
             model_config2 = excluded.model_config2,
             model_config3 = excluded.model_config3,
-            active_model_slot = excluded.active_model_slot")
+            active_model_slot = excluded.active_model_slot,
+            skill_allowlist = excluded.skill_allowlist,
+            rpm_override = excluded.rpm_override,
+            tpm_override = excluded.tpm_override,
+            deleted_at = CASE WHEN ? THEN NULL ELSE agents.deleted_at END")
     .bind(&agent.id)
     .bind(&agent.name)
     .bind(&agent.role)
@@ -145,7 +183,11 @@ pub async fn save_agent_db(pool: &SqlitePool, agent: &EngineAgent) -> Result<()>
     .bind(agent.model_config2.as_ref().and_then(|c| serde_json::to_string(c).ok()))
     .bind(agent.model_config3.as_ref().and_then(|c| serde_json::to_string(c).ok()))
     .bind(agent.active_model_slot)
-    .execute(pool)
+    .bind(agent.skill_allowlist.as_ref().and_then(|l| serde_json::to_string(l).ok()))
+    .bind(agent.rpm_override.map(i64::from))
+    .bind(agent.tpm_override.map(i64::from))
+    .bind(restore)
+    .execute(executor)
     .await?;
 
     Ok(())
@@ -0,0 +1,182 @@
+use dashmap::DashMap;
+use reqwest::Client;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use crate::agent::types::ProviderConfig;
+
+/// Default seconds between provider health checks (`PROVIDER_HEALTH_CHECK_INTERVAL_SECS`).
+pub const DEFAULT_HEALTH_CHECK_INTERVAL_SECS: u64 = 60;
+/// How long one health check may take before the provider counts as down.
+const HEALTH_CHECK_TIMEOUT_SECS: u64 = 10;
+
+const GOOGLE_MODELS_URL: &str = "https://generativelanguage.googleapis.com/v1/models";
+const GROQ_MODELS_URL: &str = "https://api.groq.com/openai/v1/models";
+
+/// Result of the latest check of one provider.
+#[derive(Debug, Clone)]
+pub struct ProviderHealth {
+    pub last_checked: Instant,
+    pub is_healthy: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+impl ProviderHealth {
+    pub fn to_json(&self, provider_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "providerId": provider_id,
+            "checked": true,
+            "isHealthy": self.is_healthy,
+            "latencyMs": self.latency_ms,
+            "error": self.error,
+            "lastCheckedSecsAgo": self.last_checked.elapsed().as_secs()
+        })
+    }
+}
+
+/// Health map key for a provider name as used by agents; `gemini` is an alias of `google`.
+pub fn health_key(provider: &str) -> &str {
+    if provider == "gemini" { "google" } else { provider }
+}
+
+/// The models-listing request that checks `provider`, or `None` if the provider is not
+/// checked (unknown protocol, or no API key configured on it or in the environment).
+fn health_request(client: &Client, provider: &ProviderConfig) -> Option<reqwest::RequestBuilder> {
+    let key = |env: &str| provider.api_key.clone().filter(|k| !k.is_empty()).or_else(|| std::env::var(env).ok().filter(|k| !k.is_empty()));
+    match health_key(&provider.id) {
+        "google" => key("GOOGLE_API_KEY").map(|k| client.get(GOOGLE_MODELS_URL).header("x-goog-api-key", k)),
+        "groq" => key("GROQ_API_KEY").map(|k| client.get(GROQ_MODELS_URL).bearer_auth(k)),
+        _ => None,
+    }
+}
+
+/// Sends `request` and reports the provider healthy if it answers with a success status in time.
+pub async fn probe(request: reqwest::RequestBuilder) -> ProviderHealth {
+    let started = Instant::now();
+    let outcome = request.timeout(Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS)).send().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let error = match outcome {
+        Ok(response) if response.status().is_success() => None,
+        Ok(response) => Some(format!("HTTP {}", response.status())),
+        Err(e) if e.is_timeout() => Some(format!("no response within {}s", HEALTH_CHECK_TIMEOUT_SECS)),
+        Err(e) => Some(e.to_string()),
+    };
+    ProviderHealth { last_checked: Instant::now(), is_healthy: error.is_none(), latency_ms, error }
+}
+
+/// Stores `health` for `provider_id` and emits `provider:health_change` when it differs from
+/// the previous result. A provider that was never checked counts as healthy.
+pub fn record(
+    health_map: &DashMap<String, ProviderHealth>,
+    event_tx: &broadcast::Sender<serde_json::Value>,
+    provider_id: &str,
+    health: ProviderHealth,
+) {
+    let was_healthy = health_map.get(provider_id).is_none_or(|previous| previous.is_healthy);
+    if was_healthy != health.is_healthy {
+        match &health.error {
+            Some(error) => tracing::warn!("🩺 [Providers] {} is unhealthy: {}", provider_id, error),
+            None => tracing::info!("🩺 [Providers] {} is healthy again", provider_id),
+        }
+        let _ = event_tx.send(serde_json::json!({
+            "type": "provider:health_change",
+            "providerId": provider_id,
+            "isHealthy": health.is_healthy,
+            "latencyMs": health.latency_ms,
+            "error": health.error,
+        }));
+    }
+    health_map.insert(provider_id.to_string(), health);
+}
+
+/// Checks every configured provider every `interval`. Providers that are not checked keep no
+/// entry, so they are never treated as unhealthy.
+pub fn spawn_provider_health_checks(
+    providers: Arc<DashMap<String, ProviderConfig>>,
+    health_map: Arc<DashMap<String, ProviderHealth>>,
+    client: Arc<Client>,
+    event_tx: broadcast::Sender<serde_json::Value>,
+    interval: Duration,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let requests: Vec<(String, reqwest::RequestBuilder)> = providers.iter()
+                .filter_map(|p| health_request(&client, p.value()).map(|request| (health_key(p.key()).to_string(), request)))
+                .collect();
+            health_map.retain(|id, _| requests.iter().any(|(checked, _)| checked == id));
+
+            let results = futures::future::join_all(requests.into_iter().map(|(id, request)| async move {
+                (id, probe(request).await)
+            })).await;
+            for (id, health) in results {
+                record(&health_map, &event_tx, &id, health);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serves one HTTP response with `status_line` and returns the URL to request.
+    async fn serve_once(status_line: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            let response = format!("HTTP/1.1 {}\r\ncontent-length: 2\r\nconnection: close\r\n\r\n{{}}", status_line);
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        format!("http://{}/v1/models", addr)
+    }
+
+    #[tokio::test]
+    async fn probe_reports_success_and_rejected_keys() {
+        let client = Client::new();
+
+        let healthy = probe(client.get(serve_once("200 OK").await)).await;
+        assert!(healthy.is_healthy);
+        assert_eq!(healthy.error, None);
+
+        let rejected = probe(client.get(serve_once("401 Unauthorized").await)).await;
+        assert!(!rejected.is_healthy);
+        assert_eq!(rejected.error.as_deref(), Some("HTTP 401 Unauthorized"));
+
+        // Bind then drop a listener so the port is known to be closed
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        assert!(!probe(client.get(format!("http://127.0.0.1:{}/", port))).await.is_healthy);
+    }
+
+    #[test]
+    fn health_change_is_emitted_only_when_the_status_flips() {
+        let (event_tx, mut events) = broadcast::channel(16);
+        let map = DashMap::new();
+        let health = |is_healthy: bool| ProviderHealth {
+            last_checked: Instant::now(),
+            is_healthy,
+            latency_ms: 5,
+            error: (!is_healthy).then(|| "HTTP 401 Unauthorized".to_string()),
+        };
+
+        record(&map, &event_tx, "groq", health(true));
+        assert!(events.try_recv().is_err(), "a first healthy result is not a change");
+
+        record(&map, &event_tx, "groq", health(false));
+        let event = events.try_recv().unwrap();
+        assert_eq!(event["type"], "provider:health_change");
+        assert_eq!(event["isHealthy"], false);
+
+        record(&map, &event_tx, "groq", health(false));
+        assert!(events.try_recv().is_err());
+
+        record(&map, &event_tx, "groq", health(true));
+        assert_eq!(events.try_recv().unwrap()["isHealthy"], true);
+    }
+}
@@ -1,102 +1,235 @@
 /// Rate limiter for LLM provider API calls.
 ///
-/// Enforces two independent limits from ModelEntry/ModelConfig:
-///   - RPM (requests per minute): a rolling window via a `Semaphore` with timed release.
-///   - TPM (tokens per minute): an atomic counter reset every 60 seconds.
+/// Enforces independent limits from ModelEntry/ModelConfig:
+///   - RPM (requests per minute): a sliding 60-second window of request timestamps.
+///   - TPM (tokens per minute): a sliding 60-second window of recorded token usage, so a burst
+///     straddling a minute boundary cannot exceed the limit.
+///   - RPD / TPD (requests / tokens per day): atomic counters reset every 86,400 seconds
+///     by a background task spawned on the first `acquire`.
 ///
-/// Both limits are opt-in — if rpm/tpm are `None` in the model config, no throttling occurs.
+/// All limits are opt-in — if a limit is `None` in the model config, it is not enforced.
 ///
 /// # Usage
 /// ```
-/// let limiter = RateLimiter::new(60, 100_000); // 60 RPM, 100k TPM
+/// let limiter = RateLimiter::new(Some(60), Some(100_000), Some(1_000), None); // 60 RPM, 100k TPM, 1k RPD
 /// limiter.acquire(512).await; // "I'm about to use ~512 tokens"
 /// // make your API call
 /// limiter.record_usage(420); // "I actually used 420 tokens"
 /// ```
-use std::sync::Arc;
-use std::sync::atomic::{AtomicU32, Ordering};
-use tokio::sync::Semaphore;
+use std::collections::VecDeque;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use tokio::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
 pub struct RateLimiter {
-    /// Semaphore permits == max concurrent requests in the current window.
-    rpm_semaphore: Option<Arc<Semaphore>>,
+    /// Start times of the requests made within the last `window`.
+    request_events: Arc<std::sync::Mutex<VecDeque<Instant>>>,
     rpm_limit: Option<u32>,
 
-    /// Tokens used in current minute window.
-    tokens_used: Arc<AtomicU32>,
+    /// `(recorded_at, tokens)` for usage recorded within the last `window`.
+    token_events: Arc<std::sync::Mutex<VecDeque<(Instant, u32)>>>,
     tpm_limit: Option<u32>,
 
-    /// Timestamp of the start of the current 60s window.
-    window_start: Arc<Mutex<Instant>>,
+    /// Length of the sliding RPM/TPM window (60s outside tests).
+    window: Duration,
+
+    /// Requests and tokens counted in the current day window.
+    day_requests: Arc<AtomicU32>,
+    day_tokens: Arc<AtomicU32>,
+    rpd_limit: Option<u32>,
+    tpd_limit: Option<u32>,
+
+    /// Timestamp of the start of the current 24h window.
+    day_window_start: Arc<Mutex<Instant>>,
+    day_reset_spawned: AtomicBool,
+}
+
+/// Point-in-time RPM/TPM utilization of a limiter, for `GET /infra/models/:id/stats`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct RateLimiterStats {
+    pub rpm_limit: Option<u32>,
+    /// Requests started within the current window.
+    pub rpm_used: u32,
+    pub tpm_limit: Option<u32>,
+    /// Tokens recorded within the current window.
+    pub tpm_used: u32,
+}
+
+const DAY: Duration = Duration::from_secs(86_400);
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// Drops events that have slid out of the window ending now.
+fn evict_expired<T>(events: &mut VecDeque<T>, window: Duration, at: impl Fn(&T) -> Instant) {
+    while events.front().is_some_and(|e| at(e).elapsed() >= window) {
+        events.pop_front();
+    }
 }
 
 impl RateLimiter {
-    pub fn new(rpm: Option<u32>, tpm: Option<u32>) -> Self {
-        let rpm_semaphore = rpm.map(|r| Arc::new(Semaphore::new(r as usize)));
+    pub fn new(rpm: Option<u32>, tpm: Option<u32>, rpd: Option<u32>, tpd: Option<u32>) -> Self {
+        Self::with_window(rpm, tpm, rpd, tpd, MINUTE)
+    }
+
+    /// Like `new`, with a custom RPM/TPM window so tests need not wait a full minute.
+    pub(crate) fn with_window(rpm: Option<u32>, tpm: Option<u32>, rpd: Option<u32>, tpd: Option<u32>, window: Duration) -> Self {
         Self {
-            rpm_semaphore,
+            request_events: Arc::new(std::sync::Mutex::new(VecDeque::new())),
             rpm_limit: rpm,
-            tokens_used: Arc::new(AtomicU32::new(0)),
+            token_events: Arc::new(std::sync::Mutex::new(VecDeque::new())),
             tpm_limit: tpm,
-            window_start: Arc::new(Mutex::new(Instant::now())),
+            window,
+            day_requests: Arc::new(AtomicU32::new(0)),
+            day_tokens: Arc::new(AtomicU32::new(0)),
+            rpd_limit: rpd,
+            tpd_limit: tpd,
+            day_window_start: Arc::new(Mutex::new(Instant::now())),
+            day_reset_spawned: AtomicBool::new(false),
         }
     }
 
+    /// Starts the task that clears the daily counters every 24h.
+    /// It holds weak references only, so it exits once the limiter is dropped.
+    fn spawn_day_reset(&self) {
+        if self.day_reset_spawned.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let requests: Weak<AtomicU32> = Arc::downgrade(&self.day_requests);
+        let tokens: Weak<AtomicU32> = Arc::downgrade(&self.day_tokens);
+        let start: Weak<Mutex<Instant>> = Arc::downgrade(&self.day_window_start);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(DAY).await;
+                let (Some(requests), Some(tokens), Some(start)) = (requests.upgrade(), tokens.upgrade(), start.upgrade()) else {
+                    break;
+                };
+                let mut start = start.lock().await;
+                requests.store(0, Ordering::SeqCst);
+                tokens.store(0, Ordering::SeqCst);
+                *start = Instant::now();
+            }
+        });
+    }
+
+    /// Blocks until the day window has room for one more request of `estimated_tokens`, then counts it.
+    async fn acquire_daily(&self, estimated_tokens: u32) {
+        loop {
+            let mut start = self.day_window_start.lock().await;
+            let elapsed = start.elapsed();
+
+            if elapsed >= DAY {
+                // The reset task is late (or the runtime was suspended): reset inline
+                self.day_requests.store(0, Ordering::SeqCst);
+                self.day_tokens.store(0, Ordering::SeqCst);
+                *start = Instant::now();
+            }
+
+            let requests_ok = self.rpd_limit
+                .is_none_or(|rpd| self.day_requests.load(Ordering::SeqCst) < rpd);
+            let tokens_ok = self.tpd_limit
+                .is_none_or(|tpd| self.day_tokens.load(Ordering::SeqCst).saturating_add(estimated_tokens) <= tpd);
+            if requests_ok && tokens_ok {
+                self.day_requests.fetch_add(1, Ordering::SeqCst);
+                break;
+            }
 
-    /// Acquires a request slot, blocking if RPM or TPM limits would be exceeded.
+            let wait = DAY.saturating_sub(elapsed);
+            drop(start); // release lock before sleeping
+
+            tracing::warn!(
+                "⏳ [RateLimiter] Daily limit ({} requests/day, {} tokens/day) reached. Waiting {}s for window reset.",
+                self.rpd_limit.map(|v| v.to_string()).unwrap_or_else(|| "∞".to_string()),
+                self.tpd_limit.map(|v| v.to_string()).unwrap_or_else(|| "∞".to_string()),
+                wait.as_secs()
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+
+    /// Acquires a request slot, blocking if the daily, RPM or TPM limits would be exceeded.
     /// `estimated_tokens`: an estimate of the tokens this request will consume.
     pub async fn acquire(&self, estimated_tokens: u32) {
+        // ── RPD / TPD enforcement ────────────────────────────────────────────
+        if self.rpd_limit.is_some() || self.tpd_limit.is_some() {
+            self.spawn_day_reset();
+            self.acquire_daily(estimated_tokens).await;
+        }
+
         // ── TPM enforcement ──────────────────────────────────────────────────
         if let Some(tpm) = self.tpm_limit {
             loop {
-                let mut start = self.window_start.lock().await;
-                let elapsed = start.elapsed();
-
-                if elapsed >= Duration::from_secs(60) {
-                    // New window: reset counter and timestamp
-                    self.tokens_used.store(0, Ordering::SeqCst);
-                    *start = Instant::now();
-                }
-
-                let current = self.tokens_used.load(Ordering::SeqCst);
-                if current + estimated_tokens <= tpm {
-                    break;
-                }
-
-                // Calculate how long until the window resets
-                let wait = Duration::from_secs(60).saturating_sub(elapsed);
-                drop(start); // release lock before sleeping
+                let wait = {
+                    let mut events = self.token_events.lock().unwrap_or_else(|e| e.into_inner());
+                    evict_expired(&mut events, self.window, |(at, _)| *at);
+                    let used: u32 = events.iter().fold(0u32, |sum, (_, tokens)| sum.saturating_add(*tokens));
+                    // An empty window always admits the request: an estimate above the limit could never fit
+                    if events.is_empty() || used.saturating_add(estimated_tokens) <= tpm {
+                        break;
+                    }
+                    // Wait until the oldest usage slides out of the window
+                    events.front().map(|(at, _)| self.window.saturating_sub(at.elapsed())).unwrap_or_default()
+                };
 
                 tracing::warn!(
-                    "⏳ [RateLimiter] TPM limit ({} tokens/min) reached. Waiting {}s for window reset.",
+                    "⏳ [RateLimiter] TPM limit ({} tokens/min) reached. Waiting {}ms for usage to expire.",
                     tpm,
-                    wait.as_secs()
+                    wait.as_millis()
                 );
                 tokio::time::sleep(wait).await;
             }
         }
 
         // ── RPM enforcement ──────────────────────────────────────────────────
-        if let Some(ref sem) = self.rpm_semaphore {
-            let permit = sem.clone().acquire_owned().await.expect("Semaphore closed");
-
-            // Schedule permit return after 60s (sliding window)
-            tokio::spawn(async move {
-                tokio::time::sleep(Duration::from_secs(60)).await;
-                drop(permit);
-            });
+        if let Some(rpm) = self.rpm_limit {
+            loop {
+                let wait = {
+                    let mut events = self.request_events.lock().unwrap_or_else(|e| e.into_inner());
+                    evict_expired(&mut events, self.window, |at| *at);
+                    if events.len() < rpm as usize {
+                        events.push_back(Instant::now());
+                        break;
+                    }
+                    events.front().map(|at| self.window.saturating_sub(at.elapsed())).unwrap_or_default()
+                };
+
+                tracing::warn!(
+                    "⏳ [RateLimiter] RPM limit ({} requests/min) reached. Waiting {}ms for a slot.",
+                    rpm,
+                    wait.as_millis()
+                );
+                tokio::time::sleep(wait).await;
+            }
         }
     }
 
     /// Records the actual tokens consumed after a successful API call.
     pub fn record_usage(&self, actual_tokens: u32) {
-        self.tokens_used.fetch_add(actual_tokens, Ordering::SeqCst);
+        if self.tpm_limit.is_some() {
+            self.token_events.lock().unwrap_or_else(|e| e.into_inner()).push_back((Instant::now(), actual_tokens));
+        }
+        self.day_tokens.fetch_add(actual_tokens, Ordering::SeqCst);
+    }
+
+    /// Current RPM/TPM usage within the sliding window.
+    pub fn stats(&self) -> RateLimiterStats {
+        let rpm_used = {
+            let mut events = self.request_events.lock().unwrap_or_else(|e| e.into_inner());
+            evict_expired(&mut events, self.window, |at| *at);
+            events.len() as u32
+        };
+        let tpm_used = {
+            let mut events = self.token_events.lock().unwrap_or_else(|e| e.into_inner());
+            evict_expired(&mut events, self.window, |(at, _)| *at);
+            events.iter().fold(0u32, |sum, (_, tokens)| sum.saturating_add(*tokens))
+        };
+        RateLimiterStats { rpm_limit: self.rpm_limit, rpm_used, tpm_limit: self.tpm_limit, tpm_used }
     }
 
     /// Convenience: returns true if this limiter has any active constraints.
     pub fn is_active(&self) -> bool {
         self.rpm_limit.is_some() || self.tpm_limit.is_some()
+            || self.rpd_limit.is_some() || self.tpd_limit.is_some()
     }
 }
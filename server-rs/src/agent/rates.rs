@@ -30,6 +30,11 @@ pub static MODEL_RATES: Lazy<HashMap<&'static str, ModelRate>> = Lazy::new(|| {
     m.insert("llama-3.3-70b-versatile", ModelRate { input_cost_per_1k: 0.00059, output_cost_per_1k: 0.00079 });
     m.insert("mixtral-8x7b-32768", ModelRate { input_cost_per_1k: 0.00027, output_cost_per_1k: 0.00027 });
 
+    // Ollama (local inference, no per-token charge)
+    for model in ["llama3.2", "mistral", "qwen2.5"] {
+        m.insert(model, ModelRate { input_cost_per_1k: 0.0, output_cost_per_1k: 0.0 });
+    }
+
     m
 });
 
@@ -55,6 +60,12 @@ pub fn calculate_cost(model_id: &str, input_tokens: u32, output_tokens: u32) ->
     input_cost + output_cost
 }
 
+/// Rough token count for `text` (about four characters per token), used for pre-flight estimates
+/// where no provider tokenizer is available.
+pub fn estimate_tokens(text: &str) -> u32 {
+    text.chars().count().div_ceil(4) as u32
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use serde_json::json;
-use crate::agent::types::{EngineAgent, ModelConfig, TokenUsage, ProviderConfig, ModelEntry};
+use crate::agent::types::{EngineAgent, ModelConfig, TokenUsage, ProviderConfig, ModelEntry, DEFAULT_MAX_HISTORY_TURNS};
 
 pub fn get_default_providers() -> Vec<ProviderConfig> {
     vec![
@@ -14,6 +14,7 @@ pub fn get_default_providers() -> Vec<ProviderConfig> {
             custom_headers: None,
             external_id: None,
             audio_model: None,
+            timeout_secs: None,
         },
         ProviderConfig {
             id: "groq".to_string(),
@@ -25,6 +26,31 @@ pub fn get_default_providers() -> Vec<ProviderConfig> {
             custom_headers: None,
             external_id: None,
             audio_model: Some("whisper-large-v3".to_string()),
+            timeout_secs: None,
+        },
+        ProviderConfig {
+            id: "openai".to_string(),
+            name: "OpenAI Compatible".to_string(),
+            icon: Some("🧠".to_string()),
+            api_key: None,
+            base_url: None, // Default URL used in openai.rs; point at Azure or a local proxy here
+            protocol: "openai".to_string(),
+            custom_headers: None,
+            external_id: None,
+            audio_model: None,
+            timeout_secs: None,
+        },
+        ProviderConfig {
+            id: "ollama".to_string(),
+            name: "Ollama (Local)".to_string(),
+            icon: Some("🦙".to_string()),
+            api_key: None, // Ollama does not authenticate
+            base_url: Some(crate::agent::ollama::DEFAULT_OLLAMA_URL.to_string()),
+            protocol: "ollama".to_string(),
+            custom_headers: None,
+            external_id: None,
+            audio_model: None,
+            timeout_secs: None,
         },
     ]
 }
@@ -40,6 +66,8 @@ pub fn get_default_models() -> Vec<ModelEntry> {
             rpd: None,
             tpd: None,
             modality: Some("llm".to_string()),
+            context_window: Some(1048576),
+            max_output_tokens: Some(8192),
         },
         ModelEntry {
             id: "gemini-pro-latest".to_string(),
@@ -50,6 +78,8 @@ pub fn get_default_models() -> Vec<ModelEntry> {
             rpd: None,
             tpd: None,
             modality: Some("llm".to_string()),
+            context_window: Some(2097152),
+            max_output_tokens: Some(8192),
         },
         ModelEntry {
             id: "llama-3.3-70b-versatile".to_string(),
@@ -60,6 +90,45 @@ pub fn get_default_models() -> Vec<ModelEntry> {
             rpd: None,
             tpd: None,
             modality: Some("llm".to_string()),
+            context_window: Some(131072),
+            max_output_tokens: Some(32768),
+        },
+        ModelEntry {
+            id: "llama3.2".to_string(),
+            name: "Llama 3.2 (Ollama)".to_string(),
+            provider_id: "ollama".to_string(),
+            // Local inference: no provider quota to respect
+            rpm: None,
+            tpm: None,
+            rpd: None,
+            tpd: None,
+            modality: Some("llm".to_string()),
+            context_window: Some(131072),
+            max_output_tokens: None,
+        },
+        ModelEntry {
+            id: "mistral".to_string(),
+            name: "Mistral 7B (Ollama)".to_string(),
+            provider_id: "ollama".to_string(),
+            rpm: None,
+            tpm: None,
+            rpd: None,
+            tpd: None,
+            modality: Some("llm".to_string()),
+            context_window: Some(32768),
+            max_output_tokens: None,
+        },
+        ModelEntry {
+            id: "qwen2.5".to_string(),
+            name: "Qwen 2.5 (Ollama)".to_string(),
+            provider_id: "ollama".to_string(),
+            rpm: None,
+            tpm: None,
+            rpd: None,
+            tpd: None,
+            modality: Some("llm".to_string()),
+            context_window: Some(32768),
+            max_output_tokens: None,
         },
     ]
 }
@@ -89,6 +158,8 @@ pub fn get_mock_registry() -> Vec<EngineAgent> {
             rpd: None,
             tpm: None,
             tpd: None,
+            max_history_turns: DEFAULT_MAX_HISTORY_TURNS,
+            safety_settings: None,
         },
         model_2: Some("gemini-pro-latest".to_string()),
         model_3: Some("llama-3.3-70b-versatile".to_string()),
@@ -105,6 +176,9 @@ pub fn get_mock_registry() -> Vec<EngineAgent> {
         theme_color: None,
         skills: vec!["issue_alpha_directive".to_string(), "propose_capability".to_string()],
         workflows: vec![],
+        skill_allowlist: None,
+        rpm_override: None,
+        tpm_override: None,
         model_config2: None,
         model_config3: None,
         active_model_slot: Some(2),
@@ -132,6 +206,8 @@ pub fn get_mock_registry() -> Vec<EngineAgent> {
             rpd: None,
             tpm: None,
             tpd: None,
+            max_history_turns: DEFAULT_MAX_HISTORY_TURNS,
+            safety_settings: None,
         },
         model_2: Some("gemini-pro-latest".to_string()),
         model_3: Some("llama-3.3-70b-versatile".to_string()),
@@ -148,6 +224,9 @@ pub fn get_mock_registry() -> Vec<EngineAgent> {
         theme_color: None,
         skills: vec![],
         workflows: vec![],
+        skill_allowlist: None,
+        rpm_override: None,
+        tpm_override: None,
         model_config2: None,
         model_config3: None,
         active_model_slot: Some(1),
@@ -175,6 +254,8 @@ pub fn get_mock_registry() -> Vec<EngineAgent> {
             rpd: None,
             tpm: None,
             tpd: None,
+            max_history_turns: DEFAULT_MAX_HISTORY_TURNS,
+            safety_settings: None,
         },
         model_2: Some("gemini-pro-latest".to_string()),
         model_3: Some("gemini-flash-latest".to_string()),
@@ -191,6 +272,9 @@ pub fn get_mock_registry() -> Vec<EngineAgent> {
         theme_color: None,
         skills: vec![],
         workflows: vec![],
+        skill_allowlist: None,
+        rpm_override: None,
+        tpm_override: None,
         model_config2: None,
         model_config3: None,
         active_model_slot: Some(3),
@@ -218,6 +302,8 @@ pub fn get_mock_registry() -> Vec<EngineAgent> {
             rpd: None,
             tpm: None,
             tpd: None,
+            max_history_turns: DEFAULT_MAX_HISTORY_TURNS,
+            safety_settings: None,
         },
         model_2: Some("gemini-pro-latest".to_string()),
         model_3: None,
@@ -230,6 +316,9 @@ pub fn get_mock_registry() -> Vec<EngineAgent> {
         theme_color: None,
         skills: vec!["query_financial_logs".to_string()],
         workflows: vec![],
+        skill_allowlist: None,
+        rpm_override: None,
+        tpm_override: None,
         model_config2: None,
         model_config3: None,
         active_model_slot: None,
@@ -257,6 +346,8 @@ pub fn get_mock_registry() -> Vec<EngineAgent> {
             rpd: None,
             tpm: None,
             tpd: None,
+            max_history_turns: DEFAULT_MAX_HISTORY_TURNS,
+            safety_settings: None,
         },
         model_2: None,
         model_3: None,
@@ -269,6 +360,9 @@ pub fn get_mock_registry() -> Vec<EngineAgent> {
         theme_color: None,
         skills: vec![],
         workflows: vec![],
+        skill_allowlist: None,
+        rpm_override: None,
+        tpm_override: None,
         model_config2: None,
         model_config3: None,
         active_model_slot: None,
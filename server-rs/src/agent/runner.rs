@@ -1,9 +1,229 @@
-use crate::agent::types::{TaskPayload, ModelConfig, TokenUsage};
+use crate::agent::types::{TaskPayload, ModelConfig, TokenUsage, ToolError};
 use crate::state::AppState;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::timeout;
+use tracing::Instrument;
 use crate::agent::hooks::HookContext;
+use crate::agent::sandbox::SandboxOutcome;
+
+/// Shared OS-level context injected into every system prompt, relative to the data directory.
+pub(crate) const IDENTITY_FILE: &str = "context/IDENTITY.md";
+pub(crate) const MEMORY_FILE: &str = "memory/LONG_TERM_MEMORY.md";
+const MAX_MEMORY_ENTRY_CHARS: usize = 2000;
+
+/// Maximum size of an incoming task message.
+pub const MAX_TASK_LENGTH: usize = 32768;
+/// Maximum recruitment depth before sub-agent spawning is refused.
+pub const MAX_SWARM_DEPTH: u32 = 5;
+
+/// Tool calls executed concurrently when MAX_PARALLEL_TOOL_CALLS is not set.
+pub const DEFAULT_MAX_PARALLEL_TOOL_CALLS: usize = 5;
+/// Test command used by `run_tests` when RUN_TESTS_COMMAND is not set.
+pub const DEFAULT_RUN_TESTS_COMMAND: &str = "cargo test --workspace --no-fail-fast";
+
+/// Hosts `git_clone` may clone from when `allowed_git_hosts` is not configured.
+pub const DEFAULT_ALLOWED_GIT_HOSTS: [&str; 2] = ["github.com", "gitlab.com"];
+
+/// Task timeout applied when the payload does not set `timeout_secs`.
+pub const DEFAULT_TASK_TIMEOUT_SECS: u64 = 300;
+
+/// Extra attempts granted to a tool call that failed with a network error or timeout.
+const MAX_TOOL_RETRIES: u32 = 1;
+/// Delay before the first tool retry; doubles with each further attempt.
+const TOOL_RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// Upper bound on `fetch_url_advanced`'s `timeout_secs`, also used when it is omitted.
+const MAX_FETCH_TIMEOUT_SECS: u64 = 30;
+
+/// Redirects `fetch_url_advanced` follows before giving up.
+const MAX_FETCH_REDIRECTS: usize = 5;
+
+/// True for loopback, private (10/8, 172.16/12, 192.168/16, fc00::/7), shared CGNAT (100.64/10),
+/// link-local (169.254/16, fe80::/10) and unspecified addresses, IPv4-mapped IPv6 included.
+fn is_internal_ip(ip: std::net::IpAddr) -> bool {
+    fn internal_v4(ip: std::net::Ipv4Addr) -> bool {
+        let [a, b, ..] = ip.octets();
+        let shared_cgnat = a == 100 && (b & 0xc0) == 64;
+        ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || shared_cgnat
+    }
+    match ip {
+        std::net::IpAddr::V4(ip) => internal_v4(ip),
+        std::net::IpAddr::V6(ip) => {
+            let unique_local = (ip.segments()[0] & 0xfe00) == 0xfc00;
+            let link_local = (ip.segments()[0] & 0xffc0) == 0xfe80;
+            ip.is_loopback() || ip.is_unspecified() || unique_local || link_local
+                || ip.to_ipv4_mapped().is_some_and(internal_v4)
+        }
+    }
+}
+
+/// Client for one `fetch_url_advanced` hop. It connects only to `addrs`, the addresses that
+/// passed the SSRF check, so a second DNS answer cannot swap in an internal one (DNS rebinding).
+/// Redirects are not followed here; the handler checks and pins each hop itself.
+fn pinned_fetch_client(url: &reqwest::Url, addrs: &[std::net::SocketAddr]) -> reqwest::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+    if let Some(domain) = url.domain() {
+        builder = builder.resolve_to_addrs(domain, addrs);
+    }
+    builder.build()
+}
+
+/// Swarm tools every agent may call without holding a skill for them.
+const CORE_TOOLS: [&str; 7] = ["spawn_subagent", "delegate_to_department", "share_finding", "verify_finding", "complete_mission", "propose_capability", "create_agent"];
+
+/// Mutation and execution tools a safe-mode run is never offered and may never call.
+const SAFE_MODE_BLACKLIST: [&str; 19] = ["issue_alpha_directive", "spawn_subagent", "execute_bash", "write_file", "delete_file", "append_file", "copy_file", "deploy", "git_clone", "update_memory", "notify_email", "fetch_url_advanced", "archive_to_s3", "create_agent", "run_tests", "format_code", "delegate_to_department", "json_writer", "api_designer"];
+
+/// Tools the runner handles itself rather than through a registry skill's command.
+const NATIVE_TOOLS: [&str; 35] = [
+    "spawn_subagent", "delegate_to_department", "issue_alpha_directive", "share_finding", "verify_finding",
+    "create_agent", "query_agent_logs", "query_financial_logs", "archive_to_vault", "archive_to_s3",
+    "execute_bash", "query_missions_db", "notify_discord", "notify_slack", "notify_email", "complete_mission",
+    "fetch_url", "fetch_url_advanced", "read_file", "write_file", "search_files", "append_file", "copy_file",
+    "write_json", "mock_http_response", "list_files", "stat_file", "delete_file", "propose_capability",
+    "run_tests", "format_code", "read_identity", "update_memory", "remember_fact", "recall_fact",
+];
+
+/// Whether `skill` is built into the engine, so it needs no definition in `data/skills`.
+pub fn is_native_skill(skill: &str) -> bool {
+    NATIVE_TOOLS.iter().any(|tool| granting_skill(tool) == skill)
+}
+
+/// The skill that grants a tool. Most tools are named after their skill.
+fn granting_skill(tool: &str) -> &str {
+    match tool {
+        "query_agent_logs" => "self_aware",
+        "write_json" => "json_writer",
+        "mock_http_response" => "api_designer",
+        other => other,
+    }
+}
+
+/// Why `tool` may not run in `ctx`, or `None` if it may. Declaring tools only steers the model;
+/// this is what stops an injected or hallucinated call to a tool the agent was never offered.
+fn tool_denial(ctx: &RunContext, tool: &str) -> Option<String> {
+    let skill = granting_skill(tool);
+    if ctx.safe_mode && (SAFE_MODE_BLACKLIST.contains(&tool) || SAFE_MODE_BLACKLIST.contains(&skill)) {
+        return Some(format!("'{}' is not available in safe mode", tool));
+    }
+    if CORE_TOOLS.contains(&tool) {
+        return None;
+    }
+    if !ctx.skills.iter().any(|s| s == skill) {
+        return Some(format!("'{}' is not one of this agent's skills", tool));
+    }
+    if ctx.skill_allowlist.as_ref().is_some_and(|allowlist| !allowlist.iter().any(|s| s == skill)) {
+        return Some(format!("'{}' is not in this agent's allowlist", tool));
+    }
+    None
+}
+
+/// The swarm role shown to an agent in its system prompt, by delegation depth.
+fn hierarchy_label(depth: u32) -> &'static str {
+    match depth {
+        0 => "OVERLORD (Strategic Intelligence Lead)",
+        1 => "ALPHA NODE (Swarm Mission Commander)",
+        2 => "CLUSTER ALPHA NODE (Department Coordinator)",
+        _ => "AGENT (Task Specialist)",
+    }
+}
+
+/// Share of the model's context window the system prompt may fill; the rest is left for
+/// history, the user message and the response.
+const PROMPT_CONTEXT_SHARE: f64 = 0.8;
+
+/// Marker left where a prompt section was shortened to fit the context window.
+const TRUNCATION_MARKER: &str = "[... truncated to fit the model's context window ...]\n";
+
+/// Workspace Anchoring: maps a task's `clusterId` to its directory under `./workspaces`,
+/// falling back to `executive-core`.
+pub fn workspace_root_for(cluster_id: Option<&str>) -> std::path::PathBuf {
+    let workspace_id = cluster_id.unwrap_or("executive-core");
+    // Sanitize the workspace ID to prevent any weird path escapes
+    let sanitized_id = workspace_id.replace("..", "").replace("/", "").replace("\\", "");
+    std::path::PathBuf::from("workspaces").join(sanitized_id)
+}
+
+/// A uniquely named workspace under `./workspaces` that is deleted on drop, so a failing test
+/// still cleans up after itself.
+#[cfg(test)]
+pub(crate) struct TestWorkspace {
+    pub cluster_id: String,
+    pub root: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl TestWorkspace {
+    pub(crate) fn new(prefix: &str) -> Self {
+        let cluster_id = format!("{}-{}", prefix, uuid::Uuid::new_v4().simple());
+        let root = workspace_root_for(Some(&cluster_id));
+        Self { cluster_id, root }
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestWorkspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Shrinks the long-term memory section, then the swarm context section, until the rendered
+/// prompt is estimated to fit within `PROMPT_CONTEXT_SHARE` of `context_window`.
+/// The most recent text (the end of each section) is kept.
+fn fit_prompt_sections(
+    ctx: &RunContext,
+    context_window: Option<u32>,
+    mut swarm_context: String,
+    mut memory: String,
+    render: impl Fn(&str, &str) -> String,
+) -> (String, String) {
+    let Some(window) = context_window else { return (swarm_context, memory) };
+    let budget = (window as f64 * PROMPT_CONTEXT_SHARE) as u32;
+    let estimate = |swarm_context: &str, memory: &str| crate::agent::rates::estimate_tokens(&render(swarm_context, memory));
+
+    let initial = estimate(&swarm_context, &memory);
+    if initial <= budget {
+        return (swarm_context, memory);
+    }
+    tracing::warn!(
+        "✂️ [Runner] System prompt for {} (~{} tokens) exceeds {}% of the {}-token context window; truncating memory and swarm context.",
+        ctx.agent_id, initial, (PROMPT_CONTEXT_SHARE * 100.0) as u32, window
+    );
+
+    let over = estimate(&swarm_context, &memory).saturating_sub(budget);
+    if over > 0 {
+        memory = keep_tail(&memory, over as usize * 4);
+    }
+    let over = estimate(&swarm_context, &memory).saturating_sub(budget);
+    if over > 0 {
+        swarm_context = keep_tail(&swarm_context, over as usize * 4);
+    }
+    (swarm_context, memory)
+}
+
+/// Drops at least `excess_chars` characters from the start of `text`, marking the cut.
+fn keep_tail(text: &str, excess_chars: usize) -> String {
+    let len = text.chars().count();
+    if len == 0 {
+        return String::new();
+    }
+    let keep = len.saturating_sub(excess_chars + TRUNCATION_MARKER.chars().count());
+    let tail: String = text.chars().skip(len - keep).collect();
+    format!("{}{}", TRUNCATION_MARKER, tail)
+}
+
+/// Resolves a task's timeout: the requested value (default 300s), capped by
+/// `AppState::max_task_timeout_secs`.
+pub fn task_timeout_secs(requested: Option<u64>, cap: u64) -> u64 {
+    requested.unwrap_or(DEFAULT_TASK_TIMEOUT_SECS).clamp(1, cap.max(1))
+}
+
+/// A fresh W3C-style trace ID (32 lowercase hex digits) for a root mission.
+fn new_trace_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
 
 /// Context bag for data resolved during the setup phase of a run.
 /// Avoids passing 10+ arguments between helpers.
@@ -16,6 +236,7 @@ struct RunContext {
     description: String,
     model_config: ModelConfig,
     skills: Vec<String>,
+    skill_allowlist: Option<Vec<String>>,
     workflows: Vec<String>,
     mission_id: String,
     depth: u32,
@@ -23,6 +244,14 @@ struct RunContext {
     provider_name: String,
     workspace_root: std::path::PathBuf,
     safe_mode: bool,
+    /// Earlier user/agent turns of this mission, oldest first. Excludes the current message.
+    history: Vec<crate::agent::types::ConversationTurn>,
+    /// Resolved task timeout; also bounds dynamic skill subprocesses.
+    task_timeout_secs: u64,
+    /// When the context was resolved; the start of the task for duration metrics.
+    started_at: std::time::Instant,
+    /// Trace shared by every span of this swarm; handed down to sub-agents.
+    trace_id: String,
 }
 
 #[derive(Clone)]
@@ -41,63 +270,153 @@ impl AgentRunner {
 
     /// The core execution loop for a mission.
     pub async fn run(&self, agent_id: String, payload: TaskPayload) -> anyhow::Result<String> {
-        // 0. Input Validation & Safety Checks
-        self.validate_input(&agent_id, &payload)?;
+        let mission_id = self.prepare_mission(&agent_id, &payload).await?;
+        self.run_mission(agent_id, payload, mission_id).await
+    }
 
-        let depth = payload.swarm_depth.unwrap_or(0);
-        let lineage = payload.swarm_lineage.clone().unwrap_or_default();
+    /// Validates the payload and persists a pending mission row for it. The mission becomes
+    /// active once its run holds a concurrency slot (see `run_mission`).
+    /// Split out of `run` so callers can learn the mission ID before execution starts.
+    pub async fn prepare_mission(&self, agent_id: &str, payload: &TaskPayload) -> anyhow::Result<String> {
+        // 0. Input Validation & Safety Checks
+        self.validate_input(agent_id, payload)?;
+
+        // 0.05 Dependency resolution: an unknown prerequisite is rejected before anything is recorded
+        let prerequisite = match &payload.depends_on {
+            Some(dep_id) => Some(
+                crate::agent::mission::get_mission_by_id(&self.state.pool, dep_id).await?
+                    .ok_or_else(|| anyhow::anyhow!("Dependency mission '{}' does not exist", dep_id))?
+            ),
+            None => None,
+        };
 
         // 0.1 Mission Initialization
         let mission_title = payload.message.chars().take(50).collect::<String>() + "...";
         
-        let agent_budget = self.state.agents.get(&agent_id)
+        let agent_budget = self.state.agents.get(agent_id)
             .map(|a| a.value().budget_usd)
             .unwrap_or(0.0);
             
         let mission_budget = payload.budget_usd
-            .unwrap_or_else(|| if agent_budget > 0.0 { agent_budget } else { 1.0 });
+            .unwrap_or(if agent_budget > 0.0 { agent_budget } else { 1.0 });
 
         let mission = crate::agent::mission::create_mission(
             &self.state.pool, 
-            &agent_id, 
+            agent_id, 
             &mission_title, 
-            mission_budget
+            mission_budget,
+            payload.tags.as_deref().unwrap_or_default()
         ).await?;
         let mission_id = mission.id;
-        
-        // Initial system check and mission activation
-        crate::agent::mission::update_mission(&self.state.pool, &mission_id, crate::agent::types::MissionStatus::Active, 0.0).await?;
-        
-        crate::agent::mission::log_step(
-            &self.state.pool,
+
+        if let Some(url) = &payload.callback_url {
+            crate::agent::mission::set_callback(&self.state.pool, &mission_id, url, payload.callback_secret.as_deref()).await?;
+        }
+
+        if let Some(prerequisite) = prerequisite {
+            crate::agent::mission::set_prerequisite(&self.state.pool, &mission_id, &prerequisite.id).await?;
+            if prerequisite.status != crate::agent::types::MissionStatus::Completed {
+                // Left pending so it is listed under `GET /missions?blocked=true`
+                crate::agent::mission::log_step_and_broadcast(&self.state, &mission_id, agent_id, "User", &payload.message, "info", None).await?;
+                let reason = format!(
+                    "Mission {} is blocked until dependency {} completes (currently {}).",
+                    mission_id, prerequisite.id, crate::agent::mission::status_to_str(&prerequisite.status)
+                );
+                crate::agent::mission::log_step_and_broadcast(&self.state, &mission_id, agent_id, "System", &reason, "warning", None).await?;
+                return Err(anyhow::anyhow!(reason));
+            }
+        }
+
+        crate::agent::mission::log_step_and_broadcast(
+            &self.state,
             &mission_id,
-            &agent_id,
+            agent_id,
             "User",
             &payload.message,
             "info",
             None
         ).await?;
 
+        Ok(mission_id)
+    }
+
+    /// Drafts a short profile description for an agent from its role and department.
+    /// A one-shot call on the agent's own model: no mission is created and nothing is logged.
+    pub async fn generate_agent_description(&self, agent_id: &str) -> anyhow::Result<String> {
+        let ctx = self.resolve_agent_context(agent_id, &TaskPayload::default(), "", 0, &[]).await?;
+        let prompt = format!(
+            "Write a 2-sentence professional description for an AI agent with role '{}' in department '{}'. Be specific about their capabilities.",
+            ctx.role, ctx.department
+        );
+
+        let (text, _, _) = self.call_provider_for_synthesis(&ctx, &prompt).await?;
+        let description = text.trim().to_string();
+        if description.is_empty() {
+            return Err(anyhow::anyhow!("Provider returned an empty description"));
+        }
+        Ok(description)
+    }
+
+    /// Runs a prepared mission under the payload's timeout (see `task_timeout_secs`).
+    /// On expiry the mission is failed, the agent returned to idle and `agent:timeout` emitted.
+    pub async fn run_mission_with_timeout(&self, agent_id: String, payload: TaskPayload, mission_id: String) -> anyhow::Result<String> {
+        let secs = task_timeout_secs(payload.timeout_secs, self.state.max_task_timeout_secs);
+        match timeout(Duration::from_secs(secs), self.run_mission(agent_id.clone(), payload, mission_id.clone())).await {
+            Ok(result) => result,
+            Err(_) => {
+                self.handle_task_timeout(&agent_id, &mission_id, secs).await?;
+                Err(anyhow::anyhow!("Task for agent {} timed out after {}s", agent_id, secs))
+            }
+        }
+    }
+
+    /// Executes a mission previously created by `prepare_mission` inside an `agent.run` span.
+    /// Sub-agents carry their parent's `trace_id`, so their span nests under the parent's tool call.
+    pub async fn run_mission(&self, agent_id: String, mut payload: TaskPayload, mission_id: String) -> anyhow::Result<String> {
+        let trace_id = payload.trace_id.get_or_insert_with(new_trace_id).clone();
+        let span = tracing::info_span!(
+            "agent.run",
+            agent.id = %agent_id,
+            mission.id = %mission_id,
+            trace_id = %trace_id,
+            swarm.depth = payload.swarm_depth.unwrap_or(0),
+        );
+        self.run_mission_in_span(agent_id, payload, mission_id).instrument(span).await
+    }
+
+    async fn run_mission_in_span(&self, agent_id: String, payload: TaskPayload, mission_id: String) -> anyhow::Result<String> {
+        // Held for the whole run, so shutdown can wait for in-flight agents to finish
+        let _active = self.state.track_active_agent();
+        let depth = payload.swarm_depth.unwrap_or(0);
+        let lineage = payload.swarm_lineage.clone().unwrap_or_default();
+
+        // 0.2 Concurrency Gate: only root missions take a slot. Sub-agents run inside their
+        // parent's slot — making them wait on the same semaphore could deadlock the swarm.
+        // The permit is held until this function returns, i.e. through `finalize_run`.
+        let _mission_slot = if depth == 0 {
+            Some(self.state.acquire_mission_slot().await)
+        } else {
+            None
+        };
+        // Until now a queued mission stays `pending`, so it never reports as running early
+        crate::agent::mission::update_mission_and_broadcast(&self.state, &mission_id, crate::agent::types::MissionStatus::Active, 0.0).await?;
+
         // 1. Resolve agent config and build context
-        let ctx = self.resolve_agent_context(&agent_id, &payload, &mission_id, depth, &lineage)?;
+        let ctx = self.resolve_agent_context(&agent_id, &payload, &mission_id, depth, &lineage).await?;
 
         tracing::info!("🏃 [Runner] Starting task for Agent {} (Model: {})", ctx.name, ctx.model_config.model_id);
         
-        let hierarchy_label = match depth {
-            0 => "OVERLORD (Strategic Intelligence Lead)",
-            1 => "ALPHA NODE (Swarm Mission Commander)",
-            2 => "CLUSTER ALPHA NODE (Department Coordinator)",
-            _ => "AGENT (Task Specialist)",
-        };
+        let hierarchy_label = hierarchy_label(depth);
 
         self.state.broadcast_sys(&format!("Agent {} starting task ({})...", ctx.name, hierarchy_label), "info");
 
-        // 1.1 Build system prompt
+        // 1.1 Build system prompt, condensing the swarm context first if it has outgrown its cap
+        let summary_usage = self.condense_mission_context(&ctx).await;
         let system_prompt = self.build_system_prompt(&ctx, hierarchy_label).await;
 
         self.broadcast_agent_status(&agent_id, "thinking");
-        crate::agent::mission::log_step(
-            &self.state.pool,
+        crate::agent::mission::log_step_and_broadcast(
+            &self.state,
             &mission_id,
             &agent_id,
             "System",
@@ -112,12 +431,16 @@ impl AgentRunner {
         let result = self.call_provider(&ctx, &system_prompt, &payload.message, Some(vec![swarm_tool])).await;
 
         let (mut output_text, function_calls, mut usage) = match result {
-            Ok(data) => data,
+            Ok(data) => {
+                self.state.metrics.provider_request_total.with_label_values(&[&ctx.provider_name, "success"]).inc();
+                data
+            }
             Err(e) => {
                 self.handle_provider_error(&ctx, &e).await?;
                 return Err(e);
             }
         };
+        self.accumulate_usage(&mut usage, summary_usage);
 
         // 3. Fiscal Governance: Cost Tracking & Budget Enforcement
         let step_cost = crate::agent::rates::calculate_cost(
@@ -132,33 +455,57 @@ impl AgentRunner {
 
         // 4. Handle Tool Loop (The "Intelligence" Layer)
         if !function_calls.is_empty() {
-            use futures::stream::{FuturesUnordered, StreamExt};
-            
+            if let Some(early_return) = self.execute_tool_calls(&ctx, function_calls, &payload.message, &mut output_text, &mut usage).await? {
+                return Ok(early_return);
+            }
+        }
+
+        // 5. Finalize
+        self.finalize_run(&ctx, &output_text, &usage).await
+    }
+
+    /// Runs the model's tool calls in batches of at most `state.max_parallel_tool_calls`.
+    /// Calls within a batch run concurrently; each later batch receives the output
+    /// accumulated so far as its parent context.
+    async fn execute_tool_calls(
+        &self,
+        ctx: &RunContext,
+        function_calls: Vec<crate::agent::types::GeminiFunctionCall>,
+        user_message: &str,
+        output_text: &mut String,
+        usage: &mut Option<TokenUsage>,
+    ) -> anyhow::Result<Option<String>> {
+        use futures::stream::{FuturesUnordered, StreamExt};
+
+        for (batch_index, batch) in function_calls.chunks(self.state.max_parallel_tool_calls).enumerate() {
+            let parent_context = if batch_index == 0 { String::new() } else { output_text.clone() };
+
             let mut futures = FuturesUnordered::new();
-            for fc in function_calls {
+            for fc in batch {
                 let runner = self.clone();
                 let ctx_clone = ctx.clone();
-                let user_msg = payload.message.clone();
-                
+                let user_msg = user_message.to_string();
+                let mut local_text = parent_context.clone();
+
                 futures.push(async move {
-                    let mut local_text = String::new();
                     let mut local_usage = None;
-                    let result = runner.execute_tool(&ctx_clone, &fc, &mut local_text, &mut local_usage, &user_msg).await;
+                    let result = runner.execute_tool(&ctx_clone, fc, &mut local_text, &mut local_usage, &user_msg).await;
                     (result, local_text, local_usage)
                 });
             }
 
             while let Some((result, local_text, local_usage)) = futures.next().await {
                 if let Some(early_return) = result? {
-                    return Ok(early_return);
+                    return Ok(Some(early_return));
                 }
-                output_text.push_str(&local_text);
-                self.accumulate_usage(&mut usage, local_usage);
+                // Handlers annotate by prefixing the parent context; keep only what this call added
+                let fresh = local_text.strip_suffix(parent_context.as_str()).unwrap_or(&local_text);
+                output_text.push_str(fresh);
+                self.accumulate_usage(usage, local_usage);
             }
         }
 
-        // 5. Finalize
-        self.finalize_run(&ctx, &output_text, &usage).await
+        Ok(None)
     }
 
     // ─────────────────────────────────────────────────────────
@@ -166,8 +513,7 @@ impl AgentRunner {
     // ─────────────────────────────────────────────────────────
 
     /// Validates input constraints before execution begins.
-    fn validate_input(&self, agent_id: &str, payload: &TaskPayload) -> anyhow::Result<()> {
-        const MAX_TASK_LENGTH: usize = 32768;
+    pub fn validate_input(&self, agent_id: &str, payload: &TaskPayload) -> anyhow::Result<()> {
         if payload.message.len() > MAX_TASK_LENGTH {
             return Err(anyhow::anyhow!("❌ Task message too long ({} bytes, max {})", payload.message.len(), MAX_TASK_LENGTH));
         }
@@ -181,20 +527,37 @@ impl AgentRunner {
             return Err(anyhow::anyhow!("🐝 CIRCULAR RECURSION DETECTED: Agent '{}' has already participated in this mission chain (Lineage: {} -> {}). Recruitment aborted.", agent_id, path, agent_id));
         }
 
-        const MAX_SWARM_DEPTH: u32 = 5;
         if depth >= MAX_SWARM_DEPTH {
             return Err(anyhow::anyhow!("🐝 Swarm depth limit exceeded (current depth: {})! To prevent infinite recursions, this agent cannot spawn more sub-agents.", depth));
         }
 
+        if let Some(url) = &payload.callback_url {
+            crate::adapter::webhook::validate_callback_url(url)?;
+        }
+
         Ok(())
     }
 
+    /// Resolves the model and assembles the system prompt `payload` would run with, without
+    /// creating a mission or touching agent state. Returns the resolved model config and the
+    /// estimated input tokens for the system prompt plus the user message.
+    pub async fn estimate_input(&self, agent_id: &str, payload: &TaskPayload) -> anyhow::Result<(ModelConfig, u32)> {
+        let depth = payload.swarm_depth.unwrap_or(0);
+        let lineage = payload.swarm_lineage.clone().unwrap_or_default();
+        let ctx = self.resolve_agent_context(agent_id, payload, "", depth, &lineage).await?;
+        let system_prompt = self.build_system_prompt(&ctx, hierarchy_label(depth)).await;
+
+        let input_tokens = crate::agent::rates::estimate_tokens(&system_prompt)
+            + crate::agent::rates::estimate_tokens(&payload.message);
+        Ok((ctx.model_config, input_tokens))
+    }
+
     // ─────────────────────────────────────────────────────────
     //  CONTEXT RESOLUTION
     // ─────────────────────────────────────────────────────────
 
     /// Resolves the full agent context from registries, applying payload overrides.
-    fn resolve_agent_context(
+    async fn resolve_agent_context(
         &self,
         agent_id: &str,
         payload: &TaskPayload,
@@ -202,13 +565,25 @@ impl AgentRunner {
         depth: u32,
         lineage: &[String],
     ) -> anyhow::Result<RunContext> {
+        // Loaded before the registry lookup so no DashMap guard is held across the query
+        let mut history = if mission_id.is_empty() {
+            Vec::new()
+        } else {
+            crate::agent::mission::get_conversation_history(&self.state.pool, mission_id).await?
+        };
+        // `prepare_mission` already logged the current message; it is sent separately
+        if history.last().is_some_and(|t| t.role == "user" && t.content == payload.message) {
+            history.pop();
+        }
+
         let entry = self.state.agents.get(agent_id)
             .ok_or_else(|| anyhow::anyhow!("Agent {} not found", agent_id))?;
         let a = entry.value();
+        let slot_config = a.active_model_config();
         
         let target_model_id = payload.model_id.clone()
-            .or_else(|| a.model_id.clone())
-            .unwrap_or_else(|| a.model.model_id.clone());
+            .or_else(|| a.active_model_id())
+            .unwrap_or_else(|| slot_config.model_id.clone());
         
         // CENTRAL REGISTRY PATH: Resolve full config from model + provider registries
         let mut resolved_config = if let Some(model_entry) = self.state.models.get(&target_model_id) {
@@ -223,14 +598,16 @@ impl AgentRunner {
                 model_id,
                 api_key: provider_config.api_key.clone(),
                 base_url: provider_config.base_url.clone(),
-                system_prompt: a.model.system_prompt.clone(),
-                temperature: a.model.temperature,
-                max_tokens: a.model.max_tokens,
+                system_prompt: slot_config.system_prompt.clone(),
+                temperature: slot_config.temperature,
+                max_tokens: slot_config.max_tokens,
                 external_id: provider_config.external_id.clone(),
                 rpm: model_entry.rpm,
                 rpd: model_entry.rpd,
                 tpm: model_entry.tpm,
                 tpd: model_entry.tpd,
+                max_history_turns: slot_config.max_history_turns,
+                safety_settings: slot_config.safety_settings.clone(),
             }
         } else if let Some(found_entry) = self.state.models.iter().find(|kv| kv.value().name.to_lowercase() == target_model_id.to_lowercase()) {
             // FUZZY RESOLUTION: ID might be a friendly name from the UI
@@ -246,18 +623,20 @@ impl AgentRunner {
                 model_id,
                 api_key: provider_config.api_key.clone(),
                 base_url: provider_config.base_url.clone(),
-                system_prompt: a.model.system_prompt.clone(),
-                temperature: a.model.temperature,
-                max_tokens: a.model.max_tokens,
+                system_prompt: slot_config.system_prompt.clone(),
+                temperature: slot_config.temperature,
+                max_tokens: slot_config.max_tokens,
                 external_id: provider_config.external_id.clone(),
                 rpm: m.rpm,
                 rpd: m.rpd,
                 tpm: m.tpm,
                 tpd: m.tpd,
+                max_history_turns: slot_config.max_history_turns,
+                safety_settings: slot_config.safety_settings.clone(),
             }
         } else {
             // FALLBACK: Use agent's internal model config
-            let mut cfg = a.model.clone();
+            let mut cfg = slot_config.clone();
             cfg.model_id = target_model_id;
             cfg
         };
@@ -269,16 +648,13 @@ impl AgentRunner {
         if let Some(eid) = &payload.external_id { resolved_config.external_id = Some(eid.clone()); }
         if let Some(m) = &payload.model_id { resolved_config.model_id = m.clone(); }
 
+        // Per-agent quotas take precedence over the model registry's
+        if let Some(rpm) = a.rpm_override { resolved_config.rpm = Some(rpm); }
+        if let Some(tpm) = a.tpm_override { resolved_config.tpm = Some(tpm); }
+
         let provider_name = resolved_config.provider.to_lowercase();
 
-        // Workspace Anchoring: Map clusterId to a physical path in ./workspaces
-        let workspace_id = payload.cluster_id.as_deref()
-            .unwrap_or("executive-core"); // Default fallback
-        
-        let mut workspace_root = std::path::PathBuf::from("workspaces");
-        // Sanitize the workspace ID to prevent any weird path escapes
-        let sanitized_id = workspace_id.replace("..", "").replace("/", "").replace("\\", "");
-        workspace_root.push(sanitized_id);
+        let workspace_root = workspace_root_for(payload.cluster_id.as_deref());
 
         let mut skills = a.skills.clone();
         let mut workflows = a.workflows.clone();
@@ -286,8 +662,7 @@ impl AgentRunner {
         let safe_mode = payload.safe_mode.unwrap_or(false);
         if safe_mode {
             // Strip mutation/execution tools
-            let blacklisted_skills = ["issue_alpha_directive", "spawn_subagent", "execute_bash", "write_file", "delete_file", "append_file", "deploy"];
-            skills.retain(|s| !blacklisted_skills.contains(&s.as_str()));
+            skills.retain(|s| !SAFE_MODE_BLACKLIST.contains(&s.as_str()));
             workflows.clear();
         }
 
@@ -299,6 +674,7 @@ impl AgentRunner {
             description: a.description.clone(),
             model_config: resolved_config,
             skills,
+            skill_allowlist: a.skill_allowlist.clone(),
             workflows,
             mission_id: mission_id.to_string(),
             depth,
@@ -306,6 +682,10 @@ impl AgentRunner {
             provider_name,
             workspace_root,
             safe_mode,
+            history,
+            task_timeout_secs: task_timeout_secs(payload.timeout_secs, self.state.max_task_timeout_secs),
+            started_at: std::time::Instant::now(),
+            trace_id: payload.trace_id.clone().unwrap_or_else(new_trace_id),
         })
     }
 
@@ -313,13 +693,63 @@ impl AgentRunner {
     //  SYSTEM PROMPT CONSTRUCTION
     // ─────────────────────────────────────────────────────────
 
+    /// Summarizes the mission's swarm context once it exceeds `max_context_chars`, so this and
+    /// later prompts carry the `[CONTEXT SUMMARY]` finding instead of every raw finding.
+    /// Returns the tokens the summary call used. On failure the raw context is kept and left to
+    /// `fit_prompt_sections`.
+    async fn condense_mission_context(&self, ctx: &RunContext) -> Option<TokenUsage> {
+        let context = crate::agent::mission::get_mission_context(&self.state.pool, &ctx.mission_id).await.ok()?;
+        let chars = context.chars().count();
+        if chars <= self.state.max_context_chars {
+            return None;
+        }
+
+        tracing::info!("🗜️ [Runner] Swarm context for mission {} is {} chars (cap {}); summarizing.", ctx.mission_id, chars, self.state.max_context_chars);
+        let mut summary_usage = None;
+        let summarized = crate::agent::mission::summarize_context(&self.state.pool, &ctx.mission_id, |context| {
+            let prompt = format!(
+                "The shared findings of a swarm mission have grown too long to pass to every agent. \
+                 Condense them into a summary of at most 500 words. Keep every concrete fact, decision, \
+                 open question and which agent reported it; drop repetition and chatter.\n\n\
+                 FINDINGS:\n{}",
+                context
+            );
+            let usage = &mut summary_usage;
+            async move {
+                let (text, _, call_usage) = self.call_provider_for_synthesis(ctx, &prompt).await?;
+                *usage = call_usage;
+                Ok(text)
+            }
+        }).await;
+
+        if let Err(e) = summarized {
+            tracing::warn!("⚠️ [Runner] Could not summarize swarm context for mission {}: {}", ctx.mission_id, e);
+        }
+        summary_usage
+    }
+
     async fn build_system_prompt(&self, ctx: &RunContext, hierarchy_label: &str) -> String {
         let swarm_context = crate::agent::mission::get_mission_context(&self.state.pool, &ctx.mission_id).await
             .unwrap_or_default();
 
-        let identity = tokio::fs::read_to_string("data/context/IDENTITY.md").await.unwrap_or_else(|_| "".to_string());
-        let memory = tokio::fs::read_to_string("data/memory/LONG_TERM_MEMORY.md").await.unwrap_or_else(|_| "".to_string());
+        // Cached at startup and refreshed via POST /system/reload-context — no per-mission disk I/O
+        let (identity, memory) = {
+            let system_context = self.state.system_context.read().await;
+            (system_context.identity.clone(), system_context.long_term_memory.clone())
+        };
+
+        let facts = crate::agent::memory::list_facts(&self.state.pool, &ctx.agent_id).await.unwrap_or_default();
+        let agent_memory = crate::agent::memory::format_facts(&facts);
+
+        let context_window = self.state.models.get(&ctx.model_config.model_id).and_then(|m| m.context_window);
+        let (swarm_context, memory) = fit_prompt_sections(ctx, context_window, swarm_context, memory, |swarm_context, memory| {
+            self.render_system_prompt(ctx, hierarchy_label, swarm_context, &identity, memory, &agent_memory)
+        });
+        self.render_system_prompt(ctx, hierarchy_label, &swarm_context, &identity, &memory, &agent_memory)
+    }
 
+    /// Renders the system prompt from already-resolved context sections.
+    fn render_system_prompt(&self, ctx: &RunContext, hierarchy_label: &str, swarm_context: &str, identity: &str, memory: &str, agent_memory: &str) -> String {
         let lineage_display = if ctx.lineage.is_empty() { "None (You are the root node)".to_string() } else { ctx.lineage.join(" -> ") };
 
         let mut forbidden = ctx.lineage.clone();
@@ -332,6 +762,13 @@ impl AgentRunner {
             ""
         };
 
+        // Facts this agent kept from earlier missions via `remember_fact`
+        let agent_memory_section = if agent_memory.is_empty() {
+            String::new()
+        } else {
+            format!("\n\n--- AGENT MEMORY ---\n{}", agent_memory)
+        };
+
         format!(
             "You are {} (ID: {}, Role: {}) at the {} level of the swarm hierarchy.\n\
              Department: {}\n\
@@ -352,9 +789,9 @@ impl AgentRunner {
              {}
 
              --- LONG-TERM SWARM MEMORY ---
-             {}{safe_mode_suffix}",
+             {}{agent_memory_section}{safe_mode_suffix}",
             ctx.name, ctx.agent_id, ctx.role, hierarchy_label, ctx.department, ctx.description,
-            if swarm_context.is_empty() { "No shared findings yet." } else { &swarm_context },
+            if swarm_context.is_empty() { "No shared findings yet." } else { swarm_context },
             lineage_display,
             ctx.skills, ctx.workflows,
             forbidden,
@@ -374,12 +811,24 @@ impl AgentRunner {
         use dashmap::DashMap;
 
         // Static cache for tool definitions, keyed by a combined hash of skills and safe_mode
-        static TOOL_CACHE: Lazy<DashMap<String, crate::agent::gemini::GeminiTool>> = Lazy::new(|| DashMap::new());
+        static TOOL_CACHE: Lazy<DashMap<String, crate::agent::gemini::GeminiTool>> = Lazy::new(DashMap::new);
+
+        // Per-agent allowlist: strictly narrows the assigned skills before anything is declared
+        let skills: Vec<String> = match &ctx.skill_allowlist {
+            Some(allowlist) => ctx.skills.iter().filter(|s| allowlist.contains(s)).cloned().collect(),
+            None => ctx.skills.clone(),
+        };
+        // Operator-disabled skills are dropped before the cache key so toggling never serves stale tools
+        let skills: Vec<String> = skills.into_iter()
+            .filter(|s| self.state.capabilities.skill(s).is_none_or(|def| def.enabled))
+            .collect();
 
         // Create a unique key for the current combination of skills and safety settings
-        let mut sorted_skills = ctx.skills.clone();
+        let mut sorted_skills = skills.clone();
         sorted_skills.sort();
-        let cache_key = format!("{}:{}", sorted_skills.join(","), ctx.safe_mode);
+        // Only the root Overlord (depth 0, holding `issue_alpha_directive`) may mint new agents
+        let can_create_agents = ctx.depth == 0 && !ctx.safe_mode && skills.iter().any(|s| s == "issue_alpha_directive");
+        let cache_key = format!("{}:{}:{}", sorted_skills.join(","), ctx.safe_mode, can_create_agents);
 
         if let Some(cached) = TOOL_CACHE.get(&cache_key) {
             return cached.value().clone();
@@ -403,6 +852,148 @@ impl AgentRunner {
             });
         }
 
+        if !ctx.safe_mode {
+            function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+                name: "delegate_to_department".to_string(),
+                description: "Delegates a sub-task to the best available agent in a department when you don't know a specific agent ID. Prefers idle, lowest-cost agents.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "department": { "type": "string", "description": "The department to delegate to (e.g., 'Engineering')." },
+                        "message": { "type": "string", "description": "The specific instruction or question for the selected agent." }
+                    },
+                    "required": ["department", "message"]
+                }),
+            });
+        }
+
+        if can_create_agents {
+            function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+                name: "create_agent".to_string(),
+                description: "Creates a brand-new specialist agent when no suitable one exists. The agent inherits your model and is immediately available to spawn_subagent. REQUIRES OVERSIGHT.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string", "description": "Unique ID for the new agent." },
+                        "name": { "type": "string", "description": "Display name." },
+                        "role": { "type": "string", "description": "The agent's role (e.g., 'Security Auditor')." },
+                        "department": { "type": "string", "description": "The department the agent belongs to." },
+                        "description": { "type": "string", "description": "What the agent specializes in." },
+                        "skills": { "type": "array", "items": { "type": "string" }, "description": "Skill names to grant the agent." }
+                    },
+                    "required": ["id", "name", "role", "department", "description", "skills"]
+                }),
+            });
+        }
+
+        if skills.iter().any(|s| s == "self_aware") {
+            function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+                name: "query_agent_logs".to_string(),
+                description: "Reviews your own last 5 missions and their step logs so you can learn from past outcomes.".to_string(),
+                parameters: serde_json::json!({ "type": "object", "properties": {} }),
+            });
+        }
+
+        if skills.iter().any(|s| s == "search_files") {
+            function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+                name: "search_files".to_string(),
+                description: "Searches workspace files under a directory for lines containing a string (or matching a regex). Returns up to 100 file/line hits.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "dir": { "type": "string", "description": "Relative directory to search. Use '.' for the whole workspace." },
+                        "pattern": { "type": "string", "description": "The text or regular expression to look for." },
+                        "use_regex": { "type": "boolean", "description": "Treat the pattern as a regular expression. Defaults to false." }
+                    },
+                    "required": ["pattern"]
+                }),
+            });
+        }
+
+        if skills.iter().any(|s| s == "stat_file") {
+            function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+                name: "stat_file".to_string(),
+                description: "Reports a workspace path's size in bytes, last modification time, and whether it is a file or directory, without reading it.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filename": { "type": "string", "description": "Relative path of the file or directory to inspect." }
+                    },
+                    "required": ["filename"]
+                }),
+            });
+        }
+
+        if !ctx.safe_mode && skills.iter().any(|s| s == "append_file") {
+            function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+                name: "append_file".to_string(),
+                description: "Appends content to the end of a workspace file without overwriting it. Creates the file if it does not exist.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filename": { "type": "string", "description": "Relative path of the file to append to." },
+                        "content": { "type": "string", "description": "The text to append." }
+                    },
+                    "required": ["filename", "content"]
+                }),
+            });
+        }
+
+        if !ctx.safe_mode && skills.iter().any(|s| s == "copy_file") {
+            function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+                name: "copy_file".to_string(),
+                description: "Copies a workspace file to a new path (e.g. a backup before editing), overwriting the destination if it exists.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "src": { "type": "string", "description": "Relative path of the file to copy." },
+                        "dst": { "type": "string", "description": "Relative destination path. Missing directories are created." }
+                    },
+                    "required": ["src", "dst"]
+                }),
+            });
+        }
+
+        if !ctx.safe_mode && skills.iter().any(|s| s == "json_writer") {
+            function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+                name: "write_json".to_string(),
+                description: "Writes structured JSON (configs, manifests) to the workspace. If a JSON Schema is given, the content is validated first and nothing is written when it fails.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filename": { "type": "string", "description": "Relative path of the JSON file to write." },
+                        "content": { "type": "object", "description": "The JSON value to write." },
+                        "schema": { "type": "object", "description": "Optional JSON Schema the content must satisfy." }
+                    },
+                    "required": ["filename", "content"]
+                }),
+            });
+        }
+
+        if !ctx.safe_mode && skills.iter().any(|s| s == "api_designer") {
+            function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+                name: "mock_http_response".to_string(),
+                description: "Records a mock HTTP response (no real request is made) to http_responses/ in the workspace so API designs can be inspected.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "status_code": { "type": "integer", "description": "HTTP status code (100-599)." },
+                        "headers": { "type": "object", "description": "Response headers as name/value pairs." },
+                        "body": { "type": "string", "description": "The response body." }
+                    },
+                    "required": ["status_code", "body"]
+                }),
+            });
+        }
+
+        if !ctx.safe_mode && skills.iter().any(|s| s == "run_tests") {
+            function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+                name: "run_tests".to_string(),
+                description: "Runs the workspace test suite (`cargo test`) and reports pass/fail/ignore counts with the output. REQUIRES OVERSIGHT.".to_string(),
+                parameters: serde_json::json!({ "type": "object", "properties": {} }),
+            });
+        }
+
         function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
             name: "share_finding".to_string(),
             description: "Shares a key finding, insight, or data point with the rest of the swarm.".to_string(),
@@ -410,12 +1001,26 @@ impl AgentRunner {
                 "type": "object",
                 "properties": {
                     "topic": { "type": "string", "description": "Short label (e.g., 'API Endpoint')." },
-                    "finding": { "type": "string", "description": "The detailed finding." }
+                    "finding": { "type": "string", "description": "The detailed finding." },
+                    "confidence": { "type": "number", "description": "How sure you are, from 0.0 (guess) to 1.0 (verified fact)." }
                 },
                 "required": ["topic", "finding"]
             }),
         });
 
+        function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
+            name: "verify_finding".to_string(),
+            description: "Confirms or disputes a finding another agent shared, by the id shown in the mission context. Disputed findings carry less weight for the rest of the swarm.".to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "finding_id": { "type": "string", "description": "The finding's id from the mission context." },
+                    "verdict": { "type": "string", "enum": ["confirmed", "disputed"] }
+                },
+                "required": ["finding_id", "verdict"]
+            }),
+        });
+
         function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
             name: "complete_mission".to_string(),
             description: "Signals that the mission objective has been achieved. Provide a final comprehensive report. REQUIRES OVERSIGHT.".to_string(),
@@ -446,8 +1051,8 @@ impl AgentRunner {
         });
 
         // Dynamic Skills: All skills are now resolving natively from the capabilities registry.
-        for skill in &ctx.skills {
-            if let Some(dynamic_skill) = self.state.capabilities.skills.get(skill) {
+        for skill in &skills {
+            if let Some(dynamic_skill) = self.state.capabilities.skill(skill) {
                 function_declarations.push(crate::agent::gemini::GeminiFunctionDeclaration {
                     name: dynamic_skill.name.clone(),
                     description: dynamic_skill.description.clone(),
@@ -483,6 +1088,7 @@ impl AgentRunner {
 
     /// Routes the generation request to the correct LLM provider using the shared HTTP client.
     /// Enforces RPM/TPM rate limits when configured on the model.
+    #[tracing::instrument(name = "call_provider", skip_all, fields(agent.id = %ctx.agent_id, model.id = %ctx.model_config.model_id))]
     async fn call_provider(
         &self,
         ctx: &RunContext,
@@ -490,20 +1096,30 @@ impl AgentRunner {
         user_message: &str,
         tools: Option<Vec<crate::agent::gemini::GeminiTool>>,
     ) -> anyhow::Result<(String, Vec<crate::agent::types::GeminiFunctionCall>, Option<crate::agent::types::TokenUsage>)> {
-        let client = (*self.state.http_client).clone();
+        // A provider that failed its last health check would only time out or reject the call
+        if let Some(health) = self.state.provider_health.get(crate::agent::provider_health::health_key(&ctx.provider_name)).filter(|h| !h.is_healthy) {
+            return Err(anyhow::anyhow!(
+                "Provider '{}' is unhealthy ({}, checked {}s ago); the call was not attempted",
+                ctx.provider_name,
+                health.error.as_deref().unwrap_or("unknown error"),
+                health.last_checked.elapsed().as_secs()
+            ));
+        }
 
-        // PERF-05 FIX: Enforce RPM and TPM limits from model configuration.
-        // Blocks the current task if we're over-quota; does not block other agents.
-        let limiter = crate::agent::rate_limiter::RateLimiter::new(
-            ctx.model_config.rpm,
-            ctx.model_config.tpm,
-        );
+        let client = (*self.state.provider_client(&ctx.provider_name)).clone();
+
+        // PERF-05 FIX: Enforce RPM/TPM and RPD/TPD limits from model configuration.
+        // The limiter is shared per model, so every agent calling it draws from the same quota.
+        let limiter = self.state.agent_rate_limiter(&ctx.agent_id, &ctx.model_config);
         if limiter.is_active() {
             // Estimate ~512 tokens for the request; we'll record actuals after.
             let estimated_tokens = 512u32;
             limiter.acquire(estimated_tokens).await;
         }
 
+        // Stream only when a WebSocket client is listening; otherwise keep the single-shot path.
+        let streaming = self.state.event_tx.receiver_count() > 0;
+
         let result = match ctx.provider_name.as_str() {
             "google" | "gemini" => {
                 tracing::info!("📡 [Runner] Calling Gemini API for agent {}...", ctx.agent_id);
@@ -511,10 +1127,14 @@ impl AgentRunner {
                     .or_else(|| std::env::var("GOOGLE_API_KEY").ok())
                     .ok_or_else(|| anyhow::anyhow!("Missing GOOGLE_API_KEY"))?;
                 let provider = crate::agent::gemini::GeminiProvider::new(client, api_key, ctx.model_config.clone());
-                provider.generate(
-                    &format!("{}\n\nUSER MESSAGE:\n{}", system_prompt, user_message),
-                    tools
-                ).await
+                match if streaming { Some(provider.generate_streaming(system_prompt, user_message, Some(&ctx.history), tools.clone()).await) } else { None } {
+                    Some(Ok(stream)) => self.forward_stream(ctx, stream).await,
+                    Some(Err(e)) => {
+                        tracing::warn!("⚠️ [Runner] Gemini stream failed to start, retrying without streaming: {}", e);
+                        provider.generate(system_prompt, user_message, Some(&ctx.history), tools).await
+                    }
+                    None => provider.generate(system_prompt, user_message, Some(&ctx.history), tools).await,
+                }
             }
             "groq" => {
                 tracing::info!("📡 [Runner] Calling Groq API for agent {}...", ctx.agent_id);
@@ -522,7 +1142,33 @@ impl AgentRunner {
                     .or_else(|| std::env::var("GROQ_API_KEY").ok())
                     .ok_or_else(|| anyhow::anyhow!("Missing GROQ_API_KEY"))?;
                 let provider = crate::agent::groq::GroqProvider::new(client, api_key, ctx.model_config.clone());
-                provider.generate(system_prompt, user_message, tools).await
+                match if streaming { Some(provider.generate_streaming(system_prompt, user_message, Some(&ctx.history), tools.clone()).await) } else { None } {
+                    Some(Ok(stream)) => self.forward_stream(ctx, stream).await.map(|(text, mut calls, usage)| {
+                        // Llama-style tags only become parseable once the full text is assembled
+                        if calls.is_empty() {
+                            calls.extend(crate::agent::groq::recover_function_call(&text));
+                        }
+                        (text, calls, usage)
+                    }),
+                    Some(Err(e)) => {
+                        tracing::warn!("⚠️ [Runner] Groq stream failed to start, retrying without streaming: {}", e);
+                        provider.generate(system_prompt, user_message, Some(&ctx.history), tools).await
+                    }
+                    None => provider.generate(system_prompt, user_message, Some(&ctx.history), tools).await,
+                }
+            }
+            "openai" => {
+                tracing::info!("📡 [Runner] Calling OpenAI-compatible API for agent {}...", ctx.agent_id);
+                let api_key = ctx.model_config.api_key.clone()
+                    .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+                    .ok_or_else(|| anyhow::anyhow!("Missing OPENAI_API_KEY"))?;
+                let provider = crate::agent::openai::OpenAIProvider::new(client, api_key, ctx.model_config.clone());
+                provider.generate(system_prompt, user_message, Some(&ctx.history), tools).await
+            }
+            "ollama" => {
+                tracing::info!("📡 [Runner] Calling Ollama for agent {}...", ctx.agent_id);
+                let provider = crate::agent::ollama::OllamaProvider::new(client, ctx.model_config.clone());
+                provider.generate(system_prompt, user_message, Some(&ctx.history), tools).await
             }
             _ => {
                 let err = format!("❌ Unsupported provider: {}", ctx.provider_name);
@@ -542,19 +1188,40 @@ impl AgentRunner {
         result
     }
 
+    /// Emits each text delta as an `agent:delta` event and returns the assembled completion.
+    async fn forward_stream(
+        &self,
+        ctx: &RunContext,
+        mut stream: crate::agent::streaming::StreamingResponse,
+    ) -> anyhow::Result<(String, Vec<crate::agent::types::GeminiFunctionCall>, Option<crate::agent::types::TokenUsage>)> {
+        use futures::StreamExt;
+        let message_id = uuid::Uuid::new_v4().to_string();
+        let mut output_text = String::new();
+
+        while let Some(delta) = stream.deltas.next().await {
+            self.state.emit_event(serde_json::json!({
+                "type": "agent:delta",
+                "agentId": ctx.agent_id,
+                "delta": delta,
+                "messageId": message_id
+            }));
+            output_text.push_str(&delta);
+        }
+
+        let (function_calls, usage) = stream.finish().await?;
+        Ok((output_text, function_calls, usage))
+    }
+
     /// Calls the provider for a synthesis/follow-up step (no tool definitions).
     async fn call_provider_for_synthesis(
         &self,
         ctx: &RunContext,
         prompt: &str,
     ) -> anyhow::Result<(String, Vec<crate::agent::types::GeminiFunctionCall>, Option<crate::agent::types::TokenUsage>)> {
-        let client = (*self.state.http_client).clone();
+        let client = (*self.state.provider_client(&ctx.provider_name)).clone();
 
         // PERF-05: Enforce rate limits on synthesis calls too — same path as call_provider.
-        let limiter = crate::agent::rate_limiter::RateLimiter::new(
-            ctx.model_config.rpm,
-            ctx.model_config.tpm,
-        );
+        let limiter = self.state.agent_rate_limiter(&ctx.agent_id, &ctx.model_config);
         if limiter.is_active() {
             limiter.acquire(256).await;
         }
@@ -566,7 +1233,7 @@ impl AgentRunner {
                     .ok_or_else(|| anyhow::anyhow!("Missing GOOGLE_API_KEY"))?;
                 let provider = crate::agent::gemini::GeminiProvider::new(client, api_key, ctx.model_config.clone());
                 let synthesis_prompt = format!("{}\n\nCRITICAL INSTRUCTION: You MUST provide a clear, textual, conversational response to this synthesis request. Do NOT output a blank response.", prompt);
-                let (txt, fcs, use_stat) = provider.generate(&synthesis_prompt, None).await?;
+                let (txt, fcs, use_stat) = provider.generate("", &synthesis_prompt, None, None).await?;
                 Ok((txt, fcs, use_stat))
             }
             "groq" => {
@@ -575,15 +1242,30 @@ impl AgentRunner {
                     .ok_or_else(|| anyhow::anyhow!("Missing GROQ_API_KEY"))?;
                 let provider = crate::agent::groq::GroqProvider::new(client, api_key, ctx.model_config.clone());
                 let synthesis_prompt = format!("{}\n\nCRITICAL INSTRUCTION: You MUST provide a clear, textual, conversational response to this synthesis request. Do NOT output a blank response.", prompt);
-                let (txt, fcs, use_stat) = provider.generate("", &synthesis_prompt, None).await?;
+                let (txt, fcs, use_stat) = provider.generate("", &synthesis_prompt, None, None).await?;
                 Ok((txt, fcs, use_stat))
             }
-            _ => Ok((prompt.to_string(), Vec::new(), None)),
-        };
-
-        // Record actual usage against the limiter window
-        if limiter.is_active() {
-            if let Ok((_, _, Some(ref usage))) = &result {
+            "openai" => {
+                let api_key = ctx.model_config.api_key.clone()
+                    .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+                    .ok_or_else(|| anyhow::anyhow!("Missing OPENAI_API_KEY"))?;
+                let provider = crate::agent::openai::OpenAIProvider::new(client, api_key, ctx.model_config.clone());
+                let synthesis_prompt = format!("{}\n\nCRITICAL INSTRUCTION: You MUST provide a clear, textual, conversational response to this synthesis request. Do NOT output a blank response.", prompt);
+                let (txt, fcs, use_stat) = provider.generate("", &synthesis_prompt, None, None).await?;
+                Ok((txt, fcs, use_stat))
+            }
+            "ollama" => {
+                let provider = crate::agent::ollama::OllamaProvider::new(client, ctx.model_config.clone());
+                let synthesis_prompt = format!("{}\n\nCRITICAL INSTRUCTION: You MUST provide a clear, textual, conversational response to this synthesis request. Do NOT output a blank response.", prompt);
+                let (txt, fcs, use_stat) = provider.generate("", &synthesis_prompt, None, None).await?;
+                Ok((txt, fcs, use_stat))
+            }
+            _ => Err(anyhow::anyhow!("Unsupported provider: {}", ctx.provider_name)),
+        };
+
+        // Record actual usage against the limiter window
+        if limiter.is_active() {
+            if let Ok((_, _, Some(ref usage))) = &result {
                 limiter.record_usage(usage.total_tokens);
             }
         }
@@ -606,9 +1288,13 @@ impl AgentRunner {
             entry.value_mut().status = "idle".to_string();
         }
         
-        crate::agent::mission::update_mission(&self.state.pool, &ctx.mission_id, crate::agent::types::MissionStatus::Failed, 0.0).await?;
-        crate::agent::mission::log_step(
-            &self.state.pool,
+        crate::agent::mission::update_mission_and_broadcast(&self.state, &ctx.mission_id, crate::agent::types::MissionStatus::Failed, 0.0).await?;
+        self.state.missions_failed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.state.metrics.provider_request_total.with_label_values(&[&ctx.provider_name, "error"]).inc();
+        self.state.metrics.agent_task_total.with_label_values(&[&ctx.agent_id, "failed"]).inc();
+        self.state.metrics.agent_task_duration_seconds.with_label_values(&[&ctx.agent_id]).observe(ctx.started_at.elapsed().as_secs_f64());
+        crate::agent::mission::log_step_and_broadcast(
+            &self.state,
             &ctx.mission_id,
             &ctx.agent_id,
             "System",
@@ -616,10 +1302,85 @@ impl AgentRunner {
             "error",
             None
         ).await?;
+        self.notify_callback(&ctx.mission_id, crate::agent::types::MissionStatus::Failed, &format!("Error: {}", e)).await;
+
+        Ok(())
+    }
+
+    /// Returns an agent still marked `thinking` to `idle`. For dispatchers that claim an agent
+    /// before its mission starts, when the mission fails to start or its run stops early.
+    pub fn release_claim(&self, agent_id: &str) {
+        let released = self.state.agents.get_mut(agent_id)
+            .filter(|entry| entry.status == "thinking")
+            .map(|mut entry| entry.value_mut().status = "idle".to_string())
+            .is_some();
+        if released {
+            self.broadcast_agent_status(agent_id, "idle");
+        }
+    }
+
+    /// Cleans up after `run_mission_with_timeout` abandoned a mission.
+    async fn handle_task_timeout(&self, agent_id: &str, mission_id: &str, secs: u64) -> anyhow::Result<()> {
+        tracing::warn!("⏱️ [Runner] Agent {} exceeded its {}s task timeout (mission {})", agent_id, secs, mission_id);
+        self.broadcast_agent_status(agent_id, "idle");
+
+        if let Some(mut entry) = self.state.agents.get_mut(agent_id) {
+            entry.value_mut().status = "idle".to_string();
+        }
+
+        self.state.emit_event(serde_json::json!({
+            "type": "agent:timeout",
+            "agentId": agent_id,
+            "missionId": mission_id,
+            "timeoutSecs": secs
+        }));
+
+        crate::agent::mission::update_mission_and_broadcast(&self.state, mission_id, crate::agent::types::MissionStatus::Failed, 0.0).await?;
+        self.state.missions_failed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        crate::agent::mission::log_step_and_broadcast(
+            &self.state,
+            mission_id,
+            agent_id,
+            "System",
+            &format!("⏱️ Task timed out after {}s", secs),
+            "error",
+            None
+        ).await?;
+        self.notify_callback(mission_id, crate::agent::types::MissionStatus::Failed, &format!("Task timed out after {}s", secs)).await;
 
         Ok(())
     }
 
+    /// POSTs a finished mission's outcome to its `callback_url`, if it has one. Delivery runs
+    /// in the background so a slow receiver cannot hold up finalization; failures are logged.
+    async fn notify_callback(&self, mission_id: &str, status: crate::agent::types::MissionStatus, output: &str) {
+        let (url, secret) = match crate::agent::mission::get_callback(&self.state.pool, mission_id).await {
+            Ok(Some(callback)) => callback,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("⚠️ [Callback] Could not read callback for mission {}: {}", mission_id, e);
+                return;
+            }
+        };
+        let cost_usd = crate::agent::mission::get_mission_by_id(&self.state.pool, mission_id).await
+            .ok()
+            .flatten()
+            .map(|m| m.cost_usd)
+            .unwrap_or(0.0);
+        let payload = crate::adapter::webhook::MissionCallbackPayload::new(
+            mission_id, crate::agent::mission::status_to_str(&status), cost_usd, output,
+        );
+
+        let client = (*self.state.http_client).clone();
+        tokio::spawn(async move {
+            match crate::adapter::webhook::deliver(&client, &url, secret.as_deref(), &payload).await {
+                Ok(code) if code.is_success() => tracing::info!("📬 [Callback] Mission {} outcome delivered to {}", payload.mission_id, url),
+                Ok(code) => tracing::warn!("⚠️ [Callback] {} answered {} for mission {}", url, code, payload.mission_id),
+                Err(e) => tracing::warn!("⚠️ [Callback] Delivery to {} failed for mission {}: {}", url, payload.mission_id, e),
+            }
+        });
+    }
+
     // ─────────────────────────────────────────────────────────
     //  BUDGET ENFORCEMENT
     // ─────────────────────────────────────────────────────────
@@ -632,9 +1393,9 @@ impl AgentRunner {
                 
                 self.state.broadcast_sys(&format!("⚠️ PROTOCOL ALERT: Mission {} exceeded budget (${:.4}). Swarm auto-paused.", mission.title, mission.budget_usd), "warning");
                 
-                crate::agent::mission::update_mission(&self.state.pool, &ctx.mission_id, crate::agent::types::MissionStatus::Paused, 0.0).await?;
-                crate::agent::mission::log_step(
-                    &self.state.pool,
+                crate::agent::mission::update_mission_and_broadcast(&self.state, &ctx.mission_id, crate::agent::types::MissionStatus::Paused, 0.0).await?;
+                crate::agent::mission::log_step_and_broadcast(
+                    &self.state,
                     &ctx.mission_id,
                     &ctx.agent_id,
                     "Finance Analyst",
@@ -655,6 +1416,7 @@ impl AgentRunner {
     // ─────────────────────────────────────────────────────────
 
     /// Dispatches a function call to the appropriate tool handler.
+    #[tracing::instrument(name = "execute_tool", skip_all, fields(agent.id = %ctx.agent_id, tool.name = %fc.name))]
     async fn execute_tool(
         &self,
         ctx: &RunContext,
@@ -669,77 +1431,103 @@ impl AgentRunner {
             skill: fc.name.clone(),
         };
 
+        // 🛡️ [Guardrail] Tools the agent was not given are refused even if the model calls them
+        if let Some(reason) = tool_denial(ctx, &fc.name) {
+            tracing::warn!("🚫 [Runner] Refused {} for agent {}: {}", fc.name, ctx.agent_id, reason);
+            *output_text = format!("(SKILL DENIED: {}) {}", reason, output_text);
+            return Ok(None);
+        }
+
         // 🛡️ [Guardrail] Pre-tool Lifecycle Hook
         self.state.hooks.trigger_hook("pre-tool", &hook_ctx, &fc.args).await?;
 
-        let result: anyhow::Result<Option<String>> = match fc.name.as_str() {
-            "spawn_subagent" => {
-                self.handle_spawn_subagent(ctx, fc, output_text, usage).await?;
-                Ok(None)
-            }
-            "issue_alpha_directive" => {
-                let result = self.handle_alpha_directive(ctx, fc).await?;
-                Ok(Some(result))
-            }
-            "share_finding" => {
-                self.handle_share_finding(ctx, fc, output_text).await?;
-                Ok(None)
-            }
-            "query_financial_logs" => {
-                self.handle_query_financial_logs(ctx, fc, output_text, usage).await?;
-                Ok(None)
-            }
-            "archive_to_vault" => {
-                self.handle_archive_to_vault(ctx, fc, output_text).await?;
-                Ok(None)
-            }
-            "notify_discord" => {
-                self.handle_notify_discord(ctx, fc, output_text).await?;
-                Ok(None)
-            }
-            "complete_mission" => {
-                self.handle_complete_mission(ctx, fc, output_text).await?;
-                Ok(None)
-            }
-            "fetch_url" => {
-                self.handle_fetch_url(ctx, fc, output_text, usage).await?;
-                Ok(None)
-            }
-            "read_file" => {
-                self.handle_read_file(ctx, fc, output_text, usage).await?;
-                Ok(None)
-            }
-            "write_file" => {
-                self.handle_write_file(ctx, fc, output_text).await?;
-                Ok(None)
-            }
-            "list_files" => {
-                self.handle_list_files(ctx, fc, output_text, usage).await?;
-                Ok(None)
-            }
-            "delete_file" => {
-                self.handle_delete_file(ctx, fc, output_text).await?;
-                Ok(None)
-            }
-            "propose_capability" => {
-                self.handle_propose_capability(ctx, fc, output_text).await?;
-                Ok(None)
+        let mut attempt = 0;
+        let result = loop {
+            // Each attempt starts from the same output/usage so a failed try leaves no trace
+            let output_before = output_text.clone();
+            let usage_before = usage.clone();
+            match self.dispatch_tool(ctx, fc, output_text, usage).await {
+                Err(e) if e.is_retryable() && attempt < MAX_TOOL_RETRIES => {
+                    let delay = Duration::from_millis(TOOL_RETRY_BASE_DELAY_MS << attempt);
+                    tracing::warn!("🔁 [Runner] Tool {} failed for agent {} ({}); retrying in {:?}", fc.name, ctx.agent_id, e, delay);
+                    *output_text = output_before;
+                    *usage = usage_before;
+                    attempt += 1;
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) if e.is_fatal() => {
+                    tracing::error!("🚫 [Runner] Tool {} aborted the run for agent {}: {}", fc.name, ctx.agent_id, e);
+                    return Err(e.into());
+                }
+                Err(e) => {
+                    tracing::warn!("⚠️ [Runner] Tool {} failed for agent {}: {}", fc.name, ctx.agent_id, e);
+                    *output_text = format!("(TOOL ERROR in {}: {}) {}", fc.name, e, output_before);
+                    *usage = usage_before;
+                    break None;
+                }
+                Ok(result) => break result,
             }
+        };
+
+        // 📝 [Audit] Post-tool Lifecycle Hook
+        self.state.hooks.trigger_hook("post-tool", &hook_ctx, &fc.args).await?;
+
+        Ok(result)
+    }
+
+    /// Routes a function call to its handler. Failures are typed so `execute_tool` can pick a recovery policy.
+    async fn dispatch_tool(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        match fc.name.as_str() {
+            "spawn_subagent" => self.handle_spawn_subagent(ctx, fc, output_text, usage).await,
+            "delegate_to_department" => self.handle_delegate_to_department(ctx, fc, output_text, usage).await,
+            "issue_alpha_directive" => self.handle_alpha_directive(ctx, fc).await,
+            "share_finding" => self.handle_share_finding(ctx, fc, output_text).await,
+            "verify_finding" => self.handle_verify_finding(ctx, fc, output_text).await,
+            "create_agent" => self.handle_create_agent(ctx, fc, output_text).await,
+            "query_agent_logs" => self.handle_query_agent_logs(ctx, output_text, usage).await,
+            "query_financial_logs" => self.handle_query_financial_logs(ctx, fc, output_text, usage).await,
+            "archive_to_vault" => self.handle_archive_to_vault(ctx, fc, output_text).await,
+            "archive_to_s3" => self.handle_archive_to_s3(ctx, fc, output_text).await,
+            "execute_bash" => self.handle_execute_bash(ctx, fc, output_text, usage).await,
+            "query_missions_db" => self.handle_query_missions_db(ctx, fc, output_text, usage).await,
+            "notify_discord" => self.handle_notify_discord(ctx, fc, output_text).await,
+            "notify_slack" => self.handle_notify_slack(ctx, fc, output_text).await,
+            "notify_email" => self.handle_notify_email(ctx, fc, output_text).await,
+            "complete_mission" => self.handle_complete_mission(ctx, fc, output_text).await,
+            "fetch_url" => self.handle_fetch_url(ctx, fc, output_text, usage).await,
+            "fetch_url_advanced" => self.handle_fetch_url_advanced(ctx, fc, output_text, usage).await,
+            "read_file" => self.handle_read_file(ctx, fc, output_text, usage).await,
+            "write_file" => self.handle_write_file(ctx, fc, output_text).await,
+            "search_files" => self.handle_search_files(ctx, fc, output_text, usage).await,
+            "append_file" => self.handle_append_file(ctx, fc, output_text).await,
+            "copy_file" => self.handle_copy_file(ctx, fc, output_text).await,
+            "write_json" => self.handle_write_json(ctx, fc, output_text).await,
+            "mock_http_response" => self.handle_mock_http_response(ctx, fc, output_text).await,
+            "list_files" => self.handle_list_files(ctx, fc, output_text, usage).await,
+            "stat_file" => self.handle_stat_file(ctx, fc, output_text, usage).await,
+            "delete_file" => self.handle_delete_file(ctx, fc, output_text).await,
+            "propose_capability" => self.handle_propose_capability(ctx, fc, output_text).await,
+            "run_tests" => self.handle_run_tests(ctx, fc, output_text, usage).await,
+            "format_code" => self.handle_format_code(ctx, fc, output_text).await,
+            "read_identity" => self.handle_read_identity(ctx, output_text, usage).await,
+            "update_memory" => self.handle_update_memory(ctx, fc, output_text).await,
+            "remember_fact" => self.handle_remember_fact(ctx, fc, output_text).await,
+            "recall_fact" => self.handle_recall_fact(ctx, fc, output_text, usage).await,
             _ => {
                 // Check Dynamic Registry
-                if let Some(dynamic_skill) = self.state.capabilities.skills.get(&fc.name) {
-                    self.handle_dynamic_skill(ctx, fc, output_text, &dynamic_skill, usage).await?;
-                    Ok(None)
+                if let Some(dynamic_skill) = self.state.capabilities.skill(&fc.name) {
+                    self.handle_dynamic_skill(ctx, fc, output_text, &dynamic_skill, usage).await
                 } else {
                     Ok(None)
                 }
             },
-        };
-
-        // 📝 [Audit] Post-tool Lifecycle Hook
-        self.state.hooks.trigger_hook("post-tool", &hook_ctx, &fc.args).await?;
-
-        Ok(result?)
+        }
     }
 
     /// Handles execution of dynamic file-based skills via subprocess.
@@ -750,19 +1538,67 @@ impl AgentRunner {
         output_text: &mut String,
         skill: &crate::agent::capabilities::SkillDefinition,
         usage: &mut Option<crate::agent::types::TokenUsage>,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
+        // The skill may have been disabled after the tool list was built
+        if !self.state.capabilities.skill(&skill.name).is_some_and(|current| current.enabled) {
+            tracing::warn!("🚫 [Dynamic Skill] Agent {} called disabled skill {}", ctx.agent_id, skill.name);
+            *output_text = format!("(SKILL EXEC BLOCKED: '{}' is disabled) {}", skill.name, output_text);
+            return Ok(None);
+        }
+
         let args_json = serde_json::to_string(&fc.args).unwrap_or_else(|_| "{}".to_string());
         tracing::info!("⚙️ [Dynamic Skill] Agent {} executing {} with args {}", ctx.agent_id, skill.name, args_json);
         self.state.broadcast_sys(&format!("⚙️ Skill Exec: {} is running {}", ctx.name, skill.name), "info");
 
+        // 🛡️ Git skills: validate target dirs, and gate clones behind host allow-listing + Oversight
+        if skill.name == "git_clone" || skill.name == "git_status" {
+            let dir = fc.args.get("dir").and_then(|v| v.as_str()).unwrap_or("");
+            if let Err(e) = Self::validate_workspace_relative_dir(dir) {
+                *output_text = format!("(SKILL EXEC BLOCKED: {}) {}", e, output_text);
+                return Ok(None);
+            }
+        }
+        if skill.name == "git_clone" {
+            let url = fc.args.get("url").and_then(|v| v.as_str()).unwrap_or("");
+            if let Err(e) = Self::validate_git_clone_url(url, &self.state.allowed_git_hosts) {
+                *output_text = format!("(GIT CLONE BLOCKED: {}) {}", e, output_text);
+                return Ok(None);
+            }
+
+            self.state.broadcast_sys(&format!("📦 Oversight: {} wants to clone {}. Review required.", ctx.name, url), "warning");
+            let approved = self.submit_oversight(crate::agent::types::ToolCall {
+                id: uuid::Uuid::new_v4().to_string(),
+                agent_id: ctx.agent_id.clone(),
+                mission_id: Some(ctx.mission_id.clone()),
+                skill: "git_clone".to_string(),
+                params: fc.args.clone(),
+                department: ctx.department.clone(),
+                description: format!("Cloning external repository {} into the workspace.", url),
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            }, Some(ctx.mission_id.clone())).await;
+
+            if !approved {
+                *output_text = format!("(Git clone REJECTED by Oversight) {}", output_text);
+                return Ok(None);
+            }
+        }
+
         // Simple parsing: if execution_command is "python script.py", we split it.
+        // `<param>` placeholders are substituted with the matching tool argument as a single argv entry.
         // We pass the args as an environment variable to prevent shell injection.
-        let mut parts = skill.execution_command.split_whitespace();
-        let program = parts.next().unwrap_or("");
+        let argv = match Self::render_skill_command(&skill.execution_command, &fc.args) {
+            Ok(argv) => argv,
+            Err(e) => {
+                *output_text = format!("(SKILL EXEC FAILED: {}) {}", e, output_text);
+                return Ok(None);
+            }
+        };
+        let mut parts = argv.iter();
+        let program = parts.next().map(|s| s.as_str()).unwrap_or("");
         
         if program.is_empty() {
              *output_text = format!("(SKILL EXEC FAILED: Empty execution command) {}", output_text);
-             return Ok(());
+             return Ok(None);
         }
 
         let mut cmd = tokio::process::Command::new(program);
@@ -773,12 +1609,24 @@ impl AgentRunner {
         // Pass arguments via env var to prevent direct command injection into arguments
         cmd.env("TADPOLE_SKILL_ARGS", &args_json);
         // Optional: Run in the workspace directory
+        tokio::fs::create_dir_all(&ctx.workspace_root).await?;
         cmd.current_dir(&ctx.workspace_root);
 
-        let output_res = timeout(Duration::from_secs(60), cmd.output()).await;
+        let limits = crate::agent::sandbox::ResourceLimits::for_skill(skill, self.state.skill_limits);
+        let started = tokio::time::Instant::now();
+        let output_res = crate::agent::sandbox::run(cmd, limits, Duration::from_secs(ctx.task_timeout_secs)).await;
+        let succeeded = matches!(&output_res, Ok(SandboxOutcome::Completed(output)) if output.status.success());
+        self.state.capabilities.record_skill_run(&skill.name, started.elapsed(), !succeeded);
 
         match output_res {
-            Ok(Ok(output)) => {
+            Ok(SandboxOutcome::CpuLimitExceeded(output)) => {
+                *output_text = format!(
+                    "(SKILL EXEC KILLED: Process exceeded its {}s CPU time limit, status {}) {}",
+                    limits.cpu_secs, output.status, output_text
+                );
+                tracing::warn!("⚠️ [Protocol] Skill {} for agent {} exceeded its {}s CPU limit and was killed.", skill.name, ctx.agent_id, limits.cpu_secs);
+            }
+            Ok(SandboxOutcome::Completed(output)) => {
                 let stdout = String::from_utf8_lossy(&output.stdout).to_string();
                 let stderr = String::from_utf8_lossy(&output.stderr).to_string();
                 
@@ -800,22 +1648,114 @@ impl AgentRunner {
                     "You executed the dynamic skill '{}'. Here is the terminal output:\n\n{}\n\nPlease address the user's initial request based on this result.",
                     skill.name, syntax_result
                 );
-                let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await?;
+                let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
                 *output_text = final_text;
                 self.accumulate_usage(usage, final_usage);
             }
-            Ok(Err(e)) => {
+            Ok(SandboxOutcome::TimedOut) => {
+                *output_text = format!("(SKILL EXEC TIMEOUT: Process took longer than {} seconds of wall-clock time and was terminated) {}", ctx.task_timeout_secs, output_text);
+                tracing::warn!("⚠️ [Protocol] Skill {} for agent {} exceeded {}s timeout and was killed.", skill.name, ctx.agent_id, ctx.task_timeout_secs);
+            }
+            Err(e) => {
                 *output_text = format!("(SKILL EXEC FAILED to start subprocess: {}) {}", e, output_text);
             }
-            Err(_) => {
-                *output_text = format!("(SKILL EXEC TIMEOUT: Process took longer than 60 seconds and was terminated) {}", output_text);
-                tracing::warn!("⚠️ [Protocol] Skill {} for agent {} exceeded 60s timeout and was killed.", skill.name, ctx.agent_id);
+        }
+
+        Ok(None)
+    }
+
+    /// Expands `<param>` placeholders in a skill's execution command into argv entries.
+    /// Substituted values are never shell-interpreted; values starting with '-' are rejected
+    /// so an agent cannot smuggle extra flags into the program.
+    fn render_skill_command(command: &str, args: &serde_json::Value) -> anyhow::Result<Vec<String>> {
+        command.split_whitespace().map(|token| {
+            match token.strip_prefix('<').and_then(|t| t.strip_suffix('>')) {
+                Some(key) => {
+                    let value = match args.get(key) {
+                        Some(serde_json::Value::String(s)) => s.clone(),
+                        Some(serde_json::Value::Null) | None => {
+                            return Err(anyhow::anyhow!("Missing required argument '{}'", key));
+                        }
+                        Some(other) => other.to_string(),
+                    };
+                    if value.starts_with('-') {
+                        return Err(anyhow::anyhow!("Argument '{}' may not start with '-'", key));
+                    }
+                    Ok(value)
+                }
+                None => Ok(token.to_string()),
+            }
+        }).collect()
+    }
+
+    /// Ensures a directory argument stays relative to the workspace root.
+    fn validate_workspace_relative_dir(dir: &str) -> anyhow::Result<()> {
+        if dir.trim().is_empty() {
+            return Err(anyhow::anyhow!("A target 'dir' is required"));
+        }
+        for component in std::path::Path::new(dir).components() {
+            match component {
+                std::path::Component::Normal(_) | std::path::Component::CurDir => {}
+                _ => return Err(anyhow::anyhow!("🚫 SECURITY FAULT: '{}' must be a relative path inside the workspace", dir)),
             }
         }
+        Ok(())
+    }
+
+    /// Only HTTPS clones from one of `allowed_hosts` are allowed.
+    fn validate_git_clone_url(url: &str, allowed_hosts: &[String]) -> anyhow::Result<()> {
+        if !url.starts_with("https://") {
+            return Err(anyhow::anyhow!("Only https:// clone URLs are permitted (got '{}')", url));
+        }
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| anyhow::anyhow!("Invalid clone URL '{}': {}", url, e))?;
+        let host = parsed.host_str().unwrap_or_default().to_lowercase();
+
+        if !allowed_hosts.iter().any(|h| h.trim().eq_ignore_ascii_case(&host)) {
+            return Err(anyhow::anyhow!("Host '{}' is not in ALLOWED_GIT_HOSTS ({})", host, allowed_hosts.join(",")));
+        }
+        Ok(())
+    }
+
+    /// SSRF guard for `fetch_url_advanced`: only http(s) URLs whose host is neither `localhost`
+    /// nor an internal address literal (see `is_internal_ip`). Hostnames are checked by
+    /// `resolve_fetch_target`.
+    fn validate_fetch_url(url: &str) -> anyhow::Result<()> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| anyhow::anyhow!("Invalid URL '{}': {}", url, e))?;
+        if parsed.scheme() != "http" && parsed.scheme() != "https" {
+            return Err(anyhow::anyhow!("Only http:// and https:// URLs may be fetched (got '{}')", url));
+        }
+        let host = parsed.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']').to_lowercase();
+        if host.is_empty() {
+            return Err(anyhow::anyhow!("URL '{}' has no host", url));
+        }
 
+        let internal = host == "localhost" || host.ends_with(".localhost")
+            || host.parse::<std::net::IpAddr>().is_ok_and(is_internal_ip);
+        if internal {
+            return Err(anyhow::anyhow!("Host '{}' is an internal address and may not be fetched", host));
+        }
         Ok(())
     }
 
+    /// Resolves `url`'s host and refuses it if any address is internal, so a public name
+    /// pointing at a private address is caught too. Returns the checked addresses to pin to.
+    async fn resolve_fetch_target(url: &reqwest::Url) -> anyhow::Result<Vec<std::net::SocketAddr>> {
+        let host = url.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+        let port = url.port_or_known_default().unwrap_or(80);
+        let addrs: Vec<std::net::SocketAddr> = tokio::net::lookup_host((host, port)).await
+            .map_err(|e| anyhow::anyhow!("Could not resolve host '{}': {}", host, e))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(anyhow::anyhow!("Host '{}' did not resolve to any address", host));
+        }
+        if let Some(addr) = addrs.iter().find(|addr| is_internal_ip(addr.ip())) {
+            return Err(anyhow::anyhow!("Host '{}' resolves to internal address {} and may not be fetched", host, addr.ip()));
+        }
+        Ok(addrs)
+    }
+
     // ─────────────────────────────────────────────────────────
     //  TOOL HANDLERS
     // ─────────────────────────────────────────────────────────
@@ -827,7 +1767,7 @@ impl AgentRunner {
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
         usage: &mut Option<crate::agent::types::TokenUsage>,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let sub_agent_id = fc.args.get("agentId").and_then(|v| v.as_str()).unwrap_or("general");
         let sub_message = fc.args.get("message").and_then(|v| v.as_str()).unwrap_or("");
 
@@ -863,6 +1803,12 @@ impl AgentRunner {
             swarm_lineage: Some(updated_lineage),
             external_id: ctx.model_config.external_id.clone(),
             safe_mode: Some(ctx.safe_mode),
+            timeout_secs: Some(ctx.task_timeout_secs),
+            depends_on: None,
+            trace_id: Some(ctx.trace_id.clone()),
+            tags: None,
+            callback_url: None,
+            callback_secret: None,
         })).await?;
 
         // Feed sub-result back for synthesis
@@ -871,12 +1817,53 @@ impl AgentRunner {
             sub_agent_id, sub_result
         );
 
-        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await?;
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
 
         *output_text = final_text;
         self.accumulate_usage(usage, final_usage);
 
-        Ok(())
+        Ok(None)
+    }
+
+    /// Handles `delegate_to_department`: picks an agent from the department and spawns it as a sub-agent.
+    async fn handle_delegate_to_department(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        let department = fc.args.get("department").and_then(|v| v.as_str()).unwrap_or("");
+        let message = fc.args.get("message").and_then(|v| v.as_str()).unwrap_or("");
+
+        let Some(selected_id) = self.select_department_agent(ctx, department) else {
+            *output_text = format!("(DELEGATION FAILED: no eligible agent in department '{}') {}", department, output_text);
+            return Ok(None);
+        };
+
+        tracing::info!("🏢 [Swarm] Agent {} delegating to {} in department {}...", ctx.agent_id, selected_id, department);
+
+        let spawn_call = crate::agent::types::GeminiFunctionCall {
+            name: "spawn_subagent".to_string(),
+            args: serde_json::json!({ "agentId": selected_id, "message": message }),
+        };
+        self.handle_spawn_subagent(ctx, &spawn_call, output_text, usage).await
+    }
+
+    /// Chooses the department agent to delegate to, skipping the caller and its lineage.
+    /// Idle agents win, cheapest first (a proxy for least busy); otherwise the first match is used.
+    fn select_department_agent(&self, ctx: &RunContext, department: &str) -> Option<String> {
+        let candidates: Vec<(String, String, f64)> = self.state.agents.iter()
+            .filter(|a| a.department.eq_ignore_ascii_case(department))
+            .filter(|a| a.id != ctx.agent_id && !ctx.lineage.contains(&a.id))
+            .map(|a| (a.id.clone(), a.status.clone(), a.cost_usd))
+            .collect();
+
+        candidates.iter()
+            .filter(|(_, status, _)| status == "idle")
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .or_else(|| candidates.first())
+            .map(|(id, _, _)| id.clone())
     }
 
     /// Ensures a sub-agent exists in the state and database.
@@ -904,6 +1891,9 @@ impl AgentRunner {
                     metadata: std::collections::HashMap::new(),
                     skills: vec!["fetch_url".to_string()],
                     workflows: vec![],
+                    skill_allowlist: None,
+                    rpm_override: None,
+                    tpm_override: None,
                     model_2: None,
                     model_3: None,
                     model_config2: None,
@@ -923,23 +1913,113 @@ impl AgentRunner {
                         rpd: parent_config.rpd,
                         tpm: parent_config.tpm,
                         tpd: parent_config.tpd,
+                        max_history_turns: parent_config.max_history_turns,
+                        safety_settings: parent_config.safety_settings.clone(),
                     },
                     active_mission: None,
                 }
             });
         
-        crate::agent::persistence::save_agent_db(&self.state.pool, &sub_agent).await?;
+        crate::agent::persistence::create_agent_db(&self.state.pool, &sub_agent).await?;
         self.state.agents.insert(sub_agent_id.to_string(), sub_agent);
 
         Ok(())
     }
 
+    /// Handles `create_agent`: registers a new agent inheriting the caller's model after oversight.
+    async fn handle_create_agent(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        const CREATED_AGENT_BUDGET_USD: f64 = 5.0;
+
+        // Guard again at execution time: the declaration filter alone is not a security boundary
+        if ctx.depth != 0 || ctx.safe_mode || !ctx.skills.iter().any(|s| s == "issue_alpha_directive") {
+            *output_text = format!("(CREATE AGENT DENIED: only the root Overlord may create agents) {}", output_text);
+            return Ok(None);
+        }
+
+        let arg = |key: &str| fc.args.get(key).and_then(|v| v.as_str()).unwrap_or("").trim().to_string();
+        let new_id = arg("id");
+        if new_id.is_empty() {
+            *output_text = format!("(CREATE AGENT FAILED: 'id' is required) {}", output_text);
+            return Ok(None);
+        }
+        if self.state.agents.contains_key(&new_id) {
+            *output_text = format!("(CREATE AGENT FAILED: agent '{}' already exists) {}", new_id, output_text);
+            return Ok(None);
+        }
+        let skills: Vec<String> = fc.args.get("skills")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|s| s.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+
+        tracing::info!("🧬 [Swarm] Agent {} requesting creation of agent {} (Waiting for Oversight)...", ctx.agent_id, new_id);
+        self.state.broadcast_sys(&format!("🧬 Oversight: {} wants to create a new agent '{}'. Review required.", ctx.name, new_id), "warning");
+
+        let approved = self.submit_oversight(crate::agent::types::ToolCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: ctx.agent_id.clone(),
+            mission_id: Some(ctx.mission_id.clone()),
+            skill: "create_agent".to_string(),
+            params: fc.args.clone(),
+            department: ctx.department.clone(),
+            description: format!("Creating new agent '{}' ({}) with skills {:?}.", new_id, arg("role"), skills),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }, Some(ctx.mission_id.clone())).await;
+
+        if !approved {
+            *output_text = format!("(Agent creation REJECTED by Oversight) {}", output_text);
+            return Ok(None);
+        }
+
+        let parent = &ctx.model_config;
+        let new_agent = crate::agent::types::EngineAgent {
+            id: new_id.clone(),
+            name: arg("name"),
+            role: arg("role"),
+            department: arg("department"),
+            description: arg("description"),
+            model_id: Some(parent.model_id.clone()),
+            tokens_used: 0,
+            status: "idle".to_string(),
+            theme_color: Some("#4fd1c5".to_string()),
+            budget_usd: CREATED_AGENT_BUDGET_USD,
+            cost_usd: 0.0,
+            metadata: std::collections::HashMap::new(),
+            skills,
+            workflows: vec![],
+            skill_allowlist: None,
+            rpm_override: None,
+            tpm_override: None,
+            model_2: None,
+            model_3: None,
+            model_config2: None,
+            model_config3: None,
+            active_model_slot: None,
+            token_usage: TokenUsage::default(),
+            model: crate::agent::types::ModelConfig {
+                api_key: None,
+                ..parent.clone()
+            },
+            active_mission: None,
+        };
+
+        self.state.register_agent(new_agent).await;
+        self.state.broadcast_sys(&format!("🧬 Swarm: {} created agent {}", ctx.name, new_id), "success");
+        *output_text = format!("(Created agent {} — available for spawn_subagent) {}", new_id, output_text);
+
+        Ok(None)
+    }
+
     /// Handles `issue_alpha_directive`: delegates to Tadpole Alpha (ID: 2).
     async fn handle_alpha_directive(
         &self,
         ctx: &RunContext,
         fc: &crate::agent::types::GeminiFunctionCall,
-    ) -> anyhow::Result<String> {
+    ) -> Result<Option<String>, ToolError> {
         let directive = fc.args.get("directive").and_then(|v| v.as_str()).unwrap_or("");
         
         tracing::info!("🧬 [Sovereignty] Agent of Nine issuing directive to Tadpole Alpha...");
@@ -963,9 +2043,15 @@ impl AgentRunner {
             swarm_lineage: Some(updated_lineage),
             external_id: None,
             safe_mode: Some(ctx.safe_mode),
+            timeout_secs: Some(ctx.task_timeout_secs),
+            depends_on: None,
+            trace_id: Some(ctx.trace_id.clone()),
+            tags: None,
+            callback_url: None,
+            callback_secret: None,
         })).await?;
 
-        Ok(format!("Directive issued to Tadpole Alpha. Mission ID: {}\n\nResult: {}", ctx.mission_id, sub_result))
+        Ok(Some(format!("Directive issued to Tadpole Alpha. Mission ID: {}\n\nResult: {}", ctx.mission_id, sub_result)))
     }
 
     /// Handles `share_finding`: persists a finding to the swarm context.
@@ -974,17 +2060,58 @@ impl AgentRunner {
         ctx: &RunContext,
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let topic = fc.args.get("topic").and_then(|v| v.as_str()).unwrap_or("General");
         let finding = fc.args.get("finding").and_then(|v| v.as_str()).unwrap_or("");
+        let confidence = fc.args.get("confidence").and_then(|v| v.as_f64()).map(|c| c as f32);
+        if let Some(c) = confidence.filter(|c| !(0.0..=1.0).contains(c)) {
+            *output_text = format!("(SHARE FAILED: confidence {} must be between 0.0 and 1.0) {}", c, output_text);
+            return Ok(None);
+        }
 
         tracing::info!("📢 [Swarm] Agent {} shared a finding on {}: {}", ctx.agent_id, topic, finding);
         self.state.broadcast_sys(&format!("📢 Swarm: {} added context for {}", ctx.name, topic), "success");
 
-        crate::agent::mission::share_finding(&self.state.pool, &ctx.mission_id, &ctx.agent_id, topic, finding).await?;
+        let id = crate::agent::mission::share_finding(&self.state.pool, &ctx.mission_id, &ctx.agent_id, topic, finding, confidence).await?;
 
-        *output_text = format!("(Shared finding on {} to swarm context) {}", topic, output_text);
-        Ok(())
+        *output_text = format!("(Shared finding {} on {} to swarm context) {}", id, topic, output_text);
+        Ok(None)
+    }
+
+    /// Handles `verify_finding`: records a confirmed/disputed verdict on another agent's finding.
+    async fn handle_verify_finding(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        let finding_id = fc.args.get("finding_id").and_then(|v| v.as_str()).unwrap_or("");
+        let verdict = match fc.args.get("verdict").and_then(|v| v.as_str()).unwrap_or("").parse::<crate::agent::types::FindingVerdict>() {
+            Ok(verdict) => verdict,
+            Err(e) => {
+                *output_text = format!("(VERIFY FAILED: {}) {}", e, output_text);
+                return Ok(None);
+            }
+        };
+
+        match crate::agent::mission::get_finding(&self.state.pool, &ctx.mission_id, finding_id).await? {
+            None => {
+                *output_text = format!("(VERIFY FAILED: no finding '{}' in this mission) {}", finding_id, output_text);
+                return Ok(None);
+            }
+            Some(finding) if finding.agent_id == ctx.agent_id => {
+                *output_text = format!("(VERIFY FAILED: agents cannot verify their own findings) {}", output_text);
+                return Ok(None);
+            }
+            Some(_) => {}
+        }
+
+        crate::agent::mission::verify_finding(&self.state.pool, &ctx.mission_id, finding_id, &ctx.agent_id, verdict).await?;
+        tracing::info!("🔎 [Swarm] Agent {} marked finding {} as {}", ctx.agent_id, finding_id, verdict.as_str());
+        self.state.broadcast_sys(&format!("🔎 Swarm: {} {} a finding", ctx.name, verdict.as_str()), "info");
+
+        *output_text = format!("(Finding {} marked {}) {}", finding_id, verdict.as_str(), output_text);
+        Ok(None)
     }
 
     /// Handles `query_financial_logs`: retrieves and analyzes mission cost history.
@@ -994,7 +2121,7 @@ impl AgentRunner {
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
         usage: &mut Option<crate::agent::types::TokenUsage>,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let limit = fc.args.get("limit").and_then(|v| v.as_i64()).unwrap_or(10);
         
         tracing::info!("📊 [Governance] Agent {} querying financial history (limit: {})...", ctx.agent_id, limit);
@@ -1008,12 +2135,59 @@ impl AgentRunner {
             history_json
         );
 
-        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &audit_prompt).await?;
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &audit_prompt).await.map_err(ToolError::from_provider)?;
 
         *output_text = final_text;
         self.accumulate_usage(usage, final_usage);
 
-        Ok(())
+        Ok(None)
+    }
+
+    /// Handles `query_agent_logs`: summarizes the agent's own recent missions for self-review.
+    async fn handle_query_agent_logs(
+        &self,
+        ctx: &RunContext,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        const RECENT_MISSIONS: usize = 5;
+        const MAX_ENTRY_CHARS: usize = 200;
+        const MAX_STEPS_PER_MISSION: i64 = 50;
+
+        tracing::info!("🪞 [Self-Review] Agent {} querying its own mission history...", ctx.agent_id);
+
+        // Fetch one extra so the in-flight mission can be skipped without shrinking the window
+        let missions = crate::agent::mission::get_agent_missions(&self.state.pool, &ctx.agent_id, RECENT_MISSIONS as i64 + 1).await?;
+
+        let mut summary = String::new();
+        for mission in missions.iter().filter(|m| m.id != ctx.mission_id).take(RECENT_MISSIONS) {
+            let logs = crate::agent::mission::get_mission_logs(&self.state.pool, &mission.id, 1, MAX_STEPS_PER_MISSION).await?.items;
+            summary.push_str(&format!(
+                "[Mission {} - {} - ${:.2}]: ",
+                mission.title,
+                crate::agent::mission::status_to_str(&mission.status),
+                mission.cost_usd
+            ));
+            let steps: Vec<String> = logs.iter().enumerate().map(|(i, log)| {
+                let text: String = log.text.chars().take(MAX_ENTRY_CHARS).collect();
+                format!("Step {} ({}): {}", i + 1, log.source, text)
+            }).collect();
+            summary.push_str(&steps.join("\n"));
+            summary.push_str("\n\n");
+        }
+        if summary.is_empty() {
+            summary = "No previous missions found.".to_string();
+        }
+
+        let review_prompt = format!(
+            "YOUR RECENT MISSION HISTORY:\n\n{}\nReflect on these outcomes, avoid repeating past mistakes, and address the user's initial request.",
+            summary
+        );
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &review_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+
+        Ok(None)
     }
 
     /// Handles `archive_to_vault`: writes data to the local Markdown vault after oversight.
@@ -1022,7 +2196,7 @@ impl AgentRunner {
         ctx: &RunContext,
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let filename = fc.args.get("filename").and_then(|v| v.as_str()).unwrap_or("unnamed.md");
         let content = fc.args.get("content").and_then(|v| v.as_str()).unwrap_or("");
 
@@ -1042,13 +2216,70 @@ impl AgentRunner {
 
         if approved {
             let adapter = crate::adapter::vault::VaultAdapter::new(std::path::PathBuf::from("vault"));
-            adapter.append_to_file(filename, content).await?;
+            adapter.append_to_file(filename, content, Some(ctx.mission_id.clone())).await?;
             *output_text = format!("(Archived to vault: {}) {}", filename, output_text);
         } else {
             *output_text = format!("(Archive REJECTED by Oversight) {}", output_text);
         }
 
-        Ok(())
+        Ok(None)
+    }
+
+    /// The object key for an `archive_to_s3` upload: `<mission_id>/<filename>`, so missions
+    /// cannot overwrite each other's artifacts. The filename may contain `/` but no `..`.
+    fn s3_archive_key(mission_id: &str, filename: &str) -> Result<String, String> {
+        let filename = filename.trim();
+        if filename.is_empty() || filename.starts_with('/') || filename.contains('\\')
+            || filename.split('/').any(|segment| segment.is_empty() || segment == "." || segment == "..") {
+            return Err(format!("invalid filename '{}'", filename));
+        }
+        Ok(format!("{}/{}", mission_id, filename))
+    }
+
+    /// Handles `archive_to_s3`: uploads content to the configured bucket after oversight.
+    async fn handle_archive_to_s3(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        let filename = fc.args.get("filename").and_then(|v| v.as_str()).unwrap_or("");
+        let content = fc.args.get("content").and_then(|v| v.as_str()).unwrap_or("");
+
+        let Some(adapter) = crate::adapter::s3::S3Adapter::from_env().await else {
+            *output_text = format!("(S3 ARCHIVE UNAVAILABLE: object storage is not configured; set S3_ENABLED=true) {}", output_text);
+            return Ok(None);
+        };
+        let key = match Self::s3_archive_key(&ctx.mission_id, filename) {
+            Ok(key) => key,
+            Err(e) => {
+                *output_text = format!("(S3 ARCHIVE BLOCKED: {}) {}", e, output_text);
+                return Ok(None);
+            }
+        };
+
+        tracing::info!("🪣 [Surface] Agent {} archiving {} to S3 (Waiting for Oversight)...", ctx.agent_id, key);
+        self.state.broadcast_sys(&format!("🪣 Oversight: {} wants to upload {} to object storage. Review required.", ctx.name, key), "warning");
+
+        let approved = self.submit_oversight(crate::agent::types::ToolCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: ctx.agent_id.clone(),
+            mission_id: Some(ctx.mission_id.clone()),
+            skill: "archive_to_s3".to_string(),
+            params: fc.args.clone(),
+            department: ctx.department.clone(),
+            description: format!("Uploading {} bytes to s3://{}/{}{}.", content.len(), adapter.bucket, adapter.prefix, key),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }, Some(ctx.mission_id.clone())).await;
+
+        if approved {
+            adapter.upload_file(&key, content.as_bytes().to_vec()).await?;
+            *output_text = format!("(Archived to s3://{}/{}{}) {}", adapter.bucket, adapter.prefix, key, output_text);
+        } else {
+            *output_text = format!("(S3 archive REJECTED by Oversight) {}", output_text);
+        }
+
+        Ok(None)
     }
 
     /// Handles `notify_discord`: sends a webhook notification after oversight.
@@ -1057,7 +2288,7 @@ impl AgentRunner {
         ctx: &RunContext,
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let msg = fc.args.get("message").and_then(|v| v.as_str()).unwrap_or("");
 
         tracing::info!("🔔 [Surface] Agent {} requesting Discord notification...", ctx.agent_id);
@@ -1076,7 +2307,7 @@ impl AgentRunner {
 
         if approved {
             if let Ok(webhook) = std::env::var("DISCORD_WEBHOOK") {
-                let adapter = crate::adapter::discord::DiscordAdapter::new(webhook);
+                let adapter = crate::adapter::discord::DiscordAdapter::new((*self.state.http_client).clone(), webhook);
                 adapter.notify(&ctx.name, msg).await?;
                 self.state.broadcast_sys(&format!("🔔 Surface: {} sent Discord alert", ctx.name), "success");
                 *output_text = format!("(Notified Discord) {}", output_text);
@@ -1087,41 +2318,135 @@ impl AgentRunner {
             *output_text = format!("(Discord notification REJECTED by Oversight) {}", output_text);
         }
 
-        Ok(())
+        Ok(None)
     }
 
-    /// Handles `complete_mission`: marks the mission as completed after oversight.
-    async fn handle_complete_mission(
+    /// Handles `notify_slack`: sends a webhook notification after oversight.
+    async fn handle_notify_slack(
         &self,
         ctx: &RunContext,
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
-    ) -> anyhow::Result<()> {
-        let report = fc.args.get("finalReport").and_then(|v| v.as_str()).unwrap_or("Mission complete.");
-        
-        tracing::info!("🏁 [Mission] Agent {} requesting completion...", ctx.agent_id);
-        self.state.broadcast_sys(&format!("🏁 Oversight: {} finished work. Reviewing final report...", ctx.name), "warning");
+    ) -> Result<Option<String>, ToolError> {
+        let msg = fc.args.get("message").and_then(|v| v.as_str()).unwrap_or("");
+        let channel = fc.args.get("channel").and_then(|v| v.as_str());
+
+        tracing::info!("🔔 [Surface] Agent {} requesting Slack notification...", ctx.agent_id);
+        self.state.broadcast_sys(&format!("🔔 Oversight: {} wants to notify Slack.", ctx.name), "warning");
 
         let approved = self.submit_oversight(crate::agent::types::ToolCall {
             id: uuid::Uuid::new_v4().to_string(),
             agent_id: ctx.agent_id.clone(),
             mission_id: Some(ctx.mission_id.clone()),
-            skill: "complete_mission".to_string(),
+            skill: "notify_slack".to_string(),
             params: fc.args.clone(),
             department: ctx.department.clone(),
-            description: "Final mission sign-off and reporting.".to_string(),
+            description: "Sending an external notification via Slack.".to_string(),
             timestamp: chrono::Utc::now().to_rfc3339(),
         }, Some(ctx.mission_id.clone())).await;
 
         if approved {
-            crate::agent::mission::update_mission(&self.state.pool, &ctx.mission_id, crate::agent::types::MissionStatus::Completed, 0.0).await?;
+            if let Ok(webhook) = std::env::var("SLACK_WEBHOOK") {
+                let adapter = crate::adapter::slack::SlackAdapter::new((*self.state.http_client).clone(), webhook);
+                adapter.notify(&ctx.name, msg, channel).await?;
+                self.state.broadcast_sys(&format!("🔔 Surface: {} sent Slack alert", ctx.name), "success");
+                *output_text = format!("(Notified Slack) {}", output_text);
+            } else {
+                *output_text = format!("(Slack notification failed - no webhook) {}", output_text);
+            }
+        } else {
+            *output_text = format!("(Slack notification REJECTED by Oversight) {}", output_text);
+        }
+
+        Ok(None)
+    }
+
+    /// Handles `notify_email`: sends an SMTP email after oversight.
+    async fn handle_notify_email(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        let to = fc.args.get("to").and_then(|v| v.as_str()).map(|s| s.to_string())
+            .or_else(|| std::env::var("EMAIL_TO_DEFAULT").ok())
+            .unwrap_or_default();
+        let subject = fc.args.get("subject").and_then(|v| v.as_str()).unwrap_or("");
+        let body = fc.args.get("body").and_then(|v| v.as_str()).unwrap_or("");
+
+        if !crate::adapter::email::is_valid_email(&to) {
+            *output_text = format!("(Email notification BLOCKED: invalid recipient '{}') {}", to, output_text);
+            return Ok(None);
+        }
+
+        tracing::info!("📧 [Surface] Agent {} requesting email to {}...", ctx.agent_id, to);
+        self.state.broadcast_sys(&format!("📧 Oversight: {} wants to email {}.", ctx.name, to), "warning");
+
+        let approved = self.submit_oversight(crate::agent::types::ToolCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: ctx.agent_id.clone(),
+            mission_id: Some(ctx.mission_id.clone()),
+            skill: "notify_email".to_string(),
+            params: fc.args.clone(),
+            department: ctx.department.clone(),
+            description: format!("Sending an email to {} with subject '{}'.", to, subject),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }, Some(ctx.mission_id.clone())).await;
+
+        if !approved {
+            *output_text = format!("(Email notification REJECTED by Oversight) {}", output_text);
+            return Ok(None);
+        }
+
+        let result = match crate::adapter::email::EmailAdapter::new(&self.state.smtp) {
+            Ok(adapter) => adapter.send(&to, subject, body).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(_) => {
+                self.state.broadcast_sys(&format!("📧 Surface: {} emailed {}", ctx.name, to), "success");
+                *output_text = format!("(Emailed {}) {}", to, output_text);
+            }
+            Err(e) => {
+                *output_text = format!("(Email notification failed: {}) {}", e, output_text);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Handles `complete_mission`: marks the mission as completed after oversight.
+    async fn handle_complete_mission(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        let report = fc.args.get("finalReport").and_then(|v| v.as_str()).unwrap_or("Mission complete.");
+        
+        tracing::info!("🏁 [Mission] Agent {} requesting completion...", ctx.agent_id);
+        self.state.broadcast_sys(&format!("🏁 Oversight: {} finished work. Reviewing final report...", ctx.name), "warning");
+
+        let approved = self.submit_oversight(crate::agent::types::ToolCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: ctx.agent_id.clone(),
+            mission_id: Some(ctx.mission_id.clone()),
+            skill: "complete_mission".to_string(),
+            params: fc.args.clone(),
+            department: ctx.department.clone(),
+            description: "Final mission sign-off and reporting.".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }, Some(ctx.mission_id.clone())).await;
+
+        if approved {
+            crate::agent::mission::update_mission_and_broadcast(&self.state, &ctx.mission_id, crate::agent::types::MissionStatus::Completed, 0.0).await?;
             self.state.broadcast_sys(&format!("✅ Mission {} COMPLETED.", ctx.mission_id), "success");
             *output_text = format!("(MISSION COMPLETED: {}) {}", report, output_text);
         } else {
             *output_text = format!("(Mission completion REJECTED) {}", output_text);
         }
 
-        Ok(())
+        Ok(None)
     }
 
     /// Handles `fetch_url`: retrieves text content from a public URL.
@@ -1131,31 +2456,159 @@ impl AgentRunner {
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
         usage: &mut Option<crate::agent::types::TokenUsage>,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let url = fc.args.get("url").and_then(|v| v.as_str()).unwrap_or("");
         tracing::info!("🌐 [Surface] Agent {} fetching URL: {}", ctx.agent_id, url);
         self.state.broadcast_sys(&format!("🌐 Surface: {} is researching {}...", ctx.name, url), "info");
 
-        match reqwest::get(url).await {
-            Ok(r) => {
-                let text = r.text().await.unwrap_or_else(|_| "Error reading text".to_string());
-                let truncated = if text.len() > 3000 { format!("{}... [TRUNCATED]", &text[..3000]) } else { text };
-                let fetch_res = format!("(FETCHED CONTENT): {}\n\n{}", truncated, output_text);
-                
-                let synthesis_prompt = format!(
-                    "You fetched the URL '{}'. Here is the content:\n\n{}\n\nPlease address the user's initial request using this information.",
-                    url, fetch_res
-                );
-                let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await?;
-                *output_text = final_text;
-                self.accumulate_usage(usage, final_usage);
+        let text = reqwest::get(url).await?.text().await?;
+        let truncated = if text.len() > 3000 { format!("{}... [TRUNCATED]", &text[..3000]) } else { text };
+        let fetch_res = format!("(FETCHED CONTENT): {}\n\n{}", truncated, output_text);
+
+        let synthesis_prompt = format!(
+            "You fetched the URL '{}'. Here is the content:\n\n{}\n\nPlease address the user's initial request using this information.",
+            url, fetch_res
+        );
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+
+        Ok(None)
+    }
+
+    /// Handles `fetch_url_advanced`: a GET or POST with caller-supplied headers and body.
+    /// Internal addresses are refused outright; anything else needs Oversight approval first.
+    async fn handle_fetch_url_advanced(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        let url = fc.args.get("url").and_then(|v| v.as_str()).unwrap_or("");
+        let method = fc.args.get("method").and_then(|v| v.as_str()).unwrap_or("GET").to_uppercase();
+        let body = fc.args.get("body").and_then(|v| v.as_str());
+        let timeout_secs = fc.args.get("timeout_secs").and_then(|v| v.as_u64())
+            .unwrap_or(MAX_FETCH_TIMEOUT_SECS)
+            .clamp(1, MAX_FETCH_TIMEOUT_SECS);
+
+        if let Err(e) = Self::validate_fetch_url(url) {
+            *output_text = format!("(FETCH BLOCKED: {}) {}", e, output_text);
+            return Ok(None);
+        }
+        let method = match method.as_str() {
+            "GET" => reqwest::Method::GET,
+            "POST" => reqwest::Method::POST,
+            other => {
+                *output_text = format!("(FETCH BLOCKED: method '{}' is not allowed; use GET or POST) {}", other, output_text);
+                return Ok(None);
             }
-            Err(e) => {
-                *output_text = format!("(FETCH FAILED: {}) {}", e, output_text);
+        };
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in fc.args.get("headers").and_then(|v| v.as_object()).into_iter().flatten() {
+            let parsed = reqwest::header::HeaderName::from_bytes(name.as_bytes()).ok()
+                .zip(value.as_str().and_then(|v| reqwest::header::HeaderValue::from_str(v).ok()));
+            match parsed {
+                Some((name, value)) => { headers.insert(name, value); }
+                None => {
+                    *output_text = format!("(FETCH BLOCKED: invalid header '{}') {}", name, output_text);
+                    return Ok(None);
+                }
             }
         }
 
-        Ok(())
+        tracing::info!("🌐 [Surface] Agent {} requesting {} {} with custom headers...", ctx.agent_id, method, url);
+        self.state.broadcast_sys(&format!("🌐 Oversight: {} wants to {} {}.", ctx.name, method, url), "warning");
+
+        // Header values are usually credentials: the reviewer sees which headers are sent, not their values
+        let mut params = fc.args.clone();
+        if let Some(sent) = params.get_mut("headers").and_then(|v| v.as_object_mut()) {
+            sent.values_mut().for_each(|v| *v = serde_json::json!("[REDACTED]"));
+        }
+        let approved = self.submit_oversight(crate::agent::types::ToolCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: ctx.agent_id.clone(),
+            mission_id: Some(ctx.mission_id.clone()),
+            skill: "fetch_url_advanced".to_string(),
+            params,
+            department: ctx.department.clone(),
+            description: format!("{} request to {} with headers [{}].", method, url, headers.keys().map(|k| k.as_str()).collect::<Vec<_>>().join(", ")),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }, Some(ctx.mission_id.clone())).await;
+
+        if !approved {
+            *output_text = format!("(Fetch REJECTED by Oversight) {}", output_text);
+            return Ok(None);
+        }
+
+        // Each hop is resolved, checked and pinned before it is requested, redirects included
+        let mut target = reqwest::Url::parse(url).map_err(anyhow::Error::from)?;
+        let (mut hop_method, mut hop_body) = (method.clone(), body.map(str::to_string));
+        let mut redirects = 0;
+        let response = loop {
+            let addrs = match Self::resolve_fetch_target(&target).await {
+                Ok(addrs) => addrs,
+                Err(e) => {
+                    *output_text = format!("(FETCH BLOCKED: {}) {}", e, output_text);
+                    return Ok(None);
+                }
+            };
+            let mut request = pinned_fetch_client(&target, &addrs)?
+                .request(hop_method.clone(), target.clone())
+                .headers(headers.clone())
+                .timeout(Duration::from_secs(timeout_secs));
+            if let Some(body) = &hop_body {
+                request = request.body(body.clone());
+            }
+            // Past Oversight a failed send is not retried: that would ask for approval again and
+            // could repeat a POST the server already received
+            let response = request.send().await.map_err(|e| ToolError::Internal(e.to_string()))?;
+            let next = response.headers().get(reqwest::header::LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| target.join(location).ok());
+            let Some(next) = next.filter(|_| response.status().is_redirection()) else { break response };
+
+            redirects += 1;
+            let refusal = if redirects > MAX_FETCH_REDIRECTS {
+                Some(format!("more than {} redirects", MAX_FETCH_REDIRECTS))
+            } else {
+                Self::validate_fetch_url(next.as_str()).err().map(|e| e.to_string())
+            };
+            if let Some(reason) = refusal {
+                *output_text = format!("(FETCH BLOCKED: redirect to '{}' refused: {}) {}", next, reason, output_text);
+                return Ok(None);
+            }
+            // 301/302/303 turn the request into a bodiless GET, as browsers do
+            if matches!(response.status().as_u16(), 301..=303) {
+                hop_method = reqwest::Method::GET;
+                hop_body = None;
+            }
+            // Credentials are not carried over to a different host
+            if next.host_str() != target.host_str() {
+                for name in [reqwest::header::AUTHORIZATION, reqwest::header::COOKIE, reqwest::header::PROXY_AUTHORIZATION] {
+                    headers.remove(name);
+                }
+            }
+            target = next;
+        };
+        let status = response.status();
+        let text = response.text().await.map_err(|e| ToolError::Internal(e.to_string()))?;
+        let truncated = if text.chars().count() > 3000 {
+            format!("{}... [TRUNCATED]", text.chars().take(3000).collect::<String>())
+        } else {
+            text
+        };
+        let fetch_res = format!("(FETCHED CONTENT, HTTP {}): {}\n\n{}", status.as_u16(), truncated, output_text);
+
+        let synthesis_prompt = format!(
+            "You sent a {} request to '{}'. Here is the response:\n\n{}\n\nPlease address the user's initial request using this information.",
+            method, url, fetch_res
+        );
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+
+        Ok(None)
     }
 
     /// Handles `read_file`: reads content from the workspace.
@@ -1165,29 +2618,23 @@ impl AgentRunner {
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
         usage: &mut Option<crate::agent::types::TokenUsage>,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let filename = fc.args.get("filename").and_then(|v| v.as_str()).unwrap_or("");
         tracing::info!("📖 [Workspace] Agent {} reading file: {}", ctx.agent_id, filename);
         
         let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
-        match adapter.read_file(filename).await {
-            Ok(content) => {
-                let truncated = if content.len() > 5000 { format!("{}... [TRUNCATED]", &content[..5000]) } else { content };
-                let read_res = format!("(FILE CONTENT OF {}):\n\n{}\n\n{}", filename, truncated, output_text);
-                
-                let synthesis_prompt = format!(
-                    "You read the file '{}'. Here is the content:\n\n{}\n\nPlease address the user's initial request based on this.",
-                    filename, read_res
-                );
-                let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await?;
-                *output_text = final_text;
-                self.accumulate_usage(usage, final_usage);
-            }
-            Err(e) => {
-                *output_text = format!("(READ FAILED: {}) {}", e, output_text);
-            }
-        }
-        Ok(())
+        let content = adapter.read_file(filename).await?;
+        let truncated = if content.len() > 5000 { format!("{}... [TRUNCATED]", &content[..5000]) } else { content };
+        let read_res = format!("(FILE CONTENT OF {}):\n\n{}\n\n{}", filename, truncated, output_text);
+
+        let synthesis_prompt = format!(
+            "You read the file '{}'. Here is the content:\n\n{}\n\nPlease address the user's initial request based on this.",
+            filename, read_res
+        );
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+        Ok(None)
     }
 
     /// Handles `write_file`: writes content to the workspace.
@@ -1196,23 +2643,186 @@ impl AgentRunner {
         ctx: &RunContext,
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let filename = fc.args.get("filename").and_then(|v| v.as_str()).unwrap_or("");
         let content = fc.args.get("content").and_then(|v| v.as_str()).unwrap_or("");
         
         tracing::info!("✍️ [Workspace] Agent {} writing to file: {}", ctx.agent_id, filename);
         
         let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
-        match adapter.write_file(filename, content).await {
-            Ok(_) => {
-                self.state.broadcast_sys(&format!("✍️ Workspace: {} wrote to {}", ctx.name, filename), "success");
-                *output_text = format!("(Successfully wrote to {}) {}", filename, output_text);
-            }
-            Err(e) => {
-                *output_text = format!("(WRITE FAILED: {}) {}", e, output_text);
+        adapter.write_file(filename, content).await?;
+        self.state.broadcast_sys(&format!("✍️ Workspace: {} wrote to {}", ctx.name, filename), "success");
+        *output_text = format!("(Successfully wrote to {}) {}", filename, output_text);
+        Ok(None)
+    }
+
+    /// Handles `search_files`: finds matching lines across workspace files and lets the agent interpret them.
+    async fn handle_search_files(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        let dir = fc.args.get("dir").and_then(|v| v.as_str()).unwrap_or(".");
+        let pattern = fc.args.get("pattern").and_then(|v| v.as_str()).unwrap_or("");
+        let use_regex = fc.args.get("use_regex").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        if pattern.is_empty() {
+            *output_text = format!("(SEARCH FAILED: pattern must not be empty) {}", output_text);
+            return Ok(None);
+        }
+
+        tracing::info!("🔎 [Workspace] Agent {} searching '{}' for: {}", ctx.agent_id, dir, pattern);
+
+        let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
+        let hits = adapter.search_files(dir, pattern, use_regex).await?;
+        let listing = if hits.is_empty() {
+            "No matches found.".to_string()
+        } else {
+            hits.iter()
+                .map(|h| format!("{}:{}: {}", h.filename, h.line_number, h.line_content))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let synthesis_prompt = format!(
+            "You searched '{}' for '{}' and found {} match(es):\n\n{}\n\nPlease address the user's initial request based on this.",
+            dir, pattern, hits.len(), listing
+        );
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+        Ok(None)
+    }
+
+    /// Handles `append_file`: appends content to a workspace file without overwriting it.
+    async fn handle_append_file(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        let filename = fc.args.get("filename").and_then(|v| v.as_str()).unwrap_or("");
+        let content = fc.args.get("content").and_then(|v| v.as_str()).unwrap_or("");
+
+        tracing::info!("📎 [Workspace] Agent {} appending to file: {}", ctx.agent_id, filename);
+
+        let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
+        adapter.append_to_file(filename, content).await?;
+        self.state.broadcast_sys(&format!("📎 Workspace: {} appended to {}", ctx.name, filename), "success");
+        *output_text = format!("(Successfully appended to {}) {}", filename, output_text);
+        Ok(None)
+    }
+
+    /// Handles `copy_file`: duplicates a workspace file within the sandbox.
+    async fn handle_copy_file(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        let src = fc.args.get("src").and_then(|v| v.as_str()).unwrap_or("");
+        let dst = fc.args.get("dst").and_then(|v| v.as_str()).unwrap_or("");
+
+        tracing::info!("📑 [Workspace] Agent {} copying {} to {}", ctx.agent_id, src, dst);
+
+        let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
+        adapter.copy_file(src, dst).await?;
+        self.state.broadcast_sys(&format!("📑 Workspace: {} copied {} to {}", ctx.name, src, dst), "success");
+        *output_text = format!("(Successfully copied {} to {}) {}", src, dst, output_text);
+        Ok(None)
+    }
+
+    /// Handles `write_json`: validates structured content against an optional JSON Schema, then writes it pretty-printed.
+    async fn handle_write_json(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        let filename = fc.args.get("filename").and_then(|v| v.as_str()).unwrap_or("");
+        let Some(content) = fc.args.get("content") else {
+            *output_text = format!("(JSON WRITE FAILED: 'content' is required) {}", output_text);
+            return Ok(None);
+        };
+
+        if let Some(schema) = fc.args.get("schema") {
+            let compiled = match jsonschema::JSONSchema::compile(schema) {
+                Ok(compiled) => compiled,
+                Err(e) => {
+                    *output_text = format!("(JSON WRITE FAILED: invalid schema: {}) {}", e, output_text);
+                    return Ok(None);
+                }
+            };
+            let details: Vec<String> = match compiled.validate(content) {
+                Ok(_) => Vec::new(),
+                Err(errors) => errors.map(|e| {
+                    let path = e.instance_path.to_string();
+                    format!("{}: {}", if path.is_empty() { "/".to_string() } else { path }, e)
+                }).collect(),
+            };
+            if !details.is_empty() {
+                *output_text = format!("(JSON VALIDATION FAILED, nothing written: {}) {}", details.join("; "), output_text);
+                return Ok(None);
             }
         }
-        Ok(())
+
+        tracing::info!("🧾 [Workspace] Agent {} writing JSON to file: {}", ctx.agent_id, filename);
+
+        let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
+        adapter.write_file(filename, &serde_json::to_string_pretty(content)?).await?;
+        self.state.broadcast_sys(&format!("🧾 Workspace: {} wrote JSON to {}", ctx.name, filename), "success");
+        *output_text = format!("(Successfully wrote JSON to {}) {}", filename, output_text);
+        Ok(None)
+    }
+
+    /// Handles `mock_http_response`: records a designed HTTP response under `http_responses/` and in the mission log.
+    async fn handle_mock_http_response(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        let status_code = match fc.args.get("status_code").and_then(|v| v.as_u64()) {
+            Some(code) if (100..=599).contains(&code) => code as u16,
+            _ => {
+                *output_text = format!("(MOCK RESPONSE FAILED: 'status_code' must be between 100 and 599) {}", output_text);
+                return Ok(None);
+            }
+        };
+        let headers = match fc.args.get("headers") {
+            Some(h) if h.is_object() => h.clone(),
+            _ => serde_json::json!({}),
+        };
+        let body = fc.args.get("body").and_then(|v| v.as_str()).unwrap_or("");
+
+        let record = serde_json::json!({
+            "statusCode": status_code,
+            "headers": headers,
+            "body": body,
+            "agentId": ctx.agent_id,
+            "missionId": ctx.mission_id,
+            "createdAt": chrono::Utc::now().to_rfc3339()
+        });
+        let filename = format!("http_responses/{}.json", uuid::Uuid::new_v4());
+
+        tracing::info!("🧪 [Workspace] Agent {} recording mock HTTP {} response to {}", ctx.agent_id, status_code, filename);
+
+        let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
+        adapter.write_file(&filename, &serde_json::to_string_pretty(&record)?).await?;
+
+        crate::agent::mission::log_step_and_broadcast(
+            &self.state,
+            &ctx.mission_id,
+            &ctx.agent_id,
+            "Agent",
+            &format!("Mock HTTP {} response recorded to {}", status_code, filename),
+            "info",
+            Some(record)
+        ).await?;
+
+        *output_text = format!("(Mock HTTP {} response recorded to {}) {}", status_code, filename, output_text);
+        Ok(None)
     }
 
     /// Handles `list_files`: lists directory contents in the workspace.
@@ -1222,29 +2832,88 @@ impl AgentRunner {
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
         usage: &mut Option<crate::agent::types::TokenUsage>,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let dir = fc.args.get("dir").and_then(|v| v.as_str()).unwrap_or(".");
         tracing::info!("📂 [Workspace] Agent {} listing directory: {}", ctx.agent_id, dir);
 
+        let recursive = fc.args.get("recursive").and_then(|v| v.as_bool()).unwrap_or(false);
         let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
-        match adapter.list_files(dir).await {
-            Ok(files) => {
-                let list = if files.is_empty() { "Empty directory.".to_string() } else { files.join(", ") };
-                let list_res = format!("(FILES IN {}): {}\n\n{}", dir, list, output_text);
-                
-                let synthesis_prompt = format!(
-                    "You listed the directory '{}'. Here are the files:\n\n{}\n\nPlease address the user's initial request based on this.",
-                    dir, list_res
-                );
-                let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await?;
-                *output_text = final_text;
-                self.accumulate_usage(usage, final_usage);
-            }
-            Err(e) => {
-                *output_text = format!("(LIST FAILED: {}) {}", e, output_text);
-            }
-        }
-        Ok(())
+        let files = adapter.list_files(dir, recursive).await?;
+        let names_only = fc.args.get("names_only").and_then(|v| v.as_bool()).unwrap_or(false);
+        // A recursive listing spans several directories, so entries keep their full path
+        let label = |f: &crate::adapter::filesystem::FileMetadata| if recursive { f.display_path() } else { f.name() };
+        let list = if files.is_empty() {
+            "Empty directory.".to_string()
+        } else if names_only {
+            files.iter().map(label).collect::<Vec<_>>().join(", ")
+        } else {
+            files.iter().map(|f| Self::describe_file(f, label(f))).collect::<Vec<_>>().join("\n")
+        };
+        let list_res = format!("(FILES IN {}): {}\n\n{}", dir, list, output_text);
+
+        let synthesis_prompt = if recursive {
+            let capped = if files.len() >= crate::adapter::filesystem::MAX_LIST_ENTRIES {
+                format!(" The listing stopped at {} entries, so it is incomplete.", crate::adapter::filesystem::MAX_LIST_ENTRIES)
+            } else {
+                String::new()
+            };
+            format!(
+                "You listed the full project structure under '{}', recursively up to {} levels deep. Paths are relative to the workspace root and directories end with '/'.{}\n\n{}\n\nUse this structure to address the user's initial request.",
+                dir, crate::adapter::filesystem::MAX_LIST_DEPTH, capped, list_res
+            )
+        } else if names_only {
+            format!(
+                "You listed the directory '{}'. Here are the files:\n\n{}\n\nPlease address the user's initial request based on this.",
+                dir, list_res
+            )
+        } else {
+            format!(
+                "You listed the directory '{}'. Each entry shows its path, whether it is a file or directory, its size in bytes and when it was last modified (UTC):\n\n{}\n\nPlease address the user's initial request based on this.",
+                dir, list_res
+            )
+        };
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+        Ok(None)
+    }
+
+    /// One line per entry for `list_files` and `stat_file` results.
+    fn describe_file(file: &crate::adapter::filesystem::FileMetadata, label: String) -> String {
+        let kind = if file.is_dir { "directory" } else if file.is_file { "file" } else { "other" };
+        format!("{} ({}, {} bytes, modified {})", label, kind, file.size_bytes, file.modified_at.to_rfc3339())
+    }
+
+    /// Handles `stat_file`: reports size, modification time and kind of a workspace path.
+    /// A missing path is reported to the model rather than aborting the run.
+    async fn handle_stat_file(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        let filename = fc.args.get("filename").and_then(|v| v.as_str()).unwrap_or("");
+        tracing::info!("📂 [Workspace] Agent {} inspecting: {}", ctx.agent_id, filename);
+
+        let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
+        let details = match adapter.stat_file(filename).await {
+            Ok(meta) => format!(
+                "path: {}\nis_file: {}\nis_dir: {}\nsize_bytes: {}\nmodified_at: {}",
+                meta.path, meta.is_file, meta.is_dir, meta.size_bytes, meta.modified_at.to_rfc3339()
+            ),
+            Err(ToolError::NotFound(msg)) => format!("Not found: {}", msg),
+            Err(e) => return Err(e),
+        };
+
+        let synthesis_prompt = format!(
+            "You inspected '{}' in the workspace. Its metadata (size in bytes, last modification time in UTC, and whether it is a file or directory):\n\n{}\n\nPlease address the user's initial request based on this.",
+            filename, details
+        );
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+        Ok(None)
     }
 
     /// Handles `delete_file`: removes a file or directory after oversight.
@@ -1253,7 +2922,7 @@ impl AgentRunner {
         ctx: &RunContext,
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let filename = fc.args.get("filename").and_then(|v| v.as_str()).unwrap_or("");
 
         tracing::info!("🗑️ [Workspace] Agent {} requesting deletion of: {}", ctx.agent_id, filename);
@@ -1272,19 +2941,555 @@ impl AgentRunner {
 
         if approved {
             let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
-            match adapter.delete_file(filename).await {
-                Ok(_) => {
-                    self.state.broadcast_sys(&format!("🗑️ Workspace: {} deleted {}", ctx.name, filename), "success");
-                    *output_text = format!("(Successfully deleted {}) {}", filename, output_text);
+            adapter.delete_file(filename).await?;
+            self.state.broadcast_sys(&format!("🗑️ Workspace: {} deleted {}", ctx.name, filename), "success");
+            *output_text = format!("(Successfully deleted {}) {}", filename, output_text);
+        } else {
+            *output_text = format!("(Delete REJECTED by Oversight) {}", output_text);
+        }
+
+        Ok(None)
+    }
+
+    /// Handles `run_tests`: runs the workspace test suite after oversight and summarizes the results.
+    /// Runs `state.run_tests_command` (RUN_TESTS_COMMAND) in the agent's workspace.
+    async fn handle_run_tests(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        const RUN_TESTS_TIMEOUT_SECS: u64 = 300;
+
+        tracing::info!("🧪 [Workspace] Agent {} requesting a test run (Waiting for Oversight)...", ctx.agent_id);
+        self.state.broadcast_sys(&format!("🧪 Oversight: {} wants to run the test suite. Review required.", ctx.name), "warning");
+
+        let approved = self.submit_oversight(crate::agent::types::ToolCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: ctx.agent_id.clone(),
+            mission_id: Some(ctx.mission_id.clone()),
+            skill: "run_tests".to_string(),
+            params: fc.args.clone(),
+            department: ctx.department.clone(),
+            description: format!("Running the test suite in {}.", ctx.workspace_root.display()),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }, Some(ctx.mission_id.clone())).await;
+
+        if !approved {
+            *output_text = format!("(Test run REJECTED by Oversight) {}", output_text);
+            return Ok(None);
+        }
+
+        let mut parts = self.state.run_tests_command.split_whitespace();
+        let program = parts.next().unwrap_or("cargo");
+
+        let mut cmd = tokio::process::Command::new(program);
+        cmd.args(parts).kill_on_drop(true);
+        tokio::fs::create_dir_all(&ctx.workspace_root).await?;
+        cmd.current_dir(&ctx.workspace_root);
+
+        let output = match timeout(Duration::from_secs(RUN_TESTS_TIMEOUT_SECS), cmd.output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => {
+                *output_text = format!("(TEST RUN FAILED to start: {}) {}", e, output_text);
+                return Ok(None);
+            }
+            Err(_) => {
+                *output_text = format!("(TEST RUN TIMEOUT: exceeded {} seconds and was terminated) {}", RUN_TESTS_TIMEOUT_SECS, output_text);
+                return Ok(None);
+            }
+        };
+
+        // Equivalent of `2>&1`: both streams are reported together
+        let mut combined = String::from_utf8_lossy(&output.stdout).to_string();
+        combined.push_str(&String::from_utf8_lossy(&output.stderr));
+
+        let (passed, failed, ignored) = Self::parse_test_counts(&combined);
+        let summary = format!("Tests: {} passed, {} failed, {} ignored", passed, failed, ignored);
+        let truncated = if combined.chars().count() > 5000 {
+            format!("{}... [TRUNCATED]", combined.chars().take(5000).collect::<String>())
+        } else {
+            combined
+        };
+
+        self.state.broadcast_sys(&format!("🧪 Workspace: {} ran tests — {}", ctx.name, summary), if failed > 0 { "warning" } else { "success" });
+
+        let synthesis_prompt = format!(
+            "You ran the workspace test suite (exit status: {}).\n\n{}\n\nFULL OUTPUT:\n{}\n\n{}\n\nPlease address the user's initial request based on these results.",
+            output.status, summary, truncated, output_text
+        );
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+
+        Ok(None)
+    }
+
+    /// Sums the counts from every `test result:` line (a workspace run prints one per test binary).
+    fn parse_test_counts(output: &str) -> (u64, u64, u64) {
+        use once_cell::sync::Lazy;
+        static TEST_RESULT: Lazy<regex::Regex> = Lazy::new(|| {
+            regex::Regex::new(r"test result: (?:ok|FAILED)\. (\d+) passed; (\d+) failed; (\d+) ignored").expect("valid test result regex")
+        });
+
+        TEST_RESULT.captures_iter(output).fold((0, 0, 0), |(p, f, i), caps| {
+            let n = |idx: usize| caps[idx].parse::<u64>().unwrap_or(0);
+            (p + n(1), f + n(2), i + n(3))
+        })
+    }
+
+    /// Returns why `command` is refused by the `execute_bash` deny-list, if it is: a recursive
+    /// forced `rm` of `/`, or `format`, `shutdown` or `reboot` anywhere in command position or
+    /// as an argument. Words are compared by basename, so `/sbin/reboot` and `FORMAT.COM` count.
+    fn bash_deny_reason(command: &str) -> Option<String> {
+        const DENIED_PROGRAMS: [&str; 3] = ["format", "shutdown", "reboot"];
+
+        let segments = command.split([';', '|', '&', '\n', '(', ')', '`', '$']);
+        for segment in segments {
+            let words: Vec<&str> = segment.split_whitespace().collect();
+            for word in &words {
+                let name = word.rsplit(['/', '\\']).next().unwrap_or(word).to_lowercase();
+                let name = name.trim_end_matches(".exe").trim_end_matches(".com");
+                if DENIED_PROGRAMS.contains(&name) {
+                    return Some(format!("'{}' is on the deny-list", name));
                 }
-                Err(e) => {
-                    *output_text = format!("(DELETE FAILED: {}) {}", e, output_text);
+            }
+
+            let Some(rm) = words.iter().position(|w| w.rsplit('/').next() == Some("rm")) else { continue };
+            let args = &words[rm + 1..];
+            let flags: String = args.iter().filter(|a| a.starts_with('-') && !a.starts_with("--")).map(|a| &a[1..]).collect();
+            let recursive = flags.contains('r') || flags.contains('R') || args.contains(&"--recursive");
+            let forced = flags.contains('f') || args.contains(&"--force");
+            if recursive && forced && args.iter().any(|a| matches!(*a, "/" | "/*" | "'/'" | "\"/\"")) {
+                return Some("'rm -rf /' is on the deny-list".to_string());
+            }
+        }
+        None
+    }
+
+    /// Handles `execute_bash`: runs a shell command in the workspace after oversight.
+    /// The process has no controlling terminal, gets `TADPOLE_AGENT_ID`, and is killed after
+    /// `EXECUTE_BASH_TIMEOUT_SECS`. Stdout and stderr are logged to the mission as `info` and
+    /// `warning` steps before the output is synthesized.
+    async fn handle_execute_bash(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        const EXECUTE_BASH_TIMEOUT_SECS: u64 = 60;
+
+        let command = fc.args.get("command").and_then(|v| v.as_str()).unwrap_or("").trim();
+        if command.is_empty() {
+            *output_text = format!("(BASH FAILED: command must not be empty) {}", output_text);
+            return Ok(None);
+        }
+        if let Some(reason) = Self::bash_deny_reason(command) {
+            tracing::warn!("🚫 [Bash] Agent {} tried a denied command: {}", ctx.agent_id, command);
+            self.state.broadcast_sys(&format!("🚫 Bash: blocked a denied command from {}", ctx.name), "warning");
+            *output_text = format!("(BASH BLOCKED: {}) {}", reason, output_text);
+            return Ok(None);
+        }
+
+        let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
+        let working_dir = match fc.args.get("working_dir").and_then(|v| v.as_str()).filter(|d| !d.trim().is_empty()) {
+            Some(dir) => adapter.get_safe_path(dir)?,
+            None => ctx.workspace_root.clone(),
+        };
+
+        tracing::info!("💻 [Bash] Agent {} requesting shell command (Waiting for Oversight): {}", ctx.agent_id, command);
+        self.state.broadcast_sys(&format!("💻 Oversight: {} wants to run a shell command. Review required.", ctx.name), "warning");
+
+        let approved = self.submit_oversight(crate::agent::types::ToolCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: ctx.agent_id.clone(),
+            mission_id: Some(ctx.mission_id.clone()),
+            skill: "execute_bash".to_string(),
+            params: fc.args.clone(),
+            department: ctx.department.clone(),
+            description: format!("Running `{}` in {}.", command, working_dir.display()),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }, Some(ctx.mission_id.clone())).await;
+
+        if !approved {
+            *output_text = format!("(Shell command REJECTED by Oversight) {}", output_text);
+            return Ok(None);
+        }
+
+        let mut cmd = if cfg!(windows) {
+            let mut c = tokio::process::Command::new("cmd.exe");
+            c.arg("/C").arg(command);
+            c
+        } else {
+            let mut c = tokio::process::Command::new("bash");
+            c.arg("-c").arg(command);
+            c
+        };
+        tokio::fs::create_dir_all(&working_dir).await?;
+        cmd.current_dir(&working_dir)
+            .env("TADPOLE_AGENT_ID", &ctx.agent_id)
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true);
+        // A new session has no controlling terminal, so the command cannot read from or write to ours
+        #[cfg(unix)]
+        // SAFETY: setsid is async-signal-safe and only affects the forked child.
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
                 }
+                Ok(())
+            });
+        }
+
+        let output = match timeout(Duration::from_secs(EXECUTE_BASH_TIMEOUT_SECS), cmd.output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => {
+                *output_text = format!("(BASH FAILED to start: {}) {}", e, output_text);
+                return Ok(None);
+            }
+            Err(_) => {
+                *output_text = format!("(BASH TIMEOUT: exceeded {} seconds and was terminated) {}", EXECUTE_BASH_TIMEOUT_SECS, output_text);
+                return Ok(None);
             }
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let truncate = |text: &str| if text.chars().count() > 5000 {
+            format!("{}... [TRUNCATED]", text.chars().take(5000).collect::<String>())
         } else {
-            *output_text = format!("(Delete REJECTED by Oversight) {}", output_text);
+            text.to_string()
+        };
+        let metadata = serde_json::json!({ "command": command, "exitCode": output.status.code() });
+        for (stream, text, severity) in [("stdout", &stdout, "info"), ("stderr", &stderr, "warning")] {
+            if text.trim().is_empty() {
+                continue;
+            }
+            crate::agent::mission::log_step_and_broadcast(
+                &self.state,
+                &ctx.mission_id,
+                &ctx.agent_id,
+                "System",
+                &format!("$ {} ({})\n{}", command, stream, truncate(text)),
+                severity,
+                Some(metadata.clone())
+            ).await?;
+        }
+
+        let synthesis_prompt = format!(
+            "You ran the shell command `{}` (exit status: {}).\n\nSTDOUT:\n{}\n\nSTDERR:\n{}\n\n{}\n\nPlease address the user's initial request based on this result.",
+            command, output.status, truncate(&stdout), truncate(&stderr), output_text
+        );
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+
+        Ok(None)
+    }
+
+    /// Handles `query_missions_db`: runs an agent-written `SELECT` over a snapshot of the
+    /// mission tables after oversight and synthesizes the rows. Disallowed SQL is refused
+    /// before it reaches Oversight.
+    async fn handle_query_missions_db(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        let sql = fc.args.get("sql").and_then(|v| v.as_str()).unwrap_or("").trim();
+        if let Err(reason) = crate::agent::mission::validate_analytics_sql(sql) {
+            tracing::warn!("🚫 [Analytics] Agent {} submitted a refused query: {}", ctx.agent_id, sql);
+            *output_text = format!("(QUERY BLOCKED: {}) {}", reason, output_text);
+            return Ok(None);
+        }
+
+        tracing::info!("📈 [Analytics] Agent {} requesting mission query (Waiting for Oversight): {}", ctx.agent_id, sql);
+        self.state.broadcast_sys(&format!("📈 Oversight: {} wants to query mission history. Review required.", ctx.name), "warning");
+
+        let approved = self.submit_oversight(crate::agent::types::ToolCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: ctx.agent_id.clone(),
+            mission_id: Some(ctx.mission_id.clone()),
+            skill: "query_missions_db".to_string(),
+            params: fc.args.clone(),
+            department: ctx.department.clone(),
+            description: format!("Running analytics query `{}`.", sql),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }, Some(ctx.mission_id.clone())).await;
+
+        if !approved {
+            *output_text = format!("(Analytics query REJECTED by Oversight) {}", output_text);
+            return Ok(None);
+        }
+
+        let rows = match crate::agent::mission::query_missions_readonly(&self.state.pool, sql).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                *output_text = format!("(QUERY FAILED: {}) {}", e, output_text);
+                return Ok(None);
+            }
+        };
+
+        let synthesis_prompt = format!(
+            "ANALYTICS QUERY `{}` RETURNED {} ROW(S) (capped at {}):\n\n{}\n\n{}\n\nPlease address the user's initial request based on these results.",
+            sql,
+            rows.len(),
+            crate::agent::mission::MAX_ANALYTICS_ROWS,
+            serde_json::to_string_pretty(&rows).unwrap_or_default(),
+            output_text
+        );
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+
+        Ok(None)
+    }
+
+    /// Handles `format_code`: pipes a workspace file through the language's formatter and writes
+    /// the result back through the workspace adapter. The formatter never sees a path inside the
+    /// workspace, so it cannot follow out-of-line modules, load project-local config or plugins,
+    /// or run binaries an agent planted there. A missing formatter is reported as a warning.
+    async fn handle_format_code(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        use tokio::io::AsyncWriteExt;
+        const FORMAT_TIMEOUT_SECS: u64 = 10;
+
+        let filename = fc.args.get("filename").and_then(|v| v.as_str()).unwrap_or("");
+        let language = fc.args.get("language").and_then(|v| v.as_str()).unwrap_or("").to_lowercase();
+
+        let (program, args): (&str, Vec<&str>) = match language.as_str() {
+            "rust" => ("rustfmt", vec!["--emit", "stdout", "--edition", "2021"]),
+            // Installed prettier only: `npx` would prefer a workspace `node_modules/.bin` or download one
+            "javascript" | "typescript" => ("prettier", vec!["--no-config", "--no-editorconfig", "--stdin-filepath", filename]),
+            "python" => ("black", vec!["--quiet", "-"]),
+            other => {
+                *output_text = format!("(FORMAT FAILED: unsupported language '{}') {}", other, output_text);
+                return Ok(None);
+            }
+        };
+
+        tracing::info!("🧹 [Workspace] Agent {} formatting {} ({})", ctx.agent_id, filename, language);
+
+        let adapter = crate::adapter::filesystem::FilesystemAdapter::new(ctx.workspace_root.clone());
+        let before = adapter.read_file(filename).await?;
+
+        let mut cmd = tokio::process::Command::new(program);
+        cmd.args(&args)
+            .current_dir(std::env::temp_dir())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+
+        let run = async {
+            let mut child = cmd.spawn()?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(before.as_bytes()).await?;
+            }
+            child.wait_with_output().await
+        };
+
+        match timeout(Duration::from_secs(FORMAT_TIMEOUT_SECS), run).await {
+            Ok(Ok(output)) if output.status.success() => {
+                let after = String::from_utf8_lossy(&output.stdout).into_owned();
+                let changed = Self::count_changed_lines(&before, &after);
+                if changed > 0 {
+                    adapter.write_file(filename, &after).await?;
+                }
+                self.state.broadcast_sys(&format!("🧹 Workspace: {} formatted {}", ctx.name, filename), "success");
+                *output_text = format!("(Formatted {} with {}: {} lines changed) {}", filename, program, changed, output_text);
+            }
+            Ok(Ok(output)) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                *output_text = format!("(FORMAT WARNING: {} exited with {}: {}) {}", program, output.status, stderr.trim(), output_text);
+            }
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::warn!("⚠️ [Workspace] Formatter '{}' is not installed; skipping format of {}", program, filename);
+                *output_text = format!("(FORMAT WARNING: formatter '{}' is not available on this host; file left unchanged) {}", program, output_text);
+            }
+            Ok(Err(e)) => {
+                *output_text = format!("(FORMAT WARNING: could not run {}: {}) {}", program, e, output_text);
+            }
+            Err(_) => {
+                *output_text = format!("(FORMAT WARNING: {} took longer than {} seconds and was terminated) {}", program, FORMAT_TIMEOUT_SECS, output_text);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Line-by-line difference count, plus any lines added or removed at the end.
+    fn count_changed_lines(before: &str, after: &str) -> usize {
+        let before: Vec<&str> = before.lines().collect();
+        let after: Vec<&str> = after.lines().collect();
+        let differing = before.iter().zip(after.iter()).filter(|(b, a)| b != a).count();
+        differing + before.len().abs_diff(after.len())
+    }
+
+    /// Handles `read_identity`: reads the global OS identity document. Read-only, no oversight.
+    async fn handle_read_identity(
+        &self,
+        ctx: &RunContext,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        tracing::info!("🪪 [Context] Agent {} reading OS identity", ctx.agent_id);
+
+        match tokio::fs::read_to_string(self.state.data_dir.join(IDENTITY_FILE)).await {
+            Ok(identity) => {
+                let synthesis_prompt = format!(
+                    "You read the global OS identity. Here is the content:\n\n{}\n\n{}\n\nPlease address the user's initial request based on this.",
+                    identity, output_text
+                );
+                let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+                *output_text = final_text;
+                self.accumulate_usage(usage, final_usage);
+            }
+            Err(e) => {
+                *output_text = format!("(IDENTITY READ FAILED: {}) {}", e, output_text);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Handles `remember_fact`: stores a key/value note the agent sees again in later missions.
+    async fn handle_remember_fact(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        let key = fc.args.get("key").and_then(|v| v.as_str()).unwrap_or("");
+        let value = fc.args.get("value").and_then(|v| v.as_str()).unwrap_or("");
+        let ttl_secs = fc.args.get("ttl_secs").and_then(|v| v.as_i64());
+
+        match crate::agent::memory::remember_fact(&self.state.pool, &ctx.agent_id, key, value, ttl_secs).await {
+            Ok(()) => {
+                tracing::info!("🧠 [Memory] Agent {} remembered '{}'", ctx.agent_id, key.trim());
+                *output_text = format!("(Remembered '{}') {}", key.trim(), output_text);
+            }
+            Err(e) => {
+                *output_text = format!("(REMEMBER FAILED: {}) {}", e, output_text);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Handles `recall_fact`: looks up one of the agent's notes; `null` if it is unknown or expired.
+    async fn handle_recall_fact(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+        usage: &mut Option<crate::agent::types::TokenUsage>,
+    ) -> Result<Option<String>, ToolError> {
+        let key = fc.args.get("key").and_then(|v| v.as_str()).unwrap_or("");
+        let value = crate::agent::memory::recall_fact(&self.state.pool, &ctx.agent_id, key).await?;
+        tracing::info!("🧠 [Memory] Agent {} recalled '{}' ({})", ctx.agent_id, key, if value.is_some() { "found" } else { "not found" });
+
+        let synthesis_prompt = format!(
+            "You recalled the fact '{}' from your memory. Its stored value (null if nothing is stored or it has expired):\n\n{}\n\nPlease address the user's initial request based on this.",
+            key, serde_json::Value::from(value)
+        );
+        let (final_text, _, final_usage) = self.call_provider_for_synthesis(ctx, &synthesis_prompt).await.map_err(ToolError::from_provider)?;
+        *output_text = final_text;
+        self.accumulate_usage(usage, final_usage);
+        Ok(None)
+    }
+
+    /// Handles `update_memory`: appends a timestamped entry to the long-term memory ledger after oversight.
+    async fn handle_update_memory(
+        &self,
+        ctx: &RunContext,
+        fc: &crate::agent::types::GeminiFunctionCall,
+        output_text: &mut String,
+    ) -> Result<Option<String>, ToolError> {
+        let entry = fc.args.get("entry").and_then(|v| v.as_str()).unwrap_or("").trim();
+
+        if entry.is_empty() {
+            *output_text = format!("(MEMORY UPDATE FAILED: 'entry' is required) {}", output_text);
+            return Ok(None);
+        }
+        if entry.chars().count() > MAX_MEMORY_ENTRY_CHARS {
+            *output_text = format!("(MEMORY UPDATE FAILED: entry exceeds {} characters) {}", MAX_MEMORY_ENTRY_CHARS, output_text);
+            return Ok(None);
+        }
+        let entry = Self::sanitize_memory_entry(entry);
+
+        tracing::info!("🧠 [Context] Agent {} updating long-term memory (Waiting for Oversight)...", ctx.agent_id);
+        self.state.broadcast_sys(&format!("🧠 Oversight: {} wants to update long-term memory. Review required.", ctx.name), "warning");
+
+        let approved = self.submit_oversight(crate::agent::types::ToolCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: ctx.agent_id.clone(),
+            mission_id: Some(ctx.mission_id.clone()),
+            skill: "update_memory".to_string(),
+            params: serde_json::json!({ "entry": entry }),
+            department: ctx.department.clone(),
+            description: "Appending an entry to the shared long-term swarm memory.".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }, Some(ctx.mission_id.clone())).await;
+
+        if !approved {
+            *output_text = format!("(Memory update REJECTED by Oversight) {}", output_text);
+            return Ok(None);
+        }
+
+        let block = format!("\n### {} ({})\n{}\n", chrono::Utc::now().to_rfc3339(), ctx.name, entry);
+        match Self::append_memory_atomically(&self.state.data_dir.join(MEMORY_FILE), &block).await {
+            Ok(_) => {
+                self.state.reload_system_context().await;
+                self.state.broadcast_sys(&format!("🧠 Context: {} updated long-term memory", ctx.name), "success");
+                *output_text = format!("(Long-term memory updated) {}", output_text);
+            }
+            Err(e) => {
+                *output_text = format!("(MEMORY UPDATE FAILED: {}) {}", e, output_text);
+            }
         }
+        Ok(None)
+    }
+
+    /// Neutralizes control markers so a memory entry cannot flip prompts into (or fake) safe mode.
+    fn sanitize_memory_entry(entry: &str) -> String {
+        use once_cell::sync::Lazy;
+        static SAFE_MODE_MARKER: Lazy<regex::Regex> = Lazy::new(|| {
+            regex::Regex::new(r"(?i)\[\s*BRAINSTORM\s+SAFE\s+MODE\s+ACTIVE\s*\]").expect("valid marker regex")
+        });
+        SAFE_MODE_MARKER.replace_all(entry, "[REDACTED MARKER]").into_owned()
+    }
 
+    /// Appends to the ledger by writing a sibling temp file and renaming it over the original,
+    /// so readers never observe a half-written file.
+    async fn append_memory_atomically(path: &std::path::Path, block: &str) -> anyhow::Result<()> {
+        use once_cell::sync::Lazy;
+        // Serializes read-modify-rename cycles so concurrent appends are not lost
+        static MEMORY_WRITE_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+        let _guard = MEMORY_WRITE_LOCK.lock().await;
+
+        let mut content = match tokio::fs::read_to_string(path).await {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        content.push_str(block);
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp_path = path.with_extension(format!("md.tmp-{}", uuid::Uuid::new_v4()));
+        tokio::fs::write(&tmp_path, content).await?;
+        if let Err(e) = tokio::fs::rename(&tmp_path, path).await {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(e.into());
+        }
         Ok(())
     }
 
@@ -1294,7 +3499,7 @@ impl AgentRunner {
         ctx: &RunContext,
         fc: &crate::agent::types::GeminiFunctionCall,
         output_text: &mut String,
-    ) -> anyhow::Result<()> {
+    ) -> Result<Option<String>, ToolError> {
         let cap_type_str = fc.args.get("type").and_then(|v| v.as_str()).unwrap_or("skill");
         let name = fc.args.get("name").and_then(|v| v.as_str()).unwrap_or("unnamed");
         
@@ -1324,7 +3529,7 @@ impl AgentRunner {
             *output_text = format!("(Capability Proposal for {} REJECTED by Oversight) {}", name, output_text);
         }
 
-        Ok(())
+        Ok(None)
     }
 
     /// Submits a capability proposal for manual user approval.
@@ -1347,6 +3552,7 @@ impl AgentRunner {
         };
 
         self.state.oversight_queue.insert(entry_id.clone(), entry.clone());
+        self.state.metrics.oversight_pending.set(self.state.oversight_queue.len() as i64);
         let (tx, rx) = tokio::sync::oneshot::channel();
         self.state.oversight_resolvers.insert(entry_id.clone(), tx);
 
@@ -1355,10 +3561,7 @@ impl AgentRunner {
             "entry": entry
         }));
 
-        match rx.await {
-            Ok(approved) => approved,
-            Err(_) => false,
-        }
+        rx.await.unwrap_or_default()
     }
     /// Finalizes the run: updates token usage, persists mission state, broadcasts results.
     async fn finalize_run(
@@ -1417,20 +3620,36 @@ impl AgentRunner {
             usage.as_ref().map(|u| u.output_tokens).unwrap_or(0)
         );
         
-        crate::agent::mission::update_mission(&self.state.pool, &ctx.mission_id, crate::agent::types::MissionStatus::Completed, final_cumulative_cost).await?;
-        crate::agent::mission::log_step(
-            &self.state.pool,
+        crate::agent::mission::update_mission_and_broadcast(&self.state, &ctx.mission_id, crate::agent::types::MissionStatus::Completed, final_cumulative_cost).await?;
+        self.state.missions_completed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.record_task_metrics(ctx, usage, final_cumulative_cost);
+        crate::agent::mission::log_step_and_broadcast(
+            &self.state,
             &ctx.mission_id,
             &ctx.agent_id,
             "Agent",
             output_text,
             "success",
-            None
+            // Kept on the step so per-agent token totals and token stats can be rebuilt from the logs
+            usage.as_ref().map(|u| serde_json::json!({ "usage": u, "costUsd": final_cumulative_cost }))
         ).await?;
+        self.notify_callback(&ctx.mission_id, crate::agent::types::MissionStatus::Completed, &final_delivery).await;
 
         Ok(final_delivery)
     }
 
+    /// Records a completed task's outcome, duration, token usage and cost for `GET /metrics`.
+    fn record_task_metrics(&self, ctx: &RunContext, usage: &Option<crate::agent::types::TokenUsage>, cost_usd: f64) {
+        let metrics = &self.state.metrics;
+        metrics.agent_task_total.with_label_values(&[&ctx.agent_id, "completed"]).inc();
+        metrics.agent_task_duration_seconds.with_label_values(&[&ctx.agent_id]).observe(ctx.started_at.elapsed().as_secs_f64());
+        if let Some(u) = usage {
+            metrics.token_usage_total.with_label_values(&[&ctx.agent_id, "input"]).inc_by(u.input_tokens as f64);
+            metrics.token_usage_total.with_label_values(&[&ctx.agent_id, "output"]).inc_by(u.output_tokens as f64);
+        }
+        metrics.mission_cost_usd_total.with_label_values(&[&ctx.agent_id]).inc_by(cost_usd);
+    }
+
     // ─────────────────────────────────────────────────────────
     //  UTILITIES
     // ─────────────────────────────────────────────────────────
@@ -1438,11 +3657,46 @@ impl AgentRunner {
     /// Submits a tool call for manual user approval.
     /// Returns true if approved, false if rejected.
     #[allow(dead_code)]
-    pub async fn submit_oversight(&self, mut tool_call: crate::agent::types::ToolCall, mission_id: Option<String>) -> bool {
-        let entry_id = uuid::Uuid::new_v4().to_string();
-        
-        tool_call.mission_id = mission_id.clone();
-        
+    #[tracing::instrument(name = "submit_oversight", skip_all, fields(agent.id = %tool_call.agent_id, tool.name = %tool_call.skill))]
+    pub async fn submit_oversight(&self, tool_call: crate::agent::types::ToolCall, mission_id: Option<String>) -> bool {
+        let deadline = Duration::from_secs(self.state.oversight_timeout_secs);
+        self.submit_oversight_with_deadline(tool_call, mission_id, deadline).await
+    }
+
+    /// `submit_oversight` with an explicit deadline. An unanswered entry is marked `timed_out`,
+    /// recorded in the ledger, announced as `oversight:timeout` and treated as a rejection.
+    async fn submit_oversight_with_deadline(
+        &self,
+        mut tool_call: crate::agent::types::ToolCall,
+        mission_id: Option<String>,
+        deadline: Duration,
+    ) -> bool {
+        let entry_id = uuid::Uuid::new_v4().to_string();
+        
+        tool_call.mission_id = mission_id.clone();
+
+        // 0. Let the policy decide without a human when a rule matches
+        let decision = match self.state.oversight_policy.evaluate(&tool_call) {
+            crate::agent::oversight_policy::PolicyAction::AutoApprove => Some("approved"),
+            crate::agent::oversight_policy::PolicyAction::AutoReject => Some("rejected"),
+            crate::agent::oversight_policy::PolicyAction::RequireHuman => None,
+        };
+        if let Some(decision) = decision {
+            tracing::info!("🛡️ [Oversight] Policy {} {} for agent {}", decision, tool_call.skill, tool_call.agent_id);
+            self.state.record_oversight_decision(&entry_id, decision, "policy", Some(tool_call.clone())).await;
+            self.state.emit_event(serde_json::json!({
+                "type": "oversight:decided",
+                "entry": {
+                    "id": entry_id,
+                    "decision": decision,
+                    "decidedBy": "policy",
+                    "decidedAt": chrono::Utc::now().to_rfc3339(),
+                    "toolCall": tool_call
+                }
+            }));
+            return decision == "approved";
+        }
+
         let entry = crate::agent::types::OversightEntry {
             id: entry_id.clone(),
             mission_id,
@@ -1454,6 +3708,7 @@ impl AgentRunner {
 
         // 1. Register in the queue
         self.state.oversight_queue.insert(entry_id.clone(), entry.clone());
+        self.state.metrics.oversight_pending.set(self.state.oversight_queue.len() as i64);
 
         // 2. Create a channel for the decision
         let (tx, rx) = tokio::sync::oneshot::channel();
@@ -1465,10 +3720,29 @@ impl AgentRunner {
             "entry": entry
         }));
 
-        // 4. Await the user's click in the dashboard
-        match rx.await {
-            Ok(approved) => approved,
-            Err(_) => false, // Resolver dropped
+        // 4. Await the user's click in the dashboard (a dropped resolver counts as rejection)
+        match timeout(deadline, rx).await {
+            Ok(decision) => decision.unwrap_or_default(),
+            Err(_) => {
+                tracing::warn!("⏱️ [Oversight] Entry {} timed out after {}s without a decision", entry_id, deadline.as_secs());
+                self.state.oversight_resolvers.remove(&entry_id);
+                let timed_out = self.state.oversight_queue.remove(&entry_id).map(|(_, mut e)| {
+                    e.status = "timed_out".to_string();
+                    e
+                });
+
+                self.state.record_oversight_decision(
+                    &entry_id,
+                    "timed_out",
+                    "system",
+                    timed_out.as_ref().and_then(|e| e.tool_call.clone()),
+                ).await;
+                self.state.emit_event(serde_json::json!({
+                    "type": "oversight:timeout",
+                    "entry": timed_out
+                }));
+                false
+            }
         }
     }
 
@@ -1498,8 +3772,103 @@ impl AgentRunner {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::test_oversight::approve_pending;
     use crate::agent::types::TaskPayload;
 
+    /// State backed by a throwaway database, for tests that persist agents.
+    async fn isolated_state() -> Arc<crate::state::AppState> {
+        Arc::new(crate::state::AppState::for_tests(Default::default()).await)
+    }
+
+    /// Base URL of a stand-in Ollama server that answers every chat request with the request's
+    /// last message, so synthesis paths run offline. Started once and shared by every test.
+    fn echo_provider_url() -> &'static str {
+        static URL: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+        URL.get_or_init(|| {
+            // A plain thread, not a task: it must outlive each test's runtime
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            std::thread::spawn(move || {
+                for socket in listener.incoming().flatten() {
+                    std::thread::spawn(move || echo_chat_reply(socket));
+                }
+            });
+            url
+        })
+    }
+
+    fn echo_chat_reply(mut socket: std::net::TcpStream) {
+        use std::io::{BufRead, Read, Write};
+
+        let mut reader = std::io::BufReader::new(socket.try_clone().unwrap());
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+        let request: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+        let prompt = request["messages"].as_array()
+            .and_then(|messages| messages.last())
+            .and_then(|message| message["content"].as_str())
+            .unwrap_or_default();
+        let reply = serde_json::json!({ "message": { "role": "assistant", "content": prompt } }).to_string();
+        let _ = write!(socket, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", reply.len(), reply);
+    }
+
+    /// Agent 1's model, pointed at the echo provider (see `echo_provider_url`) with no rate limits.
+    fn echo_model_config(state: &crate::state::AppState) -> ModelConfig {
+        ModelConfig {
+            provider: "ollama".to_string(),
+            model_id: "echo".to_string(),
+            base_url: Some(echo_provider_url().to_string()),
+            rpm: None,
+            rpd: None,
+            tpm: None,
+            tpd: None,
+            ..state.agents.get("1").unwrap().model.clone()
+        }
+    }
+
+    /// Context for `agent_id` running `mission_id` on the echo provider; override fields with `..test_ctx(..)`.
+    fn test_ctx(state: &crate::state::AppState, agent_id: &str, mission_id: &str) -> RunContext {
+        RunContext {
+            agent_id: agent_id.to_string(),
+            name: "Test Agent".to_string(),
+            role: "engineer".to_string(),
+            department: "Engineering".to_string(),
+            description: "desc".to_string(),
+            mission_id: mission_id.to_string(),
+            model_config: echo_model_config(state),
+            provider_name: "ollama".to_string(),
+            skills: vec![],
+            skill_allowlist: None,
+            workflows: vec![],
+            depth: 0,
+            lineage: vec![],
+            workspace_root: std::path::PathBuf::from("workspaces/executive-core"),
+            safe_mode: false,
+            history: Vec::new(),
+            task_timeout_secs: DEFAULT_TASK_TIMEOUT_SECS,
+            started_at: std::time::Instant::now(),
+            trace_id: String::new(),
+        }
+    }
+
     fn make_payload(msg: &str) -> TaskPayload {
         TaskPayload {
             message: msg.to_string(),
@@ -1516,12 +3885,18 @@ mod tests {
             swarm_lineage: None,
             external_id: None,
             safe_mode: None,
+            timeout_secs: None,
+            depends_on: None,
+            trace_id: None,
+            tags: None,
+            callback_url: None,
+            callback_secret: None,
         }
     }
 
     #[tokio::test]
     async fn test_finalize_run_fallback_on_empty_output() {
-        let state = Arc::new(crate::state::AppState::new().await);
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
         let runner = AgentRunner::new(state.clone());
         
         let test_uuid = uuid::Uuid::new_v4().to_string();
@@ -1532,12 +3907,9 @@ mod tests {
         sqlx::query("INSERT INTO mission_history (id, agent_id, title, status) VALUES (?, ?, 'Test Mission', 'active')").bind(&mission_id).bind(&agent_id).execute(&state.pool).await.unwrap();
         
         let ctx = RunContext {
-            agent_id: agent_id.clone(),
             name: "Test Runner".to_string(),
             role: "tester".to_string(),
             department: "QA".to_string(),
-            description: "desc".to_string(),
-            mission_id: mission_id.clone(),
             model_config: crate::agent::types::ModelConfig {
                 provider: "mock".to_string(),
                 model_id: "mock".to_string(),
@@ -1551,14 +3923,11 @@ mod tests {
                 rpd: None,
                 tpm: None,
                 tpd: None,
+                max_history_turns: crate::agent::types::DEFAULT_MAX_HISTORY_TURNS,
+                safety_settings: None,
             },
-            provider_name: "mock".to_string(),
-            skills: vec![],
-            workflows: vec![],
-            depth: 0,
-            lineage: vec![],
             workspace_root: std::path::PathBuf::from("."),
-            safe_mode: false,
+            ..test_ctx(&state, &agent_id, &mission_id)
         };
         
         let result_empty = runner.finalize_run(&ctx, "   \n  \t ", &None).await.unwrap();
@@ -1568,9 +3937,349 @@ mod tests {
         assert_eq!(result_normal, "Hello Context!");
     }
 
+    #[tokio::test]
+    async fn finished_missions_post_a_signed_callback() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let mut invalid = make_payload("Report back");
+        invalid.callback_url = Some("javascript:alert(1)".to_string());
+        assert!(runner.prepare_mission("1", &invalid).await.is_err(), "bad callback URLs are rejected up front");
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = tokio::sync::oneshot::channel::<(String, Vec<u8>)>();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                raw.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&raw).to_string();
+                let Some(header_end) = text.find("\r\n\r\n").map(|p| p + 4) else { continue };
+                let length: usize = text[..header_end].lines()
+                    .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                    .unwrap_or(0);
+                if raw.len() >= header_end + length {
+                    let signature = text[..header_end].lines()
+                        .find_map(|l| l.split_once(':').filter(|(k, _)| k.eq_ignore_ascii_case(crate::adapter::webhook::SIGNATURE_HEADER)).map(|(_, v)| v.trim().to_string()))
+                        .unwrap_or_default();
+                    socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+                    let _ = tx.send((signature, raw[header_end..header_end + length].to_vec()));
+                    return;
+                }
+            }
+        });
+
+        let mut payload = make_payload("Report back");
+        payload.callback_url = Some(format!("http://{}/done", addr));
+        payload.callback_secret = Some("pipeline-secret".to_string());
+        let mission_id = runner.prepare_mission("1", &payload).await.unwrap();
+        let mut ctx = runner.resolve_agent_context("1", &payload, &mission_id, 0, &[]).await.unwrap();
+        ctx.provider_name = "mock".to_string();
+        runner.finalize_run(&ctx, "Quarterly report filed.", &None).await.unwrap();
+
+        let (signature, body) = tokio::time::timeout(std::time::Duration::from_secs(5), rx).await.unwrap().unwrap();
+        assert_eq!(signature, crate::adapter::webhook::sign(&body, "pipeline-secret"));
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["mission_id"], mission_id.as_str());
+        assert_eq!(json["status"], "completed");
+        assert_eq!(json["output_summary"], "Quarterly report filed.");
+        assert!(json["cost_usd"].is_number());
+    }
+
+    #[tokio::test]
+    async fn uptime_reports_lifetime_mission_outcomes() {
+        use std::sync::atomic::Ordering;
+
+        let state = isolated_state().await;
+        state.missions_completed.store(0, Ordering::Relaxed);
+        state.missions_failed.store(0, Ordering::Relaxed);
+        let runner = AgentRunner::new(state.clone());
+
+        // Failure: an unknown provider errors out inside the provider call
+        let mut failing = make_payload("This mission is doomed");
+        failing.provider = Some("mock".to_string());
+        assert!(runner.run("1".to_string(), failing).await.is_err());
+
+        // Success: drive a prepared mission straight through finalization
+        let payload = make_payload("This mission succeeds");
+        let mission_id = runner.prepare_mission("1", &payload).await.unwrap();
+        let mut ctx = runner.resolve_agent_context("1", &payload, &mission_id, 0, &[]).await.unwrap();
+        ctx.provider_name = "mock".to_string();
+        runner.finalize_run(&ctx, "All done", &None).await.unwrap();
+
+        // Spend comes from mission history, so resetting the agent's cost does not lower it
+        let billed = crate::agent::mission::create_mission(&state.pool, "1", "Billed mission", 1.0, &[]).await.unwrap();
+        crate::agent::mission::update_mission(&state.pool, &billed.id, crate::agent::types::MissionStatus::Completed, 0.25).await.unwrap();
+        state.agents.get_mut("1").unwrap().cost_usd = 0.0;
+
+        let response = axum::response::IntoResponse::into_response(crate::routes::engine_control::get_uptime(axum::extract::State(state.clone())).await);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["totalCostUsd"], 0.25);
+        assert_eq!(json["missionsCompletedLifetime"], 1);
+        assert_eq!(json["missionsFailedLifetime"], 1);
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert!(json["startedAt"].is_string());
+    }
+
+    #[tokio::test]
+    async fn metrics_are_recorded_after_a_task() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let payload = make_payload("Count this mission");
+        let mission_id = runner.prepare_mission("1", &payload).await.unwrap();
+        let mut ctx = runner.resolve_agent_context("1", &payload, &mission_id, 0, &[]).await.unwrap();
+        ctx.provider_name = "mock".to_string();
+        let usage = Some(crate::agent::types::TokenUsage { input_tokens: 120, output_tokens: 30, total_tokens: 150 });
+        runner.finalize_run(&ctx, "All done", &usage).await.unwrap();
+
+        let response = axum::response::IntoResponse::into_response(crate::routes::metrics::get_metrics(axum::extract::State(state.clone())).await);
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("tadpole_agent_task_total{agent_id=\"1\",status=\"completed\"} 1"), "got: {}", text);
+        assert!(text.contains("tadpole_agent_task_duration_seconds_count{agent_id=\"1\"} 1"));
+        assert!(text.contains("tadpole_token_usage_total{agent_id=\"1\",direction=\"input\"} 120"));
+        assert!(text.contains("tadpole_token_usage_total{agent_id=\"1\",direction=\"output\"} 30"));
+        assert!(text.contains("tadpole_mission_cost_usd_total{agent_id=\"1\"}"));
+        assert!(text.contains("tadpole_oversight_pending 0"));
+    }
+
+    #[tokio::test]
+    async fn unanswered_oversight_times_out_as_rejection() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let mut events = state.event_tx.subscribe();
+
+        let tool_call = crate::agent::types::ToolCall {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: "1".to_string(),
+            mission_id: None,
+            skill: "deploy".to_string(),
+            params: serde_json::json!({}),
+            department: "Engineering".to_string(),
+            description: "Deploying to production.".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        let approved = runner.submit_oversight_with_deadline(tool_call, None, Duration::from_millis(100)).await;
+
+        assert!(!approved, "An unanswered entry must be rejected");
+        assert!(state.oversight_queue.is_empty());
+        assert!(state.oversight_resolvers.is_empty());
+        let ledger = state.oversight_ledger.lock().unwrap().clone();
+        assert_eq!(ledger[0]["decision"], "timed_out");
+        assert_eq!(ledger[0]["toolCall"]["skill"], "deploy");
+
+        let mut timeout_event = None;
+        while let Ok(event) = events.try_recv() {
+            if event["type"] == "oversight:timeout" {
+                timeout_event = Some(event);
+            }
+        }
+        assert_eq!(timeout_event.expect("oversight:timeout must be emitted")["entry"]["status"], "timed_out");
+    }
+
+    #[tokio::test]
+    async fn stalled_task_times_out_and_fails_mission() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let mut events = state.event_tx.subscribe();
+
+        // Accepts the provider request and never answers it
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let mut payload = make_payload("Summarize the quarter.");
+        payload.provider = Some("groq".to_string());
+        payload.api_key = Some("test-key".to_string());
+        payload.base_url = Some(format!("http://{}/openai/v1/chat/completions", addr));
+        payload.timeout_secs = Some(1);
+
+        let mission_id = runner.prepare_mission("1", &payload).await.unwrap();
+        let err = runner.run_mission_with_timeout("1".to_string(), payload, mission_id.clone()).await.unwrap_err();
+        assert!(err.to_string().contains("timed out after 1s"));
+
+        let mission = crate::agent::mission::get_mission_by_id(&state.pool, &mission_id).await.unwrap().unwrap();
+        assert_eq!(mission.status, crate::agent::types::MissionStatus::Failed);
+        assert_eq!(state.agents.get("1").unwrap().status, "idle");
+
+        let mut saw_timeout = false;
+        while let Ok(event) = events.try_recv() {
+            if event["type"] == "agent:timeout" && event["missionId"] == mission_id.as_str() {
+                assert_eq!(event["timeoutSecs"], 1);
+                saw_timeout = true;
+            }
+        }
+        assert!(saw_timeout, "agent:timeout must be emitted");
+
+        assert_eq!(task_timeout_secs(None, 3600), DEFAULT_TASK_TIMEOUT_SECS);
+        assert_eq!(task_timeout_secs(Some(0), 3600), 1);
+        assert_eq!(task_timeout_secs(Some(7200), 3600), 3600);
+    }
+
+    #[tokio::test]
+    async fn tool_calls_run_in_bounded_batches() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        const CALLS: usize = 8;
+        const BATCH: usize = 3;
+        let state = Arc::new(crate::state::AppState::for_tests(crate::config::TadpoleConfig {
+            max_parallel_tool_calls: Some(BATCH),
+            ..Default::default()
+        }).await);
+        let runner = AgentRunner::new(state.clone());
+
+        // Counting mock: each request is held until its whole batch has arrived, so a runner that
+        // exceeds the bound raises the peak and one that runs calls one by one never finishes
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (arrived_tx, arrived_rx) = tokio::sync::watch::channel(0usize);
+        let arrived_tx = Arc::new(arrived_tx);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        {
+            let in_flight = in_flight.clone();
+            let peak = peak.clone();
+            tokio::spawn(async move {
+                loop {
+                    let (mut socket, _) = listener.accept().await.unwrap();
+                    let in_flight = in_flight.clone();
+                    let peak = peak.clone();
+                    let arrived_tx = arrived_tx.clone();
+                    let mut arrived_rx = arrived_rx.clone();
+                    tokio::spawn(async move {
+                        let mut buf = [0u8; 1024];
+                        let _ = socket.read(&mut buf).await;
+                        peak.fetch_max(in_flight.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                        let mut arrived = 0;
+                        arrived_tx.send_modify(|n| { *n += 1; arrived = *n; });
+                        let batch_end = arrived.div_ceil(BATCH).saturating_mul(BATCH).min(CALLS);
+                        let _ = arrived_rx.wait_for(|n| *n >= batch_end).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        let _ = socket.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok").await;
+                    });
+                }
+            });
+        }
+
+        let ctx = RunContext {
+            name: "Researcher".to_string(),
+            role: "analyst".to_string(),
+            department: "Research".to_string(),
+            skills: vec!["fetch_url".to_string()],
+            ..test_ctx(&state, "1", "batch-mission")
+        };
+        let calls: Vec<_> = (0..CALLS).map(|i| crate::agent::types::GeminiFunctionCall {
+            name: "fetch_url".to_string(),
+            args: serde_json::json!({ "url": format!("http://{}/page/{}", addr, i) }),
+        }).collect();
+
+        let mut output = String::new();
+        let mut usage = None;
+        let run = runner.execute_tool_calls(&ctx, calls, "Research", &mut output, &mut usage);
+        let early = tokio::time::timeout(std::time::Duration::from_secs(10), run).await
+            .expect("calls within a batch must run concurrently")
+            .unwrap();
+
+        assert!(early.is_none());
+        assert_eq!(peak.load(Ordering::SeqCst), BATCH, "at most {} tool calls may be in flight", BATCH);
+        assert!((0..CALLS).all(|i| output.contains(&format!("/page/{}", i))));
+    }
+
+    #[tokio::test]
+    async fn mission_lifecycle_is_broadcast_in_order() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let mut events = state.event_tx.subscribe();
+
+        let payload = make_payload("Summarize the quarter");
+        let mission_id = runner.prepare_mission("1", &payload).await.unwrap();
+        let mut ctx = runner.resolve_agent_context("1", &payload, &mission_id, 0, &[]).await.unwrap();
+        ctx.provider_name = "mock".to_string();
+        runner.finalize_run(&ctx, "Quarter summarized", &None).await.unwrap();
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if event["missionId"] == mission_id.as_str() {
+                let detail = match event["type"].as_str() {
+                    Some("mission:status") => event["status"].as_str().unwrap().to_string(),
+                    Some("mission:step") => event["step"]["source"].as_str().unwrap().to_string(),
+                    _ => continue,
+                };
+                seen.push(format!("{} {}", event["type"].as_str().unwrap(), detail));
+            }
+        }
+        assert_eq!(seen, vec![
+            "mission:step User",
+            "mission:status completed",
+            "mission:step Agent",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn queued_mission_stays_pending_until_it_holds_a_slot() {
+        use crate::agent::types::MissionStatus;
+
+        let config = crate::config::TadpoleConfig { max_concurrent_missions: Some(1), ..Default::default() };
+        let state = Arc::new(crate::state::AppState::for_tests(config).await);
+        let runner = AgentRunner::new(state.clone());
+        let mut events = state.event_tx.subscribe();
+
+        // An unknown provider fails the run as soon as it starts, without any outbound request
+        let agent_id = format!("queued-{}", uuid::Uuid::new_v4());
+        let mut agent = state.agents.get("1").unwrap().clone();
+        agent.id = agent_id.clone();
+        agent.model.provider = "no-such-provider".to_string();
+        agent.active_model_slot = None;
+        state.register_agent(agent).await;
+
+        let held = state.acquire_mission_slot().await;
+        let mission_id = runner.prepare_mission(&agent_id, &make_payload("Wait your turn")).await.unwrap();
+        let run = {
+            let (runner, agent_id, mission_id) = (runner.clone(), agent_id.clone(), mission_id.clone());
+            tokio::spawn(async move { runner.run_mission(agent_id, make_payload("Wait your turn"), mission_id).await })
+        };
+
+        for _ in 0..250 {
+            if state.queued_missions.load(std::sync::atomic::Ordering::SeqCst) == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(state.queued_missions.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let mission = crate::agent::mission::get_mission_by_id(&state.pool, &mission_id).await.unwrap().unwrap();
+        assert_eq!(mission.status, MissionStatus::Pending, "a queued mission must not report as running");
+
+        drop(held);
+        let activated = timeout(Duration::from_secs(5), async {
+            loop {
+                let event = events.recv().await.unwrap();
+                if event["type"] == "mission:status" && event["missionId"] == mission_id.as_str() {
+                    break event["status"].clone();
+                }
+            }
+        }).await.unwrap();
+        assert_eq!(activated, "active");
+        // Let the run finish so no database work outlives the test
+        let _ = timeout(Duration::from_secs(5), run).await.unwrap();
+    }
+
     #[tokio::test]
     async fn validate_input_accepts_normal_message() {
-        let state = Arc::new(crate::state::AppState::new().await);
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
         let runner = AgentRunner::new(state);
         let payload = make_payload("Hello, agent!");
         let result = runner.validate_input("agent-1", &payload);
@@ -1579,7 +4288,7 @@ mod tests {
 
     #[tokio::test]
     async fn validate_input_rejects_oversized_message() {
-        let state = Arc::new(crate::state::AppState::new().await);
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
         let runner = AgentRunner::new(state);
         let long_msg = "x".repeat(40_000);
         let payload = make_payload(&long_msg);
@@ -1590,7 +4299,7 @@ mod tests {
 
     #[tokio::test]
     async fn validate_input_detects_circular_recursion() {
-        let state = Arc::new(crate::state::AppState::new().await);
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
         let runner = AgentRunner::new(state);
         let mut payload = make_payload("test");
         payload.swarm_lineage = Some(vec!["agent-1".to_string(), "agent-2".to_string()]);
@@ -1603,7 +4312,7 @@ mod tests {
 
     #[tokio::test]
     async fn validate_input_allows_non_circular_lineage() {
-        let state = Arc::new(crate::state::AppState::new().await);
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
         let runner = AgentRunner::new(state);
         let mut payload = make_payload("test");
         payload.swarm_lineage = Some(vec!["agent-1".to_string(), "agent-2".to_string()]);
@@ -1615,7 +4324,7 @@ mod tests {
 
     #[tokio::test]
     async fn validate_input_enforces_depth_limit() {
-        let state = Arc::new(crate::state::AppState::new().await);
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
         let runner = AgentRunner::new(state);
         let mut payload = make_payload("test");
         payload.swarm_depth = Some(5);
@@ -1625,61 +4334,1580 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("depth limit"));
     }
 
+    #[test]
+    fn git_clone_url_validation_enforces_https_and_allowed_hosts() {
+        let hosts = ["github.com".to_string(), "git.example.com".to_string()];
+        assert!(AgentRunner::validate_git_clone_url("https://github.com/org/repo.git", &hosts).is_ok());
+        assert!(AgentRunner::validate_git_clone_url("https://GIT.example.com/org/repo.git", &hosts).is_ok());
+        assert!(AgentRunner::validate_git_clone_url("git@github.com:org/repo.git", &hosts).is_err());
+        assert!(AgentRunner::validate_git_clone_url("http://github.com/org/repo.git", &hosts).is_err());
+        assert!(AgentRunner::validate_git_clone_url("https://gitlab.com/org/repo.git", &hosts).is_err());
+        assert!(AgentRunner::validate_git_clone_url("https://evil.example.com/repo.git", &hosts).is_err());
+        assert!(AgentRunner::validate_workspace_relative_dir("../outside").is_err());
+        assert!(AgentRunner::validate_workspace_relative_dir("/etc").is_err());
+        assert!(AgentRunner::validate_workspace_relative_dir("repos/app").is_ok());
+    }
+
+    #[test]
+    fn fetch_url_validation_rejects_internal_hosts() {
+        for url in [
+            "http://localhost:8000/engine/kill",
+            "http://api.localhost/",
+            "http://127.0.0.1/",
+            "http://127.8.0.1:9000/",
+            "http://10.0.0.5/admin",
+            "http://172.16.4.2/",
+            "http://172.31.255.1/",
+            "https://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[::ffff:10.0.0.1]/",
+            "http://100.64.0.1/",
+            "http://100.127.255.254/",
+            "http://[fc00::1]/",
+            "http://[fd12:3456::1]/",
+            "http://[fe80::1]/",
+            "http://[febf::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[::ffff:169.254.169.254]/",
+            "http://[::ffff:100.64.0.1]/",
+            "file:///etc/passwd",
+            "not a url",
+        ] {
+            assert!(AgentRunner::validate_fetch_url(url).is_err(), "{} should be rejected", url);
+        }
+        for url in [
+            "https://api.github.com/user",
+            "http://172.32.0.1/",
+            "https://8.8.8.8/",
+            "https://example.com:8443/v1",
+            "http://100.128.0.1/",
+            "http://[2606:4700::1111]/",
+            "http://[fec0::1]/",
+            "http://[::ffff:8.8.8.8]/",
+        ] {
+            assert!(AgentRunner::validate_fetch_url(url).is_ok(), "{} should be allowed", url);
+        }
+    }
+
     #[tokio::test]
-    async fn build_system_prompt_includes_role_and_department() {
-        let state = Arc::new(crate::state::AppState::new().await);
+    async fn fetch_targets_are_checked_after_resolution() {
+        // Refused for what the name resolves to, not for the name itself
+        let url = reqwest::Url::parse("http://localhost:8080/").unwrap();
+        let err = AgentRunner::resolve_fetch_target(&url).await.unwrap_err();
+        assert!(err.to_string().contains("resolves to internal address"), "got: {}", err);
+
+        let url = reqwest::Url::parse("http://[::ffff:10.0.0.1]/").unwrap();
+        assert!(AgentRunner::resolve_fetch_target(&url).await.is_err());
+
+        let url = reqwest::Url::parse("https://8.8.8.8/").unwrap();
+        let addrs = AgentRunner::resolve_fetch_target(&url).await.unwrap();
+        assert_eq!(addrs, vec!["8.8.8.8:443".parse().unwrap()]);
+    }
+
+    #[tokio::test]
+    async fn pinned_fetch_client_connects_only_to_the_checked_address() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let response = "HTTP/1.1 302 Found\r\nLocation: http://169.254.169.254/\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        // The name never reaches DNS: the client dials the pinned address and leaves the redirect alone
+        let url = reqwest::Url::parse(&format!("http://pinned.invalid:{}/", addr.port())).unwrap();
+        let response = pinned_fetch_client(&url, &[addr]).unwrap().get(url).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FOUND);
+        assert_eq!(response.headers()[reqwest::header::LOCATION], "http://169.254.169.254/");
+    }
+
+    #[test]
+    fn s3_archive_keys_are_scoped_to_the_mission() {
+        assert_eq!(AgentRunner::s3_archive_key("m-42", "reports/summary.md").unwrap(), "m-42/reports/summary.md");
+        for bad in ["", "/etc/passwd", "../m-41/summary.md", "reports//x.md", "a\\b.md", "reports/."] {
+            assert!(AgentRunner::s3_archive_key("m-42", bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_url_advanced_blocks_internal_targets_before_oversight() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let ctx = RunContext {
+            name: "Tester".to_string(),
+            role: "tester".to_string(),
+            department: "QA".to_string(),
+            model_config: crate::agent::types::ModelConfig {
+                provider: "mock".to_string(),
+                model_id: "mock".to_string(),
+                api_key: None,
+                base_url: None,
+                system_prompt: None,
+                temperature: None,
+                max_tokens: None,
+                external_id: None,
+                rpm: None,
+                rpd: None,
+                tpm: None,
+                tpd: None,
+                max_history_turns: crate::agent::types::DEFAULT_MAX_HISTORY_TURNS,
+                safety_settings: None,
+            },
+            skills: vec!["fetch_url_advanced".to_string()],
+            workspace_root: std::path::PathBuf::from("."),
+            ..test_ctx(&state, "1", &format!("mission-fetch-{}", uuid::Uuid::new_v4()))
+        };
+        let call = |args: serde_json::Value| crate::agent::types::GeminiFunctionCall { name: "fetch_url_advanced".to_string(), args };
+
+        let mut output = "draft".to_string();
+        runner.dispatch_tool(&ctx, &call(serde_json::json!({
+            "url": "http://10.0.0.5/internal",
+            "headers": { "Authorization": "Bearer secret" }
+        })), &mut output, &mut None).await.unwrap();
+        assert!(output.starts_with("(FETCH BLOCKED: Host '10.0.0.5' is an internal address"), "got: {}", output);
+
+        let mut output = String::new();
+        runner.dispatch_tool(&ctx, &call(serde_json::json!({ "url": "https://example.com/", "method": "DELETE" })), &mut output, &mut None).await.unwrap();
+        assert!(output.contains("method 'DELETE' is not allowed"), "got: {}", output);
+
+        // Neither request got as far as asking a human
+        assert!(state.oversight_queue.iter().all(|e| e.value().tool_call.as_ref().is_none_or(|call| call.mission_id.as_deref() != Some(ctx.mission_id.as_str()))));
+    }
+
+    #[tokio::test]
+    async fn fetch_url_advanced_failed_send_asks_for_oversight_once() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let mission_id = format!("mission-fetch-{}", uuid::Uuid::new_v4());
+        let ctx = RunContext {
+            name: "Tester".to_string(),
+            skills: vec!["fetch_url_advanced".to_string()],
+            workspace_root: std::path::PathBuf::from("."),
+            ..test_ctx(&state, "1", &mission_id)
+        };
+        // TEST-NET-1 passes the internal-address checks but never answers
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "fetch_url_advanced".to_string(),
+            args: serde_json::json!({ "url": "http://192.0.2.1:9/", "method": "POST", "body": "{}", "timeout_secs": 1 }),
+        };
+
+        let runner_clone = runner.clone();
+        let handle = tokio::spawn(async move {
+            let mut output = "draft".to_string();
+            runner_clone.execute_tool(&ctx, &fc, &mut output, &mut None, "").await.map(|_| output)
+        });
+
+        let mut approvals = 0;
+        while !handle.is_finished() {
+            if approve_pending(&state, &mission_id).await.is_some() {
+                approvals += 1;
+            }
+        }
+        let output = handle.await.unwrap().unwrap();
+        assert_eq!(approvals, 1, "a failed send must not be retried through Oversight");
+        assert!(output.starts_with("(TOOL ERROR in fetch_url_advanced:"), "got: {}", output);
+        assert!(output.ends_with("draft"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn git_clone_skill_runs_mock_git_in_workspace_after_oversight() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
         let runner = AgentRunner::new(state.clone());
 
+        let tmp = std::env::temp_dir().join(format!("tadpole_git_{}", uuid::Uuid::new_v4()));
+        let workspace = tmp.join("workspace");
+        std::fs::create_dir_all(&tmp).unwrap();
+        let mock_git = tmp.join("git");
+        std::fs::write(&mock_git, "#!/bin/sh\necho \"ARGS: $@\"\necho \"CWD: $(pwd)\"\n").unwrap();
+
+        let skill = crate::agent::capabilities::SkillDefinition {
+            id: None,
+            name: "git_clone".to_string(),
+            description: "mock".to_string(),
+            execution_command: format!("sh {} clone <url> <dir>", mock_git.display()),
+            schema: serde_json::json!({ "type": "object", "properties": {} }),
+            doc_url: None,
+            tags: None,
+            enabled: true,
+            skill_cpu_limit_secs: None,
+            skill_memory_limit_mb: None,
+        };
+
+        let ctx = RunContext {
+            name: "Git Agent".to_string(),
+            skills: vec!["git_clone".to_string()],
+            workspace_root: workspace.clone(),
+            ..test_ctx(&state, "git-agent", "git-mission")
+        };
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "git_clone".to_string(),
+            args: serde_json::json!({ "url": "https://github.com/example/repo.git", "dir": "repo" }),
+        };
+
+        let runner_clone = runner.clone();
+        let handle = tokio::spawn(async move {
+            let mut output = String::new();
+            let mut usage = None;
+            runner_clone.handle_dynamic_skill(&ctx, &fc, &mut output, &skill, &mut usage).await.map(|_| output)
+        });
+
+        assert!(approve_pending(&state, "git-mission").await.is_some(), "git_clone must be routed through Oversight");
+
+        let output = handle.await.unwrap().unwrap();
+        assert!(output.contains("ARGS: clone https://github.com/example/repo.git repo"), "got: {}", output);
+        assert!(output.contains(&format!("CWD: {}", std::fs::canonicalize(&workspace).unwrap().display())), "got: {}", output);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[tokio::test]
+    async fn delegate_to_department_spawns_idle_agent() {
+        let state = isolated_state().await;
+        state.agents.retain(|_, a| a.department != "Engineering");
+        let runner = AgentRunner::new(state.clone());
+
+        let suffix = uuid::Uuid::new_v4();
+        let idle_id = format!("eng-idle-{}", suffix);
+        let busy_id = format!("eng-busy-{}", suffix);
+        for (id, status, cost) in [(&busy_id, "active", 0.0), (&idle_id, "idle", 1.5)] {
+            let mut agent = state.agents.get("1").unwrap().clone();
+            agent.id = id.clone();
+            agent.department = "Engineering".to_string();
+            agent.status = status.to_string();
+            agent.cost_usd = cost;
+            state.register_agent(agent).await;
+        }
+
         let ctx = RunContext {
-            agent_id: "1".to_string(),
             name: "Agent of Nine".to_string(),
             role: "CEO".to_string(),
             department: "Executive".to_string(),
             description: "Supreme tactical orchestrator.".to_string(),
-            mission_id: "test-mission".to_string(),
-            model_config: state.agents.get("1").unwrap().model.clone(),
-            provider_name: "google".to_string(),
-            skills: vec![],
-            workflows: vec![],
-            depth: 0,
-            lineage: vec![],
-            workspace_root: std::path::PathBuf::from("workspaces/executive-core"),
-            safe_mode: false,
+            ..test_ctx(&state, "1", &format!("delegate-mission-{}", suffix))
         };
+        assert!(runner.build_tools(&ctx).function_declarations.iter().any(|d| d.name == "delegate_to_department"));
+        assert_eq!(runner.select_department_agent(&ctx, "Engineering"), Some(idle_id.clone()));
 
-        let prompt = runner.build_system_prompt(&ctx, "Alpha").await;
-        assert!(prompt.contains("Agent of Nine"));
-        assert!(prompt.contains("Executive"));
-        assert!(prompt.contains("Alpha"));
+        // The mock provider fails the sub-run, but the spawned mission records who was recruited
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "delegate_to_department".to_string(),
+            args: serde_json::json!({ "department": "Engineering", "message": "Review the build." }),
+        };
+        let mut output = String::new();
+        let mut usage = None;
+        let _ = runner.handle_delegate_to_department(&ctx, &fc, &mut output, &mut usage).await;
+
+        let count_missions = |id: String| {
+            let pool = state.pool.clone();
+            async move {
+                sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM mission_history WHERE agent_id = ?")
+                    .bind(id)
+                    .fetch_one(&pool).await.unwrap()
+            }
+        };
+        assert_eq!(count_missions(idle_id).await, 1);
+        assert_eq!(count_missions(busy_id).await, 0);
     }
 
     #[tokio::test]
-    async fn build_system_prompt_includes_lineage_when_present() {
-        let state = Arc::new(crate::state::AppState::new().await);
+    async fn create_agent_registers_spawnable_agent_after_oversight() {
+        let state = isolated_state().await;
         let runner = AgentRunner::new(state.clone());
+        let new_id = format!("created-{}", uuid::Uuid::new_v4());
 
         let ctx = RunContext {
-            agent_id: "2".to_string(),
-            name: "Tadpole".to_string(),
-            role: "COO".to_string(),
-            department: "Operations".to_string(),
-            description: "Operational coordination specialist.".to_string(),
-            mission_id: "test-mission".to_string(),
-            model_config: state.agents.get("2").unwrap().model.clone(),
-            provider_name: "google".to_string(),
-            skills: vec![],
-            workflows: vec![],
-            depth: 1,
-            lineage: vec!["Agent of Nine".to_string()],
-            workspace_root: std::path::PathBuf::from("workspaces/executive-core"),
-            safe_mode: false,
+            name: "Agent of Nine".to_string(),
+            role: "CEO".to_string(),
+            department: "Executive".to_string(),
+            description: "Supreme tactical orchestrator.".to_string(),
+            skills: vec!["issue_alpha_directive".to_string()],
+            ..test_ctx(&state, "1", "create-agent-mission")
+        };
+        assert!(runner.build_tools(&ctx).function_declarations.iter().any(|d| d.name == "create_agent"));
+
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "create_agent".to_string(),
+            args: serde_json::json!({
+                "id": new_id,
+                "name": "Threat Modeler",
+                "role": "Security Analyst",
+                "department": "Security",
+                "description": "Builds threat models.",
+                "skills": ["fetch_url"]
+            }),
         };
 
-        let prompt = runner.build_system_prompt(&ctx, "Sub-Agent").await;
-        assert!(prompt.contains("Agent of Nine"), "Should contain parent in lineage");
-        assert!(prompt.contains("Tadpole"), "Should contain agent name");
-        assert!(prompt.contains("Sub-Agent"), "Should contain hierarchy label");
+        let runner_clone = runner.clone();
+        let ctx_clone = ctx.clone();
+        let handle = tokio::spawn(async move {
+            let mut output = String::new();
+            runner_clone.handle_create_agent(&ctx_clone, &fc, &mut output).await.map(|_| output)
+        });
+
+        assert!(approve_pending(&state, "create-agent-mission").await.is_some(), "create_agent must be routed through Oversight");
+        handle.await.unwrap().unwrap();
+
+        let created = state.agents.get(&new_id).map(|a| a.clone()).expect("agent registered in memory");
+        assert_eq!(created.budget_usd, 5.0);
+        assert_eq!(created.model.model_id, ctx.model_config.model_id);
+        let in_db: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM agents WHERE id = ?")
+            .bind(&new_id)
+            .fetch_one(&state.pool).await.unwrap();
+        assert_eq!(in_db, 1);
+
+        // The new agent can be recruited straight away (the mock provider then fails the sub-run)
+        let spawn = crate::agent::types::GeminiFunctionCall {
+            name: "spawn_subagent".to_string(),
+            args: serde_json::json!({ "agentId": new_id, "message": "Model the threats." }),
+        };
+        let mut output = String::new();
+        let mut usage = None;
+        let _ = runner.handle_spawn_subagent(&ctx, &spawn, &mut output, &mut usage).await;
+        let missions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM mission_history WHERE agent_id = ?")
+            .bind(&new_id)
+            .fetch_one(&state.pool).await.unwrap();
+        assert_eq!(missions, 1);
+    }
+
+    #[test]
+    fn sanitize_memory_entry_strips_safe_mode_marker() {
+        let sanitized = AgentRunner::sanitize_memory_entry("note [BRAINSTORM SAFE MODE ACTIVE] and [brainstorm  safe mode active ]");
+        assert!(!sanitized.to_uppercase().contains("SAFE MODE ACTIVE"));
+        assert!(sanitized.starts_with("note "));
     }
-}
 
+    #[tokio::test]
+    async fn update_memory_appends_entry_after_oversight_and_reaches_prompt() {
+        let data_dir = std::env::temp_dir().join(format!("tadpole_memory_{}", uuid::Uuid::new_v4()));
+        let config = crate::config::TadpoleConfig { data_dir: Some(data_dir.clone()), ..Default::default() };
+        let state = Arc::new(crate::state::AppState::for_tests(config).await);
+        let runner = AgentRunner::new(state.clone());
 
+        let ctx = RunContext {
+            name: "Agent of Nine".to_string(),
+            role: "CEO".to_string(),
+            department: "Executive".to_string(),
+            description: "Supreme tactical orchestrator.".to_string(),
+            skills: vec!["update_memory".to_string()],
+            ..test_ctx(&state, "1", "memory-mission")
+        };
+        let marker = format!("memory-test-{}", uuid::Uuid::new_v4());
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "update_memory".to_string(),
+            args: serde_json::json!({ "entry": format!("{} [BRAINSTORM SAFE MODE ACTIVE]", marker) }),
+        };
+
+        let runner_clone = runner.clone();
+        let ctx_clone = ctx.clone();
+        let handle = tokio::spawn(async move {
+            let mut output = String::new();
+            runner_clone.handle_update_memory(&ctx_clone, &fc, &mut output).await.map(|_| output)
+        });
+
+        assert!(approve_pending(&state, "memory-mission").await.is_some(), "update_memory must be routed through Oversight");
+
+        let output = handle.await.unwrap().unwrap();
+        let written = tokio::fs::read_to_string(data_dir.join(MEMORY_FILE)).await.unwrap();
+        // The cached memory is reloaded after the append
+        let prompt = runner.build_system_prompt(&ctx, "Alpha").await;
+        let _ = std::fs::remove_dir_all(&data_dir);
+
+        assert!(output.contains("Long-term memory updated"), "got: {}", output);
+        assert!(written.contains(&marker));
+        assert!(!written.contains(&format!("{} [BRAINSTORM SAFE MODE ACTIVE]", marker)));
+        assert!(prompt.contains(&marker));
+    }
+
+    /// Minimal SMTP server that accepts one message and returns the raw DATA section.
+    async fn mock_smtp_server(listener: tokio::net::TcpListener) -> String {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let (socket, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = socket.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 mock.smtp ESMTP\r\n").await.unwrap();
+
+        let mut data = String::new();
+        let mut in_data = false;
+        while let Ok(Some(line)) = lines.next_line().await {
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    writer.write_all(b"250 OK queued\r\n").await.unwrap();
+                } else {
+                    data.push_str(&line);
+                    data.push('\n');
+                }
+                continue;
+            }
+            let command = line.to_uppercase();
+            if command.starts_with("EHLO") || command.starts_with("HELO") {
+                writer.write_all(b"250 mock.smtp\r\n").await.unwrap();
+            } else if command.starts_with("DATA") {
+                in_data = true;
+                writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await.unwrap();
+            } else if command.starts_with("QUIT") {
+                writer.write_all(b"221 Bye\r\n").await.unwrap();
+                break;
+            } else {
+                writer.write_all(b"250 OK\r\n").await.unwrap();
+            }
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn notify_email_sends_after_oversight_approval() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(mock_smtp_server(listener));
+
+        let state = Arc::new(crate::state::AppState::for_tests(crate::config::TadpoleConfig {
+            smtp_host: Some("127.0.0.1".to_string()),
+            smtp_port: Some(port),
+            smtp_tls: Some("none".to_string()),
+            email_from: Some("tadpole@example.com".to_string()),
+            ..Default::default()
+        }).await);
+        let runner = AgentRunner::new(state.clone());
+        let ctx = RunContext {
+            name: "Agent of Nine".to_string(),
+            role: "CEO".to_string(),
+            department: "Executive".to_string(),
+            description: "Supreme tactical orchestrator.".to_string(),
+            skills: vec!["notify_email".to_string()],
+            ..test_ctx(&state, "1", "email-mission")
+        };
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "notify_email".to_string(),
+            args: serde_json::json!({ "to": "ops@example.com", "subject": "Mission Report", "body": "All systems nominal." }),
+        };
+
+        let runner_clone = runner.clone();
+        let handle = tokio::spawn(async move {
+            let mut output = String::new();
+            runner_clone.handle_notify_email(&ctx, &fc, &mut output).await.map(|_| output)
+        });
+
+        let entry = approve_pending(&state, "email-mission").await.expect("notify_email must be routed through Oversight");
+        let description = entry.tool_call.map(|tc| tc.description).unwrap_or_default();
+        assert!(description.contains("ops@example.com") && description.contains("Mission Report"));
+
+        let output = handle.await.unwrap().unwrap();
+        assert!(output.contains("Emailed ops@example.com"), "got: {}", output);
+
+        let data = tokio::time::timeout(std::time::Duration::from_secs(5), server).await.unwrap().unwrap();
+        assert!(data.contains("From: tadpole@example.com"), "got: {}", data);
+        assert!(data.contains("Subject: Mission Report"), "got: {}", data);
+    }
+
+    #[test]
+    fn email_validation_blocks_obviously_invalid_addresses() {
+        assert!(crate::adapter::email::is_valid_email("ops@example.com"));
+        assert!(!crate::adapter::email::is_valid_email("not-an-email"));
+        assert!(!crate::adapter::email::is_valid_email("a@b"));
+        assert!(!crate::adapter::email::is_valid_email("evil@example.com\r\nBcc: x@y.z"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bash_deny_list_blocks_destructive_commands() {
+        for denied in [
+            "rm -rf /",
+            "sudo rm -fr /*",
+            "rm -r -f /",
+            "echo hi; /sbin/shutdown -h now",
+            "FORMAT C:",
+            "cmd /c format.com d:",
+            "ls && reboot",
+            "echo $(reboot)",
+        ] {
+            assert!(AgentRunner::bash_deny_reason(denied).is_some(), "should deny: {}", denied);
+        }
+        for allowed in ["rm -rf build/", "rm -rf /tmp/scratch", "git log --format=oneline", "cargo fmt", "echo rebooted", "ls /"] {
+            assert_eq!(AgentRunner::bash_deny_reason(allowed), None, "should allow: {}", allowed);
+        }
+    }
+
+    fn bash_ctx(state: &crate::state::AppState, mission_id: &str, workspace_root: std::path::PathBuf) -> RunContext {
+        RunContext {
+            name: "Bash Agent".to_string(),
+            skills: vec!["execute_bash".to_string()],
+            workspace_root,
+            ..test_ctx(state, "bash-agent", mission_id)
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_bash_refuses_denied_and_invalid_requests_before_oversight() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let tmp = std::env::temp_dir().join(format!("tadpole_bash_{}", uuid::Uuid::new_v4()));
+        let ctx = bash_ctx(&state, "bash-denied", tmp.join("workspace"));
+        let call = |args: serde_json::Value| crate::agent::types::GeminiFunctionCall { name: "execute_bash".to_string(), args };
+
+        let mut output = String::new();
+        let mut usage = None;
+        runner.handle_execute_bash(&ctx, &call(serde_json::json!({ "command": "sudo reboot" })), &mut output, &mut usage).await.unwrap();
+        assert!(output.contains("BASH BLOCKED") && output.contains("reboot"), "got: {}", output);
+
+        let mut output = String::new();
+        runner.handle_execute_bash(&ctx, &call(serde_json::json!({ "command": "   " })), &mut output, &mut usage).await.unwrap();
+        assert!(output.contains("must not be empty"), "got: {}", output);
+
+        let escaped = runner.handle_execute_bash(&ctx, &call(serde_json::json!({ "command": "ls", "working_dir": "../.." })), &mut String::new(), &mut usage).await;
+        assert!(escaped.is_err(), "working_dir must stay inside the workspace");
+        assert!(state.oversight_queue.is_empty(), "refused commands must not reach Oversight");
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn execute_bash_runs_in_working_dir_and_logs_both_streams() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let mission = crate::agent::mission::create_mission(&state.pool, "1", "Bash", 1.0, &[]).await.unwrap();
+        let tmp = std::env::temp_dir().join(format!("tadpole_bash_{}", uuid::Uuid::new_v4()));
+        let ctx = bash_ctx(&state, &mission.id, tmp.join("workspace"));
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "execute_bash".to_string(),
+            args: serde_json::json!({ "command": "echo \"agent=$TADPOLE_AGENT_ID dir=$(basename \"$PWD\")\"; echo oops >&2", "working_dir": "sub" }),
+        };
+
+        let runner_clone = runner.clone();
+        let handle = tokio::spawn(async move {
+            let mut output = String::new();
+            let mut usage = None;
+            runner_clone.handle_execute_bash(&ctx, &fc, &mut output, &mut usage).await.map(|_| output)
+        });
+
+        assert!(approve_pending(&state, &mission.id).await.is_some(), "execute_bash must be routed through Oversight");
+
+        let output = handle.await.unwrap().unwrap();
+        assert!(output.contains("agent=bash-agent dir=sub"), "got: {}", output);
+
+        let logs = crate::agent::mission::get_mission_logs(&state.pool, &mission.id, 1, 10).await.unwrap().items;
+        let stdout = logs.iter().find(|l| l.text.contains("(stdout)")).expect("stdout is logged");
+        let stderr = logs.iter().find(|l| l.text.contains("(stderr)")).expect("stderr is logged");
+        assert_eq!(stdout.severity, "info");
+        assert_eq!(stderr.severity, "warning");
+        assert!(stderr.text.contains("oops"));
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[tokio::test]
+    async fn query_missions_db_refuses_writes_and_compound_statements_before_oversight() {
+        use crate::agent::mission::validate_analytics_sql;
+        assert!(validate_analytics_sql("SELECT status, COUNT(*) FROM mission_history GROUP BY status").is_ok());
+        assert!(validate_analytics_sql("with t AS (SELECT cost_usd FROM mission_history) SELECT SUM(cost_usd) FROM t").is_ok());
+        for sql in [
+            "DELETE FROM mission_logs",
+            "SELECT 1; DROP TABLE mission_history",
+            "select * from mission_history where id in (select id from x) union select 1 from y; ",
+            "WITH x AS (SELECT 1) UPDATE mission_history SET status = 'failed'",
+            "INSERT INTO swarm_context SELECT * FROM swarm_context",
+            "SELECT * FROM mission_history WHERE 1 /* */ AND alter_ego = 1 OR Alter",
+            "ATTACH DATABASE 'tadpole.db' AS other",
+            "PRAGMA table_info(agents)",
+            "",
+        ] {
+            assert!(validate_analytics_sql(sql).is_err(), "should refuse: {}", sql);
+        }
+        // Column names that merely contain a keyword are fine
+        assert!(validate_analytics_sql("SELECT updated_at FROM mission_history").is_ok());
+
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let ctx = bash_ctx(&state, "analytics-denied", std::env::temp_dir());
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "query_missions_db".to_string(),
+            args: serde_json::json!({ "sql": "DROP TABLE mission_logs" }),
+        };
+        let mut output = String::new();
+        runner.handle_query_missions_db(&ctx, &fc, &mut output, &mut None).await.unwrap();
+        assert!(output.contains("QUERY BLOCKED") && output.contains("DROP"), "got: {}", output);
+        assert!(state.oversight_queue.is_empty(), "refused queries must not reach Oversight");
+    }
+
+    #[tokio::test]
+    async fn query_missions_db_caps_rows_and_cannot_see_other_tables() {
+        let state = crate::state::AppState::for_tests(Default::default()).await;
+        let mission = crate::agent::mission::create_mission(&state.pool, "1", "Analytics rows", 1.0, &[]).await.unwrap();
+        for i in 0..120 {
+            crate::agent::mission::log_step(&state.pool, &mission.id, "1", "Agent", &format!("step {}", i), "info", None).await.unwrap();
+        }
+
+        let sql = format!("SELECT id, text FROM mission_logs WHERE mission_id = '{}'", mission.id);
+        let rows = crate::agent::mission::query_missions_readonly(&state.pool, &sql).await.unwrap();
+        assert_eq!(rows.len(), crate::agent::mission::MAX_ANALYTICS_ROWS);
+        assert!(rows[0]["text"].as_str().unwrap().starts_with("step "));
+
+        let hidden = crate::agent::mission::query_missions_readonly(&state.pool, "SELECT * FROM agents").await;
+        assert!(hidden.unwrap_err().to_string().contains("no such table"));
+
+        // Webhook secrets never reach the snapshot
+        crate::agent::mission::set_callback(&state.pool, &mission.id, "https://example.com/hook", Some("hmac-secret")).await.unwrap();
+        let secret = crate::agent::mission::query_missions_readonly(&state.pool, "SELECT callback_secret FROM mission_history").await;
+        assert!(secret.unwrap_err().to_string().contains("no such column"));
+        let rows = crate::agent::mission::query_missions_readonly(&state.pool, &format!("SELECT * FROM mission_history WHERE id = '{}'", mission.id)).await.unwrap();
+        assert!(rows[0].get("callback_secret").is_none() && rows[0].get("callback_url").is_none(), "got: {}", rows[0]);
+    }
+
+    #[tokio::test]
+    async fn query_missions_db_runs_an_aggregation_after_oversight() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let title = format!("Analytics {}", uuid::Uuid::new_v4());
+        let mission = crate::agent::mission::create_mission(&state.pool, "1", &title, 2.0, &[]).await.unwrap();
+        crate::agent::mission::create_mission(&state.pool, "1", &title, 3.0, &[]).await.unwrap();
+        crate::agent::mission::update_mission(&state.pool, &mission.id, crate::agent::types::MissionStatus::Completed, 0.5).await.unwrap();
+
+        let ctx = bash_ctx(&state, &mission.id, std::env::temp_dir());
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "query_missions_db".to_string(),
+            args: serde_json::json!({
+                "sql": format!("SELECT status, COUNT(*) AS missions, SUM(budget_usd) AS budget FROM mission_history WHERE title = '{}' GROUP BY status ORDER BY status", title)
+            }),
+        };
+        let runner_clone = runner.clone();
+        let handle = tokio::spawn(async move {
+            let mut output = String::new();
+            runner_clone.handle_query_missions_db(&ctx, &fc, &mut output, &mut None).await.map(|_| output)
+        });
+
+        assert!(approve_pending(&state, &mission.id).await.is_some(), "query_missions_db must be routed through Oversight");
+
+        // The mock provider echoes the synthesis prompt, which carries the rows
+        let output = handle.await.unwrap().unwrap();
+        assert!(output.contains("RETURNED 2 ROW(S)"), "got: {}", output);
+        assert!(output.contains("\"budget\": 2.0") && output.contains("\"status\": \"completed\""), "got: {}", output);
+        assert!(output.contains("\"budget\": 3.0") && output.contains("\"status\": \"pending\""), "got: {}", output);
+    }
+
+    #[tokio::test]
+    async fn run_tests_reports_counts_from_mock_cargo() {
+        let tmp = std::env::temp_dir().join(format!("tadpole_run_tests_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&tmp).unwrap();
+        let mock_cargo = tmp.join("cargo.sh");
+        std::fs::write(&mock_cargo, "#!/bin/sh\n\
+            echo 'test result: ok. 5 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out'\n\
+            echo 'test result: FAILED. 2 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out' >&2\n\
+            exit 101\n").unwrap();
+        let state = Arc::new(crate::state::AppState::for_tests(crate::config::TadpoleConfig {
+            run_tests_command: Some(format!("sh {}", mock_cargo.display())),
+            ..Default::default()
+        }).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let ctx = RunContext {
+            name: "Test Runner".to_string(),
+            skills: vec!["run_tests".to_string()],
+            workspace_root: tmp.join("workspace"),
+            ..test_ctx(&state, "test-runner", "run-tests-mission")
+        };
+        let fc = crate::agent::types::GeminiFunctionCall { name: "run_tests".to_string(), args: serde_json::json!({}) };
+
+        let runner_clone = runner.clone();
+        let handle = tokio::spawn(async move {
+            let mut output = String::new();
+            let mut usage = None;
+            runner_clone.handle_run_tests(&ctx, &fc, &mut output, &mut usage).await.map(|_| output)
+        });
+
+        assert!(approve_pending(&state, "run-tests-mission").await.is_some(), "run_tests must be routed through Oversight");
+
+        let output = handle.await.unwrap().unwrap();
+        let _ = std::fs::remove_dir_all(&tmp);
+        assert!(output.contains("Tests: 7 passed, 2 failed, 1 ignored"), "got: {}", output);
+    }
+
+    #[tokio::test]
+    async fn search_files_finds_matches_and_rejects_traversal() {
+        let workspace = std::env::temp_dir().join(format!("tadpole_search_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(workspace.join("src/nested")).unwrap();
+        std::fs::write(workspace.join("src/main.rs"), "fn main() {\n    // TODO: wire config\n}\n").unwrap();
+        std::fs::write(workspace.join("src/nested/lib.rs"), "pub fn helper() {}\n// TODO(v2): cache\n").unwrap();
+        std::fs::write(workspace.join("README.md"), "No todos here\n").unwrap();
+
+        let adapter = crate::adapter::filesystem::FilesystemAdapter::new(workspace.clone());
+
+        let hits = adapter.search_files("src", "TODO", false).await.unwrap();
+        let found: Vec<(String, usize)> = hits.iter().map(|h| (h.filename.clone(), h.line_number)).collect();
+        assert_eq!(found, vec![("src/main.rs".to_string(), 2), ("src/nested/lib.rs".to_string(), 2)]);
+
+        let hits = adapter.search_files(".", r"TODO\(v\d\)", true).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].line_content, "// TODO(v2): cache");
+
+        assert!(adapter.search_files("../", "TODO", false).await.is_err());
+        assert!(adapter.search_files(".", "(unclosed", true).await.is_err());
+
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[tokio::test]
+    async fn append_file_creates_then_extends_without_overwriting() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let workspace = std::env::temp_dir().join(format!("tadpole_append_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+
+        let ctx = RunContext {
+            name: "Appender".to_string(),
+            skills: vec!["append_file".to_string()],
+            workspace_root: workspace.clone(),
+            ..test_ctx(&state, "appender", "append-mission")
+        };
+        assert!(runner.build_tools(&ctx).function_declarations.iter().any(|d| d.name == "append_file"));
+
+        let append = |content: &str| crate::agent::types::GeminiFunctionCall {
+            name: "append_file".to_string(),
+            args: serde_json::json!({ "filename": "notes/log.txt", "content": content }),
+        };
+
+        let mut output = String::new();
+        runner.handle_append_file(&ctx, &append("first line\n"), &mut output).await.unwrap();
+        assert!(output.contains("Successfully appended"), "got: {}", output);
+        assert_eq!(std::fs::read_to_string(workspace.join("notes/log.txt")).unwrap(), "first line\n");
+
+        let mut output = String::new();
+        runner.handle_append_file(&ctx, &append("second line\n"), &mut output).await.unwrap();
+        assert_eq!(std::fs::read_to_string(workspace.join("notes/log.txt")).unwrap(), "first line\nsecond line\n");
+
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[tokio::test]
+    async fn write_json_validates_against_schema_before_writing() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let workspace = std::env::temp_dir().join(format!("tadpole_json_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+
+        let ctx = RunContext {
+            name: "Json Writer".to_string(),
+            skills: vec!["json_writer".to_string()],
+            workspace_root: workspace.clone(),
+            ..test_ctx(&state, "json-writer", "json-mission")
+        };
+        assert!(runner.build_tools(&ctx).function_declarations.iter().any(|d| d.name == "write_json"));
+
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } },
+            "required": ["name"]
+        });
+
+        let valid = crate::agent::types::GeminiFunctionCall {
+            name: "write_json".to_string(),
+            args: serde_json::json!({ "filename": "manifest.json", "content": { "name": "tadpole", "version": 2 }, "schema": schema }),
+        };
+        let mut output = String::new();
+        runner.handle_write_json(&ctx, &valid, &mut output).await.unwrap();
+        assert!(output.contains("Successfully wrote JSON"), "got: {}", output);
+        let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(workspace.join("manifest.json")).unwrap()).unwrap();
+        assert_eq!(written["name"], "tadpole");
+
+        let invalid = crate::agent::types::GeminiFunctionCall {
+            name: "write_json".to_string(),
+            args: serde_json::json!({ "filename": "broken.json", "content": { "version": 2 }, "schema": schema }),
+        };
+        let mut output = String::new();
+        runner.handle_write_json(&ctx, &invalid, &mut output).await.unwrap();
+        assert!(output.contains("JSON VALIDATION FAILED"), "got: {}", output);
+        assert!(output.contains("name"));
+        assert!(!workspace.join("broken.json").exists(), "Invalid content must not be written");
+
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[tokio::test]
+    async fn mock_http_response_records_response_in_workspace() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let workspace = std::env::temp_dir().join(format!("tadpole_http_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        let mission_id = runner.prepare_mission("1", &make_payload("Design the users API")).await.unwrap();
+
+        let ctx = RunContext {
+            name: "Api Designer".to_string(),
+            skills: vec!["api_designer".to_string()],
+            workspace_root: workspace.clone(),
+            ..test_ctx(&state, "1", &mission_id)
+        };
+        assert!(runner.build_tools(&ctx).function_declarations.iter().any(|d| d.name == "mock_http_response"));
+
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "mock_http_response".to_string(),
+            args: serde_json::json!({
+                "status_code": 201,
+                "headers": { "Location": "/users/42" },
+                "body": "{\"id\":42}"
+            }),
+        };
+        let mut output = String::new();
+        runner.handle_mock_http_response(&ctx, &fc, &mut output).await.unwrap();
+
+        let files: Vec<_> = std::fs::read_dir(workspace.join("http_responses")).unwrap().collect();
+        assert_eq!(files.len(), 1);
+        let recorded: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap()).unwrap();
+        assert_eq!(recorded["statusCode"], 201);
+        assert_eq!(recorded["body"], "{\"id\":42}");
+        assert_eq!(recorded["headers"]["Location"], "/users/42");
+
+        let logs = crate::agent::mission::get_mission_logs(&state.pool, &mission_id, 1, 100).await.unwrap().items;
+        assert!(logs.iter().any(|l| l.text.contains("Mock HTTP 201")));
+
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[tokio::test]
+    async fn format_code_handles_rust_file_with_or_without_rustfmt() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let workspace = std::env::temp_dir().join(format!("tadpole_format_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::write(workspace.join("messy.rs"), "#[path = \"../outside.rs\"]\nmod outside;\nfn   main(){let x=1;println!(\"{}\",x);}\n").unwrap();
+        // Out-of-line modules must not be followed out of the workspace
+        let outside = workspace.join("../outside.rs");
+        std::fs::write(&outside, "fn   planted(){}\n").unwrap();
+
+        let ctx = RunContext {
+            name: "Formatter".to_string(),
+            skills: vec!["format_code".to_string()],
+            workspace_root: workspace.clone(),
+            ..test_ctx(&state, "formatter", "format-mission")
+        };
+        let fc = crate::agent::types::GeminiFunctionCall {
+            name: "format_code".to_string(),
+            args: serde_json::json!({ "filename": "messy.rs", "language": "rust" }),
+        };
+
+        let mut output = String::new();
+        runner.handle_format_code(&ctx, &fc, &mut output).await.unwrap();
+        let formatted = std::fs::read_to_string(workspace.join("messy.rs")).unwrap();
+        let untouched = std::fs::read_to_string(&outside).unwrap();
+        let _ = std::fs::remove_dir_all(&workspace);
+        let _ = std::fs::remove_file(&outside);
+        assert!(output.contains("Formatted messy.rs") || output.contains("FORMAT WARNING"), "got: {}", output);
+        if output.contains("Formatted messy.rs") {
+            assert!(formatted.contains("fn main() {"), "got: {}", formatted);
+        }
+        assert_eq!(untouched, "fn   planted(){}\n");
+        assert_eq!(AgentRunner::count_changed_lines("a\nb\n", "a\nc\nd\n"), 2);
+    }
+
+    #[tokio::test]
+    async fn query_agent_logs_summarizes_previous_missions() {
+        let state = isolated_state().await;
+        let runner = AgentRunner::new(state.clone());
+        let agent_id = format!("self-aware-{}", uuid::Uuid::new_v4());
+        let mut agent = state.agents.get("1").unwrap().clone();
+        agent.id = agent_id.clone();
+        crate::agent::persistence::save_agent_db(&state.pool, &agent).await.unwrap();
+
+        for (title, log) in [("Audit the ledger", "Found a rounding error"), ("Patch the parser", "Parser fix verified")] {
+            let mission = crate::agent::mission::create_mission(&state.pool, &agent_id, title, 1.0, &[]).await.unwrap();
+            crate::agent::mission::log_step(&state.pool, &mission.id, &agent_id, "Agent", log, "info", None).await.unwrap();
+            crate::agent::mission::update_mission(&state.pool, &mission.id, crate::agent::types::MissionStatus::Completed, 0.05).await.unwrap();
+        }
+
+        let ctx = RunContext {
+            name: "Self Aware".to_string(),
+            role: "analyst".to_string(),
+            department: "Research".to_string(),
+            skills: vec!["self_aware".to_string()],
+            ..test_ctx(&state, &agent_id, "current-mission")
+        };
+        assert!(runner.build_tools(&ctx).function_declarations.iter().any(|d| d.name == "query_agent_logs"));
+
+        let mut output = String::new();
+        let mut usage = None;
+        runner.handle_query_agent_logs(&ctx, &mut output, &mut usage).await.unwrap();
+
+        assert!(output.contains("[Mission Audit the ledger - completed - $0.05]"), "got: {}", output);
+        assert!(output.contains("Step 1 (Agent): Found a rounding error"));
+        assert!(output.contains("Patch the parser"));
+        assert!(output.contains("Parser fix verified"));
+    }
+
+    #[tokio::test]
+    async fn build_tools_respects_skill_allowlist() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let ctx = RunContext {
+            name: "Allowlisted".to_string(),
+            skills: vec!["read_file".to_string(), "write_file".to_string(), "fetch_url".to_string()],
+            skill_allowlist: Some(vec!["read_file".to_string()]),
+            depth: 1,
+            ..test_ctx(&state, "allowlist-agent", "allowlist-mission")
+        };
+
+        let mut names: Vec<String> = runner.build_tools(&ctx).function_declarations.into_iter().map(|d| d.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["complete_mission", "delegate_to_department", "propose_capability", "read_file", "share_finding", "spawn_subagent", "verify_finding"]);
+    }
+
+    #[tokio::test]
+    async fn disabled_skill_is_excluded_from_tools_and_refused_at_execution() {
+        let data_dir = std::env::temp_dir().join(format!("tadpole-disabled-skill-{}", uuid::Uuid::new_v4()));
+        let config = crate::config::TadpoleConfig { data_dir: Some(data_dir.clone()), ..Default::default() };
+        let state = Arc::new(crate::state::AppState::for_tests(config).await);
+        let runner = AgentRunner::new(state.clone());
+
+        for name in ["incident_probe", "incident_report"] {
+            state.capabilities.save_skill(crate::agent::capabilities::SkillDefinition {
+                id: None,
+                name: name.to_string(),
+                description: "probe".to_string(),
+                execution_command: "echo probe".to_string(),
+                schema: serde_json::json!({ "type": "object", "properties": {} }),
+                doc_url: None,
+                tags: None,
+                enabled: true,
+                skill_cpu_limit_secs: None,
+                skill_memory_limit_mb: None,
+            }).await.unwrap();
+        }
+        let probe = state.capabilities.skill("incident_probe").unwrap();
+
+        let ctx = RunContext {
+            name: "Incident Agent".to_string(),
+            skills: vec!["incident_probe".to_string(), "incident_report".to_string()],
+            depth: 1,
+            workspace_root: data_dir.join("workspace"),
+            safe_mode: true,
+            ..test_ctx(&state, "incident-agent", "incident-mission")
+        };
+        let declared = |runner: &AgentRunner| -> Vec<String> {
+            runner.build_tools(&ctx).function_declarations.into_iter().map(|d| d.name).collect()
+        };
+        assert!(declared(&runner).contains(&"incident_probe".to_string()));
+
+        use axum::response::IntoResponse;
+        let response = crate::routes::capabilities::disable_skill(axum::extract::Path("incident_probe".to_string()), axum::extract::State(state.clone())).await.into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let names = declared(&runner);
+        assert!(!names.contains(&"incident_probe".to_string()), "got: {:?}", names);
+        assert!(names.contains(&"incident_report".to_string()));
+
+        // A tool list built before the skill was disabled must not be able to run it
+        let fc = crate::agent::types::GeminiFunctionCall { name: "incident_probe".to_string(), args: serde_json::json!({}) };
+        let mut output = String::new();
+        runner.handle_dynamic_skill(&ctx, &fc, &mut output, &probe, &mut None).await.unwrap();
+        assert!(output.contains("is disabled"), "got: {}", output);
+
+        // The flag lives in the skill file and survives a reload
+        state.capabilities.reload_all().await.unwrap();
+        assert!(!state.capabilities.skill("incident_probe").unwrap().enabled);
+        let response = crate::routes::capabilities::enable_skill(axum::extract::Path("incident_probe".to_string()), axum::extract::State(state.clone())).await.into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert!(declared(&runner).contains(&"incident_probe".to_string()));
+
+        let response = crate::routes::capabilities::disable_skill(axum::extract::Path("no_such_skill".to_string()), axum::extract::State(state.clone())).await.into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[tokio::test]
+    async fn dynamic_skill_runs_are_counted_in_skill_metrics() {
+        let data_dir = std::env::temp_dir().join(format!("tadpole-skill-metrics-{}", uuid::Uuid::new_v4()));
+        let config = crate::config::TadpoleConfig { data_dir: Some(data_dir.clone()), ..Default::default() };
+        let state = Arc::new(crate::state::AppState::for_tests(config).await);
+        let runner = AgentRunner::new(state.clone());
+
+        for (name, command) in [("metrics_probe", "echo probe"), ("metrics_broken", "tadpole-no-such-program")] {
+            state.capabilities.save_skill(crate::agent::capabilities::SkillDefinition {
+                id: None,
+                name: name.to_string(),
+                description: "probe".to_string(),
+                execution_command: command.to_string(),
+                schema: serde_json::json!({ "type": "object", "properties": {} }),
+                doc_url: None,
+                tags: None,
+                enabled: true,
+                skill_cpu_limit_secs: None,
+                skill_memory_limit_mb: None,
+            }).await.unwrap();
+        }
+        // Settle the on-disk fingerprint so the watcher does not reload (and reset) mid-test
+        state.capabilities.reload_all().await.unwrap();
+
+        let ctx = bash_ctx(&state, "metrics-mission", data_dir.join("workspace"));
+        for name in ["metrics_probe", "metrics_probe", "metrics_broken"] {
+            let skill = state.capabilities.skill(name).unwrap();
+            let fc = crate::agent::types::GeminiFunctionCall { name: name.to_string(), args: serde_json::json!({}) };
+            let mut output = String::new();
+            runner.handle_dynamic_skill(&ctx, &fc, &mut output, &skill, &mut None).await.unwrap();
+        }
+
+        let probe = state.capabilities.skill_metrics("metrics_probe");
+        assert_eq!(probe.invocations, 2);
+        assert_eq!(probe.failure_rate, 0.0);
+        let broken = state.capabilities.skill_metrics("metrics_broken");
+        assert_eq!(broken.invocations, 1);
+        assert_eq!(broken.failure_rate, 1.0, "a skill that cannot start counts as a failure");
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[tokio::test]
+    async fn remembered_facts_are_recalled_and_injected_into_the_system_prompt() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let mut ctx = bash_ctx(&state, "memory-mission", std::env::temp_dir());
+        ctx.agent_id = format!("memory-agent-{}", uuid::Uuid::new_v4());
+
+        let call = |name: &str, args: serde_json::Value| crate::agent::types::GeminiFunctionCall { name: name.to_string(), args };
+        let mut output = String::new();
+        runner.dispatch_tool(&ctx, &call("remember_fact", serde_json::json!({ "key": "client_tz", "value": "Europe/Berlin" })), &mut output, &mut None).await.unwrap();
+        assert!(output.contains("Remembered 'client_tz'"), "got: {}", output);
+
+        let mut output = String::new();
+        runner.dispatch_tool(&ctx, &call("recall_fact", serde_json::json!({ "key": "client_tz" })), &mut output, &mut None).await.unwrap();
+        assert!(output.contains("\"Europe/Berlin\""), "got: {}", output);
+
+        let mut output = String::new();
+        runner.dispatch_tool(&ctx, &call("recall_fact", serde_json::json!({ "key": "unknown" })), &mut output, &mut None).await.unwrap();
+        assert!(output.contains("null"), "got: {}", output);
+
+        let prompt = runner.build_system_prompt(&ctx, "Alpha").await;
+        assert!(prompt.contains("--- AGENT MEMORY ---\n- client_tz: Europe/Berlin"), "got: {}", prompt);
+
+        crate::agent::memory::forget_fact(&state.pool, &ctx.agent_id, "client_tz").await.unwrap();
+        assert!(!runner.build_system_prompt(&ctx, "Alpha").await.contains("AGENT MEMORY"));
+    }
+
+    #[tokio::test]
+    async fn call_provider_fails_fast_for_an_unhealthy_provider() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let mut ctx = bash_ctx(&state, "health-mission", std::env::temp_dir());
+        ctx.provider_name = "gemini".to_string();
+
+        state.provider_health.insert("google".to_string(), crate::agent::provider_health::ProviderHealth {
+            last_checked: std::time::Instant::now(),
+            is_healthy: false,
+            latency_ms: 40,
+            error: Some("HTTP 401 Unauthorized".to_string()),
+        });
+        let started = std::time::Instant::now();
+        let err = runner.call_provider(&ctx, "system", "hello", None).await.unwrap_err().to_string();
+        assert!(err.contains("Provider 'gemini' is unhealthy (HTTP 401 Unauthorized"), "got: {}", err);
+        assert!(started.elapsed() < std::time::Duration::from_secs(1), "no request should be attempted");
+        state.provider_health.remove("google");
+    }
+
+    #[tokio::test]
+    async fn build_system_prompt_includes_role_and_department() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let ctx = RunContext {
+            name: "Agent of Nine".to_string(),
+            role: "CEO".to_string(),
+            department: "Executive".to_string(),
+            description: "Supreme tactical orchestrator.".to_string(),
+            provider_name: "google".to_string(),
+            ..test_ctx(&state, "1", "test-mission")
+        };
+
+        let prompt = runner.build_system_prompt(&ctx, "Alpha").await;
+        assert!(prompt.contains("Agent of Nine"));
+        assert!(prompt.contains("Executive"));
+        assert!(prompt.contains("Alpha"));
+    }
+
+    #[tokio::test]
+    async fn resolved_context_carries_prior_mission_turns() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let first = make_payload("Draft the Q3 hiring plan.");
+        let mission_id = runner.prepare_mission("1", &first).await.unwrap();
+        crate::agent::mission::log_step(&state.pool, &mission_id, "1", "System", "Agent is thinking...", "info", None).await.unwrap();
+        crate::agent::mission::log_step(&state.pool, &mission_id, "1", "Agent", "Plan: two engineers, one designer.", "success", None).await.unwrap();
+
+        // The follow-up is logged like any user message before the context is resolved
+        let follow_up = make_payload("Add a budget line for each role.");
+        crate::agent::mission::log_step(&state.pool, &mission_id, "1", "User", &follow_up.message, "info", None).await.unwrap();
+
+        let ctx = runner.resolve_agent_context("1", &follow_up, &mission_id, 0, &[]).await.unwrap();
+        assert_eq!(ctx.history, vec![
+            crate::agent::types::ConversationTurn { role: "user".to_string(), content: first.message.clone() },
+            crate::agent::types::ConversationTurn { role: "assistant".to_string(), content: "Plan: two engineers, one designer.".to_string() },
+        ]);
+
+        let mut config = ctx.model_config.clone();
+        config.max_history_turns = 1;
+        let window = config.history_window(Some(&ctx.history));
+        assert_eq!(window.len(), 1);
+        assert_eq!(window[0].role, "assistant", "The cap keeps the most recent turns");
+    }
+
+    #[tokio::test]
+    async fn agent_rate_override_replaces_model_limits() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let mut model = state.models.iter().next().expect("registry has models").value().clone();
+        model.id = "override-test-model".to_string();
+        model.rpm = Some(2000);
+        model.tpm = Some(500_000);
+        state.models.insert(model.id.clone(), model);
+
+        let mut agent = state.agents.get("1").unwrap().value().clone();
+        agent.id = "rate-override-agent".to_string();
+        agent.rpm_override = Some(1);
+        state.agents.insert(agent.id.clone(), agent);
+
+        let mut payload = make_payload("Summarize the quarter.");
+        payload.model_id = Some("override-test-model".to_string());
+        let ctx = runner.resolve_agent_context("rate-override-agent", &payload, "", 0, &[]).await.unwrap();
+        assert_eq!(ctx.model_config.rpm, Some(1));
+        assert_eq!(ctx.model_config.tpm, Some(500_000), "Limits without an override come from the model");
+
+        let limiter = state.agent_rate_limiter("rate-override-agent", &ctx.model_config);
+        assert_eq!(limiter.stats().rpm_limit, Some(1));
+        assert!(!Arc::ptr_eq(&limiter, &state.rate_limiter(&ctx.model_config)), "Overrides must not change the model's shared limiter");
+    }
+
+    #[tokio::test]
+    async fn resolve_agent_context_uses_the_switched_model_slot() {
+        use axum::extract::{Path, State};
+        use axum::response::IntoResponse;
+        let state = isolated_state().await;
+        let runner = AgentRunner::new(state.clone());
+
+        let mut agent = state.agents.get("1").unwrap().value().clone();
+        agent.id = format!("slot-switch-{}", uuid::Uuid::new_v4());
+        agent.status = "idle".to_string();
+        agent.active_model_slot = Some(1);
+        agent.model_id = Some("gemini-flash-latest".to_string());
+        agent.model_3 = Some("llama-3.3-70b-versatile".to_string());
+        crate::agent::persistence::save_agent_db(&state.pool, &agent).await.unwrap();
+        state.agents.insert(agent.id.clone(), agent.clone());
+        let mut events = state.event_tx.subscribe();
+
+        let payload = make_payload("Summarize the quarter.");
+        let ctx = runner.resolve_agent_context(&agent.id, &payload, "", 0, &[]).await.unwrap();
+        assert_eq!(ctx.model_config.model_id, "gemini-flash-latest");
+
+        let request = crate::routes::agent::SwitchModelRequest { slot: 3 };
+        let response = crate::routes::agent::switch_model(Path(agent.id.clone()), State(state.clone()), axum::Json(request)).await.into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let event = events.recv().await.unwrap();
+        assert_eq!(event["type"], "agent:model_switched");
+        assert_eq!(event["oldModelId"], "gemini-flash-latest");
+        assert_eq!(event["newModelId"], "llama-3.3-70b-versatile");
+
+        let ctx = runner.resolve_agent_context(&agent.id, &payload, "", 0, &[]).await.unwrap();
+        assert_eq!(ctx.model_config.model_id, "llama-3.3-70b-versatile");
+        assert_eq!(ctx.provider_name, "groq");
+        let stored = crate::agent::persistence::load_agents_db(&state.pool).await.unwrap()
+            .into_iter().find(|a| a.id == agent.id).unwrap();
+        assert_eq!(stored.active_model_slot, Some(3));
+    }
+
+    #[tokio::test]
+    async fn prepare_mission_blocks_until_dependency_completes() {
+        use crate::agent::mission::{get_mission_by_id, update_mission};
+        use crate::agent::types::MissionStatus;
+
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let dependency = crate::agent::mission::create_mission(&state.pool, "1", "Gather data", 1.0, &[]).await.unwrap();
+
+        let mut payload = make_payload("Analyze the data");
+        payload.depends_on = Some(dependency.id.clone());
+        let err = runner.prepare_mission("1", &payload).await.unwrap_err();
+        assert!(err.to_string().contains("blocked until dependency"), "{}", err);
+
+        update_mission(&state.pool, &dependency.id, MissionStatus::Completed, 0.0).await.unwrap();
+        let mission_id = runner.prepare_mission("1", &payload).await.unwrap();
+        let mission = get_mission_by_id(&state.pool, &mission_id).await.unwrap().unwrap();
+        assert_eq!(mission.status, MissionStatus::Pending, "ready to run once it gets a slot");
+        assert_eq!(mission.prerequisite_mission_id.as_deref(), Some(dependency.id.as_str()));
+
+        payload.depends_on = Some("no-such-mission".to_string());
+        assert!(runner.prepare_mission("1", &payload).await.unwrap_err().to_string().contains("does not exist"));
+    }
+
+    #[tokio::test]
+    async fn reloaded_identity_reaches_system_prompt() {
+        let data_dir = std::env::temp_dir().join(format!("tadpole_identity_{}", uuid::Uuid::new_v4()));
+        let identity_path = data_dir.join(IDENTITY_FILE);
+        std::fs::create_dir_all(identity_path.parent().unwrap()).unwrap();
+        std::fs::write(&identity_path, "# Tadpole OS\n").unwrap();
+        let config = crate::config::TadpoleConfig { data_dir: Some(data_dir.clone()), ..Default::default() };
+        let state = Arc::new(crate::state::AppState::for_tests(config).await);
+        let runner = AgentRunner::new(state.clone());
+        let marker = format!("Identity revision {}", uuid::Uuid::new_v4());
+        std::fs::write(&identity_path, format!("# Tadpole OS\n{}\n", marker)).unwrap();
+
+        let payload = make_payload("Who are you?");
+        let mission_id = runner.prepare_mission("1", &payload).await.unwrap();
+        let ctx = runner.resolve_agent_context("1", &payload, &mission_id, 0, &[]).await.unwrap();
+        let stale = runner.build_system_prompt(&ctx, "Alpha").await;
+
+        let response = axum::response::IntoResponse::into_response(
+            crate::routes::engine_control::reload_context(axum::extract::State(state.clone())).await
+        );
+        let fresh = runner.build_system_prompt(&ctx, "Alpha").await;
+        let _ = std::fs::remove_dir_all(&data_dir);
+
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert!(!stale.contains(&marker), "Prompt must come from the cache, not the disk");
+        assert!(fresh.contains(&marker), "Reloaded identity must appear in the system prompt");
+    }
+
+    #[tokio::test]
+    async fn build_system_prompt_truncates_memory_then_context_to_fit_window() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let model_id = format!("tiny-window-{}", uuid::Uuid::new_v4());
+        state.models.insert(model_id.clone(), crate::agent::types::ModelEntry {
+            id: model_id.clone(),
+            name: "Tiny Window".to_string(),
+            provider_id: "google".to_string(),
+            rpm: None,
+            tpm: None,
+            rpd: None,
+            tpd: None,
+            modality: Some("llm".to_string()),
+            context_window: Some(1000),
+            max_output_tokens: Some(256),
+        });
+        {
+            let mut system_context = state.system_context.write().await;
+            system_context.identity = "Tadpole OS".to_string();
+            system_context.long_term_memory = format!("OLDEST MEMORY\n{}\nNEWEST MEMORY", "remember this. ".repeat(400));
+        }
+
+        let mission = crate::agent::mission::create_mission(&state.pool, "1", "Window test", 1.0, &[]).await.unwrap();
+        crate::agent::mission::share_finding(&state.pool, &mission.id, "1", "Recent", "Latest finding survives", None).await.unwrap();
+
+        let mut model_config = state.agents.get("1").unwrap().model.clone();
+        model_config.model_id = model_id;
+        let ctx = RunContext {
+            name: "Agent of Nine".to_string(),
+            role: "CEO".to_string(),
+            department: "Executive".to_string(),
+            description: "Supreme tactical orchestrator.".to_string(),
+            model_config,
+            provider_name: "google".to_string(),
+            ..test_ctx(&state, "1", &mission.id.clone())
+        };
+
+        let prompt = runner.build_system_prompt(&ctx, "Alpha").await;
+        assert!(crate::agent::rates::estimate_tokens(&prompt) <= 800, "Prompt must fit 80% of the window");
+        assert!(prompt.contains(TRUNCATION_MARKER));
+        assert!(!prompt.contains("OLDEST MEMORY"), "The oldest memory is dropped first");
+        assert!(prompt.contains("NEWEST MEMORY"));
+        assert!(prompt.contains("Latest finding survives"), "Swarm context is only cut once memory alone is not enough");
+    }
+
+    #[tokio::test]
+    async fn build_system_prompt_includes_lineage_when_present() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let ctx = RunContext {
+            name: "Tadpole".to_string(),
+            role: "COO".to_string(),
+            department: "Operations".to_string(),
+            description: "Operational coordination specialist.".to_string(),
+            model_config: state.agents.get("2").unwrap().model.clone(),
+            provider_name: "google".to_string(),
+            depth: 1,
+            lineage: vec!["Agent of Nine".to_string()],
+            ..test_ctx(&state, "2", "test-mission")
+        };
+
+        let prompt = runner.build_system_prompt(&ctx, "Sub-Agent").await;
+        assert!(prompt.contains("Agent of Nine"), "Should contain parent in lineage");
+        assert!(prompt.contains("Tadpole"), "Should contain agent name");
+        assert!(prompt.contains("Sub-Agent"), "Should contain hierarchy label");
+    }
+
+    #[tokio::test]
+    async fn disputed_finding_shows_lower_confidence_in_mission_context() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let mission = crate::agent::mission::create_mission(&state.pool, "1", "Verify me", 1.0, &[]).await.unwrap();
+        let payload = TaskPayload { message: "go".to_string(), ..Default::default() };
+        let author = runner.resolve_agent_context("2", &payload, &mission.id, 1, &[]).await.unwrap();
+        let reviewer = runner.resolve_agent_context("3", &payload, &mission.id, 1, &[]).await.unwrap();
+        let call = |name: &str, args: serde_json::Value| crate::agent::types::GeminiFunctionCall { name: name.to_string(), args };
+
+        let mut output = String::new();
+        runner.handle_share_finding(&author, &call("share_finding", serde_json::json!({ "topic": "Churn", "finding": "Churn doubled in March", "confidence": 0.8 })), &mut output).await.unwrap();
+        let finding_id = crate::agent::mission::list_findings(&state.pool, &mission.id).await.unwrap()[0].id.clone();
+        let context = crate::agent::mission::get_mission_context(&state.pool, &mission.id).await.unwrap();
+        assert!(context.contains(&format!("id {} | confidence 0.80]", finding_id)), "got: {}", context);
+
+        let mut output = String::new();
+        runner.handle_verify_finding(&author, &call("verify_finding", serde_json::json!({ "finding_id": finding_id, "verdict": "disputed" })), &mut output).await.unwrap();
+        assert!(output.contains("cannot verify their own"), "got: {}", output);
+
+        let mut output = String::new();
+        runner.handle_verify_finding(&reviewer, &call("verify_finding", serde_json::json!({ "finding_id": finding_id, "verdict": "disputed" })), &mut output).await.unwrap();
+        assert!(output.contains("marked disputed"), "got: {}", output);
+        let context = crate::agent::mission::get_mission_context(&state.pool, &mission.id).await.unwrap();
+        assert!(context.contains("confidence 0.40, DISPUTED by 3 (reported 0.80)]"), "got: {}", context);
+
+        let mut output = String::new();
+        runner.handle_share_finding(&author, &call("share_finding", serde_json::json!({ "topic": "Churn", "finding": "x", "confidence": 7 })), &mut output).await.unwrap();
+        assert!(output.contains("SHARE FAILED"), "got: {}", output);
+    }
+
+    #[tokio::test]
+    async fn oversized_swarm_context_is_summarized_once_and_reused() {
+        let config = crate::config::TadpoleConfig { max_context_chars: Some(300), ..Default::default() };
+        let state = Arc::new(crate::state::AppState::for_tests(config).await);
+        let runner = AgentRunner::new(state.clone());
+        let mission = crate::agent::mission::create_mission(&state.pool, "1", "Summarize me", 1.0, &[]).await.unwrap();
+        for i in 0..10 {
+            crate::agent::mission::share_finding(&state.pool, &mission.id, "2", "Market", &format!("Competitor {} raised prices by {}% this quarter.", i, i + 3), None).await.unwrap();
+        }
+
+        let mut ctx = runner.resolve_agent_context("1", &TaskPayload { message: "go".to_string(), ..Default::default() }, &mission.id, 0, &[]).await.unwrap();
+        // The echo provider returns the synthesis prompt as the summary
+        ctx.model_config = echo_model_config(&state);
+        ctx.provider_name = "ollama".to_string();
+        let summaries = || sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM swarm_context WHERE mission_id = ?1 AND summary_through IS NOT NULL")
+            .bind(mission.id.clone())
+            .fetch_one(&state.pool);
+
+        runner.condense_mission_context(&ctx).await;
+        let context = crate::agent::mission::get_mission_context(&state.pool, &mission.id).await.unwrap();
+        assert!(context.starts_with(crate::agent::mission::CONTEXT_SUMMARY_TOPIC), "got: {}", context);
+        assert_eq!(context.matches("Competitor 9").count(), 1, "summarized findings must not be repeated: {}", context);
+        assert_eq!(summaries().await.unwrap(), 1);
+
+        // Still over the cap, but nothing new to fold in
+        runner.condense_mission_context(&ctx).await;
+        assert_eq!(summaries().await.unwrap(), 1);
+
+        crate::agent::mission::share_finding(&state.pool, &mission.id, "3", "Pricing", "Hold prices until Q3.", None).await.unwrap();
+        let context = crate::agent::mission::get_mission_context(&state.pool, &mission.id).await.unwrap();
+        assert!(context.ends_with("]: Hold prices until Q3.\n") && context.contains("[Context from 3 on Pricing |"), "got: {}", context);
+        assert!(runner.build_system_prompt(&ctx, "Alpha").await.contains("CONTEXT SUMMARY"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timed_out_hook_in_warn_mode_does_not_block_tool() {
+        use std::os::unix::fs::PermissionsExt;
+        use crate::agent::hooks::{HookFailureMode, HooksManager};
+
+        let hooks_dir = std::env::temp_dir().join(format!("tadpole_hook_timeout_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(hooks_dir.join("pre-tool")).unwrap();
+        let script = hooks_dir.join("pre-tool").join("slow.sh");
+        std::fs::write(&script, "#!/bin/sh
+sleep 30
+").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut state = crate::state::AppState::for_tests(Default::default()).await;
+        state.hooks = Arc::new(HooksManager::with_settings(hooks_dir.clone(), 1, HookFailureMode::Warn, Some(state.event_tx.clone())));
+        let state = Arc::new(state);
+        let mut events = state.event_tx.subscribe();
+        let runner = AgentRunner::new(state.clone());
+
+        let workspace = std::env::temp_dir().join(format!("tadpole_hook_ws_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::write(workspace.join("notes.txt"), "hook-free content").unwrap();
+        let ctx = RunContext {
+            name: "Researcher".to_string(),
+            role: "analyst".to_string(),
+            department: "Research".to_string(),
+            skills: vec!["read_file".to_string()],
+            workspace_root: workspace.clone(),
+            ..test_ctx(&state, "1", "hook-timeout-mission")
+        };
+
+        let started = std::time::Instant::now();
+        let call = crate::agent::types::GeminiFunctionCall { name: "read_file".to_string(), args: serde_json::json!({ "filename": "notes.txt" }) };
+        let mut output = String::new();
+        runner.execute_tool(&ctx, &call, &mut output, &mut None, "").await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(10), "the hook must be cut off at its timeout");
+        assert!(output.contains("hook-free content"), "the tool still ran: {}", output);
+
+        let event = loop {
+            let event = events.recv().await.unwrap();
+            if event["type"] == "hooks:script_failed" { break event; }
+        };
+        assert!(event["error"].as_str().unwrap().contains("timed out"));
+
+        let _ = std::fs::remove_dir_all(&hooks_dir);
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[tokio::test]
+    async fn execute_tool_applies_tool_error_policy() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let workspace = std::env::temp_dir().join(format!("tadpole_tool_error_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        let ctx = RunContext {
+            name: "Researcher".to_string(),
+            role: "analyst".to_string(),
+            department: "Research".to_string(),
+            skills: vec!["read_file".to_string(), "fetch_url".to_string()],
+            workspace_root: workspace.clone(),
+            ..test_ctx(&state, "1", "tool-error-mission")
+        };
+        let call = |name: &str, args: serde_json::Value| crate::agent::types::GeminiFunctionCall { name: name.to_string(), args };
+
+        // Security violations and missing files abort the run
+        let mut output = String::new();
+        let err = runner.execute_tool(&ctx, &call("read_file", serde_json::json!({ "filename": "../secret.txt" })), &mut output, &mut None, "").await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ToolError>(), Some(ToolError::SecurityViolation(_))), "got: {}", err);
+        let err = runner.execute_tool(&ctx, &call("read_file", serde_json::json!({ "filename": "missing.txt" })), &mut output, &mut None, "").await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ToolError>(), Some(ToolError::NotFound(_))), "got: {}", err);
+
+        // Network failures are retried once after a backoff, then reported as tool output
+        let mut output = "draft".to_string();
+        let started = std::time::Instant::now();
+        let result = runner.execute_tool(&ctx, &call("fetch_url", serde_json::json!({ "url": "http://127.0.0.1:1/" })), &mut output, &mut None, "").await.unwrap();
+        assert!(result.is_none());
+        assert!(started.elapsed() >= Duration::from_millis(TOOL_RETRY_BASE_DELAY_MS), "expected one retry delay");
+        assert!(output.starts_with("(TOOL ERROR in fetch_url: network error"), "got: {}", output);
+        assert!(output.ends_with("draft"));
+
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[tokio::test]
+    async fn execute_tool_refuses_tools_the_run_was_not_given() {
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+
+        let workspace = std::env::temp_dir().join(format!("tadpole_tool_guard_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&workspace).unwrap();
+        let mut ctx = RunContext {
+            name: "Researcher".to_string(),
+            role: "analyst".to_string(),
+            department: "Research".to_string(),
+            skills: vec!["execute_bash".to_string(), "read_file".to_string()],
+            workspace_root: workspace.clone(),
+            safe_mode: true,
+            ..test_ctx(&state, "1", "tool-guard-mission")
+        };
+        let call = |name: &str, args: serde_json::Value| crate::agent::types::GeminiFunctionCall { name: name.to_string(), args };
+        let marker = workspace.join("pwned");
+
+        // A safe-mode run must not reach the bash handler even though the agent holds the skill
+        let bash = call("execute_bash", serde_json::json!({ "command": format!("touch {}", marker.display()) }));
+        let mut output = String::new();
+        // Reaching the handler would park the call on oversight, so a timeout means it got through
+        let result = timeout(Duration::from_secs(5), runner.execute_tool(&ctx, &bash, &mut output, &mut None, "")).await
+            .expect("execute_bash reached its handler");
+        assert!(result.unwrap().is_none());
+        assert!(output.starts_with("(SKILL DENIED: 'execute_bash' is not available in safe mode)"), "got: {}", output);
+        assert!(!marker.exists());
+
+        // Neither may tools the agent was never given, safe mode or not
+        ctx.safe_mode = false;
+        let mut output = String::new();
+        runner.execute_tool(&ctx, &call("write_file", serde_json::json!({ "filename": "x.txt", "content": "x" })), &mut output, &mut None, "").await.unwrap();
+        assert!(output.starts_with("(SKILL DENIED: 'write_file' is not one of this agent's skills)"), "got: {}", output);
+        assert!(!workspace.join("x.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[test]
+    fn tool_error_classifies_underlying_failures() {
+        let missing: ToolError = std::io::Error::new(std::io::ErrorKind::NotFound, "gone").into();
+        assert!(matches!(missing, ToolError::NotFound(_)) && missing.is_fatal());
+        let denied: ToolError = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "nope").into();
+        assert!(matches!(denied, ToolError::Internal(_)) && !denied.is_fatal() && !denied.is_retryable());
+        assert!(ToolError::Timeout("slow".to_string()).is_retryable());
+
+        // A typed error that travelled through anyhow keeps its variant
+        let wrapped: ToolError = anyhow::Error::from(ToolError::SecurityViolation("escape".to_string())).into();
+        assert_eq!(wrapped, ToolError::SecurityViolation("escape".to_string()));
+        let db: ToolError = anyhow::anyhow!("database is locked").into();
+        assert_eq!(db, ToolError::Internal("database is locked".to_string()));
+    }
+
+    /// `(span name, parent span name, trace_id field)`.
+    type RecordedSpan = (String, Option<String>, Option<String>);
+
+    /// Records every span created while it is the default subscriber.
+    #[derive(Clone, Default)]
+    struct SpanRecorder(Arc<std::sync::Mutex<Vec<RecordedSpan>>>);
+
+    impl<S> tracing_subscriber::Layer<S> for SpanRecorder
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &tracing::span::Attributes<'_>, id: &tracing::span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+            struct TraceId(Option<String>);
+            impl tracing::field::Visit for TraceId {
+                fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                    if field.name() == "trace_id" {
+                        self.0 = Some(format!("{:?}", value));
+                    }
+                }
+            }
+            let mut trace_id = TraceId(None);
+            attrs.record(&mut trace_id);
+            let parent = ctx.span(id).and_then(|span| span.parent()).map(|parent| parent.name().to_string());
+            self.0.lock().unwrap().push((attrs.metadata().name().to_string(), parent, trace_id.0));
+        }
+    }
+
+    #[tokio::test]
+    async fn spawned_subagent_span_nests_under_parent_tool_call() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let recorder = SpanRecorder::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+
+        let state = Arc::new(crate::state::AppState::for_tests(Default::default()).await);
+        let runner = AgentRunner::new(state.clone());
+        let mut model_config = state.agents.get("1").unwrap().model.clone();
+        model_config.provider = "mock".to_string();
+        let ctx = RunContext {
+            name: "Researcher".to_string(),
+            role: "analyst".to_string(),
+            department: "Research".to_string(),
+            model_config,
+            trace_id: "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+            ..test_ctx(&state, "1", "trace-parent-mission")
+        };
+        let call = crate::agent::types::GeminiFunctionCall {
+            name: "spawn_subagent".to_string(),
+            args: serde_json::json!({ "agentId": "2", "message": "Check the numbers" }),
+        };
+
+        // The mock provider fails the sub-agent's model call; the spans are recorded either way
+        let _ = runner.execute_tool(&ctx, &call, &mut String::new(), &mut None, "").await;
+
+        let spans = recorder.0.lock().unwrap().clone();
+        let find = |name: &str| spans.iter().find(|(n, _, _)| n == name).cloned()
+            .unwrap_or_else(|| panic!("no {} span in {:?}", name, spans));
+        assert_eq!(find("execute_tool").1, None);
+        let (_, parent, trace_id) = find("agent.run");
+        assert_eq!(parent.as_deref(), Some("execute_tool"));
+        assert_eq!(trace_id.as_deref(), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+        assert_eq!(find("call_provider").1.as_deref(), Some("agent.run"));
+    }
+}
@@ -0,0 +1,259 @@
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::process::Command;
+
+use crate::agent::capabilities::SkillDefinition;
+
+/// CPU seconds a skill subprocess may use when neither the skill nor `SKILL_CPU_LIMIT_SECS` sets it.
+pub const DEFAULT_SKILL_CPU_LIMIT_SECS: u64 = 30;
+/// Memory cap for a skill subprocess when neither the skill nor `SKILL_MEMORY_LIMIT_MB` sets it.
+pub const DEFAULT_SKILL_MEMORY_LIMIT_MB: u64 = 256;
+
+/// Resource caps applied to a dynamic skill's subprocess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    pub cpu_secs: u64,
+    /// Virtual address space on Unix, committed memory on Windows.
+    pub memory_mb: u64,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self { cpu_secs: DEFAULT_SKILL_CPU_LIMIT_SECS, memory_mb: DEFAULT_SKILL_MEMORY_LIMIT_MB }
+    }
+}
+
+impl ResourceLimits {
+    /// The skill's own `skill_cpu_limit_secs`/`skill_memory_limit_mb`, falling back to the
+    /// system-wide `defaults` for any that are unset or zero.
+    pub fn for_skill(skill: &SkillDefinition, defaults: ResourceLimits) -> Self {
+        Self {
+            cpu_secs: skill.skill_cpu_limit_secs.filter(|n| *n > 0).unwrap_or(defaults.cpu_secs),
+            memory_mb: skill.skill_memory_limit_mb.filter(|n| *n > 0).unwrap_or(defaults.memory_mb),
+        }
+    }
+}
+
+/// How a sandboxed subprocess ended.
+#[derive(Debug)]
+pub enum SandboxOutcome {
+    /// The process exited on its own, successfully or not.
+    Completed(Output),
+    /// The OS killed the process for using more CPU time than `ResourceLimits::cpu_secs`.
+    CpuLimitExceeded(Output),
+    /// The process outran the wall-clock timeout and was killed by the engine.
+    TimedOut,
+}
+
+/// Spawns `cmd` under `limits` and collects its output, killing it once `wall_clock` elapses.
+/// CPU exhaustion is reported separately from the wall-clock timeout so a busy loop and a
+/// process stuck waiting on I/O produce different errors.
+pub async fn run(mut cmd: Command, limits: ResourceLimits, wall_clock: Duration) -> std::io::Result<SandboxOutcome> {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // A process abandoned by the timeout must not outlive it
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    set_rlimits(&mut cmd, limits);
+
+    #[cfg(unix)]
+    let cpu_before = children_cpu_time();
+    let child = cmd.spawn()?;
+    // Held until the process exits; the job's limits stay attached to it either way
+    #[cfg(windows)]
+    let _job = windows_job::confine(&child, limits)?;
+    #[cfg(not(any(unix, windows)))]
+    tracing::warn!("⚠️ [Sandbox] Resource limits ({:?}) are not supported on this platform", limits);
+
+    match tokio::time::timeout(wall_clock, child.wait_with_output()).await {
+        Ok(output) => {
+            let output = output?;
+            #[cfg(unix)]
+            let cpu_used = children_cpu_time().saturating_sub(cpu_before);
+            #[cfg(not(unix))]
+            let cpu_used = Duration::ZERO;
+            Ok(if cpu_limit_exceeded(&output.status, cpu_used, limits) {
+                SandboxOutcome::CpuLimitExceeded(output)
+            } else {
+                SandboxOutcome::Completed(output)
+            })
+        }
+        Err(_) => Ok(SandboxOutcome::TimedOut),
+    }
+}
+
+/// Sets `RLIMIT_CPU` and `RLIMIT_AS` in the child between fork and exec.
+#[cfg(unix)]
+fn set_rlimits(cmd: &mut Command, limits: ResourceLimits) {
+    let cpu = limits.cpu_secs as libc::rlim_t;
+    let memory = limits.memory_mb.saturating_mul(1024 * 1024) as libc::rlim_t;
+    // SAFETY: the hook runs in the forked child and only calls setrlimit, which is async-signal-safe.
+    unsafe {
+        cmd.pre_exec(move || {
+            // The soft limit delivers SIGXCPU; the hard limit a second later is a SIGKILL for processes that catch it
+            let cpu_limit = libc::rlimit { rlim_cur: cpu, rlim_max: cpu.saturating_add(1) };
+            if libc::setrlimit(libc::RLIMIT_CPU, &cpu_limit) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            let memory_limit = libc::rlimit { rlim_cur: memory, rlim_max: memory };
+            if libc::setrlimit(libc::RLIMIT_AS, &memory_limit) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// CPU time (user + system) of every child this process has reaped so far.
+#[cfg(unix)]
+fn children_cpu_time() -> Duration {
+    // SAFETY: rusage is plain data, and getrusage only writes into it.
+    let usage = unsafe {
+        let mut usage: libc::rusage = std::mem::zeroed();
+        libc::getrusage(libc::RUSAGE_CHILDREN, &mut usage);
+        usage
+    };
+    let duration = |t: libc::timeval| Duration::new(t.tv_sec.max(0) as u64, (t.tv_usec.max(0) as u32).saturating_mul(1000));
+    duration(usage.ru_utime) + duration(usage.ru_stime)
+}
+
+/// Whether the process was terminated by its CPU limit rather than exiting by itself.
+/// `cpu_used` is the growth of reaped-children CPU time across the run (Unix only).
+fn cpu_limit_exceeded(status: &std::process::ExitStatus, cpu_used: Duration, limits: ResourceLimits) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        match status.signal() {
+            Some(libc::SIGXCPU) => true,
+            // The hard limit's SIGKILL looks like any other kill (OOM killer, operator), so it only
+            // counts when the run actually used up its CPU allowance. Children reaped concurrently
+            // also add to `cpu_used`, so this errs towards reporting the limit.
+            Some(libc::SIGKILL) => cpu_used >= Duration::from_secs(limits.cpu_secs),
+            _ => false,
+        }
+    }
+    #[cfg(windows)]
+    {
+        let _ = (cpu_used, limits);
+        // Processes terminated by JOB_OBJECT_LIMIT_PROCESS_TIME exit with ERROR_NOT_ENOUGH_QUOTA
+        status.code() == Some(windows_sys::Win32::Foundation::ERROR_NOT_ENOUGH_QUOTA as i32)
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (status, cpu_used, limits);
+        false
+    }
+}
+
+#[cfg(windows)]
+mod windows_job {
+    use super::ResourceLimits;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_PROCESS_MEMORY, JOB_OBJECT_LIMIT_PROCESS_TIME,
+    };
+
+    /// An anonymous job object, closed on drop.
+    pub struct Job(HANDLE);
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            // SAFETY: the handle came from CreateJobObjectW and is closed exactly once.
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    /// Puts `child` in a new job object with a per-process memory and user-mode CPU time limit.
+    /// The process runs unconfined for the moment between spawn and assignment.
+    pub fn confine(child: &tokio::process::Child, limits: ResourceLimits) -> std::io::Result<Job> {
+        let Some(process) = child.raw_handle() else {
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, "skill process exited before it could be confined"));
+        };
+
+        // SAFETY: plain kernel32 calls on handles we own; the info struct is plain data and valid when zeroed.
+        unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if job == 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            let job = Job(job);
+
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_PROCESS_MEMORY | JOB_OBJECT_LIMIT_PROCESS_TIME;
+            // In 100-nanosecond ticks
+            info.BasicLimitInformation.PerProcessUserTimeLimit = (limits.cpu_secs as i64).saturating_mul(10_000_000);
+            info.ProcessMemoryLimit = (limits.memory_mb as usize).saturating_mul(1024 * 1024);
+            if SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const core::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            ) == 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if AssignProcessToJobObject(job.0, process as HANDLE) == 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(job)
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> Command {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(script);
+        cmd
+    }
+
+    #[tokio::test]
+    async fn busy_loop_hits_cpu_limit_not_wall_clock() {
+        let limits = ResourceLimits { cpu_secs: 1, memory_mb: DEFAULT_SKILL_MEMORY_LIMIT_MB };
+        let outcome = run(shell("while :; do :; done"), limits, Duration::from_secs(30)).await.unwrap();
+        assert!(matches!(outcome, SandboxOutcome::CpuLimitExceeded(_)), "got: {:?}", outcome);
+    }
+
+    #[tokio::test]
+    async fn sigkill_counts_as_cpu_limit_only_after_the_allowance_is_spent() {
+        let limits = ResourceLimits { cpu_secs: 1, memory_mb: DEFAULT_SKILL_MEMORY_LIMIT_MB };
+        // Ignoring SIGXCPU leaves the hard limit's SIGKILL one second later
+        let outcome = run(shell("trap '' XCPU; while :; do :; done"), limits, Duration::from_secs(30)).await.unwrap();
+        assert!(matches!(outcome, SandboxOutcome::CpuLimitExceeded(_)), "got: {:?}", outcome);
+
+        let outcome = run(shell("kill -KILL $$"), limits, Duration::from_secs(10)).await.unwrap();
+        assert!(matches!(outcome, SandboxOutcome::Completed(_)), "an external SIGKILL is not CPU exhaustion, got: {:?}", outcome);
+    }
+
+    #[tokio::test]
+    async fn idle_process_hits_wall_clock_and_normal_exit_completes() {
+        let outcome = run(shell("sleep 5"), ResourceLimits::default(), Duration::from_millis(200)).await.unwrap();
+        assert!(matches!(outcome, SandboxOutcome::TimedOut), "got: {:?}", outcome);
+
+        let outcome = run(shell("echo sandboxed; exit 3"), ResourceLimits::default(), Duration::from_secs(10)).await.unwrap();
+        let SandboxOutcome::Completed(output) = outcome else { panic!("got: {:?}", outcome) };
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "sandboxed");
+    }
+
+    #[test]
+    fn skill_limits_override_the_system_defaults() {
+        let mut skill: SkillDefinition = serde_json::from_value(serde_json::json!({
+            "name": "crunch",
+            "description": "d",
+            "execution_command": "python crunch.py",
+            "schema": { "type": "object", "properties": {} },
+            "skill_cpu_limit_secs": 120
+        })).unwrap();
+        let defaults = ResourceLimits { cpu_secs: 30, memory_mb: 512 };
+        assert_eq!(ResourceLimits::for_skill(&skill, defaults), ResourceLimits { cpu_secs: 120, memory_mb: 512 });
+
+        skill.skill_cpu_limit_secs = Some(0);
+        skill.skill_memory_limit_mb = Some(64);
+        assert_eq!(ResourceLimits::for_skill(&skill, defaults), ResourceLimits { cpu_secs: 30, memory_mb: 64 });
+    }
+}
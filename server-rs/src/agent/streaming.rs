@@ -0,0 +1,120 @@
+use futures::channel::mpsc;
+use futures::StreamExt;
+use tokio::sync::oneshot;
+use crate::agent::types::{GeminiFunctionCall, TokenUsage};
+
+/// Tool calls and usage collected while a streaming completion was read.
+#[derive(Debug, Default)]
+pub struct StreamOutcome {
+    pub function_calls: Vec<GeminiFunctionCall>,
+    pub usage: Option<TokenUsage>,
+}
+
+/// A streaming completion: `deltas` yields text fragments as they arrive.
+/// Once it is drained, `finish` returns the tool calls and usage from the same response.
+pub struct StreamingResponse {
+    pub deltas: mpsc::UnboundedReceiver<String>,
+    outcome: oneshot::Receiver<anyhow::Result<StreamOutcome>>,
+}
+
+impl StreamingResponse {
+    /// Waits for the stream to end and returns what was collected alongside the text.
+    pub async fn finish(self) -> anyhow::Result<(Vec<GeminiFunctionCall>, Option<TokenUsage>)> {
+        let outcome = self.outcome.await
+            .map_err(|_| anyhow::anyhow!("Stream reader stopped unexpectedly"))??;
+        Ok((outcome.function_calls, outcome.usage))
+    }
+}
+
+/// Reads a `text/event-stream` response in the background. Every `data:` payload is handed
+/// to `on_event` with the outcome accumulator and the delta sender; `[DONE]` ends the stream.
+pub fn spawn_sse_reader<F>(response: reqwest::Response, mut on_event: F) -> StreamingResponse
+where
+    F: FnMut(&str, &mut StreamOutcome, &mpsc::UnboundedSender<String>) -> anyhow::Result<()> + Send + 'static,
+{
+    let (delta_tx, delta_rx) = mpsc::unbounded();
+    let (outcome_tx, outcome_rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        let result = async {
+            let mut outcome = StreamOutcome::default();
+            let mut body = response.bytes_stream();
+            // Bytes, not text: a multi-byte character may be split across chunks
+            let mut buffer: Vec<u8> = Vec::new();
+
+            loop {
+                let chunk = body.next().await.transpose()?;
+                let at_eof = chunk.is_none();
+                match chunk {
+                    Some(chunk) => buffer.extend_from_slice(&chunk),
+                    // A last event without a trailing newline is still an event
+                    None if !buffer.is_empty() => buffer.push(b'\n'),
+                    None => break,
+                }
+                while let Some(newline) = buffer.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=newline).collect();
+                    let line = String::from_utf8_lossy(&line);
+                    let Some(data) = line.trim().strip_prefix("data:") else { continue };
+                    let data = data.trim();
+                    if data == "[DONE]" {
+                        return Ok(outcome);
+                    }
+                    if !data.is_empty() {
+                        on_event(data, &mut outcome, &delta_tx)?;
+                    }
+                }
+                if at_eof {
+                    break;
+                }
+            }
+            Ok(outcome)
+        }.await;
+
+        // Close the delta stream before reporting so consumers never wait on both
+        drop(delta_tx);
+        let _ = outcome_tx.send(result);
+    });
+
+    StreamingResponse { deltas: delta_rx, outcome: outcome_rx }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sse_reader_keeps_split_characters_and_flushes_last_line() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // "héllo" with the two bytes of 'é' in separate writes, then a final event with no newline
+        let event = "data: h\u{e9}llo\n\n".as_bytes();
+        let split = event.iter().position(|b| *b == 0xC3).unwrap() + 1;
+        let writes = [event[..split].to_vec(), event[split..].to_vec(), b"data: last".to_vec()];
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            socket.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n").await.unwrap();
+            for write in writes {
+                socket.write_all(&write).await.unwrap();
+                socket.flush().await.unwrap();
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+        });
+
+        let response = reqwest::get(format!("http://{}/", addr)).await.unwrap();
+        let mut streaming = spawn_sse_reader(response, |data, _, deltas| {
+            let _ = deltas.unbounded_send(data.to_string());
+            Ok(())
+        });
+        let mut received = Vec::new();
+        while let Some(delta) = streaming.deltas.next().await {
+            received.push(delta);
+        }
+        assert_eq!(received, ["h\u{e9}llo", "last"]);
+        assert!(streaming.finish().await.is_ok());
+    }
+}
@@ -1,13 +1,34 @@
 use std::sync::Arc;
-use tokio::sync::oneshot;
 use crate::agent::runner::{AgentRunner};
-use crate::agent::types::{TaskPayload, ModelConfig, ToolCall};
+use crate::agent::types::{OversightEntry, ToolCall};
 use crate::state::AppState;
 
+/// Waits up to a second for the oversight entry queued by `mission_id`, then resolves it.
+/// Returns the resolved entry, or `None` if that mission never asked for oversight.
+pub(crate) async fn resolve_pending(state: &AppState, mission_id: &str, approved: bool) -> Option<OversightEntry> {
+    for _ in 0..50 {
+        // The queue entry is inserted before its resolver, so wait for both
+        let entry = state.oversight_queue.iter()
+            .find(|e| e.value().mission_id.as_deref() == Some(mission_id) && state.oversight_resolvers.contains_key(e.key()))
+            .map(|e| e.value().clone());
+        if let Some((_, tx)) = entry.as_ref().and_then(|e| state.oversight_resolvers.remove(&e.id)) {
+            let _ = tx.send(approved);
+            return entry;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    None
+}
+
+/// Approves the oversight entry queued by `mission_id`; see [`resolve_pending`].
+pub(crate) async fn approve_pending(state: &AppState, mission_id: &str) -> Option<OversightEntry> {
+    resolve_pending(state, mission_id, true).await
+}
+
 #[tokio::test]
 async fn test_e2e_oversight_approval_loop() {
     // 1. Setup AppState and Database
-    let state = Arc::new(AppState::new().await);
+    let state = Arc::new(AppState::for_tests(Default::default()).await);
     let runner = AgentRunner::new(state.clone());
     
     let test_id = uuid::Uuid::new_v4().to_string();
@@ -39,29 +60,14 @@ async fn test_e2e_oversight_approval_loop() {
         runner_clone.submit_oversight(tool_call, Some(mid_clone)).await
     });
 
-    // 4. Wait for the resolver to be registered in the queue
-    let mut entry_id = String::new();
-    let mut resolved = false;
-    for _ in 0..10 {
-        if let Some(kv) = state.oversight_resolvers.iter().next() {
-            entry_id = kv.key().clone();
-            resolved = true;
-            break;
-        }
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-    }
-    assert!(resolved, "Oversight resolver should have been registered");
-
-    // 5. Simulate the UI approval event
-    if let Some((_, tx)) = state.oversight_resolvers.remove(&entry_id) {
-        let _ = tx.send(true); // Approve
-    }
+    // 4. Wait for the entry and simulate the UI approval event
+    assert!(approve_pending(&state, &mission_id).await.is_some(), "Oversight resolver should have been registered");
 
-    // 6. Verify the result
+    // 5. Verify the result
     let approved = handle.await.unwrap();
     assert!(approved, "Oversight should have been approved");
     
-    // 7. Test Rejection
+    // 6. Test Rejection
     let tool_call_rej = ToolCall {
         id: "call-456".to_string(),
         agent_id: agent_id.clone(),
@@ -74,23 +80,177 @@ async fn test_e2e_oversight_approval_loop() {
     };
 
     let runner_clone2 = runner.clone();
+    let mid_clone = mission_id.clone();
     let handle_rej = tokio::spawn(async move {
-        runner_clone2.submit_oversight(tool_call_rej, Some(mission_id)).await
+        runner_clone2.submit_oversight(tool_call_rej, Some(mid_clone)).await
     });
 
-    let mut entry_id_rej = String::new();
-    for _ in 0..10 {
-        if let Some(kv) = state.oversight_resolvers.iter().next() {
-            entry_id_rej = kv.key().clone();
+    assert!(resolve_pending(&state, &mission_id, false).await.is_some());
+
+    let approved_rej = handle_rej.await.unwrap();
+    assert!(!approved_rej, "Oversight should have been rejected");
+}
+
+#[tokio::test]
+async fn test_bulk_decision_resolves_only_listed_entries() {
+    let state = Arc::new(AppState::for_tests(Default::default()).await);
+    let runner = AgentRunner::new(state.clone());
+    let agent_id = format!("bulk-agent-{}", uuid::Uuid::new_v4());
+
+    let handles: Vec<_> = (0..3).map(|i| {
+        let runner = runner.clone();
+        let tool_call = ToolCall {
+            id: format!("call-bulk-{}", i),
+            agent_id: agent_id.clone(),
+            mission_id: None,
+            skill: "delete_file".to_string(),
+            params: serde_json::json!({ "filename": format!("file-{}.txt", i) }),
+            department: "Compliance".to_string(),
+            description: format!("Bulk oversight {}", i),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        tokio::spawn(async move { runner.submit_oversight(tool_call, None).await })
+    }).collect();
+
+    let mut pending = Vec::new();
+    for _ in 0..50 {
+        pending = state.oversight_queue.iter()
+            .filter(|e| e.value().tool_call.as_ref().is_some_and(|tc| tc.agent_id == agent_id))
+            .map(|e| (e.key().clone(), e.value().tool_call.as_ref().unwrap().id.clone()))
+            .collect();
+        if pending.len() == 3 {
             break;
         }
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
     }
+    assert_eq!(pending.len(), 3, "all three entries should be queued");
+    pending.sort_by(|a, b| a.1.cmp(&b.1));
+    let (remaining_id, _) = pending.pop().unwrap();
+    let approve: Vec<String> = pending.into_iter().map(|(id, _)| id).collect();
 
-    if let Some((_, tx)) = state.oversight_resolvers.remove(&entry_id_rej) {
-        let _ = tx.send(false); // Reject
+    let mut ids = approve.clone();
+    ids.push("already-gone".to_string());
+    let response = axum::response::IntoResponse::into_response(crate::routes::oversight::decide_oversight_bulk(
+        axum::extract::State(state.clone()),
+        axum::Json(crate::routes::oversight::BulkOversightDecision { ids, decision: "approved".to_string() }),
+    ).await);
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_slice(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(body["decided"], serde_json::json!(approve));
+    assert_eq!(body["not_found"], serde_json::json!(["already-gone"]));
+
+    let mut handles = handles.into_iter();
+    assert!(handles.next().unwrap().await.unwrap(), "first entry approved");
+    assert!(handles.next().unwrap().await.unwrap(), "second entry approved");
+    assert!(state.oversight_queue.contains_key(&remaining_id), "third entry remains pending");
+    assert!(state.oversight_resolvers.contains_key(&remaining_id));
+
+    state.oversight_queue.remove(&remaining_id);
+    if let Some((_, tx)) = state.oversight_resolvers.remove(&remaining_id) {
+        let _ = tx.send(false);
     }
+    assert!(!handles.next().unwrap().await.unwrap());
+}
 
-    let approved_rej = handle_rej.await.unwrap();
-    assert!(!approved_rej, "Oversight should have been rejected");
+#[tokio::test]
+async fn test_decision_survives_state_restart() {
+    // Outlives both states, so the shared file is removed once the test ends
+    let database = crate::state::TestDatabase::new();
+    let config = || crate::config::TadpoleConfig { database_url: Some(database.url()), ..Default::default() };
+    let state = Arc::new(AppState::new(config()).await);
+    let runner = AgentRunner::new(state.clone());
+    let agent_id = format!("persist-agent-{}", uuid::Uuid::new_v4());
+
+    let tool_call = ToolCall {
+        id: "call-persist".to_string(),
+        agent_id: agent_id.clone(),
+        mission_id: None,
+        skill: "delete_file".to_string(),
+        params: serde_json::json!({ "filename": "ledger.txt" }),
+        department: "Compliance".to_string(),
+        description: "Persisted oversight".to_string(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let handle = tokio::spawn(async move { runner.submit_oversight(tool_call, None).await });
+
+    let mut entry_id = None;
+    for _ in 0..50 {
+        entry_id = state.oversight_queue.iter()
+            .find(|e| e.value().tool_call.as_ref().is_some_and(|tc| tc.agent_id == agent_id))
+            .map(|e| e.key().clone());
+        if entry_id.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let entry_id = entry_id.expect("entry should be queued");
+
+    let response = axum::response::IntoResponse::into_response(crate::routes::oversight::decide_oversight(
+        axum::extract::Path(entry_id.clone()),
+        axum::extract::State(state.clone()),
+        axum::Json(crate::agent::types::OversightDecision { decision: "rejected".to_string() }),
+    ).await);
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    assert!(!handle.await.unwrap());
+    drop(state);
+
+    let restarted = AppState::new(config()).await;
+    let history = crate::agent::mission::get_oversight_history(&restarted.pool, &crate::agent::mission::OversightFilter {
+        agent_id: Some(agent_id.clone()),
+        page: 1,
+        per_page: 10,
+        ..Default::default()
+    }).await.unwrap();
+    assert_eq!(history.total, 1);
+    let record = &history.items[0];
+    assert_eq!(record.id, entry_id);
+    assert_eq!(record.status, "rejected");
+    assert_eq!(record.skill, "delete_file");
+    assert_eq!(record.params["filename"], "ledger.txt");
+    assert_eq!(record.decided_by.as_deref(), Some("user"));
+
+    let ledger = restarted.oversight_ledger.lock().unwrap().clone();
+    let warmed = ledger.iter().find(|e| e["id"] == entry_id.as_str()).expect("ledger is warmed from oversight_log");
+    assert_eq!(warmed["decision"], "rejected");
+    assert_eq!(warmed["toolCall"]["agentId"], agent_id.as_str());
+}
+
+#[tokio::test]
+async fn test_policy_rules_decide_without_queueing() {
+    use crate::agent::oversight_policy::{OversightPolicy, PolicyAction, PolicyRule};
+
+    let mut state = AppState::for_tests(Default::default()).await;
+    state.oversight_policy = OversightPolicy::with_rules(vec![
+        PolicyRule { skill_pattern: "notify_*".to_string(), agent_department_pattern: "*".to_string(), action: PolicyAction::AutoApprove, max_param_length: Some(200) },
+        PolicyRule { skill_pattern: "delete_*".to_string(), agent_department_pattern: "compliance".to_string(), action: PolicyAction::AutoReject, max_param_length: None },
+    ]);
+    let state = Arc::new(state);
+    let runner = AgentRunner::new(state.clone());
+    let agent_id = format!("policy-agent-{}", uuid::Uuid::new_v4());
+    let call = |skill: &str| ToolCall {
+        id: "call-policy".to_string(),
+        agent_id: agent_id.clone(),
+        mission_id: None,
+        skill: skill.to_string(),
+        params: serde_json::json!({ "message": "build finished" }),
+        department: "Compliance".to_string(),
+        description: "Policy decision".to_string(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+
+    // Neither call may block: a queued entry would wait out the full oversight timeout
+    let approved = tokio::time::timeout(std::time::Duration::from_secs(5), runner.submit_oversight(call("notify_slack"), None)).await;
+    assert_eq!(approved.ok(), Some(true));
+    let rejected = tokio::time::timeout(std::time::Duration::from_secs(5), runner.submit_oversight(call("delete_file"), None)).await;
+    assert_eq!(rejected.ok(), Some(false));
+    assert!(state.oversight_queue.iter().all(|e| e.value().tool_call.as_ref().is_none_or(|tc| tc.agent_id != agent_id)));
+
+    let history = crate::agent::mission::get_oversight_history(&state.pool, &crate::agent::mission::OversightFilter {
+        agent_id: Some(agent_id.clone()),
+        page: 1,
+        per_page: 10,
+        ..Default::default()
+    }).await.unwrap();
+    assert_eq!(history.total, 2);
+    assert!(history.items.iter().all(|r| r.decided_by.as_deref() == Some("policy")));
 }
@@ -1,6 +1,6 @@
 use sqlx::SqlitePool;
 use anyhow::Result;
-use crate::agent::persistence::{load_agents_db, save_agent_db};
+use crate::agent::persistence::{create_agent_db, load_agents_db, load_deleted_agents_db, save_agent_db, soft_delete_agent_db};
 use crate::agent::types::{EngineAgent, ModelConfig, TokenUsage};
 use std::collections::HashMap;
 
@@ -32,7 +32,11 @@ async fn test_database_persistence() -> Result<()> {
             model_3 TEXT,
             model_config2 TEXT,
             model_config3 TEXT,
-            active_model_slot INTEGER DEFAULT 1
+            active_model_slot INTEGER DEFAULT 1,
+            skill_allowlist TEXT,
+            deleted_at DATETIME,
+            rpm_override INTEGER,
+            tpm_override INTEGER
         )"
     ).execute(&pool).await?;
 
@@ -56,6 +60,8 @@ async fn test_database_persistence() -> Result<()> {
             rpd: None,
             tpm: None,
             tpd: None,
+            max_history_turns: crate::agent::types::DEFAULT_MAX_HISTORY_TURNS,
+            safety_settings: None,
         },
         model_2: None,
         model_3: None,
@@ -72,6 +78,9 @@ async fn test_database_persistence() -> Result<()> {
         cost_usd: 0.0,
         skills: vec!["skill-1".to_string()],
         workflows: vec!["workflow-1".to_string()],
+        skill_allowlist: None,
+        rpm_override: None,
+        tpm_override: None,
     };
 
     // 1. Save
@@ -85,10 +94,27 @@ async fn test_database_persistence() -> Result<()> {
 
     // 3. Update (idempotent upsert)
     agent.name = "Updated Bot".to_string();
+    agent.rpm_override = Some(1);
     save_agent_db(&pool, &agent).await?;
     let updated_agents = load_agents_db(&pool).await?;
     assert_eq!(updated_agents.len(), 1, "Upsert must not duplicate rows");
     assert_eq!(updated_agents[0].name, "Updated Bot");
+    assert_eq!(updated_agents[0].rpm_override, Some(1));
+    assert_eq!(updated_agents[0].tpm_override, None);
+
+    // 4. A routine save after deletion must not bring the agent back
+    soft_delete_agent_db(&pool, &agent.id).await?;
+    agent.cost_usd = 1.5;
+    save_agent_db(&pool, &agent).await?;
+    assert!(load_agents_db(&pool).await?.is_empty(), "save_agent_db resurrected a deleted agent");
+    assert_eq!(load_deleted_agents_db(&pool).await?.len(), 1);
+
+    // 5. Creating an agent under the deleted ID restores the row
+    create_agent_db(&pool, &agent).await?;
+    let restored = load_agents_db(&pool).await?;
+    assert_eq!(restored.len(), 1);
+    assert_eq!(restored[0].cost_usd, 1.5);
+    assert!(load_deleted_agents_db(&pool).await?.is_empty());
 
     Ok(())
 }
@@ -103,17 +129,17 @@ async fn test_mission_logic() -> Result<()> {
     
     sqlx::query("CREATE TABLE agents (id TEXT PRIMARY KEY, name TEXT NOT NULL, role TEXT NOT NULL, department TEXT NOT NULL, description TEXT NOT NULL, model_id TEXT, tokens_used INTEGER DEFAULT 0, status TEXT NOT NULL, theme_color TEXT, budget_usd REAL DEFAULT 0.0, cost_usd REAL DEFAULT 0.0, metadata TEXT NOT NULL, skills TEXT DEFAULT '[]', workflows TEXT DEFAULT '[]', model_2 TEXT, model_3 TEXT, model_config2 TEXT, model_config3 TEXT, active_model_slot INTEGER DEFAULT 1)").execute(&pool).await?;
     sqlx::query("INSERT INTO agents (id, name, role, department, description, status, metadata, skills, workflows) VALUES ('agent-1', 'Test Agent', 'tester', 'qa', 'Test agent for mission logic', 'idle', '{}', '[]', '[]')").execute(&pool).await?;
-    sqlx::query("CREATE TABLE mission_history (id TEXT PRIMARY KEY, agent_id TEXT, title TEXT, status TEXT, budget_usd REAL, cost_usd REAL, created_at DATETIME, updated_at DATETIME)").execute(&pool).await?;
-    sqlx::query("CREATE TABLE swarm_context (id TEXT PRIMARY KEY, mission_id TEXT, agent_id TEXT, topic TEXT, finding TEXT, timestamp DATETIME DEFAULT CURRENT_TIMESTAMP)").execute(&pool).await?;
+    sqlx::query("CREATE TABLE mission_history (id TEXT PRIMARY KEY, agent_id TEXT, title TEXT, status TEXT, budget_usd REAL, cost_usd REAL, created_at DATETIME, updated_at DATETIME, tags TEXT NOT NULL DEFAULT '[]')").execute(&pool).await?;
+    sqlx::query("CREATE TABLE swarm_context (id TEXT PRIMARY KEY, mission_id TEXT, agent_id TEXT, topic TEXT, finding TEXT, timestamp DATETIME DEFAULT CURRENT_TIMESTAMP, summary_through INTEGER, confidence REAL, verdict TEXT, verified_by TEXT, verified_at DATETIME)").execute(&pool).await?;
     sqlx::query("CREATE TABLE IF NOT EXISTS mission_steps (id TEXT PRIMARY KEY, mission_id TEXT, agent_id TEXT, role TEXT, message TEXT, status TEXT, tool_call TEXT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP)").execute(&pool).await?;
 
     // 1. Create Mission
-    let mission = crate::agent::mission::create_mission(&pool, "agent-1", "Test Mission", 10.0).await?;
+    let mission = crate::agent::mission::create_mission(&pool, "agent-1", "Test Mission", 10.0, &[]).await?;
     assert_eq!(mission.title, "Test Mission");
     assert_eq!(mission.cost_usd, 0.0);
 
     // 2. Share Finding
-    crate::agent::mission::share_finding(&pool, &mission.id, "agent-1", "Security", "Found open port").await?;
+    crate::agent::mission::share_finding(&pool, &mission.id, "agent-1", "Security", "Found open port", None).await?;
 
     // 3. Get Context
     let context = crate::agent::mission::get_mission_context(&pool, &mission.id).await?;
@@ -169,6 +195,12 @@ async fn test_swarm_recursion_logic() -> Result<()> {
         swarm_lineage: Some(lineage),
         external_id: None,
         safe_mode: None,
+        timeout_secs: None,
+        depends_on: None,
+        trace_id: None,
+        tags: None,
+        callback_url: None,
+        callback_secret: None,
     };
 
     let json = serde_json::to_string(&payload)?;
@@ -184,7 +216,7 @@ async fn test_swarm_recursion_logic() -> Result<()> {
 
 #[tokio::test]
 async fn test_rate_limiter_unlimited_is_noop() {
-    let limiter = crate::agent::rate_limiter::RateLimiter::new(None, None);
+    let limiter = crate::agent::rate_limiter::RateLimiter::new(None, None, None, None);
     assert!(!limiter.is_active(), "Unlimited limiter should report as inactive");
     // Should return immediately without blocking
     limiter.acquire(9999).await;
@@ -194,8 +226,8 @@ async fn test_rate_limiter_unlimited_is_noop() {
 #[tokio::test]
 async fn test_rate_limiter_active_with_limits() {
     // Construct with both limits set
-    let limiter = crate::agent::rate_limiter::RateLimiter::new(Some(60), Some(100_000));
-    assert!(limiter.is_active(), "Limiter with rpm/tpm should report as active");
+    let limiter = crate::agent::rate_limiter::RateLimiter::new(Some(60), Some(100_000), Some(1_000), Some(1_000_000));
+    assert!(limiter.is_active(), "Limiter with rpm/tpm/rpd/tpd should report as active");
 
     // Acquire should not block on the first call with ample budget
     limiter.acquire(512).await;
@@ -204,19 +236,77 @@ async fn test_rate_limiter_active_with_limits() {
 
 #[tokio::test]
 async fn test_rate_limiter_rpm_only() {
-    let limiter = crate::agent::rate_limiter::RateLimiter::new(Some(30), None);
+    let limiter = crate::agent::rate_limiter::RateLimiter::new(Some(30), None, None, None);
     assert!(limiter.is_active());
     limiter.acquire(0).await;
 }
 
 #[tokio::test]
 async fn test_rate_limiter_tpm_only() {
-    let limiter = crate::agent::rate_limiter::RateLimiter::new(None, Some(50_000));
+    let limiter = crate::agent::rate_limiter::RateLimiter::new(None, Some(50_000), None, None);
     assert!(limiter.is_active());
     limiter.acquire(100).await;
     limiter.record_usage(87);
 }
 
+#[tokio::test]
+async fn test_rate_limiter_rpd_blocks_once_daily_requests_are_spent() {
+    let limiter = crate::agent::rate_limiter::RateLimiter::new(None, None, Some(2), None);
+    assert!(limiter.is_active(), "An RPD-only limiter should report as active");
+
+    limiter.acquire(0).await;
+    limiter.acquire(0).await;
+    let third = tokio::time::timeout(std::time::Duration::from_millis(100), limiter.acquire(0)).await;
+    assert!(third.is_err(), "A third request within the day window must wait for the reset");
+}
+
+#[tokio::test]
+async fn test_rate_limiter_tpd_counts_recorded_usage() {
+    let limiter = crate::agent::rate_limiter::RateLimiter::new(None, None, None, Some(1_000));
+    limiter.acquire(100).await;
+    limiter.record_usage(950);
+
+    let next = tokio::time::timeout(std::time::Duration::from_millis(100), limiter.acquire(100)).await;
+    assert!(next.is_err(), "Recorded usage must count against the daily token budget");
+}
+
+#[tokio::test]
+async fn test_rate_limiter_tpm_allows_no_burst_across_window_boundary() {
+    use std::time::Duration;
+    let window = Duration::from_millis(300);
+    let limiter = crate::agent::rate_limiter::RateLimiter::with_window(None, Some(100), None, None, window);
+
+    // A small request early in the window, then a large one just before it would reset
+    limiter.acquire(10).await;
+    limiter.record_usage(10);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    limiter.acquire(80).await;
+    limiter.record_usage(80);
+
+    // Past the old fixed-window boundary: the 80 tokens are still inside the sliding window
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    let burst = tokio::time::timeout(Duration::from_millis(100), limiter.acquire(80)).await;
+    assert!(burst.is_err(), "A second 80-token burst right after the boundary must wait");
+
+    // Once the 80-token usage slides out, the request is admitted
+    let later = tokio::time::timeout(Duration::from_millis(400), limiter.acquire(80)).await;
+    assert!(later.is_ok(), "The request must proceed once the earlier usage expires");
+}
+
+#[tokio::test]
+async fn test_rate_limiter_rpm_counts_requests_in_sliding_window() {
+    use std::time::Duration;
+    let limiter = crate::agent::rate_limiter::RateLimiter::with_window(Some(2), None, None, None, Duration::from_millis(300));
+
+    limiter.acquire(0).await;
+    limiter.acquire(0).await;
+    let third = tokio::time::timeout(Duration::from_millis(100), limiter.acquire(0)).await;
+    assert!(third.is_err(), "A third request inside the window must wait");
+
+    let after_expiry = tokio::time::timeout(Duration::from_millis(400), limiter.acquire(0)).await;
+    assert!(after_expiry.is_ok(), "A slot frees up once the oldest request leaves the window");
+}
+
 // ─────────────────────────────────────────────────────────
 //  FILESYSTEM ADAPTER TESTS
 // ─────────────────────────────────────────────────────────
@@ -236,14 +326,100 @@ async fn test_filesystem_sandbox_write_read() -> Result<()> {
     assert_eq!(content, "Hello, Tadpole!");
 
     // List should return the file
-    let files = adapter.list_files("").await?;
-    assert!(files.iter().any(|f| f == "hello.txt"), "hello.txt should appear in listing");
+    let files = adapter.list_files("", false).await?;
+    assert!(files.iter().any(|f| f.path == "hello.txt" && f.is_file), "hello.txt should appear in listing");
+
+    // A missing file is reported as NotFound, not a generic failure
+    let missing = adapter.read_file("missing.txt").await;
+    assert!(matches!(missing, Err(crate::agent::types::ToolError::NotFound(_))), "got: {:?}", missing);
 
     // Cleanup
     let _ = tokio::fs::remove_dir_all(&tmp).await;
     Ok(())
 }
 
+#[tokio::test]
+async fn test_filesystem_stat_file_reports_metadata() -> Result<()> {
+    use crate::adapter::filesystem::FilesystemAdapter;
+
+    let tmp = std::env::temp_dir().join(format!("tadpole_test_{}", uuid::Uuid::new_v4()));
+    let adapter = FilesystemAdapter::new(tmp.clone());
+    adapter.write_file("docs/notes.txt", "12345").await?;
+
+    let file = adapter.stat_file("docs/notes.txt").await?;
+    assert_eq!(file.path, "docs/notes.txt");
+    assert_eq!(file.size_bytes, 5);
+    assert!(file.is_file && !file.is_dir);
+    assert!(file.modified_at <= chrono::Utc::now());
+
+    let dir = adapter.stat_file("docs").await?;
+    assert!(dir.is_dir && !dir.is_file);
+    assert_eq!(dir.name(), "docs/");
+
+    let missing = adapter.stat_file("docs/absent.txt").await;
+    match missing {
+        Err(crate::agent::types::ToolError::NotFound(msg)) => assert!(msg.contains("docs/absent.txt"), "got: {}", msg),
+        other => panic!("expected NotFound, got: {:?}", other),
+    }
+
+    let _ = tokio::fs::remove_dir_all(&tmp).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filesystem_copy_file_stays_in_sandbox() -> Result<()> {
+    use crate::adapter::filesystem::FilesystemAdapter;
+
+    let tmp = std::env::temp_dir().join(format!("tadpole_test_{}", uuid::Uuid::new_v4()));
+    let adapter = FilesystemAdapter::new(tmp.clone());
+    adapter.write_file("report.md", "draft v1").await?;
+
+    // Destination directories are created on demand
+    adapter.copy_file("report.md", "backups/report.md.bak").await?;
+    assert_eq!(adapter.read_file("backups/report.md.bak").await?, "draft v1");
+    assert_eq!(adapter.read_file("report.md").await?, "draft v1");
+
+    let escaped = adapter.copy_file("report.md", "../report.md").await;
+    assert!(matches!(escaped, Err(crate::agent::types::ToolError::SecurityViolation(_))), "got: {:?}", escaped);
+    let from_outside = adapter.copy_file("../../etc/passwd", "passwd").await;
+    assert!(matches!(from_outside, Err(crate::agent::types::ToolError::SecurityViolation(_))), "got: {:?}", from_outside);
+    assert!(!tmp.join("passwd").exists());
+
+    let _ = tokio::fs::remove_dir_all(&tmp).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filesystem_enforces_workspace_size_limit() -> Result<()> {
+    use crate::adapter::filesystem::FilesystemAdapter;
+
+    let tmp = std::env::temp_dir().join(format!("tadpole_test_{}", uuid::Uuid::new_v4()));
+    let adapter = FilesystemAdapter::with_max_workspace_bytes(tmp.clone(), 100);
+
+    // Filling the workspace exactly to the limit is allowed
+    adapter.write_file("a.txt", &"a".repeat(60)).await?;
+    adapter.append_to_file("a.txt", &"b".repeat(40)).await?;
+    assert_eq!(adapter.total_workspace_size().await?, 100);
+
+    // One more byte is not, whether appended or written to a new file
+    let over = adapter.append_to_file("a.txt", "c").await;
+    assert!(matches!(over, Err(crate::agent::types::ToolError::SecurityViolation(_))), "got: {:?}", over);
+    let over = adapter.write_file("b.txt", "c").await;
+    match over {
+        Err(crate::agent::types::ToolError::SecurityViolation(msg)) => assert!(msg.contains("100 byte limit"), "got: {}", msg),
+        other => panic!("expected SecurityViolation, got: {:?}", other),
+    }
+    assert!(!tmp.join("b.txt").exists());
+
+    // Overwriting only counts the difference, and deleting frees space immediately
+    adapter.write_file("a.txt", &"d".repeat(90)).await?;
+    adapter.delete_file("a.txt").await?;
+    adapter.write_file("b.txt", &"e".repeat(100)).await?;
+
+    let _ = tokio::fs::remove_dir_all(&tmp).await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_filesystem_sandbox_blocks_traversal() {
     use crate::adapter::filesystem::FilesystemAdapter;
@@ -255,6 +431,7 @@ async fn test_filesystem_sandbox_blocks_traversal() {
     // Attempt path traversal — must be rejected
     let result = adapter.read_file("../etc/passwd").await;
     assert!(result.is_err(), "Path traversal attempt must be blocked");
+    assert!(matches!(result, Err(crate::agent::types::ToolError::SecurityViolation(_))), "got: {:?}", result);
 
     let err_msg = result.unwrap_err().to_string();
     assert!(
@@ -278,3 +455,88 @@ async fn test_filesystem_sandbox_blocks_absolute_paths() {
     // Either an error (best) or a write inside sandbox (safe) — never a panic
     let _ = result; // just ensure no panic
 }
+
+// ─────────────────────────────────────────────────────────
+//  VAULT ADAPTER TESTS
+// ─────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_vault_archives_into_mission_subdirectories() -> Result<()> {
+    use crate::adapter::vault::VaultAdapter;
+
+    let tmp = std::env::temp_dir().join(format!("tadpole_vault_{}", uuid::Uuid::new_v4()));
+    let adapter = VaultAdapter::new(tmp.clone());
+
+    adapter.append_to_file("report.md", "Finding A", Some("mission-a".to_string())).await?;
+    adapter.append_to_file("report.md", "Finding B", Some("mission-b".to_string())).await?;
+    adapter.append_to_file("notes.md", "Finding B2", Some("mission-b".to_string())).await?;
+
+    let subdirs = adapter.list_subdirectories().await?;
+    assert_eq!(subdirs.len(), 2);
+    assert_eq!(subdirs[0].subdirectory, "mission-a");
+    assert_eq!(subdirs[0].file_count, 1);
+    assert_eq!(subdirs[1].subdirectory, "mission-b");
+    assert_eq!(subdirs[1].file_count, 2);
+    assert!(subdirs[1].total_size_bytes > 0);
+
+    // Subdirectories may not escape the vault
+    assert!(adapter.append_to_file("x.md", "nope", Some("../escape".to_string())).await.is_err());
+
+    let _ = tokio::fs::remove_dir_all(&tmp).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filesystem_recursive_listing_is_relative_and_depth_limited() -> Result<()> {
+    use crate::adapter::filesystem::{FilesystemAdapter, MAX_LIST_DEPTH};
+
+    let tmp = std::env::temp_dir().join(format!("tadpole_test_{}", uuid::Uuid::new_v4()));
+    let adapter = FilesystemAdapter::new(tmp.clone());
+    adapter.write_file("src/main.rs", "fn main() {}").await?;
+    adapter.write_file("tests/it.rs", "").await?;
+    // One file at every level from 1 to MAX_LIST_DEPTH + 2
+    let mut nested = String::from("deep");
+    for level in 1..=MAX_LIST_DEPTH + 2 {
+        adapter.write_file(&format!("{}/level{}.txt", nested, level), "x").await?;
+        nested.push_str("/d");
+    }
+
+    let flat: Vec<String> = adapter.list_files(".", false).await?.into_iter().map(|f| f.path).collect();
+    assert_eq!(flat, vec!["deep", "src", "tests"]);
+
+    let tree = adapter.list_files(".", true).await?;
+    let paths: Vec<&str> = tree.iter().map(|f| f.path.as_str()).collect();
+    assert!(paths.contains(&"src/main.rs") && paths.contains(&"tests/it.rs"), "got: {:?}", paths);
+    assert!(tree.iter().any(|f| f.path == "src" && f.is_dir));
+    assert!(paths.iter().all(|p| !p.starts_with('/') && !p.contains('\\') && !p.starts_with("..")), "paths must be workspace-relative: {:?}", paths);
+
+    // Levels below MAX_LIST_DEPTH are not entered
+    let deepest = paths.iter().map(|p| p.split('/').count()).max().unwrap();
+    assert_eq!(deepest, MAX_LIST_DEPTH);
+    assert!(paths.contains(&"deep/d/d/d/level4.txt"));
+    assert!(!paths.iter().any(|p| p.ends_with("level5.txt")), "got: {:?}", paths);
+
+    // Listing a subdirectory still reports paths from the workspace root
+    let sub: Vec<String> = adapter.list_files("src", true).await?.into_iter().map(|f| f.path).collect();
+    assert_eq!(sub, vec!["src/main.rs"]);
+
+    let _ = tokio::fs::remove_dir_all(&tmp).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filesystem_recursive_listing_is_capped() -> Result<()> {
+    use crate::adapter::filesystem::{FilesystemAdapter, MAX_LIST_ENTRIES};
+
+    let tmp = std::env::temp_dir().join(format!("tadpole_test_{}", uuid::Uuid::new_v4()));
+    for i in 0..MAX_LIST_ENTRIES + 20 {
+        let dir = tmp.join(format!("d{:02}", i % 20));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(format!("f{}.txt", i)), "")?;
+    }
+    let adapter = FilesystemAdapter::new(tmp.clone());
+    assert_eq!(adapter.list_files(".", true).await?.len(), MAX_LIST_ENTRIES);
+
+    let _ = tokio::fs::remove_dir_all(&tmp).await;
+    Ok(())
+}
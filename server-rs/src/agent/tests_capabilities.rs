@@ -1,17 +1,12 @@
 // use std::path::PathBuf;
 // use std::env;
 use uuid::Uuid;
-use super::capabilities::{CapabilitiesRegistry, SkillDefinition, WorkflowDefinition};
+use super::capabilities::{validate_skill_schema, CapabilitiesRegistry, SkillDefinition, WorkflowDefinition};
 
-#[tokio::test]
-async fn test_capabilities_registry_save_and_sanitize() -> anyhow::Result<()> {
-    let registry = CapabilitiesRegistry::new().await?;
-    
-    // Create a mock skill with problematic characters in the name
-    let weird_name = format!("Bad Skill! *Name_{}", Uuid::new_v4());
-    let skill = SkillDefinition {
+fn test_skill(name: &str) -> SkillDefinition {
+    SkillDefinition {
         id: None,
-        name: weird_name.clone(),
+        name: name.to_string(),
         description: "Test skill".to_string(),
         execution_command: "echo test".to_string(),
         schema: serde_json::json!({
@@ -20,35 +15,90 @@ async fn test_capabilities_registry_save_and_sanitize() -> anyhow::Result<()> {
         }),
         doc_url: None,
         tags: None,
-    };
+        enabled: true,
+        skill_cpu_limit_secs: None,
+        skill_memory_limit_mb: None,
+    }
+}
+
+#[tokio::test]
+async fn test_capabilities_registry_save_and_reload() -> anyhow::Result<()> {
+    let registry = CapabilitiesRegistry::new(None).await?;
 
-    // Save should sanitize the file name but preserve the internal name
-    registry.save_skill(skill.clone()).await?;
+    // Names that would need sanitizing into a filename are rejected outright
+    let weird_name = format!("Bad Skill! *Name_{}", Uuid::new_v4());
+    assert!(registry.save_skill(test_skill(&weird_name)).await.is_err());
+    assert!(registry.skill(&weird_name).is_none(), "Rejected skill must not reach memory");
 
-    // Verify it is in the in-memory map
-    assert!(registry.skills.contains_key(&weird_name), "Skill must be in memory with exact name");
+    let name = format!("test_skill_{}", Uuid::new_v4().simple());
+    registry.save_skill(test_skill(&name)).await?;
+    assert!(registry.skill(&name).is_some(), "Skill must be in memory with exact name");
 
-    let sanitized_filename = weird_name.replace(|c: char| !c.is_alphanumeric() && c != '_' && c != '-', "_");
-    
-    // Check if the file was created
-    // We don't have direct access to registry.skills_dir, but we can attempt to load it
-    // by reloading the registry and ensuring our weird name still parses
-    let new_registry = CapabilitiesRegistry::new().await?;
-    assert!(new_registry.skills.contains_key(&weird_name), "Skill must persist and load properly");
+    let new_registry = CapabilitiesRegistry::new(None).await?;
+    assert!(new_registry.skill(&name).is_some(), "Skill must persist and load properly");
 
     // Clean up
-    registry.delete_skill(&weird_name).await?;
-    assert!(!registry.skills.contains_key(&weird_name), "Skill must be removed from memory");
+    registry.delete_skill(&name).await?;
+    assert!(registry.skill(&name).is_none(), "Skill must be removed from memory");
     
-    let cleanup_registry = CapabilitiesRegistry::new().await?;
-    assert!(!cleanup_registry.skills.contains_key(&weird_name), "Skill must be removed from disk");
+    let cleanup_registry = CapabilitiesRegistry::new(None).await?;
+    assert!(cleanup_registry.skill(&name).is_none(), "Skill must be removed from disk");
 
     Ok(())
 }
 
+#[test]
+fn test_validate_skill_schema_rejects_malformed_definitions() {
+    assert!(validate_skill_schema(&test_skill("fetch_url-v2")).is_ok());
+
+    let mut not_object = test_skill("not_object");
+    not_object.schema = serde_json::json!(["type", "object"]);
+    let mut wrong_type = test_skill("wrong_type");
+    wrong_type.schema = serde_json::json!({ "type": "string", "properties": {} });
+    let mut no_properties = test_skill("no_properties");
+    no_properties.schema = serde_json::json!({ "type": "object" });
+    let mut no_command = test_skill("no_command");
+    no_command.execution_command = "   ".to_string();
+
+    for (skill, expected) in [
+        (not_object, "must be a JSON object"),
+        (wrong_type, "schema.type"),
+        (no_properties, "properties key"),
+        (no_command, "execution_command"),
+        (test_skill("Upper"), "must match"),
+        (test_skill("9lives"), "must match"),
+    ] {
+        let err = validate_skill_schema(&skill).expect_err(&skill.name).to_string();
+        assert!(err.contains(expected), "{}: {}", skill.name, err);
+    }
+
+    let mut everything_wrong = test_skill("");
+    everything_wrong.execution_command.clear();
+    everything_wrong.schema = serde_json::Value::Null;
+    assert_eq!(validate_skill_schema(&everything_wrong).unwrap_err().to_string().matches("; ").count(), 2);
+}
+
+#[tokio::test]
+async fn test_reload_skips_invalid_skill_files() -> anyhow::Result<()> {
+    let data_dir = std::env::temp_dir().join(format!("tadpole-capabilities-{}", Uuid::new_v4()));
+    let skills_dir = data_dir.join("skills");
+    tokio::fs::create_dir_all(&skills_dir).await?;
+    tokio::fs::write(skills_dir.join("good.json"), serde_json::to_string(&test_skill("good"))?).await?;
+    let mut bad = test_skill("bad");
+    bad.schema = serde_json::json!({ "type": "object" });
+    tokio::fs::write(skills_dir.join("bad.json"), serde_json::to_string(&bad)?).await?;
+
+    let registry = CapabilitiesRegistry::with_data_dir(data_dir.clone(), None).await?;
+    assert!(registry.skill("good").is_some());
+    assert!(registry.skill("bad").is_none(), "Invalid skill file must be skipped");
+
+    tokio::fs::remove_dir_all(&data_dir).await?;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_workflows_registry_save_and_delete() -> anyhow::Result<()> {
-    let registry = CapabilitiesRegistry::new().await?;
+    let registry = CapabilitiesRegistry::new(None).await?;
     
     let workflow_name = format!("test_workflow_{}", Uuid::new_v4());
     let workflow = WorkflowDefinition {
@@ -60,14 +110,110 @@ async fn test_workflows_registry_save_and_delete() -> anyhow::Result<()> {
     };
 
     registry.save_workflow(workflow.clone()).await?;
-    assert!(registry.workflows.contains_key(&workflow_name));
+    assert!(registry.workflow(&workflow_name).is_some());
 
-    let loaded_registry = CapabilitiesRegistry::new().await?;
-    assert!(loaded_registry.workflows.contains_key(&workflow_name));
-    assert_eq!(loaded_registry.workflows.get(&workflow_name).unwrap().content, "## Test Workflow\nSteps...");
+    let loaded_registry = CapabilitiesRegistry::new(None).await?;
+    assert!(loaded_registry.workflow(&workflow_name).is_some());
+    assert_eq!(loaded_registry.workflow(&workflow_name).unwrap().content, "## Test Workflow\nSteps...");
 
     registry.delete_workflow(&workflow_name).await?;
-    assert!(!registry.workflows.contains_key(&workflow_name));
+    assert!(registry.workflow(&workflow_name).is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reload_diff_reports_added_removed_and_modified_skills() -> anyhow::Result<()> {
+    let data_dir = std::env::temp_dir().join(format!("tadpole-capabilities-{}", Uuid::new_v4()));
+    let skills_dir = data_dir.join("skills");
+    tokio::fs::create_dir_all(&skills_dir).await?;
+
+    let skill_json = |name: &str, command: &str| serde_json::json!({
+        "name": name,
+        "description": "Test skill",
+        "execution_command": command,
+        "schema": { "type": "object", "properties": {} }
+    }).to_string();
+    tokio::fs::write(skills_dir.join("kept.json"), skill_json("kept", "echo kept")).await?;
+    tokio::fs::write(skills_dir.join("doomed.json"), skill_json("doomed", "echo doomed")).await?;
+    tokio::fs::write(skills_dir.join("tweaked.json"), skill_json("tweaked", "echo v1")).await?;
+
+    let (tx, mut rx) = tokio::sync::broadcast::channel(16);
+    let registry = CapabilitiesRegistry::with_data_dir(data_dir.clone(), Some(tx)).await?;
+    assert_eq!(registry.last_diff.lock().unwrap().added.len(), 3, "Initial load adds everything");
+    rx.recv().await?;
+    rx.recv().await?;
+
+    tokio::fs::write(skills_dir.join("fresh.json"), skill_json("fresh", "echo fresh")).await?;
+    tokio::fs::remove_file(skills_dir.join("doomed.json")).await?;
+    tokio::fs::write(skills_dir.join("tweaked.json"), skill_json("tweaked", "echo v2")).await?;
+    registry.reload_all().await?;
+
+    let diff = registry.last_diff.lock().unwrap().clone();
+    let names = |changes: &[super::capabilities::CapabilityChange]| changes.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&diff.added), vec!["fresh"]);
+    assert_eq!(names(&diff.removed), vec!["doomed"]);
+    assert_eq!(names(&diff.modified), vec!["tweaked"]);
+    assert!(registry.previous.read().unwrap().skills.contains_key("doomed"));
+
+    let event = rx.recv().await?;
+    assert_eq!(event["type"], "capabilities:diff");
+    assert_eq!(event["diff"]["modified"][0]["name"], "tweaked");
+    let event = rx.recv().await?;
+    assert_eq!(event["type"], "capabilities:reloaded");
+    assert_eq!(event["skillsLoaded"], 3);
+
+    tokio::fs::remove_dir_all(&data_dir).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watcher_reloads_when_skill_files_change() -> anyhow::Result<()> {
+    let data_dir = std::env::temp_dir().join(format!("tadpole-capabilities-{}", Uuid::new_v4()));
+    let registry = std::sync::Arc::new(CapabilitiesRegistry::with_data_dir(data_dir.clone(), None).await?);
+    registry.spawn_watcher(std::time::Duration::from_millis(50));
+
+    let skill = serde_json::json!({
+        "name": "hot_loaded",
+        "description": "Dropped in while running",
+        "execution_command": "echo hot",
+        "schema": { "type": "object", "properties": {} }
+    });
+    tokio::fs::write(data_dir.join("skills").join("hot_loaded.json"), skill.to_string()).await?;
+
+    let mut loaded = false;
+    for _ in 0..40 {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        if registry.skill("hot_loaded").is_some() {
+            loaded = true;
+            break;
+        }
+    }
+    assert!(loaded, "Watcher must pick up a new skill file without an explicit reload");
+
+    tokio::fs::remove_dir_all(&data_dir).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_skill_metrics_reset_on_reload_and_delete() -> anyhow::Result<()> {
+    let data_dir = std::env::temp_dir().join(format!("tadpole-capabilities-{}", Uuid::new_v4()));
+    let mut registry = CapabilitiesRegistry::with_data_dir(data_dir.clone(), None).await?;
+    registry.save_skill(test_skill("probe")).await?;
+    let run = std::time::Duration::from_millis(10);
+
+    registry.record_skill_run("probe", run, false);
+    registry.reload_all().await?;
+    assert_eq!(registry.skill_metrics("probe").invocations, 0, "a reload resets metrics by default");
+
+    registry.preserve_metrics_on_reload = true;
+    registry.record_skill_run("probe", run, false);
+    registry.reload_all().await?;
+    assert_eq!(registry.skill_metrics("probe").invocations, 1, "metrics survive a reload when preserved");
+
+    registry.delete_skill("probe").await?;
+    assert!(!registry.metrics.contains_key("probe"), "deleting a skill drops its metrics");
 
+    tokio::fs::remove_dir_all(&data_dir).await?;
     Ok(())
 }
@@ -0,0 +1,257 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+/// How often `spawn_token_stats_job` rolls mission logs up into `token_stats`.
+pub const TOKEN_STATS_INTERVAL_SECS: u64 = 3600;
+/// Windows returned by `GET /agents/:id/token-stats` when `limit` is not given.
+pub const DEFAULT_TOKEN_STATS_LIMIT: i64 = 24;
+/// Largest `limit` accepted by `GET /agents/:id/token-stats`.
+pub const MAX_TOKEN_STATS_LIMIT: i64 = 1000;
+
+/// Granularity of a `token_stats` row.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenWindow {
+    #[default]
+    Hour,
+    Day,
+    /// Weeks start on Monday, 00:00 UTC.
+    Week,
+}
+
+impl TokenWindow {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TokenWindow::Hour => "hour",
+            TokenWindow::Day => "day",
+            TokenWindow::Week => "week",
+        }
+    }
+
+    pub fn length(self) -> Duration {
+        match self {
+            TokenWindow::Hour => Duration::hours(1),
+            TokenWindow::Day => Duration::days(1),
+            TokenWindow::Week => Duration::weeks(1),
+        }
+    }
+
+    /// Start of the window of this kind that contains `at`.
+    pub fn start_of(self, at: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            TokenWindow::Hour => at.duration_trunc(Duration::hours(1)).unwrap_or(at),
+            TokenWindow::Day => at.duration_trunc(Duration::days(1)).unwrap_or(at),
+            TokenWindow::Week => {
+                let day = TokenWindow::Day.start_of(at);
+                day - Duration::days(day.weekday().num_days_from_monday() as i64)
+            }
+        }
+    }
+}
+
+/// Token usage and cost of one agent over one window.
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct TokenStat {
+    pub window_start: DateTime<Utc>,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_usd: f64,
+}
+
+/// Sums the usage recorded on mission logs during the hour containing `at` into per-agent
+/// `hour` rows, then rebuilds the `day` and `week` rows containing that hour from the hour rows.
+/// Running it again for the same hour replaces its rows, so partial hours can be refreshed.
+pub async fn aggregate_hour(pool: &SqlitePool, at: DateTime<Utc>) -> Result<()> {
+    let hour_start = TokenWindow::Hour.start_of(at);
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM token_stats WHERE window_type = 'hour' AND window_start = ?1")
+        .bind(hour_start)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO token_stats (agent_id, window_type, window_start, input_tokens, output_tokens, cost_usd)
+         SELECT agent_id, 'hour', ?1,
+                COALESCE(SUM(json_extract(metadata, '$.usage.inputTokens')), 0),
+                COALESCE(SUM(json_extract(metadata, '$.usage.outputTokens')), 0),
+                COALESCE(SUM(json_extract(metadata, '$.costUsd')), 0.0)
+         FROM mission_logs
+         WHERE json_valid(metadata) AND json_extract(metadata, '$.usage') IS NOT NULL
+           AND datetime(timestamp) >= datetime(?1) AND datetime(timestamp) < datetime(?2)
+         GROUP BY agent_id")
+    .bind(hour_start)
+    .bind(hour_start + TokenWindow::Hour.length())
+    .execute(&mut *tx)
+    .await?;
+
+    for window in [TokenWindow::Day, TokenWindow::Week] {
+        let start = window.start_of(hour_start);
+        sqlx::query("DELETE FROM token_stats WHERE window_type = ?1 AND window_start = ?2")
+            .bind(window.as_str())
+            .bind(start)
+            .execute(&mut *tx)
+            .await?;
+        // Hour rows share one timestamp format, so they compare correctly as text
+        sqlx::query(
+            "INSERT INTO token_stats (agent_id, window_type, window_start, input_tokens, output_tokens, cost_usd)
+             SELECT agent_id, ?1, ?2, SUM(input_tokens), SUM(output_tokens), SUM(cost_usd)
+             FROM token_stats
+             WHERE window_type = 'hour' AND window_start >= ?2 AND window_start < ?3
+             GROUP BY agent_id")
+        .bind(window.as_str())
+        .bind(start)
+        .bind(start + window.length())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The agent's latest `limit` windows of the given kind, oldest first.
+pub async fn list_token_stats(pool: &SqlitePool, agent_id: &str, window: TokenWindow, limit: i64) -> Result<Vec<TokenStat>> {
+    let mut stats: Vec<TokenStat> = sqlx::query_as(
+        "SELECT window_start, input_tokens, output_tokens, cost_usd FROM token_stats
+         WHERE agent_id = ?1 AND window_type = ?2 ORDER BY window_start DESC LIMIT ?3")
+    .bind(agent_id)
+    .bind(window.as_str())
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    stats.reverse();
+    Ok(stats)
+}
+
+/// Projected spend per day from the agent's hourly stats over the 24 hours before `now`.
+pub async fn burn_rate_usd_per_day(pool: &SqlitePool, agent_id: &str, now: DateTime<Utc>) -> Result<f64> {
+    let since = now - Duration::hours(24);
+    let hourly: Vec<TokenStat> = sqlx::query_as(
+        "SELECT window_start, input_tokens, output_tokens, cost_usd FROM token_stats
+         WHERE agent_id = ?1 AND window_type = 'hour' AND window_start >= ?2")
+    .bind(agent_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    Ok(projected_daily_burn(&hourly, now))
+}
+
+/// Scales the cost of the trailing 24 hours of `hourly` stats to a full day. The cost is spread
+/// over the time since the earliest of those hours (at least one hour), so an agent that started
+/// spending recently is not diluted by the hours before it was active.
+pub fn projected_daily_burn(hourly: &[TokenStat], now: DateTime<Utc>) -> f64 {
+    let since = now - Duration::hours(24);
+    let recent: Vec<&TokenStat> = hourly.iter().filter(|s| s.window_start >= since && s.window_start <= now).collect();
+    let Some(earliest) = recent.iter().map(|s| s.window_start).min() else {
+        return 0.0;
+    };
+    let total: f64 = recent.iter().map(|s| s.cost_usd).sum();
+    let span_hours = ((now - earliest).num_seconds() as f64 / 3600.0).clamp(1.0, 24.0);
+    total / span_hours * 24.0
+}
+
+/// Refreshes the previous and the current hour every `interval`, so each hour is complete
+/// once the following run has seen it whatever the job's phase.
+pub fn spawn_token_stats_job(pool: SqlitePool, interval: std::time::Duration) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let now = Utc::now();
+            for at in [now - TokenWindow::Hour.length(), now] {
+                if let Err(e) = aggregate_hour(&pool, at).await {
+                    tracing::warn!("⚠️ [TokenStats] Aggregation for {} failed: {}", TokenWindow::Hour.start_of(at), e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn memory_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::db::run_migrations(&pool, crate::db::MIGRATIONS).await.unwrap();
+        // Logs are written without the agents and missions they would belong to
+        sqlx::query("PRAGMA foreign_keys = OFF").execute(&pool).await.unwrap();
+        pool
+    }
+
+    /// Writes a final mission step the way the runner does, at a chosen time.
+    async fn log_usage(pool: &SqlitePool, agent_id: &str, at: DateTime<Utc>, input: u32, output: u32, cost: f64) {
+        let metadata = serde_json::json!({ "usage": { "inputTokens": input, "outputTokens": output, "totalTokens": input + output }, "costUsd": cost });
+        sqlx::query("INSERT INTO mission_logs (id, mission_id, agent_id, source, text, severity, timestamp, metadata) VALUES (?1, 'm', ?2, 'Agent', 'done', 'success', ?3, ?4)")
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(agent_id)
+            .bind(at)
+            .bind(metadata.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    fn utc(d: u32, h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, d, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn windows_start_on_the_hour_day_and_monday() {
+        let at = utc(15, 13, 45); // a Thursday
+        assert_eq!(TokenWindow::Hour.start_of(at), utc(15, 13, 0));
+        assert_eq!(TokenWindow::Day.start_of(at), utc(15, 0, 0));
+        assert_eq!(TokenWindow::Week.start_of(at), utc(12, 0, 0));
+    }
+
+    #[tokio::test]
+    async fn hourly_usage_is_aggregated_and_rolled_up() {
+        let pool = memory_pool().await;
+        log_usage(&pool, "a", utc(15, 13, 5), 100, 20, 0.01).await;
+        log_usage(&pool, "a", utc(15, 13, 59), 50, 10, 0.02).await;
+        log_usage(&pool, "a", utc(15, 14, 0), 7, 3, 0.5).await;
+        log_usage(&pool, "b", utc(15, 13, 30), 1, 1, 0.0).await;
+        // Steps without usage (e.g. user messages) are ignored
+        sqlx::query("INSERT INTO mission_logs (id, mission_id, agent_id, source, text, severity, metadata) VALUES ('x', 'm', 'a', 'User', 'hi', 'info', NULL)")
+            .execute(&pool).await.unwrap();
+
+        aggregate_hour(&pool, utc(15, 13, 30)).await.unwrap();
+        let hours = list_token_stats(&pool, "a", TokenWindow::Hour, 24).await.unwrap();
+        assert_eq!(hours.len(), 1);
+        assert_eq!(hours[0].window_start, utc(15, 13, 0));
+        assert_eq!((hours[0].input_tokens, hours[0].output_tokens), (150, 30));
+        assert!((hours[0].cost_usd - 0.03).abs() < 1e-9);
+
+        // Re-running an hour replaces it rather than double counting
+        aggregate_hour(&pool, utc(15, 13, 0)).await.unwrap();
+        aggregate_hour(&pool, utc(15, 14, 10)).await.unwrap();
+        let hours = list_token_stats(&pool, "a", TokenWindow::Hour, 24).await.unwrap();
+        assert_eq!(hours.iter().map(|s| s.input_tokens).collect::<Vec<_>>(), vec![150, 7], "oldest first");
+        assert_eq!(list_token_stats(&pool, "a", TokenWindow::Hour, 1).await.unwrap()[0].window_start, utc(15, 14, 0));
+
+        let days = list_token_stats(&pool, "a", TokenWindow::Day, 24).await.unwrap();
+        assert_eq!((days[0].window_start, days[0].input_tokens, days[0].output_tokens), (utc(15, 0, 0), 157, 33));
+        let weeks = list_token_stats(&pool, "a", TokenWindow::Week, 24).await.unwrap();
+        assert_eq!((weeks[0].window_start, weeks[0].input_tokens), (utc(12, 0, 0), 157));
+        assert_eq!(list_token_stats(&pool, "b", TokenWindow::Day, 24).await.unwrap()[0].input_tokens, 1);
+    }
+
+    #[test]
+    fn burn_rate_projects_recent_spend_to_a_day() {
+        let stat = |at: DateTime<Utc>, cost_usd: f64| TokenStat { window_start: at, input_tokens: 0, output_tokens: 0, cost_usd };
+        let now = utc(15, 12, 0);
+
+        assert_eq!(projected_daily_burn(&[], now), 0.0);
+        // $1 spread over the 4 hours since the agent started spending: $0.25/h
+        let recent = [stat(utc(15, 8, 0), 0.5), stat(utc(15, 11, 0), 0.5)];
+        assert!((projected_daily_burn(&recent, now) - 6.0).abs() < 1e-9);
+        // Less than an hour of data counts as one hour
+        assert!((projected_daily_burn(&[stat(utc(15, 12, 0), 0.1)], now) - 2.4).abs() < 1e-9);
+        // Hours older than the trailing 24 are ignored; a full day projects as-is
+        let day = [stat(utc(14, 1, 0), 100.0), stat(utc(14, 12, 0), 3.0), stat(utc(15, 11, 0), 1.0)];
+        assert!((projected_daily_burn(&day, now) - 4.0).abs() < 1e-9);
+    }
+}
@@ -1,4 +1,14 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// For `Option<Option<T>>` update fields: an absent field stays `None` (via `#[serde(default)]`),
+/// an explicit `null` becomes `Some(None)`.
+fn explicit_null<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TokenUsage {
@@ -32,6 +42,57 @@ pub struct ModelConfig {
     pub rpd: Option<u32>,
     pub tpm: Option<u32>,
     pub tpd: Option<u32>,
+    /// Cap on prior conversation turns sent with each request.
+    #[serde(rename = "maxHistoryTurns", default = "default_max_history_turns")]
+    pub max_history_turns: usize,
+    /// Overrides Gemini's default safety filters; other providers ignore it.
+    #[serde(rename = "safetySettings", default, skip_serializing_if = "Option::is_none")]
+    pub safety_settings: Option<Vec<GeminiSafetySetting>>,
+}
+
+pub const DEFAULT_MAX_HISTORY_TURNS: usize = 10;
+
+fn default_max_history_turns() -> usize {
+    DEFAULT_MAX_HISTORY_TURNS
+}
+
+impl ModelConfig {
+    /// The most recent `max_history_turns` entries of `history`, oldest first.
+    pub fn history_window<'a>(&self, history: Option<&'a [ConversationTurn]>) -> &'a [ConversationTurn] {
+        let history = history.unwrap_or_default();
+        &history[history.len().saturating_sub(self.max_history_turns)..]
+    }
+}
+
+/// One prior exchange in a mission, replayed to the provider as context.
+/// `role` is `"user"` or `"assistant"`; providers map it to their own vocabulary.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConversationTurn {
+    pub role: String,
+    pub content: String,
+}
+
+/// One entry of Gemini's `safetySettings`, e.g. `HARM_CATEGORY_HARASSMENT` at `BLOCK_ONLY_HIGH`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GeminiSafetySetting {
+    pub category: String,
+    pub threshold: String,
+}
+
+impl GeminiSafetySetting {
+    /// Disables blocking for all four harm categories.
+    #[allow(dead_code)]
+    pub fn permissive() -> Vec<Self> {
+        [
+            "HARM_CATEGORY_HARASSMENT",
+            "HARM_CATEGORY_HATE_SPEECH",
+            "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+            "HARM_CATEGORY_DANGEROUS_CONTENT",
+        ]
+        .into_iter()
+        .map(|category| Self { category: category.to_string(), threshold: "BLOCK_NONE".to_string() })
+        .collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +111,72 @@ pub struct ProviderConfig {
     pub custom_headers: Option<std::collections::HashMap<String, String>>,
     #[serde(rename = "audioModel")]
     pub audio_model: Option<String>,
+    /// HTTP timeout for calls to this provider. Overrides the `PROVIDER_TIMEOUT_*` setting;
+    /// unset falls back to it, then to the shared client's 90 seconds.
+    #[serde(rename = "timeoutSecs", default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Public projection of `ProviderConfig` safe to return to API clients.
+/// The raw API key is never exposed — only whether one is configured and its last 4 characters.
+/// Custom header values are masked the same way.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderConfigPublic {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    #[serde(rename = "apiKey")]
+    pub api_key: Option<bool>,
+    #[serde(rename = "maskedApiKey")]
+    pub masked_api_key: Option<String>,
+    #[serde(rename = "baseUrl")]
+    pub base_url: Option<String>,
+    pub protocol: String,
+    #[serde(rename = "externalId")]
+    pub external_id: Option<String>,
+    #[serde(rename = "customHeaders")]
+    pub custom_headers: Option<std::collections::HashMap<String, String>>,
+    #[serde(rename = "audioModel")]
+    pub audio_model: Option<String>,
+    #[serde(rename = "timeoutSecs")]
+    pub timeout_secs: Option<u64>,
+}
+
+const MASKED_SECRET_PREFIX: &str = "...****";
+
+/// Hides a secret behind `...****`, keeping the last four characters of longer values.
+pub fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 4 {
+        MASKED_SECRET_PREFIX.to_string()
+    } else {
+        let tail: String = chars[chars.len() - 4..].iter().collect();
+        format!("{}{}", MASKED_SECRET_PREFIX, tail)
+    }
+}
+
+impl From<&ProviderConfig> for ProviderConfigPublic {
+    fn from(p: &ProviderConfig) -> Self {
+        let key = p.api_key.as_deref().filter(|k| !k.is_empty());
+        let masked_api_key = key.map(mask_secret);
+
+        Self {
+            id: p.id.clone(),
+            name: p.name.clone(),
+            icon: p.icon.clone(),
+            api_key: Some(key.is_some()),
+            masked_api_key,
+            base_url: p.base_url.clone(),
+            protocol: p.protocol.clone(),
+            external_id: p.external_id.clone(),
+            // Header values are usually credentials too (e.g. `Authorization`), so only names are shown in full
+            custom_headers: p.custom_headers.as_ref().map(|headers| {
+                headers.iter().map(|(name, value)| (name.clone(), mask_secret(value))).collect()
+            }),
+            audio_model: p.audio_model.clone(),
+            timeout_secs: p.timeout_secs,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,6 +190,11 @@ pub struct ModelEntry {
     pub rpd: Option<u32>,
     pub tpd: Option<u32>,
     pub modality: Option<String>,
+    /// Total tokens the model accepts per request (prompt + history + output).
+    #[serde(rename = "contextWindow", default)]
+    pub context_window: Option<u32>,
+    #[serde(rename = "maxOutputTokens", default)]
+    pub max_output_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +240,17 @@ pub struct EngineAgent {
     pub skills: Vec<String>,
     pub workflows: Vec<String>,
 
+    /// When set, only these skills are ever exposed to the agent, whatever `skills` contains.
+    #[serde(rename = "skillAllowlist", default)]
+    pub skill_allowlist: Option<Vec<String>>,
+
+    /// Requests-per-minute limit for this agent alone, replacing the model registry's `rpm`.
+    #[serde(rename = "rpmOverride", default)]
+    pub rpm_override: Option<u32>,
+    /// Tokens-per-minute limit for this agent alone, replacing the model registry's `tpm`.
+    #[serde(rename = "tpmOverride", default)]
+    pub tpm_override: Option<u32>,
+
     /// Flexible metadata for frontend compatibility (role, department, etc.)
     pub metadata: std::collections::HashMap<String, serde_json::Value>,
 
@@ -121,7 +264,29 @@ pub struct EngineAgent {
     pub cost_usd: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl EngineAgent {
+    /// The model ID of the slot given by `active_model_slot` (1 when unset).
+    /// An empty slot falls back to the primary model.
+    pub fn active_model_id(&self) -> Option<String> {
+        match self.active_model_slot {
+            Some(2) => self.model_2.clone(),
+            Some(3) => self.model_3.clone(),
+            _ => None,
+        }.or_else(|| self.model_id.clone())
+    }
+
+    /// The inline config of the active slot, used for its prompt and sampling settings.
+    /// Slots without their own config share the primary one.
+    pub fn active_model_config(&self) -> &ModelConfig {
+        match self.active_model_slot {
+            Some(2) => self.model_config2.as_ref(),
+            Some(3) => self.model_config3.as_ref(),
+            _ => None,
+        }.unwrap_or(&self.model)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TaskPayload {
     pub message: String,
     #[serde(rename = "clusterId")]
@@ -146,6 +311,25 @@ pub struct TaskPayload {
     pub external_id: Option<String>,
     #[serde(rename = "safeMode")]
     pub safe_mode: Option<bool>,
+    /// Wall-clock limit for the whole task, in seconds. Defaults to 300; capped by `MAX_TASK_TIMEOUT_SECS`.
+    #[serde(rename = "timeoutSecs")]
+    pub timeout_secs: Option<u64>,
+    /// Mission that must be `completed` before this task may start. Otherwise the new mission
+    /// is recorded as blocked (left `pending`) and the task is rejected.
+    #[serde(rename = "dependsOn")]
+    pub depends_on: Option<String>,
+    /// Trace the task's spans join, like a `traceparent` trace ID. Set on sub-agent tasks so
+    /// the swarm forms one trace; root tasks start a new one.
+    #[serde(rename = "traceId")]
+    pub trace_id: Option<String>,
+    /// Labels stored on the mission this task creates.
+    pub tags: Option<Vec<String>>,
+    /// URL POSTed with the mission's outcome once it completes or fails.
+    #[serde(rename = "callbackUrl")]
+    pub callback_url: Option<String>,
+    /// Key for the callback's `X-Tadpole-Signature` HMAC. Stored with the mission, never returned.
+    #[serde(rename = "callbackSecret")]
+    pub callback_secret: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,12 +355,23 @@ pub struct AgentConfigUpdate {
     pub external_id: Option<String>,
     pub skills: Option<Vec<String>>,
     pub workflows: Option<Vec<String>>,
+    /// `null` removes the allowlist; omit the field to keep it.
+    #[serde(rename = "skillAllowlist", default, deserialize_with = "explicit_null")]
+    pub skill_allowlist: Option<Option<Vec<String>>>,
     #[serde(rename = "activeModelSlot")]
     pub active_model_slot: Option<i32>,
     #[serde(rename = "modelConfig2")]
     pub model_config2: Option<ModelConfig>,
     #[serde(rename = "modelConfig3")]
     pub model_config3: Option<ModelConfig>,
+    #[serde(rename = "safetySettings")]
+    pub safety_settings: Option<Vec<GeminiSafetySetting>>,
+    /// `0` removes the override.
+    #[serde(rename = "rpmOverride")]
+    pub rpm_override: Option<u32>,
+    /// `0` removes the override.
+    #[serde(rename = "tpmOverride")]
+    pub tpm_override: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -192,6 +387,79 @@ pub struct ToolCall {
     pub timestamp: String,
 }
 
+/// Failure raised by a tool handler. The variant decides how `execute_tool` reacts:
+/// `SecurityViolation` and `NotFound` abort the run, `NetworkError` and `Timeout` are retried once,
+/// and everything else is reported back to the model as tool output.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ToolError {
+    #[error("network error: {0}")]
+    NetworkError(String),
+    #[error("security violation: {0}")]
+    SecurityViolation(String),
+    #[error("timed out: {0}")]
+    Timeout(String),
+    #[error("provider error: {0}")]
+    ProviderError(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+impl ToolError {
+    /// True for transient failures worth one more attempt.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ToolError::NetworkError(_) | ToolError::Timeout(_))
+    }
+
+    /// True for failures that must stop the run instead of being fed back to the model.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, ToolError::SecurityViolation(_) | ToolError::NotFound(_))
+    }
+
+    /// Wraps a failed synthesis call to the model provider.
+    pub fn from_provider(e: anyhow::Error) -> Self {
+        ToolError::ProviderError(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for ToolError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => ToolError::NotFound(e.to_string()),
+            std::io::ErrorKind::TimedOut => ToolError::Timeout(e.to_string()),
+            _ => ToolError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<reqwest::Error> for ToolError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ToolError::Timeout(e.to_string())
+        } else {
+            ToolError::NetworkError(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for ToolError {
+    fn from(e: serde_json::Error) -> Self {
+        ToolError::Internal(e.to_string())
+    }
+}
+
+/// Persistence, hook and sub-agent failures surface as `anyhow::Error`. They are never retried:
+/// a handler may already have produced side effects (oversight requests, webhooks) before failing.
+impl From<anyhow::Error> for ToolError {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast::<ToolError>() {
+            Ok(tool_error) => tool_error,
+            Err(e) => ToolError::Internal(e.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum CapabilityType {
@@ -217,11 +485,28 @@ pub struct OversightEntry {
     pub tool_call: Option<ToolCall>,
     #[serde(rename = "capabilityProposal")]
     pub capability_proposal: Option<CapabilityProposal>,
-    pub status: String, // "pending" | "approved" | "rejected"
+    pub status: String, // "pending" | "approved" | "rejected" | "timed_out"
     #[serde(rename = "createdAt")]
     pub created_at: String,
 }
 
+/// A decided oversight entry as persisted in `oversight_log`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OversightRecord {
+    pub id: String,
+    pub mission_id: Option<String>,
+    pub agent_id: String,
+    pub skill: String,
+    pub params: serde_json::Value,
+    /// "approved" | "rejected" | "timed_out"
+    pub status: String,
+    pub decided_by: Option<String>,
+    pub description: Option<String>,
+    pub department: Option<String>,
+    pub created_at: Option<String>,
+    pub decided_at: Option<String>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum MissionStatus {
@@ -242,6 +527,24 @@ pub struct Mission {
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub budget_usd: f64,
     pub cost_usd: f64,
+    /// Mission this one waited on, if it was started with `dependsOn`.
+    #[serde(default)]
+    pub prerequisite_mission_id: Option<String>,
+    /// Free-form labels for grouping missions by project or initiative.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Mission this one retries, if it was started with `POST /missions/:id/retry`.
+    #[serde(default)]
+    pub parent_mission_id: Option<String>,
+}
+
+/// One page of a larger result set. `page` is 1-based; `total` counts every matching row.
+#[derive(Debug, Clone, Serialize)]
+pub struct PagedResult<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -256,6 +559,75 @@ pub struct MissionLog {
     pub metadata: Option<serde_json::Value>,
 }
 
+/// A finding an agent shared with the swarm during a mission (`swarm_context` row).
+#[derive(Debug, Clone, Serialize)]
+pub struct MissionFinding {
+    pub id: String,
+    pub agent_id: String,
+    pub topic: String,
+    pub finding: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// How sure the sharing agent was, from 0.0 to 1.0. `None` when it did not say.
+    pub confidence: Option<f32>,
+    /// The latest `verify_finding` verdict from another agent.
+    pub verdict: Option<FindingVerdict>,
+    pub verified_by: Option<String>,
+    pub verified_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Confidence a disputed finding keeps, as a share of what its author reported.
+pub const DISPUTED_CONFIDENCE_FACTOR: f32 = 0.5;
+
+impl MissionFinding {
+    /// The confidence downstream agents should give the finding: the reported value, cut by
+    /// `DISPUTED_CONFIDENCE_FACTOR` once disputed. An unrated finding counts as 0.5 when disputed.
+    pub fn effective_confidence(&self) -> Option<f32> {
+        match self.verdict {
+            Some(FindingVerdict::Disputed) => Some(self.confidence.unwrap_or(0.5) * DISPUTED_CONFIDENCE_FACTOR),
+            _ => self.confidence,
+        }
+    }
+}
+
+/// Another agent's judgement of a shared finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingVerdict {
+    Confirmed,
+    Disputed,
+}
+
+impl FindingVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Confirmed => "confirmed",
+            Self::Disputed => "disputed",
+        }
+    }
+}
+
+impl std::str::FromStr for FindingVerdict {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "confirmed" => Ok(Self::Confirmed),
+            "disputed" => Ok(Self::Disputed),
+            other => Err(anyhow::anyhow!("unknown verdict '{}' (expected 'confirmed' or 'disputed')", other)),
+        }
+    }
+}
+
+/// Everything recorded for one mission, as returned by `GET /missions/:id/export`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MissionExport {
+    pub mission: Mission,
+    pub logs: Vec<MissionLog>,
+    pub findings: Vec<MissionFinding>,
+    /// True when the mission had more log rows than the export limit and the rest were left out.
+    pub logs_truncated: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeminiFunctionCall {
     pub name: String,
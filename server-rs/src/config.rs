@@ -0,0 +1,222 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Config file read when `TADPOLE_CONFIG` is not set. Missing is fine; unparseable is fatal.
+pub const DEFAULT_CONFIG_PATH: &str = "tadpole.toml";
+
+/// Engine settings from `tadpole.toml`, overridden field by field by the matching environment
+/// variables so containerized deployments can still override a baked-in file.
+/// Every field is optional; unset values fall back to the engine's built-in defaults.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TadpoleConfig {
+    /// `PORT`
+    pub port: Option<u16>,
+    /// `NEURAL_TOKEN` (legacy name for the admin token)
+    pub neural_token: Option<String>,
+    /// `ADMIN_TOKEN` — full access. Takes precedence over `neural_token`.
+    pub admin_token: Option<String>,
+    /// `READ_TOKEN` — read-only access to `GET` routes and the WebSocket feed.
+    pub read_token: Option<String>,
+    /// `DATABASE_URL`
+    pub database_url: Option<String>,
+    /// `ALLOWED_ORIGINS` (comma-separated in the environment, an array in the file)
+    pub allowed_origins: Option<Vec<String>>,
+    /// `DATA_DIR`
+    pub data_dir: Option<PathBuf>,
+    /// `OVERSIGHT_TIMEOUT_SECS`
+    pub oversight_timeout_secs: Option<u64>,
+    /// `MAX_TASK_TIMEOUT_SECS`
+    pub max_task_timeout_secs: Option<u64>,
+    /// `LEGACY_JSON_BACKUP`
+    pub legacy_json_backup: Option<bool>,
+    /// `BUDGET_ALERT_THRESHOLD_PCT`
+    pub budget_alert_threshold_pct: Option<f64>,
+    /// `SKILL_CPU_LIMIT_SECS`
+    pub skill_cpu_limit_secs: Option<u64>,
+    /// `SKILL_MEMORY_LIMIT_MB`
+    pub skill_memory_limit_mb: Option<u64>,
+    /// `MAX_CONTEXT_CHARS`
+    pub max_context_chars: Option<usize>,
+    /// `PROVIDER_TIMEOUT_GROQ_SECS`
+    pub provider_timeout_groq_secs: Option<u64>,
+    /// `PROVIDER_TIMEOUT_GOOGLE_SECS`
+    pub provider_timeout_google_secs: Option<u64>,
+    /// `PROVIDER_OPENAI_TIMEOUT_SECS`
+    pub provider_openai_timeout_secs: Option<u64>,
+    /// `PROVIDER_HEALTH_CHECK_INTERVAL_SECS`
+    pub provider_health_check_interval_secs: Option<u64>,
+    /// `PRESERVE_SKILL_METRICS_ON_RELOAD`
+    pub preserve_skill_metrics_on_reload: Option<bool>,
+    /// `CAPABILITY_RELOAD_INTERVAL_SECS`
+    pub capability_reload_interval_secs: Option<u64>,
+    /// `MAX_PARALLEL_TOOL_CALLS`
+    pub max_parallel_tool_calls: Option<usize>,
+    /// `MAX_CONCURRENT_MISSIONS`
+    pub max_concurrent_missions: Option<usize>,
+    /// `RUN_TESTS_COMMAND`
+    pub run_tests_command: Option<String>,
+    /// `ALLOWED_GIT_HOSTS` (comma-separated in the environment, an array in the file)
+    pub allowed_git_hosts: Option<Vec<String>>,
+    /// `SMTP_HOST`
+    pub smtp_host: Option<String>,
+    /// `SMTP_PORT`
+    pub smtp_port: Option<u16>,
+    /// `SMTP_USERNAME`
+    pub smtp_username: Option<String>,
+    /// `SMTP_PASSWORD`
+    pub smtp_password: Option<String>,
+    /// `SMTP_TLS` — `starttls`, `tls` or `none`
+    pub smtp_tls: Option<String>,
+    /// `EMAIL_FROM`
+    pub email_from: Option<String>,
+}
+
+impl TadpoleConfig {
+    /// Loads `TADPOLE_CONFIG` (or `tadpole.toml`) and applies environment overrides.
+    /// An explicitly configured path must exist; any file that exists must parse.
+    pub fn load() -> Result<Self> {
+        let file = match std::env::var("TADPOLE_CONFIG") {
+            Ok(path) => Self::from_file(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_PATH).exists() => Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?,
+            Err(_) => Self::default(),
+        };
+        Ok(file.with_env_overrides(|key| std::env::var(key).ok()))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file '{}'", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid config file '{}'", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// Replaces each field whose environment variable is set. Values that fail to parse are
+    /// ignored with a warning, matching how the engine has always treated bad env values.
+    pub fn with_env_overrides(mut self, env: impl Fn(&str) -> Option<String>) -> Self {
+        fn parsed<T: std::str::FromStr>(env: &impl Fn(&str) -> Option<String>, key: &str) -> Option<T> {
+            let raw = env(key)?;
+            let value = raw.trim().parse().ok();
+            if value.is_none() {
+                tracing::warn!("⚠️ [Config] Ignoring unparseable {}={}", key, raw);
+            }
+            value
+        }
+
+        if let Some(port) = parsed(&env, "PORT") { self.port = Some(port); }
+        if let Some(token) = env("NEURAL_TOKEN") { self.neural_token = Some(token); }
+        if let Some(token) = env("ADMIN_TOKEN") { self.admin_token = Some(token); }
+        if let Some(token) = env("READ_TOKEN") { self.read_token = Some(token); }
+        if let Some(url) = env("DATABASE_URL") { self.database_url = Some(url); }
+        if let Some(origins) = env("ALLOWED_ORIGINS") {
+            self.allowed_origins = Some(origins.split(',').map(|o| o.trim().to_string()).filter(|o| !o.is_empty()).collect());
+        }
+        if let Some(dir) = env("DATA_DIR") { self.data_dir = Some(PathBuf::from(dir)); }
+        if let Some(secs) = parsed(&env, "OVERSIGHT_TIMEOUT_SECS") { self.oversight_timeout_secs = Some(secs); }
+        if let Some(secs) = parsed(&env, "MAX_TASK_TIMEOUT_SECS") { self.max_task_timeout_secs = Some(secs); }
+        // Historically only the exact string "true" enabled the backup
+        if let Some(flag) = env("LEGACY_JSON_BACKUP") { self.legacy_json_backup = Some(flag == "true"); }
+        if let Some(pct) = parsed(&env, "BUDGET_ALERT_THRESHOLD_PCT") { self.budget_alert_threshold_pct = Some(pct); }
+        if let Some(secs) = parsed(&env, "SKILL_CPU_LIMIT_SECS") { self.skill_cpu_limit_secs = Some(secs); }
+        if let Some(mb) = parsed(&env, "SKILL_MEMORY_LIMIT_MB") { self.skill_memory_limit_mb = Some(mb); }
+        if let Some(chars) = parsed(&env, "MAX_CONTEXT_CHARS") { self.max_context_chars = Some(chars); }
+        if let Some(secs) = parsed(&env, "PROVIDER_TIMEOUT_GROQ_SECS") { self.provider_timeout_groq_secs = Some(secs); }
+        if let Some(secs) = parsed(&env, "PROVIDER_TIMEOUT_GOOGLE_SECS") { self.provider_timeout_google_secs = Some(secs); }
+        if let Some(secs) = parsed(&env, "PROVIDER_OPENAI_TIMEOUT_SECS") { self.provider_openai_timeout_secs = Some(secs); }
+        if let Some(secs) = parsed(&env, "PROVIDER_HEALTH_CHECK_INTERVAL_SECS") { self.provider_health_check_interval_secs = Some(secs); }
+        if let Some(flag) = parsed(&env, "PRESERVE_SKILL_METRICS_ON_RELOAD") { self.preserve_skill_metrics_on_reload = Some(flag); }
+        if let Some(secs) = parsed(&env, "CAPABILITY_RELOAD_INTERVAL_SECS") { self.capability_reload_interval_secs = Some(secs); }
+        if let Some(n) = parsed(&env, "MAX_PARALLEL_TOOL_CALLS") { self.max_parallel_tool_calls = Some(n); }
+        if let Some(n) = parsed(&env, "MAX_CONCURRENT_MISSIONS") { self.max_concurrent_missions = Some(n); }
+        if let Some(command) = env("RUN_TESTS_COMMAND") { self.run_tests_command = Some(command); }
+        if let Some(hosts) = env("ALLOWED_GIT_HOSTS") {
+            self.allowed_git_hosts = Some(hosts.split(',').map(|h| h.trim().to_string()).filter(|h| !h.is_empty()).collect());
+        }
+        if let Some(host) = env("SMTP_HOST") { self.smtp_host = Some(host); }
+        if let Some(port) = parsed(&env, "SMTP_PORT") { self.smtp_port = Some(port); }
+        if let Some(user) = env("SMTP_USERNAME") { self.smtp_username = Some(user); }
+        if let Some(pass) = env("SMTP_PASSWORD") { self.smtp_password = Some(pass); }
+        if let Some(tls) = env("SMTP_TLS") { self.smtp_tls = Some(tls); }
+        if let Some(from) = env("EMAIL_FROM") { self.email_from = Some(from); }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const SAMPLE: &str = r#"
+        # Engine settings
+        port = 9000
+        neural_token = "file-token"   # overridden in production
+        database_url = 'sqlite:/var/lib/tadpole/tadpole.db'
+        allowed_origins = [
+            "http://localhost:5173",
+            "https://ops.example.com",
+        ]
+        oversight_timeout_secs = 600
+        legacy_json_backup = true
+    "#;
+
+    #[test]
+    fn test_parses_flat_toml_config() {
+        let config = TadpoleConfig::parse(SAMPLE).unwrap();
+        assert_eq!(config.port, Some(9000));
+        assert_eq!(config.neural_token.as_deref(), Some("file-token"));
+        assert_eq!(config.database_url.as_deref(), Some("sqlite:/var/lib/tadpole/tadpole.db"));
+        assert_eq!(config.allowed_origins, Some(vec!["http://localhost:5173".to_string(), "https://ops.example.com".to_string()]));
+        assert_eq!(config.oversight_timeout_secs, Some(600));
+        assert_eq!(config.legacy_json_backup, Some(true));
+        assert_eq!(config.max_task_timeout_secs, None);
+    }
+
+    #[test]
+    fn test_env_overrides_file_values() {
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("PORT", "7000"),
+            ("ALLOWED_ORIGINS", "https://a.example.com, https://b.example.com"),
+            ("MAX_TASK_TIMEOUT_SECS", "120"),
+            ("OVERSIGHT_TIMEOUT_SECS", "not-a-number"),
+            ("MAX_PARALLEL_TOOL_CALLS", "3"),
+            ("MAX_CONCURRENT_MISSIONS", "4"),
+            ("CAPABILITY_RELOAD_INTERVAL_SECS", "30"),
+            ("ALLOWED_GIT_HOSTS", "github.com, git.example.com"),
+            ("SMTP_PORT", "2525"),
+        ]);
+        let config = TadpoleConfig::parse(SAMPLE).unwrap()
+            .with_env_overrides(|key| env.get(key).map(|v| v.to_string()));
+
+        assert_eq!(config.port, Some(7000), "env beats file");
+        assert_eq!(config.allowed_origins, Some(vec!["https://a.example.com".to_string(), "https://b.example.com".to_string()]));
+        assert_eq!(config.max_task_timeout_secs, Some(120), "env fills fields the file leaves unset");
+        assert_eq!(config.oversight_timeout_secs, Some(600), "unparseable env values keep the file value");
+        assert_eq!(config.neural_token.as_deref(), Some("file-token"), "file value stands when env is unset");
+        assert_eq!(config.max_parallel_tool_calls, Some(3));
+        assert_eq!(config.max_concurrent_missions, Some(4));
+        assert_eq!(config.capability_reload_interval_secs, Some(30));
+        assert_eq!(config.allowed_git_hosts, Some(vec!["github.com".to_string(), "git.example.com".to_string()]));
+        assert_eq!(config.smtp_port, Some(2525));
+    }
+
+    #[test]
+    fn test_example_config_parses() {
+        let config = TadpoleConfig::parse(include_str!("../tadpole.example.toml")).unwrap();
+        assert_eq!(config.port, Some(8000));
+        assert_eq!(config.budget_alert_threshold_pct, Some(80.0));
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        assert!(TadpoleConfig::parse("port = \"not a number\"").is_err());
+        assert!(TadpoleConfig::parse("prot = 8000").is_err(), "unknown keys are typos, not ignorable");
+        assert!(TadpoleConfig::parse("[server]\nport = 8000").is_err());
+        assert!(TadpoleConfig::parse("neural_token = unquoted").is_err());
+        assert!(TadpoleConfig::parse("port = 8000\nport = 9000").is_err());
+    }
+}
@@ -1,101 +1,404 @@
 use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
 use std::str::FromStr;
-use anyhow::Result;
+use anyhow::{Context, Result};
+
+/// A schema change applied exactly once, in `version` order, inside its own transaction.
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    /// One or more `;`-separated statements.
+    pub sql: &'static str,
+}
+
+/// The engine's schema history. Append new migrations with the next version number;
+/// never edit one that has shipped, since existing databases will not re-run it.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "core tables",
+        sql: "
+            CREATE TABLE IF NOT EXISTS agents (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                role TEXT NOT NULL,
+                department TEXT NOT NULL,
+                description TEXT NOT NULL,
+                model_id TEXT,
+                tokens_used INTEGER DEFAULT 0,
+                status TEXT NOT NULL,
+                theme_color TEXT,
+                budget_usd REAL DEFAULT 0.0,
+                cost_usd REAL DEFAULT 0.0,
+                metadata TEXT NOT NULL, -- JSON blob
+                skills TEXT, -- JSON array
+                workflows TEXT, -- JSON array
+                model_2 TEXT,
+                model_3 TEXT,
+                model_config2 TEXT, -- JSON blob
+                model_config3 TEXT, -- JSON blob
+                active_model_slot INTEGER DEFAULT 1,
+                skill_allowlist TEXT -- JSON array, NULL = no restriction
+            );
+
+            CREATE TABLE IF NOT EXISTS mission_history (
+                id TEXT PRIMARY KEY,
+                agent_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                status TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                budget_usd REAL DEFAULT 0.0,
+                cost_usd REAL DEFAULT 0.0,
+                FOREIGN KEY(agent_id) REFERENCES agents(id)
+            );
+
+            CREATE TABLE IF NOT EXISTS mission_logs (
+                id TEXT PRIMARY KEY,
+                mission_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                source TEXT NOT NULL, -- 'User' | 'System' | 'Agent'
+                text TEXT NOT NULL,
+                severity TEXT NOT NULL, -- 'info' | 'success' | 'warning' | 'error'
+                timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+                metadata TEXT, -- JSON blob
+                FOREIGN KEY(mission_id) REFERENCES mission_history(id)
+            );
+
+            CREATE TABLE IF NOT EXISTS oversight_log (
+                id TEXT PRIMARY KEY,
+                mission_id TEXT,
+                agent_id TEXT NOT NULL,
+                skill TEXT NOT NULL,
+                params TEXT NOT NULL, -- JSON blob
+                status TEXT NOT NULL, -- 'pending' | 'approved' | 'rejected'
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY(mission_id) REFERENCES mission_history(id)
+            );
+
+            CREATE TABLE IF NOT EXISTS swarm_context (
+                id TEXT PRIMARY KEY,
+                mission_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                finding TEXT NOT NULL,
+                timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY(mission_id) REFERENCES mission_history(id)
+            );
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "mutation audit log",
+        sql: "
+            CREATE TABLE IF NOT EXISTS mutation_audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp DATETIME NOT NULL,
+                method TEXT NOT NULL,
+                path TEXT NOT NULL,
+                status_code INTEGER,
+                user_id TEXT,
+                request_id TEXT
+            );
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "cost reset history",
+        sql: "
+            CREATE TABLE IF NOT EXISTS cost_resets (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                agent_id TEXT NOT NULL,
+                previous_cost_usd REAL NOT NULL,
+                previous_tokens_used INTEGER NOT NULL,
+                reset_at DATETIME NOT NULL,
+                FOREIGN KEY(agent_id) REFERENCES agents(id)
+            );
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "mission prerequisites",
+        sql: "
+            ALTER TABLE mission_history ADD COLUMN prerequisite_mission_id TEXT REFERENCES mission_history(id);
+        ",
+    },
+    Migration {
+        version: 5,
+        description: "oversight decision details",
+        sql: "
+            ALTER TABLE oversight_log ADD COLUMN decided_by TEXT;
+            ALTER TABLE oversight_log ADD COLUMN decided_at DATETIME;
+            ALTER TABLE oversight_log ADD COLUMN description TEXT;
+            ALTER TABLE oversight_log ADD COLUMN department TEXT;
+            CREATE INDEX IF NOT EXISTS idx_oversight_log_decided_at ON oversight_log(decided_at);
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "mission log timeline index",
+        sql: "
+            CREATE INDEX IF NOT EXISTS idx_mission_logs_mission_timestamp ON mission_logs(mission_id, timestamp);
+        ",
+    },
+    Migration {
+        version: 7,
+        description: "agent soft delete",
+        sql: "
+            ALTER TABLE agents ADD COLUMN deleted_at DATETIME;
+        ",
+    },
+    Migration {
+        version: 8,
+        description: "mission tags",
+        sql: "
+            ALTER TABLE mission_history ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "agent rate limit overrides",
+        sql: "
+            ALTER TABLE agents ADD COLUMN rpm_override INTEGER;
+            ALTER TABLE agents ADD COLUMN tpm_override INTEGER;
+        ",
+    },
+    Migration {
+        version: 10,
+        description: "mission retries",
+        sql: "
+            ALTER TABLE mission_history ADD COLUMN parent_mission_id TEXT;
+        ",
+    },
+    Migration {
+        version: 11,
+        description: "swarm context topic index",
+        sql: "
+            CREATE INDEX IF NOT EXISTS idx_swarm_context_mission_topic ON swarm_context(mission_id, topic);
+        ",
+    },
+    Migration {
+        version: 12,
+        description: "daily cost summaries",
+        sql: "
+            CREATE TABLE IF NOT EXISTS cost_summaries (
+                date TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                total_cost_usd REAL NOT NULL,
+                mission_count INTEGER NOT NULL,
+                computed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (date, agent_id)
+            );
+        ",
+    },
+    Migration {
+        version: 13,
+        description: "swarm context summaries",
+        sql: "
+            ALTER TABLE swarm_context ADD COLUMN summary_through INTEGER;
+        ",
+    },
+    Migration {
+        version: 14,
+        description: "finding confidence and verification",
+        sql: "
+            ALTER TABLE swarm_context ADD COLUMN confidence REAL;
+            ALTER TABLE swarm_context ADD COLUMN verdict TEXT;
+            ALTER TABLE swarm_context ADD COLUMN verified_by TEXT;
+            ALTER TABLE swarm_context ADD COLUMN verified_at DATETIME;
+        ",
+    },
+    Migration {
+        version: 15,
+        description: "mission completion callbacks",
+        sql: "
+            ALTER TABLE mission_history ADD COLUMN callback_url TEXT;
+            ALTER TABLE mission_history ADD COLUMN callback_secret TEXT;
+        ",
+    },
+    Migration {
+        version: 16,
+        description: "agent memory",
+        sql: "
+            CREATE TABLE IF NOT EXISTS agent_memory (
+                id TEXT PRIMARY KEY,
+                agent_id TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                ttl_secs INTEGER,
+                UNIQUE (agent_id, key)
+            );
+        ",
+    },
+    Migration {
+        version: 17,
+        description: "rolling token stats",
+        sql: "
+            CREATE TABLE IF NOT EXISTS token_stats (
+                agent_id TEXT NOT NULL,
+                window_type TEXT NOT NULL, -- 'hour' | 'day' | 'week'
+                window_start DATETIME NOT NULL,
+                input_tokens INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0,
+                cost_usd REAL NOT NULL DEFAULT 0.0,
+                PRIMARY KEY (agent_id, window_type, window_start)
+            );
+        ",
+    },
+];
+
+/// Columns that older engines added with unconditional `ALTER TABLE` on every start.
+/// Databases created before `schema_version` existed may lack any of them.
+const LEGACY_COLUMNS: &[(&str, &str, &str)] = &[
+    ("agents", "budget_usd", "REAL DEFAULT 0.0"),
+    ("agents", "cost_usd", "REAL DEFAULT 0.0"),
+    ("agents", "skills", "TEXT"),
+    ("agents", "workflows", "TEXT"),
+    ("agents", "model_2", "TEXT"),
+    ("agents", "model_3", "TEXT"),
+    ("agents", "model_config2", "TEXT"),
+    ("agents", "model_config3", "TEXT"),
+    ("agents", "active_model_slot", "INTEGER DEFAULT 1"),
+    ("agents", "skill_allowlist", "TEXT"),
+    ("mission_history", "budget_usd", "REAL DEFAULT 0.0"),
+    ("mission_history", "cost_usd", "REAL DEFAULT 0.0"),
+];
 
 pub async fn init_db(database_url: &str) -> Result<SqlitePool> {
     let options = SqliteConnectOptions::from_str(database_url)?
         .create_if_missing(true);
 
     let pool = SqlitePool::connect_with(options).await?;
+    run_migrations(&pool, MIGRATIONS).await?;
+
+    Ok(pool)
+}
+
+/// Applies every migration newer than the recorded schema version, each in its own transaction.
+/// The first failure is returned and leaves the database at the last successful version.
+pub async fn run_migrations(pool: &SqlitePool, migrations: &[Migration]) -> Result<()> {
+    let versioned = table_exists(pool, "schema_version").await?;
+    if !versioned && table_exists(pool, "agents").await? {
+        upgrade_legacy_columns(pool).await?;
+    }
 
-    // Create tables
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS agents (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            role TEXT NOT NULL,
-            department TEXT NOT NULL,
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
             description TEXT NOT NULL,
-            model_id TEXT,
-            tokens_used INTEGER DEFAULT 0,
-            status TEXT NOT NULL,
-            theme_color TEXT,
-            budget_usd REAL DEFAULT 0.0,
-            cost_usd REAL DEFAULT 0.0,
-            metadata TEXT NOT NULL, -- JSON blob
-            model_2 TEXT,
-            model_3 TEXT,
-            model_config2 TEXT, -- JSON blob
-            model_config3 TEXT, -- JSON blob
-            active_model_slot INTEGER DEFAULT 1
+            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
         )"
-    ).execute(&pool).await?;
-
-    // Migration: Add columns if they don't exist (SQLite doesn't support IF NOT EXISTS for columns easily in one line)
-    let _ = sqlx::query("ALTER TABLE agents ADD COLUMN budget_usd REAL DEFAULT 0.0").execute(&pool).await;
-    let _ = sqlx::query("ALTER TABLE agents ADD COLUMN cost_usd REAL DEFAULT 0.0").execute(&pool).await;
-    let _ = sqlx::query("ALTER TABLE agents ADD COLUMN skills TEXT").execute(&pool).await;
-    let _ = sqlx::query("ALTER TABLE agents ADD COLUMN workflows TEXT").execute(&pool).await;
-    let _ = sqlx::query("ALTER TABLE agents ADD COLUMN model_2 TEXT").execute(&pool).await;
-    let _ = sqlx::query("ALTER TABLE agents ADD COLUMN model_3 TEXT").execute(&pool).await;
-    let _ = sqlx::query("ALTER TABLE agents ADD COLUMN model_config2 TEXT").execute(&pool).await;
-    let _ = sqlx::query("ALTER TABLE agents ADD COLUMN model_config3 TEXT").execute(&pool).await;
-    let _ = sqlx::query("ALTER TABLE agents ADD COLUMN active_model_slot INTEGER DEFAULT 1").execute(&pool).await;
-    let _ = sqlx::query("ALTER TABLE mission_history ADD COLUMN budget_usd REAL DEFAULT 0.0").execute(&pool).await;
-    let _ = sqlx::query("ALTER TABLE mission_history ADD COLUMN cost_usd REAL DEFAULT 0.0").execute(&pool).await;
+    ).execute(pool).await?;
 
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS mission_history (
-            id TEXT PRIMARY KEY,
-            agent_id TEXT NOT NULL,
-            title TEXT NOT NULL,
-            status TEXT NOT NULL,
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-            budget_usd REAL DEFAULT 0.0,
-            cost_usd REAL DEFAULT 0.0,
-            FOREIGN KEY(agent_id) REFERENCES agents(id)
-        )"
-    ).execute(&pool).await?;
+    let current: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_version")
+        .fetch_one(pool)
+        .await?;
 
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS mission_logs (
-            id TEXT PRIMARY KEY,
-            mission_id TEXT NOT NULL,
-            agent_id TEXT NOT NULL,
-            source TEXT NOT NULL, -- 'User' | 'System' | 'Agent'
-            text TEXT NOT NULL,
-            severity TEXT NOT NULL, -- 'info' | 'success' | 'warning' | 'error'
-            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
-            metadata TEXT, -- JSON blob
-            FOREIGN KEY(mission_id) REFERENCES mission_history(id)
-        )"
-    ).execute(&pool).await?;
+    let mut pending: Vec<&Migration> = migrations.iter().filter(|m| m.version > current).collect();
+    pending.sort_by_key(|m| m.version);
 
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS oversight_log (
-            id TEXT PRIMARY KEY,
-            mission_id TEXT,
-            agent_id TEXT NOT NULL,
-            skill TEXT NOT NULL,
-            params TEXT NOT NULL, -- JSON blob
-            status TEXT NOT NULL, -- 'pending' | 'approved' | 'rejected'
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-            FOREIGN KEY(mission_id) REFERENCES mission_history(id)
-        )"
-    ).execute(&pool).await?;
+    for migration in pending {
+        let mut tx = pool.begin().await?;
+        sqlx::raw_sql(migration.sql)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Migration {} ({}) failed", migration.version, migration.description))?;
+        sqlx::query("INSERT INTO schema_version (version, description) VALUES (?, ?)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        tracing::info!("🗄️ Applied schema migration {} ({})", migration.version, migration.description);
+    }
 
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS swarm_context (
-            id TEXT PRIMARY KEY,
-            mission_id TEXT NOT NULL,
-            agent_id TEXT NOT NULL,
-            topic TEXT NOT NULL,
-            finding TEXT NOT NULL,
-            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
-            FOREIGN KEY(mission_id) REFERENCES mission_history(id)
-        )"
-    ).execute(&pool).await?;
+    Ok(())
+}
 
-    Ok(pool)
+async fn table_exists(pool: &SqlitePool, table: &str) -> Result<bool> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(table)
+        .fetch_one(pool)
+        .await?;
+    Ok(count > 0)
+}
+
+/// Brings a pre-versioning database up to the migration 1 shape by adding only the columns it is missing.
+async fn upgrade_legacy_columns(pool: &SqlitePool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    for (table, column, definition) in LEGACY_COLUMNS {
+        let existing: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+            .bind(table)
+            .fetch_all(&mut *tx)
+            .await?;
+        // An absent table is created whole by migration 1
+        if existing.is_empty() || existing.iter().any(|c| c == column) {
+            continue;
+        }
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to add legacy column {}.{}", table, column))?;
+        tracing::info!("🗄️ Added missing legacy column {}.{}", table, column);
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    // Each connection to `sqlite::memory:` is a separate database, so pin the pool to one
+    async fn memory_pool() -> SqlitePool {
+        SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap()
+    }
+
+    async fn recorded_versions(pool: &SqlitePool) -> Vec<i64> {
+        sqlx::query_scalar("SELECT version FROM schema_version ORDER BY version").fetch_all(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn migrations_are_idempotent() {
+        let pool = memory_pool().await;
+        run_migrations(&pool, MIGRATIONS).await.unwrap();
+        run_migrations(&pool, MIGRATIONS).await.unwrap();
+
+        let expected: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert_eq!(recorded_versions(&pool).await, expected);
+        for table in ["agents", "mission_history", "mission_logs", "oversight_log", "swarm_context", "mutation_audit_log", "cost_resets", "agent_memory", "token_stats"] {
+            assert!(table_exists(&pool, table).await.unwrap(), "missing table {}", table);
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_migration_rolls_back_and_aborts() {
+        let pool = memory_pool().await;
+        let migrations = [
+            Migration { version: 1, description: "ok", sql: "CREATE TABLE a (id INTEGER);" },
+            Migration { version: 2, description: "broken", sql: "CREATE TABLE b (id INTEGER); NOT VALID SQL;" },
+            Migration { version: 3, description: "never reached", sql: "CREATE TABLE c (id INTEGER);" },
+        ];
+
+        let err = run_migrations(&pool, &migrations).await.unwrap_err();
+        assert!(err.to_string().contains("Migration 2 (broken) failed"), "{}", err);
+        assert_eq!(recorded_versions(&pool).await, vec![1]);
+        assert!(!table_exists(&pool, "b").await.unwrap(), "partial migration was not rolled back");
+        assert!(!table_exists(&pool, "c").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn legacy_database_gains_missing_columns() {
+        let pool = memory_pool().await;
+        sqlx::query("CREATE TABLE agents (id TEXT PRIMARY KEY, name TEXT NOT NULL, role TEXT NOT NULL, department TEXT NOT NULL, description TEXT NOT NULL, model_id TEXT, tokens_used INTEGER DEFAULT 0, status TEXT NOT NULL, theme_color TEXT, metadata TEXT NOT NULL, skills TEXT)")
+            .execute(&pool).await.unwrap();
+
+        run_migrations(&pool, MIGRATIONS).await.unwrap();
+
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info('agents')").fetch_all(&pool).await.unwrap();
+        for (table, column, _) in LEGACY_COLUMNS.iter().filter(|(t, _, _)| *t == "agents") {
+            assert!(columns.iter().any(|c| c == column), "{}.{} was not added", table, column);
+        }
+    }
 }
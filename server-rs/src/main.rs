@@ -6,38 +6,60 @@ use std::{net::SocketAddr, sync::Arc};
 use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod config;
 mod db;
 mod routes;
 mod state;
 mod agent;
 mod adapter;
 mod middleware;
+mod telemetry;
 
 use crate::state::AppState;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // 1. Initialize Tracing (Structured Logging)
+    // 1. Load Environment Variables
+    // Read before tracing starts so OTLP_ENDPOINT and RUST_LOG can come from .env
+    let dotenv_loaded = dotenvy::dotenv().is_ok();
+    let otlp_endpoint = std::env::var("OTLP_ENDPOINT").ok().filter(|e| !e.trim().is_empty());
+
+    // 2. Initialize Tracing (Structured Logging)
     // Environment filter allows for granular control over log levels via RUST_LOG env var.
+    // Spans are also exported over OTLP when built with the `otel` feature and OTLP_ENDPOINT is set.
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "server_rs=debug,tower_http=debug".into()),
         )
         .with(tracing_subscriber::fmt::layer())
+        .with(telemetry::otlp_layer(otlp_endpoint.as_deref()))
         .init();
 
-    // 2. Load Environment Variables
-    if dotenvy::dotenv().is_err() {
+    if !dotenv_loaded {
         tracing::warn!("No .env file found. Relying on system environment variables.");
     }
+    if let Some(endpoint) = &otlp_endpoint {
+        if cfg!(feature = "otel") {
+            tracing::info!("📡 Exporting agent spans to OTLP collector at {}", endpoint);
+        } else {
+            tracing::warn!(
+                "OTLP_ENDPOINT={} is set, but this build has no OpenTelemetry exporter (rebuild with `--features otel`). Agent spans go to the local log only.",
+                endpoint
+            );
+        }
+    }
+
+    // 2b. Load tadpole.toml (or TADPOLE_CONFIG); env vars override file values.
+    // A config file that exists but cannot be parsed stops startup rather than being ignored.
+    let config = crate::config::TadpoleConfig::load()?;
 
     // 3. Configure CORS
-    // Reads from ALLOWED_ORIGINS env (comma-separated). Falls back to allow-all for local dev.
-    let cors = match std::env::var("ALLOWED_ORIGINS") {
-        Ok(origins) if !origins.trim().is_empty() => {
+    // Reads from allowed_origins / ALLOWED_ORIGINS (comma-separated). Falls back to allow-all for local dev.
+    let cors = match &config.allowed_origins {
+        Some(origins) if !origins.is_empty() => {
             let allowed: Vec<_> = origins
-                .split(',')
+                .iter()
                 .filter_map(|s| s.trim().parse().ok())
                 .collect();
             
@@ -63,12 +85,18 @@ async fn main() -> anyhow::Result<()> {
                 .allow_headers(Any)
         }
     };
+    // Let browser clients read the correlation ID for bug reports
+    let cors = cors.expose_headers([axum::http::HeaderName::from_static(middleware::request_log::REQUEST_ID_HEADER)]);
 
     // 4. Initialize Global AppState
     // Wrapped in Arc for thread-safe sharing across all request handlers.
-    let app_state = Arc::new(AppState::new().await);
+    let app_state = Arc::new(AppState::new(config.clone()).await);
+    let port = app_state.port;
+
+    // 4.1 Launch periodic jobs (capability watcher, provider health checks, roll-ups)
+    app_state.spawn_background_jobs(&config);
 
-    // 4.1 Launch Heartbeat Loop to drive UI presence
+    // 4.2 Launch Heartbeat Loop to drive UI presence
     let heartbeat_state = app_state.clone();
     tokio::spawn(async move {
         loop {
@@ -76,7 +104,7 @@ async fn main() -> anyhow::Result<()> {
             let agent_count = heartbeat_state.agents.len();
             heartbeat_state.emit_event(serde_json::json!({
                 "type": "engine:health",
-                "uptime": 0, 
+                "uptime": heartbeat_state.uptime_secs(),
                 "agentCount": agent_count,
                 "timestamp": chrono::Utc::now().to_rfc3339()
             }));
@@ -84,31 +112,101 @@ async fn main() -> anyhow::Result<()> {
     });
 
     // 5. Build Axum Router
-    // Apply auth middleware to all routes except health check.
-    let protected_routes = Router::new()
+    // Read-only routes accept either the read or the admin token.
+    let read_routes = Router::new()
         .route("/agents", get(routes::agent::get_agents))
+        .route("/missions", get(routes::mission::list_missions))
+        .route("/missions/:id", get(routes::mission::get_mission))
+        .route("/missions/:id/logs", get(routes::mission::get_mission_logs))
+        .route("/missions/:id/steps", get(routes::mission::get_mission_steps))
+        .route("/missions/:id/context/search", get(routes::mission::search_mission_context))
+        .route("/missions/:id/findings", get(routes::mission::get_mission_findings))
+        .route("/missions/:id/export", get(routes::mission::export_mission))
+        .route("/agents/:id/skills", get(routes::agent::get_agent_skills))
+        .route("/agents/:id/missions", get(routes::agent::get_agent_missions))
+        .route("/agents/:id/transcript", get(routes::agent::get_agent_transcript))
+        .route("/agents/:id/workflows", get(routes::agent::get_agent_workflows))
+        .route("/agents/:id/cost-resets", get(routes::agent::get_cost_resets))
+        .route("/agents/:id/memory", get(routes::agent::get_agent_memory))
+        .route("/agents/:id/workspace", get(routes::agent::get_agent_workspace))
+        .route("/agents/:id/token-stats", get(routes::agent::get_agent_token_stats))
+        .route("/oversight/pending", get(routes::oversight::get_pending))
+        .route("/oversight/ledger", get(routes::oversight::get_ledger))
+        .route("/oversight/history", get(routes::oversight::get_history))
+        .route("/infra/providers", get(routes::model_manager::get_providers))
+        .route("/infra/providers/:id/health", get(routes::model_manager::get_provider_health))
+        .route("/infra/models", get(routes::model_manager::get_models))
+        .route("/infra/models/:id/stats", get(routes::model_manager::get_model_stats))
+        .route("/system/capabilities", get(routes::capabilities::get_capabilities))
+        .route("/system/capabilities/diff", get(routes::capabilities::get_capabilities_diff))
+        .route("/system/capabilities/metrics", get(routes::capabilities::get_capabilities_metrics))
+        .route("/system/skills/:name/metrics", get(routes::capabilities::get_skill_metrics))
+        .route("/system/audit-log", get(routes::audit::get_audit_log))
+        .route("/system/audit-log/stats", get(routes::audit::get_audit_stats))
+        .route("/system/cost-report", get(routes::mission::get_cost_report))
+        .route("/engine/config", get(routes::engine_control::get_config))
+        .route("/engine/concurrency", get(routes::engine_control::get_concurrency))
+        .route("/engine/uptime", get(routes::engine_control::get_uptime))
+        .route("/vault", get(routes::vault::list_vault));
+
+    // Mutating routes additionally require the admin token.
+    let admin_routes = Router::new()
         .route("/agents", post(routes::agent::create_agent))
+        .route("/agents/batch", post(routes::agent::batch_create_agents))
         .route("/agents/:id/send", post(routes::agent::send_task))
+        .route("/agents/:id/clone", post(routes::agent::clone_agent))
+        .route("/agents/department/:dept/broadcast", post(routes::agent::broadcast_to_department))
+        .route("/agents/:id/estimate", post(routes::agent::estimate_task))
+        .route("/missions/batch", post(routes::agent::submit_batch))
+        .route("/missions/:id/tags", put(routes::mission::update_mission_tags))
+        .route("/missions/:id/retry", post(routes::mission::retry_mission))
         .route("/agents/:id", put(routes::agent::update_agent))
+        .route("/agents/:id", axum::routing::delete(routes::agent::delete_agent))
         .route("/agents/:id/pause", post(routes::agent::pause_agent))
         .route("/agents/:id/resume", post(routes::agent::resume_agent))
+        .route("/agents/:id/generate-description", post(routes::agent::generate_description))
+        .route("/agents/:id/reset-cost", post(routes::agent::reset_cost))
+        .route("/agents/:id/budget", post(routes::agent::update_budget))
+        .route("/agents/:id/switch-model", post(routes::agent::switch_model))
+        .route("/agents/:id/memory/:key", axum::routing::delete(routes::agent::delete_agent_memory_fact))
+        .route("/agents/reset-cost-all", post(routes::agent::reset_cost_all))
         .route("/oversight/:id/decide", post(routes::oversight::decide_oversight))
-        .route("/oversight/pending", get(routes::oversight::get_pending))
-        .route("/oversight/ledger", get(routes::oversight::get_ledger))
+        .route("/oversight/decide-bulk", post(routes::oversight::decide_oversight_bulk))
         .route("/oversight/settings", put(routes::oversight::update_settings))
-        .route("/infra/providers", get(routes::model_manager::get_providers))
         .route("/infra/providers/:id", put(routes::model_manager::update_provider))
-        .route("/infra/models", get(routes::model_manager::get_models))
         .route("/infra/models/:id", put(routes::model_manager::update_model))
-        .route("/system/capabilities", get(routes::capabilities::get_capabilities))
+        .route("/system/reload-capabilities", post(routes::capabilities::reload_capabilities))
+        // Admin-only despite being a read: packs include every skill's execution command
+        .route("/system/capabilities/export", get(routes::capabilities::export_capabilities))
+        .route(
+            "/system/capabilities/import",
+            // Leave room for the multipart framing around a maximum-size pack
+            post(routes::capabilities::import_capabilities)
+                .layer(axum::extract::DefaultBodyLimit::max(routes::capabilities::MAX_CAPABILITY_IMPORT_BYTES + 64 * 1024))
+        )
         .route("/system/skills/:name", put(routes::capabilities::save_skill))
+        .route("/system/skills/:name/validate", put(routes::capabilities::validate_skill))
+        .route("/system/skills/:name/enable", put(routes::capabilities::enable_skill))
+        .route("/system/skills/:name/disable", put(routes::capabilities::disable_skill))
         .route("/system/skills/:name", axum::routing::delete(routes::capabilities::delete_skill))
         .route("/system/workflows/:name", put(routes::capabilities::save_workflow))
         .route("/system/workflows/:name", axum::routing::delete(routes::capabilities::delete_workflow))
+        .route("/system/reload-context", post(routes::engine_control::reload_context))
+        .route("/webhooks/test", post(routes::webhooks::test_webhook))
+        .route("/engine/import-agents", post(routes::engine_control::import_agents))
+        .route_layer(axum::middleware::from_fn(middleware::auth::require_admin))
+        // Outside `require_admin` so refused mutation attempts are recorded too
+        .route_layer(axum::middleware::from_fn_with_state(app_state.clone(), middleware::audit::audit_mutations));
+
+    // Apply auth middleware to all routes except health check.
+    let protected_routes = read_routes
+        .merge(admin_routes)
         .route_layer(axum::middleware::from_fn_with_state(app_state.clone(), middleware::auth::validate_token));
 
     let app = Router::new()
         .route("/engine/health", get(routes::health::health_check))
+        .route("/system/status", get(routes::health::system_status))
+        .route("/metrics", get(routes::metrics::get_metrics))
         .route("/engine/deploy", post(routes::deploy::trigger_deploy))
         .route("/engine/kill", post(routes::engine_control::kill_agents))
         .route("/engine/shutdown", post(routes::engine_control::shutdown_engine))
@@ -116,8 +214,10 @@ async fn main() -> anyhow::Result<()> {
         .route("/engine/transcribe", post(routes::audio::transcribe_audio))
         .merge(protected_routes)
         .with_state(app_state.clone())
-        // CORS must be the *outermost* layer so it runs first, before Auth
-        .layer(cors);
+        // CORS must run before Auth
+        .layer(cors)
+        // The access log wraps everything, so even CORS preflights get a request ID
+        .layer(axum::middleware::from_fn(middleware::request_log::log_requests));
 
     if app_state.deploy_token == "tadpole-dev-token-2026" {
         tracing::warn!("⚠️  SECURITY WARNING: Using hardcoded fallback NEURAL_TOKEN. Set this env var in production!");
@@ -125,13 +225,19 @@ async fn main() -> anyhow::Result<()> {
 
     // 6. Start the Server
     // Defaults to Port 8000 to maintain compatibility with the legacy Node.js dashboard.
-    let port = std::env::var("PORT").unwrap_or_else(|_| "8000".to_string());
     let addr: SocketAddr = format!("0.0.0.0:{}", port).parse()?;
     
     tracing::info!("🚀 Tadpole OS Engine (Rust Edition) listening on {}", addr);
     
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // `POST /engine/shutdown` fires this once in-flight agents have drained and state is saved
+    let mut shutdown_rx = app_state.shutdown_tx.subscribe();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            let _ = shutdown_rx.recv().await;
+        })
+        .await?;
 
+    telemetry::shutdown();
     Ok(())
 }
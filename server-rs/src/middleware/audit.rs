@@ -0,0 +1,102 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Method, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use crate::middleware::auth::AuthLevel;
+use crate::state::AppState;
+
+/// Middleware for admin routes. Records every mutating request (anything but `GET`/`HEAD`/`OPTIONS`)
+/// in `mutation_audit_log` once its response is ready, whatever the outcome. Must run inside
+/// `validate_token`; the caller's token tier is stored as `user_id`.
+pub async fn audit_mutations(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let method = req.method().clone();
+    if matches!(method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(req).await;
+    }
+
+    let path = req.uri().path().to_string();
+    let user_id = match req.extensions().get::<AuthLevel>() {
+        Some(AuthLevel::Admin) => "admin",
+        Some(AuthLevel::Read) => "read",
+        None => "anonymous",
+    };
+    let response = next.run(req).await;
+
+    let recorded = sqlx::query("INSERT INTO mutation_audit_log (timestamp, method, path, status_code, user_id, request_id) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(chrono::Utc::now())
+        .bind(method.as_str())
+        .bind(&path)
+        .bind(response.status().as_u16() as i64)
+        .bind(user_id)
+        .bind(crate::middleware::request_log::current_request_id())
+        .execute(&state.pool)
+        .await;
+    if let Err(e) = recorded {
+        tracing::error!("❌ [Audit] Failed to record {} {}: {}", method, path, e);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::Router;
+    use sqlx::Row;
+    use tower::Service;
+
+    #[tokio::test]
+    async fn mutations_are_recorded_with_status_and_caller() {
+        let mut state = AppState::for_tests(Default::default()).await;
+        state.read_token = Some("audit-read-token".to_string());
+        let state = Arc::new(state);
+        let path = format!("/audit-mw-{}", uuid::Uuid::new_v4());
+
+        let admin = Router::new()
+            .route(&path, post(|| async { "done" }).get(|| async { "read" }))
+            .route_layer(axum::middleware::from_fn(crate::middleware::auth::require_admin))
+            .route_layer(axum::middleware::from_fn_with_state(state.clone(), audit_mutations));
+        let router = admin
+            .route_layer(axum::middleware::from_fn_with_state(state.clone(), crate::middleware::auth::validate_token))
+            .layer(axum::middleware::from_fn(crate::middleware::request_log::log_requests))
+            .with_state(state.clone());
+
+        let call = |method: &str, token: String| {
+            let req = Request::builder()
+                .method(method)
+                .uri(&path)
+                .header(axum::http::header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap();
+            router.clone().call(req)
+        };
+        let response = call("POST", state.deploy_token.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let request_id = response.headers()[crate::middleware::request_log::REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        assert_eq!(call("POST", "audit-read-token".to_string()).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(call("GET", state.deploy_token.clone()).await.unwrap().status(), StatusCode::OK);
+
+        let rows = sqlx::query("SELECT method, status_code, user_id, request_id FROM mutation_audit_log WHERE path = ? ORDER BY id")
+            .bind(&path)
+            .fetch_all(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2, "the GET is not a mutation");
+        assert_eq!(rows[0].get::<String, _>("method"), "POST");
+        assert_eq!(rows[0].get::<i64, _>("status_code"), 200);
+        assert_eq!(rows[0].get::<String, _>("user_id"), "admin");
+        assert_eq!(rows[0].get::<Option<String>, _>("request_id"), Some(request_id));
+        assert_eq!(rows[1].get::<i64, _>("status_code"), 403);
+        assert_eq!(rows[1].get::<String, _>("user_id"), "read");
+    }
+}
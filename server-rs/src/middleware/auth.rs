@@ -8,10 +8,31 @@ use axum::{
 use std::sync::Arc;
 use crate::state::AppState;
 
+/// Access tier granted by a bearer token. `Admin` implies everything `Read` allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthLevel {
+    /// `READ_TOKEN`: read-only routes and the WebSocket feed.
+    Read,
+    /// `ADMIN_TOKEN` (or legacy `NEURAL_TOKEN`): every route.
+    Admin,
+}
+
+/// Resolves a presented token to its tier, or `None` if it matches neither token.
+pub fn auth_level(state: &AppState, token: &str) -> Option<AuthLevel> {
+    if token == state.deploy_token {
+        Some(AuthLevel::Admin)
+    } else if state.read_token.as_deref() == Some(token) {
+        Some(AuthLevel::Read)
+    } else {
+        None
+    }
+}
+
 /// Middleware to validate the Bearer token in the Authorization header.
+/// On success the caller's `AuthLevel` is attached as a request extension.
 pub async fn validate_token(
     State(state): State<Arc<AppState>>,
-    req: Request<Body>,
+    mut req: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
     let auth_header = req
@@ -22,11 +43,15 @@ pub async fn validate_token(
     match auth_header {
         Some(auth_str) if auth_str.starts_with("Bearer ") => {
             let token = &auth_str[7..];
-            if token == state.deploy_token {
-                Ok(next.run(req).await)
-            } else {
-                tracing::warn!("🚫 Invalid token provided in Authorization header");
-                Err(StatusCode::UNAUTHORIZED)
+            match auth_level(&state, token) {
+                Some(level) => {
+                    req.extensions_mut().insert(level);
+                    Ok(next.run(req).await)
+                }
+                None => {
+                    tracing::warn!("🚫 Invalid token provided in Authorization header");
+                    Err(StatusCode::UNAUTHORIZED)
+                }
             }
         }
         _ => {
@@ -35,3 +60,70 @@ pub async fn validate_token(
         }
     }
 }
+
+/// Middleware for mutating routes. Must run inside `validate_token`; rejects read-tier callers with 403.
+pub async fn require_admin(req: Request<Body>, next: Next) -> Result<Response, StatusCode> {
+    match req.extensions().get::<AuthLevel>() {
+        Some(AuthLevel::Admin) => Ok(next.run(req).await),
+        Some(AuthLevel::Read) => {
+            tracing::warn!("🚫 Read-only token used on {} {}", req.method(), req.uri().path());
+            Err(StatusCode::FORBIDDEN)
+        }
+        // No level means the route was mounted without `validate_token`: fail closed
+        None => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::{get, post};
+    use axum::Router;
+    use tower::Service;
+
+    async fn app() -> (Router, Arc<AppState>) {
+        let mut state = AppState::for_tests(Default::default()).await;
+        state.read_token = Some("read-only-token".to_string());
+        let state = Arc::new(state);
+
+        let admin = Router::new()
+            .route("/agents", post(|| async { "created" }))
+            .route_layer(axum::middleware::from_fn(require_admin));
+        let router = Router::new()
+            .route("/agents", get(|| async { "listed" }))
+            .merge(admin)
+            .route_layer(axum::middleware::from_fn_with_state(state.clone(), validate_token))
+            .with_state(state.clone());
+        (router, state)
+    }
+
+    async fn status(router: &Router, method: &str, token: Option<&str>) -> StatusCode {
+        let mut req = Request::builder().method(method).uri("/agents");
+        if let Some(token) = token {
+            req = req.header(axum::http::header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        router.clone().call(req.body(Body::empty()).unwrap()).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn read_token_may_read_but_not_mutate() {
+        let (router, _) = app().await;
+        assert_eq!(status(&router, "GET", Some("read-only-token")).await, StatusCode::OK);
+        assert_eq!(status(&router, "POST", Some("read-only-token")).await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn admin_token_may_read_and_mutate() {
+        let (router, state) = app().await;
+        assert_eq!(status(&router, "GET", Some(&state.deploy_token)).await, StatusCode::OK);
+        assert_eq!(status(&router, "POST", Some(&state.deploy_token)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn unknown_or_missing_token_is_unauthorized() {
+        let (router, _) = app().await;
+        assert_eq!(status(&router, "GET", Some("wrong")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&router, "POST", Some("wrong")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&router, "GET", None).await, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,166 @@
+use axum::{
+    body::Body,
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+use tracing::Instrument;
+
+/// Response header carrying the per-request correlation ID.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    /// ID of the request the current task is serving, set by `log_requests`.
+    static REQUEST_ID: String;
+}
+
+/// The ID of the request being handled, if called from inside `log_requests`.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// The `:id` segment of `/agents/:id/...` paths.
+fn agent_id_from_path(path: &str) -> Option<&str> {
+    let mut segments = path.split('/').filter(|s| !s.is_empty());
+    segments.find(|s| *s == "agents")?;
+    segments.next()
+}
+
+/// Access log middleware. Assigns every request a UUID, runs the rest of the stack inside
+/// an `http.request` span carrying it, returns it as `X-Request-Id` and logs one
+/// structured event per request once the response is ready.
+pub async fn log_requests(req: Request<Body>, next: Next) -> Response {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let agent_id = agent_id_from_path(&path).map(str::to_string);
+
+    let span = tracing::info_span!("http.request", request_id = %request_id, method = %method, path = %path);
+    let started = Instant::now();
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.run(req))
+        .instrument(span.clone())
+        .await;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    span.in_scope(|| {
+        tracing::info!(
+            request_id = %request_id,
+            method = %method,
+            path = %path,
+            status_code = response.status().as_u16(),
+            latency_ms,
+            agent_id_from_path = agent_id.as_deref(),
+            "http request"
+        );
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::error::ProblemDetails;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::Router;
+    use std::sync::{Arc, Mutex};
+    use tower::Service;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    type RecordedEvent = Vec<(String, String)>;
+
+    /// Collects the fields of every event as name/value strings.
+    #[derive(Clone, Default)]
+    struct EventRecorder(Arc<Mutex<Vec<RecordedEvent>>>);
+
+    struct FieldVisitor<'a>(&'a mut Vec<(String, String)>);
+
+    impl tracing::field::Visit for FieldVisitor<'_> {
+        fn record_f64(&mut self, field: &tracing::field::Field, value: f64) {
+            self.0.push((field.name().to_string(), value.to_string()));
+        }
+        fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+            self.0.push((field.name().to_string(), value.to_string()));
+        }
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.push((field.name().to_string(), value.to_string()));
+        }
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for EventRecorder {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+            let mut fields = Vec::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            self.0.lock().unwrap().push(fields);
+        }
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/agents/:id", get(|| async {
+                tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+                "ok"
+            }))
+            .route("/missing", get(|| async {
+                ProblemDetails::new(StatusCode::NOT_FOUND, "Not Found", "nothing here").into_response()
+            }))
+            .layer(axum::middleware::from_fn(log_requests))
+    }
+
+    #[test]
+    fn agent_id_is_taken_from_agent_paths_only() {
+        assert_eq!(agent_id_from_path("/agents/alpha/pause"), Some("alpha"));
+        assert_eq!(agent_id_from_path("/agents"), None);
+        assert_eq!(agent_id_from_path("/missions/m1"), None);
+    }
+
+    #[tokio::test]
+    async fn request_is_logged_with_id_header_and_latency() {
+        let recorder = EventRecorder::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+
+        let req = Request::builder().uri("/agents/alpha").body(Body::empty()).unwrap();
+        let response = app().call(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let request_id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&request_id).is_ok());
+
+        let events = recorder.0.lock().unwrap().clone();
+        let event = events.iter()
+            .find(|fields| fields.iter().any(|(k, v)| k == "message" && v == "http request"))
+            .expect("access log event");
+        let field = |name: &str| event.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone());
+        assert_eq!(field("request_id"), Some(request_id));
+        assert_eq!(field("method").as_deref(), Some("GET"));
+        assert_eq!(field("path").as_deref(), Some("/agents/alpha"));
+        assert_eq!(field("status_code").as_deref(), Some("200"));
+        assert_eq!(field("agent_id_from_path").as_deref(), Some("alpha"));
+        let latency: f64 = field("latency_ms").unwrap().parse().unwrap();
+        assert!(latency > 0.0, "latency was {}", latency);
+    }
+
+    #[tokio::test]
+    async fn problem_details_carry_the_request_id() {
+        let req = Request::builder().uri("/missing").body(Body::empty()).unwrap();
+        let response = app().call(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let request_id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["instance"], request_id);
+
+        // Outside a request there is nothing to correlate with
+        assert!(ProblemDetails::new(StatusCode::NOT_FOUND, "Not Found", "x").instance.is_none());
+    }
+}
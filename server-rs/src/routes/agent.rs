@@ -1,24 +1,104 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
 use std::sync::Arc;
 use crate::{
-    agent::{runner::AgentRunner, types::{EngineAgent, TaskPayload}},
+    agent::{capabilities::SkillDefinition, runner::AgentRunner, types::{EngineAgent, TaskPayload}},
     state::AppState,
     routes::error::ProblemDetails,
 };
 
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct AgentListQuery {
+    /// Also return soft-deleted agents (with `deletedAt`) so they can be recovered.
+    pub include_deleted: Option<bool>,
+}
+
+/// A soft-deleted agent as listed by `GET /agents?include_deleted=true`.
+#[derive(serde::Serialize)]
+struct DeletedAgent {
+    #[serde(flatten)]
+    agent: EngineAgent,
+    #[serde(rename = "deletedAt")]
+    deleted_at: String,
+}
+
 /// GET /agents endpoint.
 /// Serves the current state of all agents from the DashMap.
-pub async fn get_agents(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+pub async fn get_agents(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AgentListQuery>,
+) -> impl IntoResponse {
     let agents: Vec<EngineAgent> = state.agents.iter().map(|kv| kv.value().clone()).collect();
-    Json(agents)
+    if !params.include_deleted.unwrap_or(false) {
+        return Json(agents).into_response();
+    }
+
+    match crate::agent::persistence::load_deleted_agents_db(&state.pool).await {
+        Ok(deleted) => {
+            let mut listed: Vec<serde_json::Value> = agents.iter()
+                .filter_map(|agent| serde_json::to_value(agent).ok())
+                .collect();
+            listed.extend(deleted.into_iter()
+                .filter_map(|(agent, deleted_at)| serde_json::to_value(DeletedAgent { agent, deleted_at }).ok()));
+            Json(listed).into_response()
+        }
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Agent Query Failed",
+            format!("Deleted agents could not be read: {}", e)
+        ).into_response(),
+    }
+}
+
+/// DELETE /agents/:id endpoint.
+/// Soft-deletes an agent: the row is kept with `deleted_at` set and the agent leaves the live
+/// registry. Agents that are mid-mission must be paused first.
+pub async fn delete_agent(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let status = match state.agents.get(&agent_id) {
+        Some(entry) => entry.status.clone(),
+        None => return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot delete agent '{}' because it does not exist.", agent_id)
+        ).into_response(),
+    };
+
+    if status == "thinking" || status == "active" {
+        return ProblemDetails::new(
+            StatusCode::CONFLICT,
+            "Agent Busy",
+            format!("Agent '{}' is {}. Pause it before deleting.", agent_id, status)
+        ).into_response();
+    }
+
+    if let Err(e) = crate::agent::persistence::soft_delete_agent_db(&state.pool, &agent_id).await {
+        return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Agent Deletion Failed",
+            format!("Agent '{}' could not be deleted: {}", agent_id, e)
+        ).into_response();
+    }
+    state.agents.remove(&agent_id);
+    state.agent_rate_overrides.remove(&agent_id);
+    tracing::info!("🗑️ [Registry] Agent {} deleted", agent_id);
+
+    state.emit_event(serde_json::json!({
+        "type": "agent:deleted",
+        "agentId": agent_id
+    }));
+
+    Json(serde_json::json!({ "status": "deleted", "agentId": agent_id })).into_response()
 }
 
 /// POST /agents/:id/send endpoint.
+/// A task sent while the agent is still running an earlier one waits its turn; `queued` says so.
 pub async fn send_task(
     Path(agent_id): Path<String>,
     State(state): State<Arc<AppState>>,
@@ -26,7 +106,11 @@ pub async fn send_task(
 ) -> impl IntoResponse {
     tracing::info!("📡 [Gateway] Received Task '{}' for Agent {}", payload.message, agent_id);
     
-    // Verify agent exists
+    if state.is_shutting_down.load(std::sync::atomic::Ordering::SeqCst) {
+        return crate::routes::engine_control::engine_shutting_down();
+    }
+
+    // Verify agent exists; deleted agents have already left the registry
     if !state.agents.contains_key(&agent_id) {
         tracing::warn!("⚠️ Agent {} not found in registry.", agent_id);
         return ProblemDetails::new(
@@ -36,11 +120,24 @@ pub async fn send_task(
         ).into_response();
     }
 
+    // Tasks for one agent queue behind each other. Swarm spawns run the runner directly and never
+    // take this lock, so an agent delegating to itself or its parent cannot deadlock.
+    let task_lock = state.agent_task_lock(&agent_id);
+    let queued = task_lock.try_lock().is_err();
+    if queued {
+        tracing::info!("⏳ [Gateway] Agent {} is busy; task queued", agent_id);
+    }
+
     // Spawn Agent process asynchronously 
     let agent_id_for_spawn = agent_id.clone();
     tokio::spawn(async move {
+        let _turn = task_lock.lock().await;
         let runner = AgentRunner::new(state.clone());
-        if let Err(e) = runner.run(agent_id_for_spawn.clone(), payload).await {
+        let result = match runner.prepare_mission(&agent_id_for_spawn, &payload).await {
+            Ok(mission_id) => runner.run_mission_with_timeout(agent_id_for_spawn.clone(), payload, mission_id).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
             tracing::error!("❌ [Runner] Agent {} task failed: {}", agent_id_for_spawn, e);
         }
     });
@@ -49,7 +146,194 @@ pub async fn send_task(
         StatusCode::ACCEPTED,
         Json(serde_json::json!({
             "status": "accepted",
-            "agentId": agent_id
+            "agentId": agent_id,
+            "queued": queued
+        }))
+    ).into_response()
+}
+
+/// Output tokens assumed for the upper estimate when the model sets no `max_tokens`.
+const DEFAULT_ESTIMATE_MAX_OUTPUT_TOKENS: u32 = 4096;
+
+/// POST /agents/:id/estimate endpoint.
+/// Prices a task before it runs: assembles the system prompt the agent would use and estimates
+/// input tokens from it and the message. Nothing is spawned and no state is modified.
+pub async fn estimate_task(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<TaskPayload>,
+) -> impl IntoResponse {
+    if !state.agents.contains_key(&agent_id) {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot estimate a task because agent '{}' does not exist in the registry.", agent_id)
+        ).into_response();
+    }
+
+    let runner = AgentRunner::new(state.clone());
+    let (model_config, input_tokens) = match runner.estimate_input(&agent_id, &payload).await {
+        Ok(estimate) => estimate,
+        Err(e) => return ProblemDetails::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Estimate Failed",
+            format!("The task for agent '{}' could not be resolved: {}", agent_id, e)
+        ).into_response(),
+    };
+
+    let max_output_tokens = model_config.max_tokens.unwrap_or(DEFAULT_ESTIMATE_MAX_OUTPUT_TOKENS);
+    let model_id = model_config.model_id;
+    Json(serde_json::json!({
+        "estimated_input_tokens": input_tokens,
+        "estimated_min_cost_usd": crate::agent::rates::calculate_cost(&model_id, input_tokens, 0),
+        "estimated_max_cost_usd": crate::agent::rates::calculate_cost(&model_id, input_tokens, max_output_tokens),
+        "model_id": model_id
+    })).into_response()
+}
+
+/// Maximum number of agents a single batch submission may target.
+const MAX_BATCH_AGENTS: usize = 10;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct BatchMissionRequest {
+    #[serde(rename = "agentIds")]
+    pub agent_ids: Vec<String>,
+    pub payload: TaskPayload,
+}
+
+/// POST /missions/batch endpoint.
+/// Submits the same task to several agents. Each mission runs independently,
+/// so a rejection or failure for one agent never affects the others.
+pub async fn submit_batch(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BatchMissionRequest>,
+) -> impl IntoResponse {
+    if state.is_shutting_down.load(std::sync::atomic::Ordering::SeqCst) {
+        return crate::routes::engine_control::engine_shutting_down();
+    }
+    if req.agent_ids.len() > MAX_BATCH_AGENTS {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Batch Too Large",
+            format!("A batch may target at most {} agents ({} requested).", MAX_BATCH_AGENTS, req.agent_ids.len())
+        ).into_response();
+    }
+
+    tracing::info!("📡 [Gateway] Received batch task '{}' for {} agents", req.payload.message, req.agent_ids.len());
+
+    let mut submitted = Vec::new();
+    let mut rejected = Vec::new();
+
+    for agent_id in req.agent_ids {
+        if !state.agents.contains_key(&agent_id) {
+            rejected.push(serde_json::json!({ "agentId": agent_id, "reason": "not found" }));
+            continue;
+        }
+
+        // Create the mission up front so its ID can be returned to the caller
+        let runner = AgentRunner::new(state.clone());
+        let mission_id = match runner.prepare_mission(&agent_id, &req.payload).await {
+            Ok(id) => id,
+            Err(e) => {
+                rejected.push(serde_json::json!({ "agentId": agent_id, "reason": e.to_string() }));
+                continue;
+            }
+        };
+
+        let payload = req.payload.clone();
+        let agent_id_for_spawn = agent_id.clone();
+        let mission_id_for_spawn = mission_id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = runner.run_mission_with_timeout(agent_id_for_spawn.clone(), payload, mission_id_for_spawn).await {
+                tracing::error!("❌ [Runner] Agent {} batch task failed: {}", agent_id_for_spawn, e);
+            }
+        });
+
+        submitted.push(serde_json::json!({ "agentId": agent_id, "missionId": mission_id }));
+    }
+
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "submitted": submitted,
+            "rejected": rejected
+        }))
+    ).into_response()
+}
+
+/// POST /agents/department/:dept/broadcast endpoint.
+/// Sends the same task to every agent in a department. Agents already `thinking` or `active`
+/// are skipped so a broadcast never double-dispatches a busy agent. A member whose mission
+/// fails to start is returned to `idle`.
+pub async fn broadcast_to_department(
+    Path(department): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<TaskPayload>,
+) -> impl IntoResponse {
+    if state.is_shutting_down.load(std::sync::atomic::Ordering::SeqCst) {
+        return crate::routes::engine_control::engine_shutting_down();
+    }
+
+    let members: Vec<String> = state.agents.iter()
+        .filter(|kv| kv.value().department == department)
+        .map(|kv| kv.key().clone())
+        .collect();
+
+    // Reject an invalid task before any member is claimed
+    let runner = AgentRunner::new(state.clone());
+    if let Some(e) = members.iter().find_map(|agent_id| runner.validate_input(agent_id, &payload).err()) {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid Task",
+            format!("The broadcast to department '{}' was rejected: {}", department, e)
+        ).into_response();
+    }
+
+    let mut accepted = Vec::new();
+    let mut skipped = Vec::new();
+
+    for agent_id in members {
+        // Check and claim under the same entry lock so concurrent broadcasts cannot both win
+        let claimed = match state.agents.get_mut(&agent_id) {
+            Some(mut entry) if entry.status != "thinking" && entry.status != "active" => {
+                entry.status = "thinking".to_string();
+                true
+            }
+            _ => false,
+        };
+        if !claimed {
+            skipped.push(agent_id);
+            continue;
+        }
+
+        let runner = AgentRunner::new(state.clone());
+        let payload = payload.clone();
+        let agent_id_for_spawn = agent_id.clone();
+        tokio::spawn(async move {
+            let result = match runner.prepare_mission(&agent_id_for_spawn, &payload).await {
+                Ok(mission_id) => runner.run_mission_with_timeout(agent_id_for_spawn.clone(), payload, mission_id).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::error!("❌ [Runner] Agent {} broadcast task failed: {}", agent_id_for_spawn, e);
+                runner.release_claim(&agent_id_for_spawn);
+            }
+        });
+        accepted.push(agent_id);
+    }
+
+    tracing::info!("📡 [Gateway] Broadcast '{}' to {} agents in {} ({} skipped)", payload.message, accepted.len(), department, skipped.len());
+    state.emit_event(serde_json::json!({
+        "type": "swarm:broadcast",
+        "department": department,
+        "agentCount": accepted.len()
+    }));
+
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "accepted": accepted,
+            "skipped": skipped
         }))
     ).into_response()
 }
@@ -62,19 +346,151 @@ pub async fn create_agent(
 ) -> impl IntoResponse {
     tracing::info!("🆕 [Registry] Creating New Agent {}: {}", new_agent.id, new_agent.name);
 
-    state.agents.insert(new_agent.id.clone(), new_agent.clone());
+    let agent_id = new_agent.id.clone();
+    state.register_agent(new_agent).await;
+    
+    (StatusCode::CREATED, Json(serde_json::json!({ "status": "ok", "agentId": agent_id })))
+}
+
+/// Most agents one `POST /agents/batch` may create.
+const MAX_AGENT_CREATE_BATCH: usize = 50;
+
+/// POST /agents/batch endpoint.
+/// Creates up to 50 agents in one SQLite transaction: either every new agent is saved or none is.
+/// Agents whose ID is already registered are skipped; an ID repeated within the batch rejects it whole.
+/// Emits one `agents:batch_created` event.
+pub async fn batch_create_agents(
+    State(state): State<Arc<AppState>>,
+    Json(agents): Json<Vec<EngineAgent>>,
+) -> impl IntoResponse {
+    if agents.len() > MAX_AGENT_CREATE_BATCH {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Batch Too Large",
+            format!("A batch may create at most {} agents ({} submitted).", MAX_AGENT_CREATE_BATCH, agents.len())
+        ).into_response();
+    }
+    if agents.iter().any(|agent| agent.id.trim().is_empty()) {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid Agent Batch",
+            "Every agent in the batch needs a non-empty `id`.".to_string()
+        ).into_response();
+    }
+    let mut seen = std::collections::HashSet::new();
+    let mut duplicates: Vec<String> = agents.iter().filter(|agent| !seen.insert(agent.id.as_str())).map(|agent| agent.id.clone()).collect();
+    if !duplicates.is_empty() {
+        duplicates.sort();
+        duplicates.dedup();
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Duplicate Agent IDs",
+            format!("The batch lists these IDs more than once, so no agent was created: {}", duplicates.join(", "))
+        ).into_response();
+    }
+
+    // Reserve the new IDs so a concurrent create cannot claim one mid-batch
+    let mut created = Vec::new();
+    let mut skipped = Vec::new();
+    for agent in agents {
+        match state.agents.entry(agent.id.clone()) {
+            dashmap::mapref::entry::Entry::Occupied(_) => skipped.push(agent.id),
+            dashmap::mapref::entry::Entry::Vacant(slot) => { created.push(slot.insert(agent).clone()); }
+        }
+    }
+
+    let persisted: anyhow::Result<()> = async {
+        let mut tx = state.pool.begin().await?;
+        for agent in &created {
+            crate::agent::persistence::create_agent_db(&mut *tx, agent).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }.await;
+
+    if let Err(e) = persisted {
+        for agent in &created {
+            state.agents.remove(&agent.id);
+        }
+        return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Agent Persistence Failed",
+            format!("The batch was rolled back and no agent was created: {}", e)
+        ).into_response();
+    }
+
+    let created: Vec<String> = created.into_iter().map(|agent| agent.id).collect();
+    tracing::info!("🆕 [Registry] Batch created {} agents ({} skipped)", created.len(), skipped.len());
+    state.emit_event(serde_json::json!({
+        "type": "agents:batch_created",
+        "agentIds": created
+    }));
+
+    (StatusCode::CREATED, Json(serde_json::json!({ "created": created, "skipped": skipped }))).into_response()
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct CloneAgentRequest {
+    #[serde(rename = "newId")]
+    pub new_id: Option<String>,
+    #[serde(rename = "newName")]
+    pub new_name: Option<String>,
+}
+
+/// POST /agents/:id/clone endpoint.
+/// Duplicates an agent's configuration under a new ID with fresh usage counters.
+/// The body is optional; a UUID is generated when `newId` is omitted.
+pub async fn clone_agent(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    body: Option<Json<CloneAgentRequest>>,
+) -> impl IntoResponse {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+
+    let Some(mut clone) = state.agents.get(&agent_id).map(|entry| entry.value().clone()) else {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot clone agent '{}' because it does not exist in the registry.", agent_id)
+        ).into_response();
+    };
+
+    let new_id = req.new_id.filter(|id| !id.trim().is_empty()).unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    clone.id = new_id.clone();
+    clone.name = req.new_name.filter(|n| !n.trim().is_empty()).unwrap_or_else(|| format!("{} (Copy)", clone.name));
+    clone.cost_usd = 0.0;
+    clone.tokens_used = 0;
+    clone.token_usage = Default::default();
+    clone.status = "idle".to_string();
+    clone.active_mission = None;
+
+    // Reserve the ID atomically so two concurrent clones cannot claim the same one
+    match state.agents.entry(new_id.clone()) {
+        dashmap::mapref::entry::Entry::Occupied(_) => return ProblemDetails::new(
+            StatusCode::CONFLICT,
+            "Agent Already Exists",
+            format!("An agent with ID '{}' already exists.", new_id)
+        ).into_response(),
+        dashmap::mapref::entry::Entry::Vacant(slot) => { slot.insert(clone.clone()); }
+    }
+
+    if let Err(e) = crate::agent::persistence::create_agent_db(&state.pool, &clone).await {
+        state.agents.remove(&new_id);
+        return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Agent Persistence Failed",
+            format!("The clone of '{}' could not be saved: {}", agent_id, e)
+        ).into_response();
+    }
 
-    // Broadcast the creation to all UIs instantly
+    tracing::info!("🧬 [Registry] Cloned Agent {} as {}", agent_id, new_id);
     state.emit_event(serde_json::json!({
         "type": "agent:create",
-        "agentId": new_agent.id,
-        "data": new_agent
+        "agentId": new_id,
+        "data": clone
     }));
-    
-    // Trigger background persistence
-    state.save_agents().await;
-    
-    (StatusCode::CREATED, Json(serde_json::json!({ "status": "ok", "agentId": new_agent.id })))
+
+    (StatusCode::CREATED, Json(clone)).into_response()
 }
 
 /// PUT /agents/:id endpoint.
@@ -87,6 +503,7 @@ pub async fn update_agent(
     tracing::info!("🔄 [Registry] Updating Agent {}: {:?}", agent_id, update);
 
     if let Some(mut entry) = state.agents.get_mut(&agent_id) {
+        let model_changed = update.model_id.is_some();
         if let Some(name) = update.name { entry.name = name; }
         if let Some(role) = update.role { entry.role = role; }
         if let Some(dept) = update.department { entry.department = dept; }
@@ -102,11 +519,28 @@ pub async fn update_agent(
         if let Some(budget) = update.budget_usd { entry.budget_usd = budget; }
         if let Some(skills) = update.skills { entry.skills = skills; }
         if let Some(workflow) = update.workflows { entry.workflows = workflow; }
+        if let Some(allowlist) = update.skill_allowlist { entry.skill_allowlist = allowlist; }
         if let Some(m2) = update.model2 { entry.model_2 = Some(m2); }
         if let Some(m3) = update.model3 { entry.model_3 = Some(m3); }
         if let Some(active_slot) = update.active_model_slot { entry.active_model_slot = Some(active_slot); }
         if let Some(mc2) = update.model_config2 { entry.model_config2 = Some(mc2); }
         if let Some(mc3) = update.model_config3 { entry.model_config3 = Some(mc3); }
+        if let Some(safety) = update.safety_settings { entry.model.safety_settings = Some(safety); }
+        let rate_override_changed = update.rpm_override.is_some() || update.tpm_override.is_some();
+        if let Some(rpm) = update.rpm_override { entry.rpm_override = Some(rpm).filter(|v| *v > 0); }
+        if let Some(tpm) = update.tpm_override { entry.tpm_override = Some(tpm).filter(|v| *v > 0); }
+        if rate_override_changed || model_changed {
+            // Rebuilt from the new limits on the agent's next call
+            state.agent_rate_overrides.remove(&agent_id);
+        }
+        if rate_override_changed {
+            state.emit_event(serde_json::json!({
+                "type": "agent:rate_override_set",
+                "agentId": agent_id,
+                "rpmOverride": entry.rpm_override,
+                "tpmOverride": entry.tpm_override
+            }));
+        }
         
         // Broadcast the update to all UIs instantly
         state.emit_event(serde_json::json!({
@@ -193,65 +627,1494 @@ pub async fn resume_agent(
     }
 }
 
+/// Zeroes an agent's accumulated spend, records the previous values in `cost_resets`,
+/// and persists the agent. Returns `None` if the agent does not exist.
+async fn reset_agent_cost(state: &AppState, agent_id: &str) -> anyhow::Result<Option<(f64, u32)>> {
+    let (previous, snapshot) = match state.agents.get_mut(agent_id) {
+        Some(mut entry) => {
+            let previous = (entry.cost_usd, entry.tokens_used);
+            entry.cost_usd = 0.0;
+            entry.tokens_used = 0;
+            entry.token_usage = crate::agent::types::TokenUsage::default();
+            (previous, entry.clone())
+        }
+        None => return Ok(None),
+    };
 
+    sqlx::query("INSERT INTO cost_resets (agent_id, previous_cost_usd, previous_tokens_used, reset_at) VALUES (?, ?, ?, ?)")
+        .bind(agent_id)
+        .bind(previous.0)
+        .bind(previous.1 as i64)
+        .bind(chrono::Utc::now())
+        .execute(&state.pool)
+        .await?;
+    crate::agent::persistence::save_agent_db(&state.pool, &snapshot).await?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::state::AppState;
-    use crate::agent::types::{EngineAgent, ModelConfig, TokenUsage};
-    use std::collections::HashMap;
+    state.emit_event(serde_json::json!({
+        "type": "agent:update",
+        "agentId": agent_id,
+        "data": snapshot
+    }));
 
-    #[tokio::test]
-    async fn test_create_agent_handler() {
-        let state = Arc::new(AppState::new().await);
-        let agent_id = "test-new-agent".to_string();
-        
-        let new_agent = EngineAgent {
-            id: agent_id.clone(),
-            name: "Test Agent".to_string(),
-            role: "tester".to_string(),
-            department: "QA".to_string(),
-            description: "Internal Test Node".to_string(),
-            model_id: Some("gpt-4o".to_string()),
-            model: ModelConfig {
-                provider: "openai".to_string(),
-                model_id: "gpt-4o".to_string(),
-                api_key: None,
-                base_url: None,
-                system_prompt: None,
-                temperature: None,
-                max_tokens: None,
-                external_id: None,
-                rpm: None,
-                rpd: None,
-                tpm: None,
-                tpd: None,
-            },
-            model_2: None,
-            model_3: None,
-            model_config2: None,
-            model_config3: None,
-            active_model_slot: None,
-            active_mission: None,
-            status: "idle".to_string(),
-            tokens_used: 0,
-            token_usage: TokenUsage::default(),
-            metadata: HashMap::new(),
-            theme_color: None,
-            budget_usd: 100.0,
-            cost_usd: 0.0,
-            skills: vec!["skill-1".to_string()],
-            workflows: vec!["workflow-1".to_string()],
-        };
+    Ok(Some(previous))
+}
 
-        let response = create_agent(State(state.clone()), Json(new_agent)).await.into_response();
-        
-        assert_eq!(response.status(), axum::http::StatusCode::CREATED);
-        
-        // Verify it was inserted into the registry
-        assert!(state.agents.contains_key(&agent_id));
-        let registered = state.agents.get(&agent_id).unwrap();
-        assert_eq!(registered.name, "Test Agent");
+/// POST /agents/:id/reset-cost endpoint.
+/// Rolls an agent over into a new billing period.
+pub async fn reset_cost(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    tracing::info!("💸 [Finance] Resetting cost counters for Agent {}", agent_id);
+
+    match reset_agent_cost(&state, &agent_id).await {
+        Ok(Some((previous_cost, previous_tokens))) => Json(serde_json::json!({
+            "status": "ok",
+            "agentId": agent_id,
+            "previousCostUsd": previous_cost,
+            "previousTokensUsed": previous_tokens
+        })).into_response(),
+        Ok(None) => ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot reset cost because agent '{}' does not exist.", agent_id)
+        ).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Cost Reset Failed",
+            format!("Failed to reset cost for agent '{}': {}", agent_id, e)
+        ).into_response(),
+    }
+}
+
+/// POST /agents/reset-cost-all endpoint.
+/// Resets cost counters for every registered agent.
+pub async fn reset_cost_all(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let agent_ids: Vec<String> = state.agents.iter().map(|kv| kv.key().clone()).collect();
+    tracing::info!("💸 [Finance] Resetting cost counters for {} agents", agent_ids.len());
+
+    let mut reset = 0usize;
+    for agent_id in &agent_ids {
+        match reset_agent_cost(&state, agent_id).await {
+            Ok(Some(_)) => reset += 1,
+            Ok(None) => {}
+            Err(e) => return ProblemDetails::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Cost Reset Failed",
+                format!("Failed to reset cost for agent '{}' after {} successful resets: {}", agent_id, reset, e)
+            ).into_response(),
+        }
+    }
+
+    Json(serde_json::json!({ "status": "ok", "reset": reset })).into_response()
+}
+
+/// GET /agents/:id/cost-resets endpoint.
+/// Returns the billing rollover history for an agent, newest first.
+pub async fn get_cost_resets(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    use sqlx::Row;
+
+    let rows = match sqlx::query("SELECT previous_cost_usd, previous_tokens_used, reset_at FROM cost_resets WHERE agent_id = ? ORDER BY id DESC")
+        .bind(&agent_id)
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(rows) => rows,
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Cost Reset History Unavailable",
+            format!("Failed to load cost reset history for agent '{}': {}", agent_id, e)
+        ).into_response(),
+    };
+
+    let history: Vec<serde_json::Value> = rows.iter().map(|r| serde_json::json!({
+        "previousCostUsd": r.get::<f64, _>("previous_cost_usd"),
+        "previousTokensUsed": r.get::<i64, _>("previous_tokens_used"),
+        "resetAt": r.get::<chrono::DateTime<chrono::Utc>, _>("reset_at")
+    })).collect();
+
+    Json(history).into_response()
+}
+
+/// Largest amount a single `POST /agents/:id/budget` may set or add, to catch typos.
+const MAX_BUDGET_CHANGE_USD: f64 = 10_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetOperation {
+    /// Replace `budget_usd` with `amount`.
+    Set,
+    /// Increase `budget_usd` by `amount`.
+    Add,
+    /// Zero `cost_usd` for a new billing period; `amount` is ignored.
+    ResetCost,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct BudgetUpdateRequest {
+    pub operation: BudgetOperation,
+    pub amount: Option<f64>,
+}
+
+/// POST /agents/:id/budget endpoint.
+/// Tops up or replaces an agent's budget, or resets its spend, without a restart.
+/// Applies even while the agent is mid-mission so a budget-paused mission can be resumed.
+/// Emits `agent:budget_updated`.
+pub async fn update_budget(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BudgetUpdateRequest>,
+) -> impl IntoResponse {
+    let amount = match (req.operation, req.amount) {
+        (BudgetOperation::ResetCost, _) => 0.0,
+        (_, Some(amount)) if amount > 0.0 && amount <= MAX_BUDGET_CHANGE_USD => amount,
+        (_, amount) => return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid Budget Amount",
+            format!("'amount' must be greater than 0 and at most {} (got {:?}).", MAX_BUDGET_CHANGE_USD, amount)
+        ).into_response(),
+    };
+
+    let busy = match state.agents.get(&agent_id) {
+        Some(agent) => agent.status == "thinking" || agent.status == "active" || agent.active_mission.is_some(),
+        None => return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot update the budget because agent '{}' does not exist.", agent_id)
+        ).into_response(),
+    };
+    if busy {
+        tracing::warn!("💸 [Finance] Changing the budget of Agent {} while it is mid-mission ({:?}).", agent_id, req.operation);
+    }
+
+    let snapshot = if req.operation == BudgetOperation::ResetCost {
+        if let Err(e) = reset_agent_cost(&state, &agent_id).await {
+            return ProblemDetails::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Cost Reset Failed",
+                format!("Failed to reset cost for agent '{}': {}", agent_id, e)
+            ).into_response();
+        }
+        state.agents.get(&agent_id).map(|a| a.clone())
+    } else {
+        let snapshot = state.agents.get_mut(&agent_id).map(|mut entry| {
+            match req.operation {
+                BudgetOperation::Add => entry.budget_usd += amount,
+                _ => entry.budget_usd = amount,
+            }
+            entry.clone()
+        });
+        if let Some(agent) = &snapshot {
+            if let Err(e) = crate::agent::persistence::save_agent_db(&state.pool, agent).await {
+                return ProblemDetails::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Budget Update Failed",
+                    format!("The budget of agent '{}' could not be persisted: {}", agent_id, e)
+                ).into_response();
+            }
+        }
+        snapshot
+    };
+    // The agent can only vanish here if it was deleted concurrently
+    let Some(agent) = snapshot else {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot update the budget because agent '{}' does not exist.", agent_id)
+        ).into_response();
+    };
+
+    tracing::info!("💸 [Finance] Agent {} budget {:?}: budget ${:.2}, spent ${:.4}", agent_id, req.operation, agent.budget_usd, agent.cost_usd);
+    state.emit_event(serde_json::json!({
+        "type": "agent:budget_updated",
+        "agentId": agent_id,
+        "budgetUsd": agent.budget_usd,
+        "costUsd": agent.cost_usd
+    }));
+
+    Json(serde_json::json!({
+        "agent_id": agent_id,
+        "new_budget_usd": agent.budget_usd,
+        "cost_usd": agent.cost_usd,
+        "headroom_usd": (agent.budget_usd - agent.cost_usd).max(0.0)
+    })).into_response()
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SwitchModelRequest {
+    pub slot: i32,
+}
+
+/// POST /agents/:id/switch-model endpoint.
+/// Makes model slot 1, 2 or 3 the one the agent's next task runs on.
+/// Refused with `409` while the agent is `thinking`, and with `400` for an empty slot.
+/// Emits `agent:model_switched`.
+pub async fn switch_model(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SwitchModelRequest>,
+) -> impl IntoResponse {
+    // Checked and applied under one entry lock so a task cannot start in between
+    let (snapshot, old_model) = {
+        let Some(mut entry) = state.agents.get_mut(&agent_id) else {
+            return ProblemDetails::new(
+                StatusCode::NOT_FOUND,
+                "Agent Not Found",
+                format!("Cannot switch models because agent '{}' does not exist.", agent_id)
+            ).into_response();
+        };
+        let slot_model = match req.slot {
+            1 => entry.model_id.clone(),
+            2 => entry.model_2.clone(),
+            3 => entry.model_3.clone(),
+            slot => return ProblemDetails::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid Model Slot",
+                format!("'slot' must be 1, 2 or 3 (got {}).", slot)
+            ).into_response(),
+        };
+        if slot_model.as_deref().is_none_or(|m| m.trim().is_empty()) {
+            return ProblemDetails::new(
+                StatusCode::BAD_REQUEST,
+                "Empty Model Slot",
+                format!("Agent '{}' has no model assigned to slot {}.", agent_id, req.slot)
+            ).into_response();
+        }
+        if entry.status == "thinking" {
+            return ProblemDetails::new(
+                StatusCode::CONFLICT,
+                "Agent Busy",
+                format!("Agent '{}' is mid-call. Switch models once it is idle.", agent_id)
+            ).into_response();
+        }
+        let old_model = entry.active_model_id();
+        entry.active_model_slot = Some(req.slot);
+        (entry.clone(), old_model)
+    };
+
+    if let Err(e) = crate::agent::persistence::save_agent_db(&state.pool, &snapshot).await {
+        return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Model Switch Failed",
+            format!("The model slot of agent '{}' could not be persisted: {}", agent_id, e)
+        ).into_response();
+    }
+
+    let new_model = snapshot.active_model_id();
+    tracing::info!("🔀 [Agent] {} switched to slot {} ({:?} -> {:?})", agent_id, req.slot, old_model, new_model);
+    state.emit_event(serde_json::json!({
+        "type": "agent:model_switched",
+        "agentId": agent_id,
+        "slot": req.slot,
+        "oldModelId": old_model,
+        "newModelId": new_model
+    }));
+
+    Json(snapshot).into_response()
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct AgentMissionsQuery {
+    pub status: Option<crate::agent::types::MissionStatus>,
+    /// 1-based page number.
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// GET /agents/:id/missions endpoint.
+/// Pages through one agent's missions, newest first, in the `GET /missions` format.
+/// `stats` covers all of the agent's missions regardless of `status` or page.
+pub async fn get_agent_missions(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AgentMissionsQuery>,
+) -> impl IntoResponse {
+    if !state.agents.contains_key(&agent_id) {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot list missions because agent '{}' does not exist.", agent_id)
+        ).into_response();
+    }
+
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page
+        .unwrap_or(crate::routes::mission::DEFAULT_PER_PAGE)
+        .clamp(1, crate::routes::mission::MAX_PER_PAGE);
+    let result = crate::agent::mission::list_missions_for_agent(&state.pool, &agent_id, params.status, page, per_page).await;
+    let stats = crate::agent::mission::agent_mission_stats(&state.pool, &agent_id).await;
+
+    match (result, stats) {
+        (Ok(result), Ok(stats)) => Json(serde_json::json!({
+            "missions": result.items,
+            "total": result.total,
+            "page": result.page,
+            "per_page": result.per_page,
+            "stats": stats
+        })).into_response(),
+        (Err(e), _) | (_, Err(e)) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Query Failed",
+            format!("Missions of agent '{}' could not be listed: {}", agent_id, e)
+        ).into_response(),
+    }
+}
+
+/// Entries returned by `GET /agents/:id/transcript` when no `limit` is given.
+pub const DEFAULT_TRANSCRIPT_LIMIT: i64 = 50;
+/// Largest `limit` accepted by `GET /agents/:id/transcript`.
+pub const MAX_TRANSCRIPT_LIMIT: i64 = 10_000;
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct TranscriptQuery {
+    /// Return every log of this mission instead of the agent's most recent ones.
+    pub mission_id: Option<String>,
+    /// Entries to return across all missions when `mission_id` is not given.
+    pub limit: Option<i64>,
+    /// `json` (default) or `text`.
+    pub format: Option<String>,
+}
+
+/// One transcript entry in the JSON format.
+#[derive(serde::Serialize)]
+struct TranscriptEntry<'a> {
+    timestamp: chrono::DateTime<chrono::Utc>,
+    source: &'a str,
+    text: &'a str,
+    severity: &'a str,
+    metadata: &'a Option<serde_json::Value>,
+}
+
+/// One transcript entry in the text format: `<timestamp> [SOURCE] text`.
+fn transcript_line(log: &crate::agent::types::MissionLog) -> String {
+    format!("{} [{}] {}\n", log.timestamp.to_rfc3339(), log.source.to_uppercase(), log.text)
+}
+
+/// GET /agents/:id/transcript endpoint.
+/// Streams the agent's message flow, oldest first, as a JSON array or as a plain-text log.
+/// With `mission_id` the whole mission is returned; otherwise the agent's last `limit` logs.
+/// The body is written as rows are read, so a database error mid-stream truncates it.
+pub async fn get_agent_transcript(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TranscriptQuery>,
+) -> impl IntoResponse {
+    use futures::StreamExt;
+    use crate::agent::mission::TranscriptScope;
+
+    let format = params.format.as_deref().unwrap_or("json").to_lowercase();
+    if format != "json" && format != "text" {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Unsupported Transcript Format",
+            format!("Format '{}' is not supported. Use 'json' or 'text'.", format)
+        ).into_response();
+    }
+    if !state.agents.contains_key(&agent_id) {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot read the transcript because agent '{}' does not exist.", agent_id)
+        ).into_response();
+    }
+
+    let scope = match params.mission_id {
+        Some(mission_id) => match crate::agent::mission::get_mission_by_id(&state.pool, &mission_id).await {
+            Ok(Some(_)) => TranscriptScope::Mission(mission_id),
+            Ok(None) => return ProblemDetails::new(
+                StatusCode::NOT_FOUND,
+                "Mission Not Found",
+                format!("Mission '{}' does not exist.", mission_id)
+            ).into_response(),
+            Err(e) => return ProblemDetails::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Mission Query Failed",
+                format!("Mission '{}' could not be loaded: {}", mission_id, e)
+            ).into_response(),
+        },
+        None => TranscriptScope::Agent {
+            agent_id,
+            limit: params.limit.unwrap_or(DEFAULT_TRANSCRIPT_LIMIT).clamp(1, MAX_TRANSCRIPT_LIMIT),
+        },
+    };
+
+    let entries = crate::agent::mission::stream_transcript(state.pool.clone(), scope);
+    if format == "text" {
+        let body = entries.map(|entry| entry.map(|log| transcript_line(&log)));
+        return (
+            [(axum::http::header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            axum::body::Body::from_stream(body),
+        ).into_response();
+    }
+
+    let mut first = true;
+    let items = entries.map(move |entry| entry.map(|log| {
+        let json = serde_json::to_string(&TranscriptEntry {
+            timestamp: log.timestamp,
+            source: &log.source,
+            text: &log.text,
+            severity: &log.severity,
+            metadata: &log.metadata,
+        }).unwrap_or_default();
+        let separator = if std::mem::take(&mut first) { "" } else { "," };
+        format!("{}{}", separator, json)
+    }));
+    let body = futures::stream::once(async { Ok("[".to_string()) })
+        .chain(items)
+        .chain(futures::stream::once(async { Ok("]".to_string()) }));
+    (
+        [(axum::http::header::CONTENT_TYPE, "application/json")],
+        axum::body::Body::from_stream(body),
+    ).into_response()
+}
+
+/// POST /agents/:id/generate-description endpoint.
+/// Drafts a description with the agent's configured model. Nothing is persisted —
+/// the operator reviews the text and saves it via `PUT /agents/:id`.
+pub async fn generate_description(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    if !state.agents.contains_key(&agent_id) {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot generate a description because agent '{}' does not exist.", agent_id)
+        ).into_response();
+    }
+
+    let runner = AgentRunner::new(state.clone());
+    match runner.generate_agent_description(&agent_id).await {
+        Ok(description) => Json(serde_json::json!({
+            "agentId": agent_id,
+            "description": description
+        })).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::BAD_GATEWAY,
+            "Description Generation Failed",
+            format!("The provider for agent '{}' could not generate a description: {}", agent_id, e)
+        ).into_response(),
+    }
+}
+
+/// Builds the resolved-capabilities response. Unregistered names turn it into a 404 problem
+/// that still carries the definitions that did resolve, plus the missing names under `missing_key`.
+fn resolved_capabilities_response<T: serde::Serialize>(
+    agent_id: &str,
+    kind: &str,
+    resolved: Vec<T>,
+    missing_key: &str,
+    missing: Vec<String>,
+) -> axum::response::Response {
+    if missing.is_empty() {
+        return Json(serde_json::json!({ "agentId": agent_id, kind: resolved })).into_response();
+    }
+
+    ProblemDetails::new(
+        StatusCode::NOT_FOUND,
+        "Unregistered Capabilities",
+        format!("Agent '{}' references {} that are not registered: {}", agent_id, kind, missing.join(", "))
+    )
+    .with_extension(kind, serde_json::json!(resolved))
+    .with_extension(missing_key, serde_json::json!(missing))
+    .into_response()
+}
+
+/// GET /agents/:id/skills endpoint.
+/// Resolves the agent's skill names to full definitions. Execution commands are masked.
+/// Built-in skills without a registry file resolve to a stub definition tagged `native`.
+pub async fn get_agent_skills(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let Some(skill_names) = state.agents.get(&agent_id).map(|a| a.skills.clone()) else {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot list skills because agent '{}' does not exist.", agent_id)
+        ).into_response();
+    };
+
+    let mut resolved = Vec::new();
+    let mut missing = Vec::new();
+    for name in skill_names {
+        match state.capabilities.skill(&name) {
+            Some(mut skill) => {
+                skill.execution_command = "[hidden]".to_string();
+                resolved.push(skill);
+            }
+            None if crate::agent::runner::is_native_skill(&name) => resolved.push(SkillDefinition {
+                id: None,
+                description: format!("Built-in engine skill '{}'.", name),
+                name,
+                execution_command: "[hidden]".to_string(),
+                schema: serde_json::json!({}),
+                doc_url: None,
+                tags: Some(vec!["native".to_string()]),
+                enabled: true,
+                skill_cpu_limit_secs: None,
+                skill_memory_limit_mb: None,
+            }),
+            None => missing.push(name),
+        }
+    }
+
+    resolved_capabilities_response(&agent_id, "skills", resolved, "missingSkills", missing)
+}
+
+/// GET /agents/:id/workflows endpoint.
+/// Resolves the agent's workflow names to full definitions.
+pub async fn get_agent_workflows(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let Some(workflow_names) = state.agents.get(&agent_id).map(|a| a.workflows.clone()) else {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot list workflows because agent '{}' does not exist.", agent_id)
+        ).into_response();
+    };
+
+    let mut resolved = Vec::new();
+    let mut missing = Vec::new();
+    for name in workflow_names {
+        match state.capabilities.workflow(&name) {
+            Some(workflow) => resolved.push(workflow),
+            None => missing.push(name),
+        }
+    }
+
+    resolved_capabilities_response(&agent_id, "workflows", resolved, "missingWorkflows", missing)
+}
+
+/// GET /agents/:id/memory endpoint.
+/// Lists the facts the agent kept with `remember_fact`, excluding expired ones.
+pub async fn get_agent_memory(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    if !state.agents.contains_key(&agent_id) {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot list memory because agent '{}' does not exist.", agent_id)
+        ).into_response();
+    }
+
+    match crate::agent::memory::list_facts(&state.pool, &agent_id).await {
+        Ok(facts) => Json(facts).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Agent Memory Unavailable",
+            format!("Failed to load memory for agent '{}': {}", agent_id, e)
+        ).into_response(),
+    }
+}
+
+/// DELETE /agents/:id/memory/:key endpoint.
+/// Makes the agent forget one fact.
+pub async fn delete_agent_memory_fact(
+    Path((agent_id, key)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match crate::agent::memory::forget_fact(&state.pool, &agent_id, &key).await {
+        Ok(true) => Json(serde_json::json!({ "status": "ok", "key": key })).into_response(),
+        Ok(false) => ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Fact Not Found",
+            format!("Agent '{}' has no fact named '{}'.", agent_id, key)
+        ).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Agent Memory Unavailable",
+            format!("Failed to delete fact '{}' of agent '{}': {}", key, agent_id, e)
+        ).into_response(),
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct WorkspaceQuery {
+    /// Directory to list, relative to the workspace root. Defaults to `.`.
+    pub dir: Option<String>,
+    /// Walk the whole tree (up to 1000 entries, 5 levels) instead of one directory.
+    pub recursive: Option<bool>,
+    /// Workspace to inspect; the same `clusterId` missions run in. Defaults to `executive-core`.
+    pub cluster_id: Option<String>,
+}
+
+/// One entry of `GET /agents/:id/workspace`.
+#[derive(serde::Serialize)]
+struct WorkspaceEntry {
+    path: String,
+    is_dir: bool,
+    size_bytes: u64,
+}
+
+/// GET /agents/:id/workspace endpoint.
+/// Lists the files an agent sees with `list_files`, with paths relative to the workspace root.
+pub async fn get_agent_workspace(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<WorkspaceQuery>,
+) -> impl IntoResponse {
+    if !state.agents.contains_key(&agent_id) {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot list the workspace because agent '{}' does not exist.", agent_id)
+        ).into_response();
+    }
+
+    let dir = params.dir.as_deref().unwrap_or(".");
+    let workspace_root = crate::agent::runner::workspace_root_for(params.cluster_id.as_deref());
+    let adapter = crate::adapter::filesystem::FilesystemAdapter::new(workspace_root);
+    match adapter.list_files(dir, params.recursive.unwrap_or(false)).await {
+        Ok(files) => Json(files.into_iter().map(|f| WorkspaceEntry {
+            path: f.path,
+            is_dir: f.is_dir,
+            size_bytes: f.size_bytes,
+        }).collect::<Vec<_>>()).into_response(),
+        Err(e @ crate::agent::types::ToolError::SecurityViolation(_)) => ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid Workspace Path",
+            e.to_string()
+        ).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Workspace Unavailable",
+            format!("Failed to list '{}' for agent '{}': {}", dir, agent_id, e)
+        ).into_response(),
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct TokenStatsQuery {
+    /// `hour` (default), `day` or `week`.
+    pub window: Option<crate::agent::token_stats::TokenWindow>,
+    /// Most recent windows to return. Defaults to 24.
+    pub limit: Option<i64>,
+}
+
+/// GET /agents/:id/token-stats endpoint.
+/// Rolling token usage and cost per window, oldest first, with the spend projected per day
+/// from the last 24 hours. Stats are rolled up hourly, so the current hour may lag behind.
+pub async fn get_agent_token_stats(
+    Path(agent_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TokenStatsQuery>,
+) -> impl IntoResponse {
+    use crate::agent::token_stats::{self, DEFAULT_TOKEN_STATS_LIMIT, MAX_TOKEN_STATS_LIMIT};
+
+    let limit = params.limit.unwrap_or(DEFAULT_TOKEN_STATS_LIMIT);
+    if !(1..=MAX_TOKEN_STATS_LIMIT).contains(&limit) {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid Limit",
+            format!("limit must be between 1 and {}, got {}.", MAX_TOKEN_STATS_LIMIT, limit)
+        ).into_response();
+    }
+    if !state.agents.contains_key(&agent_id) {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot read token stats because agent '{}' does not exist.", agent_id)
+        ).into_response();
+    }
+
+    let window = params.window.unwrap_or_default();
+    let stats = token_stats::list_token_stats(&state.pool, &agent_id, window, limit).await;
+    let burn_rate = token_stats::burn_rate_usd_per_day(&state.pool, &agent_id, chrono::Utc::now()).await;
+    match stats.and_then(|stats| burn_rate.map(|rate| (stats, rate))) {
+        Ok((stats, burn_rate_usd_per_day)) => Json(serde_json::json!({
+            "agent_id": agent_id,
+            "window": window,
+            "stats": stats,
+            "burn_rate_usd_per_day": burn_rate_usd_per_day,
+        })).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Token Stats Unavailable",
+            format!("Failed to load token stats for agent '{}': {}", agent_id, e)
+        ).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AppState;
+    use crate::agent::types::{EngineAgent, ModelConfig, TokenUsage};
+    use std::collections::HashMap;
+
+    fn batch_agent(state: &AppState, id: &str) -> EngineAgent {
+        let mut agent = state.agents.get("1").unwrap().clone();
+        agent.id = id.to_string();
+        agent.name = format!("Batch {}", id);
+        agent
+    }
+
+    async fn agent_in_db(state: &AppState, id: &str) -> bool {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM agents WHERE id = ?").bind(id).fetch_one(&state.pool).await.unwrap() > 0
+    }
+
+    #[tokio::test]
+    async fn test_batch_create_agents_skips_existing_ids() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let mut events = state.event_tx.subscribe();
+        let (a, b) = (format!("batch-{}", uuid::Uuid::new_v4()), format!("batch-{}", uuid::Uuid::new_v4()));
+
+        let batch = vec![batch_agent(&state, &a), batch_agent(&state, "1"), batch_agent(&state, &b)];
+        let response = batch_create_agents(State(state.clone()), Json(batch)).await.into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["created"], serde_json::json!([a, b]));
+        assert_eq!(json["skipped"], serde_json::json!(["1"]));
+
+        assert!(state.agents.contains_key(&a) && agent_in_db(&state, &b).await);
+        assert_ne!(state.agents.get("1").unwrap().name, "Batch 1", "existing agents are left untouched");
+
+        let event = loop {
+            let event = events.recv().await.unwrap();
+            if event["type"] == "agents:batch_created" { break event; }
+        };
+        assert_eq!(event["agentIds"], serde_json::json!([a, b]));
+    }
+
+    #[tokio::test]
+    async fn test_batch_create_agents_rolls_back_on_duplicate_ids() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let (a, dup) = (format!("batch-{}", uuid::Uuid::new_v4()), format!("batch-{}", uuid::Uuid::new_v4()));
+
+        let batch = vec![batch_agent(&state, &a), batch_agent(&state, &dup), batch_agent(&state, &dup)];
+        let response = batch_create_agents(State(state.clone()), Json(batch)).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        for id in [&a, &dup] {
+            assert!(!state.agents.contains_key(id), "{} must not be registered", id);
+            assert!(!agent_in_db(&state, id).await, "{} must not be persisted", id);
+        }
+
+        let oversized: Vec<EngineAgent> = (0..=MAX_AGENT_CREATE_BATCH).map(|i| batch_agent(&state, &format!("{}-{}", a, i))).collect();
+        let response = batch_create_agents(State(state.clone()), Json(oversized)).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_agent_memory_routes_list_and_forget_facts() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let key = format!("route_fact_{}", uuid::Uuid::new_v4().simple());
+        crate::agent::memory::remember_fact(&state.pool, "1", &key, "kept", None).await.unwrap();
+
+        let response = get_agent_memory(Path("1".to_string()), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let facts: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert!(facts.iter().any(|f| f["key"] == key.as_str() && f["value"] == "kept"));
+
+        let response = delete_agent_memory_fact(Path(("1".to_string(), key.clone())), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = delete_agent_memory_fact(Path(("1".to_string(), key)), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = get_agent_memory(Path("no-such-agent".to_string()), State(state)).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_agent_workspace_lists_tree_relative_to_workspace_root() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let workspace = crate::agent::runner::TestWorkspace::new("route-ws");
+        let cluster = workspace.cluster_id.clone();
+        std::fs::create_dir_all(workspace.root.join("src/nested")).unwrap();
+        std::fs::write(workspace.root.join("src/nested/lib.rs"), "pub fn f() {}").unwrap();
+
+        let query = |recursive: bool, dir: &str| WorkspaceQuery { dir: Some(dir.to_string()), recursive: Some(recursive), cluster_id: Some(cluster.clone()) };
+        let response = get_agent_workspace(Path("1".to_string()), State(state.clone()), Query(query(true, "."))).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let entries: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e["path"].as_str().unwrap()).collect();
+        assert_eq!(paths, vec!["src", "src/nested", "src/nested/lib.rs"]);
+        assert_eq!(entries[0]["is_dir"], true);
+        assert_eq!(entries[2]["size_bytes"], 13);
+
+        let response = get_agent_workspace(Path("1".to_string()), State(state.clone()), Query(query(false, "../.."))).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = get_agent_workspace(Path("no-such-agent".to_string()), State(state), Query(WorkspaceQuery::default())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_agent_token_stats_returns_windows_and_burn_rate() {
+        use crate::agent::token_stats::{aggregate_hour, TokenWindow};
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = format!("token-stats-{}", uuid::Uuid::new_v4());
+        register_test_agent(&state, &agent_id, "idle").await;
+        let mission = crate::agent::mission::create_mission(&state.pool, &agent_id, "Stats", 1.0, &[]).await.unwrap();
+        let usage = serde_json::json!({ "usage": { "inputTokens": 80, "outputTokens": 20, "totalTokens": 100 }, "costUsd": 0.5 });
+        crate::agent::mission::log_step(&state.pool, &mission.id, &agent_id, "Agent", "done", "success", Some(usage)).await.unwrap();
+        aggregate_hour(&state.pool, chrono::Utc::now()).await.unwrap();
+
+        let stats = |window: Option<TokenWindow>, limit: Option<i64>| get_agent_token_stats(Path(agent_id.clone()), State(state.clone()), Query(TokenStatsQuery { window, limit }));
+        let response = stats(Some(TokenWindow::Day), None).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["window"], "day");
+        assert_eq!(json["stats"][0]["input_tokens"], 80);
+        assert_eq!(json["stats"][0]["output_tokens"], 20);
+        assert!(json["burn_rate_usd_per_day"].as_f64().unwrap() > 0.0);
+
+        assert_eq!(stats(None, Some(0)).await.into_response().status(), StatusCode::BAD_REQUEST);
+        let response = get_agent_token_stats(Path("no-such-agent".to_string()), State(state), Query(TokenStatsQuery::default())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_create_agent_handler() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = "test-new-agent".to_string();
+        
+        let new_agent = EngineAgent {
+            id: agent_id.clone(),
+            name: "Test Agent".to_string(),
+            role: "tester".to_string(),
+            department: "QA".to_string(),
+            description: "Internal Test Node".to_string(),
+            model_id: Some("gpt-4o".to_string()),
+            model: ModelConfig {
+                provider: "openai".to_string(),
+                model_id: "gpt-4o".to_string(),
+                api_key: None,
+                base_url: None,
+                system_prompt: None,
+                temperature: None,
+                max_tokens: None,
+                external_id: None,
+                rpm: None,
+                rpd: None,
+                tpm: None,
+                tpd: None,
+                max_history_turns: crate::agent::types::DEFAULT_MAX_HISTORY_TURNS,
+                safety_settings: None,
+            },
+            model_2: None,
+            model_3: None,
+            model_config2: None,
+            model_config3: None,
+            active_model_slot: None,
+            active_mission: None,
+            status: "idle".to_string(),
+            tokens_used: 0,
+            token_usage: TokenUsage::default(),
+            metadata: HashMap::new(),
+            theme_color: None,
+            budget_usd: 100.0,
+            cost_usd: 0.0,
+            skills: vec!["skill-1".to_string()],
+            workflows: vec!["workflow-1".to_string()],
+            skill_allowlist: None,
+            rpm_override: None,
+            tpm_override: None,
+        };
+
+        let response = create_agent(State(state.clone()), Json(new_agent)).await.into_response();
+        
+        assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+        
+        // Verify it was inserted into the registry
+        assert!(state.agents.contains_key(&agent_id));
+        let registered = state.agents.get(&agent_id).unwrap();
+        assert_eq!(registered.name, "Test Agent");
+    }
+
+    #[tokio::test]
+    async fn test_submit_batch_creates_one_mission_per_agent() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let workspace = crate::agent::runner::TestWorkspace::new("batch-missions");
+        let agent_ids: Vec<String> = (0..3).map(|i| format!("batch-mission-{}", i)).collect();
+        for id in &agent_ids {
+            register_test_agent(&state, id, "idle").await;
+        }
+
+        // The mock provider answers without touching the network
+        let payload: TaskPayload = serde_json::from_value(serde_json::json!({
+            "message": "Batch evaluation task",
+            "provider": "mock",
+            "clusterId": workspace.cluster_id,
+        })).unwrap();
+        let req = BatchMissionRequest { agent_ids: agent_ids.clone(), payload };
+        let response = submit_batch(State(state.clone()), Json(req)).await.into_response();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let submitted = json["submitted"].as_array().unwrap();
+        assert_eq!(submitted.len(), 3);
+        assert!(json["rejected"].as_array().unwrap().is_empty());
+
+        for entry in submitted {
+            let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM mission_history WHERE id = ? AND agent_id = ?")
+                .bind(entry["missionId"].as_str().unwrap())
+                .bind(entry["agentId"].as_str().unwrap())
+                .fetch_one(&state.pool).await.unwrap();
+            assert_eq!(count, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reset_cost_zeroes_agent_and_records_history() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = format!("cost-reset-{}", uuid::Uuid::new_v4());
+        let mut agent = state.agents.get("1").unwrap().clone();
+        agent.id = agent_id.clone();
+        agent.cost_usd = 5.0;
+        agent.tokens_used = 1234;
+        crate::agent::persistence::save_agent_db(&state.pool, &agent).await.unwrap();
+        state.agents.insert(agent_id.clone(), agent);
+
+        let response = reset_cost(Path(agent_id.clone()), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.agents.get(&agent_id).unwrap().cost_usd, 0.0);
+        assert_eq!(state.agents.get(&agent_id).unwrap().tokens_used, 0);
+
+        let previous: f64 = sqlx::query_scalar("SELECT previous_cost_usd FROM cost_resets WHERE agent_id = ?")
+            .bind(&agent_id)
+            .fetch_one(&state.pool).await.unwrap();
+        assert_eq!(previous, 5.0);
+
+        let response = get_cost_resets(Path(agent_id.clone()), State(state.clone())).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json[0]["previousTokensUsed"], 1234);
+    }
+
+    #[tokio::test]
+    async fn test_generate_description_returns_unsaved_text() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A stand-in Ollama server that returns a canned description and hands back the request
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, request_rx) = tokio::sync::oneshot::channel::<String>();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 8192];
+            // Read until the JSON body is complete
+            while !String::from_utf8_lossy(&request).trim_end().ends_with('}') {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 { break; }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let _ = request_tx.send(String::from_utf8_lossy(&request).to_string());
+            let body = serde_json::json!({
+                "message": { "role": "assistant", "content": "  Audits code for vulnerabilities. Reports findings with fixes.  " }
+            }).to_string();
+            let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = format!("describe-{}", uuid::Uuid::new_v4());
+        let mut agent = state.agents.get("1").unwrap().clone();
+        agent.id = agent_id.clone();
+        agent.role = "Security Auditor".to_string();
+        agent.department = "Security".to_string();
+        agent.description = String::new();
+        agent.model_id = None;
+        agent.active_model_slot = None;
+        agent.model.provider = "ollama".to_string();
+        agent.model.model_id = "describe-model".to_string();
+        agent.model.base_url = Some(format!("http://{}", addr));
+        agent.model.rpm = None;
+        agent.model.tpm = None;
+        agent.model.rpd = None;
+        agent.model.tpd = None;
+        state.agents.insert(agent_id.clone(), agent);
+
+        let response = generate_description(Path(agent_id.clone()), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["description"], "Audits code for vulnerabilities. Reports findings with fixes.");
+        let request = request_rx.await.unwrap();
+        assert!(request.contains("Security Auditor") && request.contains("describe-model"), "the prompt carries the role: {}", request);
+        assert!(state.agents.get(&agent_id).unwrap().description.is_empty(), "The draft must not be persisted");
+
+        let missions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM mission_history WHERE agent_id = ?")
+            .bind(&agent_id)
+            .fetch_one(&state.pool).await.unwrap();
+        assert_eq!(missions, 0);
+
+        // A provider the engine cannot call is an error, not an echo of the prompt
+        state.agents.get_mut(&agent_id).unwrap().model.provider = "no-such-provider".to_string();
+        let response = generate_description(Path(agent_id.clone()), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn test_get_agent_skills_resolves_and_reports_missing() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = format!("skills-{}", uuid::Uuid::new_v4());
+        let mut agent = state.agents.get("1").unwrap().clone();
+        agent.id = agent_id.clone();
+        agent.skills = vec!["read_file".to_string(), "write_file".to_string(), "not_a_real_skill".to_string()];
+        state.agents.insert(agent_id.clone(), agent);
+
+        let response = get_agent_skills(Path(agent_id.clone()), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], 404);
+        assert_eq!(json["title"], "Unregistered Capabilities");
+        let skills = json["skills"].as_array().unwrap();
+        assert_eq!(skills.len(), 2);
+        assert!(skills.iter().all(|s| s["execution_command"] == "[hidden]" && s["description"].is_string()));
+        assert_eq!(json["missingSkills"], serde_json::json!(["not_a_real_skill"]));
+    }
+
+    #[tokio::test]
+    async fn test_get_agent_skills_resolves_native_skills_without_registry_files() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = format!("skills-{}", uuid::Uuid::new_v4());
+        let mut agent = state.agents.get("1").unwrap().clone();
+        agent.id = agent_id.clone();
+        agent.skills = ["self_aware", "json_writer", "api_designer", "run_tests", "execute_bash"].map(String::from).to_vec();
+        state.agents.insert(agent_id.clone(), agent);
+
+        let response = get_agent_skills(Path(agent_id), State(state)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["skills"].as_array().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_submit_batch_rejects_oversized_batch() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let payload: TaskPayload = serde_json::from_value(serde_json::json!({ "message": "too many" })).unwrap();
+        let req = BatchMissionRequest {
+            agent_ids: (0..11).map(|i| format!("agent-{}", i)).collect(),
+            payload,
+        };
+        let response = submit_batch(State(state), Json(req)).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_task_prices_without_creating_a_mission() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let mut agent = state.agents.get("1").unwrap().clone();
+        agent.id = uuid::Uuid::new_v4().to_string();
+        let agent_id = agent.id.clone();
+        let expected_model = agent.model_id.clone().unwrap_or_else(|| agent.model.model_id.clone());
+        state.register_agent(agent).await;
+
+        let count_missions = || sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM mission_history WHERE agent_id = ?")
+            .bind(agent_id.clone())
+            .fetch_one(&state.pool);
+
+        let payload: TaskPayload = serde_json::from_value(serde_json::json!({ "message": "Summarise the quarterly report." })).unwrap();
+        let resp = estimate_task(Path(agent_id.clone()), State(state.clone()), Json(payload)).await.into_response();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let input_tokens = json["estimated_input_tokens"].as_u64().unwrap();
+        assert!(input_tokens > crate::agent::rates::estimate_tokens("Summarise the quarterly report.") as u64, "system prompt must be counted");
+        assert!(json["estimated_max_cost_usd"].as_f64().unwrap() > json["estimated_min_cost_usd"].as_f64().unwrap());
+        assert_eq!(json["model_id"], expected_model);
+
+        assert_eq!(count_missions().await.unwrap(), 0, "Estimating must not create a mission");
+
+        let payload: TaskPayload = serde_json::from_value(serde_json::json!({ "message": "hi" })).unwrap();
+        let resp = estimate_task(Path("no-such-agent".to_string()), State(state.clone()), Json(payload)).await.into_response();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_department_broadcast_dispatches_only_idle_members() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let department = format!("Dept-{}", uuid::Uuid::new_v4());
+        let mut events = state.event_tx.subscribe();
+
+        let seed = |status: &str, dept: &str| {
+            let mut agent = state.agents.get("1").unwrap().clone();
+            agent.id = format!("broadcast-{}", uuid::Uuid::new_v4());
+            agent.department = dept.to_string();
+            agent.status = status.to_string();
+            agent.model.provider = "mock".to_string();
+            agent.active_model_slot = None;
+            state.agents.insert(agent.id.clone(), agent.clone());
+            agent.id
+        };
+        let idle_a = seed("idle", &department);
+        let idle_b = seed("idle", &department);
+        let busy = seed("thinking", &department);
+        let outsider = seed("idle", "Elsewhere");
+
+        let payload: TaskPayload = serde_json::from_value(serde_json::json!({ "message": "All hands" })).unwrap();
+        let response = broadcast_to_department(Path(department.clone()), State(state.clone()), Json(payload)).await.into_response();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let mut accepted: Vec<String> = serde_json::from_value(json["accepted"].clone()).unwrap();
+        accepted.sort();
+        let mut expected = vec![idle_a.clone(), idle_b.clone()];
+        expected.sort();
+        assert_eq!(accepted, expected);
+        assert_eq!(json["skipped"], serde_json::json!([busy]));
+
+        assert_eq!(state.agents.get(&idle_a).unwrap().status, "thinking");
+        assert_eq!(state.agents.get(&idle_b).unwrap().status, "thinking");
+        assert_eq!(state.agents.get(&outsider).unwrap().status, "idle");
+
+        let event = loop {
+            let event = events.recv().await.unwrap();
+            if event["type"] == "swarm:broadcast" && event["department"] == department.as_str() {
+                break event;
+            }
+        };
+        assert_eq!(event["agentCount"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_department_broadcast_releases_members_when_missions_fail_to_start() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let department = format!("Dept-{}", uuid::Uuid::new_v4());
+        let members: Vec<String> = (0..2).map(|_| {
+            let mut agent = state.agents.get("1").unwrap().clone();
+            agent.id = format!("broadcast-{}", uuid::Uuid::new_v4());
+            agent.department = department.clone();
+            agent.status = "idle".to_string();
+            state.agents.insert(agent.id.clone(), agent.clone());
+            agent.id
+        }).collect();
+
+        // An oversized task is refused before anyone is claimed
+        let payload: TaskPayload = serde_json::from_value(serde_json::json!({ "message": "x".repeat(crate::agent::runner::MAX_TASK_LENGTH + 1) })).unwrap();
+        let response = broadcast_to_department(Path(department.clone()), State(state.clone()), Json(payload)).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(members.iter().all(|id| state.agents.get(id).unwrap().status == "idle"));
+
+        // A task blocked on an unfinished dependency claims every member, then releases them
+        let dependency = crate::agent::mission::create_mission(&state.pool, "1", "Gather data", 1.0, &[]).await.unwrap();
+        let payload: TaskPayload = serde_json::from_value(serde_json::json!({ "message": "All hands", "dependsOn": dependency.id })).unwrap();
+        let response = broadcast_to_department(Path(department.clone()), State(state.clone()), Json(payload)).await.into_response();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let released = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while members.iter().any(|id| state.agents.get(id).unwrap().status != "idle") {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+        }).await;
+        assert!(released.is_ok(), "broadcast members were left thinking");
+    }
+
+    #[tokio::test]
+    async fn test_clone_agent_coexists_with_source() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let mut source = state.agents.get("1").unwrap().clone();
+        source.id = format!("clone-src-{}", uuid::Uuid::new_v4());
+        source.cost_usd = 4.2;
+        source.tokens_used = 900;
+        source.status = "thinking".to_string();
+        let source_id = source.id.clone();
+        state.agents.insert(source_id.clone(), source);
+
+        let new_id = format!("clone-dst-{}", uuid::Uuid::new_v4());
+        let req = CloneAgentRequest { new_id: Some(new_id.clone()), new_name: Some("Twin".to_string()) };
+        let response = clone_agent(Path(source_id.clone()), State(state.clone()), Some(Json(req))).await.into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let clone = state.agents.get(&new_id).unwrap().clone();
+        assert_eq!(clone.name, "Twin");
+        assert_eq!(clone.status, "idle");
+        assert_eq!(clone.cost_usd, 0.0);
+        assert_eq!(clone.tokens_used, 0);
+        let source = state.agents.get(&source_id).unwrap().clone();
+        assert_eq!(source.cost_usd, 4.2, "The source agent must be left untouched");
+        assert_eq!(clone.skills, source.skills);
+
+        let req = CloneAgentRequest { new_id: Some(new_id.clone()), new_name: None };
+        let response = clone_agent(Path(source_id.clone()), State(state.clone()), Some(Json(req))).await.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = clone_agent(Path(source_id), State(state.clone()), None).await.into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = clone_agent(Path("no-such-agent".to_string()), State(state), None).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// Registers a fresh agent (persisted) so deletion tests never touch the seeded ones.
+    async fn register_test_agent(state: &AppState, id: &str, status: &str) {
+        let mut agent = crate::agent::registry::get_mock_registry().remove(0);
+        agent.id = id.to_string();
+        agent.status = status.to_string();
+        state.register_agent(agent).await;
+    }
+
+    #[tokio::test]
+    async fn test_delete_agent_conflicts_while_mission_is_running() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        register_test_agent(&state, "delete-test-busy", "thinking").await;
+
+        let response = delete_agent(Path("delete-test-busy".to_string()), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert!(state.agents.contains_key("delete-test-busy"));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_agent_is_excluded_from_list() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        register_test_agent(&state, "delete-test-idle", "idle").await;
+        let mut events = state.event_tx.subscribe();
+
+        let response = delete_agent(Path("delete-test-idle".to_string()), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let event = events.recv().await.unwrap();
+        assert_eq!(event["type"], "agent:deleted");
+
+        let list_ids = |body: axum::body::Bytes| -> Vec<String> {
+            let json: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+            json.iter().map(|a| a["id"].as_str().unwrap().to_string()).collect()
+        };
+        let response = get_agents(State(state.clone()), Query(AgentListQuery::default())).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(!list_ids(body).contains(&"delete-test-idle".to_string()));
+
+        let reloaded = crate::agent::persistence::load_agents_db(&state.pool).await.unwrap();
+        assert!(reloaded.iter().all(|a| a.id != "delete-test-idle"));
+
+        let query = AgentListQuery { include_deleted: Some(true) };
+        let response = get_agents(State(state.clone()), Query(query)).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(list_ids(body).contains(&"delete-test-idle".to_string()));
+
+        let payload: TaskPayload = serde_json::from_value(serde_json::json!({ "message": "hello" })).unwrap();
+        let response = send_task(Path("delete-test-idle".to_string()), State(state), Json(payload)).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_update_agent_sets_keeps_and_clears_skill_allowlist() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = "allowlist-update".to_string();
+        register_test_agent(&state, &agent_id, "idle").await;
+
+        let update = |body: serde_json::Value| {
+            let state = state.clone();
+            let agent_id = agent_id.clone();
+            async move {
+                let update = serde_json::from_value(body).unwrap();
+                let response = update_agent(Path(agent_id.clone()), State(state.clone()), Json(update)).await.into_response();
+                assert_eq!(response.status(), StatusCode::OK);
+                state.agents.get(&agent_id).unwrap().skill_allowlist.clone()
+            }
+        };
+        assert_eq!(update(serde_json::json!({ "skillAllowlist": ["read_file"] })).await, Some(vec!["read_file".to_string()]));
+        assert_eq!(update(serde_json::json!({ "name": "Renamed" })).await, Some(vec!["read_file".to_string()]), "absent keeps it");
+        assert_eq!(update(serde_json::json!({ "skillAllowlist": null })).await, None, "null clears it");
+    }
+
+    #[tokio::test]
+    async fn test_update_budget_set_add_and_reset_cost() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = format!("budget-{}", uuid::Uuid::new_v4());
+        let mut agent = state.agents.get("1").unwrap().clone();
+        agent.id = agent_id.clone();
+        agent.budget_usd = 5.0;
+        agent.cost_usd = 4.0;
+        agent.active_mission = None;
+        crate::agent::persistence::save_agent_db(&state.pool, &agent).await.unwrap();
+        state.agents.insert(agent_id.clone(), agent);
+        let mut events = state.event_tx.subscribe();
+
+        let budget = |operation: BudgetOperation, amount: Option<f64>| {
+            update_budget(Path(agent_id.clone()), State(state.clone()), Json(BudgetUpdateRequest { operation, amount }))
+        };
+        let json_of = |response: axum::response::Response| async move {
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let json = json_of(budget(BudgetOperation::Set, Some(20.0)).await.into_response()).await;
+        assert_eq!(json["new_budget_usd"], 20.0);
+        assert_eq!(json["headroom_usd"], 16.0);
+        let event = events.recv().await.unwrap();
+        assert_eq!(event["type"], "agent:budget_updated");
+        assert_eq!(event["budgetUsd"], 20.0);
+
+        let json = json_of(budget(BudgetOperation::Add, Some(2.5)).await.into_response()).await;
+        assert_eq!(json["new_budget_usd"], 22.5);
+        assert_eq!(json["cost_usd"], 4.0);
+
+        let json = json_of(budget(BudgetOperation::ResetCost, None).await.into_response()).await;
+        assert_eq!(json["cost_usd"], 0.0);
+        assert_eq!(json["headroom_usd"], 22.5);
+
+        let stored = crate::agent::persistence::load_agents_db(&state.pool).await.unwrap()
+            .into_iter().find(|a| a.id == agent_id).unwrap();
+        assert_eq!((stored.budget_usd, stored.cost_usd), (22.5, 0.0));
+
+        for amount in [None, Some(0.0), Some(-1.0), Some(10_000.5)] {
+            let response = budget(BudgetOperation::Add, amount).await.into_response();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{:?}", amount);
+        }
+        let response = update_budget(Path("missing".to_string()), State(state.clone()), Json(BudgetUpdateRequest { operation: BudgetOperation::Set, amount: Some(1.0) })).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_switch_model_rejects_empty_slots_and_busy_agents() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        register_test_agent(&state, "switch-test-busy", "thinking").await;
+        state.agents.get_mut("switch-test-busy").unwrap().model_3 = None;
+
+        let switch = |slot: i32| switch_model(Path("switch-test-busy".to_string()), State(state.clone()), Json(SwitchModelRequest { slot }));
+        assert_eq!(switch(4).await.into_response().status(), StatusCode::BAD_REQUEST);
+        assert_eq!(switch(3).await.into_response().status(), StatusCode::BAD_REQUEST);
+        assert_eq!(switch(2).await.into_response().status(), StatusCode::CONFLICT);
+        assert_eq!(state.agents.get("switch-test-busy").unwrap().active_model_slot, Some(2), "A refused switch must not change the slot");
+
+        let response = switch_model(Path("missing".to_string()), State(state.clone()), Json(SwitchModelRequest { slot: 1 })).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_agent_missions_are_paged_with_stats() {
+        use crate::agent::mission::{create_mission, log_step, update_mission};
+        use crate::agent::types::MissionStatus;
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = format!("missions-{}", uuid::Uuid::new_v4());
+        register_test_agent(&state, &agent_id, "idle").await;
+
+        for i in 0..3 {
+            let mission = create_mission(&state.pool, &agent_id, &format!("Mission {}", i), 1.0, &[]).await.unwrap();
+            if i < 2 {
+                update_mission(&state.pool, &mission.id, MissionStatus::Completed, 0.25).await.unwrap();
+                let usage = serde_json::json!({ "usage": { "inputTokens": 80, "outputTokens": 20, "totalTokens": 100 } });
+                log_step(&state.pool, &mission.id, &agent_id, "Agent", "done", "success", Some(usage)).await.unwrap();
+            }
+        }
+
+        let query = |status: Option<MissionStatus>, page: i64| AgentMissionsQuery { status, page: Some(page), per_page: Some(2) };
+        let list = |q: AgentMissionsQuery| get_agent_missions(Path(agent_id.clone()), State(state.clone()), Query(q));
+
+        let response = list(query(None, 2)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["total"], 3);
+        assert_eq!((json["page"].as_i64(), json["per_page"].as_i64()), (Some(2), Some(2)));
+        assert_eq!(json["missions"].as_array().unwrap().len(), 1);
+        assert!((json["stats"]["total_cost_usd"].as_f64().unwrap() - 0.5).abs() < 1e-9);
+        assert_eq!(json["stats"]["total_tokens"], 200);
+        assert!((json["stats"]["success_rate"].as_f64().unwrap() - 2.0 / 3.0).abs() < 1e-9);
+
+        let response = list(query(Some(MissionStatus::Completed), 1)).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["total"], 2);
+        assert!(json["missions"].as_array().unwrap().iter().all(|m| m["agent_id"] == agent_id.as_str()));
+
+        let response = get_agent_missions(Path("no-such-agent".to_string()), State(state.clone()), Query(AgentMissionsQuery::default())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_agent_transcript_streams_json_and_text() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = format!("transcript-{}", uuid::Uuid::new_v4());
+        register_test_agent(&state, &agent_id, "idle").await;
+        let first = crate::agent::mission::create_mission(&state.pool, &agent_id, "First", 1.0, &[]).await.unwrap();
+        let second = crate::agent::mission::create_mission(&state.pool, &agent_id, "Second", 1.0, &[]).await.unwrap();
+        for (mission, source, text) in [(&first, "User", "Audit the billing job"), (&first, "Agent", "Billing job audited"), (&second, "User", "Now the invoices")] {
+            crate::agent::mission::log_step(&state.pool, &mission.id, &agent_id, source, text, "info", Some(serde_json::json!({ "step": text }))).await.unwrap();
+        }
+        let transcript = |mission_id: Option<&str>, limit: Option<i64>, format: Option<&str>| get_agent_transcript(
+            Path(agent_id.clone()),
+            State(state.clone()),
+            Query(TranscriptQuery { mission_id: mission_id.map(str::to_string), limit, format: format.map(str::to_string) }),
+        );
+
+        let response = transcript(None, Some(2), None).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[axum::http::header::CONTENT_TYPE], "application/json");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let texts: Vec<&str> = json.as_array().unwrap().iter().map(|e| e["text"].as_str().unwrap()).collect();
+        assert_eq!(texts, vec!["Billing job audited", "Now the invoices"], "the most recent entries, oldest first");
+        assert_eq!(json[0]["source"], "Agent");
+        assert_eq!(json[0]["severity"], "info");
+        assert_eq!(json[0]["metadata"]["step"], "Billing job audited");
+        assert!(json[0]["timestamp"].is_string());
+
+        let response = transcript(Some(&first.id), None, Some("text")).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[axum::http::header::CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2, "got: {}", text);
+        assert!(lines[0].ends_with("[USER] Audit the billing job"), "got: {}", text);
+        assert!(lines[1].ends_with("[AGENT] Billing job audited"), "got: {}", text);
+
+        let response = transcript(Some(&second.id), None, None).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&body).unwrap().as_array().unwrap().len(), 1);
+
+        assert_eq!(transcript(None, None, Some("xml")).await.into_response().status(), StatusCode::BAD_REQUEST);
+        assert_eq!(transcript(Some("no-such-mission"), None, None).await.into_response().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_sends_to_one_agent_run_sequentially() {
+        use crate::agent::types::MissionStatus;
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = format!("queue-{}", uuid::Uuid::new_v4());
+        register_test_agent(&state, &agent_id, "idle").await;
+        // An unknown provider fails each run quickly without touching the network
+        let payload = || -> TaskPayload {
+            serde_json::from_value(serde_json::json!({ "message": "queued work", "provider": "mock" })).unwrap()
+        };
+
+        // Occupy the agent as a running task would
+        let lock = state.agent_task_lock(&agent_id);
+        let turn = lock.lock().await;
+        for _ in 0..2 {
+            let response = send_task(Path(agent_id.clone()), State(state.clone()), Json(payload())).await.into_response();
+            assert_eq!(response.status(), StatusCode::ACCEPTED);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["queued"], true);
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let missions = crate::agent::mission::list_missions_for_agent(&state.pool, &agent_id, None, 1, 10).await.unwrap();
+        assert_eq!(missions.total, 0, "Queued tasks must not start while the agent is busy");
+        drop(turn);
+
+        let mut missions = Vec::new();
+        for _ in 0..100 {
+            missions = crate::agent::mission::list_missions_for_agent(&state.pool, &agent_id, Some(MissionStatus::Failed), 1, 10).await.unwrap().items;
+            if missions.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(missions.len(), 2, "Both queued tasks must run once the agent is free");
+        missions.sort_by_key(|m| m.created_at);
+        assert!(missions[0].updated_at <= missions[1].created_at, "The second task must start after the first finished");
     }
 }
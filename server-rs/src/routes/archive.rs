@@ -0,0 +1,116 @@
+//! ZIP support for mission exports and capability pack imports.
+
+/// Packs `files` into a deflated ZIP archive.
+/// Fails when the archive cannot be written, e.g. when two entries share a name.
+pub(crate) fn build_zip<S: AsRef<str>>(files: &[(S, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    use std::io::Write;
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, data) in files {
+        writer.start_file(name.as_ref(), options).map_err(|e| e.to_string())?;
+        writer.write_all(data).map_err(|e| e.to_string())?;
+    }
+    Ok(writer.finish().map_err(|e| e.to_string())?.into_inner())
+}
+
+/// Largest size a single entry may inflate to, so a small upload cannot expand without bound.
+pub(crate) const MAX_ENTRY_BYTES: usize = 16 * 1024 * 1024;
+
+/// A file read from an archive. `data` holds the reason when this entry alone could not be extracted.
+#[derive(Debug)]
+pub(crate) struct ZipEntry {
+    pub name: String,
+    pub data: Result<Vec<u8>, String>,
+}
+
+/// Lists every entry of a ZIP archive.
+/// Fails only when the archive structure itself is unreadable; problems with a single entry
+/// (unsupported compression, bad checksum, over `MAX_ENTRY_BYTES`) are reported in that entry's `data`.
+pub(crate) fn read_zip(bytes: &[u8]) -> Result<Vec<ZipEntry>, String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let name = archive.name_for_index(index).unwrap_or_default().to_string();
+        let data = archive.by_index(index).map_err(|e| e.to_string()).and_then(read_entry);
+        entries.push(ZipEntry { name, data });
+    }
+    Ok(entries)
+}
+
+/// Reads one entry, stopping as soon as it passes `MAX_ENTRY_BYTES` whatever size its header claims.
+fn read_entry(file: impl std::io::Read) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+    let mut data = Vec::new();
+    file.take(MAX_ENTRY_BYTES as u64 + 1).read_to_end(&mut data).map_err(|e| e.to_string())?;
+    if data.len() > MAX_ENTRY_BYTES {
+        return Err(format!("entry exceeds {} bytes", MAX_ENTRY_BYTES));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step_text() -> String {
+        (1..=18).map(|i| format!("Step {}: verify sources.\n", i)).collect()
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_build_zip_round_trips_and_rejects_duplicate_names() {
+        let archive = build_zip(&[("a.json", b"{}".to_vec()), ("dir/b.md", step_text().into_bytes())]).unwrap();
+        let entries = read_zip(&archive).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].name, "dir/b.md");
+        assert_eq!(entries[1].data.as_deref().unwrap(), step_text().as_bytes());
+
+        assert!(build_zip(&[("a.md", Vec::new()), ("a.md", Vec::new())]).is_err());
+    }
+
+    #[test]
+    fn test_read_zip_reads_stored_and_deflated() {
+        use std::io::Write;
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let stored = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, data) in [("a.json", &b"{}"[..]), ("dir/b.md", &b"# B"[..])] {
+            writer.start_file(name, stored).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        let archive = writer.finish().unwrap().into_inner();
+        let entries = read_zip(&archive).unwrap();
+        assert_eq!(entries[1].data.as_deref().unwrap(), b"# B");
+
+        // Written by Python's zipfile with ZIP_DEFLATED
+        let deflated = hex("504b030414000000080000002100cba05d5352000000b90100000e0000007061636b2f7665726966792e6d6475cfb90d80301005d19c2ab602e40f98ab0d9780168908640312dd2372267d134d3afd30cd767bded6c7ca7ee5c54b5da5cf1bf016bc038fe03df8003e824fe00a14e858b42c7a164d8bae45dba26ffd8cbf504b0102140314000000080000002100cba05d5352000000b90100000e00000000000000000000008001000000007061636b2f7665726966792e6d64504b050600000000010001003c0000007e0000000000");
+        let entries = read_zip(&deflated).unwrap();
+        assert_eq!(entries[0].name, "pack/verify.md");
+        assert_eq!(entries[0].data.as_deref().unwrap(), step_text().as_bytes());
+
+        assert!(read_zip(b"not a zip archive at all").is_err());
+
+        // A damaged entry is reported on its own
+        let mut corrupt = archive.clone();
+        let at = corrupt.windows(3).position(|w| w == b"# B").unwrap();
+        corrupt[at] = b'!';
+        let entries = read_zip(&corrupt).unwrap();
+        assert!(entries[0].data.is_ok());
+        assert!(entries[1].data.is_err());
+    }
+
+    #[test]
+    fn test_read_zip_caps_entry_size() {
+        use std::io::Write;
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        writer.start_file("bomb.txt", zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(&vec![0u8; MAX_ENTRY_BYTES + 1]).unwrap();
+        let bomb = writer.finish().unwrap().into_inner();
+        assert!(bomb.len() < MAX_ENTRY_BYTES / 100, "deflated zeros should be tiny");
+
+        let entries = read_zip(&bomb).unwrap();
+        assert!(entries[0].data.as_ref().unwrap_err().contains("exceeds"));
+    }
+}
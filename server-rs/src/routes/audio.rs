@@ -1,14 +1,25 @@
 use axum::{
-    extract::{Multipart, State},
+    extract::{Multipart, Query, State},
     response::IntoResponse,
     Json,
 };
 use std::sync::Arc;
+use crate::agent::groq::AudioTask;
 use crate::state::AppState;
 use serde_json::json;
 
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct AudioQuery {
+    /// `transcribe` (default) keeps the spoken language; `translate` returns English.
+    pub task: Option<AudioTask>,
+}
+
+/// POST /engine/transcribe endpoint.
+/// Turns an uploaded recording into text with Groq Whisper, translating it to English
+/// when `?task=translate` is given.
 pub async fn transcribe_audio(
     State(state): State<Arc<AppState>>,
+    Query(params): Query<AudioQuery>,
     mut multipart: Multipart,
 ) -> impl IntoResponse {
     let mut audio_data = Vec::new();
@@ -26,20 +37,18 @@ pub async fn transcribe_audio(
         return Err((axum::http::StatusCode::BAD_REQUEST, "No audio file provided".to_string()));
     }
 
-    // Initialize Groq Provider for transcription using the shared HTTP client
+    let task = params.task.unwrap_or_default();
+
+    // Initialize Groq Provider for the requested task using the shared HTTP client
     let (api_key, model_id) = if let Some(groq_provider) = state.providers.get("groq") {
         let key = groq_provider.api_key.clone()
             .or_else(|| std::env::var("GROQ_API_KEY").ok())
             .ok_or_else(|| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, "Missing GROQ_API_KEY".to_string()))?;
-        
-        let model = groq_provider.audio_model.clone()
-            .unwrap_or_else(|| "whisper-large-v3".to_string());
-            
-        (key, model)
+        (key, task.model(groq_provider.audio_model.as_deref()))
     } else {
         let key = std::env::var("GROQ_API_KEY")
             .map_err(|_| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, "Missing GROQ_API_KEY".to_string()))?;
-        (key, "whisper-large-v3".to_string())
+        (key, task.model(None))
     };
     
     let config = crate::agent::types::ModelConfig {
@@ -55,17 +64,46 @@ pub async fn transcribe_audio(
         rpd: None,
         tpm: None,
         tpd: None,
+        max_history_turns: crate::agent::types::DEFAULT_MAX_HISTORY_TURNS,
+        safety_settings: None,
     };
 
     // Use the shared HTTP client from AppState (PERF-01 fix)
     let client = (*state.http_client).clone();
     let provider = crate::agent::groq::GroqProvider::new(client, api_key, config);
     
-    let text = provider.transcribe(audio_data, &filename).await
+    let text = match task {
+        AudioTask::Transcribe => provider.transcribe(audio_data, &filename).await,
+        AudioTask::Translate => provider.translate(audio_data, &filename).await,
+    }
         .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     Ok(Json(json!({
         "status": "success",
+        "task": task,
         "text": text
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Uri;
+
+    fn task_of(uri: &str) -> Option<AudioTask> {
+        Query::<AudioQuery>::try_from_uri(&uri.parse::<Uri>().unwrap()).map(|Query(q)| q.task.unwrap_or_default()).ok()
+    }
+
+    #[test]
+    fn test_task_query_selects_the_whisper_endpoint_and_model() {
+        assert_eq!(task_of("/engine/transcribe"), Some(AudioTask::Transcribe));
+        assert_eq!(task_of("/engine/transcribe?task=transcribe"), Some(AudioTask::Transcribe));
+        assert_eq!(task_of("/engine/transcribe?task=translate"), Some(AudioTask::Translate));
+        assert_eq!(task_of("/engine/transcribe?task=summarize"), None, "unknown tasks are rejected");
+
+        assert!(AudioTask::Translate.endpoint().ends_with("/audio/translations"));
+        assert!(AudioTask::Transcribe.endpoint().ends_with("/audio/transcriptions"));
+        assert_eq!(AudioTask::Translate.model(Some("whisper-large-v3-turbo")), "whisper-large-v3");
+        assert_eq!(AudioTask::Transcribe.model(Some("whisper-large-v3-turbo")), "whisper-large-v3-turbo");
+    }
+}
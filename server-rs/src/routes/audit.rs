@@ -0,0 +1,190 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Row, Sqlite};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::routes::error::ProblemDetails;
+use crate::state::AppState;
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 1000;
+
+/// A single row from the `mutation_audit_log` table.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    #[serde(rename = "statusCode")]
+    pub status_code: Option<i64>,
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+    #[serde(rename = "requestId")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditLogQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub method: Option<String>,
+    pub path_prefix: Option<String>,
+    pub limit: Option<i64>,
+    /// Row `id` of the last entry from the previous page. Only rows with a greater id are returned.
+    pub cursor: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditStatsQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Appends the shared time-range filters. Every value is bound — never interpolated.
+fn push_time_range(qb: &mut QueryBuilder<'_, Sqlite>, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) {
+    if let Some(from) = from {
+        qb.push(" AND timestamp >= ").push_bind(from);
+    }
+    if let Some(to) = to {
+        qb.push(" AND timestamp <= ").push_bind(to);
+    }
+}
+
+/// GET /system/audit-log
+/// Returns mutation audit entries in ascending id order, filtered by time range, method and path prefix.
+pub async fn get_audit_log(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AuditLogQuery>,
+) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+        "SELECT id, timestamp, method, path, status_code, user_id, request_id FROM mutation_audit_log WHERE 1 = 1"
+    );
+    push_time_range(&mut qb, params.from, params.to);
+    if let Some(method) = &params.method {
+        qb.push(" AND method = ").push_bind(method.to_uppercase());
+    }
+    if let Some(prefix) = &params.path_prefix {
+        // substr() instead of LIKE so '%' and '_' in the prefix are matched literally
+        qb.push(" AND substr(path, 1, ").push_bind(prefix.chars().count() as i64)
+            .push(") = ").push_bind(prefix.clone());
+    }
+    if let Some(cursor) = params.cursor {
+        qb.push(" AND id > ").push_bind(cursor);
+    }
+    qb.push(" ORDER BY id ASC LIMIT ").push_bind(limit);
+
+    let rows = match qb.build().fetch_all(&state.pool).await {
+        Ok(rows) => rows,
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Audit Log Query Failed",
+            format!("The audit log could not be queried: {}", e)
+        ).into_response(),
+    };
+
+    let entries: Vec<AuditLogEntry> = rows.iter().map(|r| AuditLogEntry {
+        id: r.get("id"),
+        timestamp: r.get("timestamp"),
+        method: r.get("method"),
+        path: r.get("path"),
+        status_code: r.get("status_code"),
+        user_id: r.get("user_id"),
+        request_id: r.get("request_id"),
+    }).collect();
+
+    let next_cursor = if entries.len() as i64 == limit { entries.last().map(|e| e.id) } else { None };
+
+    Json(serde_json::json!({
+        "entries": entries,
+        "nextCursor": next_cursor
+    })).into_response()
+}
+
+/// GET /system/audit-log/stats
+/// Aggregates the audit log over a time range: total requests, per-method counts and distinct users.
+pub async fn get_audit_stats(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AuditStatsQuery>,
+) -> impl IntoResponse {
+    let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+        "SELECT method, COUNT(*) AS count FROM mutation_audit_log WHERE 1 = 1"
+    );
+    push_time_range(&mut qb, params.from, params.to);
+    qb.push(" GROUP BY method");
+
+    let by_method_rows = match qb.build().fetch_all(&state.pool).await {
+        Ok(rows) => rows,
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Audit Stats Query Failed",
+            format!("The audit log statistics could not be computed: {}", e)
+        ).into_response(),
+    };
+
+    let mut by_method: HashMap<String, i64> = HashMap::new();
+    for row in &by_method_rows {
+        by_method.insert(row.get("method"), row.get("count"));
+    }
+    let total_requests: i64 = by_method.values().sum();
+
+    let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+        "SELECT COUNT(DISTINCT user_id) FROM mutation_audit_log WHERE 1 = 1"
+    );
+    push_time_range(&mut qb, params.from, params.to);
+    let unique_users: i64 = qb.build_query_scalar().fetch_one(&state.pool).await.unwrap_or(0);
+
+    Json(serde_json::json!({
+        "totalRequests": total_requests,
+        "byMethod": by_method,
+        "uniqueUsers": unique_users
+    })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_audit_log_filters_by_method() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let prefix = format!("/audit-test-{}", uuid::Uuid::new_v4());
+        let base = Utc.with_ymd_and_hms(2001, 1, 1, 0, 0, 0).unwrap();
+        let methods = ["POST", "PUT", "DELETE", "PUT", "POST", "PUT", "DELETE", "POST", "PUT", "POST"];
+
+        for (i, method) in methods.iter().enumerate() {
+            sqlx::query("INSERT INTO mutation_audit_log (timestamp, method, path, status_code, user_id) VALUES (?, ?, ?, 200, ?)")
+                .bind(base + chrono::Duration::minutes(i as i64))
+                .bind(*method)
+                .bind(format!("{}/agents/{}", prefix, i))
+                .bind(format!("user-{}", i % 3))
+                .execute(&state.pool).await.unwrap();
+        }
+
+        let params = AuditLogQuery {
+            from: Some(base),
+            to: Some(base + chrono::Duration::hours(1)),
+            method: Some("PUT".to_string()),
+            path_prefix: Some(prefix.clone()),
+            ..Default::default()
+        };
+        let response = get_audit_log(State(state.clone()), Query(params)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let entries = json["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 4, "Only the four PUT rows should be returned");
+        assert!(entries.iter().all(|e| e["method"] == "PUT"));
+    }
+}
@@ -1,6 +1,6 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Multipart, Path, State},
+    http::{header, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -8,23 +8,18 @@ use serde_json::json;
 use std::sync::Arc;
 
 use crate::state::AppState;
-use crate::agent::capabilities::{SkillDefinition, WorkflowDefinition};
+use crate::agent::capabilities::{safe_file_stem, validate_skill_schema, SkillDefinition, WorkflowDefinition};
 use crate::routes::error::ProblemDetails;
 
+/// Largest capability pack `import_capabilities` accepts.
+pub const MAX_CAPABILITY_IMPORT_BYTES: usize = 10 * 1024 * 1024;
+
 // GET /system/capabilities
 pub async fn get_capabilities(
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    let mut skills = Vec::new();
-    let mut workflows = Vec::new();
-
-    for kv in state.capabilities.skills.iter() {
-        skills.push(kv.value().clone());
-    }
-
-    for kv in state.capabilities.workflows.iter() {
-        workflows.push(kv.value().clone());
-    }
+    let skills = state.capabilities.all_skills();
+    let workflows = state.capabilities.all_workflows();
 
     (StatusCode::OK, Json(json!({
         "skills": skills,
@@ -32,12 +27,86 @@ pub async fn get_capabilities(
     })))
 }
 
+// GET /system/capabilities/diff
+pub async fn get_capabilities_diff(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let diff = state.capabilities.last_diff.lock().unwrap().clone();
+    Json(diff)
+}
+
+// GET /system/skills/:name/metrics
+pub async fn get_skill_metrics(
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    if state.capabilities.skill(&name).is_none() && !state.capabilities.metrics.contains_key(&name) {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Skill Not Found",
+            format!("No skill named '{}' is registered.", name)
+        ).into_response();
+    }
+    Json(state.capabilities.skill_metrics(&name)).into_response()
+}
+
+// GET /system/capabilities/metrics
+// Per-skill metrics plus totals across every skill that has run since the last reset.
+pub async fn get_capabilities_metrics(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let skills = state.capabilities.all_skill_metrics();
+    let invocations: u64 = skills.iter().map(|m| m.invocations).sum();
+    let failures: u64 = skills.iter().map(|m| m.failures).sum();
+    let total_duration_ms: f64 = skills.iter().map(|m| m.avg_duration_ms * m.invocations as f64).sum();
+    let rate = |value: f64| if invocations == 0 { 0.0 } else { value / invocations as f64 };
+
+    Json(json!({
+        "skills": skills,
+        "invocations": invocations,
+        "failures": failures,
+        "avg_duration_ms": rate(total_duration_ms),
+        "failure_rate": rate(failures as f64)
+    }))
+}
+
+// POST /system/reload-capabilities
+pub async fn reload_capabilities(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let policy_rules_loaded = match state.oversight_policy.reload() {
+        Ok(count) => count,
+        Err(e) => return ProblemDetails::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Oversight Policy Invalid",
+            format!("oversight_policy.json could not be loaded; the previous rules stay in effect: {}", e)
+        ).into_response(),
+    };
+
+    match state.capabilities.reload_all().await {
+        Ok((skills_loaded, workflows_loaded)) => (StatusCode::OK, Json(json!({
+            "skills_loaded": skills_loaded,
+            "workflows_loaded": workflows_loaded,
+            "policy_rules_loaded": policy_rules_loaded
+        }))).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Capabilities Reload Failed",
+            format!("The skills and workflows directories could not be reloaded: {}", e)
+        ).into_response()
+    }
+}
+
 // PUT /system/skills/:name
 pub async fn save_skill(
     Path(_name): Path<String>,
     State(state): State<Arc<AppState>>,
     Json(payload): Json<SkillDefinition>,
 ) -> impl IntoResponse {
+    if let Err(e) = validate_skill_schema(&payload) {
+        return invalid_skill(&payload, e);
+    }
+
     match state.capabilities.save_skill(payload.clone()).await {
         Ok(_) => (StatusCode::OK, Json(json!({"status": "success", "skill": payload}))).into_response(),
         Err(e) => ProblemDetails::new(
@@ -48,6 +117,26 @@ pub async fn save_skill(
     }
 }
 
+// PUT /system/skills/:name/validate
+// Dry run of `save_skill`: reports whether the definition would be accepted without writing it.
+pub async fn validate_skill(
+    Path(_name): Path<String>,
+    Json(payload): Json<SkillDefinition>,
+) -> impl IntoResponse {
+    match validate_skill_schema(&payload) {
+        Ok(()) => (StatusCode::OK, Json(json!({"status": "valid", "skill": payload.name}))).into_response(),
+        Err(e) => invalid_skill(&payload, e),
+    }
+}
+
+fn invalid_skill(skill: &SkillDefinition, error: anyhow::Error) -> axum::response::Response {
+    ProblemDetails::new(
+        StatusCode::BAD_REQUEST,
+        "Invalid Skill Definition",
+        format!("The skill '{}' is invalid: {}", skill.name, error)
+    ).into_response()
+}
+
 // DELETE /system/skills/:name
 pub async fn delete_skill(
     Path(name): Path<String>,
@@ -63,6 +152,42 @@ pub async fn delete_skill(
     }
 }
 
+// PUT /system/skills/:name/enable
+pub async fn enable_skill(
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    set_skill_enabled(&state, &name, true).await
+}
+
+// PUT /system/skills/:name/disable
+// Takes the skill away from every agent without deleting its definition.
+pub async fn disable_skill(
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    set_skill_enabled(&state, &name, false).await
+}
+
+async fn set_skill_enabled(state: &AppState, name: &str, enabled: bool) -> axum::response::Response {
+    match state.capabilities.set_skill_enabled(name, enabled).await {
+        Ok(Some(skill)) => {
+            tracing::info!("🧩 [Capabilities] Skill '{}' {}", name, if enabled { "enabled" } else { "disabled" });
+            (StatusCode::OK, Json(json!({"status": "success", "skill": skill}))).into_response()
+        }
+        Ok(None) => ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Skill Not Found",
+            format!("No skill named '{}' is registered.", name)
+        ).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Skill Save Failed",
+            format!("The system could not persist the skill '{}': {}", name, e)
+        ).into_response()
+    }
+}
+
 // PUT /system/workflows/:name
 pub async fn save_workflow(
     Path(_name): Path<String>,
@@ -93,3 +218,306 @@ pub async fn delete_workflow(
         ).into_response()
     }
 }
+
+/// GET /system/capabilities/export — Downloads every skill and workflow as a capability pack.
+///
+/// Skills are written to `skills/<name>.json` and workflows to `workflows/<name>.md`, the same
+/// layout `import_capabilities` reads back.
+pub async fn export_capabilities(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let mut skills = state.capabilities.all_skills();
+    let mut workflows = state.capabilities.all_workflows();
+    skills.sort_by(|a, b| a.name.cmp(&b.name));
+    workflows.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut files = Vec::with_capacity(skills.len() + workflows.len());
+    for skill in &skills {
+        match serde_json::to_vec_pretty(skill) {
+            Ok(json) => files.push((format!("skills/{}.json", safe_file_stem(&skill.name)), json)),
+            Err(e) => return ProblemDetails::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Capabilities Export Failed",
+                format!("The skill '{}' could not be serialized: {}", skill.name, e)
+            ).into_response(),
+        }
+    }
+    for workflow in workflows {
+        files.push((format!("workflows/{}.md", safe_file_stem(&workflow.name)), workflow.content.into_bytes()));
+    }
+
+    let archive = match crate::routes::archive::build_zip(&files) {
+        Ok(archive) => archive,
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Capabilities Export Failed",
+            format!("The capability pack could not be written: {}", e)
+        ).into_response(),
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/zip"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"tadpole_capabilities.zip\""),
+        ],
+        archive,
+    ).into_response()
+}
+
+/// POST /system/capabilities/import — Installs a capability pack from a ZIP upload.
+///
+/// Expects `multipart/form-data` with a `file` field holding a ZIP of skill `.json` files and
+/// workflow `.md` files (the workflow is named after the file). Entries that fail to parse or
+/// validate are reported in `errors` and do not stop the rest of the pack from importing.
+pub async fn import_capabilities(
+    State(state): State<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut file_data = Vec::new();
+    loop {
+        // A truncated or malformed upload must fail rather than import whatever arrived
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return ProblemDetails::new(
+                StatusCode::BAD_REQUEST,
+                "Upload Failed",
+                format!("The upload could not be read: {}", e)
+            ).into_response(),
+        };
+        if field.name() == Some("file") {
+            match field.bytes().await {
+                Ok(bytes) => file_data = bytes.to_vec(),
+                Err(e) => return ProblemDetails::new(
+                    StatusCode::BAD_REQUEST,
+                    "Upload Failed",
+                    format!("The uploaded file could not be read: {}", e)
+                ).into_response(),
+            }
+        }
+    }
+
+    if file_data.is_empty() {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "No File Provided",
+            "Upload a ZIP of skill JSON and workflow Markdown files in the 'file' field."
+        ).into_response();
+    }
+    if file_data.len() > MAX_CAPABILITY_IMPORT_BYTES {
+        return ProblemDetails::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Import Too Large",
+            format!("A capability pack may be at most {} bytes (got {}).", MAX_CAPABILITY_IMPORT_BYTES, file_data.len())
+        ).into_response();
+    }
+
+    let entries = match crate::routes::archive::read_zip(&file_data) {
+        Ok(entries) => entries,
+        Err(e) => return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid Archive",
+            format!("The uploaded file is not a readable ZIP archive: {}", e)
+        ).into_response(),
+    };
+
+    let mut imported_skills = 0usize;
+    let mut imported_workflows = 0usize;
+    let mut errors: Vec<serde_json::Value> = Vec::new();
+
+    for entry in entries {
+        // Directory records and the metadata macOS adds to archives carry no capabilities
+        if entry.name.ends_with('/') || entry.name.starts_with("__MACOSX/") {
+            continue;
+        }
+        let data = match entry.data {
+            Ok(data) => data,
+            Err(reason) => {
+                errors.push(json!({ "file": entry.name, "reason": reason }));
+                continue;
+            }
+        };
+        let path = std::path::Path::new(&entry.name);
+
+        let result = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => match serde_json::from_slice::<SkillDefinition>(&data) {
+                Ok(skill) => state.capabilities.save_skill(skill).await.map(|_| imported_skills += 1),
+                Err(e) => Err(anyhow::anyhow!("not a skill definition: {}", e)),
+            },
+            Some("md") => match String::from_utf8(data) {
+                Ok(content) if !content.trim().is_empty() => {
+                    let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+                    let workflow = WorkflowDefinition { id: None, name, content, doc_url: None, tags: None };
+                    state.capabilities.save_workflow(workflow).await.map(|_| imported_workflows += 1)
+                }
+                Ok(_) => Err(anyhow::anyhow!("workflow is empty")),
+                Err(_) => Err(anyhow::anyhow!("workflow is not valid UTF-8")),
+            },
+            _ => Err(anyhow::anyhow!("only .json skills and .md workflows can be imported")),
+        };
+        if let Err(e) = result {
+            errors.push(json!({ "file": entry.name, "reason": e.to_string() }));
+        }
+    }
+
+    tracing::info!("📥 [Import] Imported {} skills and {} workflows ({} errors).", imported_skills, imported_workflows, errors.len());
+
+    Json(json!({
+        "imported_skills": imported_skills,
+        "imported_workflows": imported_workflows,
+        "errors": errors
+    })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn state_in_temp_dir() -> (Arc<AppState>, std::path::PathBuf) {
+        let data_dir = std::env::temp_dir().join(format!("tadpole-capability-import-{}", uuid::Uuid::new_v4()));
+        let config = crate::config::TadpoleConfig { data_dir: Some(data_dir.clone()), ..Default::default() };
+        (Arc::new(AppState::for_tests(config).await), data_dir)
+    }
+
+    /// Uploads `pack` through `import_capabilities` and returns the JSON summary.
+    /// Posts `pack` as the `file` field, followed by `trailer` (the closing boundary when complete).
+    async fn import_response(state: &Arc<AppState>, pack: Vec<u8>, trailer: &str) -> axum::response::Response {
+        use axum::extract::FromRequest;
+
+        let boundary = "tadpole-import-boundary";
+        let mut body = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"pack.zip\"\r\nContent-Type: application/zip\r\n\r\n",
+            boundary
+        ).into_bytes();
+        body.extend_from_slice(&pack);
+        body.extend_from_slice(trailer.replace("{b}", boundary).as_bytes());
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri("/system/capabilities/import")
+            .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let multipart = Multipart::from_request(request, &()).await.unwrap();
+
+        import_capabilities(State(state.clone()), multipart).await.into_response()
+    }
+
+    async fn import(state: &Arc<AppState>, pack: Vec<u8>) -> serde_json::Value {
+        let response = import_response(state, pack, "\r\n--{b}--\r\n").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_import_capabilities_rejects_truncated_upload() {
+        let (state, data_dir) = state_in_temp_dir().await;
+        let pack = crate::routes::archive::build_zip(&[("pack/partial.md", b"# Partial".to_vec())]).unwrap();
+
+        // The file field is complete, but the upload is cut off inside the next part's headers
+        let response = import_response(&state, pack, "\r\n--{b}\r\nContent-Disposition: form-da").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(state.capabilities.workflow("partial").is_none(), "nothing may be imported from a truncated upload");
+
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_skill_metrics_endpoints_report_recorded_runs() {
+        let (state, data_dir) = state_in_temp_dir().await;
+        let skill: SkillDefinition = serde_json::from_value(json!({
+            "id": null, "name": "fetch_prices", "description": "d", "execution_command": "echo",
+            "schema": { "type": "object", "properties": {} }, "doc_url": null, "tags": null
+        })).unwrap();
+        state.capabilities.save_skill(skill).await.unwrap();
+
+        let response = get_skill_metrics(Path("missing".to_string()), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        state.capabilities.record_skill_run("fetch_prices", std::time::Duration::from_millis(100), false);
+        state.capabilities.record_skill_run("fetch_prices", std::time::Duration::from_millis(300), true);
+
+        let response = get_skill_metrics(Path("fetch_prices".to_string()), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["name"], "fetch_prices");
+        assert_eq!(json["invocations"], 2);
+        assert_eq!(json["avg_duration_ms"], 200.0);
+        assert_eq!(json["failure_rate"], 0.5);
+
+        let response = get_capabilities_metrics(State(state.clone())).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["invocations"], 2);
+        assert_eq!(json["failures"], 1);
+        assert_eq!(json["skills"][0]["name"], "fetch_prices");
+
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_import_capabilities_reports_invalid_entries() {
+        let (state, data_dir) = state_in_temp_dir().await;
+
+        let valid = json!({
+            "id": null, "name": "fetch_prices", "description": "Fetches prices", "execution_command": "python fetch.py",
+            "schema": { "type": "object", "properties": {} }, "doc_url": null, "tags": null
+        });
+        let invalid = json!({
+            "id": null, "name": "Bad Name", "description": "", "execution_command": "",
+            "schema": { "type": "object", "properties": {} }, "doc_url": null, "tags": null
+        });
+        let pack = crate::routes::archive::build_zip(&[
+            ("pack/fetch_prices.json", valid.to_string().into_bytes()),
+            ("pack/bad.json", invalid.to_string().into_bytes()),
+            ("pack/weekly-review.md", b"# Weekly review\n1. Gather findings".to_vec()),
+        ]).unwrap();
+
+        let json = import(&state, pack).await;
+        assert_eq!(json["imported_skills"], 1);
+        assert_eq!(json["imported_workflows"], 1);
+        let errors = json["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0]["file"], "pack/bad.json");
+        assert!(errors[0]["reason"].as_str().unwrap().contains("execution_command"));
+
+        assert!(state.capabilities.skill("fetch_prices").is_some());
+        assert!(state.capabilities.workflow("weekly-review").unwrap().content.starts_with("# Weekly review"));
+
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_export_then_import_round_trips_capabilities() {
+        let (source, source_dir) = state_in_temp_dir().await;
+        for name in ["alpha_scan", "beta-report"] {
+            let skill: SkillDefinition = serde_json::from_value(json!({
+                "id": null, "name": name, "description": "d", "execution_command": "echo",
+                "schema": { "type": "object", "properties": {} }, "doc_url": null, "tags": ["pack"]
+            })).unwrap();
+            source.capabilities.save_skill(skill).await.unwrap();
+        }
+        let workflow = WorkflowDefinition { id: None, name: "triage".to_string(), content: "# Triage".to_string(), doc_url: None, tags: None };
+        source.capabilities.save_workflow(workflow).await.unwrap();
+
+        let response = export_capabilities(State(source.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/zip");
+        assert_eq!(response.headers()[header::CONTENT_DISPOSITION], "attachment; filename=\"tadpole_capabilities.zip\"");
+        let pack = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec();
+        let names: Vec<String> = crate::routes::archive::read_zip(&pack).unwrap().into_iter().map(|e| e.name).collect();
+        assert!(names.contains(&"skills/beta-report.json".to_string()) && names.contains(&"workflows/triage.md".to_string()));
+
+        let (target, target_dir) = state_in_temp_dir().await;
+        let json = import(&target, pack).await;
+        assert_eq!(json["errors"], json!([]));
+        assert_eq!(json["imported_skills"], source.capabilities.all_skills().len());
+        assert_eq!(json["imported_workflows"], source.capabilities.all_workflows().len());
+        assert_eq!(target.capabilities.all_skills().len(), source.capabilities.all_skills().len());
+        assert_eq!(target.capabilities.workflow("triage").unwrap().content, "# Triage");
+
+        let _ = std::fs::remove_dir_all(&source_dir);
+        let _ = std::fs::remove_dir_all(&target_dir);
+    }
+}
@@ -5,14 +5,106 @@ use axum::{
     Json,
 };
 use serde::Serialize;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use crate::state::AppState;
 
+/// Deploy scripts looked for in the engine's working directory, in preference order.
+const DEPLOY_SCRIPTS: [&str; 2] = ["deploy.ps1", "deploy.sh"];
+
 #[derive(Serialize)]
 pub struct DeployResponse {
     pub status: String,
     pub output: Option<String>,
     pub error: Option<String>,
+    /// Deploy scripts found in the working directory (plus any `DEPLOY_SCRIPT` override).
+    pub scripts: Vec<String>,
+}
+
+/// The interpreter and script chosen to run a deploy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeployPlan {
+    pub program: String,
+    pub args: Vec<String>,
+    pub script: String,
+}
+
+impl DeployPlan {
+    fn new(program: &str, script: &str) -> Self {
+        let args = if program == "bash" {
+            vec![]
+        } else {
+            vec!["-ExecutionPolicy".to_string(), "Bypass".to_string(), "-File".to_string()]
+        };
+        Self { program: program.to_string(), args, script: script.to_string() }
+    }
+}
+
+/// Picks the deploy script and its interpreter.
+///
+/// `DEPLOY_SCRIPT` (`override_script`) wins when set; `.ps1` files run under PowerShell, anything
+/// else under `bash`. Otherwise `deploy.ps1` is used when PowerShell is available (always assumed
+/// on Windows), falling back to `bash deploy.sh`. `exists` and `on_path` are injected so the
+/// selection can be tested without touching the filesystem.
+pub fn select_deploy_script(
+    override_script: Option<&str>,
+    is_windows: bool,
+    exists: impl Fn(&Path) -> bool,
+    on_path: impl Fn(&str) -> bool,
+) -> Result<DeployPlan, String> {
+    let powershell = if is_windows {
+        Some("powershell.exe")
+    } else {
+        ["pwsh", "powershell"].into_iter().find(|p| on_path(p))
+    };
+
+    if let Some(script) = override_script {
+        if !exists(Path::new(script)) {
+            return Err(format!("DEPLOY_SCRIPT '{}' does not exist.", script));
+        }
+        if script.ends_with(".ps1") {
+            return powershell
+                .map(|ps| DeployPlan::new(ps, script))
+                .ok_or_else(|| format!("DEPLOY_SCRIPT '{}' needs PowerShell, which is not installed.", script));
+        }
+        return Ok(DeployPlan::new("bash", script));
+    }
+
+    let [ps1, sh] = DEPLOY_SCRIPTS;
+    if let Some(ps) = powershell.filter(|_| exists(Path::new(ps1))) {
+        return Ok(DeployPlan::new(ps, ps1));
+    }
+    if exists(Path::new(sh)) && (!is_windows || on_path("bash")) {
+        return Ok(DeployPlan::new("bash", sh));
+    }
+    Err("No runnable deploy script: expected deploy.ps1 with PowerShell or deploy.sh with bash.".to_string())
+}
+
+/// Lists the deploy scripts present, for the response body.
+fn available_scripts(override_script: Option<&str>, exists: impl Fn(&Path) -> bool) -> Vec<String> {
+    override_script.into_iter()
+        .chain(DEPLOY_SCRIPTS)
+        .filter(|s| exists(Path::new(s)))
+        .map(str::to_string)
+        .collect()
+}
+
+/// True if `program` is an executable file in one of the `PATH` directories.
+fn on_path(program: &str) -> bool {
+    let Some(paths) = std::env::var_os("PATH") else { return false };
+    std::env::split_paths(&paths).any(|dir| {
+        dir.join(program).is_file() || (cfg!(target_os = "windows") && dir.join(format!("{}.exe", program)).is_file())
+    })
+}
+
+/// Canonicalizes `script` and refuses anything that resolves outside `root` (via `..` or symlinks).
+fn confine_script(script: &str, root: &Path) -> Result<PathBuf, String> {
+    let root = std::fs::canonicalize(root).map_err(|e| format!("Cannot resolve working directory: {}", e))?;
+    let path = std::fs::canonicalize(root.join(script)).map_err(|e| format!("Cannot resolve deploy script '{}': {}", script, e))?;
+    if !path.starts_with(&root) || !path.is_file() {
+        return Err(format!("Deploy script '{}' must be a file inside {}.", script, root.display()));
+    }
+    Ok(path)
 }
 
 /// POST /engine/deploy — Triggers the deployment pipeline.
@@ -22,7 +114,7 @@ pub struct DeployResponse {
 /// Rejects all requests without a matching token with 401 Unauthorized.
 ///
 /// **Async**: Uses `tokio::process::Command` to avoid blocking the Tokio runtime
-/// while the PowerShell deployment script runs.
+/// while the deployment script runs. See `select_deploy_script` for which script is used.
 pub async fn trigger_deploy(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
@@ -45,16 +137,43 @@ pub async fn trigger_deploy(
                     status: "unauthorized".to_string(),
                     output: None,
                     error: Some("Missing or invalid Authorization header.".to_string()),
+                    scripts: vec![],
                 }),
             );
         }
     }
 
-    tracing::info!("🚀 Authenticated deploy triggered. Running deploy.ps1...");
+    let override_script = std::env::var("DEPLOY_SCRIPT").ok().filter(|s| !s.trim().is_empty());
+    let exists = |p: &Path| p.exists();
+    let scripts = available_scripts(override_script.as_deref(), exists);
+
+    let plan = select_deploy_script(override_script.as_deref(), cfg!(target_os = "windows"), exists, on_path)
+        .and_then(|plan| {
+            let cwd = std::env::current_dir().map_err(|e| format!("Cannot read working directory: {}", e))?;
+            confine_script(&plan.script, &cwd).map(|path| (plan, path))
+        });
+    let (plan, script_path) = match plan {
+        Ok(selected) => selected,
+        Err(e) => {
+            tracing::error!("❌ Deployment aborted: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DeployResponse {
+                    status: "error".to_string(),
+                    output: None,
+                    error: Some(e),
+                    scripts,
+                }),
+            );
+        }
+    };
+
+    tracing::info!("🚀 Authenticated deploy triggered. Running {} with {}...", script_path.display(), plan.program);
 
     // --- Async Process Execution ---
-    let result = tokio::process::Command::new("powershell.exe")
-        .args(["-ExecutionPolicy", "Bypass", "-File", "deploy.ps1"])
+    let result = tokio::process::Command::new(&plan.program)
+        .args(&plan.args)
+        .arg(&script_path)
         .output()
         .await;
 
@@ -65,6 +184,7 @@ pub async fn trigger_deploy(
 
             if output.status.success() {
                 tracing::info!("✅ Deployment succeeded.");
+                *state.last_deploy_at.lock().unwrap_or_else(|e| e.into_inner()) = Some(chrono::Utc::now());
                 if !stderr.is_empty() {
                     tracing::warn!("⚠️ Deployment stderr:\n{}", stderr);
                 }
@@ -74,6 +194,7 @@ pub async fn trigger_deploy(
                         status: "success".to_string(),
                         output: Some(stdout),
                         error: None,
+                        scripts,
                     }),
                 )
             } else {
@@ -84,20 +205,94 @@ pub async fn trigger_deploy(
                         status: "error".to_string(),
                         output: Some(stdout),
                         error: Some(stderr),
+                        scripts,
                     }),
                 )
             }
         }
         Err(e) => {
-            tracing::error!("❌ Failed to spawn PowerShell process: {}", e);
+            tracing::error!("❌ Failed to spawn {} for the deploy script: {}", plan.program, e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(DeployResponse {
                     status: "error".to_string(),
                     output: None,
                     error: Some(e.to_string()),
+                    scripts,
                 }),
             )
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(present: &'static [&'static str]) -> impl Fn(&Path) -> bool {
+        move |p: &Path| present.iter().any(|f| Path::new(f) == p)
+    }
+
+    fn programs(installed: &'static [&'static str]) -> impl Fn(&str) -> bool {
+        move |p: &str| installed.contains(&p)
+    }
+
+    #[test]
+    fn windows_prefers_powershell_script() {
+        let plan = select_deploy_script(None, true, files(&["deploy.ps1", "deploy.sh"]), programs(&[])).unwrap();
+        assert_eq!(plan.program, "powershell.exe");
+        assert_eq!(plan.script, "deploy.ps1");
+        assert_eq!(plan.args, vec!["-ExecutionPolicy", "Bypass", "-File"]);
+    }
+
+    #[test]
+    fn unix_falls_back_to_bash_without_powershell() {
+        let plan = select_deploy_script(None, false, files(&["deploy.ps1", "deploy.sh"]), programs(&["bash"])).unwrap();
+        assert_eq!(plan, DeployPlan { program: "bash".to_string(), args: vec![], script: "deploy.sh".to_string() });
+    }
+
+    #[test]
+    fn unix_uses_pwsh_when_installed() {
+        let plan = select_deploy_script(None, false, files(&["deploy.ps1", "deploy.sh"]), programs(&["pwsh", "bash"])).unwrap();
+        assert_eq!(plan.program, "pwsh");
+        assert_eq!(plan.script, "deploy.ps1");
+    }
+
+    #[test]
+    fn missing_scripts_are_an_error() {
+        assert!(select_deploy_script(None, false, files(&["deploy.ps1"]), programs(&["bash"])).is_err());
+        assert!(select_deploy_script(None, true, files(&["deploy.sh"]), programs(&[])).is_err());
+    }
+
+    #[test]
+    fn deploy_script_override_wins_and_picks_interpreter_by_extension() {
+        let plan = select_deploy_script(Some("ops/release.sh"), true, files(&["deploy.ps1", "ops/release.sh"]), programs(&[])).unwrap();
+        assert_eq!((plan.program.as_str(), plan.script.as_str()), ("bash", "ops/release.sh"));
+
+        let plan = select_deploy_script(Some("ops/release.ps1"), false, files(&["ops/release.ps1"]), programs(&["pwsh"])).unwrap();
+        assert_eq!(plan.program, "pwsh");
+
+        assert!(select_deploy_script(Some("ops/release.ps1"), false, files(&["ops/release.ps1"]), programs(&[])).is_err());
+        assert!(select_deploy_script(Some("missing.sh"), false, files(&["deploy.sh"]), programs(&[])).is_err());
+    }
+
+    #[test]
+    fn available_scripts_lists_only_existing_files() {
+        assert_eq!(available_scripts(Some("ops/release.sh"), files(&["deploy.sh", "ops/release.sh"])), vec!["ops/release.sh", "deploy.sh"]);
+        assert!(available_scripts(None, files(&[])).is_empty());
+    }
+
+    #[test]
+    fn confine_script_rejects_paths_outside_the_root() {
+        let root = std::env::temp_dir().join(format!("tadpole_deploy_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("inner")).unwrap();
+        std::fs::write(root.join("deploy.sh"), "echo ok").unwrap();
+
+        let inner = root.join("inner");
+        assert!(confine_script("../deploy.sh", &inner).is_err(), "traversal out of the root must be refused");
+        assert_eq!(confine_script("deploy.sh", &root).unwrap(), std::fs::canonicalize(root.join("deploy.sh")).unwrap());
+        assert!(confine_script("inner", &root).is_err(), "directories are not scripts");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,12 +1,19 @@
 use axum::{
-    extract::State,
+    extract::{Multipart, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
+use serde::Serialize;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use crate::agent::types::EngineAgent;
+use crate::routes::error::ProblemDetails;
 use crate::state::AppState;
 
+/// Maximum number of agents a single import upload may contain.
+const MAX_IMPORT_AGENTS: usize = 100;
+
 /// POST /engine/kill — Halt all running agents.
 ///
 /// Sets every agent's status to "idle" and clears their active missions.
@@ -48,32 +55,490 @@ pub async fn kill_agents(
     }))).into_response()
 }
 
+/// Longest `shutdown_engine` waits for in-flight agent runs before stopping anyway.
+pub const SHUTDOWN_DRAIN_SECS: u64 = 30;
+
 /// POST /engine/shutdown — Graceful server shutdown.
 ///
-/// Persists all agent state to the database and then terminates the process.
-/// The caller should expect the connection to drop after receiving the response.
+/// Responds immediately, then refuses new tasks, waits up to `SHUTDOWN_DRAIN_SECS` for running
+/// agents to finish, persists all agent state and signals the listener to stop. The caller
+/// should expect the connection to drop once the drain completes.
 pub async fn shutdown_engine(
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    tracing::warn!("💀 [Shutdown] Engine shutdown requested by operator. Persisting state...");
+    if state.is_shutting_down.swap(true, Ordering::SeqCst) {
+        return ProblemDetails::new(
+            StatusCode::CONFLICT,
+            "Shutdown In Progress",
+            "The engine is already shutting down."
+        ).into_response();
+    }
 
-    // Save all agents before shutting down
-    state.save_agents().await;
+    let active_agents = state.active_agent_count.load(Ordering::SeqCst);
+    tracing::warn!("💀 [Shutdown] Engine shutdown requested by operator. Draining {} active agents...", active_agents);
 
     state.emit_event(serde_json::json!({
         "type": "engine:shutdown",
-        "message": "Engine shutting down. Goodbye."
+        "message": "Engine shutting down. Goodbye.",
+        "activeAgents": active_agents
     }));
 
-    // Spawn a delayed exit so the response can be sent first
-    tokio::spawn(async {
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    tokio::spawn(async move {
+        if !state.wait_for_idle_agents(std::time::Duration::from_secs(SHUTDOWN_DRAIN_SECS)).await {
+            tracing::warn!(
+                "⚠️ [Shutdown] {} agents still running after {}s. Shutting down anyway.",
+                state.active_agent_count.load(Ordering::SeqCst),
+                SHUTDOWN_DRAIN_SECS
+            );
+        }
+        state.save_agents().await;
         tracing::info!("👋 Engine process exiting.");
-        std::process::exit(0);
+        let _ = state.shutdown_tx.send(());
     });
 
     (StatusCode::OK, Json(serde_json::json!({
         "status": "ok",
-        "message": "Shutdown initiated. State persisted."
+        "message": "Shutdown initiated. New tasks are refused while active agents finish.",
+        "activeAgents": active_agents
     }))).into_response()
 }
+
+/// The `503` returned by task-submitting routes once `shutdown_engine` has been called.
+pub(crate) fn engine_shutting_down() -> axum::response::Response {
+    ProblemDetails::new(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Engine Shutting Down",
+        "The engine is shutting down and no longer accepts new tasks."
+    ).into_response()
+}
+
+/// Non-sensitive runtime configuration of the running engine.
+/// Secrets (deploy token, provider keys) are deliberately absent from this struct.
+#[derive(Debug, Serialize)]
+pub struct RuntimeConfig {
+    pub port: u16,
+    /// Only the database type (e.g. "sqlite"), never the full connection string.
+    #[serde(rename = "databaseUrl")]
+    pub database_url: String,
+    /// Seconds a pending oversight entry waits before it is rejected.
+    #[serde(rename = "oversightTimeoutSecs")]
+    pub oversight_timeout_secs: u64,
+    #[serde(rename = "maxTaskTimeoutSecs")]
+    pub max_task_timeout_secs: u64,
+    #[serde(rename = "maxSwarmDepth")]
+    pub max_swarm_depth: u32,
+    #[serde(rename = "maxTaskLengthBytes")]
+    pub max_task_length_bytes: usize,
+    #[serde(rename = "maxConcurrentMissions")]
+    pub max_concurrent_missions: usize,
+    /// "abort": a failing lifecycle hook aborts the tool call; "warn": it is only reported.
+    #[serde(rename = "hookFailurePolicy")]
+    pub hook_failure_policy: &'static str,
+    #[serde(rename = "hookTimeoutSecs")]
+    pub hook_timeout_secs: u64,
+    /// Size cap of each agent workspace.
+    #[serde(rename = "workspaceQuotaBytes")]
+    pub workspace_quota_bytes: u64,
+    /// Days mission history is kept; `null` while no retention is configured and missions are kept indefinitely.
+    #[serde(rename = "missionRetentionDays")]
+    pub mission_retention_days: Option<u32>,
+    pub version: &'static str,
+    /// When this binary was compiled (RFC 3339, set by `build.rs`).
+    #[serde(rename = "builtAt")]
+    pub built_at: &'static str,
+}
+
+impl RuntimeConfig {
+    pub fn current(state: &AppState) -> Self {
+        Self {
+            port: state.port,
+            database_url: state.database_backend.clone(),
+            oversight_timeout_secs: state.oversight_timeout_secs,
+            max_task_timeout_secs: state.max_task_timeout_secs,
+            max_swarm_depth: crate::agent::runner::MAX_SWARM_DEPTH,
+            max_task_length_bytes: crate::agent::runner::MAX_TASK_LENGTH,
+            max_concurrent_missions: state.mission_concurrency_limit,
+            hook_failure_policy: state.hooks.failure_mode().as_str(),
+            hook_timeout_secs: state.hooks.timeout_secs(),
+            workspace_quota_bytes: crate::adapter::filesystem::max_workspace_bytes(),
+            mission_retention_days: None,
+            version: env!("CARGO_PKG_VERSION"),
+            built_at: env!("TADPOLE_BUILT_AT"),
+        }
+    }
+}
+
+/// GET /engine/config — Returns the non-sensitive runtime configuration.
+pub async fn get_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(RuntimeConfig::current(&state))
+}
+
+/// GET /engine/concurrency — Reports mission concurrency slots.
+pub async fn get_concurrency(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let max = state.mission_concurrency_limit;
+    let available = state.max_concurrent_missions.available_permits();
+    Json(serde_json::json!({
+        "max": max,
+        "inFlight": max.saturating_sub(available),
+        "queued": state.queued_missions.load(Ordering::SeqCst)
+    }))
+}
+
+/// GET /engine/uptime — Process uptime and lifetime mission statistics.
+/// `totalCostUsd` and `totalTokensUsed` sum recorded mission spend and step usage, so cost resets
+/// and deleted agents do not lower them; each is `null` if its table could not be read.
+pub async fn get_uptime(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let total_cost_usd = sqlx::query_scalar::<_, f64>("SELECT COALESCE(SUM(cost_usd), 0.0) FROM mission_history")
+        .fetch_one(&state.pool)
+        .await
+        .map_err(|e| tracing::warn!("⚠️ [Uptime] Mission spend unavailable: {}", e))
+        .ok();
+    let total_tokens_used = sqlx::query_scalar::<_, i64>(
+        "SELECT COALESCE(SUM(json_extract(metadata, '$.usage.totalTokens')), 0) FROM mission_logs WHERE json_valid(metadata)")
+        .fetch_one(&state.pool)
+        .await
+        .map_err(|e| tracing::warn!("⚠️ [Uptime] Token usage unavailable: {}", e))
+        .ok();
+
+    Json(serde_json::json!({
+        "uptimeSecs": state.uptime_secs(),
+        "startedAt": state.started_at.to_rfc3339(),
+        "missionsCompletedLifetime": state.missions_completed.load(Ordering::Relaxed),
+        "missionsFailedLifetime": state.missions_failed.load(Ordering::Relaxed),
+        "totalCostUsd": total_cost_usd,
+        "totalTokensUsed": total_tokens_used,
+        "version": env!("CARGO_PKG_VERSION")
+    }))
+}
+
+/// POST /system/reload-context — Re-reads IDENTITY.md and LONG_TERM_MEMORY.md into the prompt cache.
+pub async fn reload_context(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    state.reload_system_context().await;
+    let system_context = state.system_context.read().await;
+
+    tracing::info!("🔄 [Context] System context reloaded from disk.");
+
+    Json(serde_json::json!({
+        "status": "ok",
+        "identityBytes": system_context.identity.len(),
+        "memoryBytes": system_context.long_term_memory.len()
+    }))
+}
+
+/// POST /engine/import-agents — Bulk-imports agents from a legacy `agents.json` upload.
+///
+/// Expects `multipart/form-data` with a `file` field holding a JSON array of agents.
+/// Existing IDs are never overwritten: they are reported in `skipped`.
+pub async fn import_agents(
+    State(state): State<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut file_data = Vec::new();
+    loop {
+        // A truncated or malformed upload must fail rather than import whatever arrived
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return ProblemDetails::new(
+                StatusCode::BAD_REQUEST,
+                "Upload Failed",
+                format!("The upload could not be read: {}", e)
+            ).into_response(),
+        };
+        if field.name() == Some("file") {
+            match field.bytes().await {
+                Ok(bytes) => file_data = bytes.to_vec(),
+                Err(e) => return ProblemDetails::new(
+                    StatusCode::BAD_REQUEST,
+                    "Upload Failed",
+                    format!("The uploaded file could not be read: {}", e)
+                ).into_response(),
+            }
+        }
+    }
+
+    if file_data.is_empty() {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "No File Provided",
+            "Upload a JSON array of agents in the 'file' field."
+        ).into_response();
+    }
+
+    let entries: Vec<serde_json::Value> = match serde_json::from_slice(&file_data) {
+        Ok(entries) => entries,
+        Err(e) => return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid Agent File",
+            format!("The uploaded file is not a JSON array of agents: {}", e)
+        ).into_response(),
+    };
+
+    if entries.len() > MAX_IMPORT_AGENTS {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Import Too Large",
+            format!("An import may contain at most {} agents (got {}).", MAX_IMPORT_AGENTS, entries.len())
+        ).into_response();
+    }
+
+    let mut imported = 0usize;
+    let mut skipped: Vec<String> = Vec::new();
+    let mut failed: Vec<serde_json::Value> = Vec::new();
+
+    for entry in entries {
+        let raw_id = entry.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string();
+
+        let mut agent: EngineAgent = match serde_json::from_value(entry) {
+            Ok(agent) => agent,
+            Err(e) => {
+                failed.push(serde_json::json!({ "id": raw_id, "reason": e.to_string() }));
+                continue;
+            }
+        };
+
+        if agent.id.trim().is_empty() || agent.name.trim().is_empty() || agent.role.trim().is_empty() {
+            failed.push(serde_json::json!({ "id": raw_id, "reason": "'id', 'name' and 'role' are required" }));
+            continue;
+        }
+        if state.agents.contains_key(&agent.id) {
+            skipped.push(agent.id);
+            continue;
+        }
+
+        agent.status = "idle".to_string();
+        agent.cost_usd = 0.0;
+        agent.tokens_used = 0;
+
+        if let Err(e) = crate::agent::persistence::create_agent_db(&state.pool, &agent).await {
+            failed.push(serde_json::json!({ "id": agent.id, "reason": e.to_string() }));
+            continue;
+        }
+
+        state.emit_event(serde_json::json!({
+            "type": "agent:create",
+            "data": agent
+        }));
+        state.agents.insert(agent.id.clone(), agent);
+        imported += 1;
+    }
+
+    tracing::info!("📥 [Import] Imported {} agents ({} skipped, {} failed).", imported, skipped.len(), failed.len());
+
+    Json(serde_json::json!({
+        "imported": imported,
+        "skipped": skipped,
+        "failed": failed
+    })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_config_exposes_limits_without_secrets() {
+        use crate::agent::hooks::{HookFailureMode, HooksManager};
+
+        let mut state = AppState::for_tests(crate::config::TadpoleConfig {
+            port: Some(9123),
+            oversight_timeout_secs: Some(42),
+            max_task_timeout_secs: Some(600),
+            ..Default::default()
+        }).await;
+        state.hooks = Arc::new(HooksManager::with_settings(std::env::temp_dir(), 7, HookFailureMode::Warn, None));
+        state.mission_concurrency_limit = 3;
+
+        let response = get_config(State(Arc::new(state))).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["port"], 9123);
+        assert_eq!(json["databaseUrl"], "sqlite");
+        assert_eq!(json["oversightTimeoutSecs"], 42);
+        assert_eq!(json["maxTaskTimeoutSecs"], 600);
+        assert_eq!(json["maxConcurrentMissions"], 3);
+        assert_eq!(json["hookFailurePolicy"], "warn");
+        assert_eq!(json["hookTimeoutSecs"], 7);
+        assert_eq!(json["maxSwarmDepth"], 5);
+        assert!(json.get("missionRetentionDays").is_some_and(|v| v.is_null()), "missionRetentionDays must be present and null");
+        assert_ne!(json["builtAt"], json["version"]);
+        assert!(chrono::DateTime::parse_from_rfc3339(json["builtAt"].as_str().unwrap()).is_ok());
+
+        for (field, value) in json.as_object().unwrap() {
+            let value = value.to_string().to_lowercase();
+            assert!(!value.contains("token") && !value.contains("key"), "field '{}' leaks a secret-like value", field);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uptime_totals_survive_agent_cost_reset() {
+        use axum::extract::Path;
+
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let mission = crate::agent::mission::create_mission(&state.pool, "1", "Billed", 1.0, &[]).await.unwrap();
+        crate::agent::mission::update_mission(&state.pool, &mission.id, crate::agent::types::MissionStatus::Completed, 0.25).await.unwrap();
+        let usage = serde_json::json!({ "usage": { "inputTokens": 80, "outputTokens": 20, "totalTokens": 100 } });
+        crate::agent::mission::log_step(&state.pool, &mission.id, "1", "Agent", "done", "success", Some(usage)).await.unwrap();
+        {
+            let mut agent = state.agents.get_mut("1").unwrap();
+            agent.cost_usd = 0.25;
+            agent.tokens_used = 100;
+        }
+
+        let totals = || async {
+            let response = get_uptime(State(state.clone())).await.into_response();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            (json["totalCostUsd"].clone(), json["totalTokensUsed"].clone())
+        };
+        let before = totals().await;
+        assert_eq!(before, (serde_json::json!(0.25), serde_json::json!(100)));
+
+        let response = crate::routes::agent::reset_cost(Path("1".to_string()), State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.agents.get("1").unwrap().tokens_used, 0);
+        assert_eq!(totals().await, before, "resetting an agent must not lower the lifetime totals");
+    }
+
+    #[tokio::test]
+    async fn test_mission_slots_cap_concurrent_missions() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let config = crate::config::TadpoleConfig { max_concurrent_missions: Some(2), ..Default::default() };
+        let state = Arc::new(AppState::for_tests(config).await);
+
+        // Missions hold their slot until released, so the snapshot below does not race them
+        let thinking = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (release_tx, release_rx) = tokio::sync::watch::channel(false);
+        let mut handles = Vec::new();
+        for _ in 0..5 {
+            let state = state.clone();
+            let thinking = thinking.clone();
+            let peak = peak.clone();
+            let mut release_rx = release_rx.clone();
+            handles.push(tokio::spawn(async move {
+                let _slot = state.acquire_mission_slot().await;
+                let now = thinking.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                let _ = release_rx.wait_for(|released| *released).await;
+                thinking.fetch_sub(1, Ordering::SeqCst);
+            }));
+        }
+
+        for _ in 0..250 {
+            if thinking.load(Ordering::SeqCst) == 2 && state.queued_missions.load(Ordering::SeqCst) == 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let response = get_concurrency(State(state.clone())).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["max"], 2);
+        assert_eq!(json["inFlight"], 2);
+        assert_eq!(json["queued"], 3);
+
+        release_tx.send(true).unwrap();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2, "At most 2 missions may be thinking at once");
+        assert_eq!(state.max_concurrent_missions.available_permits(), 2);
+    }
+
+    /// Posts `json` as the `file` field of a multipart upload ending in `trailer` (`{b}` is the boundary).
+    async fn import_agents_response(state: &Arc<AppState>, json: &str, trailer: &str) -> axum::response::Response {
+        use axum::extract::FromRequest;
+
+        let boundary = "tadpole-import-boundary";
+        let body = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"agents.json\"\r\nContent-Type: application/json\r\n\r\n{json}{trailer}",
+            b = boundary,
+            json = json,
+            trailer = trailer.replace("{b}", boundary)
+        );
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri("/engine/import-agents")
+            .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let multipart = Multipart::from_request(request, &()).await.unwrap();
+
+        import_agents(State(state.clone()), multipart).await.into_response()
+    }
+
+    #[tokio::test]
+    async fn test_import_agents_skips_existing_ids() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let template = state.agents.get("1").unwrap().clone();
+        let suffix = uuid::Uuid::new_v4();
+        let agents: Vec<EngineAgent> = [format!("imported-a-{}", suffix), "1".to_string(), format!("imported-b-{}", suffix)]
+            .into_iter()
+            .map(|id| EngineAgent { id, cost_usd: 12.5, status: "active".to_string(), ..template.clone() })
+            .collect();
+
+        let response = import_agents_response(&state, &serde_json::to_string(&agents).unwrap(), "\r\n--{b}--\r\n").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["imported"], 2);
+        assert_eq!(json["skipped"], serde_json::json!(["1"]));
+
+        let imported = state.agents.get(&format!("imported-a-{}", suffix)).unwrap().clone();
+        assert_eq!(imported.status, "idle");
+        assert_eq!(imported.cost_usd, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_import_agents_rejects_truncated_upload() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let template = state.agents.get("1").unwrap().clone();
+        let id = format!("truncated-{}", uuid::Uuid::new_v4());
+        let agents = vec![EngineAgent { id: id.clone(), ..template }];
+
+        // The file field is complete, but the upload is cut off inside the next part's headers
+        let response = import_agents_response(&state, &serde_json::to_string(&agents).unwrap(), "\r\n--{b}\r\nContent-Disposition: form-da").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["title"], "Upload Failed");
+        assert!(!state.agents.contains_key(&id), "nothing may be imported from a truncated upload");
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_refuses_tasks_and_waits_for_active_agents() {
+        use axum::extract::Path;
+        use std::time::Duration;
+
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let mut shutdown_rx = state.shutdown_tx.subscribe();
+        let active = state.track_active_agent();
+
+        let response = shutdown_engine(State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["activeAgents"], 1);
+
+        let again = shutdown_engine(State(state.clone())).await.into_response();
+        assert_eq!(again.status(), StatusCode::CONFLICT);
+
+        let payload: crate::agent::types::TaskPayload = serde_json::from_value(serde_json::json!({ "message": "one more thing" })).unwrap();
+        let response = crate::routes::agent::send_task(Path("1".to_string()), State(state.clone()), Json(payload)).await.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // The listener is only released once the running agent finishes
+        assert!(!state.wait_for_idle_agents(Duration::from_millis(150)).await);
+        assert!(tokio::time::timeout(Duration::from_millis(200), shutdown_rx.recv()).await.is_err());
+        drop(active);
+        tokio::time::timeout(Duration::from_secs(5), shutdown_rx.recv()).await
+            .expect("shutdown signalled after the drain")
+            .unwrap();
+        assert_eq!(state.active_agent_count.load(Ordering::SeqCst), 0);
+    }
+}
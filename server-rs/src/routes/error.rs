@@ -18,6 +18,9 @@ pub struct ProblemDetails {
     pub instance: Option<String>,
     /// Legacy field for backward compatibility with frontend parts still expecting "message"
     pub message: String,
+    /// Problem-specific extension members, serialized alongside the standard ones.
+    #[serde(flatten)]
+    pub extensions: serde_json::Map<String, serde_json::Value>,
 }
 
 impl ProblemDetails {
@@ -28,10 +31,18 @@ impl ProblemDetails {
             title: title.into(),
             status: status.as_u16(),
             detail: detail_str.clone(),
-            instance: None,
+            // Correlates the error with the access log entry and `X-Request-Id` header
+            instance: crate::middleware::request_log::current_request_id(),
             message: detail_str,
+            extensions: serde_json::Map::new(),
         }
     }
+
+    /// Adds an extension member such as the list of names that failed to resolve.
+    pub fn with_extension(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.extensions.insert(key.into(), value);
+        self
+    }
 }
 
 impl IntoResponse for ProblemDetails {
@@ -1,5 +1,7 @@
-use axum::Json;
+use axum::{extract::State, Json};
 use serde::Serialize;
+use std::sync::Arc;
+use crate::state::AppState;
 
 #[derive(Serialize)]
 pub struct HealthResponse {
@@ -14,3 +16,78 @@ pub async fn health_check() -> Json<HealthResponse> {
         heartbeat: chrono::Utc::now().to_rfc3339(),
     })
 }
+
+#[derive(Debug, Serialize)]
+pub struct CapabilityCounts {
+    pub skills: usize,
+    pub workflows: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SystemStatus {
+    pub uptime_secs: u64,
+    pub agent_count: usize,
+    /// Agents currently `thinking` or `active`.
+    pub active_agents: usize,
+    pub pending_oversight: usize,
+    pub capabilities_loaded: CapabilityCounts,
+    pub db_pool_size: u32,
+    /// Open WebSocket connections (and any other live event subscribers).
+    pub ws_subscribers: usize,
+    /// `None` if the mission table could not be read.
+    pub total_missions: Option<i64>,
+    pub last_deploy_at: Option<String>,
+    pub version: &'static str,
+}
+
+/// GET /system/status
+/// Diagnostics for monitoring infrastructure. Unauthenticated, so it reports counts only.
+pub async fn system_status(State(state): State<Arc<AppState>>) -> Json<SystemStatus> {
+    let total_missions = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM mission_history")
+        .fetch_one(&state.pool)
+        .await
+        .map_err(|e| tracing::warn!("⚠️ [Status] Mission count unavailable: {}", e))
+        .ok();
+
+    Json(SystemStatus {
+        uptime_secs: state.uptime_secs(),
+        agent_count: state.agents.len(),
+        active_agents: state.agents.iter().filter(|a| a.status == "thinking" || a.status == "active").count(),
+        pending_oversight: state.oversight_queue.iter().filter(|e| e.status != "timed_out").count(),
+        capabilities_loaded: CapabilityCounts {
+            skills: state.capabilities.all_skills().len(),
+            workflows: state.capabilities.all_workflows().len(),
+        },
+        db_pool_size: state.pool.size(),
+        ws_subscribers: state.event_tx.receiver_count(),
+        total_missions,
+        last_deploy_at: state.last_deploy_at.lock().unwrap_or_else(|e| e.into_inner()).map(|t| t.to_rfc3339()),
+        version: env!("CARGO_PKG_VERSION"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_system_status_reports_every_field() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let _subscriber = state.event_tx.subscribe();
+
+        let Json(status) = system_status(State(state.clone())).await;
+        let json = serde_json::to_value(&status).unwrap();
+
+        for field in ["uptime_secs", "agent_count", "active_agents", "pending_oversight", "db_pool_size", "ws_subscribers", "total_missions"] {
+            assert!(json[field].as_i64().is_some_and(|n| n >= 0), "{} must be a non-negative integer, got {}", field, json[field]);
+        }
+        for field in ["skills", "workflows"] {
+            assert!(json["capabilities_loaded"][field].as_u64().is_some(), "capabilities_loaded.{} missing", field);
+        }
+        assert!(json.as_object().unwrap().contains_key("last_deploy_at"));
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(status.agent_count, state.agents.len());
+        assert!(status.ws_subscribers >= 1);
+        assert!(status.db_pool_size >= 1);
+    }
+}
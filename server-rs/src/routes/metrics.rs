@@ -0,0 +1,108 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use prometheus::{CounterVec, Encoder, HistogramOpts, HistogramVec, IntGauge, Opts, Registry, TextEncoder};
+use std::sync::Arc;
+use crate::state::AppState;
+
+/// Bucket bounds (seconds) for `tadpole_agent_task_duration_seconds`.
+const TASK_DURATION_BUCKETS: [f64; 10] = [0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// Engine metrics exposed at `GET /metrics`. Created once in `AppState::new`.
+pub struct Metrics {
+    registry: Registry,
+    /// Finished tasks by outcome (`completed` | `failed`).
+    pub agent_task_total: CounterVec,
+    pub agent_task_duration_seconds: HistogramVec,
+    /// Tokens consumed, split by `direction` (`input` | `output`).
+    pub token_usage_total: CounterVec,
+    pub mission_cost_usd_total: CounterVec,
+    pub oversight_pending: IntGauge,
+    /// Provider calls by outcome (`success` | `error`).
+    pub provider_request_total: CounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        // Names, labels and buckets are fixed, so construction and registration cannot fail
+        let counter = |name: &str, help: &str, labels: &[&str]| CounterVec::new(Opts::new(name, help), labels).expect("valid counter");
+        let metrics = Self {
+            registry: Registry::new(),
+            agent_task_total: counter("tadpole_agent_task_total", "Agent tasks finished, by outcome.", &["agent_id", "status"]),
+            agent_task_duration_seconds: HistogramVec::new(
+                HistogramOpts::new("tadpole_agent_task_duration_seconds", "Wall-clock duration of agent tasks.").buckets(TASK_DURATION_BUCKETS.to_vec()),
+                &["agent_id"],
+            ).expect("valid histogram"),
+            token_usage_total: counter("tadpole_token_usage_total", "Tokens consumed by agents.", &["agent_id", "direction"]),
+            mission_cost_usd_total: counter("tadpole_mission_cost_usd_total", "Mission spend in USD.", &["agent_id"]),
+            oversight_pending: IntGauge::new("tadpole_oversight_pending", "Oversight entries awaiting a decision.").expect("valid gauge"),
+            provider_request_total: counter("tadpole_provider_request_total", "Model provider requests, by outcome.", &["provider", "status"]),
+        };
+
+        let collectors: [Box<dyn prometheus::core::Collector>; 6] = [
+            Box::new(metrics.agent_task_total.clone()),
+            Box::new(metrics.agent_task_duration_seconds.clone()),
+            Box::new(metrics.token_usage_total.clone()),
+            Box::new(metrics.mission_cost_usd_total.clone()),
+            Box::new(metrics.oversight_pending.clone()),
+            Box::new(metrics.provider_request_total.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).expect("metric names are unique");
+        }
+        metrics
+    }
+
+    /// Encodes every metric in the Prometheus text exposition format (version 0.0.4).
+    pub fn encode(&self) -> String {
+        let mut out = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut out) {
+            tracing::error!("❌ [Metrics] Encoding failed: {}", e);
+        }
+        String::from_utf8(out).unwrap_or_default()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// GET /metrics
+/// Prometheus scrape target. Unauthenticated, like `/engine/health`.
+pub async fn get_metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    state.metrics.oversight_pending.set(state.oversight_queue.len() as i64);
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        state.metrics.encode(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_counters_histograms_and_gauges() {
+        let metrics = Metrics::new();
+        metrics.agent_task_total.with_label_values(&["1", "completed"]).inc();
+        metrics.agent_task_total.with_label_values(&["1", "completed"]).inc();
+        metrics.agent_task_duration_seconds.with_label_values(&["1"]).observe(3.0);
+        metrics.oversight_pending.set(2);
+        metrics.token_usage_total.with_label_values(&["a\"b", "input"]).inc_by(10.0);
+
+        let text = metrics.encode();
+        assert!(text.contains("# TYPE tadpole_agent_task_total counter"));
+        assert!(text.contains("tadpole_agent_task_total{agent_id=\"1\",status=\"completed\"} 2"));
+        assert!(text.contains("tadpole_agent_task_duration_seconds_bucket{agent_id=\"1\",le=\"2.5\"} 0"));
+        assert!(text.contains("tadpole_agent_task_duration_seconds_bucket{agent_id=\"1\",le=\"5\"} 1"));
+        assert!(text.contains("tadpole_agent_task_duration_seconds_bucket{agent_id=\"1\",le=\"+Inf\"} 1"));
+        assert!(text.contains("tadpole_agent_task_duration_seconds_sum{agent_id=\"1\"} 3"));
+        assert!(text.contains("tadpole_oversight_pending 2"));
+        assert!(text.contains("tadpole_token_usage_total{agent_id=\"a\\\"b\",direction=\"input\"} 10"));
+    }
+}
@@ -0,0 +1,1029 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::agent::mission::MissionFilter;
+use crate::agent::types::{MissionExport, MissionFinding, MissionStatus};
+use crate::routes::error::ProblemDetails;
+use crate::state::AppState;
+
+pub(crate) const DEFAULT_PER_PAGE: i64 = 20;
+pub(crate) const MAX_PER_PAGE: i64 = 100;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MissionListQuery {
+    pub agent_id: Option<String>,
+    pub status: Option<MissionStatus>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// `true` lists only missions waiting on an incomplete prerequisite.
+    #[serde(default)]
+    pub blocked: bool,
+    /// Only missions carrying this tag.
+    pub tag: Option<String>,
+    /// 1-based page number.
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// GET /missions
+/// Lists missions newest first, filtered by agent, status, creation time range, blocked state and tag.
+pub async fn list_missions(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<MissionListQuery>,
+) -> impl IntoResponse {
+    let filter = MissionFilter {
+        agent_id: params.agent_id,
+        status: params.status,
+        since: params.since,
+        until: params.until,
+        blocked: params.blocked,
+        tag: params.tag,
+        page: params.page.unwrap_or(1).max(1),
+        per_page: params.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE),
+    };
+
+    match crate::agent::mission::list_missions(&state.pool, &filter).await {
+        Ok(result) => Json(serde_json::json!({
+            "missions": result.items,
+            "total": result.total,
+            "page": result.page,
+            "per_page": result.per_page
+        })).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Query Failed",
+            format!("Missions could not be listed: {}", e)
+        ).into_response(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    /// 1-based page number.
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// GET /missions/:id
+pub async fn get_mission(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match crate::agent::mission::get_mission_by_id(&state.pool, &id).await {
+        Ok(Some(mission)) => Json(mission).into_response(),
+        Ok(None) => mission_not_found(&id),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Query Failed",
+            format!("Mission '{}' could not be loaded: {}", id, e)
+        ).into_response(),
+    }
+}
+
+/// GET /missions/:id/logs
+/// Returns the mission's step log in chronological order, one page at a time.
+pub async fn get_mission_logs(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<PageQuery>,
+) -> impl IntoResponse {
+    match crate::agent::mission::get_mission_by_id(&state.pool, &id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return mission_not_found(&id),
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Query Failed",
+            format!("Mission '{}' could not be loaded: {}", id, e)
+        ).into_response(),
+    }
+
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+
+    match crate::agent::mission::get_mission_logs(&state.pool, &id, page, per_page).await {
+        Ok(result) => Json(serde_json::json!({
+            "logs": result.items,
+            "total": result.total,
+            "page": result.page,
+            "per_page": result.per_page
+        })).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Log Query Failed",
+            format!("Logs for mission '{}' could not be loaded: {}", id, e)
+        ).into_response(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StepsQuery {
+    /// 1-based page number.
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    /// Only steps with this severity (`info`, `success`, `warning`, `error`).
+    pub severity: Option<String>,
+    /// Only steps from this source (e.g. `User`, `Agent`, `System`).
+    pub source: Option<String>,
+}
+
+/// GET /missions/:id/steps
+/// Pages through a mission's steps in chronological order, optionally filtered by severity and source.
+pub async fn get_mission_steps(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<StepsQuery>,
+) -> impl IntoResponse {
+    match crate::agent::mission::get_mission_by_id(&state.pool, &id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return mission_not_found(&id),
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Query Failed",
+            format!("Mission '{}' could not be loaded: {}", id, e)
+        ).into_response(),
+    }
+
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+
+    match crate::agent::mission::get_mission_logs_paged(
+        &state.pool, &id, page, per_page, params.severity.as_deref(), params.source.as_deref()
+    ).await {
+        Ok(result) => Json(serde_json::json!({
+            "steps": result.items,
+            "total": result.total,
+            "page": result.page,
+            "per_page": result.per_page,
+            "has_next": result.page * result.per_page < result.total
+        })).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Step Query Failed",
+            format!("Steps for mission '{}' could not be loaded: {}", id, e)
+        ).into_response(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ContextSearchQuery {
+    /// Substring to look for in the finding text.
+    pub q: Option<String>,
+    pub agent_id: Option<String>,
+    pub topic: Option<String>,
+}
+
+/// GET /missions/:id/context/search
+/// Searches the findings agents shared on the mission's swarm context bus, oldest first.
+/// At most `MAX_FINDING_SEARCH_RESULTS` findings are returned.
+pub async fn search_mission_context(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<ContextSearchQuery>,
+) -> impl IntoResponse {
+    match crate::agent::mission::get_mission_by_id(&state.pool, &id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return mission_not_found(&id),
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Query Failed",
+            format!("Mission '{}' could not be loaded: {}", id, e)
+        ).into_response(),
+    }
+
+    let search = crate::agent::mission::FindingSearch {
+        query: params.q,
+        agent_id: params.agent_id,
+        topic: params.topic,
+    };
+    match crate::agent::mission::search_findings(&state.pool, &id, &search).await {
+        Ok(findings) => Json(findings).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Context Search Failed",
+            format!("Findings for mission '{}' could not be searched: {}", id, e)
+        ).into_response(),
+    }
+}
+
+/// Effective confidence at or above which a finding is in the `high` tier.
+pub const HIGH_CONFIDENCE_THRESHOLD: f32 = 0.75;
+/// Effective confidence at or above which a finding is in the `medium` tier; anything lower is `low`.
+pub const MEDIUM_CONFIDENCE_THRESHOLD: f32 = 0.4;
+
+/// A finding with the confidence the swarm gives it after verification.
+#[derive(serde::Serialize)]
+struct TieredFinding {
+    #[serde(flatten)]
+    finding: MissionFinding,
+    effective_confidence: Option<f32>,
+}
+
+/// Findings grouped by effective confidence, as returned by `GET /missions/:id/findings`.
+#[derive(Default, serde::Serialize)]
+struct FindingTiers {
+    high: Vec<TieredFinding>,
+    medium: Vec<TieredFinding>,
+    low: Vec<TieredFinding>,
+    unrated: Vec<TieredFinding>,
+}
+
+/// Sorts findings into confidence tiers, most confident first within each tier.
+fn tier_findings(findings: Vec<MissionFinding>) -> FindingTiers {
+    let mut tiers = FindingTiers::default();
+    for finding in findings {
+        let effective_confidence = finding.effective_confidence();
+        let tier = match effective_confidence {
+            None => &mut tiers.unrated,
+            Some(c) if c >= HIGH_CONFIDENCE_THRESHOLD => &mut tiers.high,
+            Some(c) if c >= MEDIUM_CONFIDENCE_THRESHOLD => &mut tiers.medium,
+            Some(_) => &mut tiers.low,
+        };
+        tier.push(TieredFinding { finding, effective_confidence });
+    }
+    for tier in [&mut tiers.high, &mut tiers.medium, &mut tiers.low] {
+        tier.sort_by(|a, b| b.effective_confidence.partial_cmp(&a.effective_confidence).unwrap_or(std::cmp::Ordering::Equal));
+    }
+    tiers
+}
+
+/// GET /missions/:id/findings
+/// Returns the mission's findings grouped into `high`, `medium`, `low` and `unrated` tiers by
+/// effective confidence, which halves for disputed findings. Context summaries are left out.
+pub async fn get_mission_findings(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match crate::agent::mission::get_mission_by_id(&state.pool, &id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return mission_not_found(&id),
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Query Failed",
+            format!("Mission '{}' could not be loaded: {}", id, e)
+        ).into_response(),
+    }
+
+    match crate::agent::mission::list_findings(&state.pool, &id).await {
+        Ok(findings) => Json(tier_findings(findings)).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Finding Query Failed",
+            format!("Findings for mission '{}' could not be loaded: {}", id, e)
+        ).into_response(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportQuery {
+    /// `json` (default) or `csv`.
+    pub format: Option<String>,
+}
+
+/// GET /missions/:id/export
+/// Downloads the mission with its full log and findings, as one JSON document or as a ZIP
+/// holding `mission_logs.csv` and `findings.csv`.
+pub async fn export_mission(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<ExportQuery>,
+) -> impl IntoResponse {
+    let format = params.format.as_deref().unwrap_or("json").to_lowercase();
+    if format != "json" && format != "csv" {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Unsupported Export Format",
+            format!("Format '{}' is not supported. Use 'json' or 'csv'.", format)
+        ).into_response();
+    }
+
+    let export = match crate::agent::mission::get_full_mission_export(&state.pool, &id).await {
+        Ok(Some(export)) => export,
+        Ok(None) => return mission_not_found(&id),
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Export Failed",
+            format!("Mission '{}' could not be exported: {}", id, e)
+        ).into_response(),
+    };
+
+    if format == "csv" {
+        let archive = match crate::routes::archive::build_zip(&[
+            ("mission_logs.csv", logs_csv(&export).into_bytes()),
+            ("findings.csv", findings_csv(&export).into_bytes()),
+        ]) {
+            Ok(archive) => archive,
+            Err(e) => return ProblemDetails::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Mission Export Failed",
+                format!("Mission '{}' could not be archived: {}", id, e)
+            ).into_response(),
+        };
+        (
+            [
+                (header::CONTENT_TYPE, "application/zip".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"mission-{}.zip\"", id)),
+            ],
+            archive,
+        ).into_response()
+    } else {
+        (
+            [(header::CONTENT_DISPOSITION, format!("attachment; filename=\"mission-{}.json\"", id))],
+            Json(export),
+        ).into_response()
+    }
+}
+
+fn logs_csv(export: &MissionExport) -> String {
+    let mut out = String::from("id,timestamp,source,severity,text\r\n");
+    for log in &export.logs {
+        let fields = [log.id.as_str(), &log.timestamp.to_rfc3339(), &log.source, &log.severity, &log.text];
+        out.push_str(&csv_row(&fields));
+    }
+    out
+}
+
+fn findings_csv(export: &MissionExport) -> String {
+    let mut out = String::from("id,timestamp,agent_id,topic,finding\r\n");
+    for f in &export.findings {
+        out.push_str(&csv_row(&[&f.id, &f.timestamp.to_rfc3339(), &f.agent_id, &f.topic, &f.finding]));
+    }
+    out
+}
+
+/// Formats one RFC 4180 record: fields holding commas, quotes or line breaks are quoted.
+fn csv_row(fields: &[&str]) -> String {
+    let escaped: Vec<String> = fields.iter().map(|f| {
+        if f.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", f.replace('"', "\"\""))
+        } else {
+            f.to_string()
+        }
+    }).collect();
+    format!("{}\r\n", escaped.join(","))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MissionTagsUpdate {
+    pub tags: Vec<String>,
+}
+
+/// PUT /missions/:id/tags
+/// Replaces the mission's tags and announces the change with `mission:tagged`.
+pub async fn update_mission_tags(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<MissionTagsUpdate>,
+) -> impl IntoResponse {
+    match crate::agent::mission::update_mission_tags(&state.pool, &id, &payload.tags).await {
+        Ok(Some(tags)) => {
+            state.emit_event(serde_json::json!({
+                "type": "mission:tagged",
+                "missionId": id,
+                "tags": tags
+            }));
+            Json(serde_json::json!({ "id": id, "tags": tags })).into_response()
+        }
+        Ok(None) => mission_not_found(&id),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Update Failed",
+            format!("Tags for mission '{}' could not be saved: {}", id, e)
+        ).into_response(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MissionRetryRequest {
+    /// Budget for the retry. Defaults to the original mission's budget.
+    #[serde(alias = "budgetUsd")]
+    pub budget_usd: Option<f64>,
+}
+
+/// POST /missions/:id/retry
+/// Re-runs a failed or paused mission's original task as a new mission linked back through
+/// `parent_mission_id`. The original keeps its logs and cost. Emits `mission:retried`.
+pub async fn retry_mission(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    body: Option<Json<MissionRetryRequest>>,
+) -> impl IntoResponse {
+    if state.is_shutting_down.load(std::sync::atomic::Ordering::SeqCst) {
+        return crate::routes::engine_control::engine_shutting_down();
+    }
+
+    let mission = match crate::agent::mission::get_mission_by_id(&state.pool, &id).await {
+        Ok(Some(mission)) => mission,
+        Ok(None) => return mission_not_found(&id),
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Lookup Failed",
+            format!("Mission '{}' could not be loaded: {}", id, e)
+        ).into_response(),
+    };
+
+    if !matches!(mission.status, MissionStatus::Failed | MissionStatus::Paused) {
+        return ProblemDetails::new(
+            StatusCode::CONFLICT,
+            "Mission Not Retryable",
+            format!(
+                "Mission '{}' is {}; only failed or paused missions can be retried.",
+                id, crate::agent::mission::status_to_str(&mission.status)
+            )
+        ).into_response();
+    }
+
+    if !state.agents.contains_key(&mission.agent_id) {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Agent Not Found",
+            format!("Cannot retry mission '{}' because agent '{}' no longer exists.", id, mission.agent_id)
+        ).into_response();
+    }
+
+    let message = match crate::agent::mission::get_original_message(&state.pool, &id).await {
+        Ok(Some(message)) => message,
+        Ok(None) => return ProblemDetails::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Mission Not Retryable",
+            format!("Mission '{}' has no recorded user message to re-send.", id)
+        ).into_response(),
+        Err(e) => return ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Mission Lookup Failed",
+            format!("The original task of mission '{}' could not be loaded: {}", id, e)
+        ).into_response(),
+    };
+
+    let payload = crate::agent::types::TaskPayload {
+        message,
+        budget_usd: body.and_then(|Json(b)| b.budget_usd).or(Some(mission.budget_usd)),
+        tags: Some(mission.tags.clone()),
+        ..Default::default()
+    };
+
+    let runner = crate::agent::runner::AgentRunner::new(state.clone());
+    let retry_id = match runner.prepare_mission(&mission.agent_id, &payload).await {
+        Ok(retry_id) => retry_id,
+        Err(e) => return ProblemDetails::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Retry Rejected",
+            format!("Mission '{}' could not be restarted: {}", id, e)
+        ).into_response(),
+    };
+    if let Err(e) = crate::agent::mission::set_parent_mission(&state.pool, &retry_id, &id).await {
+        tracing::warn!("⚠️ [Missions] Retry {} could not be linked to {}: {}", retry_id, id, e);
+    }
+
+    state.emit_event(serde_json::json!({
+        "type": "mission:retried",
+        "missionId": retry_id,
+        "parentMissionId": id,
+        "agentId": mission.agent_id
+    }));
+
+    let agent_id = mission.agent_id.clone();
+    let run_id = retry_id.clone();
+    tokio::spawn(async move {
+        if let Err(e) = runner.run_mission_with_timeout(agent_id.clone(), payload, run_id).await {
+            tracing::error!("❌ [Runner] Agent {} retry failed: {}", agent_id, e);
+        }
+    });
+
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "status": "accepted",
+            "missionId": retry_id,
+            "parentMissionId": id,
+            "agentId": mission.agent_id
+        }))
+    ).into_response()
+}
+
+fn mission_not_found(id: &str) -> axum::response::Response {
+    ProblemDetails::new(
+        StatusCode::NOT_FOUND,
+        "Mission Not Found",
+        format!("No mission with ID '{}' exists.", id)
+    ).into_response()
+}
+
+/// Days `GET /system/cost-report` covers when `since` is omitted.
+const DEFAULT_COST_REPORT_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CostReportPeriod {
+    #[default]
+    Daily,
+    Weekly,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CostReportQuery {
+    #[serde(default)]
+    pub period: CostReportPeriod,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// GET /system/cost-report
+/// Mission spend per agent and day (or week, dated by its Monday) for missions created in
+/// `[since, until)`. Defaults to the last 30 days.
+pub async fn get_cost_report(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CostReportQuery>,
+) -> impl IntoResponse {
+    let until = params.until.unwrap_or_else(Utc::now);
+    let since = params.since.unwrap_or(until - chrono::Duration::days(DEFAULT_COST_REPORT_DAYS));
+    if since >= until {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid Report Range",
+            format!("'since' ({}) must be before 'until' ({}).", since.to_rfc3339(), until.to_rfc3339())
+        ).into_response();
+    }
+
+    match crate::agent::mission::compute_cost_summary(&state.pool, since, until).await {
+        Ok(daily) => match params.period {
+            CostReportPeriod::Daily => Json(daily).into_response(),
+            CostReportPeriod::Weekly => Json(crate::agent::mission::weekly_cost_summary(&daily)).into_response(),
+        },
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Cost Report Failed",
+            format!("Mission costs could not be summarized: {}", e)
+        ).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Registers a throwaway agent so each test filters on missions it created itself.
+    async fn seed_agent(state: &AppState) -> String {
+        let mut agent = state.agents.get("1").unwrap().clone();
+        agent.id = format!("mission-list-{}", uuid::Uuid::new_v4());
+        // Retried missions run for real; the mock provider keeps them off the network
+        agent.model.provider = "mock".to_string();
+        agent.active_model_slot = None;
+        let id = agent.id.clone();
+        state.register_agent(agent).await;
+        id
+    }
+
+    async fn list(state: &Arc<AppState>, params: MissionListQuery) -> serde_json::Value {
+        let response = list_missions(State(state.clone()), Query(params)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_missions_paginates_and_clamps_bounds() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        for i in 0..5 {
+            crate::agent::mission::create_mission(&state.pool, &agent_id, &format!("Task {}", i), 1.0, &[]).await.unwrap();
+        }
+
+        let json = list(&state, MissionListQuery {
+            agent_id: Some(agent_id.clone()),
+            page: Some(3),
+            per_page: Some(2),
+            ..Default::default()
+        }).await;
+        assert_eq!(json["total"], 5);
+        assert_eq!(json["page"], 3);
+        assert_eq!(json["missions"].as_array().unwrap().len(), 1, "The last page holds the remainder");
+
+        let json = list(&state, MissionListQuery {
+            agent_id: Some(agent_id.clone()),
+            page: Some(0),
+            per_page: Some(10_000),
+            ..Default::default()
+        }).await;
+        assert_eq!(json["page"], 1);
+        assert_eq!(json["per_page"], MAX_PER_PAGE);
+        assert_eq!(json["missions"].as_array().unwrap().len(), 5);
+
+        let json = list(&state, MissionListQuery {
+            agent_id: Some(agent_id),
+            page: Some(4),
+            per_page: Some(2),
+            ..Default::default()
+        }).await;
+        assert!(json["missions"].as_array().unwrap().is_empty(), "Pages past the end are empty, not an error");
+    }
+
+    #[tokio::test]
+    async fn test_list_missions_filters_by_status() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let outcomes = [MissionStatus::Completed, MissionStatus::Failed, MissionStatus::Completed, MissionStatus::Active];
+        for (i, status) in outcomes.iter().enumerate() {
+            let mission = crate::agent::mission::create_mission(&state.pool, &agent_id, &format!("Task {}", i), 1.0, &[]).await.unwrap();
+            crate::agent::mission::update_mission(&state.pool, &mission.id, *status, 0.0).await.unwrap();
+        }
+
+        let json = list(&state, MissionListQuery {
+            agent_id: Some(agent_id),
+            status: Some(MissionStatus::Completed),
+            ..Default::default()
+        }).await;
+        let missions = json["missions"].as_array().unwrap();
+        assert_eq!(json["total"], 2);
+        assert_eq!(missions.len(), 2);
+        assert!(missions.iter().all(|m| m["status"] == "completed"));
+    }
+
+    #[tokio::test]
+    async fn test_get_mission_returns_404_for_unknown_id() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let missing = format!("missing-{}", uuid::Uuid::new_v4());
+
+        let response = get_mission(State(state.clone()), Path(missing.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["title"], "Mission Not Found");
+
+        let response = get_mission_logs(State(state), Path(missing), Query(PageQuery::default())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_mission_findings_are_grouped_by_effective_confidence() {
+        use crate::agent::types::FindingVerdict;
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let mission = crate::agent::mission::create_mission(&state.pool, &agent_id, "Tiers", 1.0, &[]).await.unwrap();
+        let share = |topic: &'static str, confidence: Option<f32>| {
+            let (state, mission_id, agent_id) = (state.clone(), mission.id.clone(), agent_id.clone());
+            async move { crate::agent::mission::share_finding(&state.pool, &mission_id, &agent_id, topic, "detail", confidence).await.unwrap() }
+        };
+        share("solid", Some(0.9)).await;
+        let contested = share("contested", Some(0.95)).await;
+        share("shaky", Some(0.2)).await;
+        share("unscored", None).await;
+        crate::agent::mission::verify_finding(&state.pool, &mission.id, &contested, "reviewer", FindingVerdict::Disputed).await.unwrap().unwrap();
+
+        let response = get_mission_findings(State(state.clone()), Path(mission.id.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let topics = |tier: &str| -> Vec<String> {
+            json[tier].as_array().unwrap().iter().map(|f| f["topic"].as_str().unwrap().to_string()).collect()
+        };
+        assert_eq!(topics("high"), vec!["solid"]);
+        assert_eq!(topics("medium"), vec!["contested"], "a disputed 0.95 finding drops to 0.475");
+        assert_eq!(topics("low"), vec!["shaky"]);
+        assert_eq!(topics("unrated"), vec!["unscored"]);
+        assert_eq!(json["medium"][0]["verdict"], "disputed");
+        assert_eq!(json["medium"][0]["verified_by"], "reviewer");
+        assert!((json["medium"][0]["effective_confidence"].as_f64().unwrap() - 0.475).abs() < 1e-6);
+
+        let response = get_mission_findings(State(state), Path("no-such-mission".to_string())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_get_mission_logs_paginates_in_order() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let mission = crate::agent::mission::create_mission(&state.pool, &agent_id, "Log paging", 1.0, &[]).await.unwrap();
+        for i in 0..5 {
+            crate::agent::mission::log_step(&state.pool, &mission.id, &agent_id, "Agent", &format!("step {}", i), "info", None).await.unwrap();
+        }
+
+        let response = get_mission(State(state.clone()), Path(mission.id.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = get_mission_logs(
+            State(state.clone()),
+            Path(mission.id.clone()),
+            Query(PageQuery { page: Some(2), per_page: Some(2) })
+        ).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json["total"], 5);
+        assert_eq!(json["page"], 2);
+        assert_eq!(json["per_page"], 2);
+        let texts: Vec<&str> = json["logs"].as_array().unwrap().iter().map(|l| l["text"].as_str().unwrap()).collect();
+        assert_eq!(texts, vec!["step 2", "step 3"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_mission_steps_filters_by_severity() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let mission = crate::agent::mission::create_mission(&state.pool, &agent_id, "Step filtering", 1.0, &[]).await.unwrap();
+        for i in 0..3 {
+            crate::agent::mission::log_step(&state.pool, &mission.id, &agent_id, "Agent", &format!("ok {}", i), "info", None).await.unwrap();
+            crate::agent::mission::log_step(&state.pool, &mission.id, &agent_id, "System", &format!("boom {}", i), "error", Some(serde_json::json!({ "attempt": i }))).await.unwrap();
+        }
+
+        let steps = |params: StepsQuery| {
+            let state = state.clone();
+            let id = mission.id.clone();
+            async move {
+                let response = get_mission_steps(State(state), Path(id), Query(params)).await.into_response();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let json = steps(StepsQuery { severity: Some("error".to_string()), per_page: Some(2), ..Default::default() }).await;
+        assert_eq!(json["total"], 3);
+        assert_eq!(json["has_next"], true);
+        let items = json["steps"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|s| s["severity"] == "error"));
+        assert_eq!(items[0]["text"], "boom 0");
+        assert_eq!(items[1]["metadata"]["attempt"], 1);
+
+        let json = steps(StepsQuery { severity: Some("error".to_string()), page: Some(2), per_page: Some(2), ..Default::default() }).await;
+        assert_eq!(json["steps"].as_array().unwrap().len(), 1);
+        assert_eq!(json["has_next"], false);
+
+        let json = steps(StepsQuery { severity: Some("error".to_string()), source: Some("Agent".to_string()), ..Default::default() }).await;
+        assert_eq!(json["total"], 0);
+
+        let json = steps(StepsQuery::default()).await;
+        assert_eq!(json["total"], 6);
+        assert_eq!(json["has_next"], false);
+    }
+
+    #[tokio::test]
+    async fn test_export_mission_as_json_and_csv_zip() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let mission = crate::agent::mission::create_mission(&state.pool, &agent_id, "Export me", 1.0, &[]).await.unwrap();
+        crate::agent::mission::log_step(&state.pool, &mission.id, &agent_id, "Agent", "Found it, with \"quotes\"", "info", None).await.unwrap();
+        crate::agent::mission::share_finding(&state.pool, &mission.id, &agent_id, "Pricing", "Tier 2, underpriced", None).await.unwrap();
+
+        let response = export_mission(State(state.clone()), Path(mission.id.clone()), Query(ExportQuery::default())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[header::CONTENT_DISPOSITION].to_str().unwrap().contains(".json"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["mission"]["title"], "Export me");
+        assert_eq!(json["logs"].as_array().unwrap().len(), 1);
+        assert_eq!(json["findings"][0]["topic"], "Pricing");
+        assert_eq!(json["logs_truncated"], false);
+
+        let response = export_mission(
+            State(state.clone()),
+            Path(mission.id.clone()),
+            Query(ExportQuery { format: Some("csv".to_string()) })
+        ).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/zip");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let entries = crate::routes::archive::read_zip(&body).unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["mission_logs.csv", "findings.csv"]);
+        let logs = String::from_utf8(entries[0].data.clone().unwrap()).unwrap();
+        assert!(logs.starts_with("id,timestamp,source,severity,text\r\n"));
+        assert!(logs.contains("\"Found it, with \"\"quotes\"\"\""));
+        let findings = String::from_utf8(entries[1].data.clone().unwrap()).unwrap();
+        assert!(findings.contains("Pricing,\"Tier 2, underpriced\""));
+
+        let response = export_mission(State(state), Path(mission.id), Query(ExportQuery { format: Some("xml".to_string()) })).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_blocked_mission_is_listed_then_reported_unblocked_once() {
+        use crate::agent::mission::{poll_unblocked, update_mission};
+
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let dependency = crate::agent::mission::create_mission(&state.pool, &agent_id, "Step A", 1.0, &[]).await.unwrap();
+
+        let mut payload = crate::agent::types::TaskPayload { message: "Step B".to_string(), ..Default::default() };
+        payload.depends_on = Some(dependency.id.clone());
+        let runner = crate::agent::runner::AgentRunner::new(state.clone());
+        assert!(runner.prepare_mission(&agent_id, &payload).await.is_err());
+
+        let blocked = list(&state, MissionListQuery { agent_id: Some(agent_id.clone()), blocked: true, ..Default::default() }).await;
+        assert_eq!(blocked["total"], 1);
+        let blocked_id = blocked["missions"][0]["id"].as_str().unwrap().to_string();
+        assert_eq!(blocked["missions"][0]["prerequisite_mission_id"], dependency.id.as_str());
+
+        let mut notified = std::collections::HashSet::new();
+        let reported = |missions: Vec<crate::agent::types::Mission>| missions.iter().any(|m| m.id == blocked_id);
+        assert!(!reported(poll_unblocked(&state.pool, &mut notified).await.unwrap()), "still blocked");
+
+        update_mission(&state.pool, &dependency.id, MissionStatus::Completed, 0.0).await.unwrap();
+        assert!(reported(poll_unblocked(&state.pool, &mut notified).await.unwrap()));
+        assert!(!reported(poll_unblocked(&state.pool, &mut notified).await.unwrap()), "reported only once");
+
+        let blocked = list(&state, MissionListQuery { agent_id: Some(agent_id), blocked: true, ..Default::default() }).await;
+        assert_eq!(blocked["total"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_list_missions_filters_by_tag() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let tagged = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        let launch = crate::agent::mission::create_mission(&state.pool, &agent_id, "Launch plan", 1.0, &tagged(&["project-x", "q3"])).await.unwrap();
+        crate::agent::mission::create_mission(&state.pool, &agent_id, "Wildcard", 1.0, &tagged(&["project_xy"])).await.unwrap();
+        let other = crate::agent::mission::create_mission(&state.pool, &agent_id, "Unrelated", 1.0, &[]).await.unwrap();
+
+        let by_tag = |tag: &str| MissionListQuery { agent_id: Some(agent_id.clone()), tag: Some(tag.to_string()), ..Default::default() };
+        let json = list(&state, by_tag("project-x")).await;
+        assert_eq!(json["total"], 1, "'_' in a tag must not act as a wildcard and partial tags must not match");
+        assert_eq!(json["missions"][0]["id"], launch.id.as_str());
+        assert_eq!(json["missions"][0]["tags"], serde_json::json!(["project-x", "q3"]));
+        assert_eq!(list(&state, by_tag("project")).await["total"], 0);
+
+        // Retagging moves a mission between filters and is broadcast
+        let mut events = state.event_tx.subscribe();
+        let response = update_mission_tags(
+            State(state.clone()),
+            Path(other.id.clone()),
+            Json(MissionTagsUpdate { tags: tagged(&[" q3 ", "q3", ""]) }),
+        ).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let event = events.recv().await.unwrap();
+        assert_eq!(event["type"], "mission:tagged");
+        assert_eq!(event["tags"], serde_json::json!(["q3"]));
+        assert_eq!(list(&state, by_tag("q3")).await["total"], 2);
+
+        let response = update_mission_tags(
+            State(state.clone()),
+            Path(format!("missing-{}", uuid::Uuid::new_v4())),
+            Json(MissionTagsUpdate { tags: tagged(&["q3"]) }),
+        ).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_retry_mission_starts_linked_mission() {
+        use crate::agent::mission::{create_mission, get_mission_by_id, log_step, update_mission};
+
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let tags = vec!["q3".to_string()];
+        let original = create_mission(&state.pool, &agent_id, "Flaky task", 2.0, &tags).await.unwrap();
+        log_step(&state.pool, &original.id, &agent_id, "User", "Compile the Q3 report", "info", None).await.unwrap();
+        log_step(&state.pool, &original.id, &agent_id, "System", "Network error", "error", None).await.unwrap();
+        update_mission(&state.pool, &original.id, MissionStatus::Failed, 0.4).await.unwrap();
+        let mut events = state.event_tx.subscribe();
+
+        let body = MissionRetryRequest { budget_usd: Some(5.0) };
+        let response = retry_mission(State(state.clone()), Path(original.id.clone()), Some(Json(body))).await.into_response();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["parentMissionId"], original.id);
+        let retry_id = json["missionId"].as_str().unwrap().to_string();
+
+        let retry = get_mission_by_id(&state.pool, &retry_id).await.unwrap().unwrap();
+        assert_eq!(retry.parent_mission_id.as_deref(), Some(original.id.as_str()));
+        assert_eq!(retry.agent_id, agent_id);
+        assert_eq!(retry.budget_usd, 5.0);
+        assert_eq!(retry.tags, tags);
+        assert_eq!(
+            crate::agent::mission::get_original_message(&state.pool, &retry_id).await.unwrap().as_deref(),
+            Some("Compile the Q3 report")
+        );
+
+        // The failed attempt keeps its status and spend
+        let original_after = get_mission_by_id(&state.pool, &original.id).await.unwrap().unwrap();
+        assert_eq!(original_after.status, MissionStatus::Failed);
+        assert_eq!(original_after.cost_usd, 0.4);
+
+        let event = loop {
+            let event = events.recv().await.unwrap();
+            if event["type"] == "mission:retried" { break event; }
+        };
+        assert_eq!(event["missionId"], retry_id);
+        assert_eq!(event["parentMissionId"], original.id);
+    }
+
+    #[tokio::test]
+    async fn test_retry_mission_rejects_active_mission() {
+        use crate::agent::mission::{create_mission, log_step, update_mission};
+
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let mission = create_mission(&state.pool, &agent_id, "Still running", 1.0, &[]).await.unwrap();
+        log_step(&state.pool, &mission.id, &agent_id, "User", "Keep going", "info", None).await.unwrap();
+        update_mission(&state.pool, &mission.id, MissionStatus::Active, 0.0).await.unwrap();
+
+        let response = retry_mission(State(state.clone()), Path(mission.id.clone()), None).await.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = retry_mission(State(state), Path("no-such-mission".to_string()), None).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_context_search_matches_text_within_mission() {
+        use crate::agent::mission::{create_mission, share_finding};
+
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let mission = create_mission(&state.pool, &agent_id, "Research", 1.0, &[]).await.unwrap();
+        let other = create_mission(&state.pool, &agent_id, "Other research", 1.0, &[]).await.unwrap();
+        share_finding(&state.pool, &mission.id, &agent_id, "Pricing", "Competitor raised prices 10%", None).await.unwrap();
+        share_finding(&state.pool, &mission.id, &agent_id, "Hiring", "Two roles open", None).await.unwrap();
+        share_finding(&state.pool, &other.id, &agent_id, "Pricing", "Competitor cut prices", None).await.unwrap();
+
+        let search = |q: &str, topic: Option<&str>| ContextSearchQuery {
+            q: Some(q.to_string()),
+            agent_id: None,
+            topic: topic.map(str::to_string),
+        };
+        let response = search_mission_context(State(state.clone()), Path(mission.id.clone()), Query(search("competitor", None))).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let findings = json.as_array().unwrap();
+        assert_eq!(findings.len(), 1, "Findings from other missions must be excluded: {}", json);
+        assert_eq!(findings[0]["finding"], "Competitor raised prices 10%");
+        assert_eq!(findings[0]["agent_id"], agent_id);
+        assert!(findings[0]["id"].is_string() && findings[0]["timestamp"].is_string());
+
+        // `%` is matched literally (as a wildcard "s%" would match both findings), and the topic filter is exact
+        let found = crate::agent::mission::search_findings(&state.pool, &mission.id, &crate::agent::mission::FindingSearch {
+            query: Some("s%".to_string()), ..Default::default()
+        }).await.unwrap();
+        assert!(found.is_empty(), "{:?}", found);
+        let response = search_mission_context(State(state.clone()), Path(mission.id.clone()), Query(search("o", Some("Hiring")))).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["topic"], "Hiring");
+
+        let response = search_mission_context(State(state), Path("no-such-mission".to_string()), Query(ContextSearchQuery::default())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_daily_costs_are_recorded_reported_and_warned() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let agent_id = seed_agent(&state).await;
+        let mut agent = state.agents.get(&agent_id).unwrap().clone();
+        agent.budget_usd = 1.0;
+        crate::agent::persistence::save_agent_db(&state.pool, &agent).await.unwrap();
+        for cost in [0.5, 0.4] {
+            let mission = crate::agent::mission::create_mission(&state.pool, &agent_id, "Spend", 1.0, &[]).await.unwrap();
+            crate::agent::mission::update_mission(&state.pool, &mission.id, MissionStatus::Completed, cost).await.unwrap();
+        }
+        let mut events = state.event_tx.subscribe();
+
+        let today = Utc::now().date_naive();
+        let summaries = crate::agent::mission::record_daily_costs(&state.pool, &state.event_tx, today, 80.0).await.unwrap();
+        let ours = summaries.iter().find(|s| s.agent_id == agent_id).unwrap();
+        assert_eq!((ours.date.clone(), ours.mission_count), (today.to_string(), 2));
+        assert!((ours.total_cost_usd - 0.9).abs() < 1e-9);
+
+        let stored: (f64, i64) = sqlx::query_as("SELECT total_cost_usd, mission_count FROM cost_summaries WHERE date = ? AND agent_id = ?")
+            .bind(today.to_string())
+            .bind(&agent_id)
+            .fetch_one(&state.pool).await.unwrap();
+        assert_eq!(stored.1, 2);
+        let warning = loop {
+            let event = events.recv().await.unwrap();
+            if event["type"] == "system:budget_warning" && event["agentId"] == agent_id.as_str() {
+                break event;
+            }
+        };
+        assert!((warning["usedPct"].as_f64().unwrap() - 90.0).abs() < 1e-6);
+
+        let report = |period: CostReportPeriod| get_cost_report(State(state.clone()), Query(CostReportQuery { period, since: None, until: None }));
+        for period in [CostReportPeriod::Daily, CostReportPeriod::Weekly] {
+            let response = report(period).await.into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let rows: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+            let row = rows.iter().find(|r| r["agent_id"] == agent_id.as_str()).unwrap();
+            assert_eq!(row["mission_count"], 2);
+        }
+
+        let week = crate::agent::mission::weekly_cost_summary(&[
+            crate::agent::mission::CostSummary { date: "2026-10-14".to_string(), agent_id: "a".to_string(), total_cost_usd: 1.0, mission_count: 1 },
+            crate::agent::mission::CostSummary { date: "2026-10-18".to_string(), agent_id: "a".to_string(), total_cost_usd: 2.0, mission_count: 3 },
+        ]);
+        assert_eq!(week, vec![crate::agent::mission::CostSummary { date: "2026-10-12".to_string(), agent_id: "a".to_string(), total_cost_usd: 3.0, mission_count: 4 }]);
+
+        let until = Utc::now();
+        let response = get_cost_report(State(state.clone()), Query(CostReportQuery { period: CostReportPeriod::Daily, since: Some(until), until: Some(until) })).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,6 +1,9 @@
 pub mod agent;
+pub mod archive;
+pub mod audit;
 pub mod deploy;
 pub mod engine_control;
+pub mod mission;
 pub mod health;
 pub mod oversight;
 pub mod ws;
@@ -9,3 +12,6 @@ pub mod audio;
 pub mod error;
 
 pub mod capabilities;
+pub mod vault;
+pub mod metrics;
+pub mod webhooks;
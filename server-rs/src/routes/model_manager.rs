@@ -6,27 +6,71 @@ use axum::{
 };
 use std::sync::Arc;
 use crate::{
-    agent::types::{ProviderConfig, ModelEntry},
+    agent::types::{ProviderConfig, ProviderConfigPublic, ModelEntry},
+    routes::error::ProblemDetails,
     state::AppState,
 };
 
 /// Returns all configured AI providers.
+/// API keys and custom header values are masked via `ProviderConfigPublic` — raw secrets never leave the engine.
 pub async fn get_providers(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let providers: Vec<ProviderConfig> = state.providers.iter().map(|kv| kv.value().clone()).collect();
+    let providers: Vec<ProviderConfigPublic> = state.providers.iter().map(|kv| ProviderConfigPublic::from(kv.value())).collect();
     Json(providers)
 }
 
 /// Updates or creates a provider configuration.
+/// A `null` API key means "keep the existing key", since clients only ever see the masked value.
+/// Likewise a custom header sent back with its masked value keeps the stored value.
 pub async fn update_provider(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-    Json(config): Json<ProviderConfig>,
+    Json(mut config): Json<ProviderConfig>,
 ) -> impl IntoResponse {
+    if let Some(existing) = state.providers.get(&id) {
+        keep_stored_secrets(&mut config, &existing);
+    }
     state.providers.insert(id.clone(), config);
+    state.refresh_provider_client(&id);
     state.save_providers().await;
     (StatusCode::OK, Json(serde_json::json!({ "status": "updated", "id": id })))
 }
 
+/// Puts back the stored API key and header values a client could only have seen masked.
+fn keep_stored_secrets(config: &mut ProviderConfig, existing: &ProviderConfig) {
+    if config.api_key.is_none() {
+        config.api_key = existing.api_key.clone();
+    }
+    if let (Some(headers), Some(stored)) = (config.custom_headers.as_mut(), existing.custom_headers.as_ref()) {
+        for (name, value) in headers.iter_mut() {
+            if let Some(original) = stored.get(name).filter(|original| crate::agent::types::mask_secret(original) == *value) {
+                *value = original.clone();
+            }
+        }
+    }
+}
+
+/// GET /infra/providers/:id/health
+/// The latest background health check of a provider. Providers that are not checked (no API
+/// key, or a protocol without a health check) report `checked: false`.
+pub async fn get_provider_health(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if !state.providers.contains_key(&id) {
+        return ProblemDetails::new(
+            StatusCode::NOT_FOUND,
+            "Provider Not Found",
+            format!("No provider with ID '{}' is configured.", id)
+        ).into_response();
+    }
+
+    let key = crate::agent::provider_health::health_key(&id);
+    match state.provider_health.get(key) {
+        Some(health) => Json(health.to_json(&id)).into_response(),
+        None => Json(serde_json::json!({ "providerId": id, "checked": false })).into_response(),
+    }
+}
+
 /// Returns all available models in the registry.
 pub async fn get_models(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let models: Vec<ModelEntry> = state.models.iter().map(|kv| kv.value().clone()).collect();
@@ -40,6 +84,182 @@ pub async fn update_model(
     Json(entry): Json<ModelEntry>,
 ) -> impl IntoResponse {
     state.models.insert(id.clone(), entry);
+    // Drop the shared limiter so the next call picks up the new limits
+    state.rate_limiters.remove(&id);
     state.save_models().await;
     (StatusCode::OK, Json(serde_json::json!({ "status": "updated", "id": id })))
 }
+
+/// Percentage of `limit` still available; `None` when the limit is not enforced.
+fn headroom_pct(limit: Option<u32>, used: u32) -> Option<f64> {
+    limit.map(|limit| {
+        if limit == 0 {
+            0.0
+        } else {
+            (limit.saturating_sub(used) as f64 / limit as f64 * 100.0).max(0.0)
+        }
+    })
+}
+
+/// GET /infra/models/:id/stats
+/// Live RPM/TPM utilization of the limiter shared by every agent using this model.
+/// A model that has not been called yet reports its configured limits with zero usage.
+pub async fn get_model_stats(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let stats = match state.rate_limiters.get(&id) {
+        Some(limiter) => limiter.stats(),
+        None => match state.models.get(&id) {
+            Some(model) => crate::agent::rate_limiter::RateLimiterStats {
+                rpm_limit: model.rpm,
+                rpm_used: 0,
+                tpm_limit: model.tpm,
+                tpm_used: 0,
+            },
+            None => return ProblemDetails::new(
+                StatusCode::NOT_FOUND,
+                "Model Not Found",
+                format!("No model with ID '{}' is registered.", id)
+            ).into_response(),
+        },
+    };
+
+    Json(serde_json::json!({
+        "model_id": id,
+        "rpm_limit": stats.rpm_limit,
+        "rpm_used_last_minute": stats.rpm_used,
+        "tpm_limit": stats.tpm_limit,
+        "tpm_used_last_minute": stats.tpm_used,
+        "rpm_headroom_pct": headroom_pct(stats.rpm_limit, stats.rpm_used),
+        "tpm_headroom_pct": headroom_pct(stats.tpm_limit, stats.tpm_used)
+    })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_provider_health_reports_latest_check() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+
+        let response = get_provider_health(State(state.clone()), Path("no-such-provider".to_string())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body = |response: axum::response::Response| async move {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+        };
+        let json = body(get_provider_health(State(state.clone()), Path("ollama".to_string())).await.into_response()).await;
+        assert_eq!(json["checked"], false);
+
+        state.provider_health.insert("groq".to_string(), crate::agent::provider_health::ProviderHealth {
+            last_checked: std::time::Instant::now(),
+            is_healthy: false,
+            latency_ms: 12,
+            error: Some("HTTP 503 Service Unavailable".to_string()),
+        });
+        let json = body(get_provider_health(State(state.clone()), Path("groq".to_string())).await.into_response()).await;
+        assert_eq!(json["isHealthy"], false);
+        assert_eq!(json["latencyMs"], 12);
+        assert_eq!(json["error"], "HTTP 503 Service Unavailable");
+        state.provider_health.remove("groq");
+    }
+
+    #[tokio::test]
+    async fn test_get_providers_masks_api_key() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let secret = "sk-test-super-secret-key-9876";
+
+        state.providers.insert("masked-test".to_string(), ProviderConfig {
+            id: "masked-test".to_string(),
+            name: "Masked Test".to_string(),
+            icon: None,
+            api_key: Some(secret.to_string()),
+            base_url: None,
+            protocol: "openai".to_string(),
+            external_id: None,
+            custom_headers: Some([("X-Api-Token".to_string(), "hdr-secret-token-5432".to_string())].into()),
+            audio_model: None,
+            timeout_secs: None,
+        });
+
+        let response = get_providers(State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(!text.contains(secret), "Response must never contain the raw API key");
+        assert!(text.contains("...****9876"), "Response should include the masked key tail");
+        assert!(!text.contains("hdr-secret-token"), "Response must never contain raw header values");
+        let listed: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+        let provider = listed.iter().find(|p| p["id"] == "masked-test").unwrap();
+        assert_eq!(provider["customHeaders"]["X-Api-Token"], "...****5432");
+
+        // Saving the provider as listed keeps the real header value
+        let mut submitted = provider.clone();
+        submitted["apiKey"] = serde_json::Value::Null;
+        let submitted: ProviderConfig = serde_json::from_value(submitted).unwrap();
+        let (_, stored) = state.providers.remove("masked-test").unwrap();
+        let mut merged = submitted;
+        keep_stored_secrets(&mut merged, &stored);
+        assert_eq!(merged.custom_headers.unwrap()["X-Api-Token"], "hdr-secret-token-5432");
+        assert_eq!(merged.api_key.as_deref(), Some(secret));
+    }
+
+    #[tokio::test]
+    async fn test_model_stats_report_shared_limiter_usage() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let mut config = crate::agent::registry::get_mock_registry().remove(0).model;
+        config.model_id = "stats-test-model".to_string();
+        config.rpm = Some(10);
+        config.tpm = Some(1_000);
+
+        // Two agents on the same model must draw from one limiter
+        let first = state.rate_limiter(&config);
+        let second = state.rate_limiter(&config);
+        assert!(Arc::ptr_eq(&first, &second));
+        first.acquire(100).await;
+        second.acquire(100).await;
+        second.record_usage(250);
+
+        let response = get_model_stats(State(state.clone()), Path("stats-test-model".to_string())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["rpm_limit"], 10);
+        assert_eq!(json["rpm_used_last_minute"], 2);
+        assert_eq!(json["tpm_used_last_minute"], 250);
+        assert_eq!(json["rpm_headroom_pct"], 80.0);
+        assert_eq!(json["tpm_headroom_pct"], 75.0);
+
+        let response = get_model_stats(State(state), Path("no-such-model".to_string())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_provider_timeouts_get_dedicated_clients() {
+        let config = crate::config::TadpoleConfig { provider_timeout_groq_secs: Some(5), ..Default::default() };
+        let state = Arc::new(AppState::for_tests(config).await);
+
+        assert!(!Arc::ptr_eq(&state.provider_client("groq"), &state.http_client), "groq has its own timeout");
+        assert!(Arc::ptr_eq(&state.provider_client("ollama"), &state.http_client), "unconfigured providers share the global client");
+        assert!(Arc::ptr_eq(&state.provider_client("gemini"), &state.provider_client("google")));
+
+        // A timeout set from the UI gets a client of its own; clearing it falls back again
+        let mut ollama = state.providers.get("ollama").unwrap().clone();
+        ollama.timeout_secs = Some(300);
+        state.providers.insert("ollama".to_string(), ollama.clone());
+        state.refresh_provider_client("ollama");
+        assert!(!Arc::ptr_eq(&state.provider_client("ollama"), &state.http_client));
+
+        ollama.timeout_secs = None;
+        state.providers.insert("ollama".to_string(), ollama);
+        state.refresh_provider_client("ollama");
+        assert!(Arc::ptr_eq(&state.provider_client("ollama"), &state.http_client));
+
+        let json = serde_json::to_value(ProviderConfigPublic::from(state.providers.get("groq").unwrap().value())).unwrap();
+        assert!(json["timeoutSecs"].is_null(), "only the provider's own setting is exposed");
+    }
+}
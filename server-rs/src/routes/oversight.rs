@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -7,18 +7,20 @@ use axum::{
 use std::sync::Arc;
 use crate::state::AppState;
 use crate::{
+    agent::mission::OversightFilter,
     agent::types::{OversightEntry, OversightDecision},
     routes::error::ProblemDetails,
 };
 
 /// GET /oversight/pending
-/// Returns all entries currently awaiting a human decision.
+/// Returns all entries currently awaiting a human decision. Timed-out entries are excluded.
 pub async fn get_pending(
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
     let entries: Vec<OversightEntry> = state
         .oversight_queue
         .iter()
+        .filter(|entry| entry.value().status != "timed_out")
         .map(|entry| entry.value().clone())
         .collect();
     Json(entries)
@@ -42,6 +44,47 @@ pub async fn get_ledger(
     Json(entries)
 }
 
+const DEFAULT_HISTORY_PER_PAGE: i64 = 20;
+const MAX_HISTORY_PER_PAGE: i64 = 100;
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct OversightHistoryQuery {
+    pub agent_id: Option<String>,
+    pub skill: Option<String>,
+    /// 1-based page number.
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// GET /oversight/history
+/// Pages through every persisted decision, newest first. Unlike the ledger it is not capped
+/// and survives restarts.
+pub async fn get_history(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<OversightHistoryQuery>,
+) -> impl IntoResponse {
+    let filter = OversightFilter {
+        agent_id: params.agent_id,
+        skill: params.skill,
+        page: params.page.unwrap_or(1).max(1),
+        per_page: params.per_page.unwrap_or(DEFAULT_HISTORY_PER_PAGE).clamp(1, MAX_HISTORY_PER_PAGE),
+    };
+
+    match crate::agent::mission::get_oversight_history(&state.pool, &filter).await {
+        Ok(result) => Json(serde_json::json!({
+            "entries": result.items,
+            "total": result.total,
+            "page": result.page,
+            "per_page": result.per_page
+        })).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Oversight History Query Failed",
+            format!("Oversight history could not be read: {}", e)
+        ).into_response(),
+    }
+}
+
 #[derive(serde::Deserialize)]
 pub struct OversightSettingsPayload {
     #[serde(rename = "autoApproveSafeSkills")]
@@ -54,10 +97,7 @@ pub async fn update_settings(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<OversightSettingsPayload>,
 ) -> impl IntoResponse {
-    state.auto_approve_safe_skills.store(
-        payload.auto_approve_safe_skills,
-        std::sync::atomic::Ordering::Relaxed
-    );
+    state.oversight_policy.set_auto_approve_enabled(payload.auto_approve_safe_skills);
     
     tracing::info!("🛡️ Governance updated: Auto-Approve Safe Skills = {}", payload.auto_approve_safe_skills);
     
@@ -67,6 +107,22 @@ pub async fn update_settings(
     })))
 }
 
+/// Removes a pending entry, wakes the waiting tool call and records the decision in the ledger.
+/// Returns `false` if the entry does not exist or has already been decided.
+async fn apply_decision(state: &AppState, entry_id: &str, decision: &str) -> bool {
+    let Some((_, entry)) = state.oversight_queue.remove(entry_id) else {
+        return false;
+    };
+    state.metrics.oversight_pending.set(state.oversight_queue.len() as i64);
+
+    if let Some((_, shooter)) = state.oversight_resolvers.remove(entry_id) {
+        let _ = shooter.send(decision == "approved");
+    }
+
+    state.record_oversight_decision(entry_id, decision, "user", entry.tool_call).await;
+    true
+}
+
 /// POST /oversight/:id/decide
 /// Approves or rejects a pending entry.
 pub async fn decide_oversight(
@@ -76,12 +132,7 @@ pub async fn decide_oversight(
 ) -> impl IntoResponse {
     tracing::info!("⚖️ [Oversight] Decision for {}: {}", entry_id, payload.decision);
 
-    let approved = payload.decision == "approved";
-    
-    // 1. Remove from the pending queue
-    let removed_entry = state.oversight_queue.remove(&entry_id);
-    
-    if removed_entry.is_none() {
+    if !apply_decision(&state, &entry_id, &payload.decision).await {
         return ProblemDetails::new(
             StatusCode::NOT_FOUND,
             "Oversight Entry Not Found",
@@ -89,33 +140,6 @@ pub async fn decide_oversight(
         ).into_response();
     }
 
-    // 2. Resolve the awaiting oneshot channel
-    if let Some((_, shooter)) = state.oversight_resolvers.remove(&entry_id) {
-        let _ = shooter.send(approved);
-    }
-
-    // 3. Record the decision in the ledger
-    {
-        let ledger_entry = serde_json::json!({
-            "id": entry_id,
-            "decision": payload.decision,
-            "timestamp": chrono::Utc::now().to_rfc3339(),
-            "decidedBy": "user",
-            "toolCall": removed_entry.and_then(|(_, e)| e.tool_call).map(|tc| serde_json::json!({
-                "agentId": tc.agent_id,
-                "skill": tc.skill,
-                "params": tc.params,
-                "description": tc.description,
-                "clusterId": tc.department
-            }))
-        });
-        if let Ok(mut ledger) = state.oversight_ledger.lock() {
-            ledger.insert(0, ledger_entry);
-            ledger.truncate(200);
-        }
-    }
-
-    // 4. Broadcast the decision event
     state.emit_event(serde_json::json!({
         "type": "oversight:decided",
         "entry": {
@@ -128,3 +152,51 @@ pub async fn decide_oversight(
 
     (StatusCode::OK, Json(serde_json::json!({ "status": "ok" }))).into_response()
 }
+
+#[derive(Debug, serde::Deserialize)]
+pub struct BulkOversightDecision {
+    pub ids: Vec<String>,
+    pub decision: String,
+}
+
+/// POST /oversight/decide-bulk
+/// Applies one decision to many pending entries. IDs that are unknown or already decided
+/// are reported in `not_found` rather than failing the request.
+pub async fn decide_oversight_bulk(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<BulkOversightDecision>,
+) -> impl IntoResponse {
+    if payload.decision != "approved" && payload.decision != "rejected" {
+        return ProblemDetails::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid Decision",
+            format!("Decision must be 'approved' or 'rejected', got '{}'.", payload.decision)
+        ).into_response();
+    }
+
+    tracing::info!("⚖️ [Oversight] Bulk decision for {} entries: {}", payload.ids.len(), payload.decision);
+
+    let mut decided = Vec::new();
+    let mut not_found = Vec::new();
+    for id in payload.ids {
+        if apply_decision(&state, &id, &payload.decision).await {
+            decided.push(id);
+        } else {
+            not_found.push(id);
+        }
+    }
+
+    state.emit_event(serde_json::json!({
+        "type": "oversight:bulk_decided",
+        "decision": payload.decision,
+        "count": decided.len(),
+        "ids": decided,
+        "decidedBy": "user",
+        "decidedAt": chrono::Utc::now().to_rfc3339()
+    }));
+
+    (StatusCode::OK, Json(serde_json::json!({
+        "decided": decided,
+        "not_found": not_found
+    }))).into_response()
+}
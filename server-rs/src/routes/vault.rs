@@ -0,0 +1,21 @@
+use axum::{
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use crate::adapter::vault::VaultAdapter;
+use crate::routes::error::ProblemDetails;
+
+/// GET /vault
+/// Summarizes the archive: one entry per vault subdirectory with file count and total size.
+pub async fn list_vault() -> impl IntoResponse {
+    let adapter = VaultAdapter::new(std::path::PathBuf::from("vault"));
+    match adapter.list_subdirectories().await {
+        Ok(subdirectories) => Json(subdirectories).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Vault Unavailable",
+            format!("The vault could not be listed: {}", e)
+        ).into_response(),
+    }
+}
@@ -0,0 +1,63 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::adapter::webhook::{deliver, validate_callback_url, MissionCallbackPayload};
+use crate::routes::error::ProblemDetails;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookTestRequest {
+    pub url: String,
+    pub secret: Option<String>,
+}
+
+/// POST /webhooks/test
+/// Sends a sample mission callback to `url`, signed like a real one, so an integration can be
+/// checked before a task uses it. Reports the receiver's status; `502` if it is unreachable.
+pub async fn test_webhook(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<WebhookTestRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validate_callback_url(&payload.url) {
+        return ProblemDetails::new(StatusCode::BAD_REQUEST, "Invalid Callback URL", e.to_string()).into_response();
+    }
+
+    let sample = MissionCallbackPayload::new("test", "test", 0.0, "Tadpole OS webhook test delivery.");
+    match deliver(&state.http_client, &payload.url, payload.secret.as_deref(), &sample).await {
+        Ok(code) => Json(serde_json::json!({
+            "delivered": code.is_success(),
+            "statusCode": code.as_u16(),
+            "signed": payload.secret.as_deref().is_some_and(|s| !s.is_empty())
+        })).into_response(),
+        Err(e) => ProblemDetails::new(
+            StatusCode::BAD_GATEWAY,
+            "Callback Unreachable",
+            format!("The test delivery to '{}' failed: {}", payload.url, e)
+        ).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rejects_non_http_urls_and_reports_unreachable_receivers() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+
+        let response = test_webhook(State(state.clone()), Json(WebhookTestRequest { url: "file:///etc/passwd".to_string(), secret: None })).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Bind then drop a listener so the port is known to be closed
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let url = format!("http://127.0.0.1:{}/cb", port);
+        let response = test_webhook(State(state), Json(WebhookTestRequest { url, secret: Some("k".to_string()) })).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+}
@@ -1,12 +1,17 @@
 use axum::{
-    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, State},
+    extract::{ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade}, State},
     response::IntoResponse,
 };
-use futures::{sink::SinkExt, stream::StreamExt};
+use chrono::{DateTime, Utc};
+use futures::{sink::SinkExt, stream::{SplitSink, StreamExt}};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::ops::ControlFlow;
 use std::sync::Arc;
 use crate::state::AppState;
 
-/// The HTTP upgrade endpoint for WebSockets.
+/// The HTTP upgrade endpoint for WebSockets. Either the read or the admin token is accepted.
+/// An optional `events` query parameter (comma-separated `type` values) limits what the socket receives.
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
@@ -15,9 +20,10 @@ pub async fn ws_handler(
     let token = params.get("token");
 
     if let Some(t) = token {
-        if t == &state.deploy_token {
-            tracing::info!("✅ WebSocket handshake authorized.");
-            return ws.on_upgrade(move |socket| handle_socket(socket, state)).into_response();
+        if let Some(level) = crate::middleware::auth::auth_level(&state, t) {
+            tracing::info!("✅ WebSocket handshake authorized ({:?}).", level);
+            let filter = parse_event_filter(params.get("events").map(String::as_str));
+            return ws.on_upgrade(move |socket| handle_socket(socket, state, filter)).into_response();
         } else {
             tracing::warn!("🚫 Unauthorized WebSocket: Token mismatch. Received: {}... Expected: {}...", 
                 &t[..std::cmp::min(4, t.len())],
@@ -31,51 +37,234 @@ pub async fn ws_handler(
     (axum::http::StatusCode::UNAUTHORIZED, "Unauthorized").into_response()
 }
 
+/// Parses `?events=agent:message,agent:status` into a set of event types.
+/// An empty set means "no filter": the socket receives every event.
+fn parse_event_filter(raw: Option<&str>) -> HashSet<String> {
+    raw.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns true if an event of `event_type` should be forwarded under `filter`.
+fn is_subscribed(filter: &HashSet<String>, event_type: Option<&str>) -> bool {
+    filter.is_empty() || event_type.is_some_and(|t| filter.contains(t))
+}
+
+/// Commands a client may send over an established socket.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum InboundMessage {
+    /// Re-validates the session token without reconnecting.
+    #[serde(rename = "auth:refresh")]
+    AuthRefresh { token: String },
+}
+
+/// Per-connection state tracked by the socket loop.
+struct ConnectionState {
+    /// When the connection's token was last (re-)validated.
+    last_validated_at: DateTime<Utc>,
+}
+
 /// The actual bi-directional WebSocket loop handling messaging.
-async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
-    let (mut sender, mut _receiver) = socket.split();
+async fn handle_socket(socket: WebSocket, state: Arc<AppState>, filter: HashSet<String>) {
+    let (mut sender, mut receiver) = socket.split();
 
     // Subscribe to both Log entries and Engine events
     let mut log_rx = state.tx.subscribe();
     let mut event_rx = state.event_tx.subscribe();
+    let mut shutdown_rx = state.shutdown_tx.subscribe();
+    let mut conn = ConnectionState { last_validated_at: Utc::now() };
 
     tracing::info!("🔗 High-Performance WebSocket Connected!");
 
     // Tell the frontend we connected in Rust.
     state.broadcast_sys("Connected to Tadpole OS [Rust Engine v0.1.0]", "success");
 
-    // Spawn a task that constantly reads our global Broadcast channels
-    // and instantly forwards to this specific WebSocket connection
-    let mut send_task = tokio::spawn(async move {
-        loop {
-            tokio::select! {
-                // 1. Handle System Logs (LogEntry)
-                result = log_rx.recv() => {
-                    if let Ok(msg) = result {
-                        if let Ok(json_str) = serde_json::to_string(&msg) {
-                            if sender.send(Message::Text(json_str)).await.is_err() {
-                                break;
-                            }
+    // Forward the global Broadcast channels to this connection while serving its inbound commands
+    loop {
+        tokio::select! {
+            // 1. Handle System Logs (LogEntry)
+            result = log_rx.recv() => {
+                if let Ok(msg) = result {
+                    if !is_subscribed(&filter, Some(&msg.event_type)) {
+                        continue;
+                    }
+                    if let Ok(json_str) = serde_json::to_string(&msg) {
+                        if sender.send(Message::Text(json_str)).await.is_err() {
+                            break;
                         }
                     }
                 }
-                
-                // 2. Handle Engine Events (serde_json::Value)
-                result = event_rx.recv() => {
-                    if let Ok(msg) = result {
-                        if let Ok(json_str) = serde_json::to_string(&msg) {
-                            if sender.send(Message::Text(json_str)).await.is_err() {
-                                break;
-                            }
+            }
+
+            // 2. Handle Engine Events (serde_json::Value)
+            result = event_rx.recv() => {
+                if let Ok(msg) = result {
+                    if !is_subscribed(&filter, msg.get("type").and_then(|t| t.as_str())) {
+                        continue;
+                    }
+                    if let Ok(json_str) = serde_json::to_string(&msg) {
+                        if sender.send(Message::Text(json_str)).await.is_err() {
+                            break;
                         }
                     }
                 }
             }
-        }
-    });
 
-    // Keep the task alive until it closes
-    let _ = tokio::join!(&mut send_task);
+            // 3. Close cleanly on engine shutdown so the listener can finish draining
+            _ = shutdown_rx.recv() => {
+                let _ = sender.send(Message::Close(Some(CloseFrame {
+                    code: close_code::AWAY,
+                    reason: "Engine shutting down".into(),
+                }))).await;
+                break;
+            }
+
+            // 4. Handle client commands
+            inbound = receiver.next() => {
+                match inbound {
+                    Some(Ok(Message::Text(text))) => {
+                        if handle_inbound(&text, &state, &mut conn, &mut sender).await.is_break() {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
 
     tracing::info!("🔗 WebSocket Disconnected.");
 }
+
+/// Processes a single client command. Returns `Break` when the connection must be closed.
+async fn handle_inbound(
+    text: &str,
+    state: &AppState,
+    conn: &mut ConnectionState,
+    sender: &mut SplitSink<WebSocket, Message>,
+) -> ControlFlow<()> {
+    let command = match serde_json::from_str::<InboundMessage>(text) {
+        Ok(command) => command,
+        Err(e) => {
+            tracing::debug!("Ignoring unrecognised WebSocket message: {}", e);
+            return ControlFlow::Continue(());
+        }
+    };
+
+    match command {
+        InboundMessage::AuthRefresh { token } => {
+            if crate::middleware::auth::auth_level(state, &token).is_some() {
+                conn.last_validated_at = Utc::now();
+                let reply = serde_json::json!({
+                    "type": "auth:refreshed",
+                    // Deploy tokens are static and never expire
+                    "expiresAt": serde_json::Value::Null,
+                    "validatedAt": conn.last_validated_at.to_rfc3339()
+                });
+                if sender.send(Message::Text(reply.to_string())).await.is_err() {
+                    return ControlFlow::Break(());
+                }
+                ControlFlow::Continue(())
+            } else {
+                tracing::warn!("🚫 WebSocket token refresh rejected. Closing connection.");
+                let _ = sender.send(Message::Text(serde_json::json!({ "type": "auth:expired" }).to_string())).await;
+                let _ = sender.send(Message::Close(Some(CloseFrame {
+                    code: close_code::POLICY,
+                    reason: "Token refresh failed".into(),
+                }))).await;
+                ControlFlow::Break(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+    #[tokio::test]
+    async fn test_invalid_token_refresh_closes_with_policy_violation() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let app = axum::Router::new()
+            .route("/engine/ws", axum::routing::get(ws_handler))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let url = format!("ws://{}/engine/ws?token={}", addr, state.deploy_token);
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        socket.send(ClientMessage::Text(serde_json::json!({ "type": "auth:refresh", "token": "not-the-token" }).to_string())).await.unwrap();
+
+        let mut saw_expired = false;
+        let close_code = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while let Some(msg) = socket.next().await {
+                match msg.unwrap() {
+                    ClientMessage::Text(text) if text.contains("auth:expired") => saw_expired = true,
+                    ClientMessage::Close(frame) => return frame.map(|f| u16::from(f.code)),
+                    _ => {}
+                }
+            }
+            None
+        }).await.unwrap();
+
+        assert!(saw_expired);
+        assert_eq!(close_code, Some(1008));
+    }
+
+    #[tokio::test]
+    async fn test_filtered_socket_receives_only_subscribed_events() {
+        let state = Arc::new(AppState::for_tests(Default::default()).await);
+        let app = axum::Router::new()
+            .route("/engine/ws", axum::routing::get(ws_handler))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let url = format!("ws://{}/engine/ws?token={}&events=agent:status,%20oversight:new", addr, state.deploy_token);
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        // The socket subscribes asynchronously after the handshake, so keep emitting until it arrives
+        let emitter_state = state.clone();
+        let emitter = tokio::spawn(async move {
+            loop {
+                emitter_state.broadcast_sys("noise", "info");
+                let _ = emitter_state.event_tx.send(serde_json::json!({ "type": "agent:message", "text": "noise" }));
+                let _ = emitter_state.event_tx.send(serde_json::json!({ "type": "agent:status", "status": "idle" }));
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+        });
+
+        let received: Vec<serde_json::Value> = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            let mut received = Vec::new();
+            while let Some(Ok(ClientMessage::Text(text))) = socket.next().await {
+                received.push(serde_json::from_str(&text).unwrap());
+                if received.len() == 3 {
+                    break;
+                }
+            }
+            received
+        }).await.unwrap();
+        emitter.abort();
+
+        assert_eq!(received.len(), 3);
+        assert!(received.iter().all(|e| e["type"] == "agent:status"), "got: {:?}", received);
+    }
+
+    #[test]
+    fn test_empty_event_filter_receives_everything() {
+        let filter = parse_event_filter(Some(" , "));
+        assert!(filter.is_empty());
+        assert!(is_subscribed(&filter, Some("log")));
+        assert!(is_subscribed(&parse_event_filter(None), None));
+        assert!(!is_subscribed(&parse_event_filter(Some("agent:status")), Some("log")));
+    }
+}
@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use tokio::sync::{broadcast, oneshot};
-use std::sync::atomic::AtomicBool;
+use tokio::sync::{broadcast, oneshot, Semaphore, SemaphorePermit};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 use dashmap::DashMap;
@@ -38,6 +38,30 @@ impl LogEntry {
     }
 }
 
+/// OS-level documents injected into every system prompt, cached so missions don't hit the disk.
+#[derive(Debug, Clone, Default)]
+pub struct SystemContext {
+    pub identity: String,
+    pub long_term_memory: String,
+}
+
+impl SystemContext {
+    /// Reads `IDENTITY.md` and `LONG_TERM_MEMORY.md` under `data_dir`. Unreadable files are logged and left empty.
+    pub async fn load(data_dir: &std::path::Path) -> Self {
+        async fn read_or_warn(path: std::path::PathBuf) -> String {
+            tokio::fs::read_to_string(&path).await.unwrap_or_else(|e| {
+                tracing::warn!("⚠️ [Context] Could not read {}: {}. Continuing without it.", path.display(), e);
+                String::new()
+            })
+        }
+
+        Self {
+            identity: read_or_warn(data_dir.join(crate::agent::runner::IDENTITY_FILE)).await,
+            long_term_memory: read_or_warn(data_dir.join(crate::agent::runner::MEMORY_FILE)).await,
+        }
+    }
+}
+
 /// The global application state shared across all routes via Axum State.
 pub struct AppState {
     /// Broadcast system logs to all connected UI WebSockets
@@ -52,19 +76,51 @@ pub struct AppState {
     /// Decided oversight entries (bounded in-memory ledger for the dashboard)
     pub oversight_ledger: Mutex<Vec<serde_json::Value>>,
 
+    /// Seconds a pending oversight entry waits before it is rejected as timed out
+    /// (from OVERSIGHT_TIMEOUT_SECS, default 300).
+    pub oversight_timeout_secs: u64,
+
+    /// Port the HTTP listener binds (from PORT, default 8000).
+    pub port: u16,
+    /// Scheme of the database URL (e.g. "sqlite"). The full URL is not kept.
+    pub database_backend: String,
+
+    /// Upper bound on a task's `timeoutSecs` (from MAX_TASK_TIMEOUT_SECS, default 3600).
+    pub max_task_timeout_secs: u64,
+    /// Default CPU and memory caps for dynamic skill subprocesses
+    /// (from SKILL_CPU_LIMIT_SECS / SKILL_MEMORY_LIMIT_MB, default 30s / 256 MB).
+    pub skill_limits: crate::agent::sandbox::ResourceLimits,
+    /// Swarm context length, in characters, above which a mission's findings are summarized
+    /// (from MAX_CONTEXT_CHARS, default 8000).
+    pub max_context_chars: usize,
+    /// Also mirror agent saves to `agents.json` (LEGACY_JSON_BACKUP).
+    pub legacy_json_backup: bool,
+    /// Upper bound on tool calls an agent runs concurrently per turn
+    /// (from MAX_PARALLEL_TOOL_CALLS, default 5).
+    pub max_parallel_tool_calls: usize,
+    /// Command the `run_tests` tool runs in the agent's workspace
+    /// (from RUN_TESTS_COMMAND, default `cargo test --workspace --no-fail-fast`).
+    pub run_tests_command: String,
+    /// Hosts the `git_clone` skill may clone from (from ALLOWED_GIT_HOSTS, default github.com and gitlab.com).
+    pub allowed_git_hosts: Vec<String>,
+    /// SMTP relay used by the `notify_email` tool.
+    pub smtp: crate::adapter::email::SmtpSettings,
+
     /// Generic broadcast for Engine events (oversight:new, etc)
     pub event_tx: broadcast::Sender<serde_json::Value>,
 
-    /// Global governance setting: whether to auto-approve low-risk skills.
-    pub auto_approve_safe_skills: AtomicBool,
+    /// Rules that auto-approve or auto-reject oversight requests before they reach the queue.
+    pub oversight_policy: crate::agent::oversight_policy::OversightPolicy,
 
     /// The live agent registry, synced with persistence file
     pub agents: DashMap<String, EngineAgent>,
-    pub providers: DashMap<String, crate::agent::types::ProviderConfig>,
+    pub providers: Arc<DashMap<String, crate::agent::types::ProviderConfig>>,
     pub models: DashMap<String, crate::agent::types::ModelEntry>,
 
-    /// Token for authenticating deploy requests (from NEURAL_TOKEN env var)
+    /// Admin token: full API access and deploy requests (ADMIN_TOKEN, falling back to NEURAL_TOKEN)
     pub deploy_token: String,
+    /// Optional read-only token (READ_TOKEN) accepted on `GET` routes and the WebSocket
+    pub read_token: Option<String>,
 
     /// Database pool for persistence
     pub pool: SqlitePool,
@@ -73,33 +129,166 @@ pub struct AppState {
     /// Industry standard: one client per process, not per request.
     pub http_client: Arc<Client>,
 
+    /// Dedicated clients for providers with their own timeout, keyed by provider id.
+    /// Providers without an entry use `http_client`.
+    pub provider_clients: DashMap<String, Arc<Client>>,
+
+    /// Provider timeouts from `PROVIDER_TIMEOUT_*`, keyed by provider id. A provider's own
+    /// `timeout_secs` takes precedence.
+    pub provider_timeout_defaults: std::collections::HashMap<String, u64>,
+
+    /// Latest health check of each checked provider, keyed by provider id. Calls to a
+    /// provider marked unhealthy fail fast instead of waiting on a doomed request.
+    pub provider_health: Arc<DashMap<String, crate::agent::provider_health::ProviderHealth>>,
+
     /// Registry for dynamic file-based Skills and Workflows
     pub capabilities: Arc<crate::agent::capabilities::CapabilitiesRegistry>,
 
     /// Manager for Lifecycle Hooks (Pre/Post tool execution)
     pub hooks: Arc<crate::agent::hooks::HooksManager>,
+
+    /// Caps the number of root missions running at once (`max_concurrent_missions`, default 10).
+    pub max_concurrent_missions: Semaphore,
+
+    /// The permit count `max_concurrent_missions` was created with.
+    pub mission_concurrency_limit: usize,
+
+    /// Missions currently waiting for a concurrency permit.
+    pub queued_missions: AtomicUsize,
+
+    /// Agent runs in progress, sub-agents included. Shutdown waits for this to reach zero.
+    pub active_agent_count: AtomicU32,
+    /// Set once `POST /engine/shutdown` is accepted; new tasks are then refused with `503`.
+    pub is_shutting_down: AtomicBool,
+    /// Fires when the engine should stop serving; `main` awaits it to shut the listener down.
+    pub shutdown_tx: broadcast::Sender<()>,
+
+    /// When this engine process came online.
+    pub started_at: DateTime<Utc>,
+    /// Monotonic start time used for uptime, unaffected by wall-clock changes.
+    pub started_instant: Arc<std::time::Instant>,
+    /// When `POST /engine/deploy` last succeeded in this process.
+    pub last_deploy_at: Mutex<Option<DateTime<Utc>>>,
+
+    /// Cached identity and long-term memory for system prompts.
+    pub system_context: Arc<tokio::sync::RwLock<SystemContext>>,
+    /// Root of `context/`, `memory/`, `skills/`, `workflows/` and `hooks/` (from DATA_DIR, default `data`).
+    pub data_dir: std::path::PathBuf,
+
+    /// Lifetime mission outcomes, seeded from `mission_history` at startup.
+    pub missions_completed: AtomicU64,
+    pub missions_failed: AtomicU64,
+
+    /// Prometheus counters and gauges served at `GET /metrics`.
+    pub metrics: Arc<crate::routes::metrics::Metrics>,
+
+    /// One rate limiter per model ID, shared by every agent calling that model.
+    pub rate_limiters: DashMap<String, Arc<crate::agent::rate_limiter::RateLimiter>>,
+
+    /// Private limiters for agents with an `rpm_override`/`tpm_override`, keyed by agent ID.
+    pub agent_rate_overrides: DashMap<String, Arc<crate::agent::rate_limiter::RateLimiter>>,
+
+    /// One lock per agent, held for the whole of each `POST /agents/:id/send` run so tasks sent to
+    /// the same agent run one after another. Waiters are served in arrival order.
+    pub agent_task_locks: DashMap<String, Arc<tokio::sync::Mutex<()>>>,
+
+    /// The throwaway database behind a test engine, deleted once the state is dropped.
+    #[cfg(test)]
+    _test_database: Option<TestDatabase>,
+}
+
+/// A test SQLite file that is deleted, with its `-wal` and `-shm` companions, on drop.
+#[cfg(test)]
+pub(crate) struct TestDatabase(std::path::PathBuf);
+
+#[cfg(test)]
+impl TestDatabase {
+    /// A uniquely named database file in the temp directory. Nothing is created until it is opened.
+    pub(crate) fn new() -> Self {
+        Self(std::env::temp_dir().join(format!("tadpole_test_{}.db", uuid::Uuid::new_v4())))
+    }
+
+    pub(crate) fn url(&self) -> String {
+        format!("sqlite:{}", self.0.display())
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.0.display(), suffix));
+        }
+    }
+}
+
+/// Decrements `active_agent_count` when an agent run ends, however it ends.
+pub struct ActiveAgentGuard<'a>(&'a AtomicU32);
+
+impl Drop for ActiveAgentGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Number of decided entries kept in the in-memory oversight ledger.
+const OVERSIGHT_LEDGER_CAPACITY: usize = 200;
+
+/// Timeout of the shared `http_client`, used by providers without a timeout of their own.
+pub const DEFAULT_PROVIDER_TIMEOUT_SECS: u64 = 90;
+
+/// A provider's own `timeout_secs`, else its `PROVIDER_TIMEOUT_*` setting. `None` means the
+/// provider uses the shared client.
+fn provider_timeout(provider: &crate::agent::types::ProviderConfig, defaults: &std::collections::HashMap<String, u64>) -> Option<u64> {
+    provider.timeout_secs.filter(|n| *n > 0).or_else(|| defaults.get(&provider.id).copied())
+}
+
+fn build_http_client(timeout_secs: u64) -> Client {
+    Client::builder()
+        .timeout(std::time::Duration::from_secs(timeout_secs))
+        .pool_max_idle_per_host(20)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Shapes a persisted decision the way `GET /oversight/ledger` has always returned it.
+fn ledger_entry(record: &crate::agent::types::OversightRecord) -> serde_json::Value {
+    serde_json::json!({
+        "id": record.id,
+        "decision": record.status,
+        "timestamp": record.decided_at,
+        "decidedBy": record.decided_by,
+        "toolCall": {
+            "agentId": record.agent_id,
+            "skill": record.skill,
+            "params": record.params,
+            "description": record.description,
+            "clusterId": record.department
+        }
+    })
 }
 
 impl AppState {
-    pub async fn new() -> Self {
+    /// Builds the state from a resolved `TadpoleConfig` (file values with env overrides applied).
+    pub async fn new(config: crate::config::TadpoleConfig) -> Self {
         let (tx, _) = broadcast::channel(1000);
         let (event_tx, _) = broadcast::channel(1000);
         
         // 🔐 SEC-01 FIX: Panic on missing token. A fallback default means the API
         // is protected by a known, public secret — a critical security hole.
-        let deploy_token = std::env::var("NEURAL_TOKEN").unwrap_or_else(|_| {
+        let deploy_token = config.admin_token.clone().or_else(|| config.neural_token.clone()).unwrap_or_else(|| {
             if cfg!(debug_assertions) {
                 // Dev builds only: allow a dev token with a loud warning
-                tracing::warn!("⚠️  NEURAL_TOKEN not set. Using insecure dev token. DO NOT deploy without setting this variable.");
+                tracing::warn!("⚠️  ADMIN_TOKEN/NEURAL_TOKEN not set. Using insecure dev token. DO NOT deploy without setting this variable.");
                 "tadpole-dev-token-2026".to_string()
             } else {
-                panic!("🚨 FATAL: NEURAL_TOKEN environment variable is not set. The engine cannot start without a secure authentication token. Set NEURAL_TOKEN in your .env file.");
+                panic!("🚨 FATAL: neither ADMIN_TOKEN nor NEURAL_TOKEN is set. The engine cannot start without a secure authentication token. Set NEURAL_TOKEN in your .env file.");
             }
         });
         
         // Initialize Database
-        let mut database_url = std::env::var("DATABASE_URL")
-            .unwrap_or_else(|_| "sqlite:tadpole.db".to_string());
+        let mut database_url = config.database_url.clone()
+            .unwrap_or_else(|| "sqlite:tadpole.db".to_string());
         
         // Ensure the path is absolute for Windows environments to avoid Code 14 errors
         if database_url.starts_with("sqlite:") && !database_url.contains(":/") && !database_url.contains(":\\") && !database_url.contains("/") && !database_url.contains("\\") {
@@ -112,10 +301,25 @@ impl AppState {
 
         let pool = crate::db::init_db(&database_url).await
             .expect("Failed to initialize database");
+        let database_backend = database_url.split(':').next().unwrap_or("unknown").to_string();
+
+        // Warm the in-memory ledger with the most recent persisted decisions
+        let recent_decisions = crate::agent::mission::get_oversight_history(&pool, &crate::agent::mission::OversightFilter {
+            page: 1,
+            per_page: OVERSIGHT_LEDGER_CAPACITY as i64,
+            ..Default::default()
+        }).await;
+        let oversight_ledger: Vec<serde_json::Value> = match recent_decisions {
+            Ok(history) => history.items.iter().map(ledger_entry).collect(),
+            Err(e) => {
+                tracing::warn!("⚠️ Could not load oversight history: {}", e);
+                Vec::new()
+            }
+        };
 
         // Initialize registries
         let providers_list = crate::agent::persistence::load_providers();
-        let providers = DashMap::new();
+        let providers = Arc::new(DashMap::new());
         for p in providers_list {
             providers.insert(p.id.clone(), p);
         }
@@ -133,7 +337,7 @@ impl AppState {
                 tracing::info!("🗄️ Database empty or failed to load. Migrating from JSON registry...");
                 let legacy_agents = crate::agent::persistence::load_registry();
                 for agent in &legacy_agents {
-                    let _ = crate::agent::persistence::save_agent_db(&pool, agent).await;
+                    let _ = crate::agent::persistence::create_agent_db(&pool, agent).await;
                 }
                 legacy_agents
             }
@@ -147,33 +351,277 @@ impl AppState {
         // PERF-01 FIX: Build ONE shared http client for all providers.
         // reqwest::Client manages an internal connection pool — reusing it
         // gives us HTTP keep-alive and avoids TLS handshake overhead per call.
-        let http_client = Arc::new(
-            Client::builder()
-                .timeout(std::time::Duration::from_secs(90))
-                .pool_max_idle_per_host(20)
-                .build()
-                .expect("Failed to build HTTP client")
-        );
+        let http_client = Arc::new(build_http_client(DEFAULT_PROVIDER_TIMEOUT_SECS));
+
+        // Slow and fast providers get their own pools so one timeout doesn't fit all
+        let provider_timeout_defaults: std::collections::HashMap<String, u64> = [
+            ("groq", config.provider_timeout_groq_secs),
+            ("google", config.provider_timeout_google_secs),
+            ("openai", config.provider_openai_timeout_secs),
+        ]
+        .into_iter()
+        .filter_map(|(id, secs)| secs.filter(|n| *n > 0).map(|n| (id.to_string(), n)))
+        .collect();
+        let provider_clients = DashMap::new();
+        for provider in providers.iter() {
+            if let Some(secs) = provider_timeout(provider.value(), &provider_timeout_defaults) {
+                provider_clients.insert(provider.key().clone(), Arc::new(build_http_client(secs)));
+            }
+        }
+
+        let mission_concurrency_limit = config.max_concurrent_missions.filter(|n| *n > 0).unwrap_or(10);
+
+        // Seed lifetime mission counters so they survive restarts
+        let mut missions_completed = 0u64;
+        let mut missions_failed = 0u64;
+        let status_counts: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT status, COUNT(*) FROM mission_history GROUP BY status")
+            .fetch_all(&pool)
+            .await
+            .unwrap_or_default();
+        for (status, count) in status_counts {
+            match status.as_str() {
+                "completed" => missions_completed = count as u64,
+                "failed" => missions_failed = count as u64,
+                _ => {}
+            }
+        }
+
+        let data_dir = config.data_dir.clone().unwrap_or_else(|| std::path::PathBuf::from("data"));
+        let oversight_policy = crate::agent::oversight_policy::OversightPolicy::load(&data_dir);
+
+        let hooks = Arc::new(crate::agent::hooks::HooksManager::new(&data_dir, Some(event_tx.clone())));
 
-        let capabilities = crate::agent::capabilities::CapabilitiesRegistry::new().await
-            .expect("Failed to initialize dynamic capabilities registry (check data/ directory permissions)");
+        let oversight_timeout_secs = config.oversight_timeout_secs.filter(|n| *n > 0).unwrap_or(300);
+        let max_task_timeout_secs = config.max_task_timeout_secs.filter(|n| *n > 0).unwrap_or(3600);
+        let skill_limits = crate::agent::sandbox::ResourceLimits {
+            cpu_secs: config.skill_cpu_limit_secs.filter(|n| *n > 0).unwrap_or(crate::agent::sandbox::DEFAULT_SKILL_CPU_LIMIT_SECS),
+            memory_mb: config.skill_memory_limit_mb.filter(|n| *n > 0).unwrap_or(crate::agent::sandbox::DEFAULT_SKILL_MEMORY_LIMIT_MB),
+        };
+
+        let capabilities = match &config.data_dir {
+            Some(dir) => crate::agent::capabilities::CapabilitiesRegistry::with_data_dir(dir.clone(), Some(event_tx.clone())).await,
+            None => crate::agent::capabilities::CapabilitiesRegistry::new(Some(event_tx.clone())).await,
+        };
+        let mut capabilities = capabilities.expect("Failed to initialize dynamic capabilities registry (check data/ directory permissions)");
+        capabilities.preserve_metrics_on_reload = config.preserve_skill_metrics_on_reload.unwrap_or(false);
+        let capabilities = Arc::new(capabilities);
+        let provider_health = Arc::new(DashMap::new());
 
         Self { 
             tx,
             oversight_queue: DashMap::new(),
             oversight_resolvers: DashMap::new(),
-            oversight_ledger: Mutex::new(Vec::new()),
-            auto_approve_safe_skills: AtomicBool::new(true),
+            oversight_ledger: Mutex::new(oversight_ledger),
+            oversight_timeout_secs,
+            port: config.port.unwrap_or(8000),
+            database_backend,
+            max_task_timeout_secs,
+            skill_limits,
+            max_context_chars: config.max_context_chars
+                .filter(|n| *n > 0)
+                .unwrap_or(crate::agent::mission::DEFAULT_MAX_CONTEXT_CHARS),
+            legacy_json_backup: config.legacy_json_backup.unwrap_or(false),
+            max_parallel_tool_calls: config.max_parallel_tool_calls
+                .filter(|n| *n > 0)
+                .unwrap_or(crate::agent::runner::DEFAULT_MAX_PARALLEL_TOOL_CALLS),
+            allowed_git_hosts: config.allowed_git_hosts.clone()
+                .unwrap_or_else(|| crate::agent::runner::DEFAULT_ALLOWED_GIT_HOSTS.iter().map(|h| h.to_string()).collect()),
+            run_tests_command: config.run_tests_command.clone()
+                .filter(|c| !c.trim().is_empty())
+                .unwrap_or_else(|| crate::agent::runner::DEFAULT_RUN_TESTS_COMMAND.to_string()),
+            smtp: crate::adapter::email::SmtpSettings {
+                host: config.smtp_host.clone(),
+                port: config.smtp_port,
+                username: config.smtp_username.clone(),
+                password: config.smtp_password.clone(),
+                tls: config.smtp_tls.clone(),
+                from: config.email_from.clone(),
+            },
+            oversight_policy,
             event_tx,
             agents,
             providers,
             models,
             deploy_token,
+            read_token: config.read_token.clone().filter(|t| !t.is_empty()),
             pool,
             http_client,
-            capabilities: Arc::new(capabilities),
-            hooks: Arc::new(crate::agent::hooks::HooksManager::new(std::path::Path::new("data"))), // Default data dir, adjusted in new() logic if needed
+            provider_clients,
+            provider_health,
+            provider_timeout_defaults,
+            capabilities,
+            hooks,
+            max_concurrent_missions: Semaphore::new(mission_concurrency_limit),
+            mission_concurrency_limit,
+            queued_missions: AtomicUsize::new(0),
+            active_agent_count: AtomicU32::new(0),
+            is_shutting_down: AtomicBool::new(false),
+            shutdown_tx: broadcast::channel(1).0,
+            system_context: Arc::new(tokio::sync::RwLock::new(SystemContext::load(&data_dir).await)),
+            data_dir,
+            started_at: Utc::now(),
+            started_instant: Arc::new(std::time::Instant::now()),
+            last_deploy_at: Mutex::new(None),
+            missions_completed: AtomicU64::new(missions_completed),
+            missions_failed: AtomicU64::new(missions_failed),
+            metrics: Arc::new(crate::routes::metrics::Metrics::new()),
+            rate_limiters: DashMap::new(),
+            agent_rate_overrides: DashMap::new(),
+            agent_task_locks: DashMap::new(),
+            #[cfg(test)]
+            _test_database: None,
+        }
+    }
+
+    /// Re-reads the identity and memory documents from disk into the prompt cache.
+    pub async fn reload_system_context(&self) {
+        let fresh = SystemContext::load(&self.data_dir).await;
+        *self.system_context.write().await = fresh;
+    }
+
+    /// Prepends a decided oversight entry to the ledger, keeping the newest 200, and persists
+    /// tool-call decisions to `oversight_log` so they survive a restart.
+    pub async fn record_oversight_decision(&self, entry_id: &str, decision: &str, decided_by: &str, tool_call: Option<crate::agent::types::ToolCall>) {
+        let decided_at = Utc::now().to_rfc3339();
+        let record = tool_call.map(|tc| crate::agent::types::OversightRecord {
+            id: entry_id.to_string(),
+            mission_id: tc.mission_id,
+            agent_id: tc.agent_id,
+            skill: tc.skill,
+            params: tc.params,
+            status: decision.to_string(),
+            decided_by: Some(decided_by.to_string()),
+            description: Some(tc.description),
+            department: Some(tc.department),
+            created_at: Some(tc.timestamp),
+            decided_at: Some(decided_at.clone()),
+        });
+
+        let ledger_entry = match &record {
+            Some(record) => ledger_entry(record),
+            None => serde_json::json!({
+                "id": entry_id,
+                "decision": decision,
+                "timestamp": decided_at,
+                "decidedBy": decided_by,
+                "toolCall": null
+            }),
+        };
+        if let Ok(mut ledger) = self.oversight_ledger.lock() {
+            ledger.insert(0, ledger_entry);
+            ledger.truncate(OVERSIGHT_LEDGER_CAPACITY);
         }
+
+        if let Some(record) = record {
+            if let Err(e) = crate::agent::mission::log_oversight_decision(&self.pool, &record).await {
+                tracing::warn!("⚠️ [Oversight] Failed to persist decision for {}: {}", entry_id, e);
+            }
+        }
+    }
+
+    /// Returns the shared limiter for `config.model_id`, creating it from the config's limits on first use.
+    pub fn rate_limiter(&self, config: &crate::agent::types::ModelConfig) -> Arc<crate::agent::rate_limiter::RateLimiter> {
+        self.rate_limiters
+            .entry(config.model_id.clone())
+            .or_insert_with(|| Arc::new(crate::agent::rate_limiter::RateLimiter::new(config.rpm, config.tpm, config.rpd, config.tpd)))
+            .clone()
+    }
+
+    /// Returns the limiter for a call made by `agent_id`. Agents with a rate override get their
+    /// own limiter built from `config` (which already carries the override); all others share the model's.
+    pub fn agent_rate_limiter(&self, agent_id: &str, config: &crate::agent::types::ModelConfig) -> Arc<crate::agent::rate_limiter::RateLimiter> {
+        let overridden = self.agents.get(agent_id)
+            .is_some_and(|a| a.rpm_override.is_some() || a.tpm_override.is_some());
+        if !overridden {
+            return self.rate_limiter(config);
+        }
+        self.agent_rate_overrides
+            .entry(agent_id.to_string())
+            .or_insert_with(|| Arc::new(crate::agent::rate_limiter::RateLimiter::new(config.rpm, config.tpm, config.rpd, config.tpd)))
+            .clone()
+    }
+
+    /// Seconds elapsed since the engine came online.
+    pub fn uptime_secs(&self) -> u64 {
+        self.started_instant.elapsed().as_secs()
+    }
+
+    /// Starts the engine's periodic jobs: the capability file watcher, the blocked-mission
+    /// watcher, provider health checks, memory expiry, token-stats roll-up and cost summaries.
+    /// Called once by `main`, so states built for tests never make background requests.
+    pub fn spawn_background_jobs(&self, config: &crate::config::TadpoleConfig) {
+        // Pick up skill/workflow files dropped into data/ without a restart
+        let capability_reload_interval = config.capability_reload_interval_secs.filter(|n| *n > 0).unwrap_or(10);
+        self.capabilities.spawn_watcher(std::time::Duration::from_secs(capability_reload_interval));
+        crate::agent::mission::spawn_unblock_watcher(
+            self.pool.clone(),
+            self.event_tx.clone(),
+            std::time::Duration::from_secs(crate::agent::mission::UNBLOCK_POLL_INTERVAL_SECS),
+        );
+        crate::agent::provider_health::spawn_provider_health_checks(
+            self.providers.clone(),
+            self.provider_health.clone(),
+            self.http_client.clone(),
+            self.event_tx.clone(),
+            std::time::Duration::from_secs(config.provider_health_check_interval_secs
+                .filter(|n| *n > 0)
+                .unwrap_or(crate::agent::provider_health::DEFAULT_HEALTH_CHECK_INTERVAL_SECS)),
+        );
+        crate::agent::memory::spawn_memory_expiry_job(
+            self.pool.clone(),
+            std::time::Duration::from_secs(crate::agent::memory::MEMORY_EXPIRY_INTERVAL_SECS),
+        );
+        crate::agent::token_stats::spawn_token_stats_job(
+            self.pool.clone(),
+            std::time::Duration::from_secs(crate::agent::token_stats::TOKEN_STATS_INTERVAL_SECS),
+        );
+        crate::agent::mission::spawn_cost_summary_job(
+            self.pool.clone(),
+            self.event_tx.clone(),
+            config.budget_alert_threshold_pct
+                .filter(|pct| *pct > 0.0)
+                .unwrap_or(crate::agent::mission::DEFAULT_BUDGET_ALERT_THRESHOLD_PCT),
+        );
+    }
+
+    /// Waits for a mission concurrency permit. The slot is released when the permit is dropped.
+    pub async fn acquire_mission_slot(&self) -> SemaphorePermit<'_> {
+        /// Keeps `queued_missions` accurate even if the waiting future is cancelled.
+        struct QueuedGuard<'a>(&'a AtomicUsize);
+        impl Drop for QueuedGuard<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        self.queued_missions.fetch_add(1, Ordering::SeqCst);
+        let _queued = QueuedGuard(&self.queued_missions);
+        self.max_concurrent_missions.acquire().await
+            .expect("mission semaphore is never closed")
+    }
+
+    /// The lock that serializes directly sent tasks for `agent_id`.
+    pub fn agent_task_lock(&self, agent_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+        self.agent_task_locks.entry(agent_id.to_string()).or_default().clone()
+    }
+
+    /// Counts an agent run as active until the returned guard is dropped.
+    pub fn track_active_agent(&self) -> ActiveAgentGuard<'_> {
+        self.active_agent_count.fetch_add(1, Ordering::SeqCst);
+        ActiveAgentGuard(&self.active_agent_count)
+    }
+
+    /// Waits until no agent run is active, or `timeout` passes. Returns false on timeout.
+    pub async fn wait_for_idle_agents(&self, timeout: std::time::Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while self.active_agent_count.load(Ordering::SeqCst) > 0 {
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        true
     }
 
     /// Helper to broadcast a system log
@@ -187,6 +635,23 @@ impl AppState {
         let _ = self.event_tx.send(event);
     }
 
+    /// Inserts a new agent into the live registry, announces it to all UIs and persists it.
+    pub async fn register_agent(&self, agent: EngineAgent) {
+        self.agents.insert(agent.id.clone(), agent.clone());
+        if let Err(e) = crate::agent::persistence::create_agent_db(&self.pool, &agent).await {
+            tracing::error!("❌ Failed to save agent {} to DB: {}", agent.id, e);
+        }
+
+        // Broadcast the creation to all UIs instantly
+        self.emit_event(serde_json::json!({
+            "type": "agent:create",
+            "agentId": agent.id,
+            "data": agent
+        }));
+
+        self.save_agents().await;
+    }
+
     /// Persists the current state of all agents to the database.
     /// PERF-02 FIX: Runs all save futures concurrently via `join_all` 
     /// instead of a sequential `for` loop, reducing total save time from O(N) to O(1).
@@ -206,13 +671,31 @@ impl AppState {
         futures::future::join_all(save_futs).await;
 
         // Legacy JSON backup — opt-in only
-        if std::env::var("LEGACY_JSON_BACKUP").unwrap_or_default() == "true" {
+        if self.legacy_json_backup {
             if let Err(e) = crate::agent::persistence::save_registry(agents_vec).await {
                 tracing::error!("❌ Failed to save agent state to JSON: {}", e);
             }
         }
     }
 
+    /// The HTTP client for calls to `provider`, falling back to the shared `http_client` when
+    /// the provider has no timeout of its own. `gemini` is an alias of `google`.
+    pub fn provider_client(&self, provider: &str) -> Arc<Client> {
+        let id = if provider == "gemini" { "google" } else { provider };
+        self.provider_clients.get(id)
+            .map(|client| client.value().clone())
+            .unwrap_or_else(|| self.http_client.clone())
+    }
+
+    /// Rebuilds (or drops) `provider_id`'s dedicated client after its configuration changed.
+    pub fn refresh_provider_client(&self, provider_id: &str) {
+        let timeout = self.providers.get(provider_id).and_then(|p| provider_timeout(p.value(), &self.provider_timeout_defaults));
+        match timeout {
+            Some(secs) => { self.provider_clients.insert(provider_id.to_string(), Arc::new(build_http_client(secs))); }
+            None => { self.provider_clients.remove(provider_id); }
+        }
+    }
+
     pub async fn save_providers(&self) {
         let providers_vec: Vec<crate::agent::types::ProviderConfig> = self.providers.iter().map(|kv| kv.value().clone()).collect();
         if let Err(e) = crate::agent::persistence::save_providers(providers_vec).await {
@@ -227,3 +710,15 @@ impl AppState {
         }
     }
 }
+
+#[cfg(test)]
+impl AppState {
+    /// Engine state backed by a throwaway SQLite file, so tests never read or write the real
+    /// `tadpole.db`. The file is deleted when the state is dropped. Any `database_url` in
+    /// `config` is replaced.
+    pub async fn for_tests(config: crate::config::TadpoleConfig) -> Self {
+        let database = TestDatabase::new();
+        let state = Self::new(crate::config::TadpoleConfig { database_url: Some(database.url()), ..config }).await;
+        Self { _test_database: Some(database), ..state }
+    }
+}
@@ -0,0 +1,55 @@
+//! OpenTelemetry export of agent spans.
+//!
+//! When built with the `otel` feature and `OTLP_ENDPOINT` is set, every `tracing` span
+//! (`agent.run`, `call_provider`, `execute_tool`, ...) is batched to that collector over OTLP/gRPC.
+
+/// Service name reported to the collector.
+#[cfg(feature = "otel")]
+const SERVICE_NAME: &str = "tadpole-engine";
+
+/// Builds the OTLP layer for `endpoint`, or `None` when export is off or the exporter cannot be built.
+#[cfg(feature = "otel")]
+pub fn otlp_layer<S>(endpoint: Option<&str>) -> Option<impl tracing_subscriber::Layer<S>>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+
+    let endpoint = endpoint?;
+    let exporter = match opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+    {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("⚠️ OTLP exporter for {} could not be built, spans stay local: {}", endpoint, e);
+            return None;
+        }
+    };
+
+    let provider = opentelemetry_sdk::trace::TracerProvider::builder()
+        .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+        .with_resource(opentelemetry_sdk::Resource::new([
+            opentelemetry::KeyValue::new("service.name", SERVICE_NAME),
+            opentelemetry::KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ]))
+        .build();
+    let tracer = provider.tracer(SERVICE_NAME);
+    opentelemetry::global::set_tracer_provider(provider);
+
+    Some(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Without the `otel` feature there is no exporter; `main` warns when `OTLP_ENDPOINT` is set.
+#[cfg(not(feature = "otel"))]
+pub fn otlp_layer(_endpoint: Option<&str>) -> Option<tracing_subscriber::layer::Identity> {
+    None
+}
+
+/// Flushes spans still buffered by the batch exporter. Call once, after the server stops.
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
@@ -24,6 +24,8 @@ export interface ModelConfig {
     rpd?: number;
     tpm?: number;
     tpd?: number;
+    /** Cap on prior mission turns replayed to the provider. Defaults to 10. */
+    maxHistoryTurns?: number;
 }
 
 /**
@@ -95,7 +95,7 @@ export default function ProviderConfigPanel({ provider, onClose }: ProviderConfi
 
             await setProviderConfig(
                 provider.id,
-                state.apiKey,
+                state.apiKey || null, // Left blank: keep the stored key
                 state.baseUrl,
                 state.externalId,
                 state.protocol,
@@ -52,4 +52,16 @@ describe('providerStore', () => {
         expect(baseUrls.openai).toBe('https://custom-proxy.com');
         expect(useProviderStore.getState().encryptedConfigs.openai).toBe('encrypted_xyz');
     });
+
+    it('keeps the stored key when the key is null', async () => {
+        const { setProviderConfig, unlock } = useProviderStore.getState();
+
+        await unlock('test-pass');
+        await setProviderConfig('groq', 'abc');
+        await setProviderConfig('groq', null, 'https://other-proxy.com');
+
+        expect(useProviderStore.getState().encryptedConfigs.groq).toBe('encrypted_abc');
+        expect(useProviderStore.getState().baseUrls.groq).toBe('https://other-proxy.com');
+        expect(await useProviderStore.getState().getApiKey('groq')).toBe('abc');
+    });
 });
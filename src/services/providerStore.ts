@@ -39,7 +39,8 @@ export interface ProviderConfig {
     id: string; // 'openai', 'anthropic', etc.
     name: string;
     icon?: string;
-    apiKey?: string; // Encrypted string
+    apiKey?: boolean | null; // Whether a key is configured; GET /infra/providers never returns the key itself
+    maskedApiKey?: string | null; // e.g. "...****abcd"
     baseUrl?: string;
     externalId?: string; // Provider identity for ToS/Tracking
     protocol?: 'openai' | 'anthropic' | 'google' | 'ollama' | 'deepseek';
@@ -62,7 +63,7 @@ interface ProviderState {
     addProvider: (name: string, icon: string) => void;
     editProvider: (id: string, name: string, icon: string) => void;
     deleteProvider: (id: string) => void;
-    setProviderConfig: (id: string, apiKey: string, baseUrl?: string, externalId?: string, protocol?: ProviderConfig['protocol'], customHeaders?: Record<string, string>, audioModel?: string) => Promise<void>;
+    setProviderConfig: (id: string, apiKey: string | null, baseUrl?: string, externalId?: string, protocol?: ProviderConfig['protocol'], customHeaders?: Record<string, string>, audioModel?: string) => Promise<void>;
     getApiKey: (providerId: string) => Promise<string | null>;
     resetInactivityTimer: () => void;
 
@@ -177,9 +178,10 @@ export const useProviderStore = create<ProviderState>()(
                 const { masterKey, encryptedConfigs, baseUrls, providers } = get();
                 if (!masterKey) throw new Error('Store is locked');
 
-                const encrypted = await encrypt(apiKey, masterKey);
+                // A null key keeps the stored one, matching PUT /infra/providers/:id
+                const encrypted = apiKey === null ? encryptedConfigs[id] : await encrypt(apiKey, masterKey);
                 set({
-                    encryptedConfigs: { ...encryptedConfigs, [id]: encrypted },
+                    encryptedConfigs: encrypted === undefined ? encryptedConfigs : { ...encryptedConfigs, [id]: encrypted },
                     baseUrls: { ...baseUrls, [id]: baseUrl || '' },
                     providers: providers.map(p => p.id === id ? { ...p, externalId, protocol, customHeaders, audioModel } : p)
                 });